@@ -0,0 +1,333 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Explicit-state exploration of small systems, without any solver.
+//!
+//! When all the state variables of a system have a finite domain (see
+//! [`Bounds`](../../system/bounds/struct.Bounds.html)) and the system has
+//! few enough states, its states are enumerated. The init predicate and the
+//! transition relation are evaluated on them to explore the reachable states
+//! breadth-first.
+//!
+//! Depth `0` checks the one-state properties on the initial states, depth
+//! `d > 0` checks the relations on the transitions taken from the states
+//! discovered at depth `d - 1`, and the one-state properties on the states
+//! these transitions discover. The first counterexample reported for a
+//! property is thus a shortest one, as in symbolic BMC. Once a depth
+//! discovers no new state, the properties left are proved.
+//!
+//! Evaluation does not support function applications, systems or properties
+//! applying functions fail. Invariants and new properties sent by the
+//! supervisor are ignored.
+
+use std::collections::HashMap ;
+
+use term::{
+  Sym, Cst, Int, Term, Model, Offset, Offset2, STerm, Type, State, Factory,
+  VarMaker, CstMaker, ToPrimitive, One, Zero
+} ;
+
+use common::conf ;
+use common::msg::{ Event, MsgDown } ;
+use common::errors::* ;
+
+use system::{ Sys, Prop } ;
+use system::bounds::Bounds ;
+
+/// Values of the state variables of a system, in the order of its state.
+type Valuation = Vec<Cst> ;
+
+/// Values of each state variable of a system, if they all have a finite
+/// domain and the system has at most `max` states.
+pub fn domains(
+  factory: & Factory, sys: & Sys, max: usize
+) -> Option< Vec< Vec<Cst> > > {
+  let bounds = Bounds::of(sys) ;
+  match bounds.states().and_then( |states| states.to_usize() ) {
+    Some(states) if states <= max => (),
+    _ => return None,
+  }
+  let mut domains = Vec::with_capacity( sys.state().args().len() ) ;
+  for & (ref sym, ref typ) in sys.state().args() {
+    let mut values: Vec<Cst> = vec![] ;
+    match * typ.get() {
+      Type::Bool => {
+        values.push( factory.cst(false) ) ;
+        values.push( factory.cst(true) )
+      },
+      Type::BitVec(width) => {
+        let (mut value, end) = ( Int::zero(), Int::one() << width ) ;
+        while value < end {
+          values.push( factory.cst( (width, value.clone()) ) ) ;
+          value = value + Int::one()
+        }
+      },
+      Type::Int => match bounds.range( sym.get() ) {
+        Some( & (ref lo, ref hi) ) => {
+          let mut value = lo.clone() ;
+          while & value <= hi {
+            values.push( factory.cst( value.clone() ) ) ;
+            value = value + Int::one()
+          }
+        },
+        None => return None,
+      },
+      _ => return None,
+    }
+    domains.push(values)
+  }
+  Some(domains)
+}
+
+/// All the valuations over some domains.
+fn valuations(domains: & [ Vec<Cst> ]) -> Vec<Valuation> {
+  let mut res = vec![ vec![] ] ;
+  for values in domains {
+    let mut next = Vec::with_capacity( res.len() * values.len() ) ;
+    for valuation in res.iter() {
+      for value in values {
+        let mut valuation = valuation.clone() ;
+        valuation.push( value.clone() ) ;
+        next.push(valuation)
+      }
+    }
+    res = next
+  }
+  res
+}
+
+/// The states reached so far.
+struct Explorer<'a> {
+  /// Term factory.
+  factory: Factory,
+  /// System explored.
+  sys: & 'a Sys,
+  /// State variables of the system.
+  svars: Vec<Sym>,
+  /// States reached, with the index of the state they were reached from.
+  reached: Vec< (Valuation, Option<usize>) >,
+  /// Index of the states reached.
+  index: HashMap<Valuation, usize>,
+}
+impl<'a> Explorer<'a> {
+  /// Creates an explorer with no state reached.
+  fn mk(factory: Factory, sys: & 'a Sys) -> Self {
+    let svars = sys.state().args().iter().map(
+      |& (ref sym, _)| sym.get().clone()
+    ).collect() ;
+    Explorer {
+      factory: factory, sys: sys, svars: svars,
+      reached: vec![], index: HashMap::new(),
+    }
+  }
+
+  /// Model of a trace, the `i`th state of the trace at offset `i`.
+  fn model_of(& self, trace: & [& Valuation]) -> Model {
+    let mut model = Vec::with_capacity( self.svars.len() * trace.len() ) ;
+    for (offset, valuation) in trace.iter().enumerate() {
+      for (sym, value) in self.svars.iter().zip( valuation.iter() ) {
+        model.push(
+          (
+            (
+              self.factory.svar( sym.clone(), State::Curr ),
+              Some( Offset::of_int(offset) )
+            ),
+            value.clone()
+          )
+        )
+      }
+    }
+    model
+  }
+
+  /// Evaluates a term of the system on one or two states.
+  fn holds(& self, term: & Term, trace: & [& Valuation]) -> Res<bool> {
+    let model = self.model_of(trace) ;
+    Ok(
+      try_chain!(
+        self.factory.eval_bool(
+          term, & Offset2::init(), & model, self.sys.sym().get().clone()
+        ) => "while evaluating `{}`", term
+      )
+    )
+  }
+
+  /// Properties falsified by a state, or by a transition if `trace` has two
+  /// states.
+  fn falsified(
+    & self, props: & [Prop], trace: & [& Valuation]
+  ) -> Res< Vec<Sym> > {
+    let mut res = vec![] ;
+    for prop in props {
+      let term = match (trace.len(), prop.body()) {
+        (1, & STerm::One(ref curr, _)) => curr,
+        (2, & STerm::Two(ref term)) => term,
+        _ => continue,
+      } ;
+      if ! try!( self.holds(term, trace) ) {
+        res.push( prop.sym().get().clone() )
+      }
+    }
+    Ok(res)
+  }
+
+  /// Adds a state reached from `from`, returns its index if it is new.
+  fn reach(
+    & mut self, valuation: Valuation, from: Option<usize>
+  ) -> Option<usize> {
+    if self.index.contains_key(& valuation) { return None }
+    let index = self.reached.len() ;
+    self.index.insert( valuation.clone(), index ) ;
+    self.reached.push( (valuation, from) ) ;
+    Some(index)
+  }
+
+  /// Model of the shortest trace to a state reached.
+  fn trace_to(& self, index: usize, last: Option<& Valuation>) -> Model {
+    let mut trace = vec![] ;
+    if let Some(last) = last { trace.push(last) }
+    let mut current = Some(index) ;
+    while let Some(index) = current {
+      let (ref valuation, from) = self.reached[index] ;
+      trace.push(valuation) ;
+      current = from
+    }
+    trace.reverse() ;
+    self.model_of(& trace)
+  }
+}
+
+/// Offset reported for a depth, the one sequential BMC reports.
+fn offset_of(depth: usize) -> Offset {
+  Offset::of_int( depth.saturating_sub(1) )
+}
+
+/// Explores the states of a system over some domains.
+pub fn bmc(
+  domains: Vec< Vec<Cst> >, conf: & conf::Bmc, sys: & Sys, props: Vec<Prop>,
+  event: & mut Event
+) {
+  let all = valuations(& domains) ;
+  event.log(
+    & format!("exploring the {} state(s) of the system explicitly", all.len())
+  ) ;
+  let mut explorer = Explorer::mk( event.factory().clone(), sys ) ;
+  let mut props = props ;
+
+  // States discovered at the last depth.
+  let mut frontier: Vec<usize> = vec![] ;
+  let mut depth = 0 ;
+
+  // Depth `d` checks the unrolling at `d - 1`, see `offset_of`.
+  while conf.max().map_or(true, |max| depth <= max + 1) {
+
+    match event.recv() {
+      None => return (),
+      Some(msgs) => for msg in msgs {
+        match msg {
+          MsgDown::Forget(ps, _) => props.retain(
+            |prop| ! ps.contains( prop.sym().get() )
+          ),
+          MsgDown::Invariants(_, _) => (),
+          MsgDown::NewProps(ps) => event.warning(
+            & format!("explicit BMC ignores {} new properties", ps.len())
+          ),
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
+          ),
+        }
+      },
+    }
+
+    if props.is_empty() {
+      event.done_at( & offset_of( depth.saturating_sub(1) ) ) ;
+      return ()
+    }
+    if depth > 0 && frontier.is_empty() {
+      let o = offset_of(depth - 1) ;
+      event.log(
+        & format!("all reachable states explored at depth {}", depth - 1)
+      ) ;
+      event.proved_at(
+        props.iter().map( |prop| prop.sym().get().clone() ).collect(), & o
+      ) ;
+      event.done_at(& o) ;
+      return ()
+    }
+
+    let mut cexs: Vec<(Model, Vec<Sym>)> = vec![] ;
+    let mut discovered = vec![] ;
+
+    if depth == 0 {
+      for valuation in all.iter() {
+        let init = log_try!(
+          event, explorer.holds(& sys.init().2, & [valuation])
+          => "while evaluating the init predicate"
+        ) ;
+        if init {
+          if let Some(index) = explorer.reach(valuation.clone(), None) {
+            discovered.push(index)
+          }
+        }
+      }
+    } else {
+      for & from in frontier.iter() {
+        let source = explorer.reached[from].0.clone() ;
+        for valuation in all.iter() {
+          let trans = log_try!(
+            event, explorer.holds(& sys.trans().2, & [& source, valuation])
+            => "while evaluating the transition relation"
+          ) ;
+          if ! trans { continue }
+          let falsified = log_try!(
+            event, explorer.falsified(& props, & [& source, valuation])
+            => "while evaluating the relations"
+          ) ;
+          if ! falsified.is_empty() {
+            props.retain( |prop| ! falsified.contains( prop.sym().get() ) ) ;
+            cexs.push( (explorer.trace_to(from, Some(valuation)), falsified) )
+          }
+          if let Some(index) = explorer.reach(valuation.clone(), Some(from)) {
+            discovered.push(index)
+          }
+        }
+      }
+    }
+
+    for & index in discovered.iter() {
+      let falsified = log_try!(
+        event, explorer.falsified(& props, & [ & explorer.reached[index].0 ])
+        => "while evaluating the properties"
+      ) ;
+      if ! falsified.is_empty() {
+        props.retain( |prop| ! falsified.contains( prop.sym().get() ) ) ;
+        cexs.push( (explorer.trace_to(index, None), falsified) )
+      }
+    }
+
+    let o = offset_of(depth) ;
+    for (model, falsified) in cexs.into_iter() {
+      event.reachable(& model) ;
+      event.disproved_at(model, falsified, & o)
+    }
+    let alive: Vec<Sym> = props.iter().map(
+      |prop| prop.sym().get().clone()
+    ).collect() ;
+    if ! alive.is_empty() {
+      event.k_true( alive.clone(), & o )
+    }
+    event.depth_done(alive, & o) ;
+
+    frontier = discovered ;
+    depth += 1
+  }
+
+  let o = offset_of( depth.saturating_sub(1) ) ;
+  if props.is_empty() { event.done_at(& o) } else { event.max_reached(& o) }
+}
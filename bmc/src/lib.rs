@@ -18,17 +18,95 @@ extern crate common ;
 extern crate unroll ;
 
 use std::sync::Arc ;
+use std::collections::{ HashMap, HashSet } ;
 
-use term::Offset2 ;
-use term::smt::SolverStyle ;
+use term::{ Sym, Term, Model, Offset, Offset2, STerm, Factory } ;
+use term::tmp::TmpTerm ;
+use term::smt::{ SolverStyle, seed_option, timeout_option } ;
 
 use common::{ SolverTrait, CanRun } ;
+use common::samples::SamplePool ;
 use common::conf ;
+use common::conf::OnUnknown ;
 use common::msg::{ Event, MsgDown } ;
+use common::errors::* ;
 
 use system::{ Sys, Prop } ;
+use system::bounds::Bounds ;
 
 use unroll::* ;
+use unroll::schedule::Schedule ;
+
+mod sat ;
+mod explicit ;
+mod parallel ;
+
+/// Depths BMC checks, see the `start` and `step` options. The depth of a
+/// check is the number of transitions of the counterexamples it looks for:
+/// `0` when checking the initial states, `k + 1` after unrolling at `k`.
+#[derive(Clone, Copy)]
+struct Depths {
+  /// Smallest depth checked.
+  start: usize,
+  /// Distance between two depths checked, positive.
+  step: usize,
+}
+impl Depths {
+  /// Depths of a configuration.
+  fn of(conf: & conf::Bmc) -> Self {
+    Depths { start: * conf.start(), step: * conf.step() }
+  }
+  /// True if all the depths are checked.
+  fn all(& self) -> bool { self.start == 0 && self.step == 1 }
+  /// True if a depth is checked.
+  fn checks(& self, depth: usize) -> bool {
+    depth >= self.start && (depth - self.start) % self.step == 0
+  }
+}
+
+/// Negation of some properties asserted for one check, see the
+/// `incremental` option.
+enum Neg {
+  /// Under an activation literal.
+  Actlit(Actlit),
+  /// In an assertion scope.
+  Scope,
+}
+impl Neg {
+  /// Asserts the negation of some properties at some offset. Adds the
+  /// activation literal the check should assume to `actlits`, if any.
+  fn assert<'a, S: SolverTrait<'a>>(
+    unroller: & mut Unroller<S>, mode: conf::Incremental, neg: TmpTerm,
+    k: & Offset2, actlits: & mut Vec<String>
+  ) -> Res<Self> {
+    match mode {
+      conf::Incremental::Actlit => {
+        let actlit = try_chain!(
+          unroller.fresh_actlit() => "while declaring activation literal"
+        ) ;
+        try!( unroller.assert(& actlit.activate_term(neg), k) ) ;
+        actlits.push( actlit.name() ) ;
+        Ok( Neg::Actlit(actlit) )
+      },
+      conf::Incremental::PushPop => {
+        try!( unroller.push() ) ;
+        try!( unroller.assert(& neg, k) ) ;
+        Ok( Neg::Scope )
+      },
+    }
+  }
+
+  /// Retracts the negation. Must happen before anything else is asserted,
+  /// since it is popped along with the negation in a scope.
+  fn retract<'a, S: SolverTrait<'a>>(
+    self, unroller: & mut Unroller<S>
+  ) -> Res<()> {
+    match self {
+      Neg::Actlit(actlit) => unroller.deactivate(actlit),
+      Neg::Scope => unroller.pop(),
+    }
+  }
+}
 
 /// Bounded model-checking.
 pub struct Bmc ;
@@ -40,7 +118,7 @@ impl CanRun<conf::Bmc> for Bmc {
     & self, conf: Arc<conf::Bmc>, sys: Sys, props: Vec<Prop>, mut event: Event
   ) {
     // event.log(
-    //   & format!("checking {} propertie(s) on system {}", props.len(), sys.sym())
+    //   & format!("checking {} property(ies) on system {}", props.len(), sys.sym())
     // ) ;
 
     // event.log("creating solver") ;
@@ -51,10 +129,63 @@ impl CanRun<conf::Bmc> for Bmc {
       Some(ref cmd) => solver_conf = solver_conf.cmd(cmd.clone()),
     } ;
 
+    if * conf.step() == 0 {
+      log_error!(event, "BMC option `step` must be positive") ;
+      return ()
+    }
+    let depths = Depths::of(& conf) ;
+    // No scenarios, preferences, schedule or depth ranges.
+    let plain = conf.scenarios().is_none() && conf.preferences().is_none()
+    && conf.schedule().is_none() && depths.all() ;
+
+    // Small finite systems are explored explicitly, if asked to.
+    if let Some(max) = * conf.explicit() {
+      let domains = if plain {
+        explicit::domains(event.factory(), & sys, max)
+      } else { None } ;
+      match domains {
+        Some(domains) => return explicit::bmc(
+          domains, & conf, & sys, props, & mut event
+        ),
+        None => event.log(
+          & format!(
+            "not exploring the system explicitly: it is not finite with at \
+            most {} states, or uses scenarios, preferences, a schedule \
+            or depth ranges", max
+          )
+        ),
+      }
+    }
+
+    // Boolean-only systems go to the SAT solver, if any.
+    if let Some(ref cmd) = * conf.sat_cmd() {
+      if plain && system::aiger::is_bool_only(& sys, & props) {
+        event.log(
+          & format!("boolean-only system, running on SAT solver `{}`", cmd)
+        ) ;
+        return sat::bmc(cmd, & conf, & sys, props, & mut event)
+      }
+    }
+
+    // Several workers check consecutive depths in parallel.
+    if * conf.workers() > 1 {
+      if plain {
+        return parallel::bmc(
+          * conf.workers(), conf.clone(), sys, props, & mut event
+        )
+      }
+      event.warning(
+        "scenarios, preferences, schedules and depth ranges are not \
+        supported by parallel BMC, running sequentially"
+      )
+    }
+
     mk_solver_run!(
-      solver_conf, conf.smt_log(), "bmc", event.factory(),
-      solver => bmc(solver, sys, props, & mut event),
-      err => event.error(err)
+      solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+      race conf.smt_race(), compare conf.smt_compare(),
+      "bmc", event.factory(),
+      solver => bmc(solver, conf.clone(), depths, sys, props, & mut event),
+      err => log_error!(event, err)
     )
   }
 }
@@ -63,16 +194,63 @@ impl CanRun<conf::Bmc> for Bmc {
 fn bmc<
   'a, S: SolverTrait<'a>
 >(
-  solver: S, sys: Sys, props: Vec<Prop>, event: & mut Event
+  solver: S, conf: Arc<conf::Bmc>, depths: Depths,
+  sys: Sys, props: Vec<Prop>, event: & mut Event
 ) {
   let init_off = Offset2::init() ;
   let mut k = Offset2::init() ;
 
-  let mut unroller = log_try!(
-    event, Unroller::mk(& sys, & props, solver)
-    => "while creating unroller"
+  event.set_max_queries( * conf.max_queries() ) ;
+
+  // Scenarios and preferences are sent along with the properties.
+  let names = conf.scenario_names() ;
+  let (scenarios, props): (Vec<Prop>, Vec<Prop>) = props.into_iter().partition(
+    |prop| names.iter().any( |name| * name == prop.sym().get().sym() )
+  ) ;
+  let names = conf.preference_names() ;
+  let (mut preferences, props): (Vec<Prop>, Vec<Prop>) = props.into_iter(
+  ).partition(
+    |prop| names.iter().any( |name| * name == prop.sym().get().sym() )
+  ) ;
+  // From the most to the least important.
+  preferences.sort_by_key(
+    |pref| names.iter().position(
+      |name| * name == pref.sym().get().sym()
+    )
   ) ;
 
+  let factory = event.factory().clone() ;
+  let schedule = match * conf.schedule() {
+    Some(ref path) => log_try!(
+      event, Schedule::of_file(path, & sys, & factory)
+      => "while reading schedule `{}`", path
+    ),
+    None => Schedule::empty(),
+  } ;
+  // Properties can only be proved if all the depths are checked on all the
+  // traces.
+  let mut exhaustive = depths.all() && schedule.is_empty() ;
+
+  let mut unroller = {
+    let mut all = props.clone() ;
+    all.extend( scenarios.iter().cloned() ) ;
+    all.extend( preferences.iter().cloned() ) ;
+    log_try!(
+      event, Unroller::mk_encoded(& sys, & all, solver, conf.encoding())
+      => "while creating unroller"
+    )
+  } ;
+  unroller.set_on_unknown( * conf.on_unknown() ) ;
+  unroller.set_seed_option( seed_option( conf.smt() ) ) ;
+  if let Some(millis) = * conf.smt_timeout() {
+    log_try!(
+      event, unroller.set_timeout( timeout_option( conf.smt() ), millis )
+    )
+  }
+
+  // Kept for localization.
+  let all_props = props.clone() ;
+
   // event.log("creating manager, declaring actlits") ;
   let mut props = log_try!(
     event, PropManager::mk(props, unroller.solver())
@@ -97,8 +275,59 @@ fn bmc<
     => "while asserting one state invariants"
   ) ;
 
+  log_try!(
+    event, unroller.assert_schedule(& schedule, & k)
+    => "while asserting schedule at {}", k
+  ) ;
+
+  if ! scenarios.is_empty() {
+    if ! preferences.is_empty() {
+      event.warning("preferences are ignored when checking from scenarios")
+    }
+    return bmc_scenarios(
+      unroller, props, scenarios, sys, * conf.incremental(), depths,
+      & schedule, * conf.max(), event
+    )
+  }
+
+  // Each preference with its actlit and its body.
+  let mut prefs = Vec::with_capacity( preferences.len() ) ;
+  for pref in preferences.iter() {
+    let body = match * pref.body() {
+      STerm::One(ref state, _) => state.clone(),
+      STerm::Two(_) => {
+        log_error!(
+          event, format!("preference {} is a two-state property", pref.sym())
+        ) ;
+        return ()
+      },
+    } ;
+    let actlit = log_try!(
+      event, unroller.fresh_actlit()
+      => "while declaring activation literal for preference {}", pref.sym()
+    ) ;
+    log_try!(
+      event, unroller.assert(
+        & actlit.activate_term( TmpTerm::Trm( body.clone() ) ), & k
+      ) => "while asserting preference {} at {}", pref.sym(), k
+    ) ;
+    prefs.push( (pref.sym().get().clone(), actlit, body) )
+  }
+
   props.reset_inhibited() ;
 
+  // Every reachable state is reachable without going past the recurrence
+  // diameter, if all depths are checked.
+  let diameter = if * conf.diameter() && exhaustive {
+    let diameter = Bounds::of(& sys).diameter() ;
+    if let Some(diameter) = diameter {
+      event.log(
+        & format!("estimated recurrence diameter: {}", diameter)
+      )
+    }
+    diameter
+  } else { None } ;
+
   // Check for init is separate since only one-state properties must be
   // checked.
   let mut doing_init = true ;
@@ -110,17 +339,33 @@ fn bmc<
         event, unroller.unroll(& k)
         => "while unrolling system at {}", k
       ) ;
+      let nxt = k.nxt() ;
+      log_try!(
+        event, unroller.assert_schedule(& schedule, & nxt)
+        => "while asserting schedule at {}", nxt
+      ) ;
+      for & (ref sym, ref actlit, ref body) in prefs.iter() {
+        log_try!(
+          event, unroller.assert(
+            & actlit.activate_term( TmpTerm::Trm( body.clone() ) ), & nxt
+          ) => "while asserting preference {} at {}", sym, nxt
+        )
+      }
     }
 
     props.reset_inhibited() ;
 
+    let mut usage = unroller.mem_usage() ;
+    usage.extend( props.mem_usage() ) ;
+    event.stats(usage) ;
+
     match event.recv() {
       None => break,
       Some(msgs) => for msg in msgs {
         match msg {
-          MsgDown::Forget(ps, _) => log_try!(
-            event, props.forget(unroller.solver(), ps.iter())
-            => "while forgetting property in manager"
+          MsgDown::Forget(ps, status) => log_try!(
+            event, props.forget_status(unroller.solver(), ps.iter(), & status)
+            => "while forgetting {} {} properties in manager", ps.len(), status
           ),
           MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
             // event.log(
@@ -131,8 +376,29 @@ fn bmc<
               => "while adding invariants from supervisor"
             )
           },
-          msg => event.error(
-            format!("unexpected message `{:?}`", msg).into()
+          MsgDown::NewProps(ps) => {
+            let count = ps.len() ;
+            log_try!(
+              event, unroller.defclare_prop_funs(& ps)
+              => "while declaring functions for {} new properties", count
+            ) ;
+            let new = log_try!(
+              event, props.add_props(ps, unroller.solver())
+              => "while adding {} new properties to manager", count
+            ) ;
+            // Offsets before `k` have been checked already, but not for
+            // these properties.
+            if ! doing_init {
+              log_try!(
+                event, catch_up(
+                  & mut unroller, & mut props, & new, & k,
+                  * conf.incremental(), & depths, event
+                ) => "while checking {} new properties below {}", count, k
+              )
+            }
+          },
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
           )
         }
       },
@@ -143,14 +409,41 @@ fn bmc<
       break
     }
 
-    // Check that the unrolling is satisfiable by itself.
-    if ! log_try!(
-      event, unroller.check_sat()
-      => "could not perform `check-sat`"
-    ) {
+    if ! event.query() {
+      event.out_of_queries(k.curr()) ;
+      return ()
+    }
+
+    if event.clock().is_over() {
+      event.out_of_time( k.curr(), & format!("checking depth {}", k) ) ;
+      return ()
+    }
+
+    // Depths not checked are only unrolled.
+    let checked = depths.checks(
+      if doing_init { 0 } else { k.curr().to_usize() + 1 }
+    ) ;
+
+    // Check that the unrolling is satisfiable by itself. If the solver
+    // cannot tell, the properties are checked anyway.
+    let unrolling_sat = if checked {
+      let res = unroller.check_sat() ;
+      event.solver_unknowns( unroller.unknowns() ) ;
+      match res {
+        Err(ref e) if unknown_reason(e).is_some() && (
+          * conf.on_unknown() == OnUnknown::Skip
+        ) => true,
+        res => log_try!(
+          event, res => "could not perform `check-sat`"
+        ),
+      }
+    } else { true } ;
+    if ! unrolling_sat {
       // No more transitions can be taken, all remaining properties
-      // hold.
-      event.proved_at( props.not_inhibited(), k.curr() ) ;
+      // hold if all depths were checked on all the traces.
+      if exhaustive {
+        event.proved_at( props.not_inhibited(), k.curr() )
+      }
       event.warning(
         & format!("no more reachable state after {} transitions", k)
       ) ;
@@ -159,7 +452,9 @@ fn bmc<
     } ;
 
     'this_k: loop {
-      
+
+      if ! checked { break 'this_k }
+
       // If we're doing init, only check one state properties.
       if let Some(
         one_prop_false
@@ -167,28 +462,75 @@ fn bmc<
         props.one_false_state()
       } else { props.one_false_next() } {
 
-        // Setting up the negative actlit.
-        let actlit = log_try!(
-          event, unroller.fresh_actlit()
-          => "while declaring activation literal at {}", k
-        ) ;
-        let implication = actlit.activate_term(one_prop_false) ;
+        // Building list of actlits for this check.
+        let mut actlits = props.actlits() ;
 
-        log_try!(
-          event, unroller.assert(& implication, & k)
-          => "while asserting implication at {} (2)", k
+        let neg = log_try!(
+          event, Neg::assert(
+            & mut unroller, * conf.incremental(), one_prop_false, & k,
+            & mut actlits
+          ) => "while asserting implication at {} (2)", k
         ) ;
 
-        // Building list of actlits for this check.
-        let mut actlits = props.actlits() ;
-        actlits.push(actlit.name()) ;
+        if ! event.query() {
+          event.out_of_queries(k.curr()) ;
+          return ()
+        }
 
         // Check sat.
+        let res = unroller.check_sat_assuming( & actlits ) ;
+        event.solver_unknowns( unroller.unknowns() ) ;
+        let skipped = match res {
+          Err(ref e) => match unknown_reason(e) {
+            Some(ref reason) if (
+              * conf.on_unknown() == OnUnknown::Skip
+            ) => Some( format!("skipping depth {}: {}", k, reason) ),
+            _ => None,
+          },
+          Ok(true) if unroller.last_unknown() => Some(
+            format!(
+              "candidate counterexample at {} not reported, the solver \
+              answered `unknown`", k
+            )
+          ),
+          Ok(_) => None,
+        } ;
+        if let Some(blah) = skipped {
+          // Nothing is known about this depth, the properties cannot be
+          // k-true or proved anymore.
+          event.warning(& blah) ;
+          exhaustive = false ;
+          log_try!(
+            event, neg.retract(& mut unroller)
+            => "could not retract the negation of the properties"
+          ) ;
+          break 'this_k
+        }
         let is_sat = log_try!(
-          event, unroller.check_sat_assuming( & actlits )
-          => "during a `check_sat_assuming` query at {}", k
+          event, res => "during a `check_sat_assuming` query at {}", k
         ) ;
 
+        if is_sat && ! prefs.is_empty() {
+          match log_try!(
+            event, prefer(& mut unroller, & actlits, & prefs, event)
+            => "while looking for a counterexample satisfying the preferences"
+          ) {
+            None => {
+              event.out_of_queries(k.curr()) ;
+              return ()
+            },
+            Some(kept) => if kept < prefs.len() {
+              let mut dropped = String::new() ;
+              for & (ref sym, _, _) in prefs[kept..].iter() {
+                dropped.push_str( & format!(" {}", sym) )
+              }
+              event.log(
+                & format!("preference(s) dropped at {}:{}", k, dropped)
+              )
+            },
+          }
+        }
+
         if is_sat {
           // event.log("sat, getting falsified properties") ;
           let falsified = log_try!(
@@ -199,24 +541,43 @@ fn bmc<
             } => "could not retrieve falsified properties"
           ) ;
           let model = log_try!(
-            event, unroller.solver().get_model()
+            event, unroller.get_full_model()
             => "could not retrieve model"
           ) ;
           log_try!(
-            event, props.forget(unroller.solver(), falsified.iter())
-            => "while forgetting property in manager"
+            event, neg.retract(& mut unroller)
+            => "could not retract the negation of the properties"
           ) ;
           log_try!(
-            event, unroller.deactivate(actlit)
-            => "could not deactivate negative actlit"
+            event, props.forget(unroller.solver(), falsified.iter())
+            => "while forgetting property in manager"
           ) ;
+          event.reachable(& model) ;
+          // Localizing and extending counterexamples takes extra queries,
+          // skipped near the deadline.
+          let near = event.clock().is_near() ;
+          if conf.localize().is_some() && ! near {
+            let falsified: Vec<Prop> = all_props.iter().filter(
+              |prop| falsified.contains( prop.sym().get() )
+            ).cloned().collect() ;
+            localize_cex(& conf, & sys, & falsified, & model, event)
+          }
+          if * conf.cex_extend() > 0 && ! near {
+            extend_cex(& conf, & sys, & falsified, & model, event)
+          }
           event.disproved_at(model, falsified, k.curr())
         } else {
           // event.log("unsat") ;
-          event.k_true(props.not_inhibited(), k.curr()) ;
+          // Properties are k-true only if all depths were checked on all
+          // the traces.
+          if exhaustive {
+            let k_true = props.not_inhibited() ;
+            props.set_bounds( k_true.iter(), k.curr() ) ;
+            event.k_true(k_true, k.curr())
+          }
           log_try!(
-            event, unroller.deactivate(actlit)
-            => "could not deactivate negative actlit"
+            event, neg.retract(& mut unroller)
+            => "could not retract the negation of the properties"
           ) ;
           break 'this_k
         }
@@ -242,6 +603,31 @@ fn bmc<
 
     }
 
+    event.depth_done( props.keys(), k.curr() ) ;
+
+    if let Some(diameter) = diameter {
+      if exhaustive && ! doing_init && k.curr().to_usize() > diameter {
+        let proved = props.keys() ;
+        event.log(
+          & format!(
+            "{} property(ies) true up to the estimated recurrence diameter {}",
+            proved.len(), diameter
+          )
+        ) ;
+        event.proved_at( proved, k.curr() ) ;
+        event.done_at( k.curr() ) ;
+        return ()
+      }
+    }
+
+    // Stopping once the maximum unrolling is checked.
+    if let Some(max) = * conf.max() {
+      if ! doing_init && k.curr().to_usize() >= max {
+        event.max_reached( k.curr() ) ;
+        return ()
+      }
+    }
+
     if ! doing_init {
       k = k.nxt()
     } else {
@@ -251,6 +637,535 @@ fn bmc<
   }
 }
 
+/// BMC from several scenarios, one-state properties restricting the initial
+/// states.
+///
+/// Each scenario is asserted in the initial state under its own activation
+/// literal, so that all scenarios share the same unrolling. At each depth,
+/// the properties not falsified yet from a scenario are checked from this
+/// scenario. A counterexample from a scenario is a real counterexample, but
+/// properties are never k-true since the scenarios may not cover all the
+/// initial states.
+///
+/// Falsifications are logged per scenario as they are found. Properties are
+/// reported falsified once all scenarios have been checked at the depth they
+/// were first falsified at, and forgotten once falsified from all scenarios.
+/// Stops after unrolling at `max`, if any. The schedule is asserted on the
+/// unrolling, shared by all the scenarios.
+fn bmc_scenarios<
+  'a, S: SolverTrait<'a>
+>(
+  mut unroller: Unroller<S>, mut props: PropManager,
+  scenarios: Vec<Prop>, sys: Sys, mode: conf::Incremental, depths: Depths,
+  schedule: & Schedule, max: Option<usize>, event: & mut Event
+) {
+  let init_off = Offset2::init() ;
+  let mut k = Offset2::init() ;
+
+  // Name of each scenario, its actlit, and the properties falsified from it
+  // with the depth they were falsified at.
+  let mut scens = Vec::with_capacity( scenarios.len() ) ;
+  for scenario in scenarios.iter() {
+    let init = match * scenario.body() {
+      STerm::One(ref state, _) => state.clone(),
+      STerm::Two(_) => {
+        log_error!(
+          event, format!("scenario {} is a two-state property", scenario.sym())
+        ) ;
+        return ()
+      },
+    } ;
+    let actlit = log_try!(
+      event, unroller.fresh_actlit()
+      => "while declaring activation literal for scenario {}", scenario.sym()
+    ) ;
+    log_try!(
+      event, unroller.assert(
+        & actlit.activate_term( TmpTerm::Trm(init) ), & init_off
+      ) => "while asserting scenario {}", scenario.sym()
+    ) ;
+    scens.push(
+      ( scenario.sym().get().clone(), actlit.name(), HashMap::new() )
+    )
+  }
+
+  // Properties already reported falsified.
+  let mut reported = HashSet::new() ;
+
+  let mut doing_init = true ;
+
+  loop {
+
+    if ! doing_init {
+      log_try!(
+        event, unroller.unroll(& k)
+        => "while unrolling system at {}", k
+      ) ;
+      log_try!(
+        event, unroller.assert_schedule(schedule, & k.nxt())
+        => "while asserting schedule at {}", k.nxt()
+      ) ;
+    }
+
+    props.reset_inhibited() ;
+
+    let mut usage = unroller.mem_usage() ;
+    usage.extend( props.mem_usage() ) ;
+    event.stats(usage) ;
+
+    match event.recv() {
+      None => break,
+      Some(msgs) => for msg in msgs {
+        match msg {
+          // Properties falsified are still checked from the other scenarios.
+          MsgDown::Forget(ps, status) => if status.is_proved() {
+            log_try!(
+              event, props.forget_status(
+                unroller.solver(), ps.iter(), & status
+              ) => "while forgetting {} {} properties in manager",
+              ps.len(), status
+            )
+          },
+          MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
+            log_try!(
+              event, unroller.add_invs(invs, & init_off, & k)
+              => "while adding invariants from supervisor"
+            )
+          },
+          MsgDown::NewProps(ps) => {
+            let count = ps.len() ;
+            log_try!(
+              event, unroller.defclare_prop_funs(& ps)
+              => "while declaring functions for {} new properties", count
+            ) ;
+            log_try!(
+              event, props.add_props(ps, unroller.solver())
+              => "while adding {} new properties to manager", count
+            ) ;
+            if ! doing_init {
+              event.warning(
+                & format!(
+                  "{} new properties only checked from {} on", count, k
+                )
+              )
+            }
+          },
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
+          )
+        }
+      },
+    } ;
+
+    if props.none_left() {
+      log_scenarios(& scens, event) ;
+      event.done_at(k.curr()) ;
+      break
+    }
+
+    // Counterexamples found at this depth.
+    let mut cexs = vec![] ;
+    // Depths not checked are only unrolled.
+    let checked = depths.checks(
+      if doing_init { 0 } else { k.curr().to_usize() + 1 }
+    ) ;
+
+    for & mut (
+      ref name, ref scen_actlit, ref mut falsified
+    ) in scens.iter_mut() {
+      props.reset_inhibited() ;
+      for prop in falsified.keys() {
+        props.inhibit_one(prop) ; ()
+      }
+
+      while let Some(one_prop_false) = if ! checked {
+        None
+      } else if doing_init {
+        props.one_false_state()
+      } else { props.one_false_next() } {
+        if ! event.query() {
+          event.out_of_queries(k.curr()) ;
+          return ()
+        }
+
+        let mut actlits = props.actlits() ;
+        actlits.push( scen_actlit.clone() ) ;
+        let neg = log_try!(
+          event, Neg::assert(
+            & mut unroller, mode, one_prop_false, & k, & mut actlits
+          ) => "while asserting implication at {} (scenario {})", k, name
+        ) ;
+
+        let res = unroller.check_sat_assuming( & actlits ) ;
+        event.solver_unknowns( unroller.unknowns() ) ;
+        let skip = unroller.last_unknown() || match res {
+          Err(ref e) => unknown_reason(e).is_some() && (
+            unroller.on_unknown() == OnUnknown::Skip
+          ),
+          Ok(_) => false,
+        } ;
+        if skip {
+          event.warning(
+            & format!(
+              "scenario {}: skipping depth {}, the solver answered \
+              `unknown`", name, k
+            )
+          ) ;
+          log_try!(
+            event, neg.retract(& mut unroller)
+            => "could not retract the negation of the properties"
+          ) ;
+          break
+        }
+        let is_sat = log_try!(
+          event, res => "during a `check_sat_assuming` query at {}", k
+        ) ;
+
+        if is_sat {
+          let falsified_now = log_try!(
+            event, if doing_init {
+              props.get_false_state(unroller.solver(), & k)
+            } else {
+              props.get_false_next(unroller.solver(), & k)
+            } => "could not retrieve falsified properties"
+          ) ;
+          let model = log_try!(
+            event, unroller.get_full_model()
+            => "could not retrieve model"
+          ) ;
+          log_try!(
+            event, neg.retract(& mut unroller)
+            => "could not retract the negation of the properties"
+          ) ;
+          let mut s = String::new() ;
+          for prop in falsified_now.iter() {
+            props.inhibit_one(prop) ;
+            falsified.insert( prop.clone(), k.curr().clone() ) ;
+            s.push_str( & format!(" {}", prop) )
+          }
+          event.log(
+            & format!(
+              "scenario {}: falsified{} at {}", name, s, k.curr()
+            )
+          ) ;
+          cexs.push( (model, falsified_now) )
+        } else {
+          log_try!(
+            event, neg.retract(& mut unroller)
+            => "could not retract the negation of the properties"
+          ) ;
+          break
+        }
+      }
+    }
+
+    props.reset_inhibited() ;
+
+    // Reporting the properties falsified for the first time.
+    for (model, falsified) in cexs.into_iter() {
+      let new: Vec<Sym> = falsified.into_iter().filter(
+        |prop| reported.insert( prop.clone() )
+      ).collect() ;
+      if ! new.is_empty() {
+        event.reachable(& model) ;
+        event.disproved_at(model, new, k.curr())
+      }
+    }
+
+    // Forgetting the properties falsified from all scenarios.
+    let done: Vec<Sym> = reported.iter().filter(
+      |prop| props.contains(prop) && scens.iter().all(
+        |& (_, _, ref falsified)| falsified.contains_key(* prop)
+      )
+    ).cloned().collect() ;
+    if ! done.is_empty() {
+      log_try!(
+        event, props.forget(unroller.solver(), done.iter())
+        => "while forgetting properties falsified from all scenarios"
+      )
+    }
+
+    event.depth_done( props.keys(), k.curr() ) ;
+
+    if let Some(max) = max {
+      if ! doing_init && k.curr().to_usize() >= max {
+        log_scenarios(& scens, event) ;
+        event.max_reached( k.curr() ) ;
+        return ()
+      }
+    }
+
+    if ! doing_init {
+      k = k.nxt()
+    } else {
+      doing_init = false
+    }
+
+  }
+}
+
+/// Logs the verdicts of each scenario.
+fn log_scenarios(
+  scens: & [ (Sym, String, HashMap<Sym, Offset>) ], event: & Event
+) {
+  for & (ref name, _, ref falsified) in scens.iter() {
+    if falsified.is_empty() {
+      event.log( & format!("scenario {}: no property falsified", name) )
+    } else {
+      let mut falsified: Vec<_> = falsified.iter().collect() ;
+      falsified.sort_by( |& (_, lhs), & (_, rhs)| lhs.cmp(rhs) ) ;
+      let mut s = String::new() ;
+      for (prop, off) in falsified {
+        s.push_str( & format!(" {} ({})", prop, off) )
+      }
+      event.log( & format!("scenario {}: falsified{}", name, s) )
+    }
+  }
+}
+
+/// Looks for a counterexample satisfying the preferences after a
+/// satisfiable check under `actlits`. The least important preferences are
+/// dropped first, until there is one. The last check is satisfiable so that
+/// the counterexample can be retrieved.
+///
+/// Returns the number of preferences kept, or nothing if the query budget
+/// is exhausted.
+fn prefer<'a, S: SolverTrait<'a>>(
+  unroller: & mut Unroller<S>, actlits: & [String],
+  prefs: & [(Sym, Actlit, Term)], event: & Event
+) -> Res< Option<usize> > {
+  let mut kept = prefs.len() ;
+  while kept > 0 {
+    if ! event.query() { return Ok(None) }
+    let mut assumed = actlits.to_vec() ;
+    for & (_, ref actlit, _) in prefs[0..kept].iter() {
+      assumed.push( actlit.name() )
+    }
+    if try!( unroller.check_sat_assuming(& assumed) ) {
+      return Ok( Some(kept) )
+    }
+    kept -= 1
+  }
+  // Going back to the check without preferences.
+  if ! event.query() { return Ok(None) }
+  if try!( unroller.check_sat_assuming(actlits) ) {
+    Ok( Some(0) )
+  } else {
+    Err( "check without preferences is not satisfiable anymore".into() )
+  }
+}
+
+/// Maximum number of check-sats when localizing a counterexample.
+const LOCALIZE_QUERIES: usize = 500 ;
+
+/// Logs the conjuncts of the transition relation most likely responsible for
+/// a counterexample.
+fn localize_cex(
+  conf: & conf::Bmc, sys: & Sys, props: & [Prop], model: & Model,
+  event: & Event
+) {
+  let top = match * conf.localize() {
+    Some(top) => top,
+    None => return (),
+  } ;
+  let mut solver_conf = conf.smt().clone().default().print_success() ;
+  if let Some(ref cmd) = * conf.smt_cmd() {
+    solver_conf = solver_conf.cmd( cmd.clone() )
+  }
+  let suspects = mk_solver_run!(
+    solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+    "bmc_localize", event.factory(),
+    solver => localize::localize(
+      solver, sys, props, model, event.factory(), LOCALIZE_QUERIES
+    ),
+    err => Err(err)
+  ) ;
+  match suspects.chain_err( || "while localizing counterexample" ) {
+    Ok(ref suspects) if suspects.is_empty() => event.log(
+      "could not localize counterexample, \
+      the transition relation does not explain it"
+    ),
+    Ok(suspects) => {
+      let mut s = format!(
+        "most likely responsible conjuncts of the transition relation of {} \
+        {}:", sys.sym(), sys.sym().span
+      ) ;
+      for suspect in suspects.into_iter().take(top) {
+        s.push_str(
+          & format!(
+            "\n  #{} ({} step(s)): {}",
+            suspect.index, suspect.steps, suspect.conj
+          )
+        )
+      }
+      event.log(& s)
+    },
+    Err(e) => log_error!(event, e),
+  }
+}
+
+/// Logs the transitions following the last state of a counterexample, up to
+/// `cex_extend` of them. The states reached are sent as reachable.
+fn extend_cex(
+  conf: & conf::Bmc, sys: & Sys, falsified: & [Sym], model: & Model,
+  event: & Event
+) {
+  let mut solver_conf = conf.smt().clone().default().print_success() ;
+  if let Some(ref cmd) = * conf.smt_cmd() {
+    solver_conf = solver_conf.cmd( cmd.clone() )
+  }
+  let extended = mk_solver_run!(
+    solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+    "bmc_extend", event.factory(),
+    solver => extend::extend(
+      solver, sys, model, event.factory(), * conf.cex_extend()
+    ),
+    err => Err(err)
+  ) ;
+  match extended.chain_err( || "while extending counterexample" ) {
+    Ok(None) => event.log(
+      "no transition can be taken after the counterexample"
+    ),
+    Ok( Some(extended) ) => {
+      let len = SamplePool::states_of(model).len() ;
+      let mut s = "aftermath of the counterexample for".to_string() ;
+      for prop in falsified.iter() {
+        s.push_str( & format!(" {}", prop) )
+      }
+      s.push(':') ;
+      let states = SamplePool::states_of(& extended) ;
+      for (index, state) in states.iter().enumerate().skip(len) {
+        s.push_str( & format!("\n  state {}:", index) ) ;
+        for & (ref sym, ref cst) in state.iter() {
+          s.push_str( & format!(" {} = {},", sym, cst) )
+        }
+        s.pop() ; ()
+      }
+      event.log(& s) ;
+      event.reachable(& extended)
+    },
+    Err(e) => log_error!(event, e),
+  }
+}
+
+/// The `ite` branches of the transition relation of a system not taken in
+/// its first `bound` transitions from the initial states.
+///
+/// Each branch comes with a flag, true if it is provably never taken.
+pub fn dead_code(
+  conf: & conf::Bmc, sys: & Sys, factory: & Factory, bound: usize
+) -> Res< Vec<(deadcode::Branch, bool)> > {
+  let mut solver_conf = conf.smt().clone().default().print_success() ;
+  if let Some(ref cmd) = * conf.smt_cmd() {
+    solver_conf = solver_conf.cmd( cmd.clone() )
+  }
+  let branches = deadcode::branches(& sys.trans().2, factory) ;
+  let taken = try!(
+    mk_solver_run!(
+      solver_conf.clone(), conf.smt_log(), conf.smt_cache(),
+      conf.smt_prelude(), "bmc_dead_code", factory,
+      solver => deadcode::bmc_phase(solver, sys, & branches, bound),
+      err => Err(err)
+    ).chain_err( || "while looking for the branches taken" )
+  ) ;
+  let untaken: Vec<deadcode::Branch> = branches.into_iter().zip(
+    taken.into_iter()
+  ).filter_map(
+    |(branch, taken)| if taken.is_none() { Some(branch) } else { None }
+  ).collect() ;
+  if untaken.is_empty() { return Ok( vec![] ) }
+  let dead = try!(
+    mk_solver_run!(
+      solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+      "kind_dead_code", factory,
+      solver => deadcode::induction_phase(
+        solver, sys, & untaken, factory, bound
+      ),
+      err => Err(err)
+    ).chain_err( || "while proving branches dead" )
+  ) ;
+  Ok( untaken.into_iter().zip( dead.into_iter() ).collect() )
+}
+
+/// Checks some new properties at all the offsets strictly below `k`, at the
+/// depths checked.
+///
+/// Inhibits all the other properties for the checks, which have been done
+/// already for them. Sends a falsification message for each new property
+/// falsified.
+fn catch_up<
+  'a, S: SolverTrait<'a>
+>(
+  unroller: & mut Unroller<S>, props: & mut PropManager,
+  new: & [Sym], k: & Offset2, mode: conf::Incremental, depths: & Depths,
+  event: & Event
+) -> Res<()> {
+  let mut others = props.not_inhibited() ;
+  others.retain( |p| ! new.contains(p) ) ;
+  try!( props.inhibit(& others) ) ;
+
+  let mut off = Offset2::init() ;
+  // Check for init is separate since only one-state properties must be
+  // checked.
+  let mut doing_init = true ;
+
+  while & off < k {
+    let checked = depths.checks(
+      if doing_init { 0 } else { off.curr().to_usize() + 1 }
+    ) ;
+    while let Some(one_prop_false) = if ! checked {
+      None
+    } else if doing_init {
+      props.one_false_state()
+    } else { props.one_false_next() } {
+      if ! event.query() {
+        // Out of queries, BMC stops before its next check.
+        props.uninhibit( others.iter() ) ;
+        return Ok(())
+      }
+      let mut actlits = props.actlits() ;
+      let neg = try!(
+        Neg::assert(
+          unroller, mode, one_prop_false, & off, & mut actlits
+        ).chain_err(
+          || format!("while asserting implication at {} (catch up)", off)
+        )
+      ) ;
+
+      if try!( unroller.check_sat_assuming( & actlits ) ) {
+        let falsified = try!(
+          if doing_init {
+            props.get_false_state(unroller.solver(), & off)
+          } else {
+            props.get_false_next(unroller.solver(), & off)
+          }.chain_err( || "could not retrieve falsified properties" )
+        ) ;
+        let model = try!(
+          unroller.get_full_model().chain_err(
+            || "could not retrieve model"
+          )
+        ) ;
+        try!( neg.retract(unroller) ) ;
+        try!( props.forget(unroller.solver(), falsified.iter()) ) ;
+        event.reachable(& model) ;
+        event.disproved_at(model, falsified, off.curr())
+      } else {
+        try!( neg.retract(unroller) ) ;
+        break
+      }
+    }
+
+    if ! doing_init {
+      off = off.nxt()
+    } else {
+      doing_init = false
+    }
+  }
+
+  props.uninhibit( others.iter() ) ;
+  Ok(())
+}
+
 /// Configuration for BMC.
 #[derive(Clone)]
 pub struct BmcConf {
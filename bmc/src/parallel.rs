@@ -0,0 +1,328 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! BMC checking several depths in parallel.
+//!
+//! The thread of the technique hands out depths to a pool of workers through
+//! a work queue, at most one per worker past the smallest depth it has not
+//! reported yet. Each worker has its own solver and unroller, unrolls up to
+//! the depths it takes, and checks the properties still alive when the
+//! depth was handed out. Results are reported by increasing depth, so the
+//! first counterexample reported for a property is a shortest one.
+//!
+//! Depth `0` checks the one-state properties on the initial states, depth
+//! `d > 0` checks all the properties after `d - 1` unrollings, the same way
+//! sequential BMC does.
+//!
+//! Invariants and new properties sent by the supervisor are ignored, so are
+//! scenarios and preferences.
+
+use std::thread ;
+use std::sync::{ Arc, Mutex } ;
+use std::sync::mpsc::{ channel, Sender, Receiver } ;
+use std::collections::{ HashMap, HashSet } ;
+
+use term::{ Sym, Model, Offset, Offset2, Factory } ;
+
+use common::SolverTrait ;
+use common::conf ;
+use common::msg::{ Event, MsgDown } ;
+use common::errors::* ;
+
+use system::{ Sys, Prop } ;
+use system::bounds::Bounds ;
+
+use unroll::* ;
+
+use Neg ;
+
+/// A depth to check, with the properties still alive.
+struct Job {
+  /// Depth to check.
+  depth: usize,
+  /// Properties to check.
+  props: Vec<Sym>,
+}
+
+/// What a worker found at some depth.
+struct Done {
+  /// Depth checked.
+  depth: usize,
+  /// Counterexamples, with the properties they falsify.
+  cexs: Vec<(Model, Vec<Sym>)>,
+  /// Properties true at this depth.
+  k_true: Vec<Sym>,
+}
+
+/// Offset reported for a depth, the one sequential BMC reports.
+fn offset_of(depth: usize) -> Offset {
+  Offset::of_int( depth.saturating_sub(1) )
+}
+
+/// Runs BMC with `workers` solvers checking consecutive depths.
+pub fn bmc(
+  workers: usize, conf: Arc<conf::Bmc>, sys: Sys, props: Vec<Prop>,
+  event: & mut Event
+) {
+  event.set_max_queries( * conf.max_queries() ) ;
+
+  let diameter = if * conf.diameter() {
+    Bounds::of(& sys).diameter()
+  } else { None } ;
+
+  let (job_sender, jobs) = channel() ;
+  let jobs = Arc::new( Mutex::new(jobs) ) ;
+  let (result_sender, results) = channel() ;
+  for worker in 0..workers {
+    let (conf, sys, props) = ( conf.clone(), sys.clone(), props.clone() ) ;
+    let (jobs, results) = ( jobs.clone(), result_sender.clone() ) ;
+    let factory = event.factory().clone() ;
+    thread::spawn(
+      move || work(worker, conf, sys, props, factory, jobs, results)
+    ) ;
+  }
+  // Only the workers can send results now.
+  drop(result_sender) ;
+
+  let mut alive: Vec<Sym> = props.iter().map(
+    |prop| prop.sym().get().clone()
+  ).collect() ;
+  // Next depth to hand out.
+  let mut next = 0 ;
+  // Next depth to report.
+  let mut reported: usize = 0 ;
+  // Depths done but not reported yet.
+  let mut pending = HashMap::new() ;
+
+  loop {
+
+    match event.recv() {
+      None => return (),
+      Some(msgs) => for msg in msgs {
+        match msg {
+          MsgDown::Forget(ps, _) => alive.retain( |sym| ! ps.contains(sym) ),
+          MsgDown::Invariants(_, _) => (),
+          MsgDown::NewProps(ps) => event.warning(
+            & format!("parallel BMC ignores {} new properties", ps.len())
+          ),
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
+          ),
+        }
+      },
+    }
+
+    if alive.is_empty() {
+      event.done_at( & offset_of( reported.saturating_sub(1) ) ) ;
+      return ()
+    }
+
+    // Depth `max + 1` checks the unrolling at `max`, the last one.
+    while next < reported + workers
+    && conf.max().map_or(true, |max| next <= max + 1) {
+      if ! event.query() {
+        event.out_of_queries( & offset_of(next) ) ;
+        return ()
+      }
+      let job = Job { depth: next, props: alive.clone() } ;
+      if job_sender.send(job).is_err() {
+        log_error!(event, "all BMC workers are gone") ;
+        return ()
+      }
+      next += 1
+    }
+
+    match results.recv() {
+      Ok( Ok(done) ) => {
+        pending.insert(done.depth, done) ;
+        ()
+      },
+      Ok( Err(e) ) => {
+        log_error!(event, e) ;
+        return ()
+      },
+      Err(_) => {
+        log_error!(event, "all BMC workers are gone") ;
+        return ()
+      },
+    }
+
+    while let Some(done) = pending.remove(& reported) {
+      report(done, & mut alive, event) ;
+      reported += 1 ;
+      if alive.is_empty() { break }
+      // Every reachable state is reachable without going past the
+      // recurrence diameter.
+      if let Some(diameter) = diameter {
+        if reported > diameter + 1 {
+          event.log(
+            & format!(
+              "{} property(ies) true up to the estimated recurrence \
+              diameter {}", alive.len(), diameter
+            )
+          ) ;
+          let o = offset_of(reported - 1) ;
+          event.proved_at( alive, & o ) ;
+          event.done_at(& o) ;
+          return ()
+        }
+      }
+      if let Some(max) = * conf.max() {
+        if reported > max + 1 {
+          event.max_reached( & offset_of(max + 1) ) ;
+          return ()
+        }
+      }
+    }
+  }
+}
+
+/// Reports what a worker found, ignoring the properties falsified at a
+/// smaller depth.
+fn report(done: Done, alive: & mut Vec<Sym>, event: & Event) {
+  let o = offset_of(done.depth) ;
+  for (model, falsified) in done.cexs.into_iter() {
+    let falsified: Vec<Sym> = falsified.into_iter().filter(
+      |sym| alive.contains(sym)
+    ).collect() ;
+    if falsified.is_empty() { continue }
+    alive.retain( |sym| ! falsified.contains(sym) ) ;
+    event.reachable(& model) ;
+    event.disproved_at(model, falsified, & o)
+  }
+  let k_true: Vec<Sym> = done.k_true.into_iter().filter(
+    |sym| alive.contains(sym)
+  ).collect() ;
+  if ! k_true.is_empty() {
+    event.k_true(k_true, & o)
+  }
+  event.depth_done( alive.clone(), & o )
+}
+
+/// Creates the solver of a worker and serves the jobs of the work queue.
+fn work(
+  worker: usize, conf: Arc<conf::Bmc>, sys: Sys, props: Vec<Prop>,
+  factory: Factory, jobs: Arc< Mutex< Receiver<Job> > >,
+  results: Sender< Res<Done> >
+) {
+  let mut solver_conf = conf.smt().clone().default().print_success() ;
+  if let Some(ref cmd) = * conf.smt_cmd() {
+    solver_conf = solver_conf.cmd( cmd.clone() )
+  }
+  let res = mk_solver_run!(
+    solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+    race conf.smt_race(), compare conf.smt_compare(),
+    format!("bmc_{}", worker), & factory,
+    solver => serve(solver, & conf, & sys, props, & jobs, & results),
+    err => Err(err)
+  ) ;
+  if let Err(e) = res.chain_err( || format!("in BMC worker {}", worker) ) {
+    let _ = results.send( Err(e) ) ;
+    ()
+  }
+}
+
+/// Checks the depths of the work queue until it is closed.
+fn serve<'a, S: SolverTrait<'a>>(
+  solver: S, conf: & conf::Bmc, sys: & Sys, props: Vec<Prop>,
+  jobs: & Mutex< Receiver<Job> >, results: & Sender< Res<Done> >
+) -> Res<()> {
+  let mut unroller = try_chain!(
+    Unroller::mk_encoded(sys, & props, solver, conf.encoding())
+    => "while creating unroller"
+  ) ;
+  let mut props = try_chain!(
+    PropManager::mk(props, unroller.solver())
+    => "while creating property manager"
+  ) ;
+  let mut k = Offset2::init() ;
+  try_chain!( unroller.assert_init(& k) => "while asserting init" ) ;
+  // Number of unrollings so far, `k` is the offset of the last one.
+  let mut unrollings = 0 ;
+
+  loop {
+    let job = match jobs.lock() {
+      Ok(jobs) => match jobs.recv() {
+        Ok(job) => job,
+        Err(_) => return Ok(()),
+      },
+      Err(_) => return Ok(()),
+    } ;
+
+    while unrollings < job.depth {
+      if unrollings > 0 { k = k.nxt() }
+      try_chain!(
+        unroller.unroll(& k) => format!("while unrolling system at {}", k)
+      ) ;
+      unrollings += 1
+    }
+
+    let keep: HashSet<Sym> = job.props.into_iter().collect() ;
+    try_chain!(
+      props.retain(unroller.solver(), & keep)
+      => "while forgetting properties in manager"
+    ) ;
+    props.reset_inhibited() ;
+
+    let doing_init = job.depth == 0 ;
+    let mut cexs = vec![] ;
+    loop {
+      let one_prop_false = if doing_init {
+        props.one_false_state()
+      } else { props.one_false_next() } ;
+      let one_prop_false = match one_prop_false {
+        Some(term) => term,
+        None => break,
+      } ;
+      let mut actlits = props.actlits() ;
+      let neg = try_chain!(
+        Neg::assert(
+          & mut unroller, * conf.incremental(), one_prop_false, & k,
+          & mut actlits
+        ) => format!("while asserting implication at {}", k)
+      ) ;
+      let is_sat = try_chain!(
+        unroller.check_sat_assuming(& actlits)
+        => format!("during a `check_sat_assuming` query at {}", k)
+      ) ;
+      if is_sat {
+        let falsified = try_chain!(
+          if doing_init {
+            props.get_false_state(unroller.solver(), & k)
+          } else {
+            props.get_false_next(unroller.solver(), & k)
+          } => "could not retrieve falsified properties"
+        ) ;
+        let model = try_chain!(
+          unroller.get_full_model() => "could not retrieve model"
+        ) ;
+        try_chain!(
+          neg.retract(& mut unroller)
+          => "could not retract the negation of the properties"
+        ) ;
+        try_chain!(
+          props.forget(unroller.solver(), falsified.iter())
+          => "while forgetting property in manager"
+        ) ;
+        cexs.push( (model, falsified) )
+      } else {
+        try_chain!(
+          neg.retract(& mut unroller)
+          => "could not retract the negation of the properties"
+        ) ;
+        break
+      }
+    }
+
+    let done = Done {
+      depth: job.depth, cexs: cexs, k_true: props.not_inhibited(),
+    } ;
+    if results.send( Ok(done) ).is_err() { return Ok(()) }
+  }
+}
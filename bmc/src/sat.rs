@@ -0,0 +1,260 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! BMC of boolean-only systems with a SAT solver.
+//!
+//! The unrolling is an and-inverter graph where frame `k` is the state at
+//! depth `k`. Each query is written as a CNF in a DIMACS file, given as last
+//! argument to the SAT solver command. The solver must answer in the format
+//! of the SAT competition: an `s SATISFIABLE` or `s UNSATISFIABLE` line and,
+//! if satisfiable, the model on `v` lines.
+
+use std::io::Write ;
+use std::fs::{ self, File } ;
+use std::process::Command ;
+
+use term::{
+  Sym, Var, Cst, Term, Model, Offset, State, Factory, VarMaker, CstMaker,
+  let_expand
+} ;
+use term::aig::{ Aig, Lit, neg } ;
+
+use common::conf ;
+use common::msg::{ Event, MsgDown } ;
+use common::errors::* ;
+
+use system::{ Sys, Prop } ;
+use system::aiger::{ aig_of, prop_lit } ;
+
+/// Runs BMC on a boolean-only system with the SAT solver `cmd`.
+pub fn bmc(
+  cmd: & str, conf: & conf::Bmc, sys: & Sys, props: Vec<Prop>,
+  event: & mut Event
+) {
+  let factory = event.factory().clone() ;
+  let mut aig = aig_of(& factory, sys) ;
+  let path = format!(
+    "{}/kino_bmc_{}_{}.cnf",
+    ::std::env::temp_dir().to_string_lossy(), sys.sym(),
+    ::std::process::id()
+  ) ;
+  ::common::cleanup::temp(& path) ;
+
+  event.set_max_queries( * conf.max_queries() ) ;
+
+  let init = log_try!(
+    event, lit_of(& factory, & mut aig, & sys.init().2, 0)
+    => "while encoding the init predicate"
+  ) ;
+  let mut roots = vec![ init ] ;
+  let mut props = props ;
+  let mut k = 0 ;
+
+  'unroll: while conf.max().map_or(true, |max| k <= max) {
+
+    if k > 0 {
+      let trans = log_try!(
+        event, lit_of(& factory, & mut aig, & sys.trans().2, k - 1)
+        => "while encoding the transition relation at {}", k
+      ) ;
+      roots.push(trans)
+    }
+
+    match event.recv() {
+      None => break 'unroll,
+      Some(msgs) => for msg in msgs {
+        match msg {
+          MsgDown::Forget(ps, _) => props.retain(
+            |prop| ! ps.contains( prop.sym().get() )
+          ),
+          // Invariants and new properties are not supported.
+          _ => (),
+        }
+      },
+    }
+
+    // Two-state properties are checked from depth 1.
+    let mut checked = Vec::with_capacity( props.len() ) ;
+    for prop in props.iter() {
+      let frame = match (prop.body().state().is_some(), k) {
+        (true, _) => k,
+        (false, 0) => continue,
+        (false, _) => k - 1,
+      } ;
+      let lit = log_try!(
+        event, prop_lit(& factory, & mut aig, prop, frame).map_err(
+          Error::from
+        ) => "while encoding property {} at {}", prop.sym(), k
+      ) ;
+      checked.push( (prop.sym().get().clone(), lit) )
+    }
+
+    while ! checked.is_empty() {
+      if ! event.query() {
+        event.out_of_queries( & Offset::of_int(k) ) ;
+        return ()
+      }
+
+      let lits: Vec<Lit> = checked.iter().map(
+        |& (_, lit)| neg(lit)
+      ).collect() ;
+      let one_false = aig.or_all(& lits) ;
+      let mut query = roots.clone() ;
+      query.push(one_false) ;
+
+      let values = log_try!(
+        event, check(cmd, & aig, & query, & path)
+        => "during SAT query at {}", k
+      ) ;
+
+      if let Some(values) = values {
+        let (falsified, rest): (Vec<_>, Vec<_>) = checked.into_iter(
+        ).partition(
+          |& (_, lit)| ! value(& values, lit)
+        ) ;
+        checked = rest ;
+        let falsified: Vec<Sym> = falsified.into_iter().map(
+          |(sym, _)| sym
+        ).collect() ;
+        props.retain( |prop| ! falsified.contains( prop.sym().get() ) ) ;
+        let model = model_of(& factory, & aig, sys, & values, k) ;
+        event.reachable(& model) ;
+        event.disproved_at( model, falsified, & Offset::of_int(k) )
+      } else {
+        let k_true = checked.into_iter().map( |(sym, _)| sym ).collect() ;
+        event.k_true( k_true, & Offset::of_int(k) ) ;
+        break
+      }
+    }
+
+    if props.is_empty() { break 'unroll }
+
+    event.depth_done(
+      props.iter().map( |prop| prop.sym().get().clone() ).collect(),
+      & Offset::of_int(k)
+    ) ;
+
+    k += 1
+  }
+
+  let _ = fs::remove_file(& path) ;
+  match * conf.max() {
+    Some(max) if k > max && ! props.is_empty() => event.max_reached(
+      & Offset::of_int(max)
+    ),
+    _ => event.done_at( & Offset::of_int(k) ),
+  }
+}
+
+/// Literal of a term of the system at some frame.
+fn lit_of(
+  factory: & Factory, aig: & mut Aig, term: & Term, frame: usize
+) -> Res<Lit> {
+  aig.lit( & let_expand(factory, term), frame ).map_err(Error::from)
+}
+
+/// Value of a literal in a model, indexed by variable.
+fn value(values: & [bool], lit: Lit) -> bool {
+  let val = values.get(lit >> 1).cloned().unwrap_or(false) ;
+  if lit & 1 == 1 { ! val } else { val }
+}
+
+/// Model of the states of the system from frame `0` to `k`.
+fn model_of(
+  factory: & Factory, aig: & Aig, sys: & Sys, values: & [bool], k: usize
+) -> Model {
+  let mut model = vec![] ;
+  for frame in 0..(k + 1) {
+    for & (ref sym, _) in sys.state().args().iter() {
+      // Not constrained, any value will do.
+      let val = match aig.get_leaf(sym.get(), frame) {
+        Some(lit) => value(values, lit),
+        None => false,
+      } ;
+      let var: Var = factory.svar( sym.get().clone(), State::Curr ) ;
+      let cst: Cst = factory.cst(val) ;
+      model.push( ( (var, Some( Offset::of_int(frame) )), cst ) )
+    }
+  }
+  model
+}
+
+/// Writes the graph asserting some literals to `path` and checks it with the
+/// SAT solver `cmd`. Returns the values of the variables if satisfiable.
+fn check(
+  cmd: & str, aig: & Aig, roots: & [Lit], path: & str
+) -> Res< Option< Vec<bool> > > {
+  {
+    let mut file = match File::create(path) {
+      Ok(file) => file,
+      Err(e) => return Err(
+        ErrorKind::FileIoError(path.to_string(), e).into()
+      ),
+    } ;
+    if let Err(e) = aig.write_dimacs(& mut file, roots) {
+      return Err(
+        ErrorKind::FileIoError(path.to_string(), e).into()
+      )
+    }
+    if let Err(e) = file.flush() {
+      return Err(
+        ErrorKind::FileIoError(path.to_string(), e).into()
+      )
+    }
+  }
+
+  let mut words = cmd.split_whitespace() ;
+  let cmd = match words.next() {
+    Some(cmd) => cmd,
+    None => return Err( "empty SAT solver command".into() ),
+  } ;
+  let args: Vec<& str> = words.collect() ;
+  // SAT solvers exit with 10 or 20, the status is meaningless.
+  let output = match Command::new(cmd).args(& args).arg(path).output() {
+    Ok(output) => output,
+    Err(e) => return Err( ErrorKind::IoError(e).into() ),
+  } ;
+  let output = String::from_utf8_lossy(& output.stdout) ;
+
+  let mut sat = None ;
+  let mut values = vec![ false ; aig.var_count() + 1 ] ;
+  for line in output.lines() {
+    let line = line.trim() ;
+    if line.starts_with("s ") {
+      sat = match line[2..].trim() {
+        "SATISFIABLE" => Some(true),
+        "UNSATISFIABLE" => Some(false),
+        answer => return Err(
+          format!("unexpected answer `{}` from SAT solver", answer).into()
+        ),
+      }
+    } else if line.starts_with("v ") {
+      for lit in line[2..].split_whitespace() {
+        match lit.parse::<i64>() {
+          Ok(0) => (),
+          Ok(lit) => if let Some(val) = values.get_mut(
+            lit.abs() as usize
+          ) {
+            * val = lit > 0
+          },
+          Err(_) => return Err(
+            format!("illegal literal `{}` in SAT solver model", lit).into()
+          ),
+        }
+      }
+    }
+  }
+  match sat {
+    Some(true) => Ok( Some(values) ),
+    Some(false) => Ok(None),
+    None => Err(
+      format!("no answer from SAT solver `{}`", cmd).into()
+    ),
+  }
+}
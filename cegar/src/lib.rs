@@ -0,0 +1,588 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+#![deny(missing_docs)]
+
+//! Predicate abstraction, refined with the counterexamples it finds.
+//!
+//! The system is abstracted over a set of predicates on the current state:
+//! an abstract state is a valuation of the predicates, standing for the
+//! concrete states satisfying it. The solver enumerates the abstract
+//! initial states and the abstract successors of each abstract state, which
+//! explores the abstraction breadth-first. The first predicates are the
+//! properties, so an abstract state falsifies a property iff the predicate
+//! of the property is false in it.
+//!
+//! If no reachable abstract state falsifies a property, the properties are
+//! proved. Otherwise the path leading to such a state is checked on the
+//! system, with the initial predicate and each conjunct of the transition
+//! relation at each step under an activation literal. If it is feasible it
+//! is a counterexample. If it is spurious, the unsat core of the activation
+//! literals gives the terms explaining why, and the atoms of these terms
+//! over a single state become new predicates.
+//!
+//! Atoms mixing the current and the next state are ignored, as well as
+//! two-state properties. The properties are unknown if a spurious path
+//! yields no new predicate, or if the abstraction needs more than
+//! `max_preds` predicates.
+
+extern crate term ;
+extern crate system ;
+#[macro_use]
+extern crate common ;
+extern crate unroll ;
+
+use std::sync::Arc ;
+use std::collections::{ HashSet, HashMap } ;
+
+use term::{
+  Sym, Term, STerm, Model, Offset, Offset2, State, Type, Factory, Operator,
+  UnTermOps, CstMaker, real_term, vars_of
+} ;
+use term::tmp::TmpTerm ;
+
+use common::conf ;
+use common::SolverTrait ;
+use common::unsat_core ;
+use common::errors::* ;
+use common::msg::{ Event, MsgDown, Unknown } ;
+
+use system::{ Sys, Prop } ;
+
+use unroll::* ;
+use unroll::localize::conjuncts ;
+
+/// Predicate abstraction.
+pub struct Cegar ;
+unsafe impl Send for Cegar {}
+impl common::CanRun<conf::Cegar> for Cegar {
+  fn id(& self) -> common::Tek { common::Tek::Cegar }
+
+  fn run(
+    & self, conf: Arc<conf::Cegar>, sys: Sys, props: Vec<Prop>,
+    mut event: Event
+  ) {
+    let mut solver_conf = conf.smt().clone().default().print_success() ;
+    match * conf.smt_cmd() {
+      None => (),
+      Some(ref cmd) => solver_conf = solver_conf.cmd(cmd.clone()),
+    } ;
+
+    mk_solver_run!(
+      solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+      "cegar", event.factory(),
+      solver => cegar(solver, conf.clone(), sys, props, & mut event),
+      err => log_error!(event, err)
+    )
+  }
+}
+
+/// An abstract state, the value of each predicate.
+type AbsState = Vec<bool> ;
+
+/// Abstract states reached, with the state each of them is first reached
+/// from.
+struct Reached {
+  /// The states and the index of their predecessor, if any.
+  states: Vec< (AbsState, Option<usize>) >,
+  /// Index of each state.
+  index: HashMap<AbsState, usize>,
+}
+impl Reached {
+  /// No state reached.
+  fn mk() -> Self {
+    Reached { states: vec![], index: HashMap::new() }
+  }
+  /// Adds a state reached from another one, if any. Returns its index if it
+  /// is new.
+  fn add(& mut self, state: AbsState, from: Option<usize>) -> Option<usize> {
+    if self.index.contains_key(& state) { return None }
+    let index = self.states.len() ;
+    self.index.insert( state.clone(), index ) ;
+    self.states.push( (state, from) ) ;
+    Some(index)
+  }
+  /// A state.
+  #[inline]
+  fn state(& self, index: usize) -> & AbsState { & self.states[index].0 }
+  /// The states from an initial state to some state.
+  fn path_to(& self, mut index: usize) -> Vec<AbsState> {
+    let mut path = vec![ self.state(index).clone() ] ;
+    while let Some(from) = self.states[index].1 {
+      path.push( self.state(from).clone() ) ;
+      index = from
+    }
+    path.reverse() ;
+    path
+  }
+}
+
+/// Outcome of the exploration of the abstraction.
+enum Explored {
+  /// No reachable abstract state falsifies a property, all of them are
+  /// reached in that many steps.
+  Safe(usize),
+  /// Abstract states from an initial state to one falsifying a property.
+  Bad(Vec<AbsState>),
+  /// The query budget is exhausted.
+  OutOfQueries,
+}
+
+/// Outcome of the check of an abstract path on the system.
+enum Checked {
+  /// The path is feasible.
+  Cex(Model),
+  /// The path is spurious, the atoms explaining why.
+  Spurious(Vec<Term>),
+  /// The query budget is exhausted.
+  OutOfQueries,
+}
+
+/// Predicate abstraction of a system.
+struct Abstraction {
+  /// Initial predicate.
+  init: Term,
+  /// Top-level conjuncts of the transition relation.
+  conjs: Vec<Term>,
+  /// Boolean state variables, atoms on their own.
+  bools: HashSet<Sym>,
+  /// Predicates mined from spurious paths, after the ones of the
+  /// properties.
+  mined: Vec<Term>,
+  /// One-state invariants received, asserted in all states.
+  invs: Vec<Term>,
+}
+impl Abstraction {
+  /// Abstraction of a system, no predicate mined yet.
+  fn mk(sys: & Sys, factory: & Factory) -> Self {
+    let bools = sys.state().args().iter().filter_map(
+      |& (ref sym, ref typ)| if * typ.get() == Type::Bool {
+        Some( sym.get().clone() )
+      } else { None }
+    ).collect() ;
+    Abstraction {
+      init: sys.init().2.clone(),
+      conjs: conjuncts(& sys.trans().2, factory),
+      bools: bools,
+      mined: vec![],
+      invs: vec![],
+    }
+  }
+
+  /// The predicates: the properties, then the mined ones.
+  fn preds(& self, props: & [Prop]) -> Vec<Term> {
+    props.iter().filter_map(
+      |prop| match * prop.body() {
+        STerm::One(ref curr, _) => Some( curr.clone() ),
+        STerm::Two(_) => None,
+      }
+    ).chain( self.mined.iter().cloned() ).collect()
+  }
+
+  /// Adds the atoms of a term over a single state to `atoms`, as terms over
+  /// the current state.
+  fn atoms_of(
+    & self, term: & Term, factory: & Factory, atoms: & mut Vec<Term>
+  ) {
+    match * term.get() {
+      real_term::Term::V(ref var) => if self.bools.contains( var.get().sym() ) {
+        add_atom(term, factory, atoms)
+      },
+      real_term::Term::Op(ref op, ref kids) => {
+        match * op {
+          Operator::Eq | Operator::Distinct | Operator::IsInt |
+          Operator::Le | Operator::Ge | Operator::Lt | Operator::Gt |
+          Operator::BvUlt | Operator::BvUle | Operator::BvUgt |
+          Operator::BvUge | Operator::BvSlt | Operator::BvSle |
+          Operator::BvSgt | Operator::BvSge => add_atom(term, factory, atoms),
+          _ => (),
+        }
+        for kid in kids.iter() { self.atoms_of(kid, factory, atoms) }
+      },
+      // Atoms mentioning bound variables are ignored by `add_atom`.
+      real_term::Term::Let(_, ref body) => self.atoms_of(body, factory, atoms),
+      real_term::Term::App(_, ref kids) => for kid in kids.iter() {
+        self.atoms_of(kid, factory, atoms)
+      },
+      _ => (),
+    }
+  }
+}
+
+/// Adds an atom if it only mentions state variables, all in the current
+/// state or all in the next one. Atoms over the next state are brought back
+/// to the current one.
+fn add_atom(atom: & Term, factory: & Factory, atoms: & mut Vec<Term>) {
+  let vars = vars_of(atom) ;
+  let states: HashSet<Option<State>> = vars.iter().map(
+    |var| var.get().state()
+  ).collect() ;
+  let atom = if states.len() != 1 {
+    return ()
+  } else if states.contains( & Some(State::Curr) ) {
+    atom.clone()
+  } else if states.contains( & Some(State::Next) ) {
+    match factory.debump(atom) {
+      Ok(atom) => atom,
+      Err(_) => return (),
+    }
+  } else {
+    return ()
+  } ;
+  if ! atoms.contains(& atom) { atoms.push(atom) }
+}
+
+/// Conjunction of the predicates, negated if false in an abstract state.
+fn cube(factory: & Factory, preds: & [Term], state: & AbsState) -> Term {
+  if preds.is_empty() { return factory.cst(true) }
+  factory.and(
+    preds.iter().zip( state.iter() ).map(
+      |(pred, value)| if * value {
+        pred.clone()
+      } else {
+        factory.not( pred.clone() )
+      }
+    ).collect()
+  )
+}
+
+/// Resets the solver and declares the functions of the system.
+fn restart<'a, S: SolverTrait<'a>>(
+  unroller: & mut Unroller<S>, props: & [Prop]
+) -> Res<()> {
+  try!( unroller.reset() ) ;
+  // Resetting the solver resets its options.
+  try!( unsat_core::enable( unroller.solver() ) ) ;
+  unroller.defclare_funs(props)
+}
+
+/// The abstract states satisfying the assertions, by blocking them one by
+/// one. The predicates are evaluated at `off`. Returns nothing if the query
+/// budget is exhausted.
+fn enumerate<'a, S: SolverTrait<'a>>(
+  unroller: & mut Unroller<S>, factory: & Factory, preds: & [Term],
+  off: & Offset2, assumptions: & [String], event: & Event
+) -> Res< Option< Vec<AbsState> > > {
+  let mut res = vec![] ;
+  loop {
+    if ! event.query() { return Ok(None) }
+    let is_sat = if assumptions.is_empty() {
+      try!( unroller.check_sat() )
+    } else {
+      try!( unroller.check_sat_assuming(assumptions) )
+    } ;
+    if ! is_sat { return Ok( Some(res) ) }
+    if preds.is_empty() {
+      res.push( vec![] ) ;
+      return Ok( Some(res) )
+    }
+    let values = try!(
+      unroller.solver().get_values(preds, off).chain_err(
+        || "while retrieving the values of the predicates"
+      )
+    ) ;
+    let state: AbsState = values.into_iter().map(
+      |(_, value)| match * value.get() {
+        real_term::Cst::Bool(b) => b,
+        _ => false,
+      }
+    ).collect() ;
+    try!(
+      unroller.assert(
+        & factory.not( cube(factory, preds, & state) ), off
+      )
+    ) ;
+    res.push(state)
+  }
+}
+
+/// Explores the abstraction breadth-first, until all abstract states are
+/// reached or one of them falsifies one of the first `props` predicates.
+fn explore<'a, S: SolverTrait<'a>>(
+  unroller: & mut Unroller<S>, abs: & Abstraction, preds: & [Term],
+  props: usize, factory: & Factory, event: & Event
+) -> Res<Explored> {
+  let (curr, next) = ( Offset2::init(), Offset2::init().nxt() ) ;
+  try!( unroller.declare_svars( curr.curr() ) ) ;
+  try!( unroller.declare_svars( next.curr() ) ) ;
+  for inv in abs.invs.iter() {
+    try!( unroller.assert(inv, & curr) ) ;
+    try!( unroller.assert(inv, & next) )
+  }
+  let trans = try!( unroller.fresh_actlit() ) ;
+  let trans_body = TmpTerm::Trm( factory.and( abs.conjs.clone() ) ) ;
+  try!( unroller.assert( & trans.activate_term(trans_body), & curr ) ) ;
+  let trans = vec![ trans.name() ] ;
+
+  let mut reached = Reached::mk() ;
+
+  try!( unroller.push() ) ;
+  try!( unroller.assert(& abs.init, & curr) ) ;
+  let init = match try!(
+    enumerate(unroller, factory, preds, & curr, & [], event)
+  ) {
+    Some(init) => init,
+    None => return Ok(Explored::OutOfQueries),
+  } ;
+  try!( unroller.pop() ) ;
+  let mut frontier: Vec<usize> = init.into_iter().filter_map(
+    |state| reached.add(state, None)
+  ).collect() ;
+
+  let mut depth = 0 ;
+  loop {
+    for index in frontier.iter() {
+      if reached.state(* index)[0 .. props].iter().any( |value| ! value ) {
+        return Ok( Explored::Bad( reached.path_to(* index) ) )
+      }
+    }
+    if frontier.is_empty() { return Ok( Explored::Safe(depth) ) }
+
+    let mut successors = vec![] ;
+    for from in frontier.into_iter() {
+      try!( unroller.push() ) ;
+      let state = cube( factory, preds, reached.state(from) ) ;
+      try!( unroller.assert(& state, & curr) ) ;
+      let posts = match try!(
+        enumerate(unroller, factory, preds, & next, & trans, event)
+      ) {
+        Some(posts) => posts,
+        None => return Ok(Explored::OutOfQueries),
+      } ;
+      try!( unroller.pop() ) ;
+      for post in posts.into_iter() {
+        if let Some(index) = reached.add( post, Some(from) ) {
+          successors.push(index)
+        }
+      }
+    }
+    frontier = successors ;
+    depth += 1
+  }
+}
+
+/// Checks an abstract path on the system.
+fn check_path<'a, S: SolverTrait<'a>>(
+  unroller: & mut Unroller<S>, abs: & Abstraction, preds: & [Term],
+  path: & [AbsState], factory: & Factory, event: & Event
+) -> Res<Checked> {
+  // Activation literals, and the term each of them activates.
+  let mut actlits: Vec<(String, Term)> = vec![] ;
+  let mut off = Offset2::init() ;
+  for (step, state) in path.iter().enumerate() {
+    let terms = if step == 0 {
+      try!( unroller.declare_svars( off.curr() ) ) ;
+      vec![ abs.init.clone() ]
+    } else {
+      try!( unroller.declare_svars( off.next() ) ) ;
+      abs.conjs.clone()
+    } ;
+    for term in terms.into_iter() {
+      let actlit = try!( unroller.fresh_actlit() ) ;
+      try!(
+        unroller.assert(
+          & actlit.activate_term( TmpTerm::Trm( term.clone() ) ), & off
+        )
+      ) ;
+      actlits.push( (actlit.name(), term) )
+    }
+    if step > 0 { off = off.nxt() }
+    for inv in abs.invs.iter() {
+      try!( unroller.assert(inv, & off) )
+    }
+    try!( unroller.assert( & cube(factory, preds, state), & off ) )
+  }
+
+  if ! event.query() { return Ok(Checked::OutOfQueries) }
+  let names: Vec<String> = actlits.iter().map(
+    |& (ref name, _)| name.clone()
+  ).collect() ;
+  if try!( unroller.check_sat_assuming(& names) ) {
+    let model = try!(
+      unroller.get_full_model().chain_err(
+        || "while retrieving counterexample"
+      )
+    ) ;
+    Ok( Checked::Cex(model) )
+  } else {
+    let core = try!( unsat_core::get( unroller.solver() ) ) ;
+    let mut atoms = vec![] ;
+    for & (ref name, ref term) in actlits.iter() {
+      if core.contains(name) { abs.atoms_of(term, factory, & mut atoms) }
+    }
+    Ok( Checked::Spurious(atoms) )
+  }
+}
+
+/// The one-state properties, warns about the other ones.
+fn one_state(props: Vec<Prop>, event: & Event) -> Vec<Prop> {
+  props.into_iter().filter(
+    |prop| match * prop.body() {
+      STerm::One(_, _) => true,
+      STerm::Two(_) => {
+        event.warning(
+          & format!("ignoring two-state property {}", prop.sym())
+        ) ;
+        false
+      },
+    }
+  ).collect()
+}
+
+fn cegar<
+  'a,
+  S: SolverTrait<'a>
+>(
+  mut solver: S, conf: Arc<conf::Cegar>,
+  sys: Sys, props: Vec<Prop>, event: & mut Event
+) {
+  event.set_max_queries( * conf.max_queries() ) ;
+  let factory = event.factory().clone() ;
+
+  log_try!(
+    event, unsat_core::enable(& mut solver)
+    => "while activating unsat core production"
+  ) ;
+
+  let mut props = one_state(props, event) ;
+  let mut abs = Abstraction::mk(& sys, & factory) ;
+
+  let mut unroller = log_try!(
+    event, Unroller::mk(& sys, & props, solver)
+    => "while creating unroller"
+  ) ;
+
+  // Length of the last abstract path checked.
+  let mut depth = Offset::zero() ;
+
+  loop {
+
+    match event.recv() {
+      None => return (),
+      Some(msgs) => for msg in msgs {
+        match msg {
+          MsgDown::Forget(ps, _) => props.retain(
+            |prop| ! ps.contains( prop.sym().get() )
+          ),
+          MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym {
+            for inv in invs.into_iter() {
+              if let STerm::One(inv, _) = inv {
+                if ! abs.invs.contains(& inv) { abs.invs.push(inv) }
+              }
+            }
+          },
+          MsgDown::NewProps(ps) => props.extend( one_state(ps, event) ),
+          MsgDown::KTrue(_, _) => (),
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
+          ),
+        }
+      },
+    }
+
+    if props.is_empty() {
+      event.done_at(& depth) ;
+      return ()
+    }
+
+    let preds = abs.preds(& props) ;
+    log_try!(
+      event, restart(& mut unroller, & props)
+      => "while restarting the solver"
+    ) ;
+    let explored = log_try!(
+      event, explore(
+        & mut unroller, & abs, & preds, props.len(), & factory, event
+      ) => "while exploring the abstraction over {} predicates", preds.len()
+    ) ;
+
+    let path = match explored {
+      Explored::OutOfQueries => {
+        event.out_of_queries(& depth) ;
+        return ()
+      },
+      Explored::Safe(steps) => {
+        event.log(
+          & format!(
+            "abstraction over {} predicates saturated after {} steps",
+            preds.len(), steps
+          )
+        ) ;
+        let proved = props.iter().map(
+          |prop| prop.sym().get().clone()
+        ).collect() ;
+        let steps = Offset::of_int(steps) ;
+        event.proved_at(proved, & steps) ;
+        event.done_at(& steps) ;
+        return ()
+      },
+      Explored::Bad(path) => path,
+    } ;
+
+    depth = Offset::of_int( path.len() - 1 ) ;
+    log_try!(
+      event, restart(& mut unroller, & props)
+      => "while restarting the solver"
+    ) ;
+    let checked = log_try!(
+      event, check_path(
+        & mut unroller, & abs, & preds, & path, & factory, event
+      ) => "while checking an abstract path of length {}", path.len()
+    ) ;
+
+    match checked {
+      Checked::OutOfQueries => {
+        event.out_of_queries(& depth) ;
+        return ()
+      },
+      Checked::Cex(model) => {
+        let mut falsified = vec![] ;
+        let mut left = Vec::with_capacity( props.len() ) ;
+        let last = & path[ path.len() - 1 ] ;
+        for (prop, value) in props.into_iter().zip( last.iter() ) {
+          if * value { left.push(prop) } else {
+            falsified.push( prop.sym().get().clone() )
+          }
+        }
+        props = left ;
+        event.reachable(& model) ;
+        event.disproved_at(model, falsified, & depth)
+      },
+      Checked::Spurious(atoms) => {
+        let new: Vec<Term> = atoms.into_iter().filter(
+          |atom| ! preds.contains(atom)
+        ).collect() ;
+        if new.is_empty() || preds.len() + new.len() > * conf.max_preds() {
+          let why = if new.is_empty() {
+            format!(
+              "the spurious path of length {} yields no new predicate",
+              path.len()
+            )
+          } else {
+            format!(
+              "it needs more than {} predicates", conf.max_preds()
+            )
+          } ;
+          event.warning(
+            & format!("could not refine the abstraction, {}", why)
+          ) ;
+          event.unknown( Unknown::Abstraction(why) ) ;
+          event.done_at(& depth) ;
+          return ()
+        }
+        event.log(
+          & format!(
+            "spurious path of length {}, {} new predicate(s)",
+            path.len(), new.len()
+          )
+        ) ;
+        abs.mined.extend(new)
+      },
+    }
+  }
+}
@@ -0,0 +1,309 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Persistent cache for SMT queries.
+//!
+//! A [`CachedSolver`](struct.CachedSolver.html) wraps a solver and hashes
+//! every command it sends. The answers to the queries (`check-sat`,
+//! `check-sat-assuming`, `get-model`, `get-value` and `get-proof`) are
+//! stored on disk, keyed by the hash of the solver command and of all the
+//! commands sent before them: declarations, assertions, assumptions and
+//! previous queries.
+//! When the exact same sequence of commands is seen again, the answers are
+//! replayed from the disk instead of querying the solver.
+//! The commands are hashed as they are written, so the techniques must send
+//! them in a deterministic order. This is why the unroller declares the
+//! functions in the order of the system, and the property managers iterate
+//! over their properties in the order of the stable hashes of their keys.
+//! Invariants received from other techniques depend on timing, the keys of
+//! the queries after them are rarely hit.
+//!
+//! Only definite answers are cached: `unknown` answers to `check-sat`s and
+//! errors are not, so that a query timing out once is asked again.
+//!
+//! The cache is best effort: answers that cannot be written to the disk are
+//! simply not cached.
+
+use std::io::{ Read, Write } ;
+use std::fs ;
+use std::path::PathBuf ;
+
+use term::{ Factory, StableHasher } ;
+use term::smt::{
+  PlainSolver, Solver, Query, QueryIdent, QueryExpr, QueryExprInfo,
+  Sym2Smt, Expr2Smt, ParseSmt2
+} ;
+use term::smt::internals::{ SolverBasic, SolverPrims } ;
+use term::smt::errors::{ Res as SmtRes, ResExt as SmtResExt } ;
+use term::smt::errors::ErrorKind as SmtErrorKind ;
+
+use SolverTrait ;
+
+/// A directory storing the answers to SMT queries.
+///
+/// Each answer lives in its own file, named after the (hexadecimal) hash of
+/// the query.
+pub struct QueryCache {
+  /// Directory of the cache.
+  dir: PathBuf,
+}
+impl QueryCache {
+  /// Creates a cache in a directory. The directory is created when the first
+  /// answer is stored.
+  pub fn mk(dir: & str) -> Self {
+    QueryCache { dir: PathBuf::from(dir) }
+  }
+
+  /// Path of the file storing the answer to a query.
+  fn path_of(& self, key: u64) -> PathBuf {
+    self.dir.join( format!("{:016x}.smt2", key) )
+  }
+
+  /// Answer to a query, if any.
+  pub fn get(& self, key: u64) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(100) ;
+    match fs::File::open( self.path_of(key) ) {
+      Ok(mut file) => match file.read_to_end(& mut bytes) {
+        Ok(_) => Some(bytes),
+        Err(_) => None,
+      },
+      Err(_) => None,
+    }
+  }
+
+  /// Stores the answer to a query. Returns `false` if the answer could not
+  /// be written.
+  pub fn put(& self, key: u64, answer: & [u8]) -> bool {
+    if fs::create_dir_all(& self.dir).is_err() {
+      return false
+    }
+    // Writing to a temporary file first so that concurrent runs never see a
+    // partial answer.
+    let path = self.path_of(key) ;
+    let tmp = path.with_extension("tmp") ;
+    ::cleanup::temp(& tmp) ;
+    let written = match fs::File::create(& tmp) {
+      Ok(mut file) => file.write_all(answer).is_ok(),
+      Err(_) => false,
+    } ;
+    let stored = written && fs::rename(& tmp, & path).is_ok() ;
+    if ! stored { let _ = fs::remove_file(& tmp) ; }
+    ::cleanup::forget(& tmp) ;
+    stored
+  }
+}
+
+/// What to do with the next answer fetched.
+enum Answer {
+  /// Not a query, nothing to do.
+  Plain,
+  /// Answer not in the cache, store it once it is fetched if it is definite,
+  /// see [`is_definite`](struct.CachedSolver.html#method.is_definite). The
+  /// flag is true for `check-sat`s.
+  Record(u64, bool),
+  /// Answer found in the cache, replay it.
+  Replay(Vec<u8>),
+}
+
+/// Wraps a solver, caching the answers to the queries on disk.
+///
+/// Commands that are not queries are always sent to the solver, so that its
+/// state is the same as without the cache. Queries answered by the cache are
+/// not sent. A `check-sat` answered by the cache is sent later if a query
+/// following it is not in the cache, since `get-model`, `get-value` and
+/// `get-proof` need it.
+pub struct CachedSolver<S> {
+  /// Solver wrapped.
+  solver: S,
+  /// Cache of the answers.
+  cache: QueryCache,
+  /// Hash of the commands so far.
+  hasher: StableHasher,
+  /// What to do with the next answer.
+  answer: Answer,
+  /// Last `check-sat` answered by the cache and not sent to the solver.
+  pending: Option<Vec<u8>>,
+}
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+CachedSolver<S> {
+  /// Wraps a solver. The command of the solver and its options are part of
+  /// the keys of the cache.
+  pub fn mk(mut solver: S, dir: & str) -> Self {
+    let mut hasher = StableHasher::mk() ;
+    {
+      let conf = solver.solver().conf() ;
+      hasher.write_str( conf.get_cmd() ) ;
+      hasher.write_usize( conf.get_options().len() ) ;
+      for option in conf.get_options() {
+        hasher.write_str(option)
+      }
+    }
+    CachedSolver {
+      solver: solver,
+      cache: QueryCache::mk(dir),
+      hasher: hasher,
+      answer: Answer::Plain,
+      pending: None,
+    }
+  }
+
+  /// Adds the prelude sent to the solver wrapped to the keys of the cache,
+  /// see [`prelude`](../fn.prelude.html). Must be called before sending
+  /// any command.
+  pub fn with_prelude(mut self, prelude: & str) -> Self {
+    self.hasher.write_str(prelude) ;
+    self
+  }
+
+  /// True if some command is a query whose answer can be cached.
+  fn is_query(cmd: & [u8]) -> bool {
+    let cmd = match ::std::str::from_utf8(cmd) {
+      Ok(cmd) => cmd.trim_left(),
+      Err(_) => return false,
+    } ;
+    cmd.starts_with("(check-sat") ||
+    cmd.starts_with("(get-model") ||
+    cmd.starts_with("(get-value") ||
+    cmd.starts_with("(get-proof")
+  }
+
+  /// True if some command is a `check-sat` or a `check-sat-assuming`.
+  fn is_check_sat(cmd: & [u8]) -> bool {
+    match ::std::str::from_utf8(cmd) {
+      Ok(cmd) => cmd.trim_left().starts_with("(check-sat"),
+      Err(_) => false,
+    }
+  }
+
+  /// True if an answer is worth caching: `sat` or `unsat` for a `check-sat`,
+  /// anything but an error otherwise.
+  fn is_definite(answer: & [u8], check_sat: bool) -> bool {
+    let answer = match ::std::str::from_utf8(answer) {
+      Ok(answer) => answer.trim(),
+      Err(_) => return false,
+    } ;
+    if check_sat {
+      answer == "sat" || answer == "unsat"
+    } else {
+      ! answer.starts_with("(error")
+    }
+  }
+
+  /// Sends some bytes to the solver.
+  fn send(& mut self, cmd: & [u8]) -> SmtRes<()> {
+    self.solver.write(
+      |w| w.write_all(cmd).chain_err(
+        || SmtErrorKind::IoError(
+          "writing command to cached solver".into()
+        )
+      )
+    )
+  }
+
+  /// Sends the pending `check-sat`, if any, and drops its answer.
+  fn flush_pending(& mut self) -> SmtRes<()> {
+    if let Some(cmd) = self.pending.take() {
+      try!( self.send(& cmd) ) ;
+      try!( self.solver.parse(|_, _| (String::new(), Ok(()))) )
+    }
+    Ok(())
+  }
+}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+SolverBasic<'kid, Factory> for CachedSolver<S> {
+  fn fetch(& mut self) -> SmtRes<()> {
+    match ::std::mem::replace(& mut self.answer, Answer::Plain) {
+      Answer::Plain => self.solver.fetch(),
+      Answer::Record(key, check_sat) => {
+        try!( self.solver.fetch() ) ;
+        if Self::is_definite( self.solver.as_ref(), check_sat ) {
+          let _ = self.cache.put( key, self.solver.as_ref() ) ;
+        }
+        Ok(())
+      },
+      // Kept until the next command so that `as_ref` can read it.
+      replay => {
+        self.answer = replay ;
+        Ok(())
+      },
+    }
+  }
+  fn write<
+    F: Fn(& mut Write) -> SmtRes<()>
+  >(& mut self, f: F) -> SmtRes<()> {
+    let mut cmd: Vec<u8> = Vec::with_capacity(100) ;
+    try!( f(& mut cmd) ) ;
+    self.hasher.write(& cmd) ;
+    self.answer = Answer::Plain ;
+    if ! Self::is_query(& cmd) {
+      // Not a query, the pending `check-sat` is not needed anymore.
+      self.pending = None ;
+      return self.send(& cmd)
+    }
+    let key = self.hasher.finish() ;
+    if let Some(answer) = self.cache.get(key) {
+      if Self::is_check_sat(& cmd) {
+        self.pending = Some(cmd)
+      }
+      self.answer = Answer::Replay(answer) ;
+      return Ok(())
+    }
+    let check_sat = Self::is_check_sat(& cmd) ;
+    if check_sat {
+      self.pending = None
+    } else {
+      try!( self.flush_pending() )
+    }
+    self.answer = Answer::Record(key, check_sat) ;
+    self.send(& cmd)
+  }
+  fn comment(& mut self, txt: & str) -> SmtRes<()> {
+    self.solver.comment(txt)
+  }
+  fn parser(& self) -> & Factory {
+    self.solver.parser()
+  }
+  fn as_ref(& self) -> & [u8] {
+    match self.answer {
+      Answer::Replay(ref answer) => & answer[..],
+      _ => self.solver.as_ref(),
+    }
+  }
+  fn solver(& mut self) -> & mut PlainSolver<'kid, Factory> {
+    self.solver.solver()
+  }
+}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+SolverPrims<'kid, Factory> for CachedSolver<S> {}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+Solver<'kid, Factory> for CachedSolver<S> {}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+Query<'kid, Factory> for CachedSolver<S> {}
+
+impl<
+  'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>,
+  Info, Ident: Sym2Smt<Info>
+> QueryIdent<'kid, Factory, Info, Ident> for CachedSolver<S> {}
+
+impl<
+  'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>,
+  Info, Expr: Expr2Smt<Info>
+> QueryExpr<'kid, Factory, Info, Expr> for CachedSolver<S> {}
+
+impl<
+  'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>,
+  Expr: Expr2Smt<<Factory as ParseSmt2>::I>
+> QueryExprInfo<'kid, Factory, Expr> for CachedSolver<S> {}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+SolverTrait<'kid> for CachedSolver<S> {}
@@ -0,0 +1,337 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Process-wide registry of the resources to clean up when kino stops.
+//!
+//! Techniques register the *temporary* files they create, and the files they
+//! create for the user such as smt traces, which are *kept*. The solvers
+//! they spawn are registered too, see [`solvers`](fn.solvers.html). When
+//! kino stops, [`run`](fn.run.html)
+//!
+//! - removes the temporary files still registered,
+//! - kills the solvers registered still running: solvers busy on a query
+//!   when kino stops would keep running otherwise, and
+//! - lists the files kept, if asked to.
+//!
+//! Kino calls `run` before exiting. [`install`](fn.install.html) makes sure
+//! it is also called, listing the files kept, when kino receives `SIGINT` or
+//! `SIGTERM`, and when its main thread panics. When another thread panics,
+//! the solvers it spawned are killed. Cleaning up happens at most once,
+//! files registered afterwards are not cleaned up. `install` also handles
+//! the signals [pausing](../pause/index.html) the techniques.
+//!
+//! The solvers spawned by a thread are found through `/proc`, they are not
+//! registered, and thus not killed, on systems without it.
+
+use std::fs ;
+use std::io::{ self, Read, Write } ;
+use std::path::{ Path, PathBuf } ;
+use std::sync::{ Mutex, Once, ONCE_INIT } ;
+use std::sync::atomic::{ AtomicIsize, Ordering, ATOMIC_ISIZE_INIT } ;
+
+/// Files and processes to clean up.
+struct Registry {
+  /// Temporary files, removed.
+  temp: Vec<PathBuf>,
+  /// Files kept, with a description.
+  kept: Vec<(PathBuf, String)>,
+  /// Solver processes, with the thread that spawned them.
+  solvers: Vec<(u32, u32)>,
+  /// True once the resources are cleaned up.
+  done: bool,
+}
+
+/// Initializes the registry.
+static INIT: Once = ONCE_INIT ;
+/// The registry, never freed.
+static mut REGISTRY: * const Mutex<Registry> = 0 as * const _ ;
+
+/// The registry of the process.
+fn registry() -> & 'static Mutex<Registry> {
+  unsafe {
+    INIT.call_once(
+      || REGISTRY = Box::into_raw(
+        Box::new(
+          Mutex::new(
+            Registry {
+              temp: vec![], kept: vec![], solvers: vec![], done: false
+            }
+          )
+        )
+      )
+    ) ;
+    & * REGISTRY
+  }
+}
+
+/// Applies something to the registry, unless it is poisoned.
+fn with_registry<T, F: FnOnce(& mut Registry) -> T>(f: F) -> Option<T> {
+  match registry().lock() {
+    Ok(mut registry) => Some( f(& mut registry) ),
+    Err(_) => None,
+  }
+}
+
+/// Registers a temporary file, removed when cleaning up.
+pub fn temp<P: AsRef<Path>>(path: P) {
+  let path = path.as_ref().to_path_buf() ;
+  with_registry( |reg| reg.temp.push(path) ) ;
+  ()
+}
+
+/// Registers a file to keep when cleaning up, described by `what`.
+pub fn keep<P: AsRef<Path>>(path: P, what: String) {
+  let path = path.as_ref().to_path_buf() ;
+  with_registry( |reg| reg.kept.push( (path, what) ) ) ;
+  ()
+}
+
+/// Forgets a file, typically a temporary file that was removed or renamed.
+pub fn forget<P: AsRef<Path>>(path: P) {
+  let path = path.as_ref() ;
+  with_registry(
+    |reg| {
+      reg.temp.retain( |temp| temp != path ) ;
+      reg.kept.retain( |& (ref kept, _)| kept != path )
+    }
+  ) ;
+  ()
+}
+
+/// Runs something spawning solvers, typically `Kid::mk`, and registers the
+/// processes the current thread spawned meanwhile. They are killed when
+/// cleaning up, or when the thread panics.
+pub fn solvers<T, F: FnOnce() -> T>(spawn: F) -> T {
+  let thread = thread_id() ;
+  let before = thread_kids(thread) ;
+  let res = spawn() ;
+  let spawned: Vec<(u32, u32)> = thread_kids(thread).into_iter().filter(
+    |pid| ! before.contains(pid)
+  ).map( |pid| (thread, pid) ).collect() ;
+  if ! spawned.is_empty() {
+    with_registry( |reg| reg.solvers.extend(spawned) ) ;
+  }
+  res
+}
+
+/// Cleans up the resources registered. Returns messages for the user: the
+/// number of processes killed, the temporary files that could not be
+/// removed, and the files kept if `list_kept`.
+///
+/// Does nothing if the resources are already cleaned up.
+pub fn run(list_kept: bool) -> Vec<String> {
+  let registered: Option<
+    Option< (Vec<PathBuf>, Vec<(PathBuf, String)>, Vec<(u32, u32)>) >
+  > = with_registry(
+    |reg| if reg.done { None } else {
+      reg.done = true ;
+      Some(
+        (
+          reg.temp.drain(..).collect(), reg.kept.drain(..).collect(),
+          reg.solvers.drain(..).collect()
+        )
+      )
+    }
+  ) ;
+  let (temp, kept, solvers) = match registered {
+    Some( Some(registered) ) => registered,
+    // Poisoned, nothing to clean up.
+    None => (vec![], vec![], vec![]),
+    Some(None) => return vec![],
+  } ;
+  let mut msgs: Vec<String> = vec![] ;
+
+  let killed = kill_solvers(& solvers) ;
+  if killed > 0 {
+    msgs.push( format!("killed {} solver process(es) still running", killed) )
+  }
+  for path in temp.into_iter() {
+    match fs::remove_file(& path) {
+      Ok(()) => (),
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+      Err(e) => msgs.push(
+        format!(
+          "could not remove temporary file `{}`: {}", path.display(), e
+        )
+      ),
+    }
+  }
+  if list_kept {
+    for (path, what) in kept.into_iter() {
+      if path.exists() {
+        msgs.push( format!("{} kept at `{}`", what, path.display()) )
+      }
+    }
+  }
+  msgs
+}
+
+/// Cleans up and prints the messages on `stderr`.
+fn run_and_print() {
+  let stderr = io::stderr() ;
+  let mut stderr = stderr.lock() ;
+  for msg in run(true).into_iter() {
+    let _ = writeln!(stderr, "kino: {}", msg) ;
+  }
+  ()
+}
+
+/// Kills the solvers spawned by the current thread, when it panics.
+fn kill_thread_solvers() {
+  let thread = thread_id() ;
+  let solvers = with_registry(
+    |reg| {
+      let (mine, others): (Vec<_>, Vec<_>) = reg.solvers.drain(..).partition(
+        |& (spawner, _)| spawner == thread
+      ) ;
+      reg.solvers = others ;
+      mine
+    }
+  ) ;
+  if let Some(solvers) = solvers {
+    kill_solvers(& solvers) ;
+  }
+  ()
+}
+
+/// Identifier of the current thread for the kernel.
+#[cfg(target_os = "linux")]
+fn thread_id() -> u32 {
+  unsafe { ::libc::syscall(::libc::SYS_gettid) as u32 }
+}
+#[cfg(not(target_os = "linux"))]
+fn thread_id() -> u32 { 0 }
+
+/// Processes spawned by a thread of this process and still running.
+#[cfg(target_os = "linux")]
+fn thread_kids(thread: u32) -> Vec<u32> {
+  let mut kids = String::new() ;
+  let read = fs::File::open(
+    format!("/proc/self/task/{}/children", thread)
+  ).and_then(
+    |mut file| file.read_to_string(& mut kids)
+  ) ;
+  if read.is_err() { return vec![] }
+  kids.split_whitespace().filter_map( |pid| pid.parse().ok() ).collect()
+}
+#[cfg(not(target_os = "linux"))]
+fn thread_kids(_: u32) -> Vec<u32> { vec![] }
+
+/// Kills the solvers registered that are still running, returns how many
+/// were killed. A process is only killed if it is still a child of this
+/// process, its id may have been reused otherwise.
+#[cfg(unix)]
+fn kill_solvers(solvers: & [(u32, u32)]) -> usize {
+  use libc::{ pid_t, getpid, kill, SIGKILL } ;
+  let me = unsafe { getpid() } ;
+  let mut killed = 0 ;
+  for & (_, pid) in solvers.iter() {
+    let mut stat = String::new() ;
+    let read = fs::File::open( format!("/proc/{}/stat", pid) ).and_then(
+      |mut file| file.read_to_string(& mut stat)
+    ) ;
+    if read.is_err() { continue }
+    // `<pid> (<name>) <state> <ppid> ...`, the name can contain anything.
+    let mut fields = match stat.rfind(')') {
+      Some(index) => stat[ index + 1 .. ].split_whitespace(),
+      None => continue,
+    } ;
+    let (state, ppid) = ( fields.next(), fields.next() ) ;
+    let ppid = ppid.and_then( |ppid| ppid.parse::<pid_t>().ok() ) ;
+    // Zombies are already dead.
+    if ppid == Some(me) && state != Some("Z") {
+      if unsafe { kill(pid as pid_t, SIGKILL) } == 0 { killed += 1 }
+    }
+  }
+  killed
+}
+#[cfg(not(unix))]
+fn kill_solvers(_: & [(u32, u32)]) -> usize { 0 }
+
+/// Write end of the pipe the signal handler writes the signals to.
+static SIGNAL_PIPE: AtomicIsize = ATOMIC_ISIZE_INIT ;
+
+/// Signal handler, forwards the signal to the thread waiting on the pipe.
+#[cfg(unix)]
+extern "C" fn on_signal(signal: ::libc::c_int) {
+  let byte = signal as u8 ;
+  let fd = SIGNAL_PIPE.load(Ordering::SeqCst) as ::libc::c_int ;
+  unsafe {
+    ::libc::write(fd, & byte as * const u8 as * const ::libc::c_void, 1) ;
+  }
+}
+
+/// Cleans up on `SIGINT` and `SIGTERM`, exiting with `128` plus the signal.
+/// Pauses the techniques on `SIGUSR1`, resumes them on `SIGUSR2`.
+#[cfg(unix)]
+fn install_signals() {
+  use libc::{ c_int, c_void, pipe, read, signal, sighandler_t } ;
+  use libc::{ SIGINT, SIGTERM, SIGUSR1, SIGUSR2 } ;
+  let mut fds: [c_int ; 2] = [0, 0] ;
+  if unsafe { pipe( fds.as_mut_ptr() ) } != 0 { return () }
+  SIGNAL_PIPE.store( fds[1] as isize, Ordering::SeqCst ) ;
+  let spawned = ::std::thread::Builder::new().name(
+    "cleanup".to_string()
+  ).spawn(
+    move || loop {
+      let mut byte = 0u8 ;
+      let got = unsafe {
+        read(fds[0], & mut byte as * mut u8 as * mut c_void, 1)
+      } ;
+      if got != 1 {
+        // Interrupted by a signal, which the handler wrote to the pipe.
+        if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+          continue
+        }
+        break
+      }
+      match byte as c_int {
+        SIGUSR1 => ::pause::request(true),
+        SIGUSR2 => ::pause::request(false),
+        _ => {
+          run_and_print() ;
+          ::std::process::exit(128 + byte as i32)
+        },
+      }
+    }
+  ) ;
+  if spawned.is_ok() {
+    let handler = on_signal as extern "C" fn(c_int) as sighandler_t ;
+    unsafe {
+      signal(SIGINT, handler) ;
+      signal(SIGTERM, handler) ;
+      signal(SIGUSR1, handler) ;
+      signal(SIGUSR2, handler) ;
+    }
+  }
+}
+#[cfg(not(unix))]
+fn install_signals() {}
+
+/// Makes sure the resources are cleaned up on `SIGINT`, `SIGTERM`, and when
+/// the main thread panics, that the solvers of the other threads are killed
+/// when they panic, and handles `SIGUSR1` and `SIGUSR2`. Call once, from the
+/// main thread.
+pub fn install() {
+  use std::panic ;
+  let previous = panic::take_hook() ;
+  panic::set_hook(
+    Box::new(
+      move |info| {
+        previous(info) ;
+        if ::std::thread::current().name() == Some("main") {
+          run_and_print()
+        } else {
+          kill_thread_solvers()
+        }
+      }
+    )
+  ) ;
+  install_signals()
+}
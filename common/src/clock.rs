@@ -0,0 +1,69 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Time budget of an analysis.
+//!
+//! kino starts a [`Clock`](struct.Clock.html) with the budget of the
+//! `--timeout` option and gives a copy to each technique with its
+//! [`Event`](../msg/struct.Event.html). The techniques use it to size their
+//! queries: they skip the checks that the previous ones suggest cannot end
+//! in time, and drop the optional ones near the deadline. kino itself stops
+//! waiting for them once the budget is exhausted.
+
+use std::time::{ Duration, Instant } ;
+
+/// The deadline is near once less than `1 / NEAR` of the budget is left.
+const NEAR: u32 = 10 ;
+
+/// Time elapsed since the start of an analysis, and its budget if any.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+  /// Start of the analysis.
+  start: Instant,
+  /// Time budget, if any.
+  budget: Option<Duration>,
+}
+impl Clock {
+  /// Starts a clock, unbounded if `budget` is `None`.
+  pub fn mk(budget: Option<Duration>) -> Self {
+    Clock { start: Instant::now(), budget: budget }
+  }
+  /// Time elapsed since the clock started.
+  #[inline]
+  pub fn elapsed(& self) -> Duration {
+    Instant::now() - self.start
+  }
+  /// Budget of the clock, if any.
+  #[inline]
+  pub fn budget(& self) -> Option<Duration> { self.budget }
+  /// Time left before the deadline, `None` if there is none.
+  pub fn left(& self) -> Option<Duration> {
+    self.budget.map(
+      |budget| budget.checked_sub( self.elapsed() ).unwrap_or(
+        Duration::from_secs(0)
+      )
+    )
+  }
+  /// True iff the deadline is passed.
+  pub fn is_over(& self) -> bool {
+    self.budget.map_or( false, |budget| self.elapsed() >= budget )
+  }
+  /// True iff less than `1 / NEAR` of the budget is left.
+  pub fn is_near(& self) -> bool {
+    match (self.budget, self.left()) {
+      (Some(budget), Some(left)) => left < budget / NEAR,
+      _ => false,
+    }
+  }
+  /// True iff there is no deadline or something taking `time` ends before
+  /// it.
+  pub fn has_time_for(& self, time: Duration) -> bool {
+    self.left().map_or( true, |left| time <= left )
+  }
+}
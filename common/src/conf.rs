@@ -9,10 +9,14 @@
 
 /*! Option handling stuff. */
 
+use std::collections::HashMap ;
+
 use nom::{ multispace, IResult } ;
 
 use term::smt::SolverStyle ;
 
+use sys::order::Order ;
+
 use log::{ Formatter, Styler, MasterLog } ;
 
 /// Can be printed.
@@ -27,6 +31,7 @@ trait Parse: Sized {
 }
 
 /// A configuration item.
+#[derive(Clone)]
 struct ConfItem<T> {
   /// The key identifying the option.
   key: & 'static str,
@@ -123,6 +128,208 @@ impl Parse for SolverStyle {
   }
 }
 
+/// Encoding of the transition relation used when unrolling a system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+  /// Transition relation as a predicate over the current and next state.
+  Rel,
+  /// Next state variables defined as functions of the current state when
+  /// possible.
+  Fun,
+}
+impl Print for Encoding {
+  fn to_str(& self) -> String {
+    match * self {
+      Encoding::Rel => "rel".to_string(),
+      Encoding::Fun => "fun".to_string(),
+    }
+  }
+}
+impl Parse for Encoding {
+  fn of(val: & str) -> Result<Encoding, String> {
+    match val {
+      "rel" => Ok(Encoding::Rel),
+      "fun" => Ok(Encoding::Fun),
+      _ => Err(
+        format!("expected encoding [rel/fun], got {}", val)
+      ),
+    }
+  }
+}
+
+/// How BMC retracts the negation of the properties after each check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Incremental {
+  /// Asserted under a fresh activation literal, deactivated afterwards.
+  Actlit,
+  /// Asserted in a scope, popped afterwards.
+  PushPop,
+}
+impl Print for Incremental {
+  fn to_str(& self) -> String {
+    match * self {
+      Incremental::Actlit => "actlit".to_string(),
+      Incremental::PushPop => "push".to_string(),
+    }
+  }
+}
+impl Parse for Incremental {
+  fn of(val: & str) -> Result<Incremental, String> {
+    match val {
+      "actlit" => Ok(Incremental::Actlit),
+      "push" => Ok(Incremental::PushPop),
+      _ => Err(
+        format!("expected incremental mode [actlit/push], got {}", val)
+      ),
+    }
+  }
+}
+
+/// What a technique does when the solver answers `unknown` to a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnUnknown {
+  /// Stops with an error.
+  Abort,
+  /// Gives up on the depth of the check and goes on with the next one.
+  Skip,
+  /// Treats the answer as `sat`, a candidate counterexample that is not
+  /// reported.
+  Sat,
+  /// Checks again with other random seeds, aborts if the answer is still
+  /// `unknown` or if the solver cannot change its seed.
+  Retry,
+}
+impl Print for OnUnknown {
+  fn to_str(& self) -> String {
+    match * self {
+      OnUnknown::Abort => "abort".to_string(),
+      OnUnknown::Skip => "skip".to_string(),
+      OnUnknown::Sat => "sat".to_string(),
+      OnUnknown::Retry => "retry".to_string(),
+    }
+  }
+}
+impl Parse for OnUnknown {
+  fn of(val: & str) -> Result<OnUnknown, String> {
+    match val {
+      "abort" => Ok(OnUnknown::Abort),
+      "skip" => Ok(OnUnknown::Skip),
+      "sat" => Ok(OnUnknown::Sat),
+      "retry" => Ok(OnUnknown::Retry),
+      _ => Err(
+        format!(
+          "expected unknown policy [abort/skip/sat/retry], got {}", val
+        )
+      ),
+    }
+  }
+}
+
+/// How Kind uses the invariants it receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvUse {
+  /// Invariants are ignored.
+  No,
+  /// Invariants are asserted on the states leading to the state the
+  /// properties are checked on, but not on that state.
+  StepOnly,
+  /// Invariants are asserted on all the states of the trace.
+  Both,
+}
+impl Print for InvUse {
+  fn to_str(& self) -> String {
+    match * self {
+      InvUse::No => "none".to_string(),
+      InvUse::StepOnly => "step_only".to_string(),
+      InvUse::Both => "both".to_string(),
+    }
+  }
+}
+impl Parse for InvUse {
+  fn of(val: & str) -> Result<InvUse, String> {
+    match val {
+      "none" => Ok(InvUse::No),
+      "step_only" => Ok(InvUse::StepOnly),
+      "both" => Ok(InvUse::Both),
+      _ => Err(
+        format!(
+          "expected invariant usage [none/step_only/both], got {}", val
+        )
+      ),
+    }
+  }
+}
+
+/// When the analysis stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOn {
+  /// Once a property is disproved.
+  FirstCex,
+  /// Once a property is proved.
+  FirstProof,
+  /// Once all the properties are proved or disproved, or the techniques are
+  /// done.
+  Exhaustive,
+}
+impl Print for StopOn {
+  fn to_str(& self) -> String {
+    match * self {
+      StopOn::FirstCex => "first_cex".to_string(),
+      StopOn::FirstProof => "first_proof".to_string(),
+      StopOn::Exhaustive => "exhaustive".to_string(),
+    }
+  }
+}
+impl Parse for StopOn {
+  fn of(val: & str) -> Result<StopOn, String> {
+    match val {
+      "first_cex" => Ok(StopOn::FirstCex),
+      "first_proof" => Ok(StopOn::FirstProof),
+      "exhaustive" => Ok(StopOn::Exhaustive),
+      _ => Err(
+        format!(
+          "expected stop policy [first_cex/first_proof/exhaustive], got {}",
+          val
+        )
+      ),
+    }
+  }
+}
+
+/// Domain of the candidate terms of invariant generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TigDomain {
+  /// Boolean terms, related by implication.
+  Bool,
+  /// Octagonal terms `± x ± y` over numeric state variables, related by
+  /// `<=`.
+  Octagon,
+  /// Numeric state variables and the bounds they take in the samples,
+  /// related by `<=`.
+  Interval,
+}
+impl Print for TigDomain {
+  fn to_str(& self) -> String {
+    match * self {
+      TigDomain::Bool => "bool".to_string(),
+      TigDomain::Octagon => "octagon".to_string(),
+      TigDomain::Interval => "interval".to_string(),
+    }
+  }
+}
+impl Parse for TigDomain {
+  fn of(val: & str) -> Result<TigDomain, String> {
+    match val {
+      "bool" => Ok(TigDomain::Bool),
+      "octagon" => Ok(TigDomain::Octagon),
+      "interval" => Ok(TigDomain::Interval),
+      _ => Err(
+        format!("expected domain [bool/octagon/interval], got {}", val)
+      ),
+    }
+  }
+}
+
 impl Print for String {
   fn to_str(& self) -> String { self.clone() }
 }
@@ -159,11 +366,21 @@ impl<T: Parse> Parse for Option<T> {
 trait HasSet {
   /// Sets the value of a configuration item.
   fn set(& mut self, & str, & str) -> Result<(), String> ;
+  /// Warning for a deprecated key, with a replacement hint. `None` if the
+  /// key is not deprecated.
+  fn deprecation(& self, & str) -> Option<String> ;
 }
 
 
 
 /// Creates a configuration structure.
+///
+/// Besides the items (and ghost items, which do not appear in the help), the
+/// structure can have
+///
+/// * aliases: `"key" => "item_key"`, alternative keys for an item;
+/// * deprecated keys: `"key" => "item_key", hint "hint" ;`, keys that still
+///   work but produce a warning with the hint.
 macro_rules! conf {
   ($name:ident ($head:expr) {
     $( $item:ident (
@@ -178,7 +395,38 @@ macro_rules! conf {
     conf!{
       $name ($head) {
         $( $item ($typ, $key, $shrt, $long, $default, $val => $parser), )+
-      } with ghosts {}
+      } with ghosts {} with aliases {} with deprecated {}
+    }
+  ) ;
+  ($name:ident ($head:expr) {
+    $( $item:ident (
+      $typ:ty,
+      $key:expr,
+      $shrt:expr,
+      $long:expr,
+      $default:expr,
+      $val:ident => $parser:expr
+    ), )+
+  } with ghosts {
+    $( $g_item:ident (
+      $g_typ:ty,
+      $g_key:expr,
+      $g_shrt:expr,
+      $g_long:expr,
+      $g_default:expr,
+      $g_val:ident => $g_parser:expr
+    ), )*
+  } ) => (
+    conf!{
+      $name ($head) {
+        $( $item ($typ, $key, $shrt, $long, $default, $val => $parser), )+
+      } with ghosts {
+        $(
+          $g_item (
+            $g_typ, $g_key, $g_shrt, $g_long, $g_default, $g_val => $g_parser
+          ),
+        )*
+      } with aliases {} with deprecated {}
     }
   ) ;
   ($name:ident ($head:expr) {
@@ -199,8 +447,13 @@ macro_rules! conf {
       $g_default:expr,
       $g_val:ident => $g_parser:expr
     ), )*
+  } with aliases {
+    $( $a_key:expr => $a_tgt:expr, )*
+  } with deprecated {
+    $( $d_key:expr => $d_tgt:expr, hint $d_hint:expr ; )*
   } ) => (
     /// Configuration structure.
+    #[derive(Clone)]
     pub struct $name {
       head: String,
       $( $item: ConfItem<$typ>, )+
@@ -229,6 +482,21 @@ macro_rules! conf {
             vec.push( line.to_string() )
           } ;
         )+
+        $(
+          vec.push(
+            format!(
+              "{} {}: alias for {}", fmt.pref(), stl.emph($a_key), $a_tgt
+            )
+          ) ;
+        )*
+        $(
+          vec.push(
+            format!(
+              "{} {}: deprecated, use {} instead",
+              fmt.pref(), stl.emph($d_key), $d_tgt
+            )
+          ) ;
+        )*
         vec.push(
           format!("{}{}", fmt.pref(), fmt.trail())
         ) ;
@@ -270,11 +538,27 @@ macro_rules! conf {
               Err(e) => Err(e),
             },
           )*
+          $( ($a_key, _) => self.set($a_tgt, val), )*
+          $( ($d_key, _) => self.set($d_tgt, val), )*
           _ => Err(
             format!("unknown key \"{}\"", key)
           ),
         }
       }
+      #[allow(unused_variables)]
+      fn deprecation(& self, key: & str) -> Option<String> {
+        $(
+          if key == $d_key {
+            return Some(
+              format!(
+                "option `{}` is deprecated, use `{}` instead\n{}",
+                $d_key, $d_tgt, $d_hint
+              )
+            )
+          }
+        )*
+        None
+      }
     }
   )
 }
@@ -302,6 +586,47 @@ conf!{
       None,
       val => Option::<usize>::of(val)
     ),
+    start (
+      usize,
+      "start", "<int>".to_string(),
+      "Smallest depth checked, the depth being the number of transitions \
+      of the\ncounterexamples. Smaller depths are unrolled but not checked. \
+      Unless it is `0`,\nproperties are never k-true.".to_string(),
+      0,
+      val => usize::of(val)
+    ),
+    step (
+      usize,
+      "step", "<int>".to_string(),
+      "Checks one depth every `step` from `start` on, must be positive. \
+      Unless it is `1`,\nproperties are never k-true.".to_string(),
+      1,
+      val => usize::of(val)
+    ),
+    diameter (
+      bool,
+      "diameter", "[on/off]".to_string(),
+      "Estimates the recurrence diameter of the system from the domains of \
+      its state\nvariables. The properties not falsified past it are \
+      proved.".to_string(),
+      true,
+      val => bool::of(val)
+    ),
+    workers (
+      usize,
+      "workers", "<int>".to_string(),
+      "Number of solvers checking consecutive depths in parallel. With more \
+      than one,\ninvariants and new properties are ignored.".to_string(),
+      1,
+      val => usize::of(val)
+    ),
+    max_queries (
+      Option<usize>,
+      "max_queries", "<int>".to_string(),
+      "Maximum number of check-sats.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
     smt (
       SolverStyle,
       "smt", solver_keys(),
@@ -316,13 +641,200 @@ conf!{
       None,
       val => Option::<String>::of(val)
     ),
+    smt_race (
+      Option<String>,
+      "smt_race", "<cmd>+...".to_string(),
+      "Commands of solvers racing with the one of `smt_cmd` on each query, \
+      separated by `+`.\nThe first answer wins. They all use the style of \
+      `smt`.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_compare (
+      Option<String>,
+      "smt_compare", "<cmd>".to_string(),
+      "Command of a solver running the queries of `smt_cmd`, for debugging.\n\
+      Stops at the first satisfiability check they disagree on, dumped in \
+      the\n`smt_log` directory if any, in the temporary one \
+      otherwise.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_prelude (
+      Option<String>,
+      "smt_prelude", "<file>".to_string(),
+      "SMT-LIB file sent to the solver right after it starts: options, \
+      tactics, auxiliary\ndefinitions... Should not contain \
+      queries.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    encoding (
+      Encoding,
+      "encoding", "[rel/fun]".to_string(),
+      "Encoding of the transition relation: as a relation, or with next \
+      state variables defined\nas functions of the current state when \
+      possible.".to_string(),
+      Encoding::Rel,
+      val => Encoding::of(val)
+    ),
+    incremental (
+      Incremental,
+      "incremental", "[actlit/push]".to_string(),
+      "How the negation of the properties is retracted after each check: \
+      with activation\nliterals, or with `push` and `pop`, which some \
+      solvers handle better.".to_string(),
+      Incremental::Actlit,
+      val => Incremental::of(val)
+    ),
+    on_unknown (
+      OnUnknown,
+      "on_unknown", "[abort/skip/sat/retry]".to_string(),
+      "What to do when the solver answers `unknown`: stop with an error, \
+      skip the depth, treat\nit as `sat` without reporting the \
+      counterexample, or retry with other random\nseeds (z3 only). \
+      Depths skipped or treated as `sat` make the properties never \
+      k-true.".to_string(),
+      OnUnknown::Abort,
+      val => OnUnknown::of(val)
+    ),
+    smt_timeout (
+      Option<usize>,
+      "smt_timeout", "<millis>".to_string(),
+      "Timeout of each check-sat in milliseconds, with `:timeout` for z3 \
+      and `:tlimit-per`\nfor cvc4. Checks timing out are answered \
+      `unknown`, see `on_unknown`.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
     smt_log (
       Option<String>,
-      "smt_log", "<file>".to_string(),
-      "File to log the smt trace to.".to_string(),
+      "smt_log", "<dir>".to_string(),
+      "Directory to log the smt traces to, one file per technique.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_cache (
+      Option<String>,
+      "smt_cache", "<dir>".to_string(),
+      "Directory caching the answers of the solver across runs.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    sat_cmd (
+      Option<String>,
+      "sat_cmd", "<cmd>".to_string(),
+      "SAT solver used instead of the SMT one on boolean-only systems, \
+      without scenarios and\npreferences. It is called on a DIMACS file \
+      and must answer in the SAT competition\nformat.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    explicit (
+      Option<usize>,
+      "explicit", "<int>".to_string(),
+      "Explores the reachable states explicitly, without solver, when the \
+      state variables\nhave finite domains and the system has at most \
+      <int> states, without scenarios\nand preferences. Mostly useful to \
+      test the other engines.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    scenarios (
+      Option<String>,
+      "scenarios", "<prop>+...".to_string(),
+      "One-state properties of the system restricting the initial states, \
+      separated by `+`.\nThe properties are checked from each of them \
+      separately. Properties are never\nk-true in this mode.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    preferences (
+      Option<String>,
+      "preferences", "<prop>+...".to_string(),
+      "One-state properties of the system counterexamples should satisfy \
+      if possible, separated\nby `+` from the most to the least \
+      important. The least important ones are dropped\nfirst, and only \
+      when no counterexample satisfies them.".to_string(),
       None,
       val => Option::<String>::of(val)
     ),
+    schedule (
+      Option<String>,
+      "schedule", "<file>".to_string(),
+      "Values of some state variables over the first steps, counterexamples \
+      must have these\nvalues. CSV file, or JSON file if it ends with \
+      \".json\". Properties are never\nk-true in this mode.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    localize (
+      Option<usize>,
+      "localize", "<int>".to_string(),
+      "Number of conjuncts of the transition relation reported as the most \
+      likely\nresponsible for a counterexample.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    cex_extend (
+      usize,
+      "cex_extend", "<int>".to_string(),
+      "Number of transitions following the last state of a counterexample \
+      to report, showing\nthe aftermath of the violation.".to_string(),
+      0,
+      val => usize::of(val)
+    ),
+  }
+}
+
+impl Bmc {
+  /// Names of the scenarios, empty if none.
+  pub fn scenario_names(& self) -> Vec<& str> {
+    prop_names( self.scenarios() )
+  }
+  /// Names of the preferences, from the most to the least important, empty
+  /// if none.
+  pub fn preference_names(& self) -> Vec<& str> {
+    prop_names( self.preferences() )
+  }
+}
+
+/// True if a name matches a pattern, where `*` matches any sequence of
+/// characters and `?` any character.
+pub fn glob_matches(pattern: & str, name: & str) -> bool {
+  let (pattern, name): (Vec<char>, Vec<char>) = (
+    pattern.chars().collect(), name.chars().collect()
+  ) ;
+  // Position in the pattern and in the name after the last `*`, to
+  // backtrack to.
+  let mut star = None ;
+  let (mut p, mut n) = (0, 0) ;
+  while n < name.len() {
+    if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+      p += 1 ;
+      n += 1
+    } else if p < pattern.len() && pattern[p] == '*' {
+      star = Some( (p, n) ) ;
+      p += 1
+    } else if let Some( (star_p, star_n) ) = star {
+      // The last `*` matches one more character.
+      star = Some( (star_p, star_n + 1) ) ;
+      p = star_p + 1 ;
+      n = star_n + 1
+    } else {
+      return false
+    }
+  }
+  pattern[p..].iter().all( |c| * c == '*' )
+}
+
+/// Names of properties separated by `+`.
+fn prop_names(names: & Option<String>) -> Vec<& str> {
+  match * names {
+    None => vec![],
+    Some(ref names) => names.split('+').map(
+      |name| name.trim()
+    ).filter( |name| ! name.is_empty() ).collect(),
   }
 }
 
@@ -343,6 +855,61 @@ conf!{
       None,
       val => Option::<usize>::of(val)
     ),
+    diameter (
+      bool,
+      "diameter", "[on/off]".to_string(),
+      "Estimates the recurrence diameter of the system from the domains of \
+      its state\nvariables, used as `max` when there is none.".to_string(),
+      true,
+      val => bool::of(val)
+    ),
+    fixpoint (
+      Option<usize>,
+      "fixpoint", "<int>".to_string(),
+      "Stops after <int> unrollings without new invariants, along with \
+      invariant generation.\nThe other techniques keep running.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    compress (
+      bool,
+      "compress", "[on/off]".to_string(),
+      "Path compression, the states of the step case are pairwise \
+      distinct. Makes k-induction\ncomplete on finite-state systems, at \
+      the cost of quadratically many constraints.".to_string(),
+      false,
+      val => bool::of(val)
+    ),
+    use_invariants (
+      InvUse,
+      "use_invariants", "[none/step_only/both]".to_string(),
+      "How to use the invariants received: ignore them, assert them on \
+      the states leading\nto the state checked, or on all states.".to_string(),
+      InvUse::Both,
+      val => InvUse::of(val)
+    ),
+    max_invariants (
+      Option<usize>,
+      "max_invariants", "<int>".to_string(),
+      "Maximum number of invariants used, the ones received \
+      afterwards are ignored.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    max_invariant_size (
+      Option<usize>,
+      "max_invariant_size", "<int>".to_string(),
+      "Invariants with more than <int> nodes are ignored.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    max_queries (
+      Option<usize>,
+      "max_queries", "<int>".to_string(),
+      "Maximum number of check-sats.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
     smt (
       SolverStyle,
       "smt", solver_keys(),
@@ -357,13 +924,94 @@ conf!{
       None,
       val => Option::<String>::of(val)
     ),
+    smt_race (
+      Option<String>,
+      "smt_race", "<cmd>+...".to_string(),
+      "Commands of solvers racing with the one of `smt_cmd` on each query, \
+      separated by `+`.\nThe first answer wins. They all use the style of \
+      `smt`.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_compare (
+      Option<String>,
+      "smt_compare", "<cmd>".to_string(),
+      "Command of a solver running the queries of `smt_cmd`, for debugging.\n\
+      Stops at the first satisfiability check they disagree on, dumped in \
+      the\n`smt_log` directory if any, in the temporary one \
+      otherwise.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_prelude (
+      Option<String>,
+      "smt_prelude", "<file>".to_string(),
+      "SMT-LIB file sent to the solver right after it starts: options, \
+      tactics, auxiliary\ndefinitions... Should not contain \
+      queries.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    encoding (
+      Encoding,
+      "encoding", "[rel/fun]".to_string(),
+      "Encoding of the transition relation: as a relation, or with next \
+      state variables defined\nas functions of the current state when \
+      possible.".to_string(),
+      Encoding::Rel,
+      val => Encoding::of(val)
+    ),
+    on_unknown (
+      OnUnknown,
+      "on_unknown", "[abort/skip/sat/retry]".to_string(),
+      "What to do when the solver answers `unknown` to a step check: stop \
+      with an error, go on\nwith the next depth without proving anything \
+      at this one (`skip` and `sat`), or\nretry with other random seeds \
+      before stopping (z3 only).".to_string(),
+      OnUnknown::Abort,
+      val => OnUnknown::of(val)
+    ),
+    smt_timeout (
+      Option<usize>,
+      "smt_timeout", "<millis>".to_string(),
+      "Timeout of each check-sat in milliseconds, with `:timeout` for z3 \
+      and `:tlimit-per`\nfor cvc4. Checks timing out are answered \
+      `unknown`, see `on_unknown`.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
     smt_log (
       Option<String>,
-      "smt_log", "<file>".to_string(),
-      "File to log the smt trace to.".to_string(),
+      "smt_log", "<dir>".to_string(),
+      "Directory to log the smt traces to, one file per technique.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_cache (
+      Option<String>,
+      "smt_cache", "<dir>".to_string(),
+      "Directory caching the answers of the solver across runs.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    proofs (
+      Option<String>,
+      "proofs", "<dir>".to_string(),
+      "Directory to store the proofs of the properties proved in, one \
+      directory per system,\nfor solvers producing proofs.".to_string(),
       None,
       val => Option::<String>::of(val)
     ),
+    generalize (
+      bool,
+      "generalize", "[on/off]".to_string(),
+      "Generalizes the properties proved by replacing their constants by \
+      the parameters of\nthe system, state variables the transition \
+      relation keeps constant. The lemmas\nobtained are sent as \
+      invariants.".to_string(),
+      false,
+      val => bool::of(val)
+    ),
   }
 }
 
@@ -377,6 +1025,13 @@ conf!{
       true,
       val => bool::of(val)
     ),
+    max_queries (
+      Option<usize>,
+      "max_queries", "<int>".to_string(),
+      "Maximum number of check-sats.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
     smt (
       SolverStyle,
       "smt", solver_keys(),
@@ -391,16 +1046,108 @@ conf!{
       None,
       val => Option::<String>::of(val)
     ),
+    smt_prelude (
+      Option<String>,
+      "smt_prelude", "<file>".to_string(),
+      "SMT-LIB file sent to the solver right after it starts: options, \
+      tactics, auxiliary\ndefinitions... Should not contain \
+      queries.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    encoding (
+      Encoding,
+      "encoding", "[rel/fun]".to_string(),
+      "Encoding of the transition relation: as a relation, or with next \
+      state variables defined\nas functions of the current state when \
+      possible.".to_string(),
+      Encoding::Rel,
+      val => Encoding::of(val)
+    ),
     smt_log (
       Option<String>,
-      "smt_log", "<file>".to_string(),
-      "File to log the smt trace to.".to_string(),
+      "smt_log", "<dir>".to_string(),
+      "Directory to log the smt traces to, one file per technique.".to_string(),
       None,
       val => Option::<String>::of(val)
     ),
-  }
-}
-
+    smt_cache (
+      Option<String>,
+      "smt_cache", "<dir>".to_string(),
+      "Directory caching the answers of the solver across runs.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+  }
+}
+
+
+conf!{
+  Cegar("Predicate abstraction (Cegar) options".to_string()) {
+    is_on (
+      bool,
+      "turn", "[on/off]".to_string(),
+      "(De)activates Cegar, which model checks an abstraction of the system \
+      over predicates\nrefined with the counterexamples it \
+      finds.".to_string(),
+      false,
+      val => bool::of(val)
+    ),
+    max_preds (
+      usize,
+      "max_preds", "<int>".to_string(),
+      "Maximum number of predicates, the properties are unknown if the \
+      abstraction needs\nmore.".to_string(),
+      32,
+      val => usize::of(val)
+    ),
+    max_queries (
+      Option<usize>,
+      "max_queries", "<int>".to_string(),
+      "Maximum number of check-sats.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    smt (
+      SolverStyle,
+      "smt", solver_keys(),
+      "Kind of solver to use.".to_string(),
+      SolverStyle::Z3,
+      val => SolverStyle::of(val)
+    ),
+    smt_cmd (
+      Option<String>,
+      "smt_cmd", "<cmd>".to_string(),
+      "Command to run the solver with.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_prelude (
+      Option<String>,
+      "smt_prelude", "<file>".to_string(),
+      "SMT-LIB file sent to the solver right after it starts: options, \
+      tactics, auxiliary\ndefinitions... Should not contain \
+      queries.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_log (
+      Option<String>,
+      "smt_log", "<dir>".to_string(),
+      "Directory to log the smt traces to, one file per technique.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_cache (
+      Option<String>,
+      "smt_cache", "<dir>".to_string(),
+      "Directory caching the answers of the solver across runs.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+  }
+}
+
 
 conf!{
   Tig("Template-based Invariant Generation (TIG) options".to_string()) {
@@ -425,6 +1172,49 @@ conf!{
       None,
       val => Option::<usize>::of(val)
     ),
+    max_cand_size (
+      Option<usize>,
+      "max_cand_size", "<int>".to_string(),
+      "Candidate terms with more nodes than this are discarded.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    max_cands (
+      Option<usize>,
+      "max_cands", "<int>".to_string(),
+      "Maximum number of candidate terms. Beyond that, the candidates \
+      mentioning the\nmost state variables of the properties are \
+      kept.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    domain (
+      TigDomain,
+      "domain", "[bool/octagon/interval]".to_string(),
+      "Domain of the candidate terms: booleans, the octagonal terms \
+      `+-x +-y` over\nthe integer state variables, the rational ones if \
+      there are none, or these\nvariables and the bounds they take in the \
+      reachable states, yielding\ninvariants `lo <= x <= hi`.".to_string(),
+      TigDomain::Bool,
+      val => TigDomain::of(val)
+    ),
+    templates (
+      Option<usize>,
+      "templates", "<int>".to_string(),
+      "Instantiates the linear templates `a*x + b*y <= c` over the numeric \
+      state variables,\nsynthesizing `a`, `b` and `c` from at most <int> \
+      sampled states per iteration.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
+    iter_time (
+      Option<usize>,
+      "iter_time", "<secs>".to_string(),
+      "Time allotted to the stabilization queries of each iteration, in \
+      seconds. TIG stops\nwhen it runs out.".to_string(),
+      None,
+      val => Option::<usize>::of(val)
+    ),
     smt (
       SolverStyle,
       "smt", solver_keys(),
@@ -439,10 +1229,19 @@ conf!{
       None,
       val => Option::<String>::of(val)
     ),
+    smt_prelude (
+      Option<String>,
+      "smt_prelude", "<file>".to_string(),
+      "SMT-LIB file sent to the solver right after it starts: options, \
+      tactics, auxiliary\ndefinitions... Should not contain \
+      queries.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
     smt_log (
       Option<String>,
-      "smt_log", "<file>".to_string(),
-      "File to log the smt trace to.".to_string(),
+      "smt_log", "<dir>".to_string(),
+      "Directory to log the smt traces to, one file per technique.".to_string(),
       None,
       val => Option::<String>::of(val)
     ),
@@ -453,6 +1252,16 @@ conf!{
       None,
       val => Option::<String>::of(val)
     ),
+    graph_events (
+      bool,
+      "graph_events", "[on/off]".to_string(),
+      "Reports the evolution of the equivalence graph: the chains classes \
+      are split into, the\nterms dropped from their class, and the \
+      classes and edges once the graph is stable\nat each \
+      iteration.".to_string(),
+      false,
+      val => bool::of(val)
+    ),
   } with ghosts {
     early_eqs (
       bool,
@@ -509,10 +1318,26 @@ conf!{
       None,
       val => Option::<String>::of(val)
     ),
+    smt_prelude (
+      Option<String>,
+      "smt_prelude", "<file>".to_string(),
+      "SMT-LIB file sent to the solver right after it starts: options, \
+      tactics, auxiliary\ndefinitions... Should not contain \
+      queries.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
     smt_log (
       Option<String>,
-      "smt_log", "<file>".to_string(),
-      "File to log the smt trace to.".to_string(),
+      "smt_log", "<dir>".to_string(),
+      "Directory to log the smt traces to, one file per technique.".to_string(),
+      None,
+      val => Option::<String>::of(val)
+    ),
+    smt_cache (
+      Option<String>,
+      "smt_cache", "<dir>".to_string(),
+      "Directory caching the answers of the solver across runs.".to_string(),
       None,
       val => Option::<String>::of(val)
     ),
@@ -542,7 +1367,7 @@ named! {
   option<(String, String)>,
   chain!(
     key: string ~
-    // delimited!( opt!(multispace), char!(':'), opt!(multispace) ) ~
+    opt!( char!(':') ) ~
     multispace ~
     val: string,
     || (key, val)
@@ -563,9 +1388,56 @@ named! {
   )
 }
 
+named! {
+  scoped_options<
+    (Option<String>, Vec< (String, String) >)
+  >,
+
+  alt!(
+
+    map!(option, |o| (None, vec![o])) |
+
+    chain!(
+      opt!(multispace) ~
+      scope: string ~
+      delimited!(
+        opt!(multispace), char!('('), opt!(multispace)
+      ) ~
+      opts: options ~
+      delimited!(
+        opt!(multispace), char!(')'), opt!(multispace)
+      ),
+      || ( Some(scope), opts )
+    )
+
+  )
+}
+
+named! {
+  prop_options<
+    (String, Vec< (Option<String>, Vec< (String, String) >) >)
+  >,
+  chain!(
+    opt!(multispace) ~
+    tag!("prop") ~
+    multispace ~
+    prop: string ~
+    delimited!(
+      opt!(multispace), char!('('), opt!(multispace)
+    ) ~
+    opts: separated_list!( comma_sep, scoped_options ) ~
+    delimited!(
+      opt!(multispace), char!(')'), opt!(multispace)
+    ),
+    || (prop, opts)
+  )
+}
+
 named! {
   option_parser<
-    Vec< (Option<String>, Vec< (String, String) >) >
+    Vec< (
+      Option<String>, Vec< (Option<String>, Vec< (String, String) >) >
+    ) >
   >,
 
   separated_nonempty_list!(
@@ -574,116 +1446,241 @@ named! {
 
     alt!(
 
-      map!(option, |o| (None, vec![o])) |
-
-      chain!(
-        opt!(multispace) ~
-        scope: string ~
-        delimited!(
-          opt!(multispace), char!('('), opt!(multispace)
-        ) ~
-        opts: options ~
-        delimited!(
-          opt!(multispace), char!(')'), opt!(multispace)
-        ),
-        || ( Some(scope), opts )
-      )
+      map!( prop_options, |(prop, opts)| (Some(prop), opts) ) |
+
+      map!( scoped_options, |opts| (None, vec![opts]) )
 
     )
   )
 }
 
-/// Top level configuration.
-pub struct Master {
-  /// All the technique scopes.
-  scopes: Vec<& 'static str>,
+/// Creates the top level configuration from the configuration structures of
+/// the techniques.
+///
+/// Each technique gets a field, a scope named after the field for `-o` and
+/// `-h`, and takes part in the options set for all scopes.
+macro_rules! master {
+  ( $( $(#[$attr:meta])* $scope:ident : $conf:ident, )+ ) => (
+    /// Top level configuration.
+    #[derive(Clone)]
+    pub struct Master {
+      /// All the technique scopes.
+      scopes: Vec<& 'static str>,
+      $(
+        $(#[$attr])*
+        pub $scope: Option<$conf>,
+      )+
+      /// Directory caching the verdicts of the properties across runs.
+      pub cache: Option<String>,
+      /// Ignores the cached verdicts if true. They are still updated.
+      pub fresh: bool,
+      /// File of options, in the syntax of `-o`, read again before each
+      /// system verified with `--sys`, if any.
+      pub reload: Option<String>,
+      /// Only outputs the results, as JSON lines, if true.
+      pub quiet: bool,
+      /// Outputs the results as JSON lines like `quiet`, along with the
+      /// depths reached, the invariants discovered and structured
+      /// counterexamples, if true.
+      pub json: bool,
+      /// File every event of the analysis is written to as JSON lines, if
+      /// any.
+      pub events: Option<String>,
+      /// Prints the counterexamples in full if true, otherwise the states
+      /// repeating in a loop are shown once.
+      pub full_cex: bool,
+      /// Number of digits after the point of the decimal approximation
+      /// printed next to the rational values of the counterexamples, if any.
+      pub precision: Option<usize>,
+      /// Logs the memory usage reported by the techniques, and the time
+      /// they spent at each depth, if true.
+      pub stats: bool,
+      /// Maximum number of check-sats, over all techniques.
+      pub max_queries: Option<usize>,
+      /// Time budget of the analysis in seconds, if any.
+      pub timeout: Option<u64>,
+      /// Directory the crash reports of the techniques are written to.
+      pub crash_dir: String,
+      /// File to write the dependency graph of the state variables to.
+      pub deps: Option<String>,
+      /// File to write the system to in the AIGER format, if boolean-only.
+      pub aiger: Option<String>,
+      /// File to write the system to in the VMT format.
+      pub vmt: Option<String>,
+      /// Bound of the analysis of the branches of the transition relation
+      /// never taken, if any.
+      pub dead_code: Option<usize>,
+      /// Command replaying the proofs stored by Kind at the end of the
+      /// analysis, if any.
+      pub check_proofs: Option<String>,
+      /// Number of check-sats of the first stage, if staged.
+      pub staged: Option<usize>,
+      /// Solver confirming the properties proved with a certificate check,
+      /// if paranoid.
+      pub paranoid: Option<SolverStyle>,
+      /// Solver checking again the certificates of the properties proved at
+      /// the end of the analysis, if any.
+      pub double_check: Option<SolverStyle>,
+      /// When the analysis stops.
+      pub stop_on: StopOn,
+      /// Order of the state variables, as declared to the solvers and
+      /// printed in the counterexamples.
+      pub order: Order,
+      /// File recording the outcomes and timings of the properties across
+      /// runs, if any.
+      pub history: Option<String>,
+      /// Minimum number of conjuncts of the properties split in
+      /// sub-properties, if splitting.
+      pub split: Option<usize>,
+      /// Number of history variables added for each state variable of the
+      /// properties, none if zero.
+      pub pre: usize,
+      /// Bound of the k-liveness reduction of the liveness properties.
+      pub live: usize,
+      /// State variables the system is sliced to, along with the ones of
+      /// the properties, if slicing.
+      pub slice: Option<Vec<String>>,
+      /// Library files, defining only functions, loaded before the model.
+      pub libs: Vec<String>,
+      /// Patterns selecting the systems to verify, empty to verify the one
+      /// of the `verify` query.
+      pub systems: Vec<String>,
+      /// Names of the properties to check, empty to check all of them.
+      pub props: Vec<String>,
+      /// Tags selecting the properties to check, empty to check all of
+      /// them.
+      pub only_tags: Vec<String>,
+      /// Tags of the properties not to check.
+      pub skip_tags: Vec<String>,
+      /// Warnings raised while setting options, for deprecated keys.
+      pub warnings: Vec<String>,
+      /// Options scoped to some properties, applied on top of the global
+      /// ones.
+      prop_opts: HashMap< String, Vec< (String, Vec< (String, String) >) > >,
+    }
+    impl Master {
+      /// Default top level configuration.
+      pub fn default() -> Self {
+        Master {
+          scopes: vec![ $( stringify!($scope) ),+ ],
+          $( $scope: Some( $conf::default() ), )+
+          cache: None,
+          fresh: false,
+          reload: None,
+          quiet: false,
+          json: false,
+          events: None,
+          full_cex: false,
+          precision: None,
+          stats: false,
+          max_queries: None,
+          timeout: None,
+          crash_dir: ".".to_string(),
+          deps: None,
+          aiger: None,
+          vmt: None,
+          dead_code: None,
+          check_proofs: None,
+          staged: None,
+          paranoid: None,
+          double_check: None,
+          stop_on: StopOn::Exhaustive,
+          order: Order::Input,
+          history: None,
+          split: None,
+          pre: 0,
+          live: 1,
+          slice: None,
+          libs: vec![],
+          systems: vec![],
+          props: vec![],
+          only_tags: vec![],
+          skip_tags: vec![],
+          warnings: vec![],
+          prop_opts: HashMap::new(),
+        }
+      }
+
+      /// Sets some options in the scope of a technique.
+      fn set_tek(
+        mut self, scope: & str, opts: & [ (String, String) ]
+      ) -> Result<Self, (String, Self)> {
+        $(
+          if scope == stringify!($scope) {
+            let mut conf = self.$scope.unwrap_or_else(|| $conf::default()) ;
+            for & (ref key, ref val) in opts.iter() {
+              match conf.set(key, val) {
+                Ok(()) => if let Some(warning) = conf.deprecation(key) {
+                  if ! self.warnings.contains(& warning) {
+                    self.warnings.push(warning)
+                  }
+                },
+                Err(e) => {
+                  self.$scope = Some(conf) ;
+                  return Err( (e, self) )
+                },
+              }
+            } ;
+            self.$scope = Some(conf) ;
+            return Ok(self)
+          }
+        )+
+        Err( (
+          format!("unknown technique scope \"{}\"", scope),
+          self
+        ) )
+      }
+
+      /// Prints the help of a technique. Returns false if the scope is not a
+      /// technique.
+      fn help_tek<
+        F: Formatter, S: Styler
+      >(scope: & str, log: & MasterLog<F,S>) -> bool {
+        $(
+          if scope == stringify!($scope) {
+            for line in $conf::lines(log.fmt(), log.stl()) {
+              println!("{}", line)
+            }
+            return true
+          }
+        )+
+        false
+      }
+    }
+  )
+}
+
+master!{
   /// Optional BMC configuration.
-  pub bmc: Option<Bmc>,
+  bmc: Bmc,
   /// Optional Kind configuration.
-  pub kind: Option<Kind>,
+  kind: Kind,
   /// Optional Twind configuration.
-  pub twind: Option<Twind>,
+  twind: Twind,
+  /// Optional Cegar configuration.
+  cegar: Cegar,
   /// Optional TIG configuration.
-  pub tig: Option<Tig>,
+  tig: Tig,
   /// Optional Pruner configuration.
-  pub pruner: Option<Pruner>,
+  pruner: Pruner,
 }
+
 impl Master {
+  /// Sets an option of a technique, or of all of them if `scope` is `all`,
+  /// like `-o "<scope> <key>: <val>"` does.
+  pub fn with(
+    self, scope: & str, key: & str, val: & str
+  ) -> Result<Self, String> {
+    self.set(
+      scope, & [ (key.to_string(), val.to_string()) ]
+    ).map_err( |(e, _)| e )
+  }
+
   /// The scope to technique mapping.
   fn set(
-    mut self, scope: & str, opts: & [ (String, String) ]
+    self, scope: & str, opts: & [ (String, String) ]
   ) -> Result<Self, (String, Self)> {
     match scope {
-      "bmc" => {
-        let mut bmc = self.bmc.unwrap_or_else(|| Bmc::default()) ;
-        for & (ref key, ref val) in opts.iter() {
-          match bmc.set(key, val) {
-            Ok(()) => (),
-            Err(e) => {
-              self.bmc = Some(bmc) ;
-              return Err( (e, self) )
-            },
-          }
-        } ;
-        self.bmc = Some(bmc) ;
-        Ok(self)
-      },
-      "kind" => {
-        let mut kind = self.kind.unwrap_or_else(|| Kind::default()) ;
-        for & (ref key, ref val) in opts.iter() {
-          match kind.set(key, val) {
-            Ok(()) => (),
-            Err(e) => {
-              self.kind = Some(kind) ;
-              return Err( (e, self) )
-            },
-          }
-        } ;
-        self.kind = Some(kind) ;
-        Ok(self)
-      },
-      "twind" => {
-        let mut twind = self.twind.unwrap_or_else(|| Twind::default()) ;
-        for & (ref key, ref val) in opts.iter() {
-          match twind.set(key, val) {
-            Ok(()) => (),
-            Err(e) => {
-              self.twind = Some(twind) ;
-              return Err( (e, self) )
-            },
-          }
-        } ;
-        self.twind = Some(twind) ;
-        Ok(self)
-      },
-      "tig" => {
-        let mut tig = self.tig.unwrap_or_else(|| Tig::default()) ;
-        for & (ref key, ref val) in opts.iter() {
-          match tig.set(key, val) {
-            Ok(()) => (),
-            Err(e) => {
-              self.tig = Some(tig) ;
-              return Err( (e, self) )
-            },
-          }
-        } ;
-        self.tig = Some(tig) ;
-        Ok(self)
-      },
-      "pruner" => {
-        let mut pruner = self.pruner.unwrap_or_else(|| Pruner::default()) ;
-        for & (ref key, ref val) in opts.iter() {
-          match pruner.set(key, val) {
-            Ok(()) => (),
-            Err(e) => {
-              self.pruner = Some(pruner) ;
-              return Err( (e, self) )
-            },
-          }
-        } ;
-        self.pruner = Some(pruner) ;
-        Ok(self)
-      },
       "all" => {
         // println!("all") ;
         let scopes = self.scopes.clone() ;
@@ -709,22 +1706,140 @@ impl Master {
         } ;
         Ok(res)
       },
-      _ => Err( (
-        format!("unknown technique scope \"{}\"", scope),
-        self
-      ) ),
+      _ => self.set_tek(scope, opts),
     }
   }
 
-  /// Default top level configuration.
-  pub fn default() -> Self {
-    Master {
-      scopes: vec![ "bmc", "kind", "twind", "tig", "pruner" ],
-      bmc: Some( Bmc::default() ),
-      kind: Some( Kind::default() ),
-      twind: Some( Twind::default() ),
-      tig: Some( Tig::default() ),
-      pruner: Some( Pruner::default() ),
+  /// True if some pattern of `--sys` matches the name of a system.
+  pub fn selects_sys(& self, name: & str) -> bool {
+    self.systems.iter().any( |pattern| glob_matches(pattern, name) )
+  }
+
+  /// True if a property is checked given `--prop`.
+  pub fn selects_prop(& self, name: & str) -> bool {
+    self.props.is_empty() || self.props.iter().any( |prop| prop == name )
+  }
+
+  /// True if a property with some tags is checked given `--only-tag` and
+  /// `--skip-tag`.
+  pub fn selects_tags(& self, tags: & [String]) -> bool {
+    (
+      self.only_tags.is_empty() || tags.iter().any(
+        |tag| self.only_tags.contains(tag)
+      )
+    ) && ! tags.iter().any( |tag| self.skip_tags.contains(tag) )
+  }
+
+  /// True if some options are scoped to some properties.
+  pub fn has_prop_opts(& self) -> bool {
+    ! self.prop_opts.is_empty()
+  }
+
+  /// Configuration of a property: the global configuration with the options
+  /// scoped to the property on top of it. `None` if no options are scoped to
+  /// the property.
+  pub fn for_prop(& self, prop: & str) -> Result<Option<Self>, String> {
+    match self.prop_opts.get(prop) {
+      None => Ok(None),
+      Some(opts) => {
+        let mut conf = self.clone() ;
+        conf.prop_opts.clear() ;
+        for & (ref scope, ref args) in opts.iter() {
+          conf = match conf.set(scope, args) {
+            Ok(conf) => conf,
+            Err( (e, _) ) => return Err(
+              format!("in the options of property {}: {}", prop, e)
+            ),
+          }
+        }
+        Ok( Some(conf) )
+      },
+    }
+  }
+
+  /// Sets some options, in the syntax of `-o`.
+  pub fn options(mut self, options: & str) -> Result<Self, String> {
+    use nom::Needed ;
+    // The parser needs something after a closing parenthesis to know the
+    // scope is over.
+    let padded = format!("{} ", options.trim()) ;
+    match option_parser( padded.as_bytes() ) {
+      IResult::Done(rest, _) if ! rest.is_empty() => return Err(
+        format!(
+          "could not parse options \"{}\" from \"{}\"",
+          options, String::from_utf8_lossy(rest).trim()
+        )
+      ),
+      IResult::Done(_, opts) => for (prop, opts) in opts {
+        for (scope, args) in opts {
+          let scope = scope.unwrap_or_else(|| "all".to_string()) ;
+          match prop {
+            None => match self.set(& scope, & args) {
+              Ok(conf) => self = conf,
+              Err( (e, _) ) => return Err(e),
+            },
+            // Property options are applied when the property is checked, on
+            // top of the global ones. Checking they are legal now.
+            Some(ref prop) => match Master::default().set(& scope, & args) {
+              Ok(_) => self.prop_opts.entry(
+                prop.clone()
+              ).or_insert_with(
+                || Vec::with_capacity(3)
+              ).push( (scope, args) ),
+              Err( (e, _) ) => return Err(
+                format!("in the options of property {}: {}", prop, e)
+              ),
+            },
+          }
+        }
+      },
+      IResult::Error(e) => return Err(
+        format!("could not parse options \"{}\":\n{}", options, e)
+      ),
+      IResult::Incomplete(n) => return Err(
+        format!(
+          "incomplete ({}) options \"{}\"",
+          match n {
+            Needed::Unknown => format!("_"),
+            Needed::Size(n) => format!("{}", n),
+          },
+          options
+        )
+      ),
+    }
+    Ok(self)
+  }
+
+  /// The configuration with the options of the `--reload` file on top of
+  /// it, read again on each call. Itself if there is no such file.
+  pub fn reloaded(& self) -> Result<Self, String> {
+    use std::io::Read ;
+    let path = match self.reload {
+      Some(ref path) => path,
+      None => return Ok( self.clone() ),
+    } ;
+    let mut options = String::new() ;
+    if let Err(e) = ::std::fs::File::open(path).and_then(
+      |mut file| file.read_to_string(& mut options)
+    ) {
+      return Err( format!("could not read \"{}\": {}", path, e) )
+    }
+    if options.trim().is_empty() { return Ok( self.clone() ) }
+    self.clone().options( options.trim() ).map_err(
+      |e| format!("in \"{}\": {}", path, e)
+    )
+  }
+
+  /// The maximum number of check-sats of a technique, `None` if it has no
+  /// such option.
+  pub fn max_queries_of(& self, tek: & ::Tek) -> Option< Option<usize> > {
+    use Tek::* ;
+    match * tek {
+      Bmc => self.bmc.as_ref().map( |conf| * conf.max_queries() ),
+      KInd => self.kind.as_ref().map( |conf| * conf.max_queries() ),
+      Twind => self.twind.as_ref().map( |conf| * conf.max_queries() ),
+      Cegar => self.cegar.as_ref().map( |conf| * conf.max_queries() ),
+      _ => None,
     }
   }
 
@@ -738,46 +1853,282 @@ impl Master {
     loop {
       if let Some(nxt) = args.next() {
         if "-o" == nxt {
-          use nom::Needed ;
           match args.next() {
-            Some(options) => {
-              match option_parser(options.as_bytes()) {
-                IResult::Done(_, opts) => for opt in opts {
-                  match opt {
-                    (None, args) => match conf.set("all", & args) {
-                      Ok(c) => conf = c,
-                      Err( (e, _) ) => return Err(e),
-                    },
-                    (Some(scope), args) => match conf.set(& scope, & args) {
-                      Ok(c) => conf = c,
-                      Err( (e, _) ) => return Err(e),
-                    },
-                  }
-                },
-                IResult::Error(e) => return Err(
-                  format!("could not parse options \"{}\":\n{}", options, e)
-                ),
-                IResult::Incomplete(n) => return Err(
-                  format!(
-                    "incomplete ({}) options \"{}\"",
-                    match n {
-                      Needed::Unknown => format!("_"),
-                      Needed::Size(n) => format!("{}", n),
-                    },
-                    options
-                  )
-                ),
-              }
-            },
+            Some(options) => conf = try!( conf.options(& options) ),
             None => return Err(
               "expected options after \"-o\", found nothing".to_string()
             ),
           }
+        } else if "--cache" == nxt {
+          match args.next() {
+            Some(dir) => conf.cache = Some(dir),
+            None => return Err(
+              "expected directory after \"--cache\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--fresh" == nxt {
+          conf.fresh = true
+        } else if "--reload" == nxt {
+          match args.next() {
+            Some(file) => conf.reload = Some(file),
+            None => return Err(
+              "expected file after \"--reload\", found nothing".to_string()
+            ),
+          }
+        } else if "--quiet" == nxt {
+          conf.quiet = true
+        } else if "--json" == nxt {
+          conf.quiet = true ;
+          conf.json = true
+        } else if "--events" == nxt {
+          match args.next() {
+            Some(file) => conf.events = Some(file),
+            None => return Err(
+              "expected file after \"--events\", found nothing".to_string()
+            ),
+          }
+        } else if "--full-cex" == nxt {
+          conf.full_cex = true
+        } else if "--precision" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.precision = Some(n),
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--precision\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--precision\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--stats" == nxt {
+          conf.stats = true
+        } else if "--deps" == nxt {
+          match args.next() {
+            Some(file) => conf.deps = Some(file),
+            None => return Err(
+              "expected file after \"--deps\", found nothing".to_string()
+            ),
+          }
+        } else if "--aiger" == nxt {
+          match args.next() {
+            Some(file) => conf.aiger = Some(file),
+            None => return Err(
+              "expected file after \"--aiger\", found nothing".to_string()
+            ),
+          }
+        } else if "--vmt" == nxt {
+          match args.next() {
+            Some(file) => conf.vmt = Some(file),
+            None => return Err(
+              "expected file after \"--vmt\", found nothing".to_string()
+            ),
+          }
+        } else if "--dead-code" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.dead_code = Some(n),
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--dead-code\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--dead-code\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--check-proofs" == nxt {
+          match args.next() {
+            Some(cmd) => conf.check_proofs = Some(cmd),
+            None => return Err(
+              "expected command after \"--check-proofs\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--staged" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.staged = Some(n),
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--staged\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--staged\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--paranoid" == nxt {
+          match args.next().map( |smt| SolverStyle::of(& smt) ) {
+            Some( Ok(smt) ) => conf.paranoid = Some(smt),
+            Some( Err(e) ) => return Err(
+              format!("after \"--paranoid\": {}", e)
+            ),
+            None => return Err(
+              "expected solver after \"--paranoid\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--double-check" == nxt {
+          match args.next().map( |smt| SolverStyle::of(& smt) ) {
+            Some( Ok(smt) ) => conf.double_check = Some(smt),
+            Some( Err(e) ) => return Err(
+              format!("after \"--double-check\": {}", e)
+            ),
+            None => return Err(
+              "expected solver after \"--double-check\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--stop-on" == nxt {
+          match args.next().map( |policy| StopOn::of(& policy) ) {
+            Some( Ok(policy) ) => conf.stop_on = policy,
+            Some( Err(e) ) => return Err(
+              format!("after \"--stop-on\": {}", e)
+            ),
+            None => return Err(
+              "expected stop policy after \"--stop-on\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--history" == nxt {
+          match args.next() {
+            Some(file) => conf.history = Some(file),
+            None => return Err(
+              "expected file after \"--history\", found nothing".to_string()
+            ),
+          }
+        } else if "--split" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.split = Some(n),
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--split\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--split\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--pre" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.pre = n,
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--pre\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--pre\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--live" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.live = n,
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--live\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--live\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--slice" == nxt {
+          match args.next() {
+            Some(vars) => conf.slice = Some(
+              if vars == "props" { vec![] } else {
+                vars.split(',').map(
+                  |var| var.trim().to_string()
+                ).filter( |var| ! var.is_empty() ).collect()
+              }
+            ),
+            None => return Err(
+              "expected state variables after \"--slice\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--order" == nxt {
+          match args.next().map( |order| Order::of_str(& order) ) {
+            Some( Ok(order) ) => conf.order = order,
+            Some( Err(e) ) => return Err(
+              format!("after \"--order\": {}", e)
+            ),
+            None => return Err(
+              "expected order after \"--order\", found nothing".to_string()
+            ),
+          }
+        } else if "--lib" == nxt {
+          match args.next() {
+            Some(file) => conf.libs.push(file),
+            None => return Err(
+              "expected file after \"--lib\", found nothing".to_string()
+            ),
+          }
+        } else if "--sys" == nxt {
+          match args.next() {
+            Some(pattern) => conf.systems.push(pattern),
+            None => return Err(
+              "expected system name after \"--sys\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--prop" == nxt {
+          match args.next() {
+            Some(props) => conf.props.extend(
+              props.split(',').map(
+                |prop| prop.trim().to_string()
+              ).filter( |prop| ! prop.is_empty() )
+            ),
+            None => return Err(
+              "expected property names after \"--prop\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--only-tag" == nxt {
+          match args.next() {
+            Some(tag) => conf.only_tags.push(tag),
+            None => return Err(
+              "expected tag after \"--only-tag\", found nothing".to_string()
+            ),
+          }
+        } else if "--skip-tag" == nxt {
+          match args.next() {
+            Some(tag) => conf.skip_tags.push(tag),
+            None => return Err(
+              "expected tag after \"--skip-tag\", found nothing".to_string()
+            ),
+          }
+        } else if "--max-queries" == nxt {
+          match args.next().map( |n| n.parse::<usize>() ) {
+            Some( Ok(n) ) => conf.max_queries = Some(n),
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--max-queries\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--max-queries\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--timeout" == nxt {
+          match args.next().map( |n| n.parse::<u64>() ) {
+            Some( Ok(n) ) => conf.timeout = Some(n),
+            Some( Err(e) ) => return Err(
+              format!("expected integer after \"--timeout\":\n{}", e)
+            ),
+            None => return Err(
+              "expected integer after \"--timeout\", found nothing"
+              .to_string()
+            ),
+          }
+        } else if "--crash-dir" == nxt {
+          match args.next() {
+            Some(dir) => conf.crash_dir = dir,
+            None => return Err(
+              "expected directory after \"--crash-dir\", found nothing"
+              .to_string()
+            ),
+          }
         } else {
           if "-h" == nxt || "--help" == nxt {
             let scope = if let Some(next) = args.next() {
               next.to_string()
             } else { "".to_string() } ;
+            log.sep() ;
+            log.sep() ;
             Master::help(& scope, log) ;
             log.sep() ;
             log.sep() ;
@@ -812,21 +2163,6 @@ impl Master {
   ) {
 
     match scope {
-      "bmc" => for line in Bmc::lines(log.fmt(), log.stl()) {
-        println!("{}", line)
-      },
-      "kind" => for line in Kind::lines(log.fmt(), log.stl()) {
-        println!("{}", line)
-      },
-      "twind" => for line in Twind::lines(log.fmt(), log.stl()) {
-        println!("{}", line)
-      },
-      "tig" => for line in Tig::lines(log.fmt(), log.stl()) {
-        println!("{}", line)
-      },
-      "pruner" => for line in Pruner::lines(log.fmt(), log.stl()) {
-        println!("{}", line)
-      },
       "all" => {
         let mut fst = true ;
         for scope in Master::default().scopes {
@@ -838,7 +2174,7 @@ impl Master {
           Master::help(scope, log) ;
         }
       },
-      _ => {
+      _ => if ! Master::help_tek(scope, log) {
 
         let scopes = Master::default().scopes.into_iter().fold(
           String::new(),
@@ -854,7 +2190,8 @@ impl Master {
           log.fmt().pref(),
           & super::Tek::Tec("> kino [option]* file", ""),
           & format!("\
-where [option] can be
+where file is in the input language of kino, or a NuSMV model if it ends
+with \".smv\", and [option] can be
   {} [module]
       Displays this message if no module is specified. Otherwise displays the
       help of the module specified, among
@@ -862,15 +2199,207 @@ where [option] can be
   {} \"[ <opt> <val> | <mdl>([<opt> <val>],+) ],+\"
       Sets some options globally (first version) or for a specific module
       (second version). Check the options of each module for more details.
+      Options can be scoped to a property with \"prop <name>(<options>)\",
+      they then only apply when checking this property.
       {}:
       > kino -o \"smt_log: path/to/log, bmc(max: 7, solver: cvc4)\"
       Activates log of the solver's trace for all modules, and option `max`
-      (`solver`) in the `bmc` module to `7` (`cvc4`).\
+      (`solver`) in the `bmc` module to `7` (`cvc4`).
+      > kino -o \"kind(max: 10), prop hard(kind(max: 50))\"
+      Sets option `max` in the `kind` module to `10`, and to `50` when
+      checking property `hard`.
+  {} <dir>
+      Caches the verdicts of the properties in <dir>. Properties with a cached
+      verdict are not checked again as long as they and the system do not
+      change.
+  {}
+      Ignores the cached verdicts, but still updates them.
+  {} <file>
+      Reads options from <file>, in the syntax of \"-o\", before verifying
+      each system selected with \"--sys\". They apply on top of the options
+      of the command line to the systems left, so that the budgets,
+      verbosity and solvers of a long run can be changed without
+      restarting it. The \"max_queries\" budgets of the techniques also
+      apply to the ones running, as soon as <file> changes.
+  {}
+      Suppresses logging, only outputs the results as JSON objects, one per
+      line. Errors are output the same way.
+  {}
+      Same as \"--quiet\", but also outputs the depths the properties are
+      true up to, the invariants discovered, and the counterexamples as
+      JSON objects with the states in field \"trace\".
+  {} <file>
+      Writes every event of the analysis to <file> as JSON objects, one per
+      line, with a \"timestamp\" field: properties proved, true up to some
+      depth or falsified, invariants, statistics, results and errors. Logging
+      is not affected.
+  {}
+      Prints the counterexamples in full. By default, states repeating with
+      some period are shown once as a loop, and the JSON output gives the
+      loops in field \"loops\".
+  {} <int>
+      Prints a decimal approximation with <int> digits after the point next
+      to the rational values of the counterexamples, which are exact
+      fractions otherwise.
+  {}
+      Logs the memory usage of the term factory and of the techniques as they
+      run, and the time spent at each unrolling depth for each property.
+  {} <int>
+      Maximum number of check-sats over all techniques. Techniques stop once
+      it is reached, or once they reach their own \"max_queries\", and the
+      properties left are unknown.
+  {} <secs>
+      Time budget of the analysis. Techniques are told how much time is
+      left and stop before queries they cannot finish in time, the
+      properties left are then unknown.
+  {} <dir>
+      Directory the crash reports are written to, the current one by
+      default. A technique that panics is stopped and reported as an
+      error, with a report giving its backtrace, the depth it was at and
+      the last query it issued. The other techniques keep running.
+  {} <file>
+      Writes the dependency graph between the state variables of the system
+      to <file>, as JSON if it ends with \".json\" and in the dot format
+      otherwise.
+  {} <file>
+      Writes the system and its properties to <file> in the AIGER format if
+      the system is boolean-only: boolean state variables, no sub-systems
+      and no function calls.
+  {} <file>
+      Writes the system and its one-state safety properties to <file> in the
+      VMT format, SMT-LIB annotated with :init, :trans and :invar-property.
+  {} <int>
+      Reports the branches of the \"ite\"s of the transition relation never
+      taken in the first <int> transitions, and the ones that are provably
+      never taken.
+  {} <cmd>
+      Replays the proofs stored by Kind (see its \"proofs\" option) at the
+      end of the analysis, calling <cmd> on each problem and its proof. A
+      proof is rejected if <cmd> fails, the properties it proves are then
+      unknown.
+  {} <int>
+      Checks the properties in two stages. The first one runs the techniques
+      but invariant generation on all the properties, with a budget of <int>
+      check-sats. The second one runs all the techniques on the properties
+      left, with the rest of the budget.
+  {} <solver>
+      Paranoid mode, a property proved is only reported once confirmed,
+      either by a certificate check with <solver> ({}), or by a second
+      technique proving it too. Properties never confirmed are unknown.
+  {} <solver>
+      Checks again the certificates of the properties proved once the
+      analysis is over, with <solver> ({}), and reports which ones are
+      double-checked. Guards against the bugs of the other solvers.
+  {} <policy>
+      When the analysis stops: once a property is disproved (first_cex),
+      once a property is proved (first_proof), or once all the properties
+      are proved or disproved or the techniques are done (exhaustive, the
+      default). The properties left are unknown.
+  {} <order>
+      Order of the state variables, as declared to the solvers and printed
+      in the counterexamples: the one of the input file (input, the
+      default), inputs first then each state variable after the ones it
+      depends on (deps), alphabetical (alpha), or a list of state variables
+      separated by commas coming first, the others following in input
+      order.
+  {} <file>
+      Appends the outcome of each property, the technique deciding it and
+      the time it took to <file>, one JSON object per line, along with the
+      hash of the system and the versions of kino and of the solvers.
+      \"kino stats <file>\" summarizes the runs recorded, pointing out the
+      versions of kino slower or deciding fewer properties than the previous
+      one.
+  {} <int>
+      Splits the one-state properties that are a conjunction of at least
+      <int> conjuncts in one sub-property per conjunct, checked
+      independently. A property is then falsified if one of its
+      sub-properties is, proved if all of them are, and the sub-properties
+      decided are reported when it is not.
+  {} <int>
+      Adds <int> history variables for each state variable of the
+      properties, holding its values in the <int> previous states. The
+      invariants relating them to the current values strengthen
+      k-induction, which can then prove properties at lower depths.
+  {} <int>
+      Bound of the k-liveness reduction of the liveness properties, \"(F p)\"
+      and \"(G (F p))\", to safety: a liveness property is proved if no
+      path waits for it through more than <int> rounds of the fairness
+      constraints of the system, declared with \"define-fair\". Falsifying
+      the reduction leaves the property unknown. One by default.
+  {} <vars>
+      Slices the system before checking the properties: removes the locals,
+      the sub-systems and the conjuncts of the transition relation
+      irrelevant to the state variables of the properties and to <vars>, a
+      list of state variables separated by commas, or \"props\" for the
+      ones of the properties only.
+  {} <file>
+      Loads <file> as a library before the model: it can only declare and
+      define functions, which the model and the other libraries cannot
+      redefine. Each library is parsed once, its functions are shared by
+      all the systems verified. Can be repeated.
+  {} <name>
+      Verifies the systems whose name matches <name>, where \"*\" matches
+      any sequence of characters and \"?\" any character, instead of the
+      one of the verify query. All the properties of the systems are
+      checked, one system after the other, followed by a summary. Can be
+      repeated.
+  {} <props>
+      Only checks the properties in <props>, a comma-separated list of
+      property names. Can be repeated.
+  {} <tag>
+      Only checks the properties tagged <tag>, with \":tag <tag>\" at the
+      end of their definition. Can be repeated to check the properties with
+      any of the tags.
+  {} <tag>
+      Does not check the properties tagged <tag>. Can be repeated.
+
+On unix, signals are the only way to control an analysis once it runs:
+  SIGUSR1
+      Pauses the techniques at their next check-sat, the solvers keep their
+      state.
+  SIGUSR2
+      Resumes the techniques paused.
+  SIGINT, SIGTERM
+      Stops the analysis, kills the solvers and removes the temporary files.\
             ",
             log.mk_emph("-h / --help"),
             scopes,
             log.mk_emph("-o"),
-            log.mk_emph("Example")
+            log.mk_emph("Example"),
+            log.mk_emph("--cache"),
+            log.mk_emph("--fresh"),
+            log.mk_emph("--reload"),
+            log.mk_emph("--quiet"),
+            log.mk_emph("--json"),
+            log.mk_emph("--events"),
+            log.mk_emph("--full-cex"),
+            log.mk_emph("--precision"),
+            log.mk_emph("--stats"),
+            log.mk_emph("--max-queries"),
+            log.mk_emph("--timeout"),
+            log.mk_emph("--crash-dir"),
+            log.mk_emph("--deps"),
+            log.mk_emph("--aiger"),
+            log.mk_emph("--vmt"),
+            log.mk_emph("--dead-code"),
+            log.mk_emph("--check-proofs"),
+            log.mk_emph("--staged"),
+            log.mk_emph("--paranoid"),
+            SolverStyle::str_keys().join("|"),
+            log.mk_emph("--double-check"),
+            SolverStyle::str_keys().join("|"),
+            log.mk_emph("--stop-on"),
+            log.mk_emph("--order"),
+            log.mk_emph("--history"),
+            log.mk_emph("--split"),
+            log.mk_emph("--pre"),
+            log.mk_emph("--live"),
+            log.mk_emph("--slice"),
+            log.mk_emph("--lib"),
+            log.mk_emph("--sys"),
+            log.mk_emph("--prop"),
+            log.mk_emph("--only-tag"),
+            log.mk_emph("--skip-tag")
           )
         ) ;
         log.nl() ;
@@ -891,3 +2420,32 @@ where [option] can be
 
 
 
+
+
+
+/// Tests the patterns of `--sys`.
+#[cfg(test)]
+pub mod test {
+  use super::glob_matches ;
+
+  /// Checks the literal characters, `?` and the backtracking of `*`.
+  #[test]
+  fn globs() {
+    assert!( glob_matches("sys", "sys") ) ;
+    assert!( ! glob_matches("sys", "sys2") ) ;
+    assert!( ! glob_matches("sys2", "sys") ) ;
+    assert!( glob_matches("sys?", "sys2") ) ;
+    assert!( ! glob_matches("sys?", "sys") ) ;
+    assert!( glob_matches("*", "") ) ;
+    assert!( glob_matches("*", "anything") ) ;
+    assert!( glob_matches("s*", "sys") ) ;
+    assert!( glob_matches("*s", "sys") ) ;
+    assert!( glob_matches("*y*", "sys") ) ;
+    assert!( ! glob_matches("*x*", "sys") ) ;
+    // The first `*` must not stop at the first `_`.
+    assert!( glob_matches("*_top", "sub_sys_top") ) ;
+    assert!( glob_matches("s*_*p", "sub_sys_top") ) ;
+    assert!( ! glob_matches("s*_*x", "sub_sys_top") ) ;
+    assert!( glob_matches("**", "sys") )
+  }
+}
@@ -0,0 +1,182 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Crash reports of the techniques.
+//!
+//! A technique that panics should not take the whole analysis down, nor
+//! leave kino waiting for it. [`install`](fn.install.html) sets a panic
+//! hook that, on the threads of the techniques, records the message, the
+//! location and the backtrace of the panic instead of printing them.
+//! [`KidManager::launch`](../msg/struct.KidManager.html#method.launch)
+//! catches the panic and sends a [`Crash`](struct.Crash.html) upwards, kino
+//! reports it as an error, writes it to a crash report and keeps running
+//! the other techniques.
+//!
+//! The threads also record the last depth they were done with and the last
+//! query they issued, see [`at_depth`](fn.at_depth.html) and
+//! [`query`](fn.query.html), which end up in the crash report.
+
+use std::fmt ;
+use std::fs ;
+use std::io::Write ;
+use std::panic ;
+use std::cell::RefCell ;
+use std::time::{ SystemTime, UNIX_EPOCH } ;
+
+use error_chain::Backtrace ;
+
+use ::Tek ;
+use errors::* ;
+
+/// What a thread records for its crash report.
+struct Trace {
+  /// Technique running on the thread, `None` for the other threads.
+  tek: Option<Tek>,
+  /// Last depth the technique was done with.
+  depth: Option<String>,
+  /// Last query the technique issued.
+  query: Option<String>,
+  /// Message, location and backtrace of the panic, if any.
+  panic: Option< (String, Option<String>, String) >,
+}
+
+thread_local!{
+  static TRACE: RefCell<Trace> = RefCell::new(
+    Trace { tek: None, depth: None, query: None, panic: None }
+  )
+}
+
+/// Records the panics of the threads of the techniques instead of printing
+/// them, the other threads are handled by the previous hook. Call once,
+/// from the main thread.
+pub fn install() {
+  let previous = panic::take_hook() ;
+  panic::set_hook(
+    Box::new(
+      move |info| {
+        let is_tek = TRACE.with( |trace| trace.borrow().tek.is_some() ) ;
+        if ! is_tek { return previous(info) }
+        let payload = info.payload() ;
+        let msg = if let Some(msg) = payload.downcast_ref::<& 'static str>() {
+          msg.to_string()
+        } else if let Some(msg) = payload.downcast_ref::<String>() {
+          msg.clone()
+        } else {
+          "panicked without a message".to_string()
+        } ;
+        let location = info.location().map(
+          |loc| format!("{}:{}", loc.file(), loc.line())
+        ) ;
+        let backtrace = format!("{:?}", Backtrace::new()) ;
+        TRACE.with(
+          |trace| trace.borrow_mut().panic = Some( (msg, location, backtrace) )
+        )
+      }
+    )
+  )
+}
+
+/// Declares the current thread as running a technique.
+pub fn enter(tek: Tek) {
+  TRACE.with(
+    |trace| * trace.borrow_mut() = Trace {
+      tek: Some(tek), depth: None, query: None, panic: None
+    }
+  )
+}
+
+/// Records the last depth the technique of the current thread was done
+/// with.
+pub fn at_depth<D: fmt::Display>(depth: & D) {
+  TRACE.with( |trace| trace.borrow_mut().depth = Some( depth.to_string() ) )
+}
+
+/// Records the last query the technique of the current thread issued.
+pub fn query(query: String) {
+  TRACE.with( |trace| trace.borrow_mut().query = Some(query) )
+}
+
+/// The crash of the technique of the current thread, after it panicked.
+/// `None` if the thread does not run a technique.
+pub fn take() -> Option<Crash> {
+  TRACE.with(
+    |trace| {
+      let mut trace = trace.borrow_mut() ;
+      let tek = match trace.tek {
+        Some(tek) => tek,
+        None => return None,
+      } ;
+      let (msg, location, backtrace) = trace.panic.take().unwrap_or_else(
+        || (
+          "panicked, no details recorded".to_string(), None,
+          "no backtrace".to_string()
+        )
+      ) ;
+      Some(
+        Crash {
+          tek: tek, msg: msg, location: location, backtrace: backtrace,
+          depth: trace.depth.take(), query: trace.query.take(),
+        }
+      )
+    }
+  )
+}
+
+/// The panic of a technique, and where the technique was at.
+#[derive(Debug, Clone)]
+pub struct Crash {
+  /// The technique.
+  pub tek: Tek,
+  /// Message of the panic.
+  pub msg: String,
+  /// Location of the panic in the sources of kino, if known.
+  pub location: Option<String>,
+  /// Backtrace of the panic.
+  pub backtrace: String,
+  /// Last depth the technique was done with, if any.
+  pub depth: Option<String>,
+  /// Last query the technique issued, if any.
+  pub query: Option<String>,
+}
+impl Crash {
+  /// Writes the crash report in a directory, creating it if needed.
+  /// Returns the path of the report.
+  pub fn write(& self, dir: & str) -> Res<String> {
+    if let Err(e) = fs::create_dir_all(dir) {
+      bail!( ErrorKind::FileIoError(dir.to_string(), e) )
+    }
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(
+      |time| time.as_secs()
+    ).unwrap_or(0) ;
+    let path = format!("{}/crash_{}_{}.txt", dir, self.tek.to_str(), secs) ;
+    let txt = format!(
+      "{}\n\ndepth: {}\nlast query: {}\n\nbacktrace:\n{}\n",
+      self,
+      self.depth.as_ref().map( |d| d.as_str() ).unwrap_or("none"),
+      self.query.as_ref().map( |q| q.as_str() ).unwrap_or("none"),
+      self.backtrace
+    ) ;
+    match fs::File::create(& path) {
+      Ok(mut file) => match file.write_all( txt.as_bytes() ) {
+        Ok(()) => Ok(path),
+        Err(e) => bail!( ErrorKind::FileIoError(path, e) ),
+      },
+      Err(e) => bail!( ErrorKind::FileIoError(path, e) ),
+    }
+  }
+}
+impl fmt::Display for Crash {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    try!( write!(fmt, "{} panicked", self.tek.desc()) ) ;
+    if let Some(ref location) = self.location {
+      try!( write!(fmt, " at {}", location) )
+    }
+    write!(fmt, ": {}", self.msg)
+  }
+}
@@ -0,0 +1,176 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Falsification of the invariants on concrete reachable states.
+//!
+//! kino forwards the invariants it broadcasts for a system and the concrete
+//! traces the techniques find to a [`Falsifier`](struct.Falsifier.html),
+//! which evaluates the former on the latter in the background. One-state
+//! invariants are evaluated on each state, two-state ones on each step of
+//! the traces. An invariant evaluating to false is not an invariant: the
+//! falsifier reports it upwards as a
+//! [`Falsified`](../msg/enum.MsgUp.html#variant.Falsified) message, and
+//! stops checking it.
+//!
+//! Invariants the falsifier cannot evaluate on a state, typically because
+//! the state does not mention all their variables, are not reported.
+
+use std::thread ;
+use std::sync::mpsc::{ channel, Sender, Receiver } ;
+
+use term::{
+  Sym, Offset, Offset2, State, STerm, STermSet, Factory, Model, VarMaker
+} ;
+
+use msg::MsgUp ;
+use samples::{ Sample, SamplePool, DEFAULT_CAPACITY } ;
+use errors::* ;
+
+/// Job of the falsifier.
+enum Job {
+  /// Invariants broadcast for the system.
+  Invariants(STermSet),
+  /// States of a concrete trace, in order.
+  Trace(Vec<Sample>),
+}
+
+/// Handle on a falsifier running in the background. The falsifier stops
+/// once the handle is dropped.
+pub struct Falsifier {
+  /// Sends jobs to the falsifier.
+  s: Sender<Job>,
+}
+impl Falsifier {
+  /// Launches a falsifier for the invariants of a system, reporting to
+  /// `up`.
+  pub fn launch(up: Sender<MsgUp>, sys: Sym, factory: Factory) -> Res<Self> {
+    let (s, r) = channel() ;
+    match thread::Builder::new().name( "kino_falsifier".to_string() ).spawn(
+      move || run(r, up, sys, factory)
+    ) {
+      Ok(_) => Ok( Falsifier { s: s } ),
+      Err(e) => bail!(
+        format!("could not spawn invariant falsifier: {}", e)
+      ),
+    }
+  }
+  /// Invariants broadcast for the system. Checked on the states seen so
+  /// far, and on the traces that follow.
+  pub fn invariants(& self, invs: STermSet) {
+    // Failing to send means the falsifier stopped, nothing to check then.
+    let _ = self.s.send( Job::Invariants(invs) ) ;
+  }
+  /// Concrete trace to check the invariants on, as its states in order.
+  pub fn trace(& self, states: Vec<Sample>) {
+    let _ = self.s.send( Job::Trace(states) ) ;
+  }
+}
+
+/// Runs a falsifier until its handle is dropped or kino stops listening.
+fn run(r: Receiver<Job>, up: Sender<MsgUp>, sys: Sym, factory: Factory) {
+  // Invariants not falsified yet.
+  let mut invs = STermSet::new() ;
+  // States and steps seen so far.
+  let mut states = SamplePool::mk(DEFAULT_CAPACITY) ;
+  let mut steps: Vec<(Sample, Sample)> = Vec::with_capacity(17) ;
+
+  while let Ok(job) = r.recv() {
+    let falsified = match job {
+      Job::Invariants(new) => {
+        let new: STermSet = new.into_iter().filter(
+          |inv| ! invs.contains(inv)
+        ).collect() ;
+        let falsified = falsify(
+          & factory, & sys, & new, states.samples(), & steps
+        ) ;
+        invs.extend(
+          new.into_iter().filter( |inv| ! falsified.contains(inv) )
+        ) ;
+        falsified
+      },
+      Job::Trace(trace) => {
+        let mut new_states = Vec::with_capacity( trace.len() ) ;
+        let mut new_steps = Vec::with_capacity( trace.len() ) ;
+        for (index, state) in trace.iter().enumerate() {
+          if states.add( state.clone() ) { new_states.push( state.clone() ) }
+          if index > 0 && steps.len() < DEFAULT_CAPACITY {
+            let step = ( trace[index - 1].clone(), state.clone() ) ;
+            if ! steps.contains(& step) {
+              steps.push( step.clone() ) ;
+              new_steps.push(step)
+            }
+          }
+        }
+        let falsified = falsify(
+          & factory, & sys, & invs, & new_states, & new_steps
+        ) ;
+        for inv in falsified.iter() {
+          invs.remove(inv) ; ()
+        }
+        falsified
+      },
+    } ;
+    if ! falsified.is_empty() && up.send(
+      MsgUp::Falsified(sys.clone(), falsified)
+    ).is_err() {
+      return ()
+    }
+  }
+}
+
+/// The invariants evaluating to false on some states or steps.
+fn falsify(
+  factory: & Factory, sys: & Sym, invs: & STermSet,
+  states: & [Sample], steps: & [(Sample, Sample)]
+) -> STermSet {
+  let off = Offset2::init() ;
+  let mut falsified = STermSet::new() ;
+  if invs.is_empty() { return falsified }
+  for state in states.iter() {
+    let model = SamplePool::model_of(state, factory) ;
+    for inv in invs.iter() {
+      if let STerm::One(ref curr, _) = * inv {
+        if ! falsified.contains(inv) && factory.eval_bool(
+          curr, & off, & model, sys.clone()
+        ).ok() == Some(false) {
+          falsified.insert( inv.clone() ) ; ()
+        }
+      }
+    }
+  }
+  for & (ref pre, ref post) in steps.iter() {
+    let model = model_of_step(pre, post, factory) ;
+    for inv in invs.iter() {
+      if let STerm::Two(ref next) = * inv {
+        if ! falsified.contains(inv) && factory.eval_bool(
+          next, & off, & model, sys.clone()
+        ).ok() == Some(false) {
+          falsified.insert( inv.clone() ) ; ()
+        }
+      }
+    }
+  }
+  falsified
+}
+
+/// A model describing a step, the first state at offset `0` and the second
+/// one at offset `1`.
+fn model_of_step(pre: & Sample, post: & Sample, factory: & Factory) -> Model {
+  let mut model = SamplePool::model_of(pre, factory) ;
+  let off = Offset::of_int(1) ;
+  model.extend(
+    post.iter().map(
+      |& (ref sym, ref cst)| (
+        ( factory.svar(sym.clone(), State::Curr), Some( off.clone() ) ),
+        cst.clone()
+      )
+    )
+  ) ;
+  model
+}
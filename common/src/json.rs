@@ -0,0 +1,158 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading flat JSON: strings, scalars, and arrays of objects mapping names
+//! to them.
+//!
+//! This is what kino writes, see [`json_str`](../log/fn.json_str.html), and
+//! what its inputs in JSON use. Values are returned as strings, scalars
+//! verbatim, `null` as `None`. Nested objects and arrays are not supported.
+
+use std::iter::Peekable ;
+use std::str::Chars ;
+
+/// Characters of some JSON text.
+pub type JsonChars<'a> = Peekable< Chars<'a> > ;
+
+/// Skips whitespaces.
+pub fn skip_ws(chars: & mut JsonChars) {
+  while chars.peek().map_or(false, |c| c.is_whitespace()) {
+    chars.next() ;
+  }
+}
+
+/// Skips whitespaces, consumes `c` if it comes next.
+pub fn is_next(chars: & mut JsonChars, c: char) -> bool {
+  skip_ws(chars) ;
+  if chars.peek() == Some(& c) {
+    chars.next() ;
+    true
+  } else { false }
+}
+
+/// Skips whitespaces, fails if `c` does not come next.
+pub fn expect(chars: & mut JsonChars, c: char) -> Result<(), String> {
+  if is_next(chars, c) { Ok(()) } else {
+    Err( format!("expected `{}`", c) )
+  }
+}
+
+/// The four hexadecimal digits of a `\u` escape.
+fn hex4(chars: & mut JsonChars) -> Result<u32, String> {
+  let mut code = 0 ;
+  for _ in 0..4 {
+    match chars.next().and_then( |c| c.to_digit(16) ) {
+      Some(digit) => code = code * 16 + digit,
+      None => return Err( "expected four hexadecimal digits".to_string() ),
+    }
+  }
+  Ok(code)
+}
+
+/// Parses a JSON string, after whitespaces.
+///
+/// Handles all the escapes of JSON, including `\uXXXX` and the surrogate
+/// pairs of the characters outside of the basic multilingual plane.
+pub fn string(chars: & mut JsonChars) -> Result<String, String> {
+  try!( expect(chars, '"') ) ;
+  let mut res = String::new() ;
+  loop {
+    match chars.next() {
+      Some('"') => return Ok(res),
+      Some('\\') => match chars.next() {
+        Some('n') => res.push('\n'),
+        Some('r') => res.push('\r'),
+        Some('t') => res.push('\t'),
+        Some('b') => res.push('\u{8}'),
+        Some('f') => res.push('\u{c}'),
+        Some('u') => {
+          let mut code = try!( hex4(chars) ) ;
+          if 0xD800 <= code && code < 0xDC00 {
+            if chars.next() != Some('\\') || chars.next() != Some('u') {
+              return Err( "unpaired surrogate".to_string() )
+            }
+            let low = try!( hex4(chars) ) ;
+            if low < 0xDC00 || 0xE000 <= low {
+              return Err( "unpaired surrogate".to_string() )
+            }
+            code = 0x10000 + ( (code - 0xD800) << 10 ) + (low - 0xDC00)
+          }
+          match ::std::char::from_u32(code) {
+            Some(c) => res.push(c),
+            None => return Err(
+              format!("illegal character code `{:04x}`", code)
+            ),
+          }
+        },
+        // `"`, `\` and `/`.
+        Some(c) => res.push(c),
+        None => break,
+      },
+      Some(c) => res.push(c),
+      None => break,
+    }
+  }
+  Err( "unterminated string".to_string() )
+}
+
+/// Parses a JSON string, number, boolean or `null`, the latter being
+/// `None`.
+pub fn value(chars: & mut JsonChars) -> Result<Option<String>, String> {
+  skip_ws(chars) ;
+  if chars.peek() == Some(& '"') { return string(chars).map(Some) }
+  let mut res = String::new() ;
+  while let Some(& c) = chars.peek() {
+    if c == ',' || c == '}' || c == ']' || c.is_whitespace() { break }
+    res.push(c) ;
+    chars.next() ;
+  }
+  match & res as & str {
+    "" => Err( "expected value".to_string() ),
+    "null" => Ok(None),
+    _ => Ok( Some(res) ),
+  }
+}
+
+/// Parses a flat JSON object: its fields, in order.
+pub fn object(
+  chars: & mut JsonChars
+) -> Result< Vec< (String, Option<String>) >, String > {
+  try!( expect(chars, '{') ) ;
+  let mut fields = vec![] ;
+  if is_next(chars, '}') { return Ok(fields) }
+  loop {
+    let name = try!( string(chars) ) ;
+    try!( expect(chars, ':') ) ;
+    let value = try!(
+      value(chars).map_err( |e| format!("{} for field `{}`", e, name) )
+    ) ;
+    fields.push( (name, value) ) ;
+    if is_next(chars, '}') { return Ok(fields) }
+    try!( expect(chars, ',') )
+  }
+}
+
+/// Parses a JSON array of flat objects.
+pub fn objects(
+  chars: & mut JsonChars
+) -> Result< Vec< Vec< (String, Option<String>) > >, String > {
+  try!( expect(chars, '[') ) ;
+  let mut objects = vec![] ;
+  if is_next(chars, ']') { return Ok(objects) }
+  loop {
+    let object = try!(
+      object(chars).map_err(
+        |e| format!("{} in object {}", e, objects.len())
+      )
+    ) ;
+    objects.push(object) ;
+    if is_next(chars, ']') { return Ok(objects) }
+    try!( expect(chars, ',') )
+  }
+}
@@ -21,6 +21,7 @@ extern crate ansi_term as ansi ;
 extern crate nom ;
 #[macro_use]
 extern crate error_chain ;
+extern crate libc ;
 extern crate term ;
 extern crate system as sys ;
 
@@ -106,9 +107,39 @@ pub mod errors {
         description("io error")
         display("io error: {:?}", e)
       }
+
+      #[doc = "
+        Context of an error raised by a technique: the technique, and the
+        file and line in kino's sources where it was raised.
+      "]
+      TekContext(tek: ::Tek, file: & 'static str, line: u32) {
+        description("error raised by a technique")
+        display("in `{}`, raised at {}:{}", tek, file, line)
+      }
+
+      #[doc = "The solver answered `unknown` to a query, for some reason."]
+      SolverUnknown(reason: String) {
+        description("solver answered unknown")
+        display("solver answered `unknown`: {}", reason)
+      }
     }
   }
 
+  /// The reason of the `unknown` answer of a solver causing an error, if
+  /// any.
+  pub fn unknown_reason(e: & Error) -> Option<String> {
+    let mut current = Some(e) ;
+    while let Some(e) = current {
+      if let ErrorKind::SolverUnknown(ref reason) = * e.kind() {
+        return Some( reason.clone() )
+      }
+      current = e.1.next_error.as_ref().and_then(
+        |next| next.downcast_ref::<Error>()
+      )
+    }
+    None
+  }
+
   impl From<::sys::Error> for ErrorKind {
     fn from(e: ::sys::Error) -> ErrorKind {
       match e {
@@ -123,13 +154,16 @@ pub mod errors {
 
 /// Communicates an error and returns `()` if computation is an `Err`, yields
 /// the result (inside the `Ok`) otherwise.
+///
+/// The error is sent with the location of the macro call, see
+/// [`Event::error_at`](msg/struct.Event.html#method.error_at).
 #[macro_export]
 macro_rules! log_try {
   ($event:expr, $e:expr) => (
     match $e {
       Ok(res) => res,
       Err(e) => {
-        $event.error(e) ;
+        $event.error_at(e, file!(), line!()) ;
         return ()
       },
     }
@@ -144,6 +178,18 @@ macro_rules! log_try {
   ) ;
 }
 
+/// Sends an error upwards with the location of the macro call, see
+/// [`Event::error_at`](msg/struct.Event.html#method.error_at).
+///
+/// Techniques report their errors with this macro or `log_try`, never with
+/// `Event::error` directly, so that they all carry the `TekContext` kind.
+#[macro_export]
+macro_rules! log_error {
+  ($event:expr, $e:expr) => (
+    $event.error_at($e, file!(), line!())
+  ) ;
+}
+
 /// Combines `try` and `chain_err` for strings.
 #[macro_export]
 macro_rules! try_chain {
@@ -158,7 +204,18 @@ macro_rules! try_chain {
 
 pub mod msg ;
 pub mod log ;
+pub mod json ;
 pub mod conf ;
+pub mod cache ;
+pub mod clock ;
+pub mod samples ;
+pub mod falsify ;
+pub mod proof ;
+pub mod unsat_core ;
+pub mod values ;
+pub mod cleanup ;
+pub mod pause ;
+pub mod crash ;
 
 
 /// Solver trait that bmc and kind will use.
@@ -170,6 +227,36 @@ pub trait SolverTrait<'a>:
 }
 impl<'a> SolverTrait<'a> for PlainSolver<'a, Factory> {}
 impl<'a> SolverTrait<'a> for TeeSolver<'a, Factory> {}
+impl<
+  'a, S: term::smt::internals::SolverBasic<'a, Factory>
+> SolverTrait<'a> for term::smt::MultiSolver<S> {}
+
+/// Sends the SMT-LIB prelude stored in a file, if any, to a solver. Returns
+/// the prelude, empty if none. See
+/// [`term::smt::prelude`](../term/smt/fn.prelude.html).
+pub fn prelude<'a, S: SolverTrait<'a>>(
+  solver: & mut S, file: & Option<String>
+) -> errors::Res<String> {
+  use std::io::Read ;
+  use errors::{ ErrorKind, ResExt } ;
+  if let Some(ref path) = * file {
+    let mut script = String::new() ;
+    let read = std::fs::File::open(path).and_then(
+      |mut file| file.read_to_string(& mut script)
+    ) ;
+    if let Err(e) = read {
+      bail!( ErrorKind::FileIoError(path.clone(), e) )
+    }
+    try!(
+      term::smt::prelude(solver, & script).chain_err(
+        || format!("while sending prelude `{}` to the solver", path)
+      )
+    ) ;
+    Ok(script)
+  } else {
+    Ok( String::new() )
+  }
+}
 
 /// Creates a plain solver.
 /// 
@@ -178,13 +265,38 @@ impl<'a> SolverTrait<'a> for TeeSolver<'a, Factory> {}
 /// mk_solver! {
 ///   solver_conf,
 ///   conf.smt_log(),
+///   conf.smt_cache(),
+///   conf.smt_prelude(),
 ///   "smt_log_file_name_without_smt2_extension",
 ///   factory, // Cloned in the macro, should be a ref.
 ///   solver => blah(conf, event, solver),
-///   error => event.error(error)
+///   error => log_error!(event, error)
 /// }
 /// ```
 /// 
+/// If the command of the configuration runs cvc5, the configuration is
+/// adapted to it, see [`cvc5`](../term/smt/cvc5/index.html). The solvers
+/// spawned are registered for [cleanup](cleanup/fn.solvers.html).
+///
+/// The prelude, if any, is sent once right after the solver is created, so
+/// that it is logged, see [`prelude`](fn.prelude.html). If the cache
+/// directory is not `None`, the solver is then wrapped in a
+/// [`CachedSolver`](cache/struct.CachedSolver.html), whose keys include the
+/// prelude.
+///
+/// With `race <cmds>` after the prelude, where `<cmds>` is an
+/// `& Option<String>` of commands separated by `+`, the solver is wrapped in
+/// a [`MultiSolver`](../term/smt/struct.MultiSolver.html) racing these
+/// commands and the one of the configuration on each query. The racers
+/// receive the prelude when they are spawned. The solver wrapped receives
+/// no other command, so the smt log only has the prelude and the comments
+/// then.
+/// With `compare <cmd>` after that, `<cmd>` is an `& Option<String>` too.
+/// If it is not `None`, it joins the racers and they are compared instead,
+/// see [`compare`](../term/smt/struct.MultiSolver.html#method.compare).
+/// Disagreements are dumped in the smt log directory, or in the temporary
+/// directory if there is none.
+/// 
 /// Why use a macro? The solver stores mutable references to the stdin and
 /// stout of the kid. The kid must thus be in scope when the solver is used.
 /// 
@@ -197,17 +309,89 @@ impl<'a> SolverTrait<'a> for TeeSolver<'a, Factory> {}
 /// to reach maturity.
 #[macro_export]
 macro_rules! mk_solver_run {
+  (
+    @prelude $smt_prelude:expr, $smt_race:expr, $smt_compare:expr,
+    $dump:expr, $smt_cache:expr,
+    $solver:ident => $run:expr, $err:ident => $errun:expr
+  ) => ({
+    let mut $solver = $solver ;
+    match $crate::prelude(& mut $solver, $smt_prelude) {
+      Ok(prelude) => mk_solver_run!(
+        @race $smt_race, $smt_compare, $dump, $smt_cache, & prelude,
+        $solver => $run, $err => $errun
+      ),
+      Err($err) => $errun,
+    }
+  }) ;
+  (
+    @cache $smt_cache:expr, $prelude:expr, $solver:ident => $run:expr
+  ) => (
+    match * $smt_cache {
+      None => {
+        let mut $solver = $solver ;
+        $run
+      },
+      Some(ref dir) => {
+        let mut $solver = $crate::cache::CachedSolver::mk(
+          $solver, dir
+        ).with_prelude($prelude) ;
+        $run
+      },
+    }
+  ) ;
+  (
+    @race $smt_race:expr, $smt_compare:expr, $dump:expr,
+    $smt_cache:expr, $prelude:expr,
+    $solver:ident => $run:expr, $err:ident => $errun:expr
+  ) => (
+    match (& * $smt_race, & * $smt_compare) {
+      (& None, & None) => mk_solver_run!(
+        @cache $smt_cache, $prelude, $solver => $run
+      ),
+      (race, compare) => {
+        let mut cmds: Vec<String> = match * race {
+          Some(ref cmds) => cmds.split('+').map(
+            |cmd| cmd.trim().to_string()
+          ).collect(),
+          None => vec![],
+        } ;
+        if let Some(ref cmd) = * compare { cmds.push( cmd.clone() ) }
+        match $crate::errors::ResExt::chain_err(
+          $crate::cleanup::solvers(
+            || term::smt::MultiSolver::mk_with_prelude(
+              $solver, & cmds, $prelude
+            )
+          ), || "while spawning racing solvers"
+        ) {
+          Ok($solver) => {
+            let $solver = if compare.is_some() {
+              $solver.compare($dump)
+            } else { $solver } ;
+            mk_solver_run!(
+              @cache $smt_cache, $prelude, $solver => $run
+            )
+          },
+          Err($err) => $errun,
+        }
+      },
+    }
+  ) ;
   (
     $conf:expr,
     $smt_log:expr,
+    $smt_cache:expr,
+    $smt_prelude:expr,
+    race $smt_race:expr,
+    compare $smt_compare:expr,
     $log_file: expr,
     $factory:expr,
     $solver:ident => $run:expr,
     $err:ident => $errun:expr
   ) => (
     match $crate::errors::ResExt::chain_err(
-      term::smt::Kid::mk($conf),
-      || "while spawning solver kid"
+      $crate::cleanup::solvers(
+        || term::smt::Kid::mk( term::smt::cvc5::adapt($conf) )
+      ), || "while spawning solver kid"
     ) {
       Ok(mut kid) => match $crate::errors::ResExt::chain_err(
         term::smt::solver(
@@ -215,13 +399,27 @@ macro_rules! mk_solver_run {
         ), || "while creating solver from kid"
       ) {
         Ok($solver) => match * $smt_log {
-          None => $run,
+          None => mk_solver_run!(
+            @prelude $smt_prelude, $smt_race, $smt_compare, format!(
+              "{}/{}_disagreement",
+              std::env::temp_dir().to_string_lossy(), $log_file
+            ), $smt_cache,
+            $solver => $run, $err => $errun
+          ),
           Some(ref path) => {
             let path = format!("{}/{}.smt2", path, $log_file) ;
             match std::fs::File::create(& path) {
               Ok(file) => {
+                $crate::cleanup::keep(
+                  & path, format!("smt trace of `{}`", $log_file)
+                ) ;
                 let $solver = $solver.tee(file) ;
-                $run
+                mk_solver_run!(
+                  @prelude $smt_prelude, $smt_race, $smt_compare, format!(
+                    "{}_disagreement", & path[0 .. path.len() - 5]
+                  ), $smt_cache,
+                  $solver => $run, $err => $errun
+                )
               },
               Err(e) => {
                 use $crate::errors::Res ;
@@ -241,13 +439,44 @@ macro_rules! mk_solver_run {
       Err($err) => $errun,
     }
   ) ;
+  (
+    $conf:expr,
+    $smt_log:expr,
+    $smt_cache:expr,
+    $smt_prelude:expr,
+    $log_file: expr,
+    $factory:expr,
+    $solver:ident => $run:expr,
+    $err:ident => $errun:expr
+  ) => (
+    mk_solver_run!(
+      $conf, $smt_log, $smt_cache, $smt_prelude, race & None::<String>,
+      compare & None::<String>, $log_file, $factory,
+      $solver => $run, $err => $errun
+    )
+  ) ;
 }
 /// Same as [`mk_solver_run`](macro.mk_solver_run!.html) with two solvers.
 #[macro_export]
 macro_rules! mk_two_solver_run {
+  (
+    @prelude $smt_prelude:expr,
+    ($solver1:ident, $solver2:ident) => $run:expr, $err:ident => $errun:expr
+  ) => ({
+    let mut $solver1 = $solver1 ;
+    let mut $solver2 = $solver2 ;
+    match $crate::prelude(& mut $solver1, $smt_prelude) {
+      Ok(_) => match $crate::prelude(& mut $solver2, $smt_prelude) {
+        Ok(_) => $run,
+        Err($err) => $errun,
+      },
+      Err($err) => $errun,
+    }
+  }) ;
   (
     $conf:expr,
     $smt_log:expr,
+    $smt_prelude:expr,
     $log_file: expr,
     $factory:expr,
     (
@@ -255,13 +484,15 @@ macro_rules! mk_two_solver_run {
     ) => $run:expr,
     $err:ident => $errun:expr
   ) => (
-    match (
-      $crate::errors::ResExt::chain_err(
-        term::smt::Kid::mk($conf.clone()),
-        || format!("while creating {} solver kid", $log_suff1)
-      ), $crate::errors::ResExt::chain_err(
-        term::smt::Kid::mk($conf),
-        || format!("while creating {} solver kid", $log_suff2)
+    match $crate::cleanup::solvers(
+      || (
+        $crate::errors::ResExt::chain_err(
+          term::smt::Kid::mk( term::smt::cvc5::adapt( $conf.clone() ) ),
+          || format!("while creating {} solver kid", $log_suff1)
+        ), $crate::errors::ResExt::chain_err(
+          term::smt::Kid::mk( term::smt::cvc5::adapt($conf) ),
+          || format!("while creating {} solver kid", $log_suff2)
+        )
       )
     ) {
       ( Ok(mut kid_1), Ok(mut kid_2) ) => match (
@@ -275,7 +506,10 @@ macro_rules! mk_two_solver_run {
         ),
       ) {
         ( Ok($solver1), Ok($solver2) ) => match * $smt_log {
-          None => $run,
+          None => mk_two_solver_run!(
+            @prelude $smt_prelude,
+            ($solver1, $solver2) => $run, $err => $errun
+          ),
           Some(ref path) => {
             use $crate::errors::Res ;
             let (path_1, path_2) = (
@@ -287,9 +521,18 @@ macro_rules! mk_two_solver_run {
               std::fs::File::create(& path_2)
             ) {
               (Ok(file_1), Ok(file_2)) => {
+                $crate::cleanup::keep(
+                  & path_1, format!("smt trace of `{}`", $log_file)
+                ) ;
+                $crate::cleanup::keep(
+                  & path_2, format!("smt trace of `{}`", $log_file)
+                ) ;
                 let $solver1 = $solver1.tee(file_1) ;
                 let $solver2 = $solver2.tee(file_2) ;
-                $run
+                mk_two_solver_run!(
+                  @prelude $smt_prelude,
+                  ($solver1, $solver2) => $run, $err => $errun
+                )
               },
               (Err(e), _) => {
                 let e: $crate::errors::Error =
@@ -362,6 +605,8 @@ pub enum Tek {
   KInd,
   /// 2-induction.
   Twind,
+  /// Predicate abstraction.
+  Cegar,
   /// Invgen.
   Tig,
   /// Invariant pruner.
@@ -386,6 +631,7 @@ impl Tek {
       Bmc => "bmc",
       KInd => "k-ind",
       Twind => "2-ind",
+      Cegar => "cegar",
       Tig => "tig",
       Pruner => "pruner",
       Tec(ref s, _) => & s,
@@ -400,11 +646,22 @@ impl Tek {
       Bmc => "bounded model checking",
       KInd => "k-induction",
       Twind => "2-induction",
+      Cegar => "predicate abstraction",
       Tig => "invariant generation",
       Pruner => "invariant pruner",
       Tec(_, ref desc) => & desc,
     }
   }
+  /// True for the techniques checking properties, which handle
+  /// [`NewProps`](msg/enum.MsgDown.html#variant.NewProps) messages.
+  #[inline]
+  pub fn checks_props(& self) -> bool {
+    use Tek::* ;
+    match * self {
+      Bmc | KInd | Twind | Cegar => true,
+      _ => false,
+    }
+  }
   /// Thread name for techniques.
   #[inline(always)]
   pub fn thread_name(& self) -> String {
@@ -414,6 +671,7 @@ impl Tek {
       Bmc => "kino_bmc".to_string(),
       KInd => "kino_k-induction".to_string(),
       Twind => "kino_2-induction".to_string(),
+      Cegar => "kino_cegar".to_string(),
       Tig => "kino_invgen".to_string(),
       Pruner => "kino_pruner".to_string(),
       Tec(ref s, _) => format!("kino_{}", s),
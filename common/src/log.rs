@@ -9,19 +9,66 @@
 
 //! Logging.
 
-use std::time::Duration ;
+use std::io::Write ;
+use std::time::{ Duration, SystemTime, UNIX_EPOCH } ;
+use std::sync::{ Arc, Mutex } ;
+use std::collections::HashMap ;
 
 use ansi::Style as AStyle ;
 
-use term::{ Sym, Offset } ;
+use term::{ Sym, Term, Offset, MemUsage, STermSet } ;
 
 use sys::Cex ;
 
+use msg::{ GraphEvent, Unknown } ;
+use crash::Crash ;
+
 /// Formats a duration as seconds.
 pub fn fmt_duration(d: Duration) -> String {
   format!("{}.{} seconds", d.as_secs(), d.subsec_nanos())
 }
 
+/// Formats a duration as a JSON number of seconds.
+fn json_duration(d: Duration) -> String {
+  format!("{}.{:09}", d.as_secs(), d.subsec_nanos())
+}
+
+/// Formats a string as a JSON string.
+pub fn json_str(s: & str) -> String {
+  let mut res = String::with_capacity(s.len() + 2) ;
+  res.push('"') ;
+  for c in s.chars() {
+    match c {
+      '"' => res.push_str("\\\""),
+      '\\' => res.push_str("\\\\"),
+      '\n' => res.push_str("\\n"),
+      '\r' => res.push_str("\\r"),
+      '\t' => res.push_str("\\t"),
+      c if (c as u32) < 0x20 => res.push_str(
+        & format!("\\u{:04x}", c as u32)
+      ),
+      c => res.push(c),
+    }
+  }
+  res.push('"') ;
+  res
+}
+
+/// Formats some symbols as a JSON array of strings.
+fn json_syms<'a, Syms: Iterator<Item = & 'a Sym>>(syms: Syms) -> String {
+  let mut res = "[".to_string() ;
+  for sym in syms {
+    if res.len() > 1 { res.push(',') }
+    res.push_str( & json_str( & format!("{}", sym) ) )
+  }
+  res.push(']') ;
+  res
+}
+
+/// Time spent at each unrolling depth by the techniques, for each property
+/// they were checking.
+pub type Curves = HashMap< Sym, Vec<(super::Tek, Offset, Duration)> > ;
+
 /// Formatting elements of a log.
 pub trait Formatter: Clone {
   /// The pre prefix.
@@ -155,13 +202,189 @@ impl Styler for Style {
 
 
 
+/// Receives the results of a run, for custom reporting.
+///
+/// Sinks are registered on a [`MasterLog`](struct.MasterLog.html) with
+/// [`sink`](struct.MasterLog.html#method.sink). The log notifies them of the
+/// results it logs, in quiet mode or not, before printing anything. All the
+/// methods do nothing by default.
+pub trait ResultSink: Send {
+  /// Some properties were proved by a technique at some offset.
+  fn proved(& mut self, _tek: & super::Tek, _props: & [Sym], _at: & Offset) {}
+  /// Some properties are true up to some depth, according to a technique.
+  fn k_true(& mut self, _tek: & super::Tek, _props: & [Sym], _at: & Offset) {}
+  /// Some properties were falsified by a technique, with a counterexample.
+  fn falsified(& mut self, _tek: & super::Tek, _props: & [Sym], _cex: & Cex) {}
+  /// A technique discovered invariants for a system, at some depth if any.
+  fn invariants(
+    & mut self, _tek: & super::Tek, _sys: & Sym, _invs: & STermSet,
+    _at: Option<usize>
+  ) {}
+  /// Memory usage reported by a technique, when kino logs statistics.
+  fn stats(& mut self, _tek: & super::Tek, _usage: & [MemUsage]) {}
+  /// An error raised by a technique or kino, crashes included.
+  fn error(& mut self, _tek: & super::Tek, _msg: & str) {}
+  /// End of an analysis: `safe`, `unsafe` or `unknown`, with the properties
+  /// left unknown, the reason why the analysis stopped, why the techniques
+  /// stopped without (dis)proving them, and how long it took if known.
+  fn result(
+    & mut self, _result: & str, _unknown: & [Sym], _reason: Option<& str>,
+    _why: & [(super::Tek, Unknown)], _time: Option<Duration>
+  ) {}
+}
+
+/// Result sink writing every event of a run as a JSON object, one per line.
+///
+/// Each object has an `event` field, `proved`, `k_true`, `falsified`,
+/// `invariants`, `stats`, `result` or `error`, and a `timestamp` field, the
+/// number of seconds since the Unix epoch. The other fields are the ones of
+/// the JSON mode of the log. Lines are flushed as they are written, so that
+/// the output can be followed while kino is running. Write errors are
+/// ignored, they must not stop the analysis.
+pub struct JsonSink<W: Write + Send> {
+  /// Where the events are written.
+  out: W,
+}
+impl<W: Write + Send> JsonSink<W> {
+  /// Creates a JSON sink.
+  pub fn mk(out: W) -> Self {
+    JsonSink { out: out }
+  }
+  /// Writes an event, values must already be JSON.
+  fn event(& mut self, event: & str, fields: & [ (& str, String) ]) {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(
+      json_duration
+    ).unwrap_or( "null".to_string() ) ;
+    let mut line = format!(
+      "{{\"event\":{},\"timestamp\":{}", json_str(event), timestamp
+    ) ;
+    for & (key, ref val) in fields.iter() {
+      line.push_str( & format!(",{}:{}", json_str(key), val) )
+    }
+    line.push('}') ;
+    let _ = writeln!(self.out, "{}", line).and_then(
+      |()| self.out.flush()
+    ) ;
+  }
+}
+impl<W: Write + Send> ResultSink for JsonSink<W> {
+  fn proved(& mut self, tek: & super::Tek, props: & [Sym], at: & Offset) {
+    self.event(
+      "proved", & [
+        ("tek", json_str( tek.to_str() )),
+        ("props", json_syms( props.iter() )),
+        ("k", format!("{}", at)),
+      ]
+    )
+  }
+  fn k_true(& mut self, tek: & super::Tek, props: & [Sym], at: & Offset) {
+    self.event(
+      "k_true", & [
+        ("tek", json_str( tek.to_str() )),
+        ("props", json_syms( props.iter() )),
+        ("k", format!("{}", at)),
+      ]
+    )
+  }
+  fn falsified(& mut self, tek: & super::Tek, props: & [Sym], cex: & Cex) {
+    self.event(
+      "falsified", & [
+        ("tek", json_str( tek.to_str() )),
+        ("props", json_syms( props.iter() )),
+        ("at", format!("{}", cex.len())),
+        ("cex", cex.to_json(false)),
+      ]
+    )
+  }
+  fn invariants(
+    & mut self, tek: & super::Tek, sys: & Sym, invs: & STermSet,
+    at: Option<usize>
+  ) {
+    let mut invs: Vec<String> = invs.iter().map(
+      |inv| json_str( & format!("{}", inv) )
+    ).collect() ;
+    invs.sort() ;
+    self.event(
+      "invariants", & [
+        ("tek", json_str( tek.to_str() )),
+        ("sys", json_str( & format!("{}", sys) )),
+        (
+          "at", at.map( |at| format!("{}", at) ).unwrap_or(
+            "null".to_string()
+          )
+        ),
+        ("invariants", format!("[{}]", invs.join(","))),
+      ]
+    )
+  }
+  fn stats(& mut self, tek: & super::Tek, usage: & [MemUsage]) {
+    let mem: Vec<String> = usage.iter().map(
+      |u| format!(
+        "{{\"name\":{},\"entries\":{},\"bytes\":{}}}",
+        json_str(& u.name), u.entries, u.bytes
+      )
+    ).collect() ;
+    self.event(
+      "stats", & [
+        ("tek", json_str( tek.to_str() )),
+        ("memory", format!("[{}]", mem.join(","))),
+      ]
+    )
+  }
+  fn error(& mut self, tek: & super::Tek, msg: & str) {
+    self.event(
+      "error", & [
+        ("tek", json_str( tek.to_str() )),
+        ("msg", json_str(msg)),
+      ]
+    )
+  }
+  fn result(
+    & mut self, result: & str, unknown: & [Sym], reason: Option<& str>,
+    why: & [(super::Tek, Unknown)], time: Option<Duration>
+  ) {
+    let why: Vec<String> = why.iter().map(
+      |& (ref t, ref why)| format!(
+        "{{\"tek\":{},\"why\":{}}}",
+        json_str( t.to_str() ), json_str( & format!("{}", why) )
+      )
+    ).collect() ;
+    self.event(
+      "result", & [
+        ("result", json_str(result)),
+        ("unknown", json_syms( unknown.iter() )),
+        ("reason", reason.map( json_str ).unwrap_or( "null".to_string() )),
+        ("why", format!("[{}]", why.join(","))),
+        (
+          "time", time.map( json_duration ).unwrap_or( "null".to_string() )
+        ),
+      ]
+    )
+  }
+}
+
 /// Logger used by kino at top level.
+///
+/// In quiet mode, human-oriented logging is suppressed and results, errors
+/// included, are printed as JSON objects, one per line.
 #[derive(Clone)]
 pub struct MasterLog<F, S> {
   /// Formatting.
   fmt: F,
   /// Styling.
   stl: S,
+  /// Quiet mode.
+  quiet: bool,
+  /// JSON mode, quiet with structured counterexamples, depths and
+  /// invariants.
+  json: bool,
+  /// Prints counterexamples in full, without compressing their loops.
+  full_cex: bool,
+  /// Digits of the decimal approximation of the rationals of the
+  /// counterexamples, if any.
+  precision: Option<usize>,
+  /// Result sinks, shared by the clones of the log.
+  sinks: Vec< Arc< Mutex< Box<ResultSink> > > >,
 }
 
 impl<F, S: Clone> MasterLog<F, S> {
@@ -173,19 +396,71 @@ impl<F, S: Clone> MasterLog<F, S> {
   /// The styler.
   #[inline(always)]
   pub fn stl(& self) -> & S { & self.stl }
+  /// Same log in quiet mode.
+  pub fn quiet(mut self) -> Self {
+    self.quiet = true ;
+    self
+  }
+  /// Same log in JSON mode, which is quiet.
+  pub fn json_mode(mut self) -> Self {
+    self.quiet = true ;
+    self.json = true ;
+    self
+  }
+  /// Same log printing counterexamples in full.
+  pub fn full_cex(mut self) -> Self {
+    self.full_cex = true ;
+    self
+  }
+  /// Same log printing a decimal approximation of the rationals of the
+  /// counterexamples, with `precision` digits after the point.
+  pub fn precision(mut self, precision: usize) -> Self {
+    self.precision = Some(precision) ;
+    self
+  }
+  /// Same log notifying a result sink of the results it logs.
+  pub fn sink<Sink: ResultSink + 'static>(mut self, sink: Sink) -> Self {
+    self.sinks.push( Arc::new( Mutex::new( Box::new(sink) ) ) ) ;
+    self
+  }
+  /// True if the log is in quiet mode.
+  #[inline(always)]
+  pub fn is_quiet(& self) -> bool { self.quiet }
+  /// Applies something to the result sinks, skipping the poisoned ones.
+  fn notify<Fun: FnMut(& mut ResultSink)>(& self, mut f: Fun) {
+    for sink in self.sinks.iter() {
+      if let Ok(mut sink) = sink.lock() { f(& mut * * sink) }
+    }
+  }
+  /// Prints a JSON object from some fields, values must already be JSON.
+  fn json(& self, fields: & [ (& str, String) ]) {
+    let mut line = "{".to_string() ;
+    for & (key, ref val) in fields.iter() {
+      if line.len() > 1 { line.push(',') }
+      line.push_str( & format!("{}:{}", json_str(key), val) )
+    }
+    line.push('}') ;
+    println!("{}", line)
+  }
 }
 
 impl MasterLog<Format, Style> {
   /// Creates a default log.
   pub fn default() -> Self {
-    MasterLog { fmt: Format::default(), stl: Style::default() }
+    MasterLog {
+      fmt: Format::default(), stl: Style::default(), quiet: false,
+      json: false, full_cex: false, precision: None, sinks: vec![],
+    }
   }
 }
 
 impl MasterLog<NoFormat, NoStyle> {
   /// Creates a no formatting, no styling log.
   pub fn empty() -> Self {
-    MasterLog { fmt: NoFormat, stl: NoStyle }
+    MasterLog {
+      fmt: NoFormat, stl: NoStyle, quiet: false, json: false,
+      full_cex: false, precision: None, sinks: vec![],
+    }
   }
 }
 
@@ -211,22 +486,26 @@ impl<
 
   /// Prints a separation between log sections.
   pub fn sep(& self) {
+    if self.quiet { return }
     println!("")
   }
 
   /// Prints a newline in a log section.
   pub fn nl(& self) {
+    if self.quiet { return }
     println!("{} {}", self.fmt.ppre(), self.fmt.pref())
   }
 
   /// Prints a trailer line.
   pub fn trail(& self) {
+    if self.quiet { return }
     println!("{} {}{}", self.fmt.ppre(), self.fmt.pref(), self.fmt.trail()) ;
     self.sep()
   }
 
   /// Prints a title line.
   pub fn title(& self, e: & str) {
+    if self.quiet { return }
     println!(
       "{} {}{} {}",
       self.fmt.ppre(), self.fmt.pref(), self.fmt.head(), self.mk_emph(e)
@@ -235,6 +514,7 @@ impl<
 
   /// Prints some log lines.
   pub fn print(& self, e: & str) {
+    if self.quiet { return }
     for line in e.lines() {
       println!("{} {} {}", self.fmt.ppre(), self.fmt.pref(), line)
     }
@@ -244,6 +524,7 @@ impl<
   pub fn pref_log(
     & self, pref: & str, title: & super::Tek, bla: & str
   ) {
+    if self.quiet { return }
     println!(
       "{} {} {}", self.fmt.ppre(), pref, self.emph(title.to_str())
     ) ;
@@ -272,6 +553,21 @@ impl<
 
   /// Prints some bad text line by line.
   pub fn bad(& self, t: & super::Tek, bla: & str) {
+    self.notify( |sink| sink.error(t, bla) ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("error")),
+          ("tek", json_str( t.to_str() )),
+          ("msg", json_str(bla)),
+        ]
+      )
+    }
+    self.print_bad(t, bla)
+  }
+
+  /// Prints some bad text line by line, without notifying the sinks.
+  fn print_bad(& self, t: & super::Tek, bla: & str) {
     self.pref_log( & self.mk_bad( self.fmt.pref() ), t, bla ) ;
     self.nl()
   }
@@ -287,6 +583,16 @@ impl<
 
   /// Logs a `safe` end of analysis.
   pub fn log_safe(& self, time: Duration) {
+    self.notify( |sink| sink.result("safe", & [], None, & [], Some(time)) ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("result")),
+          ("result", json_str("safe")),
+          ("time", json_duration(time)),
+        ]
+      )
+    }
     let pref = format!(
       "{} {}",
       self.fmt.ppre(),
@@ -305,6 +611,16 @@ impl<
 
   /// Logs an `unsafe` end of analysis.
   pub fn log_unsafe(& self, time: Duration) {
+    self.notify( |sink| sink.result("unsafe", & [], None, & [], Some(time)) ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("result")),
+          ("result", json_str("unsafe")),
+          ("time", json_duration(time)),
+        ]
+      )
+    }
     let pref = format!(
       "{} {}",
       self.fmt.ppre(),
@@ -322,10 +638,43 @@ impl<
     self.nl()
   }
 
-  /// Logs a `unknown` end of analysis.
+  /// Logs a `unknown` end of analysis, with the reason why the analysis
+  /// stopped if any, and why the techniques stopped without (dis)proving
+  /// the properties.
   pub fn log_unknown<
     'a, Props: Iterator<Item = & 'a Sym>
-  >(& self, props: Props, time: Duration) {
+  >(
+    & self, props: Props, time: Duration, reason: Option<& str>,
+    why: & [(super::Tek, Unknown)]
+  ) {
+    let props: Vec<Sym> = props.cloned().collect() ;
+    self.notify(
+      |sink| sink.result("unknown", & props, reason, why, Some(time))
+    ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("result")),
+          ("result", json_str("unknown")),
+          ("unknown", json_syms( props.iter() )),
+          (
+            "reason",
+            reason.map( json_str ).unwrap_or( "null".to_string() )
+          ),
+          (
+            "why", format!(
+              "[{}]", why.iter().map(
+                |& (ref t, ref why)| format!(
+                  "{{\"tek\":{},\"why\":{}}}",
+                  json_str( t.to_str() ), json_str( & format!("{}", why) )
+                )
+              ).collect::<Vec<_>>().join(",")
+            )
+          ),
+          ("time", json_duration(time)),
+        ]
+      )
+    }
     let pref = format!(
       "{} {}",
       self.fmt.ppre(),
@@ -340,11 +689,20 @@ impl<
         )
       )
     ) ;
+    if let Some(reason) = reason {
+      println!("{} {}", pref, self.mk_sad(reason))
+    }
+    for & (ref t, ref why) in why.iter() {
+      println!(
+        "{} {} {}", pref, self.emph( t.to_str() ),
+        self.mk_sad( & format!("{}", why) )
+      )
+    }
     println!(
       "{} could not (dis)prove",
       pref
     ) ;
-    for prop in props {
+    for prop in props.iter() {
       println!(
         "{} - {}",
         pref,
@@ -358,6 +716,15 @@ impl<
 
   /// Logs a `unknown` end of analysis without any unknown properties.
   pub fn just_log_unknown(& self) {
+    self.notify( |sink| sink.result("unknown", & [], None, & [], None) ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("result")),
+          ("result", json_str("unknown")),
+        ]
+      )
+    }
     let pref = format!(
       "{} {}",
       self.fmt.ppre(),
@@ -373,15 +740,57 @@ impl<
     self.nl()
   }
 
+  /// Logs the verdicts of systems verified one after the other: their name,
+  /// `safe`, `unsafe` or `unknown`, and their number of properties.
+  pub fn log_summary(& self, results: & [ (Sym, & 'static str, usize) ]) {
+    if self.quiet {
+      let mut syss = "[".to_string() ;
+      for & (ref sys, result, props) in results.iter() {
+        if syss.len() > 1 { syss.push(',') }
+        syss.push_str(
+          & format!(
+            "{{\"sys\":{},\"result\":{},\"props\":{}}}",
+            json_str( & format!("{}", sys) ), json_str(result), props
+          )
+        )
+      }
+      syss.push(']') ;
+      return self.json(
+        & [
+          ("event", json_str("summary")),
+          ("systems", syss),
+        ]
+      )
+    }
+    let mut blah = "summary:".to_string() ;
+    for & (ref sys, result, props) in results.iter() {
+      blah.push_str(
+        & format!("\n  {}: {}, {} property(ies)", sys, result, props)
+      )
+    }
+    self.log(& super::Tek::Kino, & blah)
+  }
+
   /// Logs the fact that a property proved some techniques.
   pub fn log_proved(
     & self, t: & super::Tek, props: & [Sym], info: & Offset
   ) {
+    self.notify( |sink| sink.proved(t, props, info) ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("proved")),
+          ("tek", json_str( t.to_str() )),
+          ("props", json_syms( props.iter() )),
+          ("k", format!("{}", info)),
+        ]
+      )
+    }
     let pref = format!(
       "{} {}", self.fmt.ppre(), self.mk_happy(self.fmt.pref())
     ) ;
     println!(
-      "{} {} proved {} propertie(s) at {}:",
+      "{} {} proved {} property(ies) at {}:",
       pref, self.emph(t.desc()), props.len(), info
     ) ;
     println!("{}", pref) ;
@@ -398,6 +807,16 @@ impl<
   pub fn log_error(
     & self, t: & super::Tek, error: & ::errors::ErrorKind
   ) {
+    self.notify( |sink| sink.error(t, & format!("{}", error)) ) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("error")),
+          ("tek", json_str( t.to_str() )),
+          ("msg", json_str( & format!("{}", error) )),
+        ]
+      )
+    }
     use errors::ErrorKind::* ;
     let pref = format!(
       "{} {}", self.fmt.ppre(), self.mk_bad(self.fmt.pref())
@@ -455,29 +874,438 @@ impl<
     self.nl()
   }
 
+  /// Logs an error raised by a technique, with its chain of causes.
+  ///
+  /// The technique context of the error, if any, is printed as the location
+  /// the error was raised at.
+  pub fn log_error_chain(& self, t: & super::Tek, error: & ::errors::Error) {
+    use errors::ErrorKind::TekContext ;
+    let (location, skip) = match * error.kind() {
+      TekContext(_, file, line) => (Some( format!("{}:{}", file, line) ), 1),
+      _ => (None, 0),
+    } ;
+    let chain: Vec<String> = error.iter().skip(skip).map(
+      |err| format!("{}", err)
+    ).collect() ;
+    self.notify( |sink| sink.error(t, & chain.join("\n")) ) ;
+    if self.quiet {
+      let mut json_chain = "[".to_string() ;
+      for err in chain.iter() {
+        if json_chain.len() > 1 { json_chain.push(',') }
+        json_chain.push_str( & json_str(err) )
+      }
+      json_chain.push(']') ;
+      return self.json(
+        & [
+          ("event", json_str("error")),
+          ("tek", json_str( t.to_str() )),
+          (
+            "msg", chain.last().map(
+              |err| json_str(err)
+            ).unwrap_or("null".to_string())
+          ),
+          ("chain", json_chain),
+          (
+            "location", location.as_ref().map(
+              |loc| json_str(loc)
+            ).unwrap_or("null".to_string())
+          ),
+        ]
+      )
+    }
+    self.print_bad(t, "Error:") ;
+    for err in chain.iter() {
+      self.print_bad(t, & format!("> {}", err))
+    }
+    if let Some(location) = location {
+      self.print_bad(t, & format!("(raised at {})", location))
+    }
+  }
+
+  /// Notifies the result sinks of invariants discovered by a technique,
+  /// logging them is up to the caller. Outputs them in JSON mode.
+  pub fn invariants(
+    & self, t: & super::Tek, sys: & Sym, invs: & STermSet, at: Option<usize>
+  ) {
+    self.notify( |sink| sink.invariants(t, sys, invs, at) ) ;
+    if self.json {
+      let mut invs: Vec<String> = invs.iter().map(
+        |inv| json_str( & format!("{}", inv) )
+      ).collect() ;
+      invs.sort() ;
+      self.json(
+        & [
+          ("event", json_str("invariants")),
+          ("tek", json_str( t.to_str() )),
+          ("sys", json_str( & format!("{}", sys) )),
+          (
+            "at", at.map( |at| format!("{}", at) ).unwrap_or(
+              "null".to_string()
+            )
+          ),
+          ("invariants", format!("[{}]", invs.join(","))),
+        ]
+      )
+    }
+  }
+
+  /// Notifies the result sinks that some properties are true up to some
+  /// depth according to a technique. Outputs it in JSON mode only.
+  pub fn log_k_true(
+    & self, t: & super::Tek, props: & [Sym], at: & Offset
+  ) {
+    self.notify( |sink| sink.k_true(t, props, at) ) ;
+    if self.json {
+      self.json(
+        & [
+          ("event", json_str("k_true")),
+          ("tek", json_str( t.to_str() )),
+          ("props", json_syms( props.iter() )),
+          ("k", format!("{}", at)),
+        ]
+      )
+    }
+  }
+
+  /// Logs the memory usage reported by a technique.
+  pub fn log_stats(& self, t: & super::Tek, usage: & [MemUsage]) {
+    self.notify( |sink| sink.stats(t, usage) ) ;
+    if self.quiet {
+      let mut mem = "[".to_string() ;
+      for u in usage.iter() {
+        if mem.len() > 1 { mem.push(',') }
+        mem.push_str(
+          & format!(
+            "{{\"name\":{},\"entries\":{},\"bytes\":{}}}",
+            json_str(& u.name), u.entries, u.bytes
+          )
+        )
+      }
+      mem.push(']') ;
+      return self.json(
+        & [
+          ("event", json_str("stats")),
+          ("tek", json_str( t.to_str() )),
+          ("memory", mem),
+        ]
+      )
+    }
+    let mut blah = "memory usage:".to_string() ;
+    for u in usage.iter() {
+      blah.push_str( & format!("\n  {}", u) )
+    }
+    self.log(t, & blah)
+  }
+
+  /// Logs the crash of a technique, and the path of its crash report if it
+  /// could be written.
+  pub fn log_crash(& self, crash: & Crash, report: Option<& str>) {
+    self.notify( |sink| sink.error(& crash.tek, & format!("{}", crash)) ) ;
+    let opt = |s: Option<& str>| s.map(json_str).unwrap_or("null".to_string()) ;
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("crash")),
+          ("tek", json_str( crash.tek.to_str() )),
+          ("msg", json_str(& crash.msg)),
+          ("location", opt( crash.location.as_ref().map(|s| s.as_str()) )),
+          ("depth", opt( crash.depth.as_ref().map(|s| s.as_str()) )),
+          ("query", opt( crash.query.as_ref().map(|s| s.as_str()) )),
+          ("report", opt(report)),
+        ]
+      )
+    }
+    let mut blah = format!("{}", crash) ;
+    if let Some(ref depth) = crash.depth {
+      blah.push_str( & format!("\nafter depth {}", depth) )
+    }
+    if let Some(report) = report {
+      blah.push_str( & format!("\ncrash report written to {}", report) )
+    }
+    self.print_bad(& crash.tek, & blah)
+  }
+
+  /// Logs the number of `unknown` answers the solvers of the techniques
+  /// gave during the analysis.
+  pub fn log_solver_unknowns(& self, unknowns: & [(super::Tek, usize)]) {
+    if unknowns.is_empty() { return () }
+    if self.quiet {
+      let mut counts = "{".to_string() ;
+      for & (ref t, count) in unknowns.iter() {
+        if counts.len() > 1 { counts.push(',') }
+        counts.push_str(
+          & format!("{}:{}", json_str( t.to_str() ), count)
+        )
+      }
+      counts.push('}') ;
+      return self.json(
+        & [
+          ("event", json_str("solver_unknowns")),
+          ("counts", counts),
+        ]
+      )
+    }
+    for & (ref t, count) in unknowns.iter() {
+      self.log(
+        t, & format!("the solver answered `unknown` {} time(s)", count)
+      )
+    }
+  }
+
+  /// Logs the properties whose certificates were checked again with a
+  /// second solver, and the ones the second solver did not confirm.
+  pub fn log_double_checked(
+    & self, solver: & str, checked: & [Sym], failed: & [Sym]
+  ) {
+    if checked.is_empty() && failed.is_empty() { return () }
+    if self.quiet {
+      return self.json(
+        & [
+          ("event", json_str("double_checked")),
+          ("solver", json_str(solver)),
+          ("checked", json_syms( checked.iter() )),
+          ("failed", json_syms( failed.iter() )),
+        ]
+      )
+    }
+    if ! checked.is_empty() {
+      self.happy(
+        & super::Tek::Kino, & format!(
+          "{} property(ies) double-checked with {}", checked.len(), solver
+        )
+      )
+    }
+    for prop in failed.iter() {
+      self.bad(
+        & super::Tek::Kino, & format!(
+          "the certificate of {} is not double-checked by {}", prop, solver
+        )
+      )
+    }
+  }
+
+  /// Logs an event of the evolution of the equivalence graph of invariant
+  /// generation.
+  pub fn log_graph(& self, t: & super::Tek, event: & GraphEvent) {
+    let term = |term: & Term| json_str( & format!("{}", term) ) ;
+    if self.quiet {
+      let fields = match * event {
+        GraphEvent::Stable(iteration, ref classes) => {
+          let mut json = "[".to_string() ;
+          for & (ref rep, size, ref kids) in classes.iter() {
+            if json.len() > 1 { json.push(',') }
+            let mut kids_json = "[".to_string() ;
+            for kid in kids.iter() {
+              if kids_json.len() > 1 { kids_json.push(',') }
+              kids_json.push_str( & term(kid) )
+            }
+            kids_json.push(']') ;
+            json.push_str(
+              & format!(
+                "{{\"rep\":{},\"size\":{},\"kids\":{}}}",
+                term(rep), size, kids_json
+              )
+            )
+          }
+          json.push(']') ;
+          vec![
+            ("kind", json_str("stable")),
+            ("iteration", format!("{}", iteration)),
+            ("classes", json),
+          ]
+        },
+        GraphEvent::Split(ref rep, ref chain) => {
+          let mut json = "[".to_string() ;
+          for & (ref val, ref rep, size) in chain.iter() {
+            if json.len() > 1 { json.push(',') }
+            json.push_str(
+              & format!(
+                "{{\"value\":{},\"rep\":{},\"size\":{}}}",
+                json_str(val), term(rep), size
+              )
+            )
+          }
+          json.push(']') ;
+          vec![
+            ("kind", json_str("split")),
+            ("rep", term(rep)),
+            ("chain", json),
+          ]
+        },
+        GraphEvent::Dropped(ref rep, ref dropped) => vec![
+          ("kind", json_str("dropped")),
+          ("rep", term(rep)),
+          ("term", term(dropped)),
+        ],
+      } ;
+      let mut all = vec![
+        ("event", json_str("graph")),
+        ("tek", json_str( t.to_str() )),
+      ] ;
+      all.extend(fields) ;
+      return self.json(& all)
+    }
+    let blah = match * event {
+      GraphEvent::Stable(iteration, ref classes) => {
+        let mut blah = format!(
+          "graph stable at {}, {} classes:", iteration, classes.len()
+        ) ;
+        for & (ref rep, size, ref kids) in classes.iter() {
+          blah.push_str( & format!("\n  {} ({} members)", rep, size) ) ;
+          for kid in kids.iter() {
+            blah.push_str( & format!("\n    -> {}", kid) )
+          }
+        }
+        blah
+      },
+      GraphEvent::Split(ref rep, ref chain) => {
+        let mut blah = format!(
+          "class of {} split into {} classes:", rep, chain.len()
+        ) ;
+        for & (ref val, ref rep, size) in chain.iter() {
+          blah.push_str(
+            & format!("\n  {}: {} ({} members)", val, rep, size)
+          )
+        }
+        blah
+      },
+      GraphEvent::Dropped(ref rep, ref dropped) => format!(
+        "{} dropped from the class of {}", dropped, rep
+      ),
+    } ;
+    self.log(t, & blah)
+  }
+
+  /// Logs the time spent at each depth by the techniques, per property.
+  pub fn log_curves(& self, curves: & Curves) {
+    let mut props: Vec<& Sym> = curves.keys().collect() ;
+    props.sort_by( |lhs, rhs| lhs.get().sym().cmp( rhs.get().sym() ) ) ;
+    if self.quiet {
+      let mut json = "{".to_string() ;
+      for prop in props.into_iter() {
+        if json.len() > 1 { json.push(',') }
+        json.push_str( & format!("{}:[", json_str( & format!("{}", prop) )) ) ;
+        for (index, & (ref t, ref o, time)) in curves[prop].iter().enumerate() {
+          if index > 0 { json.push(',') }
+          json.push_str(
+            & format!(
+              "{{\"tek\":{},\"k\":{},\"time\":{}}}",
+              json_str( t.to_str() ), o, json_duration(time)
+            )
+          )
+        }
+        json.push(']')
+      }
+      json.push('}') ;
+      return self.json(
+        & [
+          ("event", json_str("curves")),
+          ("props", json),
+        ]
+      )
+    }
+    let mut blah = "time per depth:".to_string() ;
+    for prop in props.into_iter() {
+      blah.push_str( & format!("\n  {}:", prop) ) ;
+      for & (ref t, ref o, time) in curves[prop].iter() {
+        blah.push_str(
+          & format!("\n    {} at {}: {}", t, o, fmt_duration(time))
+        )
+      }
+    }
+    self.log(& super::Tek::Kino, & blah)
+  }
+
   /// Logs a cex for some properties.
   pub fn log_cex(
     & self, t: & super::Tek, cex: & Cex, props: & [Sym]
   ) {
+    let approx ;
+    let cex = match self.precision {
+      Some(precision) => {
+        approx = cex.clone().with_precision( Some(precision) ) ;
+        & approx
+      },
+      None => cex,
+    } ;
+    self.notify( |sink| sink.falsified(t, props, cex) ) ;
+    if self.quiet {
+      let mut vmt: Vec<u8> = Vec::with_capacity(100) ;
+      let res = if self.full_cex {
+        cex.write_vmt(props, & mut vmt)
+      } else {
+        cex.write_vmt_compressed(props, & mut vmt)
+      } ;
+      let vmt = match res {
+        Ok(()) => json_str( & String::from_utf8_lossy(& vmt) ),
+        Err(_) => "null".to_string(),
+      } ;
+      let loops: Vec<String> = cex.loops().into_iter().map(
+        |(from, to, period)| format!(
+          "{{\"from\":{},\"to\":{},\"period\":{}}}", from, to, period
+        )
+      ).collect() ;
+      let mut fields = vec![
+        ("event", json_str("falsified")),
+        ("tek", json_str( t.to_str() )),
+        ("props", json_syms( props.iter() )),
+        ("length", format!("{}", cex.len())),
+        ("loops", format!("[{}]", loops.join(","))),
+        ("cex", vmt),
+      ] ;
+      if self.json {
+        fields.push( ("trace", cex.to_json(! self.full_cex)) )
+      }
+      return self.json(& fields)
+    }
     let pref = format!(
       "{} {}", self.fmt.ppre(), self.mk_bad(self.fmt.pref())
     ) ;
     println!(
-      "{} {} falsified {} propertie(s) at {}:",
+      "{} {} falsified {} property(ies) at {}:",
       pref, self.emph(t.to_str()), props.len(), cex.len()
     ) ;
     for prop in props.iter() {
       println!("{}   {}", pref, self.mk_bad(prop.sym())) ;
     } ;
     println!("{} {}:", pref, self.mk_emph("cex")) ;
-    // for line in cex.format().lines() {
-    //   println!("{}   {}", pref, line)
-    // } ;
-    println!("{}", pref) ;
-    cex.print_vmt(props) ;
-    // cex.write_vmt(props, & mut stdout()).expect(
-    //   "could not write counterexample to stdout"
-    // ) ;
+    for line in cex.table(! self.full_cex).into_iter() {
+      println!("{}   {}", pref, line)
+    } ;
+    self.nl()
+  }
+
+  /// Logs assumptions on the inputs of a system that would rule out a
+  /// counterexample, as property definitions usable in a `verify-assuming`.
+  pub fn log_suggestions(
+    & self, t: & super::Tek, sys: & Sym, props: & [Sym],
+    suggestions: & [String]
+  ) {
+    if self.quiet {
+      let list: Vec<String> = suggestions.iter().map(
+        |s| json_str(s)
+      ).collect() ;
+      return self.json(
+        & [
+          ("event", json_str("suggestions")),
+          ("tek", json_str( t.to_str() )),
+          ("sys", json_str( & format!("{}", sys) )),
+          ("props", json_syms( props.iter() )),
+          ("assumptions", format!("[{}]", list.join(","))),
+        ]
+      )
+    }
+    let pref = format!("{} {}", self.fmt.ppre(), self.fmt.pref()) ;
+    println!(
+      "{} {} assumption(s) on the inputs ruling out this cex:",
+      pref, self.mk_emph("candidate")
+    ) ;
+    for (index, suggestion) in suggestions.iter().enumerate() {
+      println!(
+        "{}   (define-prop {}_assume_{} {} {})",
+        pref, sys, index, sys, suggestion
+      )
+    }
     self.nl()
   }
 }
\ No newline at end of file
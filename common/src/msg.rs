@@ -11,22 +11,74 @@
 
 use std::fmt ;
 use std::thread ;
+use std::panic::{ self, AssertUnwindSafe } ;
+use std::cell::Cell ;
+use std::time::{ Duration, Instant } ;
 use std::sync::mpsc ;
-use std::sync::mpsc::{ Sender, Receiver, TryRecvError } ;
-use std::collections::HashMap ;
+use std::sync::atomic::{ AtomicUsize, Ordering } ;
+use std::sync::mpsc::{
+  Sender, Receiver, TryRecvError, RecvTimeoutError
+} ;
+use std::collections::{ HashMap, HashSet } ;
 
 use std::sync::Arc ;
 
 use term::{
-  Offset, Sym, Factory, Model, STermSet
+  Offset, Sym, Term, Factory, Model, STermSet, MemUsage
 } ;
 
 use sys::{ Prop, Sys } ;
 
 use ::{ Tek, CanRun } ;
+use samples::{ Sample, SamplePool, DEFAULT_CAPACITY } ;
+use clock::Clock ;
+use crash ;
+use crash::Crash ;
 
 use errors::* ;
 
+/// Budget of `check-sat`s shared by all the techniques.
+#[derive(Clone)]
+pub struct QueryBudget {
+  /// Number of `check-sat`s issued so far.
+  used: Arc<AtomicUsize>,
+  /// Maximum number of `check-sat`s.
+  max: Option<usize>,
+}
+impl QueryBudget {
+  /// Creates a budget, unbounded if `max` is `None`.
+  pub fn mk(max: Option<usize>) -> Self {
+    QueryBudget { used: Arc::new( AtomicUsize::new(0) ), max: max }
+  }
+  /// Number of `check-sat`s issued so far.
+  #[inline]
+  pub fn used(& self) -> usize {
+    self.used.load(Ordering::SeqCst)
+  }
+  /// A budget sharing the count of this one, exhausted after `max` more
+  /// `check-sat`s or when this one is.
+  pub fn sub(& self, max: usize) -> Self {
+    let max = self.used() + max ;
+    QueryBudget {
+      used: self.used.clone(),
+      max: Some( self.max.map_or(max, |m| ::std::cmp::min(m, max)) ),
+    }
+  }
+  /// True iff the budget is exhausted.
+  #[inline]
+  pub fn is_exhausted(& self) -> bool {
+    self.max.map_or(false, |max| self.used() >= max)
+  }
+  /// Counts a `check-sat`. Returns `false` if the budget is exhausted.
+  fn spend(& self) -> bool {
+    let used = self.used.fetch_add(1, Ordering::SeqCst) ;
+    match self.max {
+      Some(max) => used < max,
+      None => true,
+    }
+  }
+}
+
 /// Wrapper around master and kids receive and send channels.
 pub struct KidManager {
   /// Receives messages from kids.
@@ -35,12 +87,30 @@ pub struct KidManager {
   s: Sender<MsgUp>,
   /// Senders to running techniques.
   senders: HashMap<Tek, mpsc::Sender<MsgDown>>,
+  /// Properties each running technique works on.
+  props: HashMap<Tek, HashSet<Sym>>,
+  /// Budget of `check-sat`s of the kids.
+  budget: QueryBudget,
+  /// Time budget of the kids.
+  clock: Clock,
 }
 impl KidManager {
   /// Constructs a kid manager.
   pub fn mk() -> Self {
+    Self::mk_with_budget( QueryBudget::mk(None) )
+  }
+  /// Constructs a kid manager whose kids share a budget of `check-sat`s.
+  pub fn mk_with_budget(budget: QueryBudget) -> Self {
     let (sender, receiver) = mpsc::channel() ;
-    KidManager { r: receiver, s: sender, senders: HashMap::new() }
+    KidManager {
+      r: receiver, s: sender, senders: HashMap::new(),
+      props: HashMap::new(), budget: budget, clock: Clock::mk(None)
+    }
+  }
+  /// Sets the time budget of the kids.
+  pub fn with_clock(mut self, clock: Clock) -> Self {
+    self.clock = clock ;
+    self
   }
   /// Launches a technique.
   pub fn launch<
@@ -50,29 +120,50 @@ impl KidManager {
   ) -> Res<()> {
     let (s,r) = mpsc::channel() ;
     let id = t.id() ;
+    let syms = props.iter().map(
+      |prop| prop.sym().get().clone()
+    ).collect() ;
     let event = Event::mk(
       self.s.clone(), r, t.id().clone(), f.clone(), & props
-    ) ;
+    ).with_budget( self.budget.clone() ).with_clock(self.clock) ;
     match self.senders.get( & id ) {
       None => (),
       Some(_) => bail!(
         ErrorKind::TekDuplicateError(id)
       ),
     }
+    // Sends the crash of the technique upwards if it panics.
+    let up = self.s.clone() ;
     match thread::Builder::new().name( id.thread_name() ).spawn(
-      move || t.run(conf, sys, props, event)
+      move || {
+        crash::enter(id) ;
+        let run = panic::catch_unwind(
+          AssertUnwindSafe( move || t.run(conf, sys, props, event) )
+        ) ;
+        if run.is_err() {
+          if let Some(crash) = crash::take() {
+            let _ = up.send( MsgUp::Crashed(crash) ) ;
+          }
+        }
+      }
     ) {
       Ok(_) => (),
       Err(e) => bail!(
         ErrorKind::TekSpawnError(e, id)
       ),
     } ;
+    self.props.insert(id, syms) ;
     match self.senders.insert(id, s) {
       None => Ok(()),
       Some(_) => unreachable!(),
     }
   }
 
+  /// A sender of messages to kino, for the services running along the
+  /// kids.
+  #[inline]
+  pub fn sender(& self) -> Sender<MsgUp> { self.s.clone() }
+
   /// Broadcasts a message to the kids.
   #[inline(always)]
   pub fn broadcast(& self, msg: MsgDown) {
@@ -93,14 +184,105 @@ impl KidManager {
       || ErrorKind::MsgRcvError(Tek::Kino)
     )
   }
+  /// Receive a message from the kids before the time budget is exhausted,
+  /// `None` if it is.
+  pub fn recv_in_time(& self) -> Res< Option<MsgUp> > {
+    match self.clock.left() {
+      None => self.recv().map(Some),
+      Some(left) => match self.r.recv_timeout(left) {
+        Ok(msg) => Ok( Some(msg) ),
+        Err(RecvTimeoutError::Timeout) => Ok(None),
+        Err(RecvTimeoutError::Disconnected) => bail!(
+          ErrorKind::MsgRcvError(Tek::Kino)
+        ),
+      },
+    }
+  }
   /// Forget a kid.
   #[inline(always)]
   pub fn forget(& mut self, t: & Tek) -> Res<()> {
+    self.props.remove(t) ;
     match self.senders.remove(t) {
       Some(_) => Ok(()),
       None => bail!( ErrorKind::TekUnknownError(* t) ),
     }
   }
+  /// Tells the kids working on some properties to forget them, the other
+  /// kids are not bothered.
+  ///
+  /// Kids checking properties (see
+  /// [`Tek::checks_props`](../enum.Tek.html#method.checks_props)) left with
+  /// nothing to work on are reassigned to `cluster` instead of waiting for
+  /// their current loop to end. They receive the new properties before the
+  /// `Forget` message, so that they do not stop in between. Returns the kids
+  /// reassigned.
+  pub fn forget_props(
+    & mut self, props: & [Sym], status: Status, cluster: & [Prop]
+  ) -> Vec<Tek> {
+    let mut reassigned = vec![] ;
+    for (tek, kid_props) in self.props.iter_mut() {
+      let forgotten: Vec<Sym> = props.iter().filter(
+        |prop| kid_props.remove(prop)
+      ).cloned().collect() ;
+      if forgotten.is_empty() { continue }
+      let sender = match self.senders.get(tek) {
+        Some(sender) => sender,
+        None => continue,
+      } ;
+      // Failing to send means the technique already exited.
+      if kid_props.is_empty() && tek.checks_props() && ! cluster.is_empty() {
+        if sender.send( MsgDown::NewProps( cluster.to_vec() ) ).is_ok() {
+          for prop in cluster.iter() {
+            kid_props.insert( prop.sym().get().clone() ) ; ()
+          }
+          reassigned.push(* tek)
+        }
+      }
+      let _ = sender.send( MsgDown::Forget(forgotten, status) ) ;
+    }
+    reassigned
+  }
+  /// Sends some new properties to the kids checking properties, see
+  /// [`Tek::checks_props`](../enum.Tek.html#method.checks_props).
+  pub fn add_props(& mut self, props: & [Prop]) {
+    for (tek, kid_props) in self.props.iter_mut() {
+      if ! tek.checks_props() { continue }
+      let sender = match self.senders.get(tek) {
+        Some(sender) => sender,
+        None => continue,
+      } ;
+      // Failing to send means the technique already exited.
+      if sender.send( MsgDown::NewProps( props.to_vec() ) ).is_ok() {
+        for prop in props.iter() {
+          kid_props.insert( prop.sym().get().clone() ) ; ()
+        }
+      }
+    }
+  }
+  /// Pauses the kids at their next query boundary.
+  #[inline]
+  pub fn pause(& self) {
+    self.broadcast(MsgDown::Pause)
+  }
+  /// Resumes paused kids.
+  #[inline]
+  pub fn resume(& self) {
+    self.broadcast(MsgDown::Resume)
+  }
+  /// Sends their new maximum number of check-sats to the kids,
+  /// `max_queries` gives it for a technique, `None` if it has none to
+  /// update.
+  pub fn reload<F: Fn(& Tek) -> Option< Option<usize> >>(
+    & self, max_queries: F
+  ) {
+    for (tek, sender) in self.senders.iter() {
+      if let Some(max) = max_queries(tek) {
+        // Failing to send means the technique already exited.
+        let _ = sender.send( MsgDown::Reload(max) ) ;
+      }
+    }
+  }
+
   /// True iff there's no more kids known by the manager.
   #[inline(always)]
   pub fn kids_done(& self) -> bool { self.senders.is_empty() }
@@ -149,6 +331,13 @@ pub enum Info {
   /// Typical techniques unroll the system, this communicates the number of
   /// unrollings.
   At(Offset),
+  /// No new invariants were discovered for a while, stopped at some
+  /// unrolling. Invariant generation is stopped too, the other techniques
+  /// keep running.
+  Fixpoint(Offset),
+  /// Reached the maximum unrolling allowed by the `max` option, stopped
+  /// after unrolling at some offset.
+  MaxReached(Offset),
   /// An error occurred.
   Error,
 }
@@ -156,31 +345,148 @@ impl fmt::Display for Info {
   fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
     match * self {
       Info::At(ref o) => write!(fmt, "at {}", o),
+      Info::Fixpoint(ref o) => write!(fmt, "at fixpoint at {}", o),
+      Info::MaxReached(ref o) => write!(fmt, "at maximum unrolling {}", o),
       Info::Error => write!(fmt, "error"),
     }
   }
 }
 
-/// Status of a property.
-#[derive(Debug, Clone)]
+/// Why a technique stops without (dis)proving the properties it checks.
+///
+/// Sent with [`Event::unknown`](struct.Event.html#method.unknown), kino
+/// reports them for the properties left unknown at the end of the analysis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unknown {
+  /// The technique reached its maximum unrolling.
+  Bound(Offset),
+  /// The technique exhausted its budget of `check-sat`s, after that many.
+  Queries(usize),
+  /// The technique ran out of time, during the query described.
+  Timeout(String),
+  /// The solver answered `unknown`, for the reason it gave.
+  Solver(String),
+  /// The technique is disabled, or cannot run on the system.
+  Disabled(String),
+  /// The technique could not refine its abstraction, for the reason given.
+  Abstraction(String),
+  /// The technique panicked, with this message.
+  Crash(String),
+  /// The technique and invariant generation were stopped because no new
+  /// invariants were discovered for a while, at some unrolling.
+  Fixpoint(Offset),
+}
+impl fmt::Display for Unknown {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    match * self {
+      Unknown::Bound(ref o) => write!(fmt, "reached its maximum bound {}", o),
+      Unknown::Queries(n) => write!(
+        fmt, "exhausted its query budget after {} check-sats", n
+      ),
+      Unknown::Timeout(ref query) => write!(
+        fmt, "ran out of time while {}", query
+      ),
+      Unknown::Solver(ref reason) => write!(
+        fmt, "solver answered unknown: {}", reason
+      ),
+      Unknown::Disabled(ref why) => write!(fmt, "disabled, {}", why),
+      Unknown::Abstraction(ref why) => write!(
+        fmt, "could not refine its abstraction, {}", why
+      ),
+      Unknown::Crash(ref msg) => write!(fmt, "crashed: {}", msg),
+      Unknown::Fixpoint(ref o) => write!(
+        fmt, "reached a fixpoint at {}, no new invariants were discovered", o
+      ),
+    }
+  }
+}
+
+/// Status of a property, used as the reason why it is forgotten.
+///
+/// Proved properties are invariants and techniques can keep them as
+/// strengthening assertions. Disproved properties should be dropped
+/// completely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Status {
   /// Property was proved.
   Proved,
   /// Property was disproved.
   Disproved,
 }
+impl Status {
+  /// True iff the status is `Proved`.
+  #[inline]
+  pub fn is_proved(& self) -> bool {
+    * self == Status::Proved
+  }
+}
+impl fmt::Display for Status {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    match * self {
+      Status::Proved => write!(fmt, "proved"),
+      Status::Disproved => write!(fmt, "disproved"),
+    }
+  }
+}
 
 /// Message from kino to the techniques.
+///
+/// The configurations of the techniques are read at launch time. Options
+/// changed with `--reload` apply from the next system verified, but for the
+/// budgets of check-sats which `Reload` updates while the techniques run.
 #[derive(Debug, Clone)]
 pub enum MsgDown {
   /// Contains invariants for a system.
   Invariants(Sym, STermSet),
   /// Invariant pruning job.
   InvariantPruning(Tek, Sym, STermSet, Option<usize>),
-  /// Some properties have been proved or disproved.
+  /// Some properties have been proved or disproved, the status says which.
   Forget(Vec<Sym>, Status),
   /// Some properties were found k-true.
   KTrue(Vec<Sym>, Offset),
+  /// New properties to check, added while the techniques are running.
+  NewProps(Vec<Prop>),
+  /// Techniques should stop issuing queries until they receive `Resume`.
+  /// Sent when the operator asks, see the [`pause`](../pause/index.html)
+  /// module.
+  ///
+  /// Handled by [`Event::recv`](struct.Event.html#method.recv), so
+  /// techniques pause at their next query boundary and keep their state.
+  Pause,
+  /// Techniques can issue queries again.
+  Resume,
+  /// Concrete reachable states, added to the
+  /// [sample pool](struct.Event.html#method.samples) of the techniques by
+  /// `Event::recv`.
+  Reachable(Vec<Sample>),
+  /// Invariants of a system falsified by a reachable state.
+  ///
+  /// Handled by [`Event::recv`](struct.Event.html#method.recv), which drops
+  /// them from the invariants the technique receives afterwards.
+  Retract(Sym, STermSet),
+  /// New maximum number of check-sats of the technique, sent when the
+  /// `--reload` file changes.
+  ///
+  /// Handled by [`Event::recv`](struct.Event.html#method.recv), so it
+  /// applies from the next query of the technique.
+  Reload(Option<usize>),
+}
+
+/// Evolution of the equivalence graph of invariant generation, sent when
+/// the `graph_events` option of tig is on.
+#[derive(Debug, Clone)]
+pub enum GraphEvent {
+  /// The graph once stable at some iteration: the iteration, and the
+  /// classes as their representative, their number of members and the
+  /// representatives of their kids.
+  Stable(usize, Vec<(Term, usize, Vec<Term>)>),
+  /// A class was split: its representative, and the chain it was split into
+  /// as the value, the representative and the number of members of each
+  /// class, by decreasing value.
+  Split(Term, Vec<(String, Term, usize)>),
+  /// A term proved equal to the representative of its class, dropped from
+  /// it: the representative and the term.
+  Dropped(Term, Term),
 }
 
 /// Message from the techniques to kino.
@@ -219,6 +525,40 @@ pub enum MsgUp {
   Proved(Vec<Sym>, Tek, Offset),
   /// Some properties were falsified.
   Disproved(Model, Vec<Sym>, Tek, Info),
+  /// Statistics: approximate memory usage of the term factory and of the
+  /// data structures of a technique.
+  Stats(Tek, Vec<MemUsage>),
+  /// Concrete reachable states.
+  Reachable(Tek, Vec<Sample>),
+  /// A technique is done with an unrolling depth: properties checked at
+  /// this depth, and time spent on it.
+  Depth(Tek, Vec<Sym>, Offset, Duration),
+  /// Evolution of the equivalence graph of invariant generation.
+  Graph(Tek, GraphEvent),
+  /// A technique stops without (dis)proving the properties it checks.
+  Unknown(Tek, Unknown),
+  /// Number of `unknown` answers the solver of a technique gave so far.
+  SolverUnknowns(Tek, usize),
+  /// Invariants of a system falsified by a reachable state, sent by the
+  /// [falsifier](../falsify/index.html).
+  Falsified(Sym, STermSet),
+  /// A technique panicked, see the [`crash`](../crash/index.html) module.
+  Crashed(Crash),
+  /// Outcome of the certificate check of some properties proved by a
+  /// technique at some offset, in paranoid mode: `true` if it confirms the
+  /// proof.
+  Certified(Vec<Sym>, Tek, Offset, bool),
+  /// The operator asks to pause the techniques, see the
+  /// [`pause`](../pause/index.html) module.
+  Pause,
+  /// The operator asks to resume the techniques.
+  Resume,
+  /// Properties to add to the analysis running, see
+  /// [`pause::add_props`](../pause/fn.add_props.html).
+  NewProps(Vec<Prop>),
+  /// The `--reload` file changed, see
+  /// [`pause::watch`](../pause/fn.watch.html).
+  Reload,
 }
 impl fmt::Display for MsgUp {
   fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
@@ -270,15 +610,34 @@ impl fmt::Display for MsgUp {
       KTrue(_, _, ref t, _) => write!(fmt, "KTrue({})", t),
       Proved(_, ref t, _) => write!(fmt, "Proved({})", t),
       Disproved(_, _, ref t, _) => write!(fmt, "Disproved({})", t),
+      Stats(ref t, _) => write!(fmt, "Stats({})", t),
+      Reachable(ref t, ref samples) => write!(
+        fmt, "Reachable({}, {})", t, samples.len()
+      ),
+      Depth(ref t, _, ref o, _) => write!(fmt, "Depth({}, {})", t, o),
+      Graph(ref t, _) => write!(fmt, "Graph({})", t),
+      Unknown(ref t, ref why) => write!(fmt, "Unknown({}, {})", t, why),
+      SolverUnknowns(ref t, n) => write!(fmt, "SolverUnknowns({}, {})", t, n),
+      Falsified(ref sym, ref invs) => write!(
+        fmt, "Falsified({}, {})", sym, invs.len()
+      ),
+      Crashed(ref crash) => write!(fmt, "Crashed({})", crash.tek),
+      Certified(_, ref t, ref o, ok) => write!(
+        fmt, "Certified({}, {}, {})", t, o, ok
+      ),
+      Pause => write!(fmt, "Pause"),
+      Resume => write!(fmt, "Resume"),
+      NewProps(ref props) => write!(fmt, "NewProps({})", props.len()),
+      Reload => write!(fmt, "Reload"),
     }
   }
 }
 
-/// Exits with code `0`.
-fn exit<T>(_: T) {
-  use std::process::exit ;
-  exit(0)
-}
+/// Ignores a message kino did not receive: its analysis is over, the
+/// technique stops at its next [`recv`](struct.Event.html#method.recv).
+/// Exiting instead would end the whole process, and the other analyses it
+/// runs.
+fn hung_up<T>(_: T) {}
 
 /// Used by the techniques to communicate with kino.
 pub struct Event {
@@ -292,6 +651,22 @@ pub struct Event {
   f: Factory,
   /// K-true properties.
   k_true: HashMap<Sym, Option<Offset>>,
+  /// Budget of `check-sat`s shared with the other techniques.
+  budget: QueryBudget,
+  /// Number of `check-sat`s issued by the technique.
+  queries: Cell<usize>,
+  /// Maximum number of `check-sat`s of the technique.
+  max_queries: Option<usize>,
+  /// Concrete reachable states known by the technique.
+  samples: SamplePool,
+  /// Start of the current unrolling depth.
+  depth_start: Cell<Instant>,
+  /// Time budget shared with the other techniques.
+  clock: Clock,
+  /// Number of `unknown` answers of the solver last sent upwards.
+  unknowns: Cell<usize>,
+  /// Invariants retracted by kino, by system.
+  retracted: HashMap<Sym, STermSet>,
 }
 impl Event {
   /// Creates a new `Event`.
@@ -306,7 +681,89 @@ impl Event {
         Some(_) => unreachable!(),
       }
     } ;
-    Event { s: s, r: r, t: t, f: f, k_true: k_true }
+    Event {
+      s: s, r: r, t: t, f: f, k_true: k_true,
+      budget: QueryBudget::mk(None), queries: Cell::new(0), max_queries: None,
+      samples: SamplePool::mk(DEFAULT_CAPACITY),
+      depth_start: Cell::new( Instant::now() ),
+      clock: Clock::mk(None),
+      unknowns: Cell::new(0),
+      retracted: HashMap::new(),
+    }
+  }
+  /// Sets the budget of `check-sat`s shared with the other techniques.
+  pub fn with_budget(mut self, budget: QueryBudget) -> Self {
+    self.budget = budget ;
+    self
+  }
+  /// Sets the time budget shared with the other techniques.
+  pub fn with_clock(mut self, clock: Clock) -> Self {
+    self.clock = clock ;
+    self
+  }
+  /// Time budget shared with the other techniques.
+  #[inline]
+  pub fn clock(& self) -> & Clock { & self.clock }
+  /// Sets the maximum number of `check-sat`s of the technique.
+  pub fn set_max_queries(& mut self, max: Option<usize>) {
+    self.max_queries = max
+  }
+
+  /// Counts a `check-sat` against the budget of the technique and the one
+  /// shared with the other techniques. Returns `false` if one of them is
+  /// exhausted, in which case the technique should not issue the query and
+  /// call [`out_of_queries`](#method.out_of_queries).
+  pub fn query(& self) -> bool {
+    if let Some(max) = self.max_queries {
+      if self.queries.get() >= max { return false }
+    }
+    if ! self.budget.spend() { return false }
+    self.queries.set( self.queries.get() + 1 ) ;
+    true
+  }
+  /// Number of `check-sat`s issued by the technique.
+  #[inline]
+  pub fn queries(& self) -> usize { self.queries.get() }
+  /// Reports that the technique stops because its budget of `check-sat`s is
+  /// exhausted, the properties left are unknown. Sends a done message at the
+  /// bound reached.
+  pub fn out_of_queries(& self, o: & Offset) {
+    self.warning(
+      & format!(
+        "query budget exhausted after {} check-sats, stopping at {}",
+        self.queries.get(), o
+      )
+    ) ;
+    self.unknown( Unknown::Queries( self.queries.get() ) ) ;
+    self.done_at(o)
+  }
+  /// Reports that the technique stops before `query` because it cannot end
+  /// before the deadline, the properties left are unknown. Sends a done
+  /// message at the bound reached.
+  pub fn out_of_time(& self, o: & Offset, query: & str) {
+    self.warning(
+      & format!(
+        "out of time after {}, stopping at {} before {}",
+        ::log::fmt_duration( self.clock.elapsed() ), o, query
+      )
+    ) ;
+    self.unknown( Unknown::Timeout( query.to_string() ) ) ;
+    self.done_at(o)
+  }
+  /// Reports that the technique stops because it reached the maximum
+  /// unrolling `o` allowed by its `max` option, the properties left are
+  /// unknown.
+  pub fn max_reached(& self, o: & Offset) {
+    self.unknown( Unknown::Bound( o.clone() ) ) ;
+    self.done( Info::MaxReached( o.clone() ) )
+  }
+  /// Reports why the technique stops without (dis)proving the properties it
+  /// checks. Errors caused by an `unknown` answer of the solver report it
+  /// on their own.
+  pub fn unknown(& self, why: Unknown) {
+    self.s.send(
+      MsgUp::Unknown(self.t, why)
+    ).unwrap_or_else( hung_up )
   }
 
   /// The technique this event manager belongs to.
@@ -322,27 +779,27 @@ impl Event {
   ) {
     self.s.send(
       MsgUp::PrunedInvariants(self.t, tek, sys.clone(), invs, old_card, info)
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
 
   /// Sends an invariant message upwards.
   pub fn invariants(& self, sys: & Sym, invs: STermSet) {
     self.s.send(
       MsgUp::Invariants(self.t, sys.clone(), invs, None)
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
   /// Sends an invariant message upwards, with a notion of offset.
   pub fn invariants_at(& self, sys: & Sym, invs: STermSet, at: usize) {
     self.s.send(
       MsgUp::Invariants(self.t, sys.clone(), invs, Some(at))
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
 
   /// Sends a done message upwards.
   pub fn done(& self, info: Info) {
     self.s.send(
       MsgUp::Done(self.t, info)
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
   /// Sends a done message upwards.
   pub fn done_at(& self, o: & Offset) {
@@ -352,7 +809,7 @@ impl Event {
   pub fn proved(& self, props: Vec<Sym>, info: Offset) {
     self.s.send(
       MsgUp::Proved(props, self.t, info)
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
   /// Sends a proved message upwards.
   pub fn proved_at(& self, props: Vec<Sym>, o: & Offset) {
@@ -362,7 +819,7 @@ impl Event {
   pub fn disproved(& self, model: Model, props: Vec<Sym>, info: Info) {
     self.s.send(
       MsgUp::Disproved(model, props, self.t, info)
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
   /// Sends a falsification message upwards.
   pub fn disproved_at(& self, model: Model, props: Vec<Sym>, o: & Offset) {
@@ -372,25 +829,93 @@ impl Event {
   pub fn k_true(& self, props: Vec<Sym>, o: & Offset) {
     self.s.send(
       MsgUp::KTrue(self.t, props, self.t, o.clone())
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
+  }
+  /// Sends the states of a concrete trace upwards, typically a
+  /// counterexample.
+  pub fn reachable(& self, model: & Model) {
+    let samples = SamplePool::states_of(model) ;
+    if samples.is_empty() { return () }
+    self.s.send(
+      MsgUp::Reachable(self.t, samples)
+    ).unwrap_or_else( hung_up )
+  }
+  /// Sends the time spent on an unrolling depth upwards, along with the
+  /// properties checked at this depth. The time is measured from the
+  /// previous call, or from the creation of the event.
+  pub fn depth_done(& self, props: Vec<Sym>, o: & Offset) {
+    let now = Instant::now() ;
+    let time = now - self.depth_start.get() ;
+    self.depth_start.set(now) ;
+    crash::at_depth(o) ;
+    self.s.send(
+      MsgUp::Depth(self.t, props, o.clone(), time)
+    ).unwrap_or_else( hung_up )
+  }
+  /// Sends the number of `unknown` answers the solver gave so far upwards,
+  /// if it changed since the last time.
+  pub fn solver_unknowns(& self, count: usize) {
+    if count == self.unknowns.get() { return () }
+    self.unknowns.set(count) ;
+    self.s.send(
+      MsgUp::SolverUnknowns(self.t, count)
+    ).unwrap_or_else( hung_up )
+  }
+  /// Concrete reachable states received so far.
+  #[inline]
+  pub fn samples(& self) -> & SamplePool {
+    & self.samples
+  }
+  /// Sends the memory usage of some data structures upwards, along with the
+  /// one of the factory.
+  pub fn stats(& self, usage: Vec<MemUsage>) {
+    let mut all = self.f.mem_usage() ;
+    all.extend(usage) ;
+    self.s.send(
+      MsgUp::Stats(self.t, all)
+    ).unwrap_or_else( hung_up )
+  }
+  /// Sends an event of the evolution of the equivalence graph of invariant
+  /// generation upwards.
+  pub fn graph(& self, event: GraphEvent) {
+    self.s.send(
+      MsgUp::Graph(self.t, event)
+    ).unwrap_or_else( hung_up )
   }
   /// Sends a log message upwards.
   pub fn log(& self, s: & str) {
     self.s.send(
       MsgUp::Bla(self.t, s.to_string())
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
-  /// Sends an error upwards.
-  pub fn error(& self, e: ::errors::Error) {
+  /// Sends an error upwards, as is. Techniques use
+  /// [`log_error`](../macro.log_error.html) instead, which adds their
+  /// context.
+  pub fn error<E: Into<::errors::Error>>(& self, e: E) {
+    let e = e.into() ;
+    if let Some(reason) = unknown_reason(& e) {
+      self.unknown( Unknown::Solver(reason) )
+    }
     self.s.send(
       MsgUp::Error(self.t, e)
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
+  }
+  /// Sends an error upwards, chained with the technique and the location it
+  /// was raised at. Usually called as `error_at(e, file!(), line!())`.
+  pub fn error_at<E: Into<::errors::Error>>(
+    & self, e: E, file: & 'static str, line: u32
+  ) {
+    let res: Res<()> = Err( e.into() ) ;
+    match res.chain_err( || ErrorKind::TekContext(self.t, file, line) ) {
+      Err(e) => self.error(e),
+      Ok(()) => unreachable!(),
+    }
   }
   /// Sends a warning upwards.
   pub fn warning(& self, s: & str) {
     self.s.send(
       MsgUp::Warning(self.t, s.to_string())
-    ).unwrap_or_else( exit )
+    ).unwrap_or_else( hung_up )
   }
   /// The factory in an `Event`.
   pub fn factory(& self) -> & Factory {
@@ -405,18 +930,84 @@ impl Event {
     }
   }
   /// Receive messages from the master.
+  ///
+  /// Techniques call this between their queries. If the master asked to
+  /// pause, blocks until it asks to resume. Messages received in the meantime
+  /// are returned once resumed.
+  ///
+  /// Invariants retracted by the master are dropped from the invariants
+  /// received afterwards, and reloaded budgets of check-sats apply from the
+  /// next query.
   pub fn recv(& mut self) -> Option<Vec<MsgDown>> {
     let mut vec = vec![] ;
+    let mut paused = false ;
     loop {
-      match self.r.try_recv() {
-        Ok( MsgDown::KTrue(props, o) ) => {
+      let msg = if paused {
+        match self.r.recv() {
+          Ok(msg) => msg,
+          Err(_) => return None,
+        }
+      } else {
+        match self.r.try_recv() {
+          Ok(msg) => msg,
+          Err( TryRecvError::Empty ) => break,
+          Err( TryRecvError::Disconnected ) => return None,
+        }
+      } ;
+      match msg {
+        MsgDown::KTrue(props, o) => {
           for prop in props {
             self.k_true.insert(prop, Some(o)) ; ()
           }
         },
-        Ok( msg ) => vec.push(msg),
-        Err( TryRecvError::Empty ) => break,
-        Err( TryRecvError::Disconnected ) => return None,
+        MsgDown::NewProps(props) => {
+          for prop in props.iter() {
+            self.k_true.insert(prop.sym().get().clone(), None) ; ()
+          }
+          vec.push( MsgDown::NewProps(props) )
+        },
+        MsgDown::Pause => if ! paused {
+          paused = true ;
+          self.log("paused")
+        },
+        MsgDown::Resume => if paused {
+          paused = false ;
+          self.log("resumed")
+        },
+        MsgDown::Reachable(samples) => {
+          self.samples.add_all(samples) ; ()
+        },
+        MsgDown::Reload(max) => if max != self.max_queries {
+          self.max_queries = max ;
+          self.log(
+            & match max {
+              Some(max) => format!("reloaded, at most {} check-sats", max),
+              None => "reloaded, no limit on check-sats".to_string(),
+            }
+          )
+        },
+        MsgDown::Retract(sym, invs) => {
+          self.warning(
+            & format!(
+              "{} invariant(s) of {} retracted, falsified by a reachable \
+              state", invs.len(), sym
+            )
+          ) ;
+          self.retracted.entry(sym).or_insert_with(
+            STermSet::new
+          ).extend(invs)
+        },
+        MsgDown::Invariants(sym, mut invs) => {
+          if let Some(retracted) = self.retracted.get(& sym) {
+            for inv in retracted.iter() {
+              invs.remove(inv) ; ()
+            }
+          }
+          if ! invs.is_empty() {
+            vec.push( MsgDown::Invariants(sym, invs) )
+          }
+        },
+        msg => vec.push(msg),
       }
     } ;
     Some(vec)
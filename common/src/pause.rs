@@ -0,0 +1,134 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Pausing and resuming the techniques from outside kino, and adding
+//! properties to the analyses running.
+//!
+//! On unix, `SIGUSR1` pauses the techniques and `SIGUSR2` resumes them,
+//! see [`cleanup::install`](../cleanup/fn.install.html), so that an
+//! operator can yield the machine for a while without losing the state of
+//! the analysis.
+//!
+//! The master of an analysis [registers](fn.register.html) the sender of
+//! its manager, [`request`](fn.request.html) sends it a `Pause` or a
+//! `Resume` message that it broadcasts to its kids. They pause at their
+//! next query boundary, see `MsgDown::Pause`. A pause requested before an
+//! analysis starts applies to it, the master checks
+//! [`paused`](fn.paused.html) once its kids are launched.
+//!
+//! [`add_props`](fn.add_props.html) sends properties to the masters the same
+//! way, each one passes those of its system it does not check yet to its
+//! kids as a `MsgDown::NewProps`.
+//!
+//! While an analysis runs, a master [watches](fn.watch.html) the file of
+//! `--reload`. When it changes, the master reads it again and sends their
+//! new budgets of check-sats to its kids as a `MsgDown::Reload`.
+
+use std::fs ;
+use std::thread ;
+use std::time::Duration ;
+use std::sync::{ Arc, Mutex } ;
+use std::sync::mpsc::Sender ;
+use std::sync::atomic::{ AtomicBool, Ordering, ATOMIC_BOOL_INIT } ;
+
+use sys::Prop ;
+
+use msg::MsgUp ;
+
+/// True while a pause is requested.
+static PAUSED: AtomicBool = ATOMIC_BOOL_INIT ;
+
+/// Senders of the masters registered.
+static MASTERS: Mutex< Vec< Sender<MsgUp> > > = Mutex::new( Vec::new() ) ;
+
+/// Applies something to the senders of the masters registered, unless the
+/// lock is poisoned.
+fn with_masters<T, F: FnOnce(& mut Vec< Sender<MsgUp> >) -> T>(
+  f: F
+) -> Option<T> {
+  match MASTERS.lock() {
+    Ok(mut masters) => Some( f(& mut masters) ),
+    Err(_) => None,
+  }
+}
+
+/// True if a pause is requested.
+#[inline]
+pub fn paused() -> bool { PAUSED.load(Ordering::SeqCst) }
+
+/// Registers the sender of the manager of a master, which receives the
+/// requests from now on.
+pub fn register(sender: Sender<MsgUp>) {
+  with_masters( |masters| masters.push(sender) ) ;
+  ()
+}
+
+/// Requests a pause if `pause`, resuming otherwise. The masters whose
+/// analysis is over are forgotten.
+pub fn request(pause: bool) {
+  PAUSED.store(pause, Ordering::SeqCst) ;
+  with_masters(
+    |masters| masters.retain(
+      |sender| sender.send(
+        if pause { MsgUp::Pause } else { MsgUp::Resume }
+      ).is_ok()
+    )
+  ) ;
+  ()
+}
+
+/// Sends some properties to the masters registered. Returns the number of
+/// masters reached, the ones whose analysis is over are forgotten.
+pub fn add_props(props: Vec<Prop>) -> usize {
+  with_masters(
+    |masters| {
+      masters.retain(
+        |sender| sender.send( MsgUp::NewProps( props.clone() ) ).is_ok()
+      ) ;
+      masters.len()
+    }
+  ).unwrap_or(0)
+}
+
+/// Watches a file for a master, stops when dropped.
+pub struct Watch {
+  /// Set when the watch is over.
+  over: Arc<AtomicBool>,
+}
+impl Drop for Watch {
+  fn drop(& mut self) {
+    self.over.store(true, Ordering::SeqCst)
+  }
+}
+
+/// Sends `Reload` to a master each time the modification time of a file
+/// changes, checked every 100 milliseconds until the `Watch` returned is
+/// dropped.
+pub fn watch(path: String, sender: Sender<MsgUp>) -> Watch {
+  let over = Arc::new( AtomicBool::new(false) ) ;
+  let is_over = over.clone() ;
+  let modified = move || fs::metadata(& path).and_then(
+    |meta| meta.modified()
+  ).ok() ;
+  thread::spawn(
+    move || {
+      let mut last = modified() ;
+      while ! is_over.load(Ordering::SeqCst) {
+        thread::sleep( Duration::from_millis(100) ) ;
+        let current = modified() ;
+        if current != last {
+          last = current ;
+          if sender.send(MsgUp::Reload).is_err() { break }
+        }
+      }
+    }
+  ) ;
+  Watch { over: over }
+}
@@ -0,0 +1,299 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Proofs of the unsat checks establishing the validity of properties.
+//!
+//! Proofs are not produced by the checks of the techniques, which depend on
+//! everything they learnt before. Kind replays the certificate of the
+//! properties it proves instead, see `unroll::certify`, in a fresh solver
+//! asked to produce proofs with [`enable`](fn.enable.html) and wrapped in a
+//! [`Recorder`](struct.Recorder.html). Each unsat check of the certificate,
+//! the base case at each depth and the step case, is stored with
+//! [`store`](fn.store.html) as two files:
+//!
+//! - `<name>.smt2`, the problem: comments naming the properties the check
+//!   proves, followed by all the commands sent to the solver up to the
+//!   check, declarations and assertions included;
+//! - `<name>.proof`, the answer of the solver to `get-proof`.
+//!
+//! [`check`](fn.check.html) replays the proofs of a directory with an
+//! external proof checker, called on each problem and its proof. Since the
+//! names of the files only depend on the systems, the directory is cleared
+//! with [`clear`](fn.clear.html) before each run. Each system has its own
+//! directory, see [`dir_of`](fn.dir_of.html).
+
+use std::io::{ Read, Write } ;
+use std::fs ;
+use std::path::Path ;
+use std::process::Command ;
+
+use term::{ Sym, Factory } ;
+use term::smt::{
+  PlainSolver, Solver, Query, QueryIdent, QueryExpr, QueryExprInfo,
+  Sym2Smt, Expr2Smt, ParseSmt2
+} ;
+use term::smt::internals::{ SolverBasic, SolverPrims } ;
+use term::smt::errors::Res as SmtRes ;
+use term::smt::errors::ErrorKind as SmtErrorKind ;
+
+use errors::* ;
+use SolverTrait ;
+
+/// Extension of the files storing proofs.
+pub const EXTENSION: & 'static str = "proof" ;
+
+/// Extension of the files storing the problems the proofs are about.
+pub const PROBLEM_EXTENSION: & 'static str = "smt2" ;
+
+/// Prefix of the comments naming the properties a proof is about.
+const PROVES: & 'static str = "; proves " ;
+
+/// Directory storing the proofs about a system, in the directory of the
+/// proofs.
+pub fn dir_of(dir: & str, sys: & Sym) -> String {
+  format!("{}/{}", dir, sys)
+}
+
+/// Asks a solver to produce proofs. Must be called before anything is
+/// declared or asserted.
+pub fn enable<'a, S: SolverTrait<'a>>(solver: & mut S) -> Res<()> {
+  solver.set_option(":produce-proofs", "true").chain_err(
+    || "while activating proof production"
+  )
+}
+
+/// The proof of the last check of a solver, which must be unsat.
+pub fn get<'a, S: SolverTrait<'a>>(solver: & mut S) -> Res<String> {
+  try!(
+    solver.print_get_proof().chain_err( || "while asking for a proof" )
+  ) ;
+  solver.parse(
+    |bytes, _| (
+      String::new(), Ok( String::from_utf8_lossy(bytes).trim().to_string() )
+    )
+  ).chain_err( || "while retrieving proof" )
+}
+
+/// Wraps a solver, recording the commands sent to it so that the problem a
+/// proof is about can be stored with it.
+///
+/// Queries other than satisfiability checks (`get-model`, `get-proof`...)
+/// are not recorded.
+pub struct Recorder<S> {
+  /// Solver wrapped.
+  solver: S,
+  /// Commands sent so far.
+  script: Vec<u8>,
+}
+impl<'kid, S: SolverBasic<'kid, Factory>> Recorder<S> {
+  /// Wraps a solver.
+  pub fn mk(solver: S) -> Self {
+    Recorder { solver: solver, script: Vec::with_capacity(1000) }
+  }
+  /// Commands sent so far.
+  pub fn script(& self) -> & [u8] { & self.script }
+}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+SolverBasic<'kid, Factory> for Recorder<S> {
+  fn fetch(& mut self) -> SmtRes<()> {
+    self.solver.fetch()
+  }
+  fn write<
+    F: Fn(& mut Write) -> SmtRes<()>
+  >(& mut self, f: F) -> SmtRes<()> {
+    let mut cmd: Vec<u8> = Vec::with_capacity(100) ;
+    try!( f(& mut cmd) ) ;
+    let is_get = ::std::str::from_utf8(& cmd).map(
+      |cmd| cmd.trim_left().starts_with("(get-")
+    ).unwrap_or(false) ;
+    if ! is_get {
+      self.script.extend_from_slice(& cmd) ;
+      if cmd.last() != Some(& b'\n') { self.script.push(b'\n') }
+    }
+    // Both `ResExt`s are in scope.
+    self.solver.write(
+      |w| ::term::smt::errors::ResExt::chain_err(
+        w.write_all(& cmd),
+        || SmtErrorKind::IoError( "writing command to recorded solver".into() )
+      )
+    )
+  }
+  fn comment(& mut self, txt: & str) -> SmtRes<()> {
+    self.solver.comment(txt)
+  }
+  fn parser(& self) -> & Factory {
+    self.solver.parser()
+  }
+  fn as_ref(& self) -> & [u8] {
+    self.solver.as_ref()
+  }
+  fn solver(& mut self) -> & mut PlainSolver<'kid, Factory> {
+    self.solver.solver()
+  }
+}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+SolverPrims<'kid, Factory> for Recorder<S> {}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+Solver<'kid, Factory> for Recorder<S> {}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+Query<'kid, Factory> for Recorder<S> {}
+
+impl<
+  'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>,
+  Info, Ident: Sym2Smt<Info>
+> QueryIdent<'kid, Factory, Info, Ident> for Recorder<S> {}
+
+impl<
+  'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>,
+  Info, Expr: Expr2Smt<Info>
+> QueryExpr<'kid, Factory, Info, Expr> for Recorder<S> {}
+
+impl<
+  'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>,
+  Expr: Expr2Smt<<Factory as ParseSmt2>::I>
+> QueryExprInfo<'kid, Factory, Expr> for Recorder<S> {}
+
+impl<'kid, S: SolverBasic<'kid, Factory> + SolverPrims<'kid, Factory>>
+SolverTrait<'kid> for Recorder<S> {}
+
+/// Writes a file.
+fn write_file(path: & str, txt: & str) -> Res<()> {
+  match fs::File::create(path) {
+    Ok(mut file) => match file.write_all( txt.as_bytes() ) {
+      Ok(()) => Ok(()),
+      Err(e) => bail!( ErrorKind::FileIoError(path.to_string(), e) ),
+    },
+    Err(e) => bail!( ErrorKind::FileIoError(path.to_string(), e) ),
+  }
+}
+
+/// Stores a proof and the problem it is about in a directory, creating it
+/// if needed. `name` is the name of the files without extension, `script`
+/// the commands of the problem. Returns the path of the proof.
+pub fn store(
+  dir: & str, name: & str, props: & [Sym], script: & [u8], proof: & str
+) -> Res<String> {
+  if let Err(e) = fs::create_dir_all(dir) {
+    bail!( ErrorKind::FileIoError(dir.to_string(), e) )
+  }
+  let mut problem = String::with_capacity( script.len() + 100 ) ;
+  for prop in props.iter() {
+    problem.push_str( & format!("{}{}\n", PROVES, prop.get().sym()) )
+  }
+  problem.push_str( & String::from_utf8_lossy(script) ) ;
+  try!(
+    write_file(
+      & format!("{}/{}.{}", dir, name, PROBLEM_EXTENSION), & problem
+    )
+  ) ;
+  let path = format!("{}/{}.{}", dir, name, EXTENSION) ;
+  try!( write_file(& path, & format!("{}\n", proof)) ) ;
+  Ok(path)
+}
+
+/// Removes a proof and its problem, if they exist.
+pub fn remove(path: & str) {
+  let _ = fs::remove_file(path) ;
+  let _ = fs::remove_file(
+    Path::new(path).with_extension(PROBLEM_EXTENSION)
+  ) ;
+}
+
+/// Removes the proofs and problems stored in a directory by previous runs.
+/// Does nothing if the directory does not exist.
+pub fn clear(dir: & str) -> Res<()> {
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(_) => return Ok(()),
+  } ;
+  for entry in entries {
+    let path = match entry {
+      Ok(entry) => entry.path(),
+      Err(e) => bail!( ErrorKind::FileIoError(dir.to_string(), e) ),
+    } ;
+    let ext = path.extension().and_then( |ext| ext.to_str() ) ;
+    if ext == Some(EXTENSION) || ext == Some(PROBLEM_EXTENSION) {
+      if let Err(e) = fs::remove_file(& path) {
+        bail!(
+          ErrorKind::FileIoError(path.to_string_lossy().into_owned(), e)
+        )
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Names of the properties the problem of a proof is about.
+fn proved_by(problem: & Path) -> Vec<String> {
+  let mut txt = String::new() ;
+  let read = fs::File::open(problem).and_then(
+    |mut file| file.read_to_string(& mut txt)
+  ) ;
+  if read.is_err() { return vec![] }
+  txt.lines().take_while(
+    |line| line.starts_with(PROVES)
+  ).map(
+    |line| line[PROVES.len()..].trim().to_string()
+  ).collect()
+}
+
+/// Replays the proofs stored in a directory by calling `cmd` on each
+/// problem and its proof, in this order. The first word of `cmd` is the
+/// command, the other ones its first arguments.
+///
+/// Returns the path of each proof, sorted, with the names of the properties
+/// it proves and the error output of the checker if it failed.
+pub fn check(
+  dir: & str, cmd: & str
+) -> Res< Vec<(String, Vec<String>, Option<String>)> > {
+  let mut words = cmd.split_whitespace() ;
+  let cmd = match words.next() {
+    Some(cmd) => cmd,
+    None => bail!("empty proof checker command"),
+  } ;
+  let args: Vec<& str> = words.collect() ;
+
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(e) => bail!( ErrorKind::FileIoError(dir.to_string(), e) ),
+  } ;
+  let mut paths = vec![] ;
+  for entry in entries {
+    let path = match entry {
+      Ok(entry) => entry.path(),
+      Err(e) => bail!( ErrorKind::FileIoError(dir.to_string(), e) ),
+    } ;
+    if path.extension().and_then( |ext| ext.to_str() ) == Some(EXTENSION) {
+      paths.push(path)
+    }
+  }
+  paths.sort() ;
+
+  let mut res = Vec::with_capacity( paths.len() ) ;
+  for path in paths.into_iter() {
+    let problem = path.with_extension(PROBLEM_EXTENSION) ;
+    let output = match Command::new(cmd).args(& args).arg(
+      & problem
+    ).arg(& path).output() {
+      Ok(output) => output,
+      Err(e) => bail!( ErrorKind::IoError(e) ),
+    } ;
+    let failure = if output.status.success() { None } else {
+      Some( String::from_utf8_lossy(& output.stderr).trim().to_string() )
+    } ;
+    res.push(
+      ( path.to_string_lossy().into_owned(), proved_by(& problem), failure )
+    )
+  }
+  Ok(res)
+}
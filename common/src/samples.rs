@@ -0,0 +1,142 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Pool of concrete reachable states.
+//!
+//! Techniques finding concrete traces of the system (typically BMC, with its
+//! counterexamples) send the states of these traces to kino, which
+//! broadcasts the ones it has not seen yet. Each technique has its own
+//! [`SamplePool`](struct.SamplePool.html), maintained by
+//! [`Event::recv`](../msg/struct.Event.html#method.recv).
+//!
+//! Since all the states of the pool are reachable, a candidate invariant
+//! evaluating to false on one of them is not an invariant, and a
+//! counterexample to induction that is also a sample is a real
+//! counterexample.
+
+use std::collections::HashSet ;
+
+use term::{
+  Sym, Cst, CstSet, Model, Offset, State, Factory, VarMaker, MemUsage
+} ;
+
+/// A concrete state: the values of the state variables, sorted by symbol.
+pub type Sample = Vec<(Sym, Cst)> ;
+
+/// Default maximum number of samples in a pool.
+pub const DEFAULT_CAPACITY: usize = 1000 ;
+
+/// Bounded pool of concrete reachable states.
+///
+/// Once the pool is full, new samples are dropped.
+pub struct SamplePool {
+  /// Samples, in the order they were added.
+  samples: Vec<Sample>,
+  /// Samples, for fast membership checks.
+  known: HashSet<Sample>,
+  /// Maximum number of samples.
+  max: usize,
+}
+impl SamplePool {
+  /// Creates an empty pool holding at most `max` samples.
+  pub fn mk(max: usize) -> Self {
+    SamplePool {
+      samples: Vec::with_capacity(17),
+      known: HashSet::with_capacity(17),
+      max: max,
+    }
+  }
+
+  /// Number of samples in the pool.
+  #[inline]
+  pub fn len(& self) -> usize { self.samples.len() }
+  /// True iff the pool is empty.
+  #[inline]
+  pub fn is_empty(& self) -> bool { self.samples.is_empty() }
+  /// True iff the pool is full.
+  #[inline]
+  pub fn is_full(& self) -> bool { self.samples.len() >= self.max }
+  /// The samples, in the order they were added.
+  #[inline]
+  pub fn samples(& self) -> & [Sample] { & self.samples }
+  /// True iff a state is in the pool.
+  #[inline]
+  pub fn contains(& self, sample: & Sample) -> bool {
+    self.known.contains(sample)
+  }
+
+  /// Adds a sample. Returns `false` if it was already there or if the pool
+  /// is full.
+  pub fn add(& mut self, mut sample: Sample) -> bool {
+    if self.is_full() { return false }
+    sample.sort_by(
+      |& (ref lhs, _), & (ref rhs, _)| lhs.get().sym().cmp( rhs.get().sym() )
+    ) ;
+    if self.known.contains(& sample) { return false }
+    self.known.insert( sample.clone() ) ;
+    self.samples.push(sample) ;
+    true
+  }
+
+  /// Adds some samples, returns the ones that were not already there.
+  pub fn add_all(& mut self, samples: Vec<Sample>) -> Vec<Sample> {
+    let mut new = Vec::with_capacity( samples.len() ) ;
+    for sample in samples {
+      if self.add( sample.clone() ) { new.push(sample) }
+    }
+    new
+  }
+
+  /// Values a state variable takes in the samples.
+  pub fn values_of(& self, sym: & Sym) -> CstSet {
+    let mut res = CstSet::with_capacity( self.samples.len() ) ;
+    for sample in self.samples.iter() {
+      for & (ref s, ref cst) in sample.iter() {
+        if s == sym {
+          res.insert( cst.clone() ) ;
+          break
+        }
+      }
+    }
+    res
+  }
+
+  /// Approximate memory usage of the pool.
+  pub fn mem_usage(& self) -> Vec<MemUsage> {
+    let values = self.samples.iter().fold(0, |acc, s| acc + s.len()) ;
+    vec![
+      MemUsage::of_table::<(Sym, Cst)>("samples", values),
+    ]
+  }
+
+  /// The states of a model, one per offset. Values without an offset are
+  /// ignored.
+  pub fn states_of(model: & Model) -> Vec<Sample> {
+    let mut res: Vec<Sample> = Vec::with_capacity(7) ;
+    for & ( (ref var, ref off), ref cst ) in model.iter() {
+      if let Some(ref off) = * off {
+        let off = off.to_usize() ;
+        while res.len() <= off { res.push( Vec::with_capacity(17) ) }
+        res[off].push( ( var.get().sym().clone(), cst.clone() ) )
+      }
+    }
+    res
+  }
+
+  /// A model describing a sample as the state at offset `0`.
+  pub fn model_of(sample: & Sample, factory: & Factory) -> Model {
+    let off = Offset::of_int(0) ;
+    sample.iter().map(
+      |& (ref sym, ref cst)| (
+        ( factory.svar(sym.clone(), State::Curr), Some( off.clone() ) ),
+        cst.clone()
+      )
+    ).collect()
+  }
+}
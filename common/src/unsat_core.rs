@@ -0,0 +1,79 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Unsat cores of the checks under assumptions.
+//!
+//! Solvers are asked to produce unsat cores with [`enable`](fn.enable.html)
+//! before anything is declared. After an unsat `check-sat-assuming`,
+//! [`get`](fn.get.html) retrieves a subset of the assumptions already
+//! inconsistent with the assertions. Activation literals being assumptions,
+//! the core tells which of the terms they activate are involved in the
+//! unsatisfiability.
+
+use errors::* ;
+use SolverTrait ;
+
+/// Asks a solver to produce unsat cores. Must be called before anything is
+/// declared or asserted.
+pub fn enable<'a, S: SolverTrait<'a>>(solver: & mut S) -> Res<()> {
+  solver.produce_unsat_core().chain_err(
+    || "while activating unsat core production"
+  )
+}
+
+/// The unsat core of the last check of a solver, which must be unsat. The
+/// names are the ones of the assumptions, quoted symbols keeping their
+/// `|`s.
+pub fn get<'a, S: SolverTrait<'a>>(solver: & mut S) -> Res< Vec<String> > {
+  try!(
+    solver.print_get_unsat_core().chain_err(
+      || "while asking for an unsat core"
+    )
+  ) ;
+  let answer = try!(
+    solver.parse(
+      |bytes, _| (
+        String::new(), Ok( String::from_utf8_lossy(bytes).trim().to_string() )
+      )
+    ).chain_err( || "while retrieving unsat core" )
+  ) ;
+  names_of(& answer).ok_or_else(
+    || format!("could not parse unsat core `{}`", answer).into()
+  )
+}
+
+/// The symbols of a list of symbols.
+fn names_of(list: & str) -> Option< Vec<String> > {
+  if ! list.starts_with('(') || ! list.ends_with(')') { return None }
+  let mut names = vec![] ;
+  let mut chars = list[ 1 .. list.len() - 1 ].chars() ;
+  let mut name = String::new() ;
+  while let Some(c) = chars.next() {
+    match c {
+      '|' => {
+        name.push(c) ;
+        loop {
+          match chars.next() {
+            Some('|') => break,
+            Some(c) => name.push(c),
+            None => return None,
+          }
+        }
+        name.push('|')
+      },
+      '(' | ')' => return None,
+      c if c.is_whitespace() => if ! name.is_empty() {
+        names.push( ::std::mem::replace(& mut name, String::new()) )
+      },
+      c => name.push(c),
+    }
+  }
+  if ! name.is_empty() { names.push(name) }
+  Some(names)
+}
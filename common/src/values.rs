@@ -0,0 +1,107 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Boolean values of terms in the model of the last check.
+//!
+//! The `get_values` query of the solvers parses the terms of the answer back
+//! to terms of the factory, which then have to be mapped back to the terms
+//! of the query. This fails on the terms the solver does not print the way
+//! they were sent. [`bools`](fn.bools.html) only reads the values of the
+//! answer, which come in the order of the terms of the query.
+
+use term::{ Term, Offset2 } ;
+
+use errors::* ;
+use SolverTrait ;
+
+/// The values of some boolean terms at some offset in the model of the last
+/// check of a solver, which must be satisfiable.
+pub fn bools<'a, S: SolverTrait<'a>>(
+  solver: & mut S, terms: & [Term], o: & Offset2
+) -> Res< Vec<bool> > {
+  if terms.is_empty() { return Ok( vec![] ) }
+  try!(
+    solver.print_get_values(terms, o).chain_err(
+      || format!("while asking for the values of {} terms", terms.len())
+    )
+  ) ;
+  let answer = try!(
+    solver.parse(
+      |bytes, _| (
+        String::new(), Ok( String::from_utf8_lossy(bytes).trim().to_string() )
+      )
+    ).chain_err( || "while retrieving values" )
+  ) ;
+  match bools_of(& answer) {
+    Some(ref values) if values.len() == terms.len() => Ok( values.clone() ),
+    Some(values) => bail!(
+      format!(
+        "expected {} values, got {} in `{}`",
+        terms.len(), values.len(), answer
+      )
+    ),
+    None => bail!(
+      format!("could not parse boolean values `{}`", answer)
+    ),
+  }
+}
+
+/// The values of the answer to a `get-value` query on boolean terms.
+///
+/// The answer is a list of pairs of a term and a value. Quoted symbols and
+/// string literals can contain parentheses, so they are skipped as a whole.
+fn bools_of(answer: & str) -> Option< Vec<bool> > {
+  let mut values = vec![] ;
+  let mut chars = answer.chars() ;
+  let mut depth = 0 ;
+  // Last element of the current pair, if it is an atom.
+  let mut last: Option<String> = None ;
+  let mut atom = String::new() ;
+  while let Some(c) = chars.next() {
+    match c {
+      '|' | '"' => {
+        atom.push(c) ;
+        loop {
+          match chars.next() {
+            Some(d) if d == c => break,
+            Some(d) => atom.push(d),
+            None => return None,
+          }
+        }
+        atom.push(c)
+      },
+      '(' | ')' => {
+        if ! atom.is_empty() {
+          let atom = ::std::mem::replace(& mut atom, String::new()) ;
+          if depth == 2 { last = Some(atom) }
+        }
+        if c == '(' {
+          depth += 1 ;
+          if depth == 3 { last = None }
+        } else {
+          if depth == 0 { return None }
+          if depth == 2 {
+            match last.take() {
+              Some(ref value) if value == "true" => values.push(true),
+              Some(ref value) if value == "false" => values.push(false),
+              _ => return None,
+            }
+          }
+          depth -= 1
+        }
+      },
+      c if c.is_whitespace() => if ! atom.is_empty() {
+        let atom = ::std::mem::replace(& mut atom, String::new()) ;
+        if depth == 2 { last = Some(atom) }
+      },
+      c => atom.push(c),
+    }
+  }
+  if depth == 0 && atom.is_empty() { Some(values) } else { None }
+}
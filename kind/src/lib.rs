@@ -19,16 +19,21 @@ extern crate common ;
 extern crate unroll ;
 
 use std::sync::Arc ;
-use std::time::Duration ;
+use std::time::{ Duration, Instant } ;
 use std::thread::sleep ;
 
-use term::Offset2 ;
+use term::{ Sym, Offset, Offset2, STerm, STermSet, Factory } ;
+use term::smt::{ SolverStyle, seed_option, timeout_option } ;
 
 use common::conf ;
+use common::conf::{ InvUse, OnUnknown } ;
 use common::SolverTrait ;
-use common::msg::{ Event, MsgDown, Status } ;
+use common::proof ;
+use common::errors::* ;
+use common::msg::{ Event, MsgDown, Status, Info } ;
 
 use system::{ Sys, Prop } ;
+use system::bounds::Bounds ;
 
 use unroll::* ;
 
@@ -42,7 +47,7 @@ impl common::CanRun<conf::Kind> for KInd {
     & self, conf: Arc<conf::Kind>, sys: Sys, props: Vec<Prop>, mut event: Event
   ) {
     // event.log(
-    //   & format!("checking {} propertie(s) on system {}", props.len(), sys.sym())
+    //   & format!("checking {} property(ies) on system {}", props.len(), sys.sym())
     // ) ;
 
     // event.log("creating solver") ;
@@ -54,13 +59,195 @@ impl common::CanRun<conf::Kind> for KInd {
     } ;
 
     mk_solver_run!(
-      solver_conf, conf.smt_log(), "kind", event.factory(),
+      solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+      race conf.smt_race(), compare conf.smt_compare(),
+      "kind", event.factory(),
       solver => kind(solver, conf.clone(), sys, props, & mut event),
-      err => event.error(err)
+      err => log_error!(event, err)
     )
   }
 }
 
+/// Adds the invariants received to the unroller, following the invariant
+/// usage policy of the configuration. Returns `true` if some of them are
+/// new, in which case the step cases that failed may succeed now.
+fn add_invs<'a, S: SolverTrait<'a>>(
+  conf: & conf::Kind, unroller: & mut Unroller<S>, invs: STermSet,
+  check: & Offset2, k: & Offset2
+) -> Res<bool> {
+  let begin = match * conf.use_invariants() {
+    InvUse::No => return Ok(false),
+    // Unrolling backwards, the state checked is the first one.
+    InvUse::StepOnly => check.nxt(),
+    InvUse::Both => check.clone(),
+  } ;
+  let mut room = conf.max_invariants().map(
+    |max| max.saturating_sub( unroller.invs().len() )
+  ) ;
+  let mut kept = STermSet::with_capacity( invs.len() ) ;
+  for inv in invs.into_iter() {
+    if unroller.invs().contains(& inv) { continue }
+    if let Some(max) = * conf.max_invariant_size() {
+      if inv.size() > max { continue }
+    }
+    if let Some(ref mut room) = room {
+      if * room == 0 { break }
+      * room -= 1
+    }
+    kept.insert(inv) ;
+    ()
+  }
+  // If nothing is unrolled yet outside of the state checked, unrolling will
+  // assert them.
+  unroller.strengthen(kept, & begin, k).map( |count| count > 0 )
+}
+
+/// Confirms that some properties proved at `k` are invariants with a
+/// certificate check, using a solver of some style.
+///
+/// The certificate is the conjunction of the properties at depth `k + 1`,
+/// then the same with `invs` if that fails. Does not use the solver cache.
+pub fn certify(
+  conf: & conf::Kind, smt: SolverStyle, sys: & Sys, props: & [Prop],
+  invs: & STermSet, k: usize, factory: & Factory
+) -> Res<bool> {
+  let mut conj: Vec<STerm> = props.iter().map(
+    |prop| prop.body().clone()
+  ).collect() ;
+  let mut candidates = vec![ conj.clone() ] ;
+  if ! invs.is_empty() {
+    conj.extend( invs.iter().cloned() ) ;
+    candidates.push(conj)
+  }
+  // The cache and the prelude are the ones of the solver of kind, not of the
+  // one certifying.
+  let none: Option<String> = None ;
+  for conj in candidates.into_iter() {
+    let solver_conf = smt.clone().default().print_success() ;
+    let certified = try!(
+      mk_solver_run!(
+        solver_conf, conf.smt_log(), & none, & none, "kind_certify", factory,
+        solver => certify::check(solver, sys, props, & conj, k + 1, factory),
+        err => Err(err)
+      ).chain_err( || "during certificate check" )
+    ) ;
+    if certified { return Ok(true) }
+  }
+  Ok(false)
+}
+
+/// Stores the proofs of some properties proved at `k` in a directory, see
+/// the [`proof`](../common/proof/index.html) module. The certificate of the
+/// properties is checked again in a fresh solver producing proofs, the
+/// proof of each of its checks is stored as `<name>_<check>`.
+///
+/// The certificate is the conjunction of the properties at depth `k + 1`,
+/// then the same with `invs` if that fails. Returns the paths of the
+/// proofs, nothing if neither certificate is confirmed.
+pub fn prove(
+  conf: & conf::Kind, sys: & Sys, props: & [Prop], invs: & STermSet,
+  k: usize, factory: & Factory, dir: & str, name: & str
+) -> Res< Option< Vec<String> > > {
+  let syms: Vec<Sym> = props.iter().map(
+    |prop| prop.sym().get().clone()
+  ).collect() ;
+  let mut conj: Vec<STerm> = props.iter().map(
+    |prop| prop.body().clone()
+  ).collect() ;
+  let mut candidates = vec![ conj.clone() ] ;
+  if ! invs.is_empty() {
+    conj.extend( invs.iter().cloned() ) ;
+    candidates.push(conj)
+  }
+  // The proofs must not depend on the cache or on the prelude.
+  let none: Option<String> = None ;
+  for conj in candidates.into_iter() {
+    let mut solver_conf = conf.smt().clone().default().print_success() ;
+    if let Some(ref cmd) = * conf.smt_cmd() {
+      solver_conf = solver_conf.cmd( cmd.clone() )
+    }
+    let mut paths = vec![] ;
+    let proved = mk_solver_run!(
+      solver_conf, conf.smt_log(), & none, & none, "kind_proof", factory,
+      solver => {
+        let mut solver = proof::Recorder::mk(solver) ;
+        match proof::enable(& mut solver) {
+          Ok(()) => certify::check_with(
+            solver, sys, props, & conj, k + 1, factory,
+            |unroller, check| {
+              let proof = try!( proof::get( unroller.solver() ) ) ;
+              let path = try!(
+                proof::store(
+                  dir, & format!("{}_{}", name, check), & syms,
+                  unroller.solver().script(), & proof
+                )
+              ) ;
+              paths.push(path) ;
+              Ok(())
+            }
+          ),
+          Err(e) => Err(e),
+        }
+      },
+      err => Err(err)
+    ).chain_err( || "while producing proofs" ) ;
+    match proved {
+      Ok(true) => return Ok( Some(paths) ),
+      // Proofs of a certificate that does not hold prove nothing.
+      Ok(false) => for path in paths.iter() { proof::remove(path) },
+      Err(e) => {
+        for path in paths.iter() { proof::remove(path) }
+        return Err(e)
+      },
+    }
+  }
+  Ok(None)
+}
+
+/// Tries to generalize a proved property into lemmas, sent as invariants.
+fn generalize_prop(
+  conf: & conf::Kind, sys: & Sys, prop: & Prop, invs: & STermSet,
+  event: & Event
+) {
+  let candidates = match generalize::candidates(event.factory(), sys, prop) {
+    Ok(candidates) => candidates,
+    Err(e) => return log_error!(event, e),
+  } ;
+  if candidates.is_empty() { return () }
+  let mut solver_conf = conf.smt().clone().default().print_success() ;
+  if let Some(ref cmd) = * conf.smt_cmd() {
+    solver_conf = solver_conf.cmd( cmd.clone() )
+  }
+  let lemmas = mk_solver_run!(
+    solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+    "kind_generalize", event.factory(),
+    solver => generalize::check(
+      solver, sys, prop, invs, event.factory(), candidates
+    ),
+    err => Err(err)
+  ) ;
+  let lemmas = lemmas.chain_err(
+    || format!("while generalizing {}", prop.sym())
+  ) ;
+  match lemmas {
+    Ok(ref lemmas) if lemmas.is_empty() => (),
+    Ok(lemmas) => {
+      let mut set = STermSet::with_capacity( lemmas.len() ) ;
+      for lemma in lemmas.into_iter() {
+        event.log(
+          & format!(
+            "generalized {}: {} replaced by parameter {}",
+            prop.sym(), lemma.cst, lemma.param
+          )
+        ) ;
+        set.insert(lemma.lemma) ; ()
+      }
+      event.invariants(sys.sym().get(), set)
+    },
+    Err(e) => log_error!(event, e),
+  }
+}
+
 fn kind<
   'a,
   S: SolverTrait<'a>
@@ -69,14 +256,26 @@ fn kind<
   sys: Sys, props: Vec<Prop>, event: & mut Event
 ) {
 
+  event.set_max_queries( * conf.max_queries() ) ;
+
   // Reversed to unroll backwards.
   let check_offset = Offset2::init().rev() ;
   let mut k = check_offset.clone() ;
 
   let mut unroller = log_try!(
-    event, Unroller::mk(& sys, & props, solver)
+    event, Unroller::mk_encoded(& sys, & props, solver, conf.encoding())
     => "while creating unroller"
   ) ;
+  unroller.set_on_unknown( * conf.on_unknown() ) ;
+  unroller.set_seed_option( seed_option( conf.smt() ) ) ;
+  if let Some(millis) = * conf.smt_timeout() {
+    log_try!(
+      event, unroller.set_timeout( timeout_option( conf.smt() ), millis )
+    )
+  }
+
+  // Kept for generalization.
+  let all_props = props.clone() ;
 
   // event.log("creating manager, declaring actlits") ;
   let mut props = log_try!(
@@ -109,16 +308,54 @@ fn kind<
     => "while unrolling system"
   ) ;
 
+  // Path compression, the state unrolled differs from the ones after it.
+  if * conf.compress() {
+    log_try!(
+      event, unroller.assert_distinct( k.curr() )
+      => "while asserting path compression at {}", k
+    )
+  }
+
   log_try!(
     event, props.activate_state(unroller.solver(), & k)
     => "while activating one-state property"
   ) ;
 
+  // Number of unrollings since the last time new invariants were received.
+  let mut stale = 0 ;
+
+  // Number of proofs stored so far.
+  let mut proofs = 0 ;
+
+  // Time the last step check took. Step checks get harder with the depth,
+  // the next one is skipped if it cannot take less before the deadline.
+  let mut last_check = Duration::from_secs(0) ;
+
+  // The estimated recurrence diameter is the default maximum.
+  let max = match * conf.max() {
+    None if * conf.diameter() => Bounds::of(& sys).diameter(),
+    max => max,
+  } ;
+
   'out: loop {
 
-    if let Some(ref max) = * conf.max() {
+    if let Some(ref max) = max {
       if max < & k.curr().to_usize() {
-        event.done_at( & k.next() ) ;
+        event.max_reached( & Offset::of_int(* max) ) ;
+        break 'out
+      }
+    }
+
+    // Checking again with the same invariants cannot prove more, stopping
+    // once invariant generation seems to be stuck.
+    if let Some(ref fixpoint) = * conf.fixpoint() {
+      if stale >= * fixpoint {
+        event.warning(
+          & format!(
+            "no new invariants for {} unrollings, fixpoint reached", stale
+          )
+        ) ;
+        event.done( Info::Fixpoint( k.curr().clone() ) ) ;
         break 'out
       }
     }
@@ -129,29 +366,51 @@ fn kind<
 
     props.reset_inhibited() ;
 
+    let mut usage = unroller.mem_usage() ;
+    usage.extend( props.mem_usage() ) ;
+    event.stats(usage) ;
+
     // event.log( & format!("activating state at {}", k) ) ;
 
     match event.recv() {
       None => return (),
       Some(msgs) => for msg in msgs {
         match msg {
-          MsgDown::Forget(ps, _) => log_try!(
-            event, props.forget(unroller.solver(), ps.iter())
-            => "while forgetting some properties\n\
-              because of a `Forget` message (1)"
+          MsgDown::Forget(ps, status) => log_try!(
+            event, props.forget_status(
+              unroller.solver(), ps.iter(), & status
+            )
+            => "while forgetting {} properties\n\
+              because of a `Forget` message (1)", status
           ),
           MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
             // event.log(
             //   & format!("received {} invariants", invs.len())
             // ) ;
             // event.log( & format!("add_invs [{}, {}]", check_offset, k) ) ;
+            if ! invs.is_empty() { stale = 0 }
+            // Nothing inhibited yet at this depth.
             log_try!(
-              event, unroller.add_invs(invs, & check_offset, & k)
+              event, add_invs(
+                & conf, & mut unroller, invs, & check_offset, & k
+              )
               => "while adding invariants from supervisor"
-            )
+            ) ;
+          },
+          MsgDown::NewProps(ps) => {
+            let count = ps.len() ;
+            log_try!(
+              event, unroller.defclare_prop_funs(& ps)
+              => "while declaring functions for {} new properties", count
+            ) ;
+            log_try!(
+              event, props.add_props_between(
+                ps, unroller.solver(), & check_offset, & k
+              ) => "while adding {} new properties to manager", count
+            ) ;
           },
-          msg => event.error(
-            format!("unexpected message `{:?}`", msg).into()
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
           ),
         }
       },
@@ -165,7 +424,14 @@ fn kind<
     // event.log("splitting") ;
 
     'split: while let Some(one_prop_false) = props.one_false_next() {
-        
+
+      if ! event.clock().has_time_for(last_check) {
+        event.out_of_time(
+          k.curr(), & format!("checking the step case at {}", k)
+        ) ;
+        return ()
+      }
+
       // Setting up the negative actlit.
       let actlit = log_try!(
         event, unroller.fresh_actlit()
@@ -182,10 +448,38 @@ fn kind<
       let mut actlits = props.actlits() ;
       actlits.push(actlit.name()) ;
 
+      if ! event.query() {
+        event.out_of_queries(k.curr()) ;
+        return ()
+      }
+
       // Check sat.
+      let start = Instant::now() ;
+      let res = unroller.check_sat_assuming( & actlits ) ;
+      last_check = Instant::now() - start ;
+      event.solver_unknowns( unroller.unknowns() ) ;
+
+      // If the solver cannot tell, nothing is proved at this k.
+      let skip = unroller.last_unknown() || match res {
+        Err(ref e) => unknown_reason(e).is_some() && (
+          * conf.on_unknown() == OnUnknown::Skip
+        ),
+        Ok(_) => false,
+      } ;
+      if skip {
+        event.warning(
+          & format!(
+            "step case at {} skipped, the solver answered `unknown`", k
+          )
+        ) ;
+        log_try!(
+          event, unroller.deactivate(actlit)
+          => "while deactivating negative actlit"
+        ) ;
+        break 'split
+      }
       let is_sat = log_try!(
-        event, unroller.check_sat_assuming( & actlits )
-        => "during a `check_sat_assuming` query at {}", k
+        event, res => "during a `check_sat_assuming` query at {}", k
       ) ;
 
       if is_sat {
@@ -213,19 +507,10 @@ fn kind<
         // Wait until we get something from BMC.
         // event.log("waiting for bmc") ;
         loop {
-          let mut invariant = true ;
-          let at_least = k.curr().pre() ;
-          for prop in unfalsifiable.iter() {
-            match * event.get_k_true(prop) {
-              Some(ref o) => {
-                if o < & at_least {
-                  invariant = false ;
-                  break
-                }
-              },
-              _ => { invariant = false ; break }
-            }
-          } ;
+          props.update_bounds(event) ;
+          let invariant = props.all_true_at(
+            unfalsifiable.iter(), & k.curr().pre()
+          ) ;
 
           if invariant {
             log_try!(
@@ -235,6 +520,43 @@ fn kind<
               => "while forgetting some properties\n\
                 because I just proved them invariant"
             ) ;
+            if let Some(ref dir) = * conf.proofs() {
+              let mut proved: Vec<Prop> = all_props.iter().filter(
+                |prop| unfalsifiable.contains( prop.sym().get() )
+              ).cloned().collect() ;
+              proved.sort_by(
+                |lhs, rhs| lhs.sym().get().get().sym().cmp(
+                  rhs.sym().get().get().sym()
+                )
+              ) ;
+              let name = format!("{}_{}", sys.sym(), proofs) ;
+              proofs += 1 ;
+              match prove(
+                & conf, & sys, & proved, unroller.invs(),
+                k.curr().to_usize(), event.factory(),
+                & proof::dir_of( dir, sys.sym().get() ), & name
+              ) {
+                Ok( Some(paths) ) => event.log(
+                  & format!(
+                    "{} proof(s) at {} stored as {}_*", paths.len(),
+                    k.curr(), name
+                  )
+                ),
+                Ok(None) => event.warning(
+                  & format!(
+                    "no proof at {}, the certificate check failed", k.curr()
+                  )
+                ),
+                Err(e) => log_error!(event, e),
+              }
+            }
+            if * conf.generalize() {
+              for prop in all_props.iter().filter(
+                |prop| unfalsifiable.contains( prop.sym().get() )
+              ) {
+                generalize_prop(& conf, & sys, prop, unroller.invs(), event)
+              }
+            }
             event.proved_at(unfalsifiable.into_iter().collect(), k.curr()) ;
             break 'split
           } else {
@@ -247,7 +569,7 @@ fn kind<
                   match msg {
                     MsgDown::Forget(ps, Status::Proved) => {
                       log_try!(
-                        event, props.forget(unroller.solver(), ps.iter())
+                        event, props.forget_keep(unroller.solver(), ps.iter())
                         => "while forgetting some properties\n\
                           because of a `Forget` message (2, proved)"
                       ) ;
@@ -274,13 +596,31 @@ fn kind<
                       // event.log(
                       //   & format!("add_invs [{}, {}]", check_offset, k)
                       // ) ;
-                      log_try!(
-                        event, unroller.add_invs(invs, & check_offset, & k)
+                      if ! invs.is_empty() { stale = 0 }
+                      let new = log_try!(
+                        event, add_invs(
+                          & conf, & mut unroller, invs, & check_offset, & k
+                        )
                         => "while adding invariants from supervisor"
-                      )
+                      ) ;
+                      // Step cases failing before may succeed now.
+                      if new { props.reset_inhibited() }
                     },
-                    msg => event.error(
-                      format!("unexpected message `{:?}`", msg).into()
+                    MsgDown::NewProps(ps) => {
+                      let count = ps.len() ;
+                      log_try!(
+                        event, unroller.defclare_prop_funs(& ps)
+                        => "while declaring functions for {} new properties",
+                        count
+                      ) ;
+                      log_try!(
+                        event, props.add_props_between(
+                          ps, unroller.solver(), & check_offset, & k
+                        ) => "while adding {} new properties to manager", count
+                      ) ;
+                    },
+                    msg => log_error!(
+                      event, format!("unexpected message `{:?}`", msg)
                     ),
                   }
                 }
@@ -305,23 +645,42 @@ fn kind<
         None => return (),
         Some(msgs) => for msg in msgs {
           match msg {
-            MsgDown::Forget(ps, _) => log_try!(
-              event, props.forget(unroller.solver(), ps.iter())
-              => "while forgetting some properties \
-                because of a `Forget` message (1)"
+            MsgDown::Forget(ps, status) => log_try!(
+              event, props.forget_status(
+                unroller.solver(), ps.iter(), & status
+              )
+              => "while forgetting {} properties \
+                because of a `Forget` message (1)", status
             ),
             MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
               // event.log(
               //   & format!("received {} invariants", invs.len())
               // ) ;
               // event.log( & format!("add_invs [{}, {}]", check_offset, k) ) ;
-              log_try!(
-                event, unroller.add_invs(invs, & check_offset, & k)
+              if ! invs.is_empty() { stale = 0 }
+              let new = log_try!(
+                event, add_invs(
+                  & conf, & mut unroller, invs, & check_offset, & k
+                )
                 => "while adding invariants from supervisor"
-              )
+              ) ;
+              // Retrying the step cases that failed with fewer invariants.
+              if new { props.reset_inhibited() }
             },
-            msg => event.error(
-              format!("unexpected message `{:?}`", msg).into()
+            MsgDown::NewProps(ps) => {
+              let count = ps.len() ;
+              log_try!(
+                event, unroller.defclare_prop_funs(& ps)
+                => "while declaring functions for {} new properties", count
+              ) ;
+              log_try!(
+                event, props.add_props_between(
+                  ps, unroller.solver(), & check_offset, & k
+                ) => "while adding {} new properties to manager", count
+              ) ;
+            },
+            msg => log_error!(
+              event, format!("unexpected message `{:?}`", msg)
             ),
           }
         },
@@ -335,13 +694,22 @@ fn kind<
       break
     }
 
+    event.depth_done( props.keys(), k.curr() ) ;
+
     k = k.nxt() ;
+    stale += 1 ;
 
     // event.log( & format!("unroll {}", k) ) ;
     log_try!(
       event, unroller.unroll_bak(& k)
       => "while unrolling system"
     ) ;
+    if * conf.compress() {
+      log_try!(
+        event, unroller.assert_distinct( k.curr() )
+        => "while asserting path compression at {}", k
+      )
+    }
 
     // event.log( & format!("activate next at {}", k) ) ;
     log_try!(
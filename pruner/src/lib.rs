@@ -44,7 +44,7 @@ impl common::CanRun<conf::Pruner> for Pruner {
     props: Vec<Prop>, mut event: Event
   ) {
     // event.log(
-    //   & format!("checking {} propertie(s) on system {}", props.len(), sys.sym())
+    //   & format!("checking {} property(ies) on system {}", props.len(), sys.sym())
     // ) ;
 
     // event.log("creating solver") ;
@@ -56,9 +56,10 @@ impl common::CanRun<conf::Pruner> for Pruner {
     } ;
 
     mk_solver_run!(
-      solver_conf, conf.smt_log(), "pruner", event.factory(),
+      solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+      "pruner", event.factory(),
       solver => pruner(solver, sys, props, & mut event),
-      err => event.error(err)
+      err => log_error!(event, err)
     )
   }
 }
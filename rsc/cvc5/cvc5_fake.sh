@@ -0,0 +1,11 @@
+#!/bin/sh
+# Fake cvc5, for the tests of the cvc5 backend: rejects `check-sat`s with
+# arguments, answers `sat` to `check-sat-assuming`s, and omits the `model`
+# keyword in its models. In its models, `x` is `3`.
+while read line ; do
+  case "$line" in
+    *check-sat-assuming*) echo "sat" ;;
+    *check-sat*) echo '(error "check-sat takes no arguments")' ;;
+    *get-model*) printf '(\n  (define-fun | x| () Int 3)\n)\n' ;;
+  esac
+done
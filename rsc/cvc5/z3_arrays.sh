@@ -0,0 +1,19 @@
+#!/bin/sh
+# Fake z3, for the tests of the model parser: answers `sat` to all the
+# satisfiability checks, with or without assumptions, and gives the value
+# of the array `a` as the function `k!0` of its models, which `b` is
+# written without.
+while read line ; do
+  case "$line" in
+    *check-sat*) echo "sat" ;;
+    *get-model*)
+      printf '(model\n'
+      printf '  (define-fun | a| () (Array Int Int) (_ as-array k!0))\n'
+      printf '  (define-fun | b| () (Array Int Int)\n'
+      printf '    (store (store ((as const (Array Int Int)) 0) 2 7) 1 5))\n'
+      printf '  (define-fun k!0 ((x!0 Int)) Int\n'
+      printf '    (ite (= x!0 1) 5 (ite (= x!0 2) 7 0)))\n'
+      printf ')\n'
+      ;;
+  esac
+done
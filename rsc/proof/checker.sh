@@ -0,0 +1,7 @@
+#!/bin/sh
+# Fake proof checker, for the proof tests: called on a problem and its
+# proof, rejects the proofs mentioning `bad`.
+if grep -q bad "$2" ; then
+  echo "bad proof for $1" >&2
+  exit 1
+fi
@@ -0,0 +1,10 @@
+#!/bin/sh
+# Fake solver, for the racing tests: answers checks right away, and models
+# after two seconds. In its models, `x` is `1`, with the leading space of
+# the symbols kino declares.
+while read line ; do
+  case "$line" in
+    *check-sat*) echo "sat" ;;
+    *get-model*) sleep 2 ; echo "(model (define-fun | x| () Int 1) )" ;;
+  esac
+done
@@ -0,0 +1,13 @@
+#!/bin/sh
+# Fake solver, for the tests of the `unknown` policies: answers `unknown` to
+# the checks until its random seed is set with `smt.random_seed`, and `sat`
+# after that.
+seeded=no
+while read line ; do
+  case "$line" in
+    *smt.random_seed*) seeded=yes ;;
+    *reason-unknown*) echo '(:reason-unknown "incomplete")' ;;
+    *check-sat*)
+      if [ "$seeded" = yes ] ; then echo "sat" ; else echo "unknown" ; fi ;;
+  esac
+done
@@ -0,0 +1,13 @@
+#!/bin/sh
+# Fake solver, for the tests of the timeouts of the checks: answers
+# `unknown` to the checks once a timeout is set with `:timeout` or
+# `:tlimit-per`, as if they all timed out, and `sat` before that.
+timeout=no
+while read line ; do
+  case "$line" in
+    *:timeout*|*:tlimit-per*) timeout=yes ;;
+    *reason-unknown*) echo '(:reason-unknown "timeout")' ;;
+    *check-sat*)
+      if [ "$timeout" = yes ] ; then echo "unknown" ; else echo "sat" ; fi ;;
+  esac
+done
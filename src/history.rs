@@ -0,0 +1,407 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Outcomes and timings of the properties, recorded across runs.
+//!
+//! With `--history <file>`, each analysis appends one line per property to
+//! `<file>`, a JSON object:
+//!
+//! ```text
+//! {"date":1476400000,"kino":"0.1.0","solvers":"bmc: Z3 version 4.5.0",
+//!  "system":"sys","hash":"00c0ffee00c0ffee","prop":"p",
+//!  "outcome":"proved","k":3,"tek":"k-ind","time":0.42,"total":0.57}
+//! ```
+//!
+//! (on a single line) where
+//!
+//! - `date` is the number of seconds since the epoch,
+//! - `solvers` the versions of the solvers of the techniques, as their
+//!   command reports them with `--version`,
+//! - `hash` the [stable hash](../../term/trait.StableHash.html) of the
+//!   system,
+//! - `outcome` is `proved`, `falsified` or `unknown`,
+//! - `k` the depth of the proof or of the counterexample, or the depth the
+//!   property is known to be true up to if unknown,
+//! - `tek` the technique deciding the property, and `time` the number of
+//!   seconds it took since the beginning of the analysis, `null` for the
+//!   properties left unknown and the ones with a cached verdict,
+//! - `total` the duration of the whole analysis.
+//!
+//! `kino stats <file>` summarizes such a file: the outcomes and the mean
+//! time of each property for each version of kino, pointing out the
+//! versions deciding fewer runs or being slower than the previous one.
+
+use std::io::{ Read, Write } ;
+use std::fs::{ File, OpenOptions } ;
+use std::process::Command ;
+use std::collections::HashMap ;
+use std::time::{ Instant, Duration, SystemTime, UNIX_EPOCH } ;
+
+use term::{ Sym, StableHash } ;
+
+use system::{ Sys, Prop, PropStatus } ;
+use system::ctxt::Context ;
+
+use common::Tek ;
+use common::conf ;
+use common::json ;
+use common::log::{ MasterLog, Formatter, Styler, json_str } ;
+
+/// Version of kino, recorded with each run.
+const VERSION: & 'static str = env!("CARGO_PKG_VERSION") ;
+
+/// A mean time is a regression if it is this many times the previous one.
+const SLOWER: f64 = 1.5 ;
+/// Differences in mean times below this many seconds are noise.
+const NOISE: f64 = 0.1 ;
+
+/// When the properties are decided during an analysis, and by which
+/// technique.
+pub struct Decided {
+  /// Beginning of the analysis.
+  start: Instant,
+  /// Technique deciding each property, and when.
+  props: HashMap<Sym, (Tek, Duration)>,
+}
+impl Decided {
+  /// Starts measuring.
+  pub fn mk() -> Self {
+    Decided { start: Instant::now(), props: HashMap::new() }
+  }
+  /// Some technique decided a property now. Only the first decision of a
+  /// property is remembered.
+  pub fn decide(& mut self, prop: & Sym, tek: Tek) {
+    if ! self.props.contains_key(prop) {
+      let time = Instant::now() - self.start ;
+      self.props.insert( prop.clone(), (tek, time) ) ; ()
+    }
+  }
+}
+
+/// A line of a history file.
+struct Record {
+  /// Seconds since the epoch.
+  date: u64,
+  /// Version of kino.
+  kino: String,
+  /// Versions of the solvers.
+  solvers: String,
+  /// Name of the system.
+  system: String,
+  /// Stable hash of the system.
+  hash: String,
+  /// Name of the property.
+  prop: String,
+  /// `proved`, `falsified` or `unknown`.
+  outcome: String,
+  /// Depth of the outcome.
+  k: Option<usize>,
+  /// Technique deciding the property.
+  tek: Option<String>,
+  /// Seconds taken to decide the property.
+  time: Option<f64>,
+  /// Seconds taken by the whole analysis.
+  total: f64,
+}
+impl Record {
+  /// JSON object of a record, on one line.
+  fn to_json(& self) -> String {
+    format!(
+      "{{\"date\":{},\"kino\":{},\"solvers\":{},\"system\":{},\"hash\":{},\
+      \"prop\":{},\"outcome\":{},\"k\":{},\"tek\":{},\"time\":{},\
+      \"total\":{}}}",
+      self.date, json_str(& self.kino), json_str(& self.solvers),
+      json_str(& self.system), json_str(& self.hash), json_str(& self.prop),
+      json_str(& self.outcome),
+      self.k.map_or( "null".to_string(), |k| format!("{}", k) ),
+      self.tek.as_ref().map_or( "null".to_string(), |tek| json_str(tek) ),
+      self.time.map_or( "null".to_string(), |time| format!("{}", time) ),
+      self.total
+    )
+  }
+
+  /// Parses a line of a history file.
+  fn of_json(line: & str) -> Result<Self, String> {
+    let mut fields = try!( json_fields(line) ) ;
+    macro_rules! field {
+      ($name:expr) => (
+        match fields.remove($name) {
+          Some(value) => value,
+          None => return Err( format!("no field `{}`", $name) ),
+        }
+      ) ;
+      ($name:expr => $typ:ty) => (
+        match fields.remove($name).map( |value| value.parse::<$typ>() ) {
+          Some( Ok(value) ) => Some(value),
+          Some( Err(_) ) => return Err(
+            format!("illegal value for field `{}`", $name)
+          ),
+          None => None,
+        }
+      ) ;
+    }
+    Ok(
+      Record {
+        date: field!("date" => u64).unwrap_or(0),
+        kino: field!("kino"),
+        solvers: field!("solvers"),
+        system: field!("system"),
+        hash: field!("hash"),
+        prop: field!("prop"),
+        outcome: field!("outcome"),
+        k: field!("k" => usize),
+        tek: fields.remove("tek"),
+        time: field!("time" => f64),
+        total: field!("total" => f64).unwrap_or(0.),
+      }
+    )
+  }
+}
+
+/// A duration in seconds.
+fn secs(d: Duration) -> f64 {
+  d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9
+}
+
+/// Versions of the solvers of the techniques that are on.
+pub fn solvers(conf: & conf::Master) -> String {
+  let mut versions = HashMap::new() ;
+  let mut res = vec![] ;
+  macro_rules! solver {
+    ($( $tek:ident ),+) => ( $(
+      if let Some(ref tek) = conf.$tek {
+        if * tek.is_on() {
+          let cmd = tek.smt_cmd().clone().unwrap_or_else(
+            || tek.smt().cmd()
+          ) ;
+          res.push(
+            format!(
+              "{}: {}", stringify!($tek), version_of(& cmd, & mut versions)
+            )
+          )
+        }
+      }
+    )+ ) ;
+  }
+  solver!(bmc, kind, twind, cegar, tig, pruner) ;
+  res.join(", ")
+}
+
+/// First line of the output of a solver called with `--version`, the
+/// command itself if that fails.
+fn version_of(cmd: & str, versions: & mut HashMap<String, String>) -> String {
+  if let Some(version) = versions.get(cmd) { return version.clone() }
+  let program = cmd.split_whitespace().next().unwrap_or(cmd) ;
+  let output = Command::new(program).arg("--version").output().ok() ;
+  let version = output.and_then(
+    |output| String::from_utf8(output.stdout).ok()
+  ).and_then(
+    |out| out.lines().map( |line| line.trim().to_string() ).find(
+      |line| ! line.is_empty()
+    )
+  ).unwrap_or_else( || cmd.to_string() ) ;
+  versions.insert( cmd.to_string(), version.clone() ) ;
+  version
+}
+
+/// Appends the outcomes of the properties of an analysis to a history
+/// file.
+pub fn record(
+  file: & str, solvers: & str, c: & Context, sys: & Sys, props: & [Prop],
+  decided: & Decided, total: Duration
+) -> Result<(), String> {
+  let date = SystemTime::now().duration_since(UNIX_EPOCH).map(
+    |d| d.as_secs()
+  ).unwrap_or(0) ;
+  let hash = format!("{:016x}", sys.stable_hash()) ;
+  let mut txt = String::new() ;
+  for prop in props.iter() {
+    let (outcome, k) = match c.get_prop( prop.sym().get() ) {
+      Some( & (_, PropStatus::Falsified(ref cex)) ) => (
+        "falsified", Some( cex.len() )
+      ),
+      Some( & (_, PropStatus::Invariant(k)) ) |
+      Some( & (_, PropStatus::MinInvariant(k, _)) ) => ("proved", Some(k)),
+      Some( & (_, PropStatus::KTrue(k)) ) => ("unknown", Some(k)),
+      _ => ("unknown", None),
+    } ;
+    let decision = if outcome == "unknown" { None } else {
+      decided.props.get( prop.sym().get() )
+    } ;
+    let record = Record {
+      date: date,
+      kino: VERSION.to_string(),
+      solvers: solvers.to_string(),
+      system: sys.sym().get().sym().to_string(),
+      hash: hash.clone(),
+      prop: prop.sym().get().sym().to_string(),
+      outcome: outcome.to_string(),
+      k: k,
+      tek: decision.map( |& (tek, _)| tek.to_str().to_string() ),
+      time: decision.map( |& (_, time)| secs(time) ),
+      total: secs(total),
+    } ;
+    txt.push_str( & record.to_json() ) ;
+    txt.push('\n')
+  }
+  let mut history = try!(
+    OpenOptions::new().create(true).append(true).open(file).map_err(
+      |e| format!("could not open history file {}:\n{}", file, e)
+    )
+  ) ;
+  history.write_all( txt.as_bytes() ).map_err(
+    |e| format!("could not write to history file {}:\n{}", file, e)
+  )
+}
+
+/// Outcomes of the runs of a property with some version of kino.
+struct Summary {
+  /// Runs proving the property.
+  proved: usize,
+  /// Runs falsifying the property.
+  falsified: usize,
+  /// Runs leaving the property unknown.
+  unknown: usize,
+  /// Mean time to decide the property, over the runs deciding it.
+  mean: Option<f64>,
+}
+impl Summary {
+  /// Summary of some runs.
+  fn of(runs: & [& Record]) -> Self {
+    let (mut proved, mut falsified, mut unknown) = (0, 0, 0) ;
+    let (mut sum, mut timed) = (0., 0) ;
+    for run in runs.iter() {
+      match & run.outcome as & str {
+        "proved" => proved += 1,
+        "falsified" => falsified += 1,
+        _ => unknown += 1,
+      }
+      if let Some(time) = run.time {
+        sum += time ;
+        timed += 1
+      }
+    }
+    Summary {
+      proved: proved, falsified: falsified, unknown: unknown,
+      mean: if timed > 0 { Some(sum / timed as f64) } else { None },
+    }
+  }
+
+  /// Regressions with respect to the summary of a previous version.
+  fn regressions(& self, prev: & Summary) -> Vec<String> {
+    let mut res = vec![] ;
+    if prev.unknown == 0 && self.unknown > 0 {
+      res.push(
+        format!("{} run(s) do not decide the property anymore", self.unknown)
+      )
+    }
+    if let (Some(mean), Some(prev)) = (self.mean, prev.mean) {
+      if mean > prev * SLOWER && mean - prev > NOISE {
+        res.push(
+          format!(
+            "{:.2} times slower ({:.3}s, was {:.3}s)", mean / prev, mean, prev
+          )
+        )
+      }
+    }
+    res
+  }
+}
+
+/// Summarizes a history file.
+pub fn report<F: Formatter, S: Styler>(
+  log: & MasterLog<F,S>, file: & str
+) -> Result<(), String> {
+  let mut txt = String::new() ;
+  try!(
+    File::open(file).and_then(
+      |mut f| f.read_to_string(& mut txt)
+    ).map_err(
+      |e| format!("could not read history file {}:\n{}", file, e)
+    )
+  ) ;
+  let mut records = vec![] ;
+  for (index, line) in txt.lines().enumerate() {
+    if line.trim().is_empty() { continue }
+    match Record::of_json(line) {
+      Ok(record) => records.push(record),
+      Err(e) => return Err(
+        format!("on line {} of history file {}:\n{}", index + 1, file, e)
+      ),
+    }
+  }
+  if records.is_empty() {
+    log.print("no run recorded") ;
+    return Ok(())
+  }
+
+  // Runs of each property of each system, in order of first appearance.
+  let mut keys = vec![] ;
+  let mut runs: HashMap<_, Vec<& Record>> = HashMap::new() ;
+  for record in records.iter() {
+    let key = ( & record.system, & record.hash, & record.prop ) ;
+    if ! runs.contains_key(& key) { keys.push(key) }
+    runs.entry(key).or_insert_with(Vec::new).push(record)
+  }
+
+  for key in keys.into_iter() {
+    let (system, hash, prop) = key ;
+    log.title( & format!("{} of {} ({})", prop, system, hash) ) ;
+    // Runs of each version, in order of first appearance.
+    let mut versions: Vec< (& str, Vec<& Record>) > = vec![] ;
+    for record in runs[& key].iter() {
+      match versions.iter().position(
+        |& (version, _)| version == record.kino
+      ) {
+        Some(index) => versions[index].1.push(record),
+        None => versions.push( (& record.kino as & str, vec![record]) ),
+      }
+    }
+    let mut prev: Option<Summary> = None ;
+    for (version, runs) in versions.into_iter() {
+      let summary = Summary::of(& runs) ;
+      log.print(
+        & format!(
+          "kino {}: {} run(s), {} proved, {} falsified, {} unknown, {}",
+          version, runs.len(), summary.proved, summary.falsified,
+          summary.unknown, match summary.mean {
+            Some(mean) => format!("{:.3}s on average", mean),
+            None => "no timing".to_string(),
+          }
+        )
+      ) ;
+      if let Some(last) = runs.last() {
+        if ! last.solvers.is_empty() {
+          log.print( & format!("  solvers: {}", last.solvers) )
+        }
+      }
+      if let Some(ref prev) = prev {
+        for regression in summary.regressions(prev).into_iter() {
+          log.print(
+            & format!(
+              "  {}", log.mk_bad( & format!("regression: {}", regression) )
+            )
+          )
+        }
+      }
+      prev = Some(summary)
+    }
+  }
+  Ok(())
+}
+
+/// Fields of a flat JSON object, the ones that are `null` being left out.
+fn json_fields(line: & str) -> Result<HashMap<String, String>, String> {
+  let fields = try!( json::object(& mut line.chars().peekable()) ) ;
+  Ok(
+    fields.into_iter().filter_map(
+      |(name, value)| value.map( |value| (name, value) )
+    ).collect()
+  )
+}
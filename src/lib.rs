@@ -22,13 +22,16 @@ extern crate common ;
 extern crate bmc ;
 extern crate kind ;
 extern crate twind ;
+extern crate cegar ;
 extern crate tig ;
 extern crate pruner ;
 
 mod master ;
+mod verdicts ;
+pub mod history ;
 
-// use term::{ Sym, SymMaker } ;
-use system::Prop ;
+pub use term::{ Sym, SymMaker } ;
+pub use system::{ Sys, Prop, PropStatus } ;
 pub use system::Error as SysError ;
 // pub use unroll::* ;
 // pub use common::* ;
@@ -64,18 +67,37 @@ pub mod teks {
 }
 
 pub use master::Master ;
+/// Top level log, and the trait of custom reporting of its results.
+pub use common::log::{ MasterLog, ResultSink, JsonSink } ;
 pub use system::ctxt::Context ;
-use system::ctxt::Res as CtxtRes ;
+/// Stable structural hashes for terms, systems and properties.
+pub use term::{ StableHash, StableHasher } ;
+/// Result of reading a file: a verify query, or nothing to do.
+pub use system::ctxt::Res as CtxtRes ;
 
-/// Loads a file, creates a context.
+/// Loads a file, creates a context. Files ending with `.smv` are
+/// translated from SMV first.
 pub fn load(path: & str) -> Res< (Context, CtxtRes) > {
   use std::fs::File ;
+  use std::io::Read ;
   use term::Factory ;
   match File::open(path) {
     Ok(mut file) => {
       let factory = Factory::mk() ;
       let mut context = Context::mk(factory, 1000) ;
-      match context.read(& mut file) {
+      let read = if path.ends_with(".smv") {
+        let mut txt = String::new() ;
+        if let Err(e) = file.read_to_string(& mut txt) {
+          bail!( ErrorKind::SysError( SysError::Io(e) ) )
+        }
+        match system::smv::translate(& txt) {
+          Ok(txt) => context.read(& mut txt.as_bytes()),
+          Err(e) => bail!( ErrorKind::AnalysisError(e) ),
+        }
+      } else {
+        context.read(& mut file)
+      } ;
+      match read {
         Ok(res) => Ok( (context, res) ),
         Err(e) => bail!( ErrorKind::SysError(e) ),
       }
@@ -88,13 +110,19 @@ pub fn load(path: & str) -> Res< (Context, CtxtRes) > {
 
 /// Loads a file, creates a context, runs the master.
 pub fn analyze(path: & str) -> Res<(Context, Vec<Prop>)> {
+  analyze_with( path, ::common::conf::Master::default() )
+}
+
+/// Loads a file, creates a context, runs the master with a configuration.
+pub fn analyze_with(
+  path: & str, conf: ::common::conf::Master
+) -> Res<(Context, Vec<Prop>)> {
   let (mut context, res) = try!( load(path) ) ;
   match res {
     CtxtRes::Success => Err("got success".into()),
     CtxtRes::Exit => Ok( (context, vec![]) ),
     CtxtRes::Check(sys, props) => {
       let log = ::common::log::MasterLog::default() ;
-      let conf = ::common::conf::Master::default() ;
       match Master::launch(
         & log, & mut context, sys, props.clone(), None, conf
       ) {
@@ -24,31 +24,69 @@ extern crate common ;
 extern crate bmc ;
 extern crate kind ;
 extern crate twind ;
+extern crate cegar ;
 extern crate tig ;
 extern crate pruner ;
 
 use std::process::exit ;
 
 use system::ctxt::* ;
+use system::library::LibCache ;
 
 use common::Tek::Kino ;
-use common::log::MasterLog ;
+use common::log::{ MasterLog, JsonSink, Formatter, Styler } ;
 
 pub mod master ;
+pub mod verdicts ;
+pub mod history ;
 
 use master::Master ;
 
+/// Cleans up the resources of the run and exits.
+fn stop<F: Formatter, S: Styler>(log: & MasterLog<F, S>, code: i32) -> ! {
+  for msg in common::cleanup::run(false).into_iter() {
+    log.sad(& Kino, & msg)
+  }
+  exit(code)
+}
+
 fn main() {
   use std::fs::File ;
 
-  let log = MasterLog::default() ;
+  common::cleanup::install() ;
+  common::crash::install() ;
 
-  log.sep() ;
-  log.sep() ;
+  let log = MasterLog::default() ;
 
+  // `kino stats <file>` summarizes a history file, see `--history`.
+  let args: Vec<String> = ::std::env::args().skip(1).collect() ;
+  if args.first().map_or(false, |arg| arg == "stats") {
+    log.sep() ;
+    log.sep() ;
+    log.title("Statistics") ;
+    let res = match args.get(1) {
+      Some(file) if args.len() == 2 => history::report(& log, file),
+      _ => Err( "expected \"kino stats <file>\"".to_string() ),
+    } ;
+    let code = match res {
+      Ok(()) => 0,
+      Err(e) => {
+        log.bad(& Kino, & e) ;
+        2
+      },
+    } ;
+    log.trail() ;
+    log.sep() ;
+    log.sep() ;
+    exit(code)
+  }
+
+  // Options are parsed first to know whether logging is quiet.
   let (conf, file) = match common::conf::Master::mk(& log) {
     Ok(conf) => conf,
     Err(e) => {
+      log.sep() ;
+      log.sep() ;
       log.title("CLA parsing") ;
       log.nl() ;
       log.bad(& Kino, & e) ;
@@ -59,14 +97,94 @@ fn main() {
     },
   } ;
 
+  let log = if conf.json {
+    log.json_mode()
+  } else if conf.quiet { log.quiet() } else { log } ;
+  let log = if conf.full_cex { log.full_cex() } else { log } ;
+  let log = match conf.precision {
+    Some(precision) => log.precision(precision),
+    None => log,
+  } ;
+  let log = match conf.events {
+    Some(ref file) => match File::create(file) {
+      Ok(f) => log.sink( JsonSink::mk(f) ),
+      Err(e) => {
+        log.sep() ;
+        log.sep() ;
+        log.bad(
+          & Kino, & format!("could not create {:?}:\n{}", file, e)
+        ) ;
+        log.trail() ;
+        log.sep() ;
+        log.sep() ;
+        exit(2)
+      },
+    },
+    None => log,
+  } ;
+
+  log.sep() ;
+  log.sep() ;
+
+  for warning in conf.warnings.iter() {
+    log.sad(& Kino, warning)
+  }
+
   let factory = term::Factory::mk() ;
-  let mut context = Context::mk(factory, 10000) ;
+  let mut context = Context::mk(factory.clone(), 10000) ;
+
+  // Libraries, parsed once and added to the context before the model.
+  let mut libs = LibCache::mk() ;
+  for lib in conf.libs.iter() {
+    log.title( & format!("loading library \"{}\"", lib) ) ;
+    match libs.load(lib, & factory).and_then(
+      |lib| context.add_library(& lib)
+    ) {
+      Ok(()) => log.print( & log.mk_happy("success") ),
+      Err(e) => {
+        log.nl() ;
+        log.bad(& Kino, & e) ;
+        log.trail() ;
+        log.sep() ;
+        stop(& log, 2)
+      },
+    }
+  }
+
   log.title( & format!("opening \"{}\"", file) ) ;
   match File::open(& file) {
     Ok(mut f) => {
       log.print( & log.mk_happy("success") ) ;
+
+      // SMV models are translated to the input language first.
+      let smv = if file.ends_with(".smv") {
+        use std::io::Read ;
+        log.title("translating from SMV") ;
+        let mut txt = String::new() ;
+        let translated = f.read_to_string(& mut txt).map_err(
+          |e| format!("could not read file \"{}\":\n> {}", file, e)
+        ).and_then( |_| system::smv::translate(& txt) ) ;
+        match translated {
+          Ok(txt) => {
+            log.print( & log.mk_happy("success") ) ;
+            Some(txt)
+          },
+          Err(e) => {
+            log.nl() ;
+            log.bad(& Kino, & e) ;
+            log.trail() ;
+            log.sep() ;
+            stop(& log, 2)
+          },
+        }
+      } else { None } ;
+
       log.title("parsing") ;
-      match context.read(& mut f) {
+      let res = match smv {
+        Some(ref txt) => context.read(& mut txt.as_bytes()),
+        None => context.read(& mut f),
+      } ;
+      match res {
         Ok(res) => {
           log.print( & log.mk_happy("success") ) ;
 
@@ -80,6 +198,17 @@ fn main() {
             log.print(line)
           } ;
 
+          // Systems selected with `--sys` replace the verify query. Exits
+          // with `1` if one of them is unsafe.
+          if ! conf.systems.is_empty() {
+            log.trail() ;
+            match Master::launch_selected(& log, & mut context, conf) {
+              Ok(false) => stop(& log, 0),
+              Ok(true) => stop(& log, 1),
+              Err(()) => stop(& log, 2),
+            }
+          }
+
           match res {
             Res::Success => panic!("update master to work with new parsers"),
             Res::Exit => log.trail(),
@@ -88,8 +217,8 @@ fn main() {
               match Master::launch(
                 & log, & mut context, sys, props, None, conf
               ) {
-                Ok(()) => exit(0),
-                Err(()) => exit(2),
+                Ok(()) => stop(& log, 0),
+                Err(()) => stop(& log, 2),
               }
             },
             Res::CheckAss(_, _, _) => {
@@ -118,5 +247,6 @@ fn main() {
     },
   } ;
 
-  log.sep()
+  log.sep() ;
+  stop(& log, 0)
 }
@@ -12,25 +12,44 @@
 //! It runs on a system and tries to prove some properties.
 
 use std::sync::Arc ;
-use std::collections::HashMap ;
+use std::time::Duration ;
+use std::collections::{ HashMap, HashSet } ;
 
-use term::{ Term, STermSet } ;
+use term::{ Term, Sym, SymMaker, Offset, STerm, STermSet } ;
+use term::smt::SolverStyle ;
 
-use system::{ Prop, Sys } ;
+use system::{ Prop, Sys, PropStatus } ;
 use system::ctxt::Context ;
+use system::deps::DepGraph ;
+use system::order ;
+use system::pre ;
+use system::live ;
 
-use common::Tek::Kino ;
+use common::Tek ;
+use common::Tek::{ Kino, Tec } ;
 use common::conf ;
+use common::conf::StopOn ;
+use common::proof ;
+use common::pause ;
 use common::msg::MsgUp::* ;
-use common::msg::{ KidManager, MsgDown, Info, Status } ;
-use common::log::{ MasterLog, Formatter, Styler } ;
+use common::msg::{ KidManager, QueryBudget, MsgDown, Info, Status } ;
+use common::clock::Clock ;
+use common::msg::Unknown as Why ;
+use common::log::{ MasterLog, Formatter, Styler, Curves, fmt_duration } ;
+use common::samples::{ SamplePool, DEFAULT_CAPACITY } ;
+use common::falsify::Falsifier ;
 
 use bmc ;
 use kind ;
 use twind ;
+use cegar ;
 use tig ;
 use pruner ;
 
+use verdicts::{ Verdict, VerdictCache } ;
+use history ;
+use history::Decided ;
+
 /// If the result is an error, prints it using `bad`.
 macro_rules! try_log {
   ($e:expr, $log:expr, $( $arg:expr ),+ ) => (
@@ -83,15 +102,658 @@ impl Master {
     log.title( & format!("Running on {}", sys.sym().sym()) ) ;
     log.nl() ;
 
+    // State variables in the order asked, for the unrollers and the cexs.
+    let sys = try_log_run!(
+      order::reorder(& sys, & conf.order, c.factory()), log, {
+        log.trail() ;
+        return Err(())
+      }, "while ordering the state variables of {}", sys.sym()
+    ) ;
+
+    if let Some(name) = Self::unknown_prop(& props, & conf) {
+      log.bad(
+        & Kino, & format!(
+          "no property named \"{}\" in the verify query", name
+        )
+      ) ;
+      log.trail() ;
+      return Err(())
+    }
+    let props = Self::select_tagged(log, c, props, & conf) ;
+
+    // History variables of the state variables of the properties, if asked.
+    let sys = if conf.pre > 0 {
+      let svars = pre::select(& sys, & props) ;
+      try_log_run!(
+        pre::add_history(& sys, & svars, conf.pre, c.factory()), log, {
+          log.trail() ;
+          return Err(())
+        }, "while adding history variables to {}", sys.sym()
+      )
+    } else { sys } ;
+
+    // Liveness properties reduced to safety ones on an extended system.
+    let (sys, props) = try_log_run!(
+      live::reduce(& sys, & props, conf.live, c.factory()), log, {
+        log.trail() ;
+        return Err(())
+      }, "while reducing the liveness properties of {}", sys.sym()
+    ) ;
+
+    // System sliced to the properties and the state variables asked, if
+    // asked.
+    let sys = match conf.slice {
+      Some(ref vars) => try_log_run!(
+        Self::slice(log, c, & sys, & props, vars), log, {
+          log.trail() ;
+          return Err(())
+        }, "while slicing {}", sys.sym()
+      ),
+      None => sys,
+    } ;
+
+    // Dependency graph of the state variables, if asked.
+    if let Some(ref file) = conf.deps {
+      try_log!(
+        Self::write_deps(& sys, file), log,
+        "while writing the dependency graph to {}", file
+      )
+    }
+
+    // AIGER export of boolean-only systems, if asked.
+    if let Some(ref file) = conf.aiger {
+      try_log!(
+        Self::write_aiger(c, & sys, & props, file), log,
+        "while writing the system in the AIGER format to {}", file
+      )
+    }
+
+    // VMT export, if asked.
+    if let Some(ref file) = conf.vmt {
+      try_log!(
+        Self::write_vmt(& sys, & props, file), log,
+        "while writing the system in the VMT format to {}", file
+      )
+    }
+
+    // Branches of the transition relation never taken, if asked.
+    if let Some(bound) = conf.dead_code {
+      let bmc_conf = conf.bmc.clone().unwrap_or_else( conf::Bmc::default ) ;
+      try_log!(
+        Self::report_dead_code(log, c, & sys, & bmc_conf, bound), log,
+        "while looking for the branches never taken"
+      )
+    }
+
+    // Conjunctions split in sub-properties if asked, checked instead of
+    // them.
+    let checked = match conf.split {
+      Some(min) => try_log_run!(
+        Self::split(log, c, & props, min), log, {
+          log.trail() ;
+          return Err(())
+        }, "while splitting the properties"
+      ),
+      None => props.clone(),
+    } ;
+
+    // Directory of the proofs about the system, cleared so that the proofs
+    // of previous runs are not checked again.
+    let proofs = conf.kind.as_ref().and_then(
+      |kind| kind.proofs().as_ref().map(
+        |dir| proof::dir_of( dir, sys.sym().get() )
+      )
+    ) ;
+    if let Some(ref dir) = proofs {
+      try_log_run!(
+        proof::clear(dir), log, {
+          log.trail() ;
+          return Err(())
+        }, "while clearing the proofs in {}", dir
+      )
+    }
+
+    // Proofs to replay at the end, if asked.
+    let check_proofs = conf.check_proofs.clone().map(
+      |cmd| ( cmd, proofs.clone() )
+    ) ;
+
+    // Solver checking again the certificates at the end and the
+    // configuration to run it with, if asked.
+    let double_check = conf.double_check.clone().map(
+      |smt| ( smt, conf.kind.clone().unwrap_or_else( conf::Kind::default ) )
+    ) ;
+
+    // Verdicts of previous runs, for the lemmas the techniques can use: the
+    // properties checked and the invariants known for the system and its
+    // sub-systems.
+    let verdicts = conf.cache.as_ref().map(
+      |dir| {
+        let mut lemmas = STermSet::new() ;
+        for sym in Some( sys.sym() ).into_iter().chain(
+          sys.subsys().iter().map( |& (ref sub, _)| sub.sym() )
+        ) {
+          if let Some(invs) = c.get_invs( sym.get() ) {
+            lemmas.extend( invs.iter().cloned() )
+          }
+        }
+        for prop in checked.iter() {
+          lemmas.insert( prop.body().clone() ) ; ()
+        }
+        VerdictCache::mk(dir, & sys, & lemmas)
+      }
+    ) ;
+    let to_check = match verdicts {
+      Some(ref cache) if ! conf.fresh => try_log_run!(
+        Self::cached(log, c, & sys, cache, & checked), log, {
+          log.trail() ;
+          return Err(())
+        }, "while retrieving cached verdicts"
+      ),
+      _ => checked.clone(),
+    } ;
+
+    // Budget of check-sats, shared by all the runs.
+    let budget = QueryBudget::mk(conf.max_queries) ;
+    // Time budget, shared by all the runs.
+    let clock = Clock::mk( conf.timeout.map(Duration::from_secs) ) ;
+
+    // Time spent at each depth by the techniques, reported with the
+    // statistics.
+    let stats = conf.stats ;
+    let mut curves = Curves::new() ;
+
+    let stop_on = conf.stop_on ;
+
+    // History file and versions of the solvers, if recording.
+    let record = conf.history.clone().map(
+      |file| ( file, history::solvers(& conf) )
+    ) ;
+
+    // Properties with options scoped to them are checked separately, with
+    // their own configuration.
+    let mut runs = Vec::with_capacity(3) ;
+    if conf.has_prop_opts() {
+      let mut global = Vec::with_capacity( to_check.len() ) ;
+      for prop in to_check.into_iter() {
+        match conf.for_prop( prop.sym().get().sym() ) {
+          Ok(None) => global.push(prop),
+          Ok( Some(prop_conf) ) => runs.push(
+            (prop_conf, vec![prop], true)
+          ),
+          Err(e) => {
+            log.bad(& Kino, & e) ;
+            log.trail() ;
+            return Err(())
+          },
+        }
+      }
+      if ! global.is_empty() {
+        runs.insert(0, (conf, global, false))
+      }
+    } else if ! to_check.is_empty() {
+      runs.push( (conf, to_check, false) )
+    }
+
+    // Result returned at the end.
+    let mut result = Ok(()) ;
+
+    // Why the analysis stopped early, if it did.
+    let mut reason = None ;
+    // Why the techniques stopped without (dis)proving the properties.
+    let mut why = vec![] ;
+
+    let start_time = Instant::now() ;
+    let mut decided = Decided::mk() ;
+    // Properties added during the runs, see `pause::add_props`.
+    let mut added = vec![] ;
+
+    for (conf, to_check, scoped) in runs.into_iter() {
+      // A cached verdict or a previous run can meet the stop policy.
+      let must_stop = try_log_run!(
+        Self::must_stop(c, & checked, stop_on), log, {
+          log.trail() ;
+          return Err(())
+        }, "while checking the stop policy"
+      ) ;
+      if must_stop {
+        if reason.is_none() { reason = Self::stop_reason(stop_on) }
+        break
+      }
+      // The time budget was exhausted by a previous run.
+      if clock.is_over() { break }
+      if scoped {
+        log.log(
+          & Kino, & format!(
+            "checking {} with its own options", to_check[0].sym()
+          )
+        )
+      }
+      let res = match conf.staged {
+        Some(queries) => Self::run_staged(
+          log, c, & sys, to_check, conf, & verdicts, & budget, & clock,
+          queries,
+          & mut reason, & mut why, & mut curves, & mut decided, & mut added
+        ),
+        None => Self::run(
+          log, c, & sys, to_check, conf, & verdicts, & budget, & clock,
+          & mut reason,
+          & mut why, & mut curves, & mut decided, & mut added
+        ),
+      } ;
+      if res.is_err() {
+        result = Err(())
+      }
+    }
+
+    let time = Instant::now() - start_time ;
+
+    // The verdicts on the properties added count too.
+    let props: Vec<Prop> = props.into_iter().chain(added).collect() ;
+
+    // Before recombining, the proofs are about the sub-properties.
+    match check_proofs {
+      Some( (ref cmd, Some(ref dir)) ) => try_log!(
+        Self::check_proofs(log, c, dir, cmd), log,
+        "while checking the proofs in {}", dir
+      ),
+      Some( (_, None) ) => log.bad(
+        & Kino, "cannot check proofs, kind does not store them \
+        (see its \"proofs\" option)"
+      ),
+      None => (),
+    }
+
+    try_log!(
+      Self::recombine(log, c, & props), log,
+      "while recombining the sub-properties"
+    ) ;
+
+    if let Some( (ref file, ref solvers) ) = record {
+      try_log!(
+        history::record(file, solvers, c, & sys, & props, & decided, time),
+        log, "while recording the run in {}", file
+      )
+    }
+
+    if let Some( (ref smt, ref kind_conf) ) = double_check {
+      Self::double_check(log, c, & sys, & props, smt, kind_conf)
+    }
+
+    if stats {
+      log.log_curves(& curves)
+    }
+
+    let some_prop_disproved = try_log_run!(
+      c.some_prop_disproved(& props), log, {
+        log.just_log_unknown() ;
+        return Err(())
+      }, "during post-run analysis"
+    ) ;
+    let some_prop_unknown = try_log_run!(
+      c.some_prop_unknown(& props), log, {
+        log.just_log_unknown() ;
+        return Err(())
+      }, "during post-run analysis"
+    ) ;
+
+    if ! some_prop_disproved {
+      if ! some_prop_unknown {
+        log.log_safe(time)
+      } else {
+        log.log_unknown(
+          try_log_run!(
+            c.unknown_props(& props), log, {
+              log.just_log_unknown() ;
+              return Err(())
+            }, "during post-run analysis"
+          ).into_iter(),
+          time, reason.as_ref().map(|s| & s[..]), & why
+        )
+      }
+    } else {
+      log.log_unsafe(time)
+    }
+
+    log.trail() ;
+
+    result
+
+  }
+
+  /// Launches the master on the systems selected by the `--sys` patterns of
+  /// the configuration, one after the other, with all their properties.
+  /// Logs a summary of the verdicts at the end. Returns true if some
+  /// property was falsified.
+  pub fn launch_selected<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, conf: conf::Master
+  ) -> Result<bool, ()> {
+    let selected: Vec<(Sys, Vec<Prop>)> = c.systems().into_iter().filter(
+      |sys| conf.selects_sys( sys.sym().get().sym() )
+    ).map(
+      |sys| ( sys.clone(), c.props_of( sys.sym().get() ) )
+    ).collect() ;
+    {
+      let all: Vec<Prop> = selected.iter().flat_map(
+        |& (_, ref props)| props.iter().cloned()
+      ).collect() ;
+      if let Some(name) = Self::unknown_prop(& all, & conf) {
+        log.bad(
+          & Kino, & format!(
+            "no property named \"{}\" in the systems selected", name
+          )
+        ) ;
+        log.trail() ;
+        return Err(())
+      }
+    }
+    let selected: Vec<(Sys, Vec<Prop>)> = selected.into_iter().map(
+      |(sys, props)| (sys, Self::select_tagged(log, c, props, & conf))
+    ).collect() ;
+    for pattern in conf.systems.iter() {
+      let matched = selected.iter().any(
+        |& (ref sys, _)| conf::glob_matches( pattern, sys.sym().get().sym() )
+      ) ;
+      if ! matched {
+        log.bad(
+          & Kino, & format!("no system matches \"{}\"", pattern)
+        ) ;
+        log.trail() ;
+        return Err(())
+      }
+    }
+
+    let mut result = Ok(()) ;
+    let mut unsafe_sys = false ;
+    let mut summary = Vec::with_capacity( selected.len() ) ;
+    for (sys, props) in selected.into_iter() {
+      if props.is_empty() {
+        log.log(
+          & Kino, & format!("no property to check on {}", sys.sym())
+        ) ;
+        summary.push( (sys.sym().get().clone(), "no properties", 0) ) ;
+        continue
+      }
+      // Options of `--reload`, read again for each system. Unreadable ones
+      // leave the configuration of the command line.
+      let mut sys_conf = match conf.reloaded() {
+        Ok(sys_conf) => sys_conf,
+        Err(e) => {
+          log.bad(
+            & Kino, & format!("could not reload the options: {}", e)
+          ) ;
+          conf.clone()
+        },
+      } ;
+      // Properties already selected by their names, some of which belong
+      // to the other systems.
+      sys_conf.props.clear() ;
+      if Self::launch(
+        log, c, sys.clone(), props.clone(), None, sys_conf
+      ).is_err() {
+        result = Err(())
+      }
+      let verdict = match (
+        c.some_prop_disproved(& props), c.some_prop_unknown(& props)
+      ) {
+        ( Ok(true), _ ) => {
+          unsafe_sys = true ;
+          "unsafe"
+        },
+        ( Ok(false), Ok(false) ) => "safe",
+        _ => "unknown",
+      } ;
+      summary.push( (sys.sym().get().clone(), verdict, props.len()) )
+    }
+
+    log.title("Summary") ;
+    log.nl() ;
+    log.log_summary(& summary) ;
+    log.trail() ;
+    result.map( |()| unsafe_sys )
+  }
+
+  /// The first property name of `--prop` none of some properties has, if
+  /// any.
+  fn unknown_prop<'a>(
+    props: & [Prop], conf: & 'a conf::Master
+  ) -> Option<& 'a String> {
+    conf.props.iter().find(
+      |name| ! props.iter().any(
+        |prop| prop.sym().get().sym() == name.as_str()
+      )
+    )
+  }
+
+  /// The properties selected by their names and tags, see `--prop`,
+  /// `--only-tag` and `--skip-tag`.
+  fn select_tagged<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, props: Vec<Prop>,
+    conf: & conf::Master
+  ) -> Vec<Prop> {
+    let (selected, skipped): (Vec<Prop>, Vec<Prop>) = props.into_iter(
+    ).partition(
+      |prop| conf.selects_prop( prop.sym().get().sym() ) && conf.selects_tags(
+        c.prop_tags( prop.sym().get() )
+      )
+    ) ;
+    if ! skipped.is_empty() {
+      log.log(
+        & Kino, & format!(
+          "skipping {} property(ies) because of their names or tags",
+          skipped.len()
+        )
+      )
+    }
+    selected
+  }
+
+  /// Splits the properties that are a conjunction of at least `min`
+  /// conjuncts, see `--split`. Returns the properties to check: the
+  /// sub-properties of the ones split, and the other ones.
+  fn split<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, props: & [Prop], min: usize
+  ) -> Result<Vec<Prop>, String> {
+    let mut res = Vec::with_capacity( props.len() ) ;
+    for prop in props.iter() {
+      let subs = try!( c.split_prop(prop.sym().get(), min) ) ;
+      if subs.is_empty() {
+        res.push( prop.clone() )
+      } else {
+        log.log(
+          & Kino, & format!(
+            "splitting {} in {} sub-properties", prop.sym(), subs.len()
+          )
+        ) ;
+        res.extend(subs)
+      }
+    }
+    Ok(res)
+  }
+
+  /// Recombines the statuses of the sub-properties of the properties split,
+  /// logging how many of them are decided.
+  fn recombine<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, props: & [Prop]
+  ) -> Result<(), String> {
+    for prop in props.iter() {
+      if let Some( (count, proved, falsified) ) = try!(
+        c.recombine_prop( prop.sym().get() )
+      ) {
+        log.log(
+          & Kino, & format!(
+            "{}: {} of {} sub-properties proved, {} falsified",
+            prop.sym(), proved, count, falsified
+          )
+        )
+      }
+    }
+    Ok(())
+  }
+
+  /// Checks some properties in two stages.
+  ///
+  /// The first stage runs all the techniques but invariant generation, with
+  /// a budget of `queries` check-sats. The second one runs all the
+  /// techniques on the properties left, with the rest of the budget.
+  fn run_staged<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, sys: & Sys, props: Vec<Prop>,
+    conf: conf::Master, verdicts: & Option<VerdictCache>,
+    budget: & QueryBudget, clock: & Clock, queries: usize,
+    reason: & mut Option<String>,
+    why: & mut Vec<(Tek, Why)>, curves: & mut Curves,
+    decided: & mut Decided, added: & mut Vec<Prop>
+  ) -> Result<(), ()> {
+    let mut first = conf.clone() ;
+    first.tig = None ;
+    first.pruner = None ;
+    log.log(
+      & Kino, & format!(
+        "stage 1: {} property(ies), {} check-sats", props.len(), queries
+      )
+    ) ;
+    try!(
+      Self::run(
+        log, c, sys, props.clone(), first, verdicts, & budget.sub(queries),
+        clock, reason, why, curves, decided, added
+      )
+    ) ;
+
+    let left: Vec<Prop> = {
+      let unknown = try_log_run!(
+        c.unknown_props(& props), log, { return Err(()) },
+        "after the first stage"
+      ) ;
+      props.iter().filter(
+        |prop| unknown.contains( & prop.sym().get() )
+      ).cloned().collect()
+    } ;
+    if left.is_empty() || budget.is_exhausted() || clock.is_over() {
+      return Ok(())
+    }
+    let must_stop = try_log_run!(
+      Self::must_stop(c, & props, conf.stop_on), log, { return Err(()) },
+      "after the first stage"
+    ) ;
+    if must_stop { return Ok(()) }
+
+    // Whatever stopped the first stage early does not apply to the second
+    // one.
+    * reason = None ;
+    why.clear() ;
+    log.log(
+      & Kino, & format!("stage 2: {} property(ies) left", left.len())
+    ) ;
+    Self::run(
+      log, c, sys, left, conf, verdicts, budget, clock, reason, why,
+      curves, decided, added
+    )
+  }
+
+  /// Launches the techniques on some properties and handles their messages
+  /// until they are done or the properties are all proved or disproved.
+  ///
+  /// Stops early when a technique reaches a fixpoint of invariant
+  /// generation or when the stop policy is met, `reason` is then the reason
+  /// why the properties left are unknown. Why the techniques stop without
+  /// (dis)proving the properties is added to `why`, the time spent at each
+  /// depth to `curves`, the properties decided to `decided`, and the ones
+  /// added during the run to `added`.
+  fn run<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, sys: & Sys, props: Vec<Prop>,
+    conf: conf::Master, verdicts: & Option<VerdictCache>,
+    budget: & QueryBudget, clock: & Clock, reason: & mut Option<String>,
+    why: & mut Vec<(Tek, Why)>, curves: & mut Curves,
+    decided: & mut Decided, added: & mut Vec<Prop>
+  ) -> Result<(), ()> {
     // Creating manager for techniques.
-    let mut manager = KidManager::mk() ;
+    let mut manager = KidManager::mk_with_budget(
+      budget.clone()
+    ).with_clock(* clock) ;
+    // Pause and resume requests of the operator.
+    pause::register( manager.sender() ) ;
+    // Changes of the `--reload` file, and the configuration it applies to.
+    let _watch = conf.reload.clone().map(
+      |path| pause::watch( path, manager.sender() )
+    ) ;
+    let reload_conf = conf.clone() ;
+
+    let stats = conf.stats ;
+    let stop_on = conf.stop_on ;
+    let crash_dir = conf.crash_dir.clone() ;
+    let live_bound = conf.live ;
+
+    // Solver confirming the properties proved and the configuration to run
+    // it with, if paranoid.
+    let paranoid = conf.paranoid.clone().map(
+      |smt| ( smt, conf.kind.clone().unwrap_or_else( conf::Kind::default ) )
+    ) ;
+    // Properties proved but not confirmed yet, with the technique that
+    // proved them.
+    let mut unconfirmed: HashMap<Sym, Tek> = HashMap::new() ;
+    // Properties whose proof a certificate check confirmed, reported as
+    // proved when their `Proved` message comes back.
+    let mut certified: HashSet<Sym> = HashSet::new() ;
+    // Number of certificate checks running.
+    let mut certifying = 0usize ;
+    // Number of `unknown` answers of the solver of each technique.
+    let mut unknowns: HashMap<Tek, usize> = HashMap::new() ;
+
+    // Techniques deciding the properties that are off.
+    if ! conf.bmc.as_ref().map_or( false, |conf| * conf.is_on() )
+    && ! conf.cegar.as_ref().map_or( false, |conf| * conf.is_on() ) {
+      why.push(
+        ( Tek::Bmc, Why::Disabled("no property can be falsified".into()) )
+      )
+    }
+    if ! conf.kind.as_ref().map_or( false, |conf| * conf.is_on() )
+    && ! conf.twind.as_ref().map_or( false, |conf| * conf.is_on() )
+    && ! conf.cegar.as_ref().map_or( false, |conf| * conf.is_on() ) {
+      why.push(
+        ( Tek::KInd, Why::Disabled("no property can be proved".into()) )
+      )
+    }
 
     // Launching BMC.
     match conf.bmc {
       None => (),
       Some(conf) => if * conf.is_on() {
+        // Scenarios and preferences are sent to BMC along with the
+        // properties.
+        let mut bmc_props = props.clone() ;
+        for (desc, name) in conf.scenario_names().into_iter().map(
+          |name| ("scenario", name)
+        ).chain(
+          conf.preference_names().into_iter().map(
+            |name| ("preference", name)
+          )
+        ) {
+          match c.get_prop( & c.factory().sym(name) ) {
+            Some( & (ref prop, _) ) if (
+              prop.sys().sym().get() == sys.sym().get()
+            ) => {
+              if let STerm::Two(_) = * prop.body() {
+                log.bad(
+                  & Kino, & format!(
+                    "{} `{}` is a two-state property", desc, name
+                  )
+                ) ;
+                return Err(())
+              }
+              bmc_props.push( prop.clone() )
+            },
+            _ => {
+              log.bad(
+                & Kino, & format!(
+                  "unknown {} `{}` for system {}", desc, name, sys.sym()
+                )
+              ) ;
+              return Err(())
+            },
+          }
+        }
         match manager.launch(
-          bmc::Bmc, sys.clone(), props.clone(), c.factory(), Arc::new(conf)
+          bmc::Bmc, sys.clone(), bmc_props, c.factory(), Arc::new(conf)
         ) {
           Ok(()) => (),
           Err(errors) => {
@@ -129,7 +791,8 @@ impl Master {
       None => (),
       Some(conf) => if * conf.is_on() {
         match manager.launch(
-          twind::Twind, sys.clone(), props.clone(), c.factory(), Arc::new(conf)
+          twind::Twind, sys.clone(), props.clone(),
+          c.factory(), Arc::new(conf)
         ) {
           Ok(()) => (),
           Err(errors) => {
@@ -143,6 +806,26 @@ impl Master {
       },
     } ;
 
+    // Launching predicate abstraction.
+    match conf.cegar {
+      None => (),
+      Some(conf) => if * conf.is_on() {
+        match manager.launch(
+          cegar::Cegar, sys.clone(), props.clone(),
+          c.factory(), Arc::new(conf)
+        ) {
+          Ok(()) => (),
+          Err(errors) => {
+            log.bad(& Kino, "Error launching predicate abstraction:") ;
+            for err in errors.iter() {
+              log.bad(& Kino, & format!("> {}", err))
+            }
+            return Err(())
+          },
+        }
+      },
+    } ;
+
     // Launching invgen.
     match conf.tig {
       None => (),
@@ -185,15 +868,41 @@ impl Master {
     // Result returned when exting the loop.
     let mut result = Ok(()) ;
 
-    let start_time = Instant::now() ;
+    // Concrete reachable states sent by the kids.
+    let mut samples = SamplePool::mk(DEFAULT_CAPACITY) ;
+
+    // Checks the invariants broadcast on the concrete traces in the
+    // background.
+    let falsifier = match Falsifier::launch(
+      manager.sender(), sys.sym().get().clone(), c.factory().clone()
+    ) {
+      Ok(falsifier) => falsifier,
+      Err(errors) => {
+        log.bad(& Kino, "Error launching invariant falsifier:") ;
+        for err in errors.iter() {
+          log.bad(& Kino, & format!("> {}", err))
+        }
+        return Err(())
+      },
+    } ;
+
+    // Pause requested before the kids were launched.
+    if pause::paused() {
+      log.log(& Kino, "pausing the techniques") ;
+      manager.pause()
+    }
+
+    // Properties of the run, shadowed in the message loop. Properties added
+    // during the run end up here too.
+    let mut checked = props ;
 
     // Entering message loop.
     'msg_loop: loop {
-      // Stopping if no more kids running.
-      if manager.kids_done() { break } ;
+      // Stopping if no more kids running, and no certificate check.
+      if manager.kids_done() && certifying == 0 { break } ;
       // Stopping if no property left to prove.
       if ! try_log_run!(
-        c.some_prop_unknown(& props), log, {
+        c.some_prop_unknown(& checked), log, {
           result = Err(()) ;
           break 'msg_loop
         },
@@ -203,21 +912,55 @@ impl Master {
       }
 
       // Receiving a message.
-      match manager.recv() {
+      let msg = match manager.recv_in_time() {
+        Ok( Some(msg) ) => Ok(msg),
+        Ok(None) => {
+          let blah = format!(
+            "time budget of {} exhausted",
+            fmt_duration( clock.budget().unwrap_or_default() )
+          ) ;
+          log.sad(& Kino, & blah) ;
+          * reason = Some(blah) ;
+          break 'msg_loop
+        },
+        Err(e) => Err(e),
+      } ;
+      match msg {
 
         Ok( Bla(from, bla) ) => log.log(& from, & bla),
 
-        Ok( Error(from, errors) ) => {
-          log.bad(& from, "Error:") ;
-          for err in errors.iter() {
-            log.bad(& from, & format!("> {}", err))
-          }
-        },
+        Ok( Error(from, error) ) => log.log_error_chain(& from, & error),
 
         Ok( Warning(from, bla) ) => log.sad(& from, & bla),
 
         Ok( Disproved(model, props, from, _) ) => {
-          let cex = c.cex_of(& model, & sys) ;
+          // Falsifying the reduction of a liveness property proves nothing.
+          let (props, bounded): (Vec<Sym>, Vec<Sym>) = props.into_iter(
+          ).partition(
+            |prop| match c.get_prop(prop) {
+              Some( & (ref prop, _) ) => prop.live().is_none(),
+              None => true,
+            }
+          ) ;
+          if ! bounded.is_empty() {
+            for prop in bounded.iter() {
+              log.sad(
+                & from, & format!(
+                  "liveness property {} exceeds its bound {}, unknown",
+                  prop, live_bound
+                )
+              )
+            }
+            // They stay unknown, they are not left to check for all that.
+            checked.retain(
+              |prop| ! bounded.contains( prop.sym().get() )
+            ) ;
+            Self::forget(
+              log, c, & mut manager, & bounded, Status::Disproved, & checked
+            )
+          }
+          if props.is_empty() { continue 'msg_loop }
+          let cex = c.cex_of(& model, sys) ;
           for prop in props.iter() {
             try_log_run!(
               c.set_prop_false(prop, cex.clone()), log, {
@@ -225,13 +968,44 @@ impl Master {
                 break 'msg_loop
               },
               "on disproved message from {}", from
-            )
+            ) ;
+            decided.decide(prop, from)
           }
           log.log_cex(& from, & cex, & props) ;
-          manager.broadcast( MsgDown::Forget(props, Status::Disproved) ) ;
+          let suggestions = cex.suggest_assumptions() ;
+          if ! suggestions.is_empty() {
+            log.log_suggestions(
+              & from, sys.sym().get(), & props, & suggestions
+            )
+          }
+          if let Some(ref cache) = * verdicts {
+            let verdict = Verdict::Falsified(model) ;
+            for prop in props.iter() {
+              Self::cache_verdict(log, c, cache, prop, & verdict)
+            }
+          }
+          Self::forget(
+            log, c, & mut manager, & props, Status::Disproved, & checked
+          ) ;
+          if stop_on == StopOn::FirstCex {
+            * reason = Self::stop_reason(stop_on) ;
+            break 'msg_loop
+          }
         },
 
         Ok( Proved(props, from, info) ) => {
+          let props = match paranoid {
+            None => props,
+            Some( (ref smt, ref kind_conf) ) => {
+              let (confirmed, checking) = Self::confirm(
+                log, c, sys, props, & from, & info, smt, kind_conf,
+                & mut unconfirmed, & mut certified, & manager
+              ) ;
+              if checking { certifying += 1 }
+              confirmed
+            },
+          } ;
+          if props.is_empty() { continue 'msg_loop }
           log.log_proved(& from, & props, & info) ;
           let mut invs = STermSet::with_capacity(props.len()) ;
           for prop in props.iter() {
@@ -243,7 +1017,8 @@ impl Master {
                 ) ;
                 continue
               },
-              Some(ref prop) => {
+              // The body of a liveness property is not an invariant.
+              Some(ref prop) => if prop.0.live().is_none() {
                 invs.insert( prop.0.body().clone() ) ;
                 ()
               },
@@ -254,15 +1029,29 @@ impl Master {
                 break 'msg_loop
               },
               "on proved message from {}", from
-            )
+            ) ;
+            decided.decide(prop, from) ;
+            if let Some(ref cache) = * verdicts {
+              Self::cache_verdict(
+                log, c, cache, prop, & Verdict::Proved( info.to_usize() )
+              )
+            }
           } ;
-          manager.broadcast( MsgDown::Forget(props, Status::Proved) ) ;
+          Self::forget(
+            log, c, & mut manager, & props, Status::Proved, & checked
+          ) ;
+          falsifier.invariants( invs.clone() ) ;
           manager.broadcast(
             MsgDown::Invariants(sys.sym().get().clone(), invs)
           ) ;
+          if stop_on == StopOn::FirstProof {
+            * reason = Self::stop_reason(stop_on) ;
+            break 'msg_loop
+          }
         },
 
         Ok( KTrue(from, props, _, o) ) => {
+          log.log_k_true(& from, & props, & o) ;
           for prop in props.iter() {
             try_log_run!(
               c.set_prop_k_true( prop, o.to_usize() ), log, {
@@ -295,11 +1084,15 @@ impl Master {
                 } else { format!("") }
               )
             ) ;
+            log.invariants(& from, & sym, & set, at) ;
             try_log!(
               c.add_invs(& sym, set.clone()), log,
               "while adding {} invariants for {} from {} to context",
               set.len(), sym, from
             ) ;
+            if sym == * sys.sym().get() {
+              falsifier.invariants( set.clone() )
+            }
             manager.broadcast(
               MsgDown::Invariants( sym, set )
             )
@@ -339,11 +1132,15 @@ impl Master {
             //   if set.len() == 1 { "" } else { "s" }
             // )
           ) ;
+          log.invariants(& from, & sym, & set, at) ;
           try_log!(
             c.add_invs( & sym, set.clone() ), log,
             "while adding {} invariants for {} from {} to context",
             set.len(), sym, from
           ) ;
+          if sym == * sys.sym().get() {
+            falsifier.invariants( set.clone() )
+          }
           manager.broadcast(
             MsgDown::Invariants( sym, set )
           )
@@ -357,6 +1154,22 @@ impl Master {
           )
         },
 
+        Ok( Done(from, Info::Fixpoint(k)) ) => {
+          // Invariant generation is stuck, the technique that noticed and
+          // invariant generation stop. The others may still (dis)prove the
+          // properties.
+          let unknown = Why::Fixpoint(k) ;
+          log.sad( & from, & format!("stopping: {}", unknown) ) ;
+          why.push( (from, unknown) ) ;
+          try_log!(
+            manager.forget(& from), log,
+            "after reception of a `Done` at fixpoint message from {}", from
+          ) ;
+          if from != Tek::Tig && manager.forget(& Tek::Tig).is_ok() {
+            log.log( & Tek::Tig, "stopping, invariant generation is stuck" )
+          }
+        },
+
         Ok( Done(from, info) ) => {
           log.log(& from, & format!("done {}", info)) ;
           try_log!(
@@ -365,51 +1178,575 @@ impl Master {
           )
         },
 
+        Ok( Reachable(_, new) ) => {
+          falsifier.trace( new.clone() ) ;
+          // Only broadcasting the states the kids do not know yet.
+          let new = samples.add_all(new) ;
+          if ! new.is_empty() {
+            manager.broadcast( MsgDown::Reachable(new) )
+          }
+        },
+
+        Ok( Stats(from, usage) ) => if stats {
+          log.log_stats(& from, & usage)
+        },
+
+        Ok( Depth(from, props, o, time) ) => if stats {
+          for prop in props.into_iter() {
+            curves.entry(prop).or_insert_with(Vec::new).push(
+              (from, o.clone(), time)
+            )
+          }
+        },
+
+        Ok( Graph(from, event) ) => log.log_graph(& from, & event),
+
+        Ok( Unknown(from, unknown) ) => {
+          log.sad( & from, & format!("stopping: {}", unknown) ) ;
+          why.push( (from, unknown) )
+        },
+
+        Ok( SolverUnknowns(from, count) ) => {
+          unknowns.insert(from, count) ;
+        },
+
+        Ok( Crashed(crash) ) => {
+          // The other techniques keep running.
+          let from = crash.tek ;
+          let path = match crash.write(& crash_dir) {
+            Ok(path) => Some(path),
+            Err(e) => {
+              log.log_error_chain(& Kino, & e) ;
+              None
+            },
+          } ;
+          log.log_crash(& crash, path.as_ref().map( |path| path.as_str() )) ;
+          why.push( (from, Why::Crash( crash.msg.clone() )) ) ;
+          try_log!(
+            manager.forget(& from), log,
+            "after {} crashed", from
+          )
+        },
+
+        Ok( Falsified(sym, invs) ) => {
+          // Some technique is unsound, or kino mixed up the invariants.
+          let mut blah = format!(
+            "{} invariant(s) of {} falsified by a reachable state, \
+            retracting them:", invs.len(), sym
+          ) ;
+          for inv in invs.iter() {
+            blah.push_str( & format!("\n  {}", inv) )
+          }
+          log.bad(& Kino, & blah) ;
+          c.retract_invs(& sym, & invs) ;
+          manager.broadcast( MsgDown::Retract(sym, invs) )
+        },
+
+        Ok( Certified(props, from, info, ok) ) => {
+          certifying -= 1 ;
+          // Properties proved by another technique in the meantime are
+          // confirmed already.
+          let props: Vec<Sym> = props.into_iter().filter(
+            |prop| unconfirmed.get(prop) == Some(& from)
+          ).collect() ;
+          if props.is_empty() { continue 'msg_loop }
+          if ok {
+            log.log(
+              & Kino, & format!(
+                "proof of {} property(ies) by {} confirmed by a certificate \
+                check", props.len(), from
+              )
+            ) ;
+            for prop in props.iter() {
+              unconfirmed.remove(prop) ;
+              certified.insert( prop.clone() ) ; ()
+            }
+            // Handled as a proof confirmed.
+            let _ = manager.sender().send( Proved(props, from, info) ) ;
+          } else {
+            log.sad(
+              & Kino, & format!(
+                "could not confirm the proof of {} property(ies) by {} with \
+                a certificate check, waiting for another technique to prove \
+                them", props.len(), from
+              )
+            )
+          }
+        },
+
+        Ok(Pause) => {
+          log.log(& Kino, "pausing the techniques") ;
+          manager.pause()
+        },
+
+        Ok(Reload) => match reload_conf.reloaded() {
+          Ok(conf) => {
+            log.log(& Kino, "reloading the budgets of the techniques") ;
+            manager.reload( |tek| conf.max_queries_of(tek) )
+          },
+          Err(e) => log.bad(
+            & Kino, & format!("could not reload the options: {}", e)
+          ),
+        },
+
+        Ok(Resume) => {
+          log.log(& Kino, "resuming the techniques") ;
+          manager.resume()
+        },
+
+        Ok( NewProps(new) ) => {
+          // Properties of other systems or checked already are ignored.
+          let new: Vec<Prop> = new.into_iter().filter(
+            |prop| prop.sys().sym().get() == sys.sym().get() && ! checked.iter(
+            ).any( |old| old.sym().get() == prop.sym().get() )
+          ).collect() ;
+          if new.is_empty() { continue 'msg_loop }
+          log.log(
+            & Kino, & format!("adding {} property(ies)", new.len())
+          ) ;
+          manager.add_props(& new) ;
+          added.extend( new.iter().cloned() ) ;
+          checked.extend(new)
+        },
+
         Ok( msg ) => log.bad( & Kino, & format!("unknown message {}", msg) ),
 
-        Err(e) => log.bad(
-          & Kino, & format!("{:?}", e)
+        Err(e) => log.log_error_chain(& Kino, & e)
+
+      }
+    }
+
+    for (prop, tek) in unconfirmed.into_iter() {
+      log.sad(
+        & Kino, & format!(
+          "the proof of {} by {} was never confirmed, it is unknown",
+          prop, tek
         )
+      )
+    }
+
+    let mut unknowns: Vec<(Tek, usize)> = unknowns.into_iter().collect() ;
+    unknowns.sort_by( |lhs, rhs| lhs.0.to_str().cmp( rhs.0.to_str() ) ) ;
+    log.log_solver_unknowns(& unknowns) ;
 
+    result
+  }
+
+  /// Confirms some properties proved by a technique, in paranoid mode.
+  ///
+  /// Returns the properties confirmed, because another technique proved
+  /// them already or because a certificate check confirmed them, and
+  /// whether a certificate check was launched for the other ones. The check
+  /// runs in the background and sends its outcome as a `Certified` message,
+  /// meanwhile the properties are remembered in `unconfirmed` so that
+  /// another technique proving them confirms them.
+  fn confirm<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, sys: & Sys, props: Vec<Sym>,
+    from: & Tek, k: & Offset, smt: & SolverStyle, conf: & conf::Kind,
+    unconfirmed: & mut HashMap<Sym, Tek>, certified: & mut HashSet<Sym>,
+    manager: & KidManager
+  ) -> (Vec<Sym>, bool) {
+    let (mut confirmed, mut to_check) = ( vec![], vec![] ) ;
+    for prop in props.into_iter() {
+      if certified.remove(& prop) {
+        confirmed.push(prop) ;
+        continue
+      }
+      let other = match unconfirmed.get(& prop) {
+        Some(tek) if tek != from => Some(* tek),
+        _ => None,
+      } ;
+      if let Some(tek) = other {
+        unconfirmed.remove(& prop) ;
+        log.log(
+          & Kino, & format!(
+            "proof of {} by {} confirmed by {}", prop, tek, from
+          )
+        ) ;
+        confirmed.push(prop)
+      } else {
+        to_check.push(prop)
       }
     }
+    if to_check.is_empty() { return (confirmed, false) }
 
-    let time = Instant::now() - start_time ;
+    let mut checked = Vec::with_capacity( to_check.len() ) ;
+    for prop in to_check.into_iter() {
+      match c.get_prop(& prop) {
+        Some( & (ref prop, _) ) => checked.push( prop.clone() ),
+        // No certificate, the property stays unknown.
+        None => {
+          log.sad(
+            & Kino, & format!(
+              "unknown property {} proved by {}, it is unconfirmed",
+              prop, from
+            )
+          ) ;
+          unconfirmed.insert(prop, * from) ; ()
+        },
+      }
+    }
+    if checked.is_empty() { return (confirmed, false) }
 
-    let some_prop_disproved = try_log_run!(
-      c.some_prop_disproved(& props), log, {
-        log.just_log_unknown() ;
-        return Err(())
-      }, "during post-run analysis"
+    let syms: Vec<Sym> = checked.iter().map(
+      |prop| prop.sym().get().clone()
+    ).collect() ;
+    for sym in syms.iter() {
+      unconfirmed.insert( sym.clone(), * from ) ; ()
+    }
+    let invs = c.get_invs( sys.sym().get() ).cloned().unwrap_or_else(
+      STermSet::new
     ) ;
-    let some_prop_unknown = try_log_run!(
-      c.some_prop_unknown(& props), log, {
-        log.just_log_unknown() ;
-        return Err(())
-      }, "during post-run analysis"
+    let (conf, smt, sys) = ( conf.clone(), smt.clone(), sys.clone() ) ;
+    let (from, k, factory) = ( * from, * k, c.factory().clone() ) ;
+    let sender = manager.sender() ;
+    let spawned = ::std::thread::Builder::new().name(
+      "kino_certifier".to_string()
+    ).spawn(
+      move || {
+        let certified = kind::certify(
+          & conf, smt, & sys, & checked, & invs, k.to_usize(), & factory
+        ) ;
+        let ok = match certified {
+          Ok(ok) => ok,
+          Err(e) => {
+            let _ = sender.send( Error(Kino, e) ) ;
+            false
+          },
+        } ;
+        let _ = sender.send( Certified(syms, from, k, ok) ) ;
+      }
     ) ;
+    match spawned {
+      Ok(_) => (confirmed, true),
+      Err(e) => {
+        log.bad(
+          & Kino, & format!("could not spawn certificate check: {}", e)
+        ) ;
+        (confirmed, false)
+      },
+    }
+  }
 
-    if ! some_prop_disproved {
-      if ! some_prop_unknown {
-        log.log_safe(time)
-      } else {
-        log.log_unknown(
-          try_log_run!(
-            c.unknown_props(& props), log, {
-              log.just_log_unknown() ;
-              return Err(())
-            }, "during post-run analysis"
-          ).into_iter(),
-          time
+  /// Checks again the certificates of the properties proved with a second
+  /// solver, and reports the ones it confirms.
+  ///
+  /// The properties proved at the same depth are certified together. If
+  /// their conjunction is not confirmed, they are certified one by one.
+  fn double_check<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, sys: & Sys, props: & [Prop],
+    smt: & SolverStyle, conf: & conf::Kind
+  ) {
+    // Properties proved, by depth of their certificate.
+    let mut proved: HashMap< usize, Vec<Prop> > = HashMap::new() ;
+    for prop in props.iter() {
+      // Liveness properties are proved through their safety reduction.
+      if prop.live().is_some() { continue }
+      match c.get_prop( prop.sym().get() ) {
+        Some( & (_, PropStatus::Invariant(k)) ) |
+        Some( & (_, PropStatus::MinInvariant(k, _)) ) => proved.entry(
+          k
+        ).or_insert_with(Vec::new).push( prop.clone() ),
+        _ => (),
+      }
+    }
+    if proved.is_empty() { return () }
+
+    let no_invs = STermSet::new() ;
+    let invs = c.get_invs( sys.sym().get() ).unwrap_or(& no_invs) ;
+    let certify = |props: & [Prop], k: usize| match kind::certify(
+      conf, smt.clone(), sys, props, invs, k, c.factory()
+    ) {
+      Ok(certified) => certified,
+      Err(e) => {
+        log.log_error_chain(& Kino, & e) ;
+        false
+      },
+    } ;
+
+    let mut depths: Vec<usize> = proved.keys().cloned().collect() ;
+    depths.sort() ;
+    let (mut checked, mut failed) = ( vec![], vec![] ) ;
+    for k in depths.into_iter() {
+      let props = & proved[& k] ;
+      if certify(props, k) {
+        checked.extend( props.iter().map( |p| p.sym().get().clone() ) ) ;
+        continue
+      }
+      if props.len() == 1 {
+        failed.push( props[0].sym().get().clone() ) ;
+        continue
+      }
+      for prop in props.iter() {
+        let sym = prop.sym().get().clone() ;
+        if certify( & [ prop.clone() ], k ) {
+          checked.push(sym)
+        } else {
+          failed.push(sym)
+        }
+      }
+    }
+    log.log_double_checked(& smt.cmd(), & checked, & failed)
+  }
+
+  /// True if the stop policy is met by some properties.
+  fn must_stop(
+    c: & Context, props: & [Prop], stop_on: StopOn
+  ) -> Result<bool, String> {
+    match stop_on {
+      StopOn::FirstCex => c.some_prop_disproved(props),
+      StopOn::FirstProof => c.some_prop_proved(props),
+      StopOn::Exhaustive => Ok(false),
+    }
+  }
+
+  /// Why the analysis stopped early because of the stop policy.
+  fn stop_reason(stop_on: StopOn) -> Option<String> {
+    match stop_on {
+      StopOn::FirstCex => Some(
+        "stopped at the first counterexample".to_string()
+      ),
+      StopOn::FirstProof => Some( "stopped at the first proof".to_string() ),
+      StopOn::Exhaustive => None,
+    }
+  }
+
+  /// Tells the kids working on some properties that were just proved or
+  /// disproved to forget them. Kids left without anything to check are
+  /// reassigned to the hardest cluster of properties left, see
+  /// [`hardest_cluster`](#method.hardest_cluster).
+  fn forget<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, manager: & mut KidManager,
+    props: & [Sym], status: Status, checked: & [Prop]
+  ) {
+    let cluster = Self::hardest_cluster(c, checked) ;
+    for tek in manager.forget_props(props, status, & cluster) {
+      log.log(
+        & Kino, & format!(
+          "reassigning {} to {} property(ies) left", tek, cluster.len()
+        )
+      )
+    }
+  }
+
+  /// The hardest cluster of properties left: the unknown properties that are
+  /// k-true for the lowest k, if any.
+  fn hardest_cluster(c: & Context, props: & [Prop]) -> Vec<Prop> {
+    let mut cluster = vec![] ;
+    // `None` if some property is not even 0-true.
+    let mut lowest = None ;
+    for prop in props.iter() {
+      let k = match c.get_prop( prop.sym().get() ) {
+        Some( & (_, PropStatus::Unknown) ) => None,
+        Some( & (_, PropStatus::KTrue(k)) ) => Some(k),
+        _ => continue,
+      } ;
+      if cluster.is_empty() || k < lowest {
+        lowest = k ;
+        cluster.clear()
+      } else if k > lowest {
+        continue
+      }
+      cluster.push( prop.clone() )
+    }
+    cluster
+  }
+
+  /// Retrieves the cached verdicts of some properties and updates the
+  /// context accordingly. Returns the properties with no cached verdict.
+  fn cached<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, sys: & Sys,
+    cache: & VerdictCache, props: & [Prop]
+  ) -> Result<Vec<Prop>, String> {
+    let tek = Tec("cache", "result cache") ;
+    let mut to_check = Vec::with_capacity( props.len() ) ;
+    for prop in props.iter() {
+      let sym = prop.sym().get().clone() ;
+      let verdict = cache.get(prop, c.factory()) ;
+      match verdict {
+        Some( Verdict::Proved(k) ) => {
+          try!( c.set_prop_inv(& sym, k) ) ;
+          log.log_proved(& tek, & [ sym ], & Offset::of_int(k))
+        },
+        Some( Verdict::Falsified(model) ) => {
+          let cex = c.cex_of(& model, sys) ;
+          try!( c.set_prop_false(& sym, cex.clone()) ) ;
+          log.log_cex(& tek, & cex, & [ sym ])
+        },
+        None => to_check.push( prop.clone() ),
+      }
+    }
+    Ok(to_check)
+  }
+
+  /// Caches the verdict of a property.
+  fn cache_verdict<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, cache: & VerdictCache,
+    prop: & Sym, verdict: & Verdict
+  ) {
+    match c.get_prop(prop) {
+      Some( & (ref prop, _) ) => try_log!(
+        cache.put(prop, verdict), log,
+        "while caching the verdict of property {}", prop.sym()
+      ),
+      None => log.bad(
+        & Kino, & format!("cannot cache verdict of unknown property {}", prop)
+      ),
+    }
+  }
+
+  /// Logs the branches of the transition relation of a system never taken in
+  /// its first `bound` transitions.
+  fn report_dead_code<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, sys: & Sys, conf: & conf::Bmc,
+    bound: usize
+  ) -> Result<(), String> {
+    let branches = try!(
+      bmc::dead_code(conf, sys, c.factory(), bound).map_err(
+        |e| format!("{}", e)
+      )
+    ) ;
+    if branches.is_empty() {
+      log.log(
+        & Kino, & format!(
+          "all the branches of {} are taken in {} transition(s)",
+          sys.sym(), bound
         )
+      ) ;
+      return Ok(())
+    }
+    let mut s = format!(
+      "branches of the transition relation of {} {} never taken:",
+      sys.sym(), sys.sym().span
+    ) ;
+    for (branch, dead) in branches.into_iter() {
+      s.push_str(
+        & format!(
+          "\n  #{} ({} of {}) {}", branch.index,
+          if branch.then { "then" } else { "else" }, branch.cond,
+          if dead {
+            "is dead".to_string()
+          } else {
+            format!("not taken in {} transition(s)", bound)
+          }
+        )
+      )
+    }
+    log.log(& Kino, & s) ;
+    Ok(())
+  }
+
+  /// Replays the proofs stored in a directory with a proof checker. The
+  /// properties a rejected proof is about are downgraded to unknown.
+  fn check_proofs<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & mut Context, dir: & str, cmd: & str
+  ) -> Result<(), String> {
+    let checked = try!(
+      proof::check(dir, cmd).map_err( |e| format!("{}", e) )
+    ) ;
+    let mut rejected = 0 ;
+    for & (ref path, ref props, ref failure) in checked.iter() {
+      if let Some(ref blah) = * failure {
+        rejected += 1 ;
+        log.bad(& Kino, & format!("proof {} rejected:\n{}", path, blah)) ;
+        for prop in props.iter() {
+          let sym = c.factory().sym( prop.as_str() ) ;
+          try!( c.set_prop_unknown(& sym) ) ;
+          log.bad(
+            & Kino, & format!(
+              "{} is unknown, one of its proofs was rejected", prop
+            )
+          )
+        }
       }
-    } else {
-      log.log_unsafe(time)
     }
+    log.log(
+      & Kino, & format!(
+        "{} proof(s) checked, {} rejected", checked.len(), rejected
+      )
+    ) ;
+    Ok(())
+  }
 
-    log.trail() ;
+  /// Slices a system to the state variables of some properties and some
+  /// other state variables, and reports what it removed.
+  fn slice<F: Formatter, S: Styler>(
+    log: & MasterLog<F,S>, c: & Context, sys: & Sys, props: & [Prop],
+    vars: & [String]
+  ) -> Result<Sys, String> {
+    let mut keep = pre::select(sys, props) ;
+    for var in vars.iter() {
+      let sym = c.factory().sym( var.as_str() ) ;
+      let is_local = sys.locals().iter().any(
+        |& (ref local, _, _)| local == & sym
+      ) ;
+      if ! is_local && ! sys.state().contains(& sym) {
+        return Err(
+          format!(
+            "`{}` is not a state variable of system {}", var, sys.sym()
+          )
+        )
+      }
+      keep.push(sym)
+    }
+    let sliced = Arc::new( sys.slice(& keep, c.factory()) ) ;
+    log.log(
+      & Kino, & format!(
+        "sliced {}: removed {} local(s) and {} sub-system(s)",
+        sys.sym(),
+        sys.locals().len() - sliced.locals().len(),
+        sys.subsys().len() - sliced.subsys().len()
+      )
+    ) ;
+    Ok(sliced)
+  }
 
-    result
+  /// Writes a boolean-only system and some properties over it to a file in
+  /// the AIGER format.
+  fn write_aiger(
+    c: & Context, sys: & Sys, props: & [Prop], file: & str
+  ) -> Result<(), String> {
+    use system::aiger::{ is_bool_only, write_aiger } ;
+    if ! is_bool_only(sys, props) {
+      return Err(
+        format!("system {} is not boolean-only", sys.sym())
+      )
+    }
+    match ::std::fs::File::create(file) {
+      Ok(mut f) => write_aiger(c.factory(), sys, props, & mut f),
+      Err(e) => Err( format!("could not create {:?}:\n{}", file, e) ),
+    }
+  }
+
+  /// Writes a system and some properties over it to a file in the VMT
+  /// format.
+  fn write_vmt(
+    sys: & Sys, props: & [Prop], file: & str
+  ) -> Result<(), String> {
+    match ::std::fs::File::create(file) {
+      Ok(mut f) => sys.to_vmt(props, & mut f).map_err(
+        |e| format!("could not write to {:?}:\n{}", file, e)
+      ),
+      Err(e) => Err( format!("could not create {:?}:\n{}", file, e) ),
+    }
+  }
 
+  /// Writes the dependency graph of the state variables of a system to a
+  /// file, as JSON if its name ends with `.json` and in the dot format
+  /// otherwise.
+  fn write_deps(sys: & Sys, file: & str) -> Result<(), String> {
+    use std::io::Write ;
+    let graph = DepGraph::of(sys) ;
+    let txt = if file.ends_with(".json") {
+      graph.to_json()
+    } else {
+      graph.to_dot()
+    } ;
+    match ::std::fs::File::create(file) {
+      Ok(mut f) => f.write_all( txt.as_bytes() ).map_err(
+        |e| format!("could not write to {:?}:\n{}", file, e)
+      ),
+      Err(e) => Err( format!("could not create {:?}:\n{}", file, e) ),
+    }
   }
-}
\ No newline at end of file
+}
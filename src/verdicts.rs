@@ -0,0 +1,221 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Verdicts of the properties, cached on disk across runs.
+//!
+//! The verdict of a property is stored in `<dir>/<key>/<property hash>`,
+//! where the hashes are [stable hashes](../../term/trait.StableHash.html).
+//! The key covers the system and the lemmas the verdict can depend on: the
+//! techniques use the properties checked along with it, once proved, and
+//! the invariants known already. Changing the system, the property or one
+//! of the lemmas thus invalidates the verdict.
+//!
+//! A verdict file starts with `proved <k>` or `falsified`. In the latter
+//! case, the following lines are the model of the counterexample, one value
+//! per line:
+//!
+//! ```text
+//! <offset or -> <b|i|r|x> <value> <symbol>
+//! ```
+//!
+//! Rationals are written `<num>/<den>`, bitvectors `<value>/<width>`,
+//! arrays in SMT-LIB with commas instead of spaces.
+
+use std::io::{ Read, Write } ;
+use std::fs ;
+use std::path::PathBuf ;
+
+use term::{
+  Factory, Model, Offset, State, Var, Cst, Int, Rat, IResult, STermSet,
+  SymMaker, VarMaker, CstMaker, StableHash, StableHasher, real_term
+} ;
+use term::smt::ParseSmt2 ;
+
+use system::{ Sys, Prop } ;
+
+/// Verdict of a property.
+pub enum Verdict {
+  /// Proved invariant, with the `k` it was proved at.
+  Proved(usize),
+  /// Falsified, with the model of the counterexample.
+  Falsified(Model),
+}
+
+/// Directory storing the verdicts of the properties of a system.
+pub struct VerdictCache {
+  /// Directory of the verdicts of the system.
+  dir: PathBuf,
+}
+impl VerdictCache {
+  /// Creates the verdict cache of a system in a directory, for verdicts
+  /// depending on some lemmas.
+  pub fn mk(dir: & str, sys: & Sys, lemmas: & STermSet) -> Self {
+    let mut hasher = StableHasher::mk() ;
+    sys.stable_hash_into(& mut hasher) ;
+    hasher.write_set( lemmas.iter() ) ;
+    VerdictCache {
+      dir: PathBuf::from(dir).join( format!("{:016x}", hasher.finish()) ),
+    }
+  }
+
+  /// Path of the verdict of a property.
+  fn path_of(& self, prop: & Prop) -> PathBuf {
+    self.dir.join( format!("{:016x}", prop.stable_hash()) )
+  }
+
+  /// Cached verdict of a property, if any.
+  ///
+  /// Verdict files that cannot be read or parsed are ignored.
+  pub fn get(& self, prop: & Prop, factory: & Factory) -> Option<Verdict> {
+    let mut txt = String::new() ;
+    match fs::File::open( self.path_of(prop) ) {
+      Ok(mut file) => if file.read_to_string(& mut txt).is_err() {
+        return None
+      },
+      Err(_) => return None,
+    }
+    let mut lines = txt.lines() ;
+    let head = match lines.next() {
+      Some(head) => head,
+      None => return None,
+    } ;
+    if head.starts_with("proved ") {
+      return head[7..].trim().parse::<usize>().ok().map(Verdict::Proved)
+    }
+    if head.trim() != "falsified" {
+      return None
+    }
+    let mut model = Vec::with_capacity(17) ;
+    for line in lines {
+      if line.trim().is_empty() { continue }
+      match Self::parse_value(line, factory) {
+        Some(value) => model.push(value),
+        None => return None,
+      }
+    }
+    Some( Verdict::Falsified(model) )
+  }
+
+  /// Stores the verdict of a property.
+  pub fn put(& self, prop: & Prop, verdict: & Verdict) -> Result<(), String> {
+    let path = self.path_of(prop) ;
+    let mut txt = String::with_capacity(100) ;
+    match * verdict {
+      Verdict::Proved(k) => txt.push_str( & format!("proved {}\n", k) ),
+      Verdict::Falsified(ref model) => {
+        txt.push_str("falsified\n") ;
+        for & ( (ref var, ref off), ref cst ) in model.iter() {
+          txt.push_str(
+            & format!(
+              "{} {} {}\n",
+              match * off {
+                Some(ref off) => format!("{}", off),
+                None => "-".to_string(),
+              },
+              Self::fmt_cst(cst),
+              var.get().sym().get().sym()
+            )
+          )
+        }
+      },
+    }
+    if let Err(e) = fs::create_dir_all(& self.dir) {
+      return Err(
+        format!("could not create directory {:?}:\n{}", self.dir, e)
+      )
+    }
+    match fs::File::create(& path) {
+      Ok(mut file) => file.write_all( txt.as_bytes() ).map_err(
+        |e| format!("could not write verdict to {:?}:\n{}", path, e)
+      ),
+      Err(e) => Err(
+        format!("could not create verdict file {:?}:\n{}", path, e)
+      ),
+    }
+  }
+
+  /// Formats a constant as a kind and a value.
+  fn fmt_cst(cst: & Cst) -> String {
+    match * cst.get() {
+      real_term::Cst::Bool(b) => format!("b {}", b),
+      real_term::Cst::Int(ref i) => format!("i {}", i),
+      real_term::Cst::Rat(ref r) => format!("r {}/{}", r.numer(), r.denom()),
+      real_term::Cst::BitVec(width, ref v) => format!("x {}/{}", v, width),
+      // No spaces in values, arrays are written in SMT-LIB with commas.
+      real_term::Cst::Array(_, _, _) => {
+        use term::write::Writable ;
+        let mut bytes: Vec<u8> = vec![] ;
+        cst.write(& mut bytes).expect(
+          "[verdicts] writing a constant to a vector cannot fail"
+        ) ;
+        format!("a {}", String::from_utf8_lossy(& bytes).replace(' ', ","))
+      },
+    }
+  }
+
+  /// Parses a line of a counterexample model.
+  fn parse_value(
+    line: & str, factory: & Factory
+  ) -> Option<( (Var, Option<Offset>), Cst )> {
+    let mut elems = line.splitn(4, ' ') ;
+    let (off, kind, val, sym) = match (
+      elems.next(), elems.next(), elems.next(), elems.next()
+    ) {
+      (Some(off), Some(kind), Some(val), Some(sym)) => (off, kind, val, sym),
+      _ => return None,
+    } ;
+    let sym = factory.sym(sym) ;
+    let (var, off): (Var, Option<Offset>) = if off == "-" {
+      (factory.var(sym), None)
+    } else {
+      match off.parse::<usize>() {
+        Ok(off) => (
+          factory.svar(sym, State::Curr), Some( Offset::of_int(off) )
+        ),
+        Err(_) => return None,
+      }
+    } ;
+    let cst: Cst = match kind {
+      "b" => match val.parse::<bool>() {
+        Ok(b) => factory.cst(b),
+        Err(_) => return None,
+      },
+      "i" => match Int::parse_bytes(val.as_bytes(), 10) {
+        Some(i) => factory.cst(i),
+        None => return None,
+      },
+      "r" => {
+        let mut frac = val.split('/') ;
+        match (
+          frac.next().and_then(|n| Int::parse_bytes(n.as_bytes(), 10)),
+          frac.next().and_then(|d| Int::parse_bytes(d.as_bytes(), 10))
+        ) {
+          (Some(num), Some(den)) => factory.cst( Rat::new(num, den) ),
+          _ => return None,
+        }
+      },
+      "x" => {
+        let mut bv = val.split('/') ;
+        match (
+          bv.next().and_then(|v| Int::parse_bytes(v.as_bytes(), 10)),
+          bv.next().and_then(|w| w.parse::<usize>().ok())
+        ) {
+          (Some(v), Some(width)) => factory.cst( (width, v) ),
+          _ => return None,
+        }
+      },
+      "a" => match factory.parse_value( val.replace(',', " ").as_bytes() ) {
+        IResult::Done(_, cst) => cst,
+        _ => return None,
+      },
+      _ => return None,
+    } ;
+    Some( ( (var, off), cst ) )
+  }
+}
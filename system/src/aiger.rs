@@ -0,0 +1,147 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Boolean-only systems and their AIGER export.
+//!
+//! A system is boolean-only if its state variables and local definitions
+//! are boolean, if it has no sub-systems and calls no functions, and if its
+//! init predicate, its transition relation and its properties are
+//! propositional. Such systems can be handled by SAT solvers instead of SMT
+//! solvers.
+//!
+//! The AIGER export uses the 1.9 version of the ASCII format. Transition
+//! relations are relations, not functions, so the next state is a copy of
+//! the state as inputs:
+//!
+//! - each state variable is a latch, uninitialized, whose next value is its
+//!   input copy,
+//! - an extra latch, `false` initially and `true` afterwards, tells whether
+//!   the current state is the initial one,
+//! - the constraint is `init` in the initial state and `trans` between the
+//!   state and its inputs copy,
+//! - there is one bad state literal per property, its negation.
+
+use std::io ;
+
+use term::{ Sym, Type, Factory, SymMaker, STerm, let_expand } ;
+use term::aig::{ Aig, Lit, TRUE, neg } ;
+
+use base::{ Sys, Prop } ;
+
+/// True iff a system and some properties over it are boolean-only.
+pub fn is_bool_only(sys: & Sys, props: & [::Prop]) -> bool {
+  use term::aig::is_propositional ;
+  sys.subsys().is_empty() && sys.calls().is_empty() && sys.state().args(
+  ).iter().all(
+    |& (_, ref typ)| * typ.get() == Type::Bool
+  ) && sys.locals().iter().all(
+    |& (_, ref typ, ref def)| * typ == Type::Bool && is_propositional(def)
+  ) && is_propositional(& sys.init().2) && is_propositional(
+    & sys.trans().2
+  ) && props.iter().all(
+    |prop| prop.calls().is_empty() && match * prop.body() {
+      STerm::One(ref curr, _) => is_propositional(curr),
+      STerm::Two(ref next) => is_propositional(next),
+    }
+  )
+}
+
+/// Creates an and-inverter graph defining the local variables of a
+/// boolean-only system.
+pub fn aig_of(factory: & Factory, sys: & Sys) -> Aig {
+  let mut aig = Aig::mk() ;
+  for & (ref sym, _, ref def) in sys.locals().iter() {
+    aig.define( sym.clone(), let_expand(factory, def) )
+  }
+  aig
+}
+
+/// Literal of the body of a property, at the frame of its current state.
+pub fn prop_lit(
+  factory: & Factory, aig: & mut Aig, prop: & Prop, frame: usize
+) -> Result<Lit, String> {
+  match * prop.body() {
+    STerm::One(ref curr, _) => aig.lit( & let_expand(factory, curr), frame ),
+    STerm::Two(ref next) => aig.lit( & let_expand(factory, next), frame ),
+  }
+}
+
+/// Writes a boolean-only system and some properties over it in the AIGER
+/// ASCII format.
+pub fn write_aiger<W: io::Write>(
+  factory: & Factory, sys: & Sys, props: & [::Prop], w: & mut W
+) -> Result<(), String> {
+  let mut aig = aig_of(factory, sys) ;
+
+  // Inputs come first, then latches, then the gates.
+  let svars: Vec<Sym> = sys.state().args().iter().map(
+    |& (ref sym, _)| sym.get().clone()
+  ).collect() ;
+  let inputs: Vec<Lit> = svars.iter().map(
+    |sym| aig.leaf(sym, 1)
+  ).collect() ;
+  let latches: Vec<Lit> = svars.iter().map(
+    |sym| aig.leaf(sym, 0)
+  ).collect() ;
+  let not_first = aig.leaf( & factory.sym("@not first"), 0 ) ;
+
+  let init = try!(
+    aig.lit( & let_expand(factory, & sys.init().2), 0 )
+  ) ;
+  let trans = try!(
+    aig.lit( & let_expand(factory, & sys.trans().2), 0 )
+  ) ;
+  let init = aig.or(not_first, init) ;
+  let constraint = aig.and(init, trans) ;
+
+  let mut bads = Vec::with_capacity( props.len() ) ;
+  for prop in props.iter() {
+    let lit = try!( prop_lit(factory, & mut aig, prop, 0) ) ;
+    bads.push( (prop.sym().get().clone(), neg(lit)) )
+  }
+
+  let ands = aig.ands() ;
+  let mut aag = format!(
+    "aag {} {} {} 0 {} {} 1 0 0\n",
+    aig.var_count(), inputs.len(), latches.len() + 1, ands.len(), bads.len()
+  ) ;
+  for input in inputs.iter() {
+    aag.push_str( & format!("{}\n", input) )
+  }
+  for (latch, input) in latches.iter().zip( inputs.iter() ) {
+    aag.push_str( & format!("{} {} {}\n", latch, input, latch) )
+  }
+  aag.push_str( & format!("{} {} 0\n", not_first, TRUE) ) ;
+  for & (_, bad) in bads.iter() {
+    aag.push_str( & format!("{}\n", bad) )
+  }
+  aag.push_str( & format!("{}\n", constraint) ) ;
+  for & (gate, lhs, rhs) in ands.iter() {
+    aag.push_str( & format!("{} {} {}\n", gate, lhs, rhs) )
+  }
+  // Symbol table.
+  for (index, sym) in svars.iter().enumerate() {
+    aag.push_str( & format!("i{} {}'\n", index, sym) )
+  }
+  for (index, sym) in svars.iter().enumerate() {
+    aag.push_str( & format!("l{} {}\n", index, sym) )
+  }
+  aag.push_str( & format!("l{} @not_first\n", svars.len()) ) ;
+  for (index, & (ref sym, _)) in bads.iter().enumerate() {
+    aag.push_str( & format!("b{} {}\n", index, sym) )
+  }
+  aag.push_str("c0 init_and_trans\n") ;
+  aag.push_str(
+    & format!("c\ngenerated by kino from system {}\n", sys.sym())
+  ) ;
+
+  w.write_all( aag.as_bytes() ).map_err(
+    |e| format!("could not write AIGER of system {}:\n{}", sys.sym(), e)
+  )
+}
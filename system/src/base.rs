@@ -8,14 +8,18 @@
 // except according to those terms.
 
 use std::fmt ;
+use std::io ;
 use std::hash::{ Hash, Hasher } ;
 use std::cmp::{ PartialEq, Eq } ;
 use std::iter::Iterator ;
-use std::collections::HashSet ;
+use std::collections::{ HashMap, HashSet } ;
 
 use term::{
-  Sym, Var, Type, Term, STerm, STermSet
+  Sym, Var, Type, Term, STerm, STermSet, StableHash, StableHasher, State,
+  Operator, Factory, CstMaker, SymMaker, VarMaker, AppMaker, OpMaker,
+  BindMaker, UnTermOps
 } ;
+use term::real_term ;
 use term::real_term::Cst ;
 use term::parsing::Spnd ;
 
@@ -235,6 +239,13 @@ impl fmt::Display for Uf {
     write!(fmt, "{} ({}) -> {}", self.sym, self.sig, self.typ)
   }
 }
+impl StableHash for Uf {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    self.sym.stable_hash_into(hasher) ;
+    hasher.write_all( self.sig().len(), self.sig().iter() ) ;
+    self.typ.stable_hash_into(hasher)
+  }
+}
 impl PartialEq for Uf {
   fn eq(& self, other: & Uf) -> bool {
     self.sym == other.sym
@@ -294,6 +305,18 @@ impl fmt::Display for Fun {
     )
   }
 }
+impl StableHash for Fun {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    self.sym.stable_hash_into(hasher) ;
+    hasher.write_usize( self.args().len() ) ;
+    for & (ref sym, ref typ) in self.args() {
+      sym.stable_hash_into(hasher) ;
+      typ.stable_hash_into(hasher)
+    }
+    self.typ.stable_hash_into(hasher) ;
+    self.body.stable_hash_into(hasher)
+  }
+}
 impl PartialEq for Fun {
   fn eq(& self, other: & Fun) -> bool {
     self.sym == other.sym
@@ -374,6 +397,20 @@ impl Callable {
     Ok( typ.get().clone() )
   }
 }
+impl StableHash for Callable {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    match * self {
+      Callable::Dec(ref f) => {
+        hasher.write_u8(0) ;
+        f.stable_hash_into(hasher)
+      },
+      Callable::Def(ref f) => {
+        hasher.write_u8(1) ;
+        f.stable_hash_into(hasher)
+      },
+    }
+  }
+}
 impl fmt::Display for Callable {
   fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
     match * self {
@@ -395,6 +432,9 @@ pub enum PropStatus {
   Invariant(usize),
   /// Minimized k-inductive invariant with lemmas.
   MinInvariant(usize, STermSet),
+  /// Split in sub-properties, one per conjunct, until their statuses are
+  /// recombined.
+  Split(Vec<Sym>),
 }
 impl fmt::Display for PropStatus {
   fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
@@ -407,6 +447,7 @@ impl fmt::Display for PropStatus {
       MinInvariant(ref k, ref invs) => write!(
         fmt, "{}-inductive with {} lemmas (minimized)", k, invs.len()
       ),
+      Split(ref subs) => write!(fmt, "split in {} sub-properties", subs.len()),
     }
   }
 }
@@ -422,6 +463,8 @@ pub struct Prop {
   body: STerm,
   /// Calls in the property.
   calls: CallSet,
+  /// Temporal operator of a liveness property, `None` for safety.
+  live: Option<Live>,
 }
 impl Prop {
   /// Creates a new property.
@@ -429,8 +472,19 @@ impl Prop {
   pub fn mk(
     sym: Spnd<Sym>, sys: ::Sys, body: STerm, calls: CallSet
   ) -> Self {
-    Prop { sym: sym, sys: sys, body: body, calls: calls }
+    Prop { sym: sym, sys: sys, body: body, calls: calls, live: None }
   }
+  /// Creates a new liveness property, the body is the argument of the
+  /// temporal operator.
+  #[inline(always)]
+  pub fn mk_live(
+    sym: Spnd<Sym>, sys: ::Sys, body: STerm, calls: CallSet, live: Live
+  ) -> Self {
+    Prop { sym: sym, sys: sys, body: body, calls: calls, live: Some(live) }
+  }
+  /// Temporal operator of a liveness property, `None` for safety.
+  #[inline(always)]
+  pub fn live(& self) -> Option<Live> { self.live }
   /// Identifier of a property.
   #[inline(always)]
   pub fn sym(& self) -> & Spnd<Sym> { & self.sym }
@@ -446,9 +500,30 @@ impl Prop {
 }
 impl fmt::Display for Prop {
   fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
-    write!(
-      fmt, "{} ({}) {{ {} }}", self.sym.get(), self.sys.sym(), self.body
-    )
+    match self.live {
+      None => write!(
+        fmt, "{} ({}) {{ {} }}", self.sym.get(), self.sys.sym(), self.body
+      ),
+      Some(live) => write!(
+        fmt, "{} ({}) {{ {} {} }}",
+        self.sym.get(), self.sys.sym(), live, self.body
+      ),
+    }
+  }
+}
+impl StableHash for Prop {
+  /// Hashes the name, the system, the body, the temporal operator and the
+  /// functions used by the property.
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    self.sym.stable_hash_into(hasher) ;
+    self.sys.stable_hash_into(hasher) ;
+    self.body.stable_hash_into(hasher) ;
+    match self.live {
+      None => hasher.write_usize(0),
+      Some(Live::Eventually) => hasher.write_usize(1),
+      Some(Live::InfinitelyOften) => hasher.write_usize(2),
+    }
+    hasher.write_set( self.calls.get().iter() )
   }
 }
 impl PartialEq for Prop {
@@ -463,6 +538,24 @@ impl Hash for Prop {
   }
 }
 
+/// Temporal operator of a liveness property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Live {
+  /// `(F p)`, `p` eventually holds.
+  Eventually,
+  /// `(G (F p))`, `p` holds infinitely often.
+  InfinitelyOften,
+}
+impl fmt::Display for Live {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    match * self {
+      Live::Eventually => write!(fmt, "F"),
+      Live::InfinitelyOften => write!(fmt, "G F"),
+    }
+  }
+}
+
 /// A transition system.
 #[derive(Debug,Clone)]
 pub struct Sys {
@@ -476,6 +569,9 @@ pub struct Sys {
   init: (Sym, Vec<(Var, Type)>, Term, Term),
   /// Identifier of the transition relation of the system.
   trans: (Sym, Vec<(Var, Type)>, Term, Term),
+  /// Fairness constraints, one-state terms holding infinitely often on the
+  /// paths liveness properties range over.
+  fair: Vec<Term>,
   /// Calls of the system.
   subsys: Vec<(::Sys, Vec<Term>)>,
   /// Callables used by this system **recursively**.
@@ -488,12 +584,13 @@ impl Sys {
     sym: Spnd<Sym>, state: Args, locals: Vec<(Sym, Type, Term)>,
     init: (Sym, Vec<(Var, Type)>, Term, Term),
     trans: (Sym, Vec<(Var, Type)>, Term, Term),
+    fair: Vec<Term>,
     subsys: Vec<(::Sys, Vec<Term>)>,
     calls: CallSet,
   ) -> Self {
     Sys {
       sym: sym, state: state, locals: locals,
-      init: init, trans: trans,
+      init: init, trans: trans, fair: fair,
       subsys: subsys, calls: calls,
     }
   }
@@ -522,6 +619,9 @@ impl Sys {
   /// Trans term.
   #[inline(always)]
   pub fn trans_term(& self) -> & Term { & self.trans.3 }
+  /// Fairness constraints of a system.
+  #[inline(always)]
+  pub fn fair(& self) -> & [Term] { & self.fair }
   /// Sub-systems of a system.
   #[inline(always)]
   pub fn subsys(& self) -> & [ (::Sys, Vec<Term>) ] { & self.subsys }
@@ -539,6 +639,82 @@ impl Sys {
   #[inline(always)]
   pub fn calls(& self) -> & CallSet { & self.calls }
 
+  /// The same system with some state variables appended to its state.
+  ///
+  /// The terms of `init` and `trans` are conjoined to the initial predicate
+  /// and the transition relation, `fair` is appended to the fairness
+  /// constraints, and `calls` are the callables the new terms use. Fails
+  /// if the system has a state variable named like a new one.
+  pub fn with_extra_state(
+    & self, extra: Vec<(Spnd<Sym>, Spnd<Type>)>, init: Vec<Term>,
+    trans: Vec<Term>, fair: Vec<Term>, calls: & [::Callable],
+    factory: & Factory
+  ) -> Result<Sys, String> {
+    let mut state = self.state.args().to_vec() ;
+    let (ref init_sym, ref init_state, ref init_body, _) = self.init ;
+    let (ref trans_sym, ref old_trans, ref trans_body, _) = self.trans ;
+    let mut init_state = init_state.clone() ;
+    // Current state variables first, then the next ones.
+    let half = old_trans.len() / 2 ;
+    let mut trans_curr = old_trans[0 .. half].to_vec() ;
+    let mut trans_next = old_trans[half ..].to_vec() ;
+
+    for (sym, typ) in extra.into_iter() {
+      if self.state.contains( sym.get() ) {
+        return Err(
+          format!(
+            "system {} already has a state variable `{}`", self.sym, sym
+          )
+        )
+      }
+      let curr: Var = factory.svar( sym.get().clone(), State::Curr ) ;
+      let next: Var = factory.svar( sym.get().clone(), State::Next ) ;
+      if let Err(e) = factory.set_var_type(
+        Some( self.sym.get().clone() ), curr.clone(), typ.get().clone()
+      ) {
+        return Err( format!("{}", e) )
+      }
+      init_state.push( (curr.clone(), typ.get().clone()) ) ;
+      trans_curr.push( (curr, typ.get().clone()) ) ;
+      trans_next.push( (next, typ.get().clone()) ) ;
+      state.push( (sym, typ) )
+    }
+    trans_curr.extend(trans_next) ;
+    let trans_state = trans_curr ;
+
+    let init_term = factory.app(
+      init_sym.clone(), init_state.iter().map(
+        |& (ref var, _)| factory.mk_var( var.clone() )
+      ).collect()
+    ) ;
+    let trans_term = factory.app(
+      trans_sym.clone(), trans_state.iter().map(
+        |& (ref var, _)| factory.mk_var( var.clone() )
+      ).collect()
+    ) ;
+    let conj = |body: & Term, mut conjs: Vec<Term>| if conjs.is_empty() {
+      body.clone()
+    } else {
+      conjs.insert( 0, body.clone() ) ;
+      factory.and(conjs)
+    } ;
+    let init_body = conj(init_body, init) ;
+    let trans_body = conj(trans_body, trans) ;
+    let mut fairs = self.fair.clone() ;
+    fairs.extend(fair) ;
+    let mut all_calls = self.calls.clone() ;
+    for fun in calls.iter() { all_calls.insert( fun.clone() ) }
+
+    Ok(
+      Sys::mk(
+        self.sym.clone(), Args::mk(state), self.locals.clone(),
+        (init_sym.clone(), init_state, init_body, init_term),
+        (trans_sym.clone(), trans_state, trans_body, trans_term),
+        fairs, self.subsys.clone(), all_calls
+      )
+    )
+  }
+
   /// Default value for a symbol.
   pub fn default_value(& self, sym: & Sym) -> Result<Cst, String> {
     for & (ref sym, ref typ) in self.state().args() {
@@ -549,12 +725,444 @@ impl Sys {
     Err( format!("[sys] unknown symbol {}", sym) )
   }
 
+  /// The same system without the locals, the sub-system instances and the
+  /// conjuncts of the transition relation irrelevant to some state
+  /// variables and locals.
+  ///
+  /// A top-level conjunct of the transition relation is relevant if it
+  /// mentions the next value of a relevant variable, or if it mentions no
+  /// next value and some relevant variable. All the state variables of a
+  /// relevant conjunct are relevant, until a fixed point is reached.
+  /// Sub-system instances go with their conjunct, in the transition
+  /// relation and in the init predicate. Locals are kept if `keep`, the init
+  /// predicate, the fairness constraints or a relevant conjunct use them.
+  ///
+  /// The state does not change: the state variables not relevant are left
+  /// unconstrained by the transition relation.
+  ///
+  /// Takes a factory since systems do not store one, and the init predicate
+  /// and transition relation of the slice are new terms.
+  pub fn slice(& self, keep: & [Sym], factory: & Factory) -> Sys {
+    // State variables and locals each local uses directly.
+    let names: HashSet<Sym> = self.locals.iter().map(
+      |& (ref sym, _, _)| sym.clone()
+    ).collect() ;
+    let mut defs = HashMap::with_capacity( self.locals.len() ) ;
+    for & (ref sym, _, ref term) in self.locals.iter() {
+      defs.insert( sym.clone(), used_by(term, & names) ) ;
+      ()
+    }
+    // Expands the locals used directly.
+    let expand = |
+      mut svars: HashSet<(Sym, State)>, direct: HashSet<Sym>
+    | {
+      let mut locals = HashSet::new() ;
+      let mut to_do: Vec<Sym> = direct.into_iter().collect() ;
+      while let Some(local) = to_do.pop() {
+        if locals.contains(& local) { continue }
+        if let Some( & (ref sub_svars, ref sub_locals) ) = defs.get(& local) {
+          svars.extend( sub_svars.iter().cloned() ) ;
+          to_do.extend( sub_locals.iter().cloned() )
+        }
+        locals.insert(local) ;
+        ()
+      }
+      (svars, locals)
+    } ;
+    // State variables and locals used by a term, locals expanded.
+    let used = |term: & Term| {
+      let (svars, direct) = used_by(term, & names) ;
+      expand(svars, direct)
+    } ;
+
+    let mut conjs = vec![] ;
+    conjuncts_of(& self.trans.2, & mut conjs) ;
+    let trans_len = conjs.len() ;
+    let conj_used: Vec<_> = conjs.iter().map( |conj| used(conj) ).collect() ;
+
+    // Relevant variables, the locals of `keep` expanded.
+    let (kept_locals, kept_svars): (Vec<Sym>, Vec<Sym>) = keep.iter(
+    ).cloned().partition( |sym| names.contains(sym) ) ;
+    let (svars, mut locals) = expand(
+      HashSet::new(), kept_locals.into_iter().collect()
+    ) ;
+    let mut relevant: HashSet<Sym> = kept_svars.into_iter().chain(
+      svars.into_iter().map( |(sym, _)| sym )
+    ).collect() ;
+
+    let mut kept = vec![ false ; conjs.len() ] ;
+    let mut fixed_point = false ;
+    while ! fixed_point {
+      fixed_point = true ;
+      for (index, & (ref svars, _)) in conj_used.iter().enumerate() {
+        if kept[index] { continue }
+        let has_next = svars.iter().any(
+          |& (_, state)| state == State::Next
+        ) ;
+        let is_relevant = svars.is_empty() || svars.iter().any(
+          |& (ref sym, state)| (
+            state == State::Next || ! has_next
+          ) && relevant.contains(sym)
+        ) ;
+        if is_relevant {
+          kept[index] = true ;
+          fixed_point = false ;
+          relevant.extend( svars.iter().map( |& (ref sym, _)| sym.clone() ) )
+        }
+      }
+    }
+
+    // Sub-system instances whose conjunct is not relevant.
+    let mut subsys = Vec::with_capacity( self.subsys.len() ) ;
+    let mut dropped = vec![] ;
+    for & (ref sub, ref params) in self.subsys.iter() {
+      let index = conjs.iter().position(
+        |conj| match * conj.get() {
+          real_term::Term::App(ref sym, ref args) => sym == & sub.trans().0
+          && args.len() >= params.len()
+          && & args[0 .. params.len()] == & params[..],
+          _ => false,
+        }
+      ) ;
+      match index {
+        Some(index) if ! kept[index] => dropped.push(
+          ( sub.init().0.clone(), params.clone() )
+        ),
+        _ => subsys.push( (sub.clone(), params.clone()) ),
+      }
+    }
+
+    let mut init_conjs = vec![] ;
+    conjuncts_of(& self.init.2, & mut init_conjs) ;
+    let init_len = init_conjs.len() ;
+    init_conjs.retain(
+      |conj| match * conj.get() {
+        real_term::Term::App(ref sym, ref args) => ! dropped.iter().any(
+          |& (ref init, ref params)| sym == init && args == params
+        ),
+        _ => true,
+      }
+    ) ;
+
+    for conj in init_conjs.iter().chain( self.fair.iter() ) {
+      locals.extend( used(conj).1 )
+    }
+    let mut trans_conjs = Vec::with_capacity( conjs.len() ) ;
+    for ( (conj, (_, conj_locals)), kept ) in conjs.into_iter().zip(
+      conj_used.into_iter()
+    ).zip( kept.into_iter() ) {
+      if kept {
+        locals.extend(conj_locals) ;
+        trans_conjs.push(conj)
+      }
+    }
+
+    let and = |conjs: Vec<Term>| if conjs.is_empty() {
+      factory.cst(true)
+    } else { factory.and(conjs) } ;
+    let init = if init_conjs.len() == init_len {
+      self.init.2.clone()
+    } else { and(init_conjs) } ;
+    let trans = if trans_conjs.len() == trans_len {
+      self.trans.2.clone()
+    } else { and(trans_conjs) } ;
+
+    Sys::mk(
+      self.sym.clone(), self.state.clone(),
+      self.locals.iter().filter(
+        |& & (ref sym, _, _)| locals.contains(sym)
+      ).cloned().collect(),
+      ( self.init.0.clone(), self.init.1.clone(), init, self.init.3.clone() ),
+      (
+        self.trans.0.clone(), self.trans.1.clone(), trans,
+        self.trans.3.clone()
+      ),
+      self.fair.clone(), subsys, self.calls.clone()
+    )
+  }
+
+  /// The same system with its sub-system instances inlined, recursively.
+  ///
+  /// The state variables `x` of the `i`-th instance, of a sub-system `sub`,
+  /// join the state as `sub[i].x`. The renamed init predicate and
+  /// transition relation of the instance replace its application, along
+  /// with the equalities between its state variables and the actual
+  /// parameters: in the initial state, and in both states of the
+  /// transition relation so that induction does not lose them. The locals
+  /// and the fairness constraints of the instance are renamed and inlined
+  /// too.
+  ///
+  /// Returns a `Result`, unlike the other transformations, because
+  /// flattening can fail: a namespaced state variable can already be a state
+  /// variable of the system, and the factory can refuse to register its
+  /// type.
+  pub fn flatten(& self, factory: & Factory) -> Result<Sys, String> {
+    if self.subsys.is_empty() { return Ok( self.clone() ) }
+
+    let mut state = self.state.args().to_vec() ;
+    let mut locals = self.locals.clone() ;
+    let mut fair = self.fair.clone() ;
+    let (init_sym, mut init_state, init_body, _) = self.init.clone() ;
+    let (trans_sym, old_trans, trans_body, _) = self.trans.clone() ;
+    // Current state variables first, then the next ones.
+    let half = old_trans.len() / 2 ;
+    let mut trans_curr = old_trans[0 .. half].to_vec() ;
+    let mut trans_next = old_trans[half ..].to_vec() ;
+
+    let mut init_conjs = vec![] ;
+    conjuncts_of(& init_body, & mut init_conjs) ;
+    let mut trans_conjs = vec![] ;
+    conjuncts_of(& trans_body, & mut trans_conjs) ;
+
+    for (index, & (ref sub, ref params)) in self.subsys.iter().enumerate() {
+      let flat = try!( sub.flatten(factory) ) ;
+      let prefix = format!("{}[{}].", sub.sym().get().sym(), index) ;
+      let mut renaming = HashMap::with_capacity( flat.state.len() ) ;
+      let (mut sub_init, mut sub_trans) = ( vec![], vec![] ) ;
+
+      for (
+        & (ref svar, ref typ), param
+      ) in flat.state.args().iter().zip( params.iter() ) {
+        let sym = factory.sym(
+          format!("{}{}", prefix, svar.get().sym())
+        ) ;
+        if self.state.contains(& sym) {
+          return Err(
+            format!(
+              "cannot flatten system {}, `{}` is a state variable already",
+              self.sym, sym
+            )
+          )
+        }
+        let curr: Var = factory.svar( sym.clone(), State::Curr ) ;
+        let next: Var = factory.svar( sym.clone(), State::Next ) ;
+        if let Err(e) = factory.set_var_type(
+          Some( self.sym.get().clone() ), curr.clone(), typ.get().clone()
+        ) {
+          return Err( format!("{}", e) )
+        }
+        init_state.push( (curr.clone(), typ.get().clone()) ) ;
+        trans_curr.push( (curr.clone(), typ.get().clone()) ) ;
+        trans_next.push( (next.clone(), typ.get().clone()) ) ;
+
+        let param_next = try!(
+          factory.bump( param.clone() ).map_err( |e| format!("{}", e) )
+        ) ;
+        let (curr, next) = ( factory.mk_var(curr), factory.mk_var(next) ) ;
+        sub_init.push( factory.eq( vec![ curr.clone(), param.clone() ] ) ) ;
+        sub_trans.push( factory.eq( vec![ curr, param.clone() ] ) ) ;
+        sub_trans.push( factory.eq( vec![ next, param_next ] ) ) ;
+
+        state.push(
+          ( Spnd::mk(sym.clone(), svar.span.clone()), typ.clone() )
+        ) ;
+        renaming.insert( svar.get().clone(), sym ) ;
+        ()
+      }
+      sub_init.push( rename(factory, & renaming, & flat.init.2) ) ;
+      sub_trans.push( rename(factory, & renaming, & flat.trans.2) ) ;
+      for & (ref sym, ref typ, ref term) in flat.locals.iter() {
+        locals.push(
+          (
+            factory.sym( format!("{}{}", prefix, sym.sym()) ), typ.clone(),
+            rename(factory, & renaming, term)
+          )
+        )
+      }
+      for term in flat.fair.iter() {
+        fair.push( rename(factory, & renaming, term) )
+      }
+
+      // Replacing the applications of the instance.
+      let init_app = init_conjs.iter().position(
+        |conj| match * conj.get() {
+          real_term::Term::App(ref sym, ref args) => sym == & sub.init().0
+          && args == params,
+          _ => false,
+        }
+      ) ;
+      let trans_app = trans_conjs.iter().position(
+        |conj| match * conj.get() {
+          real_term::Term::App(ref sym, ref args) => sym == & sub.trans().0
+          && args.len() >= params.len()
+          && & args[0 .. params.len()] == & params[..],
+          _ => false,
+        }
+      ) ;
+      match (init_app, trans_app) {
+        ( Some(init_app), Some(trans_app) ) => {
+          init_conjs[init_app] = factory.and(sub_init) ;
+          trans_conjs[trans_app] = factory.and(sub_trans)
+        },
+        _ => return Err(
+          format!(
+            "cannot flatten system {}, \
+            instance {} of sub-system {} is not applied",
+            self.sym, index, sub.sym()
+          )
+        ),
+      }
+    }
+
+    trans_curr.extend(trans_next) ;
+    let trans_state = trans_curr ;
+
+    let init_term = factory.app(
+      init_sym.clone(), init_state.iter().map(
+        |& (ref var, _)| factory.mk_var( var.clone() )
+      ).collect()
+    ) ;
+    let trans_term = factory.app(
+      trans_sym.clone(), trans_state.iter().map(
+        |& (ref var, _)| factory.mk_var( var.clone() )
+      ).collect()
+    ) ;
+
+    Ok(
+      Sys::mk(
+        self.sym.clone(), Args::mk(state), locals,
+        (init_sym, init_state, factory.and(init_conjs), init_term),
+        (trans_sym, trans_state, factory.and(trans_conjs), trans_term),
+        fair, vec![], self.calls.clone()
+      )
+    )
+  }
+
+  /// Writes a system and some properties over it in the VMT format.
+  ///
+  /// VMT is SMT-LIB where each state variable `x` is declared twice, `x`
+  /// and `x.next` for its next value, linked by a `:next` annotation. The
+  /// init predicate, the transition relation and the properties are
+  /// annotated with `:init`, `:trans` and `:invar-property`. The functions
+  /// and the init predicates and transition relations of the sub-systems
+  /// are defined first. Two-state and liveness properties have no VMT
+  /// counterpart, they are skipped with a comment.
+  pub fn to_vmt(
+    & self, props: & [::Prop], w: & mut io::Write
+  ) -> io::Result<()> {
+    use term::write::Writable ;
+    try!( writeln!(w, "; System {} in the VMT format.", self.sym) ) ;
+
+    for callable in self.calls.get() {
+      match ** callable {
+        Callable::Dec(ref f) => {
+          try!( write!(w, "(declare-fun |{}| (", f.sym().get().sym()) ) ;
+          for (index, typ) in f.sig().iter().enumerate() {
+            if index > 0 { try!( write!(w, " ") ) }
+            try!( typ.get().write(w) )
+          }
+          try!( write!(w, ") ") ) ;
+          try!( f.typ().get().write(w) ) ;
+          try!( writeln!(w, ")") )
+        },
+        Callable::Def(ref f) => {
+          try!( write!(w, "(define-fun |{}| (", f.sym().get().sym()) ) ;
+          for (index, & (ref sym, ref typ)) in f.args().iter().enumerate() {
+            if index > 0 { try!( write!(w, " ") ) }
+            try!( write!(w, "(|{}| ", sym.get().sym()) ) ;
+            try!( typ.get().write(w) ) ;
+            try!( write!(w, ")") )
+          }
+          try!( write!(w, ") ") ) ;
+          try!( f.typ().get().write(w) ) ;
+          try!( write!(w, " ") ) ;
+          try!( write_vmt(w, f.body()) ) ;
+          try!( writeln!(w, ")") )
+        },
+      }
+    }
+
+    let mut defined = HashSet::new() ;
+    for & (ref sub, _) in self.subsys.iter() {
+      try!( sub.write_vmt_subsys(w, & mut defined) )
+    }
+
+    for & (ref sym, ref typ) in self.state.args() {
+      let sym = sym.get().sym() ;
+      for name in [ format!("|{}|", sym), format!("|{}.next|", sym) ].iter() {
+        try!( write!(w, "(declare-fun {} () ", name) ) ;
+        try!( typ.get().write(w) ) ;
+        try!( writeln!(w, ")") )
+      }
+      try!( write!(w, "(define-fun |.sv.{}| () ", sym) ) ;
+      try!( typ.get().write(w) ) ;
+      try!( writeln!(w, " (! |{}| :next |{}.next|))", sym, sym) )
+    }
+
+    try!( write!(w, "(define-fun |.init| () Bool (! ") ) ;
+    try!( write_vmt(w, & self.init.2) ) ;
+    try!( writeln!(w, " :init true))") ) ;
+    try!( write!(w, "(define-fun |.trans| () Bool (! ") ) ;
+    try!( write_vmt(w, & self.trans.2) ) ;
+    try!( writeln!(w, " :trans true))") ) ;
+
+    let mut index = 0 ;
+    for prop in props.iter() {
+      match ( prop.body(), prop.live() ) {
+        ( & STerm::One(ref body, _), None ) => {
+          try!(
+            write!(
+              w, "(define-fun |.prop.{}| () Bool (! ", prop.sym().get().sym()
+            )
+          ) ;
+          try!( write_vmt(w, body) ) ;
+          try!( writeln!(w, " :invar-property {}))", index) ) ;
+          index += 1
+        },
+        _ => try!(
+          writeln!(
+            w, "; skipped property {}, not a one-state safety property",
+            prop.sym()
+          )
+        ),
+      }
+    }
+    Ok(())
+  }
+
+  /// Defines the init predicate and transition relation of a sub-system in
+  /// the VMT format, after the ones of its own sub-systems. Does nothing if
+  /// it is in `defined`.
+  fn write_vmt_subsys(
+    & self, w: & mut io::Write, defined: & mut HashSet<Sym>
+  ) -> io::Result<()> {
+    use term::write::{ Writable, StateWritable, SymPrintStyle } ;
+    if ! defined.insert( self.sym.get().clone() ) { return Ok(()) }
+    for & (ref sub, _) in self.subsys.iter() {
+      try!( sub.write_vmt_subsys(w, defined) )
+    }
+    for & (ref sym, ref params, ref body) in [
+      (& self.init.0, & self.init.1, & self.init.2),
+      (& self.trans.0, & self.trans.1, & self.trans.2),
+    ].iter() {
+      try!( write!(w, "(define-fun |{}| (", sym.sym()) ) ;
+      for (index, & (ref var, ref typ)) in params.iter().enumerate() {
+        if index > 0 { try!( write!(w, " ") ) }
+        try!( write!(w, "(") ) ;
+        try!( var.write(w, & VmtSVars, SymPrintStyle::External) ) ;
+        try!( write!(w, " ") ) ;
+        try!( typ.write(w) ) ;
+        try!( write!(w, ")") )
+      }
+      try!( write!(w, ") Bool ") ) ;
+      try!( write_vmt(w, body) ) ;
+      try!( writeln!(w, ")") )
+    }
+    Ok(())
+  }
+
   /// String representation of a system as lines.
   pub fn lines(& self) -> String {
     let mut s = format!(
       "{} ({})\n  init:  {}\n  trans: {}",
       self.sym, self.state, self.init.2, self.trans.2
     ) ;
+    if ! self.fair.is_empty() {
+      s = format!("{}\n  fairness:", s) ;
+      for fair in self.fair.iter() {
+        s = format!("{}\n    {}", s, fair) ;
+      } ;
+    } ;
     if ! self.subsys.is_empty() {
       s = format!("{}\n  sub-systems:", s) ;
       for & (ref sub_sym, ref params) in self.subsys.iter() {
@@ -574,6 +1182,127 @@ impl Sys {
     s
   }
 }
+
+/// Writes the state variables of a term as in the VMT format: `|x|` for
+/// the current value of `x` and `|x.next|` for its next value.
+struct VmtSVars ;
+impl ::term::write::SVarWriter<Sym> for VmtSVars {
+  fn sv_write(
+    & self, w: & mut io::Write, sym: & Sym, st: & State,
+    style: ::term::write::SymPrintStyle
+  ) -> io::Result<()> {
+    use term::write::SymWritable ;
+    try!( write!(w, "|") ) ;
+    try!( sym.write(w, style) ) ;
+    match * st {
+      State::Curr => write!(w, "|"),
+      State::Next => write!(w, ".next|"),
+    }
+  }
+}
+
+/// Writes a term in the VMT format.
+fn write_vmt(w: & mut io::Write, term: & Term) -> io::Result<()> {
+  use term::write::{ StateWritable, SymPrintStyle } ;
+  term.write(w, & VmtSVars, SymPrintStyle::External)
+}
+
+/// Top-level conjuncts of a term, in order.
+fn conjuncts_of(term: & Term, conjs: & mut Vec<Term>) {
+  match * term.get() {
+    real_term::Term::Op(Operator::And, ref kids) => for kid in kids.iter() {
+      conjuncts_of(kid, conjs)
+    },
+    _ => conjs.push( term.clone() ),
+  }
+}
+
+/// Renames some state variables of a term.
+fn rename(
+  factory: & Factory, renaming: & HashMap<Sym, Sym>, term: & Term
+) -> Term {
+  use term::zip::{ Step, fold } ;
+  fold(
+    |step| match step {
+      Step::V(var) => {
+        let renamed: Option<Term> = match * var.get() {
+          real_term::Var::SVar(ref sym, state) => renaming.get(sym).map(
+            |nu| factory.svar( nu.clone(), state )
+          ),
+          real_term::Var::Var(_) => None,
+        } ;
+        match renamed {
+          Some(term) => term,
+          None => factory.mk_var(var),
+        }
+      },
+      Step::App(sym, args) => factory.app(sym, args),
+      Step::Op(op, args) => factory.op(op, args),
+      Step::Let(bindings, body) => factory.let_b(bindings, body),
+      Step::Forall(vars, body) => factory.forall(vars, body),
+      Step::Exists(vars, body) => factory.exists(vars, body),
+      Step::C(cst) => factory.mk_cst(cst),
+    }, term.clone()
+  )
+}
+
+/// State variables and locals appearing in a term. Locals are not expanded.
+fn used_by(
+  term: & Term, locals: & HashSet<Sym>
+) -> (HashSet<(Sym, State)>, HashSet<Sym>) {
+  use term::real_term::Term::* ;
+  let (mut svars, mut used) = ( HashSet::new(), HashSet::new() ) ;
+  let mut stack = vec![ term ] ;
+  while let Some(term) = stack.pop() {
+    match * term.get() {
+      V(ref var) => match * var.get() {
+        real_term::Var::SVar(ref sym, state) => {
+          svars.insert( (sym.clone(), state) ) ; ()
+        },
+        real_term::Var::Var(ref sym) => if locals.contains(sym) {
+          used.insert( sym.clone() ) ; ()
+        },
+      },
+      C(_) => (),
+      Op(_, ref kids) | App(_, ref kids) => stack.extend( kids.iter() ),
+      Let(ref bindings, ref body) => {
+        for & (_, ref term) in bindings.iter() { stack.push(term) }
+        stack.push(body)
+      },
+      Forall(_, ref body) | Exists(_, ref body) => stack.push(body),
+    }
+  }
+  (svars, used)
+}
+
+impl StableHash for Sys {
+  /// Hashes the name, state, locals, init and trans of the system, its
+  /// sub-systems recursively and the functions it uses. Does not depend on
+  /// the order of the functions.
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    self.sym.stable_hash_into(hasher) ;
+    hasher.write_usize( self.state.len() ) ;
+    for & (ref sym, ref typ) in self.state.args() {
+      sym.stable_hash_into(hasher) ;
+      typ.stable_hash_into(hasher)
+    }
+    hasher.write_usize( self.locals.len() ) ;
+    for & (ref sym, ref typ, ref term) in self.locals.iter() {
+      sym.stable_hash_into(hasher) ;
+      typ.stable_hash_into(hasher) ;
+      term.stable_hash_into(hasher)
+    }
+    self.init.2.stable_hash_into(hasher) ;
+    self.trans.2.stable_hash_into(hasher) ;
+    hasher.write_all( self.fair.len(), self.fair.iter() ) ;
+    hasher.write_usize( self.subsys.len() ) ;
+    for & (ref sub, ref params) in self.subsys.iter() {
+      sub.stable_hash_into(hasher) ;
+      hasher.write_all( params.len(), params.iter() )
+    }
+    hasher.write_set( self.calls.get().iter() )
+  }
+}
 impl PartialEq<Sym> for Sys {
   fn eq(& self, rhs: & Sym) -> bool {
     self.sym.get() == rhs
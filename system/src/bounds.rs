@@ -0,0 +1,226 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Structural bounds of a system.
+//!
+//! The *domain* of a state variable is the number of values it can take in
+//! the reachable states, if it is known to be finite. Booleans take two
+//! values, bitvectors of width `w` take `2^w` values, and integers take the
+//! values of their *range*, if any. The range of an integer state variable
+//! is the smallest interval containing
+//!
+//! - the values the top-level conjuncts of the init predicate allow for it,
+//!   and
+//! - the values the top-level conjuncts of the transition relation allow for
+//!   its next value.
+//!
+//! Only comparisons with constants are considered, and equalities with
+//! `ite`s of constants.
+//!
+//! The recurrence diameter of a system, the length of its longest loop-free
+//! path, is less than its number of states: the product of the domains of
+//! its state variables. It is unknown if some domain is, or if the system
+//! has sub-systems. Every reachable state is reachable in at most that many
+//! transitions, so BMC up to the recurrence diameter plus one proves the
+//! properties it does not falsify.
+
+use std::collections::HashMap ;
+
+use term::{ Sym, Term, Type, Int, Operator, State, One } ;
+use term::real_term::{ Term as RTerm, Var as RVar, Cst as RCst } ;
+
+use base::Sys ;
+
+/// Interval, the bounds are included.
+type Range = ( Option<Int>, Option<Int> ) ;
+
+/// Structural bounds of a system.
+pub struct Bounds {
+  /// Identifier of the system.
+  sys: Sym,
+  /// Ranges of the integer state variables that have one.
+  ranges: HashMap<Sym, (Int, Int)>,
+  /// Domains of the state variables that have a finite one.
+  domains: HashMap<Sym, Int>,
+  /// Number of states of the system, if finite.
+  states: Option<Int>,
+}
+impl Bounds {
+  /// Computes the bounds of a system.
+  pub fn of(sys: & Sys) -> Self {
+    let mut init = HashMap::new() ;
+    for conj in conjuncts(& sys.init().2) {
+      constrain(& conj, State::Curr, & mut init)
+    }
+    let mut next = HashMap::new() ;
+    for conj in conjuncts(& sys.trans().2) {
+      constrain(& conj, State::Next, & mut next)
+    }
+
+    let mut ranges = HashMap::new() ;
+    let mut domains = HashMap::new() ;
+    let mut states = if sys.subsys().is_empty() {
+      Some( Int::one() )
+    } else { None } ;
+    for & (ref sym, ref typ) in sys.state().args().iter() {
+      let sym = sym.get() ;
+      let domain = match * typ.get() {
+        Type::Bool => Some( Int::one() + Int::one() ),
+        Type::BitVec(width) => Some( Int::one() << width ),
+        Type::Int => match (init.get(sym), next.get(sym)) {
+          (
+            Some( & (Some(ref i_lo), Some(ref i_hi)) ),
+            Some( & (Some(ref n_lo), Some(ref n_hi)) ),
+          ) => {
+            let lo = if i_lo < n_lo { i_lo.clone() } else { n_lo.clone() } ;
+            let hi = if i_hi > n_hi { i_hi.clone() } else { n_hi.clone() } ;
+            let size = if lo > hi {
+              Int::one()
+            } else { & hi - & lo + Int::one() } ;
+            ranges.insert( sym.clone(), (lo, hi) ) ;
+            Some(size)
+          },
+          _ => None,
+        },
+        _ => None,
+      } ;
+      states = match (states, domain.as_ref()) {
+        (Some(states), Some(domain)) => Some(states * domain),
+        _ => None,
+      } ;
+      if let Some(domain) = domain {
+        domains.insert( sym.clone(), domain ) ;
+        ()
+      }
+    }
+
+    Bounds {
+      sys: sys.sym().get().clone(), ranges: ranges, domains: domains,
+      states: states,
+    }
+  }
+
+  /// Identifier of the system.
+  #[inline]
+  pub fn sys(& self) -> & Sym { & self.sys }
+  /// Range of an integer state variable, bounds included, if any.
+  #[inline]
+  pub fn range(& self, svar: & Sym) -> Option<& (Int, Int)> {
+    self.ranges.get(svar)
+  }
+  /// Number of values of a state variable, if finite.
+  #[inline]
+  pub fn domain(& self, svar: & Sym) -> Option<& Int> {
+    self.domains.get(svar)
+  }
+  /// Number of states of the system, if finite.
+  #[inline]
+  pub fn states(& self) -> Option<& Int> { self.states.as_ref() }
+  /// Upper bound on the recurrence diameter of the system, if any.
+  pub fn diameter(& self) -> Option<usize> {
+    use term::ToPrimitive ;
+    self.states.as_ref().and_then(
+      |states| ( states - Int::one() ).to_usize()
+    )
+  }
+}
+
+/// Top-level conjuncts of a term, under `let`s.
+fn conjuncts(term: & Term) -> Vec<Term> {
+  let mut res = vec![] ;
+  let mut stack = vec![ term.clone() ] ;
+  while let Some(term) = stack.pop() {
+    match * term.get() {
+      RTerm::Op(Operator::And, ref kids) => stack.extend(
+        kids.iter().cloned()
+      ),
+      RTerm::Let(_, ref body) => stack.push( body.clone() ),
+      _ => res.push( term.clone() ),
+    }
+  }
+  res
+}
+
+/// State variable of some term, if it is one in some state.
+fn svar_of(term: & Term, state: State) -> Option<& Sym> {
+  if let RTerm::V(ref var) = * term.get() {
+    if let RVar::SVar(ref sym, st) = * var.get() {
+      if st == state { return Some(sym) }
+    }
+  }
+  None
+}
+
+/// Range of the values of a term that is a constant, an `ite` of constants,
+/// or the opposite of a constant.
+fn values_of(term: & Term) -> Option<(Int, Int)> {
+  match * term.get() {
+    RTerm::C(ref cst) => match * cst.get() {
+      RCst::Int(ref int) => Some( (int.clone(), int.clone()) ),
+      _ => None,
+    },
+    RTerm::Op(Operator::Sub, ref kids) if kids.len() == 1 => {
+      values_of(& kids[0]).map( |(lo, hi)| (- hi, - lo) )
+    },
+    RTerm::Op(Operator::Ite, ref kids) if kids.len() == 3 => {
+      match ( values_of(& kids[1]), values_of(& kids[2]) ) {
+        ( Some( (t_lo, t_hi) ), Some( (e_lo, e_hi) ) ) => Some(
+          (
+            if t_lo < e_lo { t_lo } else { e_lo },
+            if t_hi > e_hi { t_hi } else { e_hi },
+          )
+        ),
+        _ => None,
+      }
+    },
+    _ => None,
+  }
+}
+
+/// Narrows the ranges of the state variables in some state with a
+/// conjunct.
+fn constrain(
+  conj: & Term, state: State, ranges: & mut HashMap<Sym, Range>
+) {
+  use term::Operator::* ;
+  let (op, lhs, rhs) = match * conj.get() {
+    RTerm::Op(op, ref kids) if kids.len() == 2 => (op, & kids[0], & kids[1]),
+    _ => return (),
+  } ;
+  // Normalized as `svar op values`.
+  let (op, svar, values) = match (svar_of(lhs, state), svar_of(rhs, state)) {
+    (Some(svar), None) => (op, svar, values_of(rhs)),
+    (None, Some(svar)) => (
+      match op { Le => Ge, Ge => Le, Lt => Gt, Gt => Lt, op => op },
+      svar, values_of(lhs)
+    ),
+    _ => return (),
+  } ;
+  let (lo, hi) = match values {
+    Some(values) => values,
+    None => return (),
+  } ;
+  let (lo, hi) = match op {
+    Eq => ( Some(lo), Some(hi) ),
+    Le => ( None, Some(hi) ),
+    Lt => ( None, Some(hi - Int::one()) ),
+    Ge => ( Some(lo), None ),
+    Gt => ( Some(lo + Int::one()), None ),
+    _ => return (),
+  } ;
+  let range = ranges.entry( svar.clone() ).or_insert( (None, None) ) ;
+  if let Some(lo) = lo {
+    let tighter = range.0.as_ref().map( |old| lo > * old ).unwrap_or(true) ;
+    if tighter { range.0 = Some(lo) }
+  }
+  if let Some(hi) = hi {
+    let tighter = range.1.as_ref().map( |old| hi < * old ).unwrap_or(true) ;
+    if tighter { range.1 = Some(hi) }
+  }
+}
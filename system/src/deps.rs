@@ -0,0 +1,256 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Dependency graph between the state variables of a system.
+//!
+//! State variable `x` depends on `y` if the transition relation constrains
+//! the next value of `x` using `y`, current or next. The transition relation
+//! is split into its top-level conjuncts: the next state variables of a
+//! conjunct depend on all the state variables of that conjunct. Calls to
+//! functions and sub-systems are handled conservatively, local definitions
+//! are expanded.
+//!
+//! State variables whose next value is not always defined by an equality
+//! `(= (_ next x) ...)`, possibly under `ite`s, are considered inputs.
+
+use std::collections::{ HashMap, HashSet } ;
+
+use term::{ Sym, Term, Operator, State } ;
+use term::real_term ;
+
+use base::Sys ;
+
+/// Dependency graph between the state variables of a system.
+pub struct DepGraph {
+  /// Identifier of the system.
+  sys: Sym,
+  /// State variables of the system, in order.
+  svars: Vec<Sym>,
+  /// Maps state variables to the state variables they depend on.
+  deps: HashMap<Sym, HashSet<Sym>>,
+  /// State variables whose next value is defined by the transition relation.
+  defined: HashSet<Sym>,
+}
+impl DepGraph {
+  /// Computes the dependency graph of a system.
+  pub fn of(sys: & Sys) -> Self {
+    let svars: Vec<Sym> = sys.state().args().iter().map(
+      |& (ref sym, _)| sym.get().clone()
+    ).collect() ;
+    let mut deps = HashMap::with_capacity( svars.len() ) ;
+    for svar in svars.iter() {
+      deps.insert( svar.clone(), HashSet::new() ) ;
+    }
+
+    let mut defined = HashSet::new() ;
+    let mut locals = HashMap::new() ;
+    for & (ref sym, _, ref term) in sys.locals().iter() {
+      let vars = vars_of(term, & locals) ;
+      locals.insert( sym.clone(), vars ) ;
+    }
+    let mut conjs = vec![ sys.trans().2.clone() ] ;
+    while let Some(conj) = conjs.pop() {
+      match * conj.get() {
+        real_term::Term::Op(Operator::And, ref kids) => conjs.extend(
+          kids.iter().cloned()
+        ),
+        real_term::Term::Let(ref bindings, ref body) => {
+          for & (ref sym, ref term) in bindings.iter() {
+            let vars = vars_of(term, & locals) ;
+            locals.insert( sym.clone(), vars ) ;
+          }
+          conjs.push( body.clone() )
+        },
+        _ => {
+          defined.extend( defs_of(& conj) ) ;
+          let vars = vars_of(& conj, & locals) ;
+          for & (ref sym, ref state) in vars.iter() {
+            if * state != State::Next { continue }
+            if let Some(set) = deps.get_mut(sym) {
+              for & (ref dep, _) in vars.iter() {
+                if dep != sym { set.insert( dep.clone() ) ; () }
+              }
+            }
+          }
+        },
+      }
+    }
+
+    DepGraph {
+      sys: sys.sym().get().clone(), svars: svars, deps: deps, defined: defined
+    }
+  }
+
+  /// State variables of the system, in order.
+  #[inline]
+  pub fn svars(& self) -> & [Sym] { & self.svars }
+
+  /// True iff a state variable is an input: its next value is not always
+  /// defined by the transition relation.
+  #[inline]
+  pub fn is_input(& self, svar: & Sym) -> bool {
+    ! self.defined.contains(svar)
+  }
+  /// The inputs of the system, in order.
+  pub fn inputs(& self) -> Vec<& Sym> {
+    self.svars.iter().filter( |svar| self.is_input(svar) ).collect()
+  }
+
+  /// State variables a state variable depends on.
+  #[inline]
+  pub fn deps_of(& self, svar: & Sym) -> Option<& HashSet<Sym>> {
+    self.deps.get(svar)
+  }
+
+  /// State variables some state variables depend on, transitively. Includes
+  /// the state variables themselves.
+  pub fn cone_of<'a, Syms: IntoIterator<Item = & 'a Sym>>(
+    & self, svars: Syms
+  ) -> HashSet<Sym> {
+    let mut res = HashSet::new() ;
+    let mut to_do: Vec<Sym> = svars.into_iter().cloned().collect() ;
+    while let Some(svar) = to_do.pop() {
+      if res.contains(& svar) { continue }
+      if let Some(deps) = self.deps.get(& svar) {
+        to_do.extend( deps.iter().cloned() )
+      }
+      res.insert(svar) ;
+      ()
+    }
+    res
+  }
+
+  /// Dependencies of a state variable, sorted by name.
+  fn sorted_deps(& self, svar: & Sym) -> Vec<& Sym> {
+    let mut deps: Vec<& Sym> = match self.deps.get(svar) {
+      Some(deps) => deps.iter().collect(),
+      None => vec![],
+    } ;
+    deps.sort_by( |lhs, rhs| lhs.get().sym().cmp( rhs.get().sym() ) ) ;
+    deps
+  }
+
+  /// The graph in the `dot` format. Edges go from a state variable to the
+  /// ones it depends on.
+  pub fn to_dot(& self) -> String {
+    let mut s = format!(
+      "digraph {} {{\n", quote( self.sys.get().sym() )
+    ) ;
+    for svar in self.svars.iter() {
+      s.push_str( & format!("  {} ;\n", quote( svar.get().sym() )) )
+    }
+    for svar in self.svars.iter() {
+      for dep in self.sorted_deps(svar) {
+        s.push_str(
+          & format!(
+            "  {} -> {} ;\n",
+            quote( svar.get().sym() ), quote( dep.get().sym() )
+          )
+        )
+      }
+    }
+    s.push_str("}\n") ;
+    s
+  }
+
+  /// The graph as a JSON object, mapping each state variable to the list of
+  /// state variables it depends on.
+  pub fn to_json(& self) -> String {
+    let mut s = format!(
+      "{{\"system\":{},\"deps\":{{", quote( self.sys.get().sym() )
+    ) ;
+    for (index, svar) in self.svars.iter().enumerate() {
+      if index > 0 { s.push(',') }
+      s.push_str( & format!("{}:[", quote( svar.get().sym() )) ) ;
+      for (index, dep) in self.sorted_deps(svar).into_iter().enumerate() {
+        if index > 0 { s.push(',') }
+        s.push_str( & quote( dep.get().sym() ) )
+      }
+      s.push(']')
+    }
+    s.push_str("}}\n") ;
+    s
+  }
+}
+
+/// State variables appearing in a term, expanding local definitions.
+fn vars_of(
+  term: & Term, locals: & HashMap<Sym, HashSet<(Sym, State)>>
+) -> HashSet<(Sym, State)> {
+  use term::real_term::Term::* ;
+  let mut res = HashSet::new() ;
+  let mut stack = vec![ term ] ;
+  while let Some(term) = stack.pop() {
+    match * term.get() {
+      V(ref var) => match * var.get() {
+        real_term::Var::SVar(ref sym, ref state) => {
+          res.insert( (sym.clone(), * state) ) ; ()
+        },
+        real_term::Var::Var(ref sym) => if let Some(vars) = locals.get(sym) {
+          res.extend( vars.iter().cloned() )
+        },
+      },
+      C(_) => (),
+      Op(_, ref kids) | App(_, ref kids) => stack.extend( kids.iter() ),
+      Let(ref bindings, ref body) => {
+        for & (_, ref term) in bindings.iter() { stack.push(term) }
+        stack.push(body)
+      },
+      Forall(_, ref body) | Exists(_, ref body) => stack.push(body),
+    }
+  }
+  res
+}
+
+/// State variables whose next value is defined by a conjunct of the
+/// transition relation.
+fn defs_of(term: & Term) -> HashSet<Sym> {
+  use term::real_term::Term::* ;
+  let mut res = HashSet::new() ;
+  match * term.get() {
+    Op(Operator::And, ref kids) => for kid in kids.iter() {
+      res.extend( defs_of(kid) )
+    },
+    // Sub-systems and functions may define the next state variables they
+    // are given.
+    Op(Operator::Eq, ref kids) | App(_, ref kids) => for kid in kids.iter() {
+      if let V(ref var) = * kid.get() {
+        if let real_term::Var::SVar(ref sym, State::Next) = * var.get() {
+          res.insert( sym.clone() ) ;
+        }
+      }
+    },
+    // Defined iff defined in both branches.
+    Op(Operator::Ite, ref kids) if kids.len() == 3 => {
+      let thn = defs_of(& kids[1]) ;
+      let els = defs_of(& kids[2]) ;
+      res.extend( thn.intersection(& els).cloned() )
+    },
+    Let(_, ref body) => res.extend( defs_of(body) ),
+    _ => (),
+  }
+  res
+}
+
+/// Quotes a string, escaping quotes and backslashes. Valid for both `dot`
+/// and JSON.
+pub fn quote(s: & str) -> String {
+  let mut res = String::with_capacity(s.len() + 2) ;
+  res.push('"') ;
+  for c in s.chars() {
+    match c {
+      '"' => res.push_str("\\\""),
+      '\\' => res.push_str("\\\\"),
+      '\n' => res.push_str("\\n"),
+      c => res.push(c),
+    }
+  }
+  res.push('"') ;
+  res
+}
@@ -48,6 +48,11 @@ extern crate nom ;
 extern crate error_chain ;
 #[macro_use]
 extern crate term ;
+#[cfg(feature = "serialize")]
+extern crate serde ;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive ;
 
 use std::sync::Arc ;
 use std::fmt ;
@@ -130,6 +135,17 @@ impl fmt::Display for Error {
 mod base ;
 mod type_check ;
 mod parse ;
+pub mod deps ;
+pub mod bounds ;
+pub mod aiger ;
+pub mod order ;
+pub mod pre ;
+pub mod live ;
+pub mod ltl ;
+pub mod library ;
+pub mod smv ;
+#[cfg(feature = "serialize")]
+pub mod serial ;
 
 /// Real types of the elements of a context.
 pub mod real_sys {
@@ -148,7 +164,7 @@ pub mod ctxt {
   pub use type_check::type_check ;
 }
 
-pub use base::{ CallSet, PropStatus } ;
+pub use base::{ CallSet, PropStatus, Live } ;
 
 pub use parse::Cex ;
 
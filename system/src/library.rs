@@ -0,0 +1,101 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Library files, containing only function declarations and definitions.
+//!
+//! A library is parsed and type checked in a context of its own, which must
+//! not define systems or properties. Its callables are then added to the
+//! context of the model with
+//! [`Context::add_library`](../ctxt/struct.Context.html#method.add_library),
+//! which fails if one of their symbols is already defined.
+//!
+//! [`LibCache`](struct.LibCache.html) elaborates each file at most once in
+//! a run: loading it again yields the same callables, which adding to a
+//! context a second time does nothing.
+
+use std::sync::Arc ;
+use std::fs::{ self, File } ;
+use std::path::PathBuf ;
+use std::collections::HashMap ;
+
+use term::Factory ;
+
+use parse::{ Context, Res } ;
+
+/// The callables of a library file.
+pub struct Library {
+  /// Path of the file.
+  path: String,
+  /// Callables of the file.
+  callables: Vec<::Callable>,
+}
+impl Library {
+  /// Parses a library file.
+  pub fn read(path: & str, factory: & Factory) -> Result<Self, String> {
+    let mut file = match File::open(path) {
+      Ok(file) => file,
+      Err(e) => return Err(
+        format!("could not open library \"{}\": {}", path, e)
+      ),
+    } ;
+    let mut ctxt = Context::mk(factory.clone(), 10000) ;
+    match ctxt.read(& mut file) {
+      Ok(Res::Success) | Ok(Res::Exit) => (),
+      Ok(_) => return Err(
+        format!("library \"{}\" has a verify query", path)
+      ),
+      Err(e) => return Err(
+        format!("in library \"{}\": {}", path, e)
+      ),
+    }
+    if ! ctxt.defines_only_callables() {
+      return Err(
+        format!(
+          "library \"{}\" defines systems or properties, \
+          it should only declare and define functions", path
+        )
+      )
+    }
+    Ok(
+      Library { path: path.to_string(), callables: ctxt.callables() }
+    )
+  }
+  /// Path of the file of a library.
+  #[inline(always)]
+  pub fn path(& self) -> & str { & self.path }
+  /// Callables of a library.
+  #[inline(always)]
+  pub fn callables(& self) -> & [::Callable] { & self.callables }
+}
+
+/// Libraries loaded in a run, by canonical path.
+pub struct LibCache {
+  /// Libraries loaded.
+  libs: HashMap<PathBuf, Arc<Library>>,
+}
+impl LibCache {
+  /// Creates an empty cache.
+  pub fn mk() -> Self {
+    LibCache { libs: HashMap::new() }
+  }
+  /// Loads a library file, parsing it only if it was not loaded already.
+  pub fn load(
+    & mut self, path: & str, factory: & Factory
+  ) -> Result<Arc<Library>, String> {
+    let key = fs::canonicalize(path).unwrap_or_else(
+      |_| PathBuf::from(path)
+    ) ;
+    if let Some(lib) = self.libs.get(& key) {
+      return Ok( lib.clone() )
+    }
+    let lib = Arc::new( try!( Library::read(path, factory) ) ) ;
+    let _ = self.libs.insert( key, lib.clone() ) ;
+    Ok(lib)
+  }
+}
@@ -0,0 +1,220 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! K-liveness reduction of liveness properties to safety.
+//!
+//! A counterexample to a liveness property is a fair path, on which each
+//! fairness constraint of the system holds infinitely often, where some
+//! *waiting* condition eventually holds forever:
+//!
+//! - for `(F p)`, `<prop>@live.wait` holds while `p` has never held,
+//! - for `(G (F p))`, `<prop>@live.wait` is chosen non-deterministically,
+//!   and once it holds it keeps holding and `p` does not.
+//!
+//! A *visit* happens in a state where the system is waiting and each
+//! fairness constraint held at least once since the previous visit, which
+//! the boolean `<prop>@live.fair<i>` state variables record. The integer
+//! state variable `<prop>@live.count` counts the visits, and the property
+//! becomes `(and (<= 0 <prop>@live.count) (<= <prop>@live.count k))`. The
+//! lower bound always holds, but makes the property k-inductive when
+//! waiting for long without visiting is impossible.
+//!
+//! A counterexample visits infinitely often, so proving the safety property
+//! for any `k` proves the liveness property. Falsifying it proves nothing
+//! though: the bound `k` was too low, or the liveness property is false.
+
+use std::sync::Arc ;
+
+use term::{
+  Sym, Term, STerm, Type, Int, State, Factory, Zero, One,
+  OpMaker, CstMaker, VarMaker, SymMaker, UnTermOps
+} ;
+use term::parsing::Spnd ;
+
+use base::{ Prop, Live } ;
+
+/// Name of a state variable of the reduction of a property.
+pub fn name_of(prop: & Sym, var: & str) -> String {
+  format!("{}@live.{}", prop.sym(), var)
+}
+
+/// The system extended with the state variables of the reduction of the
+/// liveness properties, and the properties with the liveness ones replaced
+/// by their safety version for bound `k`.
+pub fn reduce(
+  sys: & ::Sys, props: & [::Prop], k: usize, factory: & Factory
+) -> Result< (::Sys, Vec<::Prop>), String > {
+  use term::Operator::* ;
+  if props.iter().all( |prop| prop.live().is_none() ) {
+    return Ok( (sys.clone(), props.to_vec()) )
+  }
+
+  let mut extra = vec![] ;
+  let mut init_conjs = vec![] ;
+  let mut trans_conjs = vec![] ;
+
+  let zero: Term = factory.cst( Int::zero() ) ;
+  let one: Term = factory.cst( Int::one() ) ;
+  let bound: Term = factory.cst( Int::from(k) ) ;
+
+  let mut reduced = Vec::with_capacity( props.len() ) ;
+  for prop in props.iter() {
+    let live = match prop.live() {
+      Some(live) => live,
+      None => {
+        reduced.push( prop.clone() ) ;
+        continue
+      },
+    } ;
+    let span = prop.sym().span.clone() ;
+    let (p, p_next) = match * prop.body() {
+      STerm::One(ref curr, ref next) => (curr.clone(), next.clone()),
+      STerm::Two(_) => return Err(
+        format!("liveness property {} has a two-state body", prop.sym())
+      ),
+    } ;
+
+    // Declares a new state variable, returns its current and next version.
+    let mut declare = |var: & str, typ: Type| {
+      let sym = factory.sym( name_of(prop.sym().get(), var) ) ;
+      if sys.state().contains(& sym) {
+        return Err(
+          format!(
+            "cannot reduce liveness property {}, system {} already has a \
+            state variable `{}`", prop.sym(), sys.sym(), sym
+          )
+        )
+      }
+      let curr: Term = factory.svar( sym.clone(), State::Curr ) ;
+      let next: Term = factory.svar( sym.clone(), State::Next ) ;
+      extra.push(
+        ( Spnd::mk(sym, span.clone()), Spnd::mk(typ, span.clone()) )
+      ) ;
+      Ok( (curr, next) )
+    } ;
+
+    let (wait, wait_next) = try!( declare("wait", Type::Bool) ) ;
+    match live {
+      Live::Eventually => {
+        init_conjs.push(
+          factory.eq( vec![ wait.clone(), factory.not( p.clone() ) ] )
+        ) ;
+        trans_conjs.push(
+          factory.eq(
+            vec![
+              wait_next.clone(), factory.and(
+                vec![ wait.clone(), factory.not(p_next) ]
+              )
+            ]
+          )
+        )
+      },
+      Live::InfinitelyOften => {
+        init_conjs.push(
+          factory.op( Impl, vec![ wait.clone(), factory.not(p) ] )
+        ) ;
+        trans_conjs.push(
+          factory.op( Impl, vec![ wait.clone(), wait_next.clone() ] )
+        ) ;
+        trans_conjs.push(
+          factory.op(
+            Impl, vec![ wait_next.clone(), factory.not(p_next) ]
+          )
+        )
+      },
+    }
+
+    // Visit in the current state, and what the fairness flags become.
+    let mut visit = vec![ wait ] ;
+    let mut flags = Vec::with_capacity( sys.fair().len() ) ;
+    for (index, fair) in sys.fair().iter().enumerate() {
+      let (flag, flag_next) = try!(
+        declare(& format!("fair{}", index), Type::Bool)
+      ) ;
+      let seen = factory.op( Or, vec![ flag.clone(), fair.clone() ] ) ;
+      init_conjs.push( factory.not(flag) ) ;
+      visit.push( seen.clone() ) ;
+      flags.push( (flag_next, seen) )
+    }
+    let visit = factory.and(visit) ;
+    for (flag_next, seen) in flags.into_iter() {
+      trans_conjs.push(
+        factory.eq(
+          vec![
+            flag_next, factory.and(
+              vec![ factory.not( visit.clone() ), seen ]
+            )
+          ]
+        )
+      )
+    }
+    let visit_next = match factory.bump( visit.clone() ) {
+      Ok(visit) => visit,
+      Err(_) => return Err(
+        format!(
+          "liveness property {} or the fairness constraints of system {} \
+          mention next state variables", prop.sym(), sys.sym()
+        )
+      ),
+    } ;
+
+    let (count, count_next) = try!( declare("count", Type::Int) ) ;
+    init_conjs.push(
+      factory.eq(
+        vec![
+          count.clone(), factory.op(
+            Ite, vec![ visit, one.clone(), zero.clone() ]
+          )
+        ]
+      )
+    ) ;
+    trans_conjs.push(
+      factory.eq(
+        vec![
+          count_next.clone(), factory.op(
+            Add, vec![
+              count.clone(), factory.op(
+                Ite, vec![ visit_next, one.clone(), zero.clone() ]
+              )
+            ]
+          )
+        ]
+      )
+    ) ;
+
+    let safe = factory.and(
+      vec![
+        factory.op( Le, vec![ zero.clone(), count.clone() ] ),
+        factory.op( Le, vec![ count, bound.clone() ] )
+      ]
+    ) ;
+    let safe_next = factory.and(
+      vec![
+        factory.op( Le, vec![ zero.clone(), count_next.clone() ] ),
+        factory.op( Le, vec![ count_next, bound.clone() ] )
+      ]
+    ) ;
+    reduced.push(
+      Arc::new(
+        Prop::mk_live(
+          prop.sym().clone(), prop.sys().clone(),
+          STerm::One(safe, safe_next), prop.calls().clone(), live
+        )
+      )
+    )
+  }
+  let sys = Arc::new(
+    try!(
+      sys.with_extra_state(
+        extra, init_conjs, trans_conjs, vec![], & [], factory
+      )
+    )
+  ) ;
+  Ok( (sys, reduced) )
+}
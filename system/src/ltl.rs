@@ -0,0 +1,322 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! LTL properties, compiled to a tableau composed with the system.
+//!
+//! `(define-ltl <prop> <sys> <formula>)` defines a property from an LTL
+//! formula over one-state atoms, with the boolean operators `not`, `and`,
+//! `or`, `=>` and the temporal ones
+//!
+//! - `(X p)`, `p` holds in the next state,
+//! - `(F p)`, `p` eventually holds, `(G p)`, `p` always holds,
+//! - `(U p q)`, `p` holds until `q` does, which it eventually does,
+//! - `(W p q)`, `p` holds until `q` does, or forever,
+//! - `(R p q)`, `q` holds until and including when `p` does, or forever.
+//!
+//! The tableau of the negation of the formula is added to the system: a
+//! boolean state variable `<prop>@ltl.x<i>` for each `X` and `U`
+//! sub-formula holds iff the sub-formula, shifted by one state, holds. Each
+//! `U` sub-formula adds a fairness constraint to the system, so that it is
+//! not postponed forever. `<prop>@ltl.start` holds on the paths starting
+//! where the negation of the formula holds.
+//!
+//! The formula holds iff no fair path has `<prop>@ltl.start`, so the
+//! property is the liveness property `(F (not <prop>@ltl.start))`, reduced
+//! to safety by the [`live`](../live/index.html) module. The tableau only
+//! constrains its own state variables: it does not change the behavior of
+//! the system, and a path of the system can always be extended to a fair
+//! path of the tableau.
+
+use std::sync::Arc ;
+
+use term::{
+  Sym, Term, Type, State, Factory, OpMaker, CstMaker, VarMaker, SymMaker,
+  UnTermOps
+} ;
+use term::real_term::Term as RTerm ;
+use term::parsing::Spnd ;
+
+/// An LTL formula, where `F`, `G`, `W` and `R` are rewritten with `U`.
+#[derive(Debug, Clone)]
+pub enum Ltl {
+  /// One-state atom.
+  Atom(Term),
+  /// Negation.
+  Not(Box<Ltl>),
+  /// Conjunction.
+  And(Vec<Ltl>),
+  /// Disjunction.
+  Or(Vec<Ltl>),
+  /// Next.
+  X(Box<Ltl>),
+  /// Until.
+  U(Box<Ltl>, Box<Ltl>),
+}
+impl Ltl {
+  /// True if a symbol is a temporal operator, and not a function.
+  pub fn is_temporal(sym: & Sym, is_fun: & Fn(& Sym) -> bool) -> bool {
+    match sym.sym() {
+      "X" | "F" | "G" | "U" | "W" | "R" => ! is_fun(sym),
+      _ => false,
+    }
+  }
+
+  /// True if a term mentions a temporal operator.
+  fn has_temporal(term: & Term, is_fun: & Fn(& Sym) -> bool) -> bool {
+    match * term.get() {
+      RTerm::App(ref sym, ref kids) => Self::is_temporal(sym, is_fun) || (
+        kids.iter().any( |kid| Self::has_temporal(kid, is_fun) )
+      ),
+      RTerm::Op(_, ref kids) => kids.iter().any(
+        |kid| Self::has_temporal(kid, is_fun)
+      ),
+      RTerm::Forall(_, ref kid) |
+      RTerm::Exists(_, ref kid) => Self::has_temporal(kid, is_fun),
+      RTerm::Let(ref binds, ref kid) => Self::has_temporal(kid, is_fun) || (
+        binds.iter().any( |& (_, ref bound)| Self::has_temporal(bound, is_fun) )
+      ),
+      _ => false,
+    }
+  }
+
+  /// Builds a formula from a term, the applications of `X`, `F`, `G`, `U`,
+  /// `W` and `R` being temporal operators unless `is_fun` says they are
+  /// functions.
+  pub fn of_term(
+    term: & Term, factory: & Factory, is_fun: & Fn(& Sym) -> bool
+  ) -> Result<Ltl, String> {
+    use term::Operator::* ;
+    if ! Self::has_temporal(term, is_fun) {
+      return Ok( Ltl::Atom( term.clone() ) )
+    }
+    let mut kids = vec![] ;
+    match * term.get() {
+      RTerm::App(ref sym, ref args) if Self::is_temporal(sym, is_fun) => {
+        let arity = match sym.sym() {
+          "X" | "F" | "G" => 1,
+          _ => 2,
+        } ;
+        if args.len() != arity {
+          return Err(
+            format!(
+              "temporal operator `{}` expects {} argument(s), got {}",
+              sym, arity, args.len()
+            )
+          )
+        }
+        for arg in args.iter() {
+          kids.push( try!( Self::of_term(arg, factory, is_fun) ) )
+        }
+        let tru = Ltl::Atom( factory.cst(true) ) ;
+        let mut kids = kids.into_iter() ;
+        let lhs = kids.next().unwrap() ;
+        let res = match sym.sym() {
+          "X" => Ltl::X( Box::new(lhs) ),
+          "F" => Ltl::U( Box::new(tru), Box::new(lhs) ),
+          "G" => Ltl::globally(lhs, factory),
+          "U" => Ltl::U( Box::new(lhs), Box::new( kids.next().unwrap() ) ),
+          "W" => {
+            let rhs = kids.next().unwrap() ;
+            Ltl::Or(
+              vec![
+                Ltl::U( Box::new( lhs.clone() ), Box::new(rhs) ),
+                Ltl::globally(lhs, factory)
+              ]
+            )
+          },
+          _ => {
+            let rhs = kids.next().unwrap() ;
+            Ltl::Not(
+              Box::new(
+                Ltl::U(
+                  Box::new( Ltl::Not( Box::new(lhs) ) ),
+                  Box::new( Ltl::Not( Box::new(rhs) ) )
+                )
+              )
+            )
+          },
+        } ;
+        Ok(res)
+      },
+      RTerm::Op(op, ref args) => {
+        for arg in args.iter() {
+          kids.push( try!( Self::of_term(arg, factory, is_fun) ) )
+        }
+        match op {
+          Not if kids.len() == 1 => Ok(
+            Ltl::Not( Box::new( kids.pop().unwrap() ) )
+          ),
+          And => Ok( Ltl::And(kids) ),
+          Or => Ok( Ltl::Or(kids) ),
+          Impl if kids.len() == 2 => {
+            let rhs = kids.pop().unwrap() ;
+            let lhs = kids.pop().unwrap() ;
+            Ok( Ltl::Or( vec![ Ltl::Not( Box::new(lhs) ), rhs ] ) )
+          },
+          _ => Err(
+            format!(
+              "temporal operators can only appear under `not`, `and`, `or` \
+              and `=>`, found `{}`", op
+            )
+          ),
+        }
+      },
+      _ => Err(
+        format!(
+          "temporal operators can only appear under `not`, `and`, `or` \
+          and `=>`, in `{}`", term
+        )
+      ),
+    }
+  }
+
+  /// `(G f)`, that is `(not (U true (not f)))`.
+  fn globally(f: Ltl, factory: & Factory) -> Ltl {
+    Ltl::Not(
+      Box::new(
+        Ltl::U(
+          Box::new( Ltl::Atom( factory.cst(true) ) ),
+          Box::new( Ltl::Not( Box::new(f) ) )
+        )
+      )
+    )
+  }
+
+  /// The one-state atoms of a formula.
+  pub fn atoms(& self) -> Vec<& Term> {
+    let mut atoms = vec![] ;
+    let mut stack = vec![ self ] ;
+    while let Some(f) = stack.pop() {
+      match * f {
+        Ltl::Atom(ref term) => atoms.push(term),
+        Ltl::Not(ref kid) | Ltl::X(ref kid) => stack.push(kid),
+        Ltl::And(ref kids) | Ltl::Or(ref kids) => stack.extend(kids),
+        Ltl::U(ref lhs, ref rhs) => {
+          stack.push(lhs) ;
+          stack.push(rhs)
+        },
+      }
+    }
+    atoms
+  }
+}
+
+/// Name of the state variable of the tableau of a property.
+pub fn name_of(prop: & Sym, var: & str) -> String {
+  format!("{}@ltl.{}", prop.sym(), var)
+}
+
+/// Tableau of a formula being built.
+struct Tableau<'a> {
+  /// Factory.
+  factory: & 'a Factory,
+  /// Property the tableau is for.
+  prop: & 'a Sym,
+  /// Boolean state variables of the tableau.
+  svars: Vec<Sym>,
+  /// Conjuncts of the transition relation.
+  trans: Vec<Term>,
+  /// Fairness constraints.
+  fair: Vec<Term>,
+}
+impl<'a> Tableau<'a> {
+  /// A new state variable, its current and next versions.
+  fn fresh(& mut self) -> (Term, Term) {
+    let sym = self.factory.sym(
+      name_of( self.prop, & format!("x{}", self.svars.len()) )
+    ) ;
+    self.svars.push( sym.clone() ) ;
+    (
+      self.factory.svar( sym.clone(), State::Curr ),
+      self.factory.svar(sym, State::Next)
+    )
+  }
+
+  /// Next state version of a term.
+  fn bump(& self, term: & Term) -> Result<Term, String> {
+    self.factory.bump( term.clone() ).map_err(
+      |_| format!("atom `{}` of the formula mentions next state", term)
+    )
+  }
+
+  /// The term holding iff a formula holds in the current state.
+  fn sat(& mut self, f: & Ltl) -> Result<Term, String> {
+    use term::Operator::* ;
+    match * f {
+      Ltl::Atom(ref term) => Ok( term.clone() ),
+      Ltl::Not(ref kid) => Ok( self.factory.not( try!( self.sat(kid) ) ) ),
+      Ltl::And(ref kids) => {
+        let mut conjs = Vec::with_capacity( kids.len() ) ;
+        for kid in kids.iter() { conjs.push( try!( self.sat(kid) ) ) }
+        Ok( self.factory.and(conjs) )
+      },
+      Ltl::Or(ref kids) => {
+        let mut disjs = Vec::with_capacity( kids.len() ) ;
+        for kid in kids.iter() { disjs.push( try!( self.sat(kid) ) ) }
+        Ok( self.factory.op(Or, disjs) )
+      },
+      Ltl::X(ref kid) => {
+        let kid = try!( self.sat(kid) ) ;
+        let kid_next = try!( self.bump(& kid) ) ;
+        let (curr, _) = self.fresh() ;
+        self.trans.push( self.factory.eq( vec![ curr.clone(), kid_next ] ) ) ;
+        Ok(curr)
+      },
+      Ltl::U(ref lhs, ref rhs) => {
+        let lhs = try!( self.sat(lhs) ) ;
+        let rhs = try!( self.sat(rhs) ) ;
+        let (curr, _) = self.fresh() ;
+        let sat = self.factory.op(
+          Or, vec![
+            rhs.clone(), self.factory.and( vec![ lhs, curr.clone() ] )
+          ]
+        ) ;
+        let sat_next = try!( self.bump(& sat) ) ;
+        self.trans.push( self.factory.eq( vec![ curr, sat_next ] ) ) ;
+        self.fair.push(
+          self.factory.op( Or, vec![ self.factory.not( sat.clone() ), rhs ] )
+        ) ;
+        Ok(sat)
+      },
+    }
+  }
+}
+
+/// The system extended with the tableau of the negation of a formula, and
+/// the argument of the `F` liveness property equivalent to the formula.
+pub fn compile(
+  sys: & ::Sys, prop: & Spnd<Sym>, formula: & Ltl, factory: & Factory
+) -> Result< (::Sys, Term), String > {
+  let mut tableau = Tableau {
+    factory: factory, prop: prop.get(),
+    svars: vec![], trans: vec![], fair: vec![],
+  } ;
+  let neg = factory.not( try!( tableau.sat(formula) ) ) ;
+  let start = factory.sym( name_of(prop.get(), "start") ) ;
+  let start_curr: Term = factory.svar( start.clone(), State::Curr ) ;
+  let start_next: Term = factory.svar( start.clone(), State::Next ) ;
+  let Tableau { svars, mut trans, fair, .. } = tableau ;
+  trans.push( factory.eq( vec![ start_next, start_curr.clone() ] ) ) ;
+
+  let extra = svars.into_iter().chain( Some(start).into_iter() ).map(
+    |sym| (
+      Spnd::mk( sym, prop.span.clone() ),
+      Spnd::mk( Type::Bool, prop.span.clone() )
+    )
+  ).collect() ;
+  let init = factory.eq( vec![ start_curr.clone(), neg ] ) ;
+  let sys = Arc::new(
+    try!(
+      sys.with_extra_state(
+        extra, vec![ init ], trans, fair, & [], factory
+      )
+    )
+  ) ;
+  Ok( (sys, factory.not(start_curr)) )
+}
@@ -0,0 +1,193 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Order of the state variables of a system.
+//!
+//! The order of the state of a system is the order in which the unrollers
+//! declare the state variables, and the order of the columns of the
+//! counterexamples. Solvers and readers of traces are both sensitive to it.
+//! [`reorder`](fn.reorder.html) builds the same system with its state in
+//! some other order:
+//!
+//! - `input`, the order of the input file,
+//! - `deps`, the inputs (see [`DepGraph`](../deps/struct.DepGraph.html))
+//!   first, then each state variable after the ones it depends on, cycles
+//!   being broken in input order,
+//! - `alpha`, alphabetical order,
+//! - a list of state variables separated by commas, the ones not listed
+//!   coming after them in input order.
+
+use std::sync::Arc ;
+use std::collections::{ HashMap, HashSet } ;
+
+use term::{ Sym, Factory, AppMaker } ;
+
+use base::{ Sys, Args } ;
+use deps::DepGraph ;
+
+/// An order of the state variables of a system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Order {
+  /// Order of the input file.
+  Input,
+  /// Dependency order.
+  Deps,
+  /// Alphabetical order.
+  Alpha,
+  /// Some state variables first, in this order.
+  Custom(Vec<String>),
+}
+impl Order {
+  /// String representation, parsed by [`of_str`](#method.of_str).
+  pub fn to_str(& self) -> String {
+    match * self {
+      Order::Input => "input".to_string(),
+      Order::Deps => "deps".to_string(),
+      Order::Alpha => "alpha".to_string(),
+      Order::Custom(ref names) => names.join(","),
+    }
+  }
+
+  /// Parses an order, anything but `input`, `deps` and `alpha` is a list
+  /// of state variables separated by commas.
+  pub fn of_str(txt: & str) -> Result<Self, String> {
+    match txt.trim() {
+      "input" => Ok(Order::Input),
+      "deps" => Ok(Order::Deps),
+      "alpha" => Ok(Order::Alpha),
+      txt => {
+        let names: Vec<String> = txt.split(',').map(
+          |name| name.trim().to_string()
+        ).collect() ;
+        if names.iter().any( |name| name.is_empty() ) {
+          Err(
+            format!(
+              "expected order [input/deps/alpha/<svar>,...], got {}", txt
+            )
+          )
+        } else { Ok( Order::Custom(names) ) }
+      },
+    }
+  }
+
+  /// State variables of a system in this order. Fails if a custom order
+  /// lists something that is not a state variable of the system.
+  pub fn svars(& self, sys: & Sys) -> Result<Vec<Sym>, String> {
+    let input: Vec<Sym> = sys.state().args().iter().map(
+      |& (ref sym, _)| sym.get().clone()
+    ).collect() ;
+    match * self {
+      Order::Input => Ok(input),
+      Order::Alpha => {
+        let mut res = input ;
+        res.sort_by( |lhs, rhs| lhs.sym().cmp( rhs.sym() ) ) ;
+        Ok(res)
+      },
+      Order::Deps => {
+        let graph = DepGraph::of(sys) ;
+        let mut res = Vec::with_capacity( input.len() ) ;
+        let mut known = HashSet::with_capacity( input.len() ) ;
+        for svar in graph.inputs() {
+          known.insert( svar.clone() ) ;
+          res.push( svar.clone() )
+        }
+        for svar in input.iter() {
+          deps_first(& graph, svar, & mut known, & mut res)
+        }
+        Ok(res)
+      },
+      Order::Custom(ref names) => {
+        let mut res = Vec::with_capacity( input.len() ) ;
+        for name in names.iter() {
+          match input.iter().find( |svar| svar.sym() == * name ) {
+            Some(svar) => if ! res.contains(svar) {
+              res.push( svar.clone() )
+            },
+            None => return Err(
+              format!(
+                "`{}` is not a state variable of system {}", name, sys.sym()
+              )
+            ),
+          }
+        }
+        for svar in input.into_iter() {
+          if ! res.contains(& svar) { res.push(svar) }
+        }
+        Ok(res)
+      },
+    }
+  }
+}
+
+/// Adds a state variable after the ones it depends on, if not known
+/// already.
+fn deps_first(
+  graph: & DepGraph, svar: & Sym, known: & mut HashSet<Sym>,
+  res: & mut Vec<Sym>
+) {
+  if ! known.insert( svar.clone() ) { return () }
+  if let Some(deps) = graph.deps_of(svar) {
+    // Hash sets have no order, following the one of the system.
+    for dep in graph.svars().iter() {
+      if deps.contains(dep) { deps_first(graph, dep, known, res) }
+    }
+  }
+  res.push( svar.clone() )
+}
+
+/// The same system with its state in some order.
+pub fn reorder(
+  sys: & ::Sys, order: & Order, factory: & Factory
+) -> Result<::Sys, String> {
+  let svars = try!( order.svars(sys) ) ;
+  let args = sys.state().args() ;
+  let index: HashMap<& Sym, usize> = args.iter().enumerate().map(
+    |(index, & (ref sym, _))| (sym.get(), index)
+  ).collect() ;
+  let perm: Vec<usize> = svars.iter().map( |svar| index[svar] ).collect() ;
+  if perm.iter().enumerate().all( |(index, old)| index == * old ) {
+    return Ok( sys.clone() )
+  }
+
+  let state = perm.iter().map( |old| args[* old].clone() ).collect() ;
+  let (init_sym, old_init, init_body, _) = sys.init().clone() ;
+  let init_state: Vec<_> = perm.iter().map(
+    |old| old_init[* old].clone()
+  ).collect() ;
+  // Current state variables first, then the next ones.
+  let (trans_sym, old_trans, trans_body, _) = sys.trans().clone() ;
+  let half = old_trans.len() / 2 ;
+  let trans_state: Vec<_> = perm.iter().map(
+    |old| old_trans[* old].clone()
+  ).chain(
+    perm.iter().map( |old| old_trans[half + * old].clone() )
+  ).collect() ;
+
+  let init_term = factory.app(
+    init_sym.clone(), init_state.iter().map(
+      |& (ref var, _)| factory.mk_var( var.clone() )
+    ).collect()
+  ) ;
+  let trans_term = factory.app(
+    trans_sym.clone(), trans_state.iter().map(
+      |& (ref var, _)| factory.mk_var( var.clone() )
+    ).collect()
+  ) ;
+
+  Ok(
+    Arc::new(
+      Sys::mk(
+        sys.sym().clone(), Args::mk(state), sys.locals().to_vec(),
+        (init_sym, init_state, init_body, init_term),
+        (trans_sym, trans_state, trans_body, trans_term),
+        sys.fair().to_vec(), sys.subsys().to_vec(), sys.calls().clone()
+      )
+    )
+  )
+}
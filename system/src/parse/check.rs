@@ -19,6 +19,7 @@ use term::parsing::* ;
 use term::real_term ;
 
 use base::* ;
+use ltl::Ltl ;
 use super::{ Context, Atom, Res } ;
 
 use self::CheckError::* ;
@@ -300,7 +301,7 @@ pub fn check_fun_dec(
   ctxt: & Context, sym: Spnd<Sym>, sig: Sig, typ: Spnd<Type>
 ) -> Result<Callable, InternalParseError> {
   new_check_sym!(ctxt, sym) ;
-  match ctxt.factory().set_fun_type(sym.get().clone(), * typ.get()) {
+  match ctxt.factory().set_fun_type(sym.get().clone(), typ.get().clone()) {
     Ok(()) => (),
     Err(e) => return Err(
       InternalParseError::mk(
@@ -466,10 +467,170 @@ fn check_term_and_dep(
 pub fn check_prop(
   ctxt: & Context, sym: Spnd<Sym>, spnd_sys: Spnd<Sym>, body: TermAndDep
 ) -> Result<Prop, InternalParseError> {
+  new_check_sym!(ctxt, sym) ;
+  let (sys, body, calls) = try!(
+    check_one_state(ctxt, & sym.span, spnd_sys, body, "property")
+  ) ;
+  Ok(
+    Prop::mk(sym.clone(), sys, body, calls)
+  )
+}
+
+/// Checks that an LTL property definition is legal. Returns its system, its
+/// formula and the functions it uses.
+pub fn check_ltl(
+  ctxt: & Context, sym: Spnd<Sym>, spnd_sys: Spnd<Sym>, body: TermAndDep
+) -> Result<(::Sys, Ltl, CallSet), InternalParseError> {
   use term::State::Curr ;
-  use term::UnTermOps ;
   new_check_sym!(ctxt, sym) ;
 
+  let sys = match ctxt.get_sys( & spnd_sys ) {
+    Some(s) => s.clone(),
+    None => return Err(
+      InternalParseError::mk(
+        spnd_sys.span, "unknown system".into(), vec![]
+      )
+    ),
+  } ;
+  // The tableau extends the state of the system.
+  let used = ctxt.syss.values().any(
+    |other| other.subsys().iter().any(
+      |& (ref sub, _)| sub.sym().get() == sys.sym().get()
+    )
+  ) ;
+  if used {
+    return Err(
+      InternalParseError::mk(
+        spnd_sys.span, format!(
+          "cannot add the tableau of an LTL property to system `{}`, \
+          it is used as a sub-system", sys.sym()
+        ), vec![]
+      )
+    )
+  }
+
+  let is_fun = |sym: & Sym| ctxt.get_callable(sym).is_some() ;
+  let mut calls = CallSet::empty() ;
+
+  // All symbols used in applications are temporal operators or exist.
+  for (ref app_sym, ref spns) in body.apps.iter() {
+    if Ltl::is_temporal(app_sym, & is_fun) { continue }
+    match app_defined(ctxt, app_sym) {
+      None => return Err(
+        InternalParseError::vec_mk(
+          spns,
+          format!(
+            "application of unknown function symbol `{}` in formula", app_sym
+          ),
+          "also used here"
+        )
+      ),
+      Some(fun) => { calls.insert(fun) ; },
+    }
+  } ;
+  // Stateful var belong to state of system, non-stateful var exist.
+  for (ref var, ref spns) in body.vars.iter() {
+    match * var.get() {
+      real_term::Var::Var(ref var_sym) => match var_defined(ctxt, var_sym) {
+        None => return Err(
+          InternalParseError::vec_mk(
+            spns,
+            format!(
+              "unknown constant function symbol `{}` in formula", var_sym
+            ),
+            "also used here"
+          )
+        ),
+        Some(fun) => { calls.insert(fun) ; },
+      },
+      real_term::Var::SVar(ref var_sym, Curr) => if ! svar_in_state(
+        var_sym, sys.state()
+      ) {
+        return Err(
+          InternalParseError::vec_mk(
+            spns,
+            format!("unknown state variable `{}` in formula", var_sym),
+            "also used here"
+          ).add_note(
+            spnd_sys.span,
+            "state variables must belong \
+            to the system referenced here".into()
+          )
+        )
+      },
+      real_term::Var::SVar(_, ::term::State::Next) => return Err(
+        InternalParseError::vec_mk(
+          spns,
+          format!(
+            "illegal state variable in next state `{}` in formula", var.get()
+          ),
+          "also used here"
+        ).add_note(
+          spnd_sys.span,
+          "use the temporal operator `X` instead".into()
+        )
+      ),
+    }
+  } ;
+
+  let ltl = match Ltl::of_term(& body.term, ctxt.factory(), & is_fun) {
+    Ok(ltl) => ltl,
+    Err(blah) => return Err(
+      InternalParseError::mk(body.span, blah, vec![])
+    ),
+  } ;
+  for atom in ltl.atoms() {
+    try!{
+      new_type_check!(
+        ctxt, atom, Spnd::mk(
+          Type::Bool, sym.span.clone()
+        ), state: sys.state().args(),
+        sym.span,
+        t => "atom `{}` of LTL formula should have type Bool, got {}", atom, t
+      )
+    }
+  }
+
+  Ok( (sys, ltl, calls) )
+}
+
+/// Checks that a fairness constraint is legal, and returns the system
+/// extended with it.
+pub fn check_fair(
+  ctxt: & Context, spnd_sys: Spnd<Sym>, body: TermAndDep
+) -> Result<Sys, InternalParseError> {
+  let span = spnd_sys.span.clone() ;
+  let (sys, body, calls) = try!(
+    check_one_state(ctxt, & span, spnd_sys, body, "fairness constraint")
+  ) ;
+  let fair = match body {
+    STerm::One(curr, _) => curr,
+    STerm::Two(next) => next,
+  } ;
+  let mut sys_calls = sys.calls().clone() ;
+  for fun in calls.get().iter() {
+    sys_calls.insert( fun.clone() ) ;
+  }
+  let mut fairs = sys.fair().to_vec() ;
+  fairs.push(fair) ;
+  Ok(
+    Sys::mk(
+      sys.sym().clone(), sys.state().clone(), sys.locals().to_vec(),
+      sys.init().clone(), sys.trans().clone(), fairs,
+      sys.subsys().to_vec(), sys_calls
+    )
+  )
+}
+
+/// Checks that a one-state boolean term over the state of a system is
+/// legal. `desc` describes the term in the errors.
+fn check_one_state(
+  ctxt: & Context, span: & Spn, spnd_sys: Spnd<Sym>, body: TermAndDep,
+  desc: & 'static str
+) -> Result<(::Sys, STerm, CallSet), InternalParseError> {
+  use term::State::Curr ;
+  use term::UnTermOps ;
+
   let sys = match ctxt.get_sys( & spnd_sys ) {
     Some(s) => s.clone(),
     None => {
@@ -552,10 +713,10 @@ pub fn check_prop(
   try!{
     new_type_check!(
       ctxt, body.term, Spnd::mk(
-        Type::Bool, sym.span.clone()
+        Type::Bool, span.clone()
       ), state: sys.state().args(),
-      sym.span,
-      t => "body of property should have type Bool, got {}", t
+      span.clone(),
+      t => "body of {} should have type Bool, got {}", desc, t
     )
   }
 
@@ -563,9 +724,7 @@ pub fn check_prop(
   let nxt = ctxt.factory().bump(body.term.clone()).unwrap() ;
   let body = STerm::One(body.term, nxt) ;
 
-  Ok(
-    Prop::mk(sym.clone(), sys.clone(), body, calls)
-  )
+  Ok( (sys, body, calls) )
 }
 
 /// Checks that a relation definition is legal.
@@ -920,7 +1079,7 @@ pub fn check_sys(
       sym, state, local_vars,
       (init_sym, init_state, init, init_term),
       (trans_sym, trans_state, trans, trans_term),
-      subsys, calls,
+      vec![], subsys, calls,
     )
   )
 }
@@ -26,12 +26,14 @@ static check_ass_desc: & 'static str = "verify with assumption query" ;
 use std::io ;
 use std::fmt ;
 use std::sync::Arc ;
+use std::cell::RefCell ;
 // use std::time::Duration ;
 // use std::thread::sleep ;
 use std::collections::{ HashSet, HashMap } ;
 
 use term::{
-  Type, Offset, Cst, Sym, Term, Factory, Model, STermSet
+  Type, Offset, Cst, Sym, Term, STerm, Factory, Model, STermSet, Operator,
+  SymMaker, UnTermOps, real_term
 } ;
 use term::parsing::* ;
 
@@ -40,6 +42,8 @@ use base::* ;
 mod parsers ;
 pub use self::parsers::InternalParseError ;
 pub mod check ;
+mod patterns ;
+mod stream ;
 
 use self::parsers::* ;
 
@@ -176,17 +180,99 @@ impl Res {
 pub struct Cex {
   sys: ::Sys,
   no_state: HashMap<Sym, Cst>,
-  trace: HashMap<Offset, HashMap<Sym, Cst>>
+  trace: HashMap<Offset, HashMap<Sym, Cst>>,
+  /// Digits of the decimal approximation printed next to rationals, if any.
+  precision: Option<usize>,
 }
 impl Cex {
+  /// Same cex printing a decimal approximation with `precision` digits
+  /// after the point next to its rational values, if any.
+  pub fn with_precision(mut self, precision: Option<usize>) -> Self {
+    self.precision = precision ;
+    self
+  }
+  /// Decimal approximation of a constant, if it is a rational that is not
+  /// an integer and the cex has a precision.
+  fn approx(& self, cst: & Cst) -> Option<String> {
+    self.precision.and_then( |precision| cst.approx(precision) )
+  }
+  /// A constant with its decimal approximation, if any.
+  fn show(& self, cst: & Cst) -> String {
+    match self.approx(cst) {
+      Some(approx) => format!("{} (~{})", cst, approx),
+      None => format!("{}", cst),
+    }
+  }
   /// Length of a cex. Number of states minus one.
   pub fn len(& self) -> usize {
     assert!(self.trace.len() > 0) ;
     self.trace.len() - 1
   }
+  /// States of a cex, in order.
+  fn states(& self) -> Vec<& HashMap<Sym, Cst>> {
+    let mut res = Vec::with_capacity( self.trace.len() ) ;
+    let mut off = Offset::zero() ;
+    while let Some(state) = self.trace.get(& off) {
+      res.push(state) ;
+      off = off.nxt()
+    }
+    res
+  }
+
+  /// Segments of a cex where the same states repeat, as `(first, last,
+  /// period)`: state `i + period` is state `i` for all `i` from `first` to
+  /// `last - period`.
+  ///
+  /// Only segments of three periods at least are reported, the period
+  /// chosen for a segment being the one covering the most states.
+  pub fn loops(& self) -> Vec<(usize, usize, usize)> {
+    let states = self.states() ;
+    let len = states.len() ;
+    let mut res = vec![] ;
+    let mut first = 0 ;
+    while first < len {
+      // Longest segment starting at `first`, as `(last, period)`.
+      let mut best: Option<(usize, usize)> = None ;
+      for period in 1..(len - first) / 3 + 1 {
+        let mut last = first + period - 1 ;
+        while last + 1 < len && states[last + 1] == states[last + 1 - period] {
+          last += 1
+        }
+        let covered = last + 1 - first ;
+        if covered >= 3 * period && best.map_or(
+          true, |(best_last, _)| last > best_last
+        ) {
+          best = Some( (last, period) )
+        }
+      }
+      match best {
+        Some( (last, period) ) => {
+          res.push( (first, last, period) ) ;
+          first = last + 1
+        },
+        None => first += 1,
+      }
+    }
+    res
+  }
+
   /// Formats a counterexample vmt-style.
   pub fn write_vmt<W: io::Write>(
     & self, props: & [ Sym ], fmt: & mut W
+  ) -> io::Result<()> {
+    self.write_vmt_loops(props, & [], fmt)
+  }
+  /// Formats a counterexample vmt-style, the [loops](#method.loops) being
+  /// shown once.
+  pub fn write_vmt_compressed<W: io::Write>(
+    & self, props: & [ Sym ], fmt: & mut W
+  ) -> io::Result<()> {
+    self.write_vmt_loops(props, & self.loops(), fmt)
+  }
+  /// Formats a counterexample vmt-style, some loops being shown once.
+  fn write_vmt_loops<W: io::Write>(
+    & self, props: & [ Sym ], loops: & [ (usize, usize, usize) ],
+    fmt: & mut W
   ) -> io::Result<()> {
     try!( write!(fmt, "(cex\n  ( ") ) ;
     for prop in props.iter() {
@@ -204,59 +290,224 @@ impl Cex {
           write!(
             fmt, "\n    (declare-fun {} () {} {})", sym, cst.typ(), cst
           )
-        )
+        ) ;
+        if let Some(approx) = self.approx(cst) {
+          try!( write!(fmt, " ; ~{}", approx) )
+        }
       }
       try!( write!(fmt, "\n  )\n") ) ;
     }
 
     // Printing states.
-    let mut off = Offset::zero() ;
-    while let Some( ref cex ) = self.trace.get(& off) {
-      try!( write!(fmt, "  ; state {}:\n  (and\n", off) ) ;
-      for (ref sym, ref cst) in cex.iter() {
-        try!( write!(fmt, "    (= {} {})\n", sym, cst) )
+    let states = self.states() ;
+    let mut index = 0 ;
+    while index < states.len() {
+      // Last state shown, and the loop it ends if any.
+      let (last, looped) = match loops.iter().find(
+        |& & (first, _, _)| first == index
+      ) {
+        Some( & (first, last, period) ) => (
+          first + period - 1, Some( (last, period) )
+        ),
+        None => (index, None),
+      } ;
+      for off in index..(last + 1) {
+        try!( write!(fmt, "  ; state {}:\n  (and\n", off) ) ;
+        // In the order of the state, hash maps have none.
+        for & (ref sym, _) in self.sys.state().args() {
+          let cst = match states[off].get( sym.get() ) {
+            Some(cst) => cst,
+            None => continue,
+          } ;
+          try!( write!(fmt, "    (= {} {})", sym, cst) ) ;
+          if let Some(approx) = self.approx(cst) {
+            try!( write!(fmt, " ; ~{}", approx) )
+          }
+          try!( write!(fmt, "\n") )
+        }
+        try!( write!(fmt, "  )\n") )
+      }
+      index = match looped {
+        Some( (loop_last, period) ) => {
+          try!(
+            write!(
+              fmt, "  ; states {}-{}: loop of period {} (shown once)\n",
+              index, loop_last, period
+            )
+          ) ;
+          loop_last + 1
+        },
+        None => last + 1,
       }
-      try!( write!(fmt, "  )\n") ) ;
-      off = off.nxt()
     }
 
     write!(fmt, ")\n")
   }
-  /// Prints a counterexample vmt-style.
+  /// Prints a counterexample vmt-style, the [loops](#method.loops) being
+  /// shown once if `compress`.
   pub fn print_vmt(
-    & self, props: & [ Sym ]
+    & self, props: & [ Sym ], compress: bool
   ) {
-    print!("(cex\n  ( ") ;
-    for prop in props.iter() {
-      print!("{} ", prop)
+    let stdout = io::stdout() ;
+    let mut stdout = stdout.lock() ;
+    let res = if compress {
+      self.write_vmt_compressed(props, & mut stdout)
+    } else {
+      self.write_vmt(props, & mut stdout)
+    } ;
+    res.expect("could not write counterexample to stdout")
+  }
+  /// Indices of the states shown when some loops are shown once.
+  fn shown(len: usize, loops: & [ (usize, usize, usize) ]) -> Vec<usize> {
+    let mut shown = Vec::with_capacity(len) ;
+    let mut index = 0 ;
+    while index < len {
+      match loops.iter().find( |& & (first, _, _)| first == index ) {
+        Some( & (first, last, period) ) => {
+          shown.extend( first .. first + period ) ;
+          index = last + 1
+        },
+        None => {
+          shown.push(index) ;
+          index += 1
+        },
+      }
     }
-    print!(")\n") ;
+    shown
+  }
 
-    // Printing function symbols.
-    if self.no_state.is_empty() {
-      print!("  () ; no function symbols\n")
+  /// A counterexample as a JSON object. Field `funs` maps the function
+  /// symbols to their value, and `states` lists the states with their
+  /// index and the values of the state variables, which have their external
+  /// names. Values are strings in the SMT-LIB syntax. The
+  /// [loops](#method.loops) are listed in field `loops`, and their states
+  /// are shown once if `compress`.
+  pub fn to_json(& self, compress: bool) -> String {
+    use deps::quote ;
+    let mut funs: Vec<(& str, String)> = self.no_state.iter().map(
+      |(sym, cst)| ( sym.get().sym(), format!("{}", cst) )
+    ).collect() ;
+    funs.sort() ;
+    let mut s = "{\"funs\":{".to_string() ;
+    for (index, (name, val)) in funs.into_iter().enumerate() {
+      if index > 0 { s.push(',') }
+      s.push_str( & format!("{}:{}", quote(name), quote(& val)) )
+    }
+
+    let states = self.states() ;
+    let loops = self.loops() ;
+    let shown = if compress {
+      Self::shown( states.len(), & loops )
     } else {
-      print!("  ( ; function symbols:") ;
-      for (ref sym, ref cst) in self.no_state.iter() {
-        print!(
-          "\n    (declare-fun {} () {} {})", sym, cst.typ(), cst
+      (0 .. states.len()).collect()
+    } ;
+    let args = self.sys.state().args() ;
+    s.push_str("},\"states\":[") ;
+    for (index, off) in shown.into_iter().enumerate() {
+      if index > 0 { s.push(',') }
+      s.push_str( & format!("{{\"state\":{},\"values\":{{", off) ) ;
+      for (index, & (ref sym, _)) in args.iter().enumerate() {
+        if index > 0 { s.push(',') }
+        let val = match states[off].get( sym.get() ) {
+          Some(cst) => quote( & format!("{}", cst) ),
+          None => "null".to_string(),
+        } ;
+        s.push_str( & format!("{}:{}", quote( sym.get().sym() ), val) )
+      }
+      s.push_str("}}")
+    }
+    s.push_str("],\"loops\":[") ;
+    for (index, & (first, last, period)) in loops.iter().enumerate() {
+      if index > 0 { s.push(',') }
+      s.push_str(
+        & format!(
+          "{{\"from\":{},\"to\":{},\"period\":{}}}", first, last, period
         )
+      )
+    }
+    s.push_str("]}") ;
+    s
+  }
+
+  /// A counterexample as a table, one row per state variable and one column
+  /// per state, the variables having their external names. The values of
+  /// the function symbols come first. The [loops](#method.loops) are shown
+  /// once if `compress`.
+  pub fn table(& self, compress: bool) -> Vec<String> {
+    use std::cmp::max ;
+    let mut lines = vec![] ;
+
+    if ! self.no_state.is_empty() {
+      let mut funs: Vec<(& str, String)> = self.no_state.iter().map(
+        |(sym, cst)| ( sym.get().sym(), self.show(cst) )
+      ).collect() ;
+      funs.sort() ;
+      let width = funs.iter().fold( 0, |w, & (name, _)| max(w, name.len()) ) ;
+      lines.push( "function symbols:".to_string() ) ;
+      for (name, val) in funs.into_iter() {
+        lines.push( format!("  {:<2$} = {}", name, val, width) )
       }
-      print!("\n  )\n") ;
     }
 
-    // Printing states.
-    let mut off = Offset::zero() ;
-    while let Some( ref cex ) = self.trace.get(& off) {
-      print!("  ; state {}:\n  (and\n", off) ;
-      for (ref sym, ref cst) in cex.iter() {
-        print!("    (= {} {})\n", sym, cst)
+    // States shown, and the loops shown once.
+    let states = self.states() ;
+    let loops = if compress { self.loops() } else { vec![] } ;
+    let shown = Self::shown( states.len(), & loops ) ;
+
+    // Rows, the first one being the header.
+    let mut rows = vec![
+      (
+        "state".to_string(),
+        shown.iter().map( |off| format!("{}", off) ).collect::<Vec<_>>()
+      )
+    ] ;
+    for & (ref sym, _) in self.sys.state().args() {
+      rows.push(
+        (
+          sym.get().sym().to_string(),
+          shown.iter().map(
+            |off| match states[* off].get( sym.get() ) {
+              Some(cst) => self.show(cst),
+              None => "-".to_string(),
+            }
+          ).collect()
+        )
+      )
+    }
+    let name_width = rows.iter().fold(
+      0, |w, & (ref name, _)| max( w, name.len() )
+    ) ;
+    let widths: Vec<usize> = (0 .. shown.len()).map(
+      |col| rows.iter().fold(
+        0, |w, & (_, ref vals)| max( w, vals[col].len() )
+      )
+    ).collect() ;
+
+    for (row, & (ref name, ref vals)) in rows.iter().enumerate() {
+      let mut line = format!("{:<1$}", name, name_width) ;
+      for (val, width) in vals.iter().zip( widths.iter() ) {
+        line.push_str( & format!(" | {:>1$}", val, width) )
+      }
+      lines.push(line) ;
+      if row == 0 {
+        let mut sep: String = (0 .. name_width).map( |_| '-' ).collect() ;
+        for width in widths.iter() {
+          sep.push_str("-|-") ;
+          sep.extend( (0 .. * width).map( |_| '-' ) )
+        }
+        lines.push(sep)
       }
-      print!("  )\n") ;
-      off = off.nxt()
     }
 
-    print!(")\n")
+    for & (first, last, period) in loops.iter() {
+      lines.push(
+        format!(
+          "states {} to {} loop with period {}, shown once",
+          first, last, period
+        )
+      )
+    }
+    lines
   }
   /// Formats a counterexample human-style.
   pub fn format(& self) -> String {
@@ -279,7 +530,7 @@ impl Cex {
         format!("{}",off).len(), offset_len
       ) ;
       for (ref sym, ref cst) in map.iter() {
-        let len = format!("{}", cst).len() ;
+        let len = self.show(cst).len() ;
         let len = match cst_lens.get(sym) {
           None => unreachable!(),
           Some(l) => max(* l, len),
@@ -301,7 +552,9 @@ impl Cex {
     if ! self.no_state.is_empty() {
       s = format!("declare-funs:") ;
       for (ref sym, ref cst) in self.no_state.iter() {
-        s = format!("{}\n  {2:^1$} = {3}", s, no_state_len, sym, cst)
+        s = format!(
+          "{}\n  {2:^1$} = {3}", s, no_state_len, sym, self.show(cst)
+        )
       } ;
       s = format!("{}\ntrace:\n", s)
     }
@@ -347,7 +600,7 @@ impl Cex {
             s = format!("{} | ", s) ;
             let width = cst_lens.get(sym).unwrap() ;
             let fmt = match map.get(sym) {
-              Some(ref cst) => format!("{}", cst),
+              Some(ref cst) => self.show(cst),
               None => "-".to_string(),
             } ;
             if width > & fmt.len() {
@@ -364,6 +617,54 @@ impl Cex {
     } ;
     s
   }
+
+  /// Candidate assumptions on the inputs of the system ruling this
+  /// counterexample out, in the input language.
+  ///
+  /// Suggests that numeric inputs stay within the bounds the trace goes
+  /// beyond, and that boolean inputs constant along the trace are not. These
+  /// are only hints: an assumption is relevant only if the environment of
+  /// the system does guarantee it.
+  pub fn suggest_assumptions(& self) -> Vec<String> {
+    use term::Signed ;
+    use term::real_term::Cst as RCst ;
+    let graph = ::deps::DepGraph::of(& self.sys) ;
+    let mut res = vec![] ;
+    for input in graph.inputs() {
+      let mut values = vec![] ;
+      let mut off = Offset::zero() ;
+      while let Some(map) = self.trace.get(& off) {
+        if let Some(cst) = map.get(input) { values.push(cst.clone()) }
+        off = off.nxt()
+      }
+      let var = format!("(_ curr {})", input) ;
+      // Hash consed constants are ordered by creation, not by value.
+      let (min, max) = match (
+        values.iter().min_by( |lhs, rhs| lhs.get().cmp( rhs.get() ) ),
+        values.iter().max_by( |lhs, rhs| lhs.get().cmp( rhs.get() ) )
+      ) {
+        (Some(min), Some(max)) => (min.clone(), max.clone()),
+        _ => continue,
+      } ;
+      match ( min.get(), max.get() ) {
+        (& RCst::Bool(lo), & RCst::Bool(hi)) => {
+          if lo == hi && values.len() > 1 {
+            res.push( if lo { format!("(not {})", var) } else { var } )
+          }
+        },
+        (& RCst::Int(ref lo), & RCst::Int(ref hi)) => {
+          if lo.is_negative() { res.push( format!("(> {} {})", var, min) ) }
+          if hi.is_positive() { res.push( format!("(< {} {})", var, max) ) }
+        },
+        (& RCst::Rat(ref lo), & RCst::Rat(ref hi)) => {
+          if lo.is_negative() { res.push( format!("(> {} {})", var, min) ) }
+          if hi.is_positive() { res.push( format!("(< {} {})", var, max) ) }
+        },
+        _ => (),
+      }
+    }
+    res
+  }
 }
 
 /// Maintains the context and can read commands from an `io::Read`.
@@ -394,10 +695,20 @@ pub struct Context {
   callables: HashMap<Sym, ::Callable>,
   /// Map from systems to properties.
   props: HashMap<Sym, (::Prop, PropStatus)>,
+  /// Tags of the properties, given with `:tag` in their definition.
+  tags: HashMap<Sym, Vec<String>>,
   /// Systems.
   syss: HashMap<Sym, ::Sys>,
+  /// Systems in the order they were defined.
+  sys_order: Vec<Sym>,
   /// Maps system identifiers to their invariants.
   invs: HashMap<Sym, STermSet>,
+  /// Text fed but not forming a complete item yet.
+  fed: String,
+  /// Items fed waiting for some symbols to be defined.
+  pending: Vec<stream::Pending>,
+  /// Symbols looked up but not defined since the last reset.
+  misses: RefCell< HashSet<Sym> >,
 }
 impl Context {
   /// Creates an empty context.
@@ -414,10 +725,15 @@ impl Context {
       // states: HashMap::with_capacity(23),
       callables: HashMap::with_capacity(23),
       props: HashMap::with_capacity(53),
+      tags: HashMap::with_capacity(53),
       // inits: HashMap::with_capacity(23),
       // transs: HashMap::with_capacity(23),
       syss: HashMap::with_capacity(23),
+      sys_order: Vec::with_capacity(23),
       invs: HashMap::with_capacity(127),
+      fed: String::new(),
+      pending: vec![],
+      misses: RefCell::new( HashSet::new() ),
     }
   }
 
@@ -430,12 +746,22 @@ impl Context {
   /// identifier.
   #[inline]
   pub fn get_callable(& self, sym: & Sym) -> Option<& ::Callable> {
-    self.callables.get(sym)
+    self.miss( sym, self.callables.get(sym) )
   }
   /// Option of the property corresponding to an identifier.
   #[inline]
   pub fn get_prop(& self, sym: & Sym) -> Option<& (::Prop, PropStatus) > {
-    self.props.get(sym)
+    self.miss( sym, self.props.get(sym) )
+  }
+  /// Remembers a symbol looked up if it is not defined, for the items fed
+  /// to wait for it.
+  #[inline]
+  fn miss<T>(& self, sym: & Sym, found: Option<T>) -> Option<T> {
+    if found.is_none() && ! self.all.contains(sym) {
+      self.misses.borrow_mut().insert( sym.clone() ) ;
+      ()
+    }
+    found
   }
   /// Updates the status of a property to invariant.
   pub fn set_prop_k_true(
@@ -492,6 +818,22 @@ impl Context {
       )
     }
   }
+  /// Downgrades the status of a proved property to unknown, typically
+  /// because its proof was rejected. Does nothing if it is not proved.
+  pub fn set_prop_unknown(& mut self, sym: & Sym) -> Result<(), String> {
+    if let Some( & mut (_, ref mut status) ) = self.props.get_mut(sym) {
+      match * status {
+        PropStatus::Invariant(_) | PropStatus::MinInvariant(_, _) => (),
+        _ => return Ok(()),
+      }
+      * status = PropStatus::Unknown ;
+      Ok(())
+    } else {
+      Err(
+        format!("[Context::set_prop_unknown] unknown property {}", sym)
+      )
+    }
+  }
   /// Updates the status of a property to falsified.
   pub fn set_prop_false(
     & mut self, sym: & Sym, cex: Cex
@@ -524,6 +866,149 @@ impl Context {
     }
   }
 
+  /// Splits a one-state property whose body is a conjunction of at least
+  /// `min` conjuncts in one sub-property per conjunct, named
+  /// `<prop>.<index>`. The status of the property is `Split` until
+  /// [`recombine_prop`](#method.recombine_prop). Returns the
+  /// sub-properties, nothing if the property is not split. Liveness
+  /// properties are never split.
+  pub fn split_prop(
+    & mut self, sym: & Sym, min: usize
+  ) -> Result<Vec<::Prop>, String> {
+    let prop = match self.props.get(sym) {
+      Some( & (ref prop, PropStatus::Unknown) ) if prop.live().is_none() => {
+        prop.clone()
+      },
+      Some(_) => return Ok( vec![] ),
+      None => return Err(
+        format!("[Context::split_prop] unknown property {}", sym)
+      ),
+    } ;
+    let conjs = match * prop.body() {
+      STerm::One(ref curr, _) => match * curr.get() {
+        real_term::Term::Op(Operator::And, ref kids) if kids.len() >= min => {
+          kids.clone()
+        },
+        _ => return Ok( vec![] ),
+      },
+      STerm::Two(_) => return Ok( vec![] ),
+    } ;
+    let mut subs = Vec::with_capacity( conjs.len() ) ;
+    for (index, conj) in conjs.into_iter().enumerate() {
+      let sub_sym = self.factory.sym( format!("{}.{}", sym.sym(), index) ) ;
+      if self.all.contains(& sub_sym) {
+        return Err(
+          format!(
+            "[Context::split_prop] cannot split property {}, \
+            {} is already defined", sym, sub_sym
+          )
+        )
+      }
+      let next = try!(
+        self.factory.bump( conj.clone() ).map_err(
+          |e| format!(
+            "[Context::split_prop] while splitting property {}:\n{}", sym, e
+          )
+        )
+      ) ;
+      subs.push(
+        Prop::mk(
+          Spnd::mk( sub_sym, prop.sym().span.clone() ), prop.sys().clone(),
+          STerm::One(conj, next), prop.calls().clone()
+        )
+      )
+    }
+    let syms: Vec<Sym> = subs.iter().map(
+      |sub| sub.sym().get().clone()
+    ).collect() ;
+    for sub in subs.into_iter() {
+      self.internal_add_prop(sub, PropStatus::Unknown)
+    }
+    if let Some( & mut (_, ref mut status) ) = self.props.get_mut(sym) {
+      * status = PropStatus::Split( syms.clone() )
+    }
+    Ok(
+      syms.iter().map( |sub| self.props[sub].0.clone() ).collect()
+    )
+  }
+
+  /// Recombines the statuses of the sub-properties of a split property into
+  /// the weakest of them. It is falsified if one of them is, with the
+  /// shortest counterexample, proved like the sub-property proved at the
+  /// highest depth if all of them are, lemmas first, and true up to the
+  /// lowest depth they are all true up to otherwise. Returns the number of
+  /// sub-properties, of the ones proved and of the ones falsified, nothing
+  /// if the property is not split.
+  pub fn recombine_prop(
+    & mut self, sym: & Sym
+  ) -> Result< Option<(usize, usize, usize)>, String > {
+    let subs = match self.props.get(sym) {
+      Some( & (_, PropStatus::Split(ref subs)) ) => subs.clone(),
+      Some(_) => return Ok(None),
+      None => return Err(
+        format!("[Context::recombine_prop] unknown property {}", sym)
+      ),
+    } ;
+    let (mut proved, mut falsified) = (0, 0) ;
+    let mut cex: Option<Cex> = None ;
+    // Weakest proof: highest depth, and lemmas if any.
+    let mut inv: Option<(usize, Option<STermSet>)> = None ;
+    // Lowest depth the sub-properties not proved are true up to, `None` if
+    // one of them is not even 0-true.
+    let mut k_true = Some(usize::max_value()) ;
+    for sub in subs.iter() {
+      match self.props.get(sub) {
+        Some( & (_, PropStatus::Falsified(ref sub_cex)) ) => {
+          falsified += 1 ;
+          if cex.as_ref().map_or(true, |cex| sub_cex.len() < cex.len()) {
+            cex = Some( sub_cex.clone() )
+          }
+        },
+        Some( & (_, PropStatus::Invariant(k)) ) => {
+          proved += 1 ;
+          if inv.as_ref().map_or(true, |& (inv_k, _)| k > inv_k) {
+            inv = Some( (k, None) )
+          }
+        },
+        Some( & (_, PropStatus::MinInvariant(k, ref lemmas)) ) => {
+          proved += 1 ;
+          if inv.as_ref().map_or(
+            true, |& (inv_k, ref inv_lemmas)| k > inv_k || (
+              k == inv_k && inv_lemmas.is_none()
+            )
+          ) {
+            inv = Some( (k, Some( lemmas.clone() )) )
+          }
+        },
+        Some( & (_, PropStatus::KTrue(k)) ) => k_true = k_true.map(
+          |lowest| if k < lowest { k } else { lowest }
+        ),
+        Some(_) => k_true = None,
+        None => return Err(
+          format!(
+            "[Context::recombine_prop] unknown sub-property {} of {}",
+            sub, sym
+          )
+        ),
+      }
+    }
+    let status = match (cex, k_true) {
+      ( Some(cex), _ ) => PropStatus::Falsified(cex),
+      ( None, _ ) if proved == subs.len() => match inv {
+        Some( (k, Some(lemmas)) ) => PropStatus::MinInvariant(k, lemmas),
+        Some( (k, None) ) => PropStatus::Invariant(k),
+        // No sub-property.
+        None => PropStatus::Invariant(0),
+      },
+      ( None, Some(k) ) => PropStatus::KTrue(k),
+      ( None, None ) => PropStatus::Unknown,
+    } ;
+    if let Some( & mut (_, ref mut status_ref) ) = self.props.get_mut(sym) {
+      * status_ref = status
+    }
+    Ok( Some( (subs.len(), proved, falsified) ) )
+  }
+
   /// Returns true iff some properties are neither proved or disproved.
   #[inline]
   pub fn some_prop_unknown(& self, props: & [::Prop]) -> Result<bool, String> {
@@ -580,10 +1065,105 @@ impl Context {
     Ok(false)
   }
 
+  /// Returns true iff some properties are proved.
+  #[inline]
+  pub fn some_prop_proved(
+    & self, props: & [::Prop]
+  ) -> Result<bool, String> {
+    for prop in props {
+      match self.props.get( prop.sym() ) {
+        Some( & (_, PropStatus::Invariant(_)) ) => return Ok(true),
+        Some( _ ) => (),
+        None => return Err(
+          format!("[Context::some_prop_proved] unknown property {}", prop)
+        ),
+      }
+    }
+    Ok(false)
+  }
+
   /// Option of the system corresponding to an identifier.
   #[inline]
   pub fn get_sys(& self, sym: & Sym) -> Option<& ::Sys> {
-    self.syss.get(sym)
+    self.miss( sym, self.syss.get(sym) )
+  }
+
+  /// Systems in the order they were defined.
+  pub fn systems(& self) -> Vec<& ::Sys> {
+    self.sys_order.iter().map( |sym| & self.syss[sym] ).collect()
+  }
+
+  /// Function declarations and definitions, sorted by name.
+  pub fn callables(& self) -> Vec<::Callable> {
+    let mut callables: Vec<::Callable> = self.callables.values().cloned(
+    ).collect() ;
+    callables.sort_by(
+      |lhs, rhs| lhs.sym().sym().cmp( rhs.sym().sym() )
+    ) ;
+    callables
+  }
+
+  /// True if the context defines no system and no property, as the one of
+  /// a library.
+  pub fn defines_only_callables(& self) -> bool {
+    self.syss.is_empty() && self.props.is_empty()
+  }
+
+  /// Adds the callables of a library. Fails if one of their symbols is
+  /// defined already, unless by the same callable when the library was
+  /// added before.
+  pub fn add_library(
+    & mut self, lib: & ::library::Library
+  ) -> Result<(), String> {
+    let mut added = Vec::with_capacity( lib.callables().len() ) ;
+    for fun in lib.callables().iter() {
+      let sym = fun.sym() ;
+      if let Some(known) = self.callables.get(sym) {
+        if & * * known as * const Callable == & * * fun as * const Callable {
+          continue
+        }
+      }
+      if self.all.contains(sym) {
+        return Err(
+          format!(
+            "symbol `{}` of library \"{}\" is already defined",
+            sym, lib.path()
+          )
+        )
+      }
+      added.push( fun.clone() )
+    }
+    for fun in added.into_iter() {
+      let sym = fun.sym().clone() ;
+      let _ = self.all.insert( sym.clone() ) ;
+      let _ = self.callables.insert(sym, fun) ;
+    }
+    Ok(())
+  }
+
+  /// Properties and relations of a system, sorted by name.
+  pub fn props_of(& self, sys: & Sym) -> Vec<::Prop> {
+    let mut props: Vec<::Prop> = self.props.values().filter_map(
+      |& (ref prop, _)| if prop.sys().sym().get() == sys {
+        Some( prop.clone() )
+      } else { None }
+    ).collect() ;
+    props.sort_by(
+      |lhs, rhs| lhs.sym().get().sym().cmp( rhs.sym().get().sym() )
+    ) ;
+    props
+  }
+
+  /// Tags of a property.
+  #[inline]
+  pub fn prop_tags(& self, sym: & Sym) -> & [String] {
+    self.tags.get(sym).map( |tags| & tags[..] ).unwrap_or(& [])
+  }
+
+  /// Invariants of a system, if any.
+  #[inline]
+  pub fn get_invs(& self, sym: & Sym) -> Option<& STermSet> {
+    self.invs.get(sym)
   }
 
   /// Add invariants for a system.
@@ -612,6 +1192,15 @@ impl Context {
     }
   }
 
+  /// Removes some invariants of a system, typically falsified ones.
+  pub fn retract_invs(& mut self, sym: & Sym, invs: & STermSet) {
+    if let Some(set) = self.invs.get_mut(sym) {
+      for inv in invs.iter() {
+        set.remove(inv) ; ()
+      }
+    }
+  }
+
   /// Prints the state of the context to stdin. Used for debugging. See also
   /// [the `lines` function][lines fun].
   ///
@@ -823,7 +1412,9 @@ impl Context {
       }
     }
 
-    Cex { sys: sys.clone(), no_state: no_state, trace: trace }
+    Cex {
+      sys: sys.clone(), no_state: no_state, trace: trace, precision: None
+    }
   }
 
 
@@ -870,7 +1461,7 @@ impl Context {
       ),
     }
     match self.syss.insert(sym.get().clone(), Arc::new(sys)) {
-      None => (),
+      None => self.sys_order.push( sym.get().clone() ),
       Some(e) => {
         self.stdin_print() ;
         println!("added {} which already exists in syss", e) ;
@@ -904,22 +1495,93 @@ impl Context {
 
   /// Adds a state property definition to the context.
   pub fn add_prop(
+    & mut self, sym: Spnd<Sym>, sys: Spnd<Sym>, body: TermAndDep,
+    tags: Vec<Spnd<Sym>>
+  ) -> Result<(), InternalParseError> {
+    let prop = match try!( patterns::elaborate(self, & sym, & sys, body) ) {
+      patterns::Elab::One(body) => try!(
+        check::check_prop(self, sym, sys, body)
+      ),
+      patterns::Elab::Two(body) => try!(
+        check::check_rel(self, sym, sys, body)
+      ),
+      patterns::Elab::Live(live, body) => {
+        let prop = try!( check::check_prop(self, sym, sys, body) ) ;
+        Prop::mk_live(
+          prop.sym().clone(), prop.sys().clone(), prop.body().clone(),
+          prop.calls().clone(), live
+        )
+      },
+    } ;
+    if ! tags.is_empty() {
+      let tags = tags.into_iter().map(
+        |tag| tag.get().sym().to_string()
+      ).collect() ;
+      self.tags.insert( prop.sym().get().clone(), tags ) ;
+    }
+    self.internal_add_prop(prop, PropStatus::Unknown) ;
+    Ok(())
+  }
+
+  /// Adds an LTL property definition to the context. The system is extended
+  /// with the tableau of the formula, see the [`ltl`](../ltl/index.html)
+  /// module.
+  pub fn add_ltl(
     & mut self, sym: Spnd<Sym>, sys: Spnd<Sym>, body: TermAndDep
   ) -> Result<(), InternalParseError> {
-    check::check_prop(self, sym, sys, body).map(
-      |prop| self.internal_add_prop(prop, PropStatus::Unknown)
-    )
+    let span = body.span.clone() ;
+    let (sys, ltl, calls) = try!(
+      check::check_ltl(self, sym.clone(), sys, body)
+    ) ;
+    let compiled = ::ltl::compile(& sys, & sym, & ltl, & self.factory) ;
+    let (sys, live) = match compiled {
+      Ok(res) => res,
+      Err(blah) => return Err( InternalParseError::mk(span, blah, vec![]) ),
+    } ;
+    let _ = self.syss.insert( sys.sym().get().clone(), sys.clone() ) ;
+    // Unwrap cannot fail, the body only mentions the current state.
+    let live_next = self.factory.bump( live.clone() ).unwrap() ;
+    let prop = Prop::mk_live(
+      sym, sys, STerm::One(live, live_next), calls, Live::Eventually
+    ) ;
+    self.internal_add_prop(prop, PropStatus::Unknown) ;
+    Ok(())
   }
 
   /// Adds a state relation definition to the context.
   pub fn add_rel(
     & mut self, sym: Spnd<Sym>, sys: Spnd<Sym>, body: TermAndDep
   ) -> Result<(), InternalParseError> {
+    let body = match try!( patterns::elaborate(self, & sym, & sys, body) ) {
+      patterns::Elab::One(body) => body,
+      patterns::Elab::Two(body) => body,
+      patterns::Elab::Live(live, body) => return Err(
+        InternalParseError::mk(
+          body.span, format!(
+            "liveness pattern `{}` is only legal in `define-prop`", live
+          ), vec![]
+        )
+      ),
+    } ;
     check::check_rel(self, sym, sys, body).map(
       |rel| self.internal_add_prop(rel, PropStatus::Unknown)
     )
   }
 
+  /// Adds a fairness constraint to a system of the context. Properties
+  /// already defined over the system are not affected.
+  pub fn add_fair(
+    & mut self, sys: Spnd<Sym>, body: TermAndDep
+  ) -> Result<(), InternalParseError> {
+    check::check_fair(self, sys, body).map(
+      |sys| {
+        let sym = sys.sym().get().clone() ;
+        let _ = self.syss.insert( sym, Arc::new(sys) ) ;
+        ()
+      }
+    )
+  }
+
   /// Adds a system definition to the context.
   pub fn add_sys(
     & mut self, sym: Spnd<Sym>, state: Args,
@@ -114,7 +114,7 @@ fn line_extractor(
   // }
   let n = spn.bgn ;
   let mut bgn = 1 ;
-  let mut end = 1 ;
+  let mut end = 0 ;
   let mut gotit = false ;
   let mut cpt = 0 ;
   let mut offset = 1 ;
@@ -146,10 +146,11 @@ fn line_extractor(
       _ => (),
     }
   }
-  if end < bgn { end = cpt }
+  // Last line, not ending with a newline.
+  if end < bgn { end = cpt + 1 }
   debug_assert!(bgn > 0) ;
   debug_assert!(end > 0) ;
-  debug_assert!(end < txt.len()) ;
+  debug_assert!(end <= txt.len() + 1) ;
   let line = if end >= bgn {
     (
       & txt[ (bgn - 1) .. (end - 1) ]
@@ -481,10 +482,22 @@ fn prop_parser<'a>(
     body: parse_or_fail!(
       len_add!( len < trm (offset + len, c) )
       ! at sym.span.clone(), "parse error in body of `define-prop`"
+    ) >>
+    len_add!(len < opt spc cmt) >>
+    tags: many0!(
+      do_parse!(
+        len_add!(len < tag ":tag") >>
+        len_add!(len < opt spc cmt) >>
+        tag: parse_or_fail!(
+          len_add!( len < sym (offset + len, c) )
+          ! at (offset + len), "for tag in `define-prop`"
+        ) >>
+        len_add!(len < opt spc cmt) >> (tag)
+      )
     ) >> ({
       let sym_span = sym.span.clone() ;
       try_parserr!(
-        _ = c.add_prop(sym, sys, body) => Spnd::len_mk(
+        _ = c.add_prop(sym, sys, body, tags) => Spnd::len_mk(
           Res::Success, offset, len
         ), (sym_span, "in this `define-prop`".into())
       )
@@ -492,6 +505,63 @@ fn prop_parser<'a>(
   )
 }
 
+/// Parses an LTL property definition.
+fn ltl_parser<'a>(
+  bytes: & 'a [u8], offset: usize, c: & mut Context
+) -> IRes<'a, Spnd<Res>> {
+  let mut len = 0 ;
+  do_parse!(
+    bytes,
+    sym: parse_or_fail!(
+      len_add!( len < sym (offset + len, c) )
+      ! at (offset + len), "in `define-ltl`"
+    ) >>
+    len_add!(len < opt spc cmt) >>
+    sys: parse_or_fail!(
+      len_add!( len < sym (offset + len, c) )
+      ! at (offset + len), "for system name in `define-ltl`"
+    ) >>
+    len_add!(len < opt spc cmt) >>
+    body: parse_or_fail!(
+      len_add!( len < trm (offset + len, c) )
+      ! at sym.span.clone(), "parse error in formula of `define-ltl`"
+    ) >> ({
+      let sym_span = sym.span.clone() ;
+      try_parserr!(
+        _ = c.add_ltl(sym, sys, body) => Spnd::len_mk(
+          Res::Success, offset, len
+        ), (sym_span, "in this `define-ltl`".into())
+      )
+    })
+  )
+}
+
+/// Parses a fairness constraint.
+fn fair_parser<'a>(
+  bytes: & 'a [u8], offset: usize, c: & mut Context
+) -> IRes<'a, Spnd<Res>> {
+  let mut len = 0 ;
+  do_parse!(
+    bytes,
+    sys: parse_or_fail!(
+      len_add!( len < sym (offset + len, c) )
+      ! at (offset + len), "for system name in `define-fair`"
+    ) >>
+    len_add!(len < opt spc cmt) >>
+    body: parse_or_fail!(
+      len_add!( len < trm (offset + len, c) )
+      ! at sys.span.clone(), "parse error in body of `define-fair`"
+    ) >> ({
+      let sys_span = sys.span.clone() ;
+      try_parserr!(
+        _ = c.add_fair(sys, body) => Spnd::len_mk(
+          Res::Success, offset, len
+        ), (sys_span, "in this `define-fair`".into())
+      )
+    })
+  )
+}
+
 /// Parses a state relation definition.
 fn rel_parser<'a>(
   bytes: & 'a [u8], offset: usize, c: & mut Context
@@ -869,6 +939,16 @@ pub fn item_parser<'a>(
               len_add!(len < opt spc cmt)
             ) >> apply!(rel_parser, offset + len, ctx) |
 
+            terminated!(
+              len_add!(len < tag "define-ltl"),
+              len_add!(len < opt spc cmt)
+            ) >> apply!(ltl_parser, offset + len, ctx) |
+
+            terminated!(
+              len_add!(len < tag "define-fair"),
+              len_add!(len < opt spc cmt)
+            ) >> apply!(fair_parser, offset + len, ctx) |
+
             terminated!(
               len_add!(len < tag "define-sys"),
               len_add!(len < opt spc cmt)
@@ -1330,6 +1410,22 @@ mod test {
       Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 41) ),
     }
 
+    let txt = "\
+(define-prop tagged prout (= (_ curr x) 0) :tag safety :tag regression)\
+    " ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 71) ),
+    }
+    let tagged = ctx.factory().sym("tagged") ;
+    assert_eq!(
+      ctx.prop_tags(& tagged),
+      & [ "safety".to_string(), "regression".to_string() ]
+    ) ;
+
     let txt = "(define-prop blah prout)" ;
     match try_parse_command!(item_parser, 7, ctx, txt) {
       Err(e) => {
@@ -1345,6 +1441,118 @@ mod test {
     }
   }
 
+  #[test]
+  fn patterns() {
+    use super::item_parser ;
+
+    let mut ctx = get_context() ;
+
+    let txt = "\
+(define-sys prout
+  ;; State.
+  ( (x Int) )
+  ;; Init.
+  (>= (_curr x) 0)
+  ;; Trans.
+  (> (_ next x) (_ curr x))
+  ;; No calls.
+  ()
+)\
+    " ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 135) ),
+    }
+
+    let txt = "(define-prop pos prout (never (< (_ curr x) 0)))" ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 48) ),
+    }
+
+    let txt = "\
+(define-prop up prout (stays_until (> (_ curr x) 3) (> (_ curr x) 7)))\
+    " ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 70) ),
+    }
+
+    let txt = "\
+(define-prop soon prout (implies_within 2 (> (_ curr x) 3) (> (_ curr x) 5)))\
+    " ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 77) ),
+    }
+    let (prout, obs) = (
+      ctx.factory().sym("prout"), ctx.factory().sym("soon@within")
+    ) ;
+    assert!( ctx.get_sys(& prout).unwrap().state().contains(& obs) ) ;
+
+    let txt = "(define-prop bad prout (implies_within (_ curr x) true true))" ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        assert!(
+          e.blah.starts_with(
+            "first argument of `implies_within` should be a natural constant"
+          )
+        )
+      },
+      Ok(res) => panic!("unexpected result: {:?}", res),
+    }
+
+    let txt = "(define-fair prout (> (_ curr x) 10))" ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 37) ),
+    }
+    assert_eq!( ctx.get_sys(& prout).unwrap().fair().len(), 1 ) ;
+
+    let txt = "(define-prop live prout (G (F (> (_ curr x) 5))))" ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 49) ),
+    }
+    let live = ctx.factory().sym("live") ;
+    assert_eq!(
+      ctx.get_prop(& live).unwrap().0.live(),
+      Some( ::base::Live::InfinitelyOften )
+    ) ;
+
+    let txt = "\
+(define-ltl resp prout (G (=> (> (_ curr x) 3) (F (> (_ curr x) 7)))))\
+    " ;
+    match try_parse_command!(item_parser, 7, ctx, txt) {
+      Err(e) => {
+        e.print() ;
+        panic!("unexpected result")
+      },
+      Ok(res) => assert_eq!( res.1.to_span(), Spn::len_mk(7, 70) ),
+    }
+    let start = ctx.factory().sym("resp@ltl.start") ;
+    assert!( ctx.get_sys(& prout).unwrap().state().contains(& start) ) ;
+  }
+
   #[test]
   fn rel_parser() {
     use super::item_parser ;
@@ -1394,4 +1602,47 @@ mod test {
       Ok(res) => panic!("unexpected result: {:?}", res),
     }
   }
+  #[test]
+  fn feed() {
+    let mut ctx = get_context() ;
+    let counter = ctx.factory().sym("counter") ;
+
+    // Property before its system, system split across two calls.
+    let txt = "\
+(define-prop pos counter (>= (_ curr x) 0))
+(define-sys counter
+  ( (x Int) )
+" ;
+    assert!( ctx.feed(txt).unwrap().is_empty() ) ;
+    assert!( ctx.waiting_for().contains(& counter) ) ;
+    let txt = "\
+  (= (_ curr x) 0)
+  (= (_ next x) (+ (_ curr x) 1))
+  ()
+)
+(verify counter (pos))\
+" ;
+    let res = ctx.feed(txt).unwrap() ;
+    assert!( ctx.get_sys(& counter).is_some() ) ;
+    assert!( ctx.waiting_for().is_empty() ) ;
+    assert_eq!( res.len(), 1 ) ;
+    match res[0] {
+      Res::Check(ref sys, ref props) => {
+        assert_eq!( sys.sym().get(), & counter ) ;
+        assert_eq!( props.len(), 1 )
+      },
+      ref res => panic!("unexpected result: {:?}", res),
+    }
+    ctx.end_feed().unwrap() ;
+
+    // Errors unrelated to dependencies fail immediately.
+    let txt = "(define-prop bad counter (_ curr y))" ;
+    assert!( ctx.feed(txt).is_err() ) ;
+
+    // Waiting for a function never defined.
+    let txt = "(define-prop odd counter (is_odd (_ curr x)))" ;
+    assert!( ctx.feed(txt).unwrap().is_empty() ) ;
+    assert!( ctx.end_feed().is_err() ) ;
+    assert!( ctx.waiting_for().is_empty() ) ;
+  }
 }
\ No newline at end of file
@@ -0,0 +1,325 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Syntactic sugar for common property patterns.
+//!
+//! The body of a `define-prop` or a `define-rel` can be a pattern,
+//! elaborated into core terms when the property is added:
+//!
+//! - `(always p)` is `p`,
+//! - `(never p)` is `(not p)`,
+//! - `(stays_until p q)`, once `p` holds it keeps holding until `q` does, is
+//!   the relation `(=> (and p (not q)) (or (_ next p) (_ next q)))`,
+//! - `(implies_within k p q)`, whenever `p` holds `q` holds in the same
+//!   state or in one of the `k` next ones: `(implies_within 0 p q)` is
+//!   `(=> p q)`,
+//! - `(F p)`, `p` eventually holds, and `(G (F p))`, `p` holds infinitely
+//!   often, are liveness properties on the paths of the system where its
+//!   fairness constraints, `(define-fair <sys> <term>)`, hold infinitely
+//!   often. Only `define-prop` accepts them.
+//!
+//! The arguments are one-state terms. Patterns are only recognized at the
+//! top level of the body, and not if a function with the same name exists.
+//!
+//! `implies_within` needs an *observer*: the integer state variable
+//! `<prop>@within` is added to the system, and counts the transitions since
+//! the oldest state where `p` holds and `q` has not held since, `-1` if
+//! there is none. It is `k` when `q` did not hold in the `k + 1` states
+//! since then, so the property is `(< <prop>@within k)`. A system already
+//! used as a sub-system cannot be extended this way.
+
+use std::sync::Arc ;
+use std::collections::HashMap ;
+
+use term::{
+  Sym, Var, Term, Type, Int, State, Zero, One,
+  OpMaker, CstMaker, VarMaker, UnTermOps, SymMaker
+} ;
+use term::real_term::{ Term as RTerm, Var as RVar, Cst as RCst } ;
+use term::parsing::{ Spn, Spnd, TermAndDep } ;
+
+use base::Live ;
+
+use super::{ Context, InternalParseError } ;
+
+/// Body of a property after elaboration.
+pub enum Elab {
+  /// One-state body.
+  One(TermAndDep),
+  /// Two-state body.
+  Two(TermAndDep),
+  /// Liveness property, the body is the one-state argument of the temporal
+  /// operator.
+  Live(Live, TermAndDep),
+}
+
+/// Elaborates the body of a property if it is a pattern.
+pub fn elaborate(
+  ctxt: & mut Context, prop: & Spnd<Sym>, sys: & Spnd<Sym>, body: TermAndDep
+) -> Result<Elab, InternalParseError> {
+  use term::Operator::* ;
+  let (name, kids) = match * body.term.get() {
+    RTerm::App(ref sym, ref kids) => ( sym.clone(), kids.clone() ),
+    _ => return Ok( Elab::One(body) ),
+  } ;
+  if ctxt.get_callable(& name).is_some() { return Ok( Elab::One(body) ) }
+  let arity = match name.get().sym() {
+    "always" | "never" | "F" | "G" => 1,
+    "stays_until" => 2,
+    "implies_within" => 3,
+    _ => return Ok( Elab::One(body) ),
+  } ;
+  if kids.len() != arity {
+    return Err(
+      InternalParseError::mk(
+        body.span, format!(
+          "pattern `{}` expects {} argument(s), got {}",
+          name, arity, kids.len()
+        ), vec![]
+      )
+    )
+  }
+
+  let factory = ctxt.factory().clone() ;
+  let mut body = body ;
+  let _ = body.apps.remove(& name) ;
+
+  match name.get().sym() {
+
+    "always" => {
+      body.term = kids[0].clone() ;
+      Ok( Elab::One(body) )
+    },
+
+    "never" => {
+      body.term = factory.not( kids[0].clone() ) ;
+      Ok( Elab::One(body) )
+    },
+
+    "F" => {
+      body.term = kids[0].clone() ;
+      Ok( Elab::Live(Live::Eventually, body) )
+    },
+
+    "G" => {
+      let arg = match * kids[0].get() {
+        RTerm::App(ref sym, ref args) if sym.get().sym() == "F" && (
+          args.len() == 1 && ctxt.get_callable(sym).is_none()
+        ) => {
+          let _ = body.apps.remove(sym) ;
+          args[0].clone()
+        },
+        _ => return Err(
+          InternalParseError::mk(
+            body.span, format!(
+              "argument of pattern `G` should be `(F p)`, got `{}`", kids[0]
+            ), vec![]
+          )
+        ),
+      } ;
+      body.term = arg ;
+      Ok( Elab::Live(Live::InfinitelyOften, body) )
+    },
+
+    "stays_until" => {
+      let (p, q) = ( kids[0].clone(), kids[1].clone() ) ;
+      let p_next = try!( bump(ctxt, & p, & body.span) ) ;
+      let q_next = try!( bump(ctxt, & q, & body.span) ) ;
+      body.term = factory.op(
+        Impl, vec![
+          factory.op( And, vec![ p, factory.not(q) ] ),
+          factory.op( Or, vec![ p_next, q_next ] ),
+        ]
+      ) ;
+      add_next(ctxt, & mut body.vars) ;
+      Ok( Elab::Two(body) )
+    },
+
+    _ => {
+      let k = match * kids[0].get() {
+        RTerm::C(ref cst) => match * cst.get() {
+          RCst::Int(ref k) if * k >= Int::zero() => Some( k.clone() ),
+          _ => None,
+        },
+        _ => None,
+      } ;
+      let k = match k {
+        Some(k) => k,
+        None => return Err(
+          InternalParseError::mk(
+            body.span, format!(
+              "first argument of `implies_within` should be a natural \
+              constant, got `{}`", kids[0]
+            ), vec![]
+          )
+        ),
+      } ;
+      let (p, q) = ( kids[1].clone(), kids[2].clone() ) ;
+      let p_next = try!( bump(ctxt, & p, & body.span) ) ;
+      let q_next = try!( bump(ctxt, & q, & body.span) ) ;
+
+      let obs_sym = factory.sym( format!("{}@within", prop.get().sym()) ) ;
+      let obs: Term = factory.svar( obs_sym.clone(), State::Curr ) ;
+      let obs_next: Term = factory.svar( obs_sym.clone(), State::Next ) ;
+      let none: Term = factory.cst( - Int::one() ) ;
+      let zero: Term = factory.cst( Int::zero() ) ;
+      let one: Term = factory.cst( Int::one() ) ;
+
+      let init = factory.op(
+        Eq, vec![
+          obs.clone(), factory.op(
+            Ite, vec![
+              factory.op( And, vec![ p, factory.not(q) ] ),
+              zero.clone(), none.clone(),
+            ]
+          )
+        ]
+      ) ;
+      let trans = factory.op(
+        Eq, vec![
+          obs_next, factory.op(
+            Ite, vec![
+              q_next, none.clone(), factory.op(
+                Ite, vec![
+                  factory.op( Ge, vec![ obs.clone(), zero.clone() ] ),
+                  factory.op( Add, vec![ obs.clone(), one ] ),
+                  factory.op( Ite, vec![ p_next, zero, none ] ),
+                ]
+              )
+            ]
+          )
+        ]
+      ) ;
+      try!( observe(ctxt, sys, & obs_sym, init, trans, & body) ) ;
+
+      body.term = factory.op( Lt, vec![ obs, factory.cst(k) ] ) ;
+      let obs: Var = factory.svar( obs_sym, State::Curr ) ;
+      let span = body.span.clone() ;
+      body.vars.insert( obs, vec![ span ] ) ;
+      Ok( Elab::One(body) )
+    },
+  }
+}
+
+/// Next state version of a one-state argument of a pattern.
+fn bump(
+  ctxt: & Context, term: & Term, span: & Spn
+) -> Result<Term, InternalParseError> {
+  ctxt.factory().bump( term.clone() ).map_err(
+    |_| InternalParseError::mk(
+      span.clone(), format!(
+        "argument `{}` of pattern mentions next state variables", term
+      ), vec![]
+    )
+  )
+}
+
+/// Adds the next state version of the current state variables of a term.
+fn add_next(ctxt: & Context, vars: & mut HashMap<Var, Vec<Spn>>) {
+  let mut nexts = Vec::with_capacity( vars.len() ) ;
+  for (var, spns) in vars.iter() {
+    if let RVar::SVar(ref sym, State::Curr) = * var.get() {
+      let next: Var = ctxt.factory().svar( sym.clone(), State::Next ) ;
+      nexts.push( (next, spns.clone()) )
+    }
+  }
+  vars.extend(nexts)
+}
+
+/// Adds an observer state variable to a system, constrained by an init and
+/// a trans term. The arguments of the pattern, in `body`, must only mention
+/// the state of the system and existing functions.
+fn observe(
+  ctxt: & mut Context, sys_sym: & Spnd<Sym>, obs_sym: & Sym,
+  init: Term, trans: Term, body: & TermAndDep
+) -> Result<(), InternalParseError> {
+  let span = body.span.clone() ;
+  let sys = match ctxt.get_sys( sys_sym.get() ) {
+    Some(sys) => sys.clone(),
+    None => return Err(
+      InternalParseError::mk(
+        sys_sym.span.clone(), "unknown system".into(), vec![]
+      )
+    ),
+  } ;
+  let used = ctxt.syss.values().any(
+    |other| other.subsys().iter().any(
+      |& (ref sub, _)| sub.sym().get() == sys.sym().get()
+    )
+  ) ;
+  if used {
+    return Err(
+      InternalParseError::mk(
+        span, format!(
+          "cannot add the observer of `implies_within` to system `{}`, \
+          it is used as a sub-system", sys.sym()
+        ), vec![]
+      )
+    )
+  }
+  if sys.state().contains(obs_sym) {
+    return Err(
+      InternalParseError::mk(
+        span, format!(
+          "system `{}` already has a state variable `{}`", sys.sym(), obs_sym
+        ), vec![]
+      )
+    )
+  }
+
+  let mut calls = vec![] ;
+  for (var, spns) in body.vars.iter() {
+    match * var.get() {
+      RVar::SVar(ref sym, _) => if ! sys.state().contains(sym) {
+        return Err(
+          InternalParseError::vec_mk(
+            spns, format!("unknown state variable `{}` in pattern", sym),
+            "also used here"
+          )
+        )
+      },
+      RVar::Var(ref sym) => match ctxt.get_callable(sym) {
+        Some(fun) => calls.push( fun.clone() ),
+        None => return Err(
+          InternalParseError::vec_mk(
+            spns, format!("unknown constant function symbol `{}`", sym),
+            "also used here"
+          )
+        ),
+      },
+    }
+  }
+  for (sym, spns) in body.apps.iter() {
+    match ctxt.get_callable(sym) {
+      Some(fun) => calls.push( fun.clone() ),
+      None => return Err(
+        InternalParseError::vec_mk(
+          spns, format!("application of unknown function symbol `{}`", sym),
+          "also used here"
+        )
+      ),
+    }
+  }
+
+  let factory = ctxt.factory().clone() ;
+  let extra = vec![
+    (
+      Spnd::mk( obs_sym.clone(), span.clone() ),
+      Spnd::mk( Type::Int, span.clone() )
+    )
+  ] ;
+  let extended = match sys.with_extra_state(
+    extra, vec![ init ], vec![ trans ], vec![], & calls, & factory
+  ) {
+    Ok(extended) => extended,
+    Err(e) => return Err( InternalParseError::mk(span, e, vec![]) ),
+  } ;
+  ctxt.syss.insert( sys.sym().get().clone(), Arc::new(extended) ) ;
+  Ok(())
+}
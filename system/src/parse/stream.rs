@@ -0,0 +1,201 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Incremental elaboration of items.
+//!
+//! [`Context::feed`](../struct.Context.html#method.feed) takes some text,
+//! splits it into items and elaborates them in order. The text of an item
+//! can span several calls: the end of the text that does not form a
+//! complete item yet is kept for the next call.
+//!
+//! An item mentioning a function symbol, a system or a property that is not
+//! defined yet *waits* for it instead of failing. It is elaborated again as
+//! soon as one of the symbols it waits for is defined, so that definitions
+//! can come in any order. Items failing for any other reason fail
+//! immediately.
+//!
+//! [`Context::end_feed`](../struct.Context.html#method.end_feed) forgets the
+//! items still waiting, and fails if there are some.
+
+use std::collections::{ HashSet, VecDeque } ;
+
+use term::Sym ;
+
+use Error as ExtError ;
+use Line ;
+
+use super::{ Context, Res } ;
+use super::parsers::item_parser ;
+
+/// An item waiting for some symbols to be defined.
+pub struct Pending {
+  /// Text of the item.
+  txt: String,
+  /// Line of the item in the text fed.
+  line: usize,
+  /// Symbols the item waits for.
+  waiting: HashSet<Sym>,
+  /// Error elaborating the item raised.
+  error: ExtError,
+}
+
+/// Outcome of the elaboration of an item.
+enum Elab {
+  /// Elaborated.
+  Done(Res),
+  /// Waiting for some symbols, with the error elaborating the item raised.
+  Wait(HashSet<Sym>, ExtError),
+}
+
+/// Error at the beginning of an item.
+fn error_at(txt: & str, line: usize, blah: & str) -> ExtError {
+  let first = txt.lines().next().unwrap_or("").to_string() ;
+  ExtError::parse_mk(
+    Line::mk(first, "^".to_string(), line, 1), blah.to_string(), vec![]
+  )
+}
+
+impl Context {
+  /// Feeds some text and elaborates the items it completes, waiting for
+  /// their dependencies if needed.
+  ///
+  /// Returns the results of the queries (`verify`, `verify-assuming`,
+  /// `exit`) elaborated, in the order they were elaborated.
+  pub fn feed(& mut self, txt: & str) -> Result<Vec<Res>, ExtError> {
+    let mut text = ::std::mem::replace(& mut self.fed, String::new()) ;
+    // Line of the beginning of `text`.
+    let mut line = self.line + 1 - text.matches('\n').count() ;
+    self.line += txt.matches('\n').count() ;
+    text.push_str(txt) ;
+
+    let mut items = vec![] ;
+    let (mut depth, mut comment) = (0usize, false) ;
+    // Beginning of the current item and its line.
+    let mut start: Option<(usize, usize)> = None ;
+    for (index, c) in text.char_indices() {
+      if c == '\n' { line += 1 }
+      if comment {
+        if c == '\n' { comment = false }
+        continue
+      }
+      match c {
+        ';' => comment = true,
+        '(' => {
+          if start.is_none() { start = Some( (index, line) ) }
+          depth += 1
+        },
+        ')' if depth > 0 => {
+          depth -= 1 ;
+          if depth == 0 {
+            if let Some( (bgn, bgn_line) ) = start {
+              items.push( ( text[ bgn .. index + 1 ].to_string(), bgn_line ) )
+            }
+            start = None
+          }
+        },
+        // Garbage between items, fails when the next item is elaborated.
+        c if ! c.is_whitespace() => if start.is_none() {
+          start = Some( (index, line) )
+        },
+        _ => (),
+      }
+    }
+    if let Some( (bgn, _) ) = start {
+      self.fed.push_str(& text[bgn ..])
+    }
+
+    let mut results = vec![] ;
+    for (txt, line) in items {
+      try!( self.elaborate(txt, line, & mut results) )
+    }
+    Ok(results)
+  }
+
+  /// Symbols the items fed are waiting for.
+  pub fn waiting_for(& self) -> HashSet<Sym> {
+    let mut res = HashSet::new() ;
+    for pending in self.pending.iter() {
+      res.extend( pending.waiting.iter().cloned() )
+    }
+    res
+  }
+
+  /// Stops feeding: forgets the items still waiting and the incomplete item
+  /// at the end of the text fed. Fails with the error of the first of these
+  /// items, if any.
+  pub fn end_feed(& mut self) -> Result<(), ExtError> {
+    let fed = ::std::mem::replace(& mut self.fed, String::new()) ;
+    let pending = ::std::mem::replace(& mut self.pending, vec![]) ;
+    if let Some(pending) = pending.into_iter().next() {
+      Err(pending.error)
+    } else if ! fed.is_empty() {
+      let line = self.line + 1 - fed.matches('\n').count() ;
+      Err( error_at(& fed, line, "incomplete item at the end of the text fed") )
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Elaborates an item, and then the items waiting for the symbols it
+  /// defines.
+  fn elaborate(
+    & mut self, txt: String, line: usize, results: & mut Vec<Res>
+  ) -> Result<(), ExtError> {
+    let mut todo = VecDeque::new() ;
+    todo.push_back( (txt, line) ) ;
+    while let Some( (txt, line) ) = todo.pop_front() {
+      match try!( self.elaborate_one(& txt, line) ) {
+        Elab::Wait(waiting, error) => self.pending.push(
+          Pending { txt: txt, line: line, waiting: waiting, error: error }
+        ),
+        Elab::Done(res) => {
+          match res {
+            Res::Success => (),
+            res => results.push(res),
+          }
+          let mut index = 0 ;
+          while index < self.pending.len() {
+            let ready = self.pending[index].waiting.iter().any(
+              |sym| self.all.contains(sym)
+            ) ;
+            if ready {
+              let pending = self.pending.remove(index) ;
+              todo.push_back( (pending.txt, pending.line) )
+            } else {
+              index += 1
+            }
+          }
+        },
+      }
+    }
+    Ok(())
+  }
+
+  /// Elaborates an item, waits if it mentions symbols not defined yet.
+  fn elaborate_one(
+    & mut self, txt: & str, line: usize
+  ) -> Result<Elab, ExtError> {
+    use nom::IResult::* ;
+    self.misses.borrow_mut().clear() ;
+    match item_parser(txt.as_bytes(), 1, self) {
+      Done(_, res) => Ok( Elab::Done( res.destroy().0 ) ),
+      Error( ::nom::ErrorKind::Custom(e) ) => {
+        let waiting: HashSet<Sym> = self.misses.borrow_mut().drain().collect() ;
+        let error = e.to_parse_error(txt, line) ;
+        if waiting.is_empty() {
+          Err(error)
+        } else {
+          Ok( Elab::Wait(waiting, error) )
+        }
+      },
+      Incomplete(_) => Err( error_at(txt, line, "incomplete item") ),
+      _ => Err( error_at(txt, line, "expected VMT-LIB command") ),
+    }
+  }
+}
@@ -0,0 +1,108 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! History variables, the previous values of some state variables.
+//!
+//! [`add_history`](fn.add_history.html) builds the same system with
+//! `depth` history variables for some state variables: `pre1.x` is the
+//! value of `x` in the previous state, `pre2.x` the value of `pre1.x` in
+//! the previous state, and so on. They are unconstrained in the initial
+//! states. History variables are state variables since they are not
+//! functions of the current state, which the locals of a system are.
+//!
+//! The behavior of the system is the same, but invariants can now relate
+//! the current and previous values of the state variables. Such invariants
+//! strengthen the step case of k-induction, which can then prove properties
+//! at lower depths. [`select`](fn.select.html) picks the state variables
+//! of the properties.
+
+use std::sync::Arc ;
+use std::collections::HashSet ;
+
+use term::{
+  Sym, Term, STerm, State, Factory, SymMaker, VarMaker, state_vars_of
+} ;
+use term::parsing::Spnd ;
+
+use base::Sys ;
+
+/// Name of the history variable holding the value of a state variable
+/// `index` states ago.
+pub fn name_of(svar: & Sym, index: usize) -> String {
+  format!("pre{}.{}", index, svar.sym())
+}
+
+/// The state variables the properties mention, in the order of the state
+/// of the system.
+pub fn select(sys: & Sys, props: & [::Prop]) -> Vec<Sym> {
+  let mut mentioned = HashSet::new() ;
+  for prop in props.iter() {
+    let body = match * prop.body() {
+      STerm::One(ref curr, _) => curr,
+      STerm::Two(ref next) => next,
+    } ;
+    for var in state_vars_of(body).into_iter() {
+      mentioned.insert( var.get().sym().clone() ) ;
+    }
+  }
+  sys.state().args().iter().filter_map(
+    |& (ref sym, _)| if mentioned.contains( sym.get() ) {
+      Some( sym.get().clone() )
+    } else { None }
+  ).collect()
+}
+
+/// The same system with `depth` history variables for each of some state
+/// variables, appended to its state.
+pub fn add_history(
+  sys: & ::Sys, svars: & [Sym], depth: usize, factory: & Factory
+) -> Result<::Sys, String> {
+  if depth == 0 || svars.is_empty() { return Ok( sys.clone() ) }
+  let args = sys.state().args() ;
+
+  // New state variables, their declaration, and the state variable each of
+  // them is the previous value of.
+  let mut history = Vec::with_capacity( svars.len() * depth ) ;
+  for svar in svars.iter() {
+    let decl = match args.iter().find( |& & (ref sym, _)| sym.get() == svar ) {
+      Some( & (ref sym, ref typ) ) => (sym.span.clone(), typ.clone()),
+      None => return Err(
+        format!(
+          "`{}` is not a state variable of system {}", svar, sys.sym()
+        )
+      ),
+    } ;
+    let mut prev = svar.clone() ;
+    for index in 1 .. depth + 1 {
+      let sym = factory.sym( name_of(svar, index) ) ;
+      if sys.state().contains(& sym) {
+        return Err(
+          format!(
+            "cannot add history variable `{}` to system {}, \
+            it is a state variable already", sym, sys.sym()
+          )
+        )
+      }
+      history.push( (sym.clone(), decl.clone(), prev) ) ;
+      prev = sym
+    }
+  }
+
+  let mut extra = Vec::with_capacity( history.len() ) ;
+  let mut trans = Vec::with_capacity( history.len() ) ;
+  for (sym, (span, typ), prev) in history.into_iter() {
+    let next: Term = factory.svar( sym.clone(), State::Next ) ;
+    let prev: Term = factory.svar(prev, State::Curr) ;
+    trans.push( factory.eq( vec![ next, prev ] ) ) ;
+    extra.push( (Spnd::mk(sym, span), typ) )
+  }
+  sys.with_extra_state(
+    extra, vec![], trans, vec![], & [], factory
+  ).map( Arc::new )
+}
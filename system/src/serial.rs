@@ -0,0 +1,336 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serialization of systems and properties, with the `serialize` feature.
+//!
+//! Implements [`term::serial::Serial`][serial] for the real structures of
+//! module [`real_sys`][real sys], wrap them in an `Arc` once re-created.
+//!
+//! Re-creating a system registers the types of its state variables in the
+//! factory, and re-creating a function registers its type, as parsing does.
+//! A property stores its system, so serializing several properties over the
+//! same system stores the system several times.
+//!
+//! [serial]: ../../term/serial/trait.Serial.html (Serial trait)
+//! [real sys]: ../real_sys/index.html (real_sys module)
+
+use std::sync::Arc ;
+
+use term::{ Sym, Var, Type, Term, STerm, State, Factory, VarMaker } ;
+use term::parsing::{ Spn, Spnd } ;
+use term::serial::{ Serial, PVar, PTerm, PSTerm } ;
+use term::errors::* ;
+
+use base::{ CallSet, Sig, Args, Uf, Fun, Callable, Prop, Sys, Live } ;
+
+/// Plain spanned value.
+pub type PSpnd<T> = (T, Spn) ;
+
+/// Plain formal parameters.
+pub type PArgs = Vec<( PSpnd<String>, PSpnd<Type> )> ;
+
+/// Plain init predicate or transition relation: symbol, formal parameters,
+/// definition and application.
+pub type PPred = (String, Vec<(PVar, Type)>, PTerm, PTerm) ;
+
+/// Plain uninterpreted function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PUf {
+  /// Identifier of the function.
+  pub sym: PSpnd<String>,
+  /// Signature of the function.
+  pub sig: Vec< PSpnd<Type> >,
+  /// Return type of the function.
+  pub typ: PSpnd<Type>,
+}
+
+/// Plain function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PFun {
+  /// Identifier of the function.
+  pub sym: PSpnd<String>,
+  /// Formal arguments of the function.
+  pub args: PArgs,
+  /// Return type of the function.
+  pub typ: PSpnd<Type>,
+  /// Body of the function.
+  pub body: PTerm,
+  /// Callables used by the function, in topological order.
+  pub calls: Vec<PCallable>,
+}
+
+/// Plain callable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PCallable {
+  /// Uninterpreted function.
+  Dec(PUf),
+  /// Function.
+  Def(PFun),
+}
+
+/// Plain system.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PSys {
+  /// Identifier of the system.
+  pub sym: PSpnd<String>,
+  /// State of the system.
+  pub state: PArgs,
+  /// Local variables of the system.
+  pub locals: Vec<(String, Type, PTerm)>,
+  /// Init predicate of the system.
+  pub init: PPred,
+  /// Transition relation of the system.
+  pub trans: PPred,
+  /// Fairness constraints of the system.
+  pub fair: Vec<PTerm>,
+  /// Sub-systems of the system, with their parameters.
+  pub subsys: Vec<(PSys, Vec<PTerm>)>,
+  /// Callables used by the system, in topological order.
+  pub calls: Vec<PCallable>,
+}
+
+/// Plain property.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PProp {
+  /// Identifier of the property.
+  pub sym: PSpnd<String>,
+  /// System the property is over.
+  pub sys: PSys,
+  /// Body of the property.
+  pub body: PSTerm,
+  /// Callables used by the property, in topological order.
+  pub calls: Vec<PCallable>,
+  /// Temporal operator of a liveness property.
+  pub live: Option<Live>,
+}
+
+/// Plain version of a spanned symbol.
+fn plain_of_sym(sym: & Spnd<Sym>) -> PSpnd<String> {
+  ( sym.get().to_plain(), sym.span.clone() )
+}
+/// Spanned symbol from its plain version.
+fn sym_of_plain(factory: & Factory, sym: PSpnd<String>) -> Res< Spnd<Sym> > {
+  let (sym, span) = sym ;
+  Ok( Spnd::mk( try!( Sym::of_plain(factory, sym) ), span ) )
+}
+
+/// Plain version of a spanned type.
+fn plain_of_typ(typ: & Spnd<Type>) -> PSpnd<Type> {
+  ( typ.get().clone(), typ.span.clone() )
+}
+/// Spanned type from its plain version.
+fn typ_of_plain(typ: PSpnd<Type>) -> Spnd<Type> {
+  let (typ, span) = typ ;
+  Spnd::mk(typ, span)
+}
+
+/// Plain version of some formal parameters.
+fn plain_of_args(args: & [ (Spnd<Sym>, Spnd<Type>) ]) -> PArgs {
+  args.iter().map(
+    |& (ref sym, ref typ)| ( plain_of_sym(sym), plain_of_typ(typ) )
+  ).collect()
+}
+/// Formal parameters from their plain version.
+fn args_of_plain(factory: & Factory, args: PArgs) -> Res<Args> {
+  let mut res = Vec::with_capacity( args.len() ) ;
+  for (sym, typ) in args.into_iter() {
+    res.push( ( try!( sym_of_plain(factory, sym) ), typ_of_plain(typ) ) )
+  }
+  Ok( Args::mk(res) )
+}
+
+/// Plain version of some terms.
+fn plain_of_terms(terms: & [Term]) -> Vec<PTerm> {
+  terms.iter().map( |term| term.to_plain() ).collect()
+}
+/// Terms from their plain version.
+fn terms_of_plain(factory: & Factory, terms: Vec<PTerm>) -> Res< Vec<Term> > {
+  let mut res = Vec::with_capacity( terms.len() ) ;
+  for term in terms.into_iter() {
+    res.push( try!( Term::of_plain(factory, term) ) )
+  }
+  Ok(res)
+}
+
+/// Plain version of a call set.
+fn plain_of_calls(calls: & CallSet) -> Vec<PCallable> {
+  calls.get().iter().map( |call| call.to_plain() ).collect()
+}
+/// Call set from its plain version.
+fn calls_of_plain(
+  factory: & Factory, calls: Vec<PCallable>
+) -> Res<CallSet> {
+  let mut res = CallSet::empty() ;
+  for call in calls.into_iter() {
+    res.insert( Arc::new( try!( Callable::of_plain(factory, call) ) ) )
+  }
+  Ok(res)
+}
+
+/// Plain version of an init predicate or a transition relation.
+fn plain_of_pred(pred: & (Sym, Vec<(Var, Type)>, Term, Term)) -> PPred {
+  let (ref sym, ref params, ref def, ref app) = * pred ;
+  (
+    sym.to_plain(),
+    params.iter().map(
+      |& (ref var, ref typ)| ( var.to_plain(), typ.clone() )
+    ).collect(),
+    def.to_plain(), app.to_plain()
+  )
+}
+/// Init predicate or transition relation from its plain version.
+fn pred_of_plain(
+  factory: & Factory, pred: PPred
+) -> Res< (Sym, Vec<(Var, Type)>, Term, Term) > {
+  let (sym, params, def, app) = pred ;
+  let mut nu_params = Vec::with_capacity( params.len() ) ;
+  for (var, typ) in params.into_iter() {
+    nu_params.push( ( try!( Var::of_plain(factory, var) ), typ ) )
+  }
+  Ok( (
+    try!( Sym::of_plain(factory, sym) ), nu_params,
+    try!( Term::of_plain(factory, def) ), try!( Term::of_plain(factory, app) )
+  ) )
+}
+
+impl Serial for Uf {
+  type Plain = PUf ;
+  fn to_plain(& self) -> PUf {
+    PUf {
+      sym: plain_of_sym( self.sym() ),
+      sig: self.sig().iter().map( plain_of_typ ).collect(),
+      typ: plain_of_typ( self.typ() ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PUf) -> Res<Uf> {
+    let sym = try!( sym_of_plain(factory, plain.sym) ) ;
+    let typ = typ_of_plain(plain.typ) ;
+    try!( factory.set_fun_type( sym.get().clone(), typ.get().clone() ) ) ;
+    let sig = plain.sig.into_iter().map( typ_of_plain ).collect() ;
+    Ok( Uf::mk( sym, Sig::mk(sig), typ ) )
+  }
+}
+
+impl Serial for Fun {
+  type Plain = PFun ;
+  fn to_plain(& self) -> PFun {
+    PFun {
+      sym: plain_of_sym( self.sym() ),
+      args: plain_of_args( self.args() ),
+      typ: plain_of_typ( self.typ() ),
+      body: self.body().to_plain(),
+      calls: plain_of_calls( self.calls() ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PFun) -> Res<Fun> {
+    let calls = try!( calls_of_plain(factory, plain.calls) ) ;
+    let sym = try!( sym_of_plain(factory, plain.sym) ) ;
+    let typ = typ_of_plain(plain.typ) ;
+    try!( factory.set_fun_type( sym.get().clone(), typ.get().clone() ) ) ;
+    let args = try!( args_of_plain(factory, plain.args) ) ;
+    let body = try!( Term::of_plain(factory, plain.body) ) ;
+    Ok( Fun::mk(sym, args, typ, body, calls) )
+  }
+}
+
+impl Serial for Callable {
+  type Plain = PCallable ;
+  fn to_plain(& self) -> PCallable {
+    match * self {
+      Callable::Dec(ref uf) => PCallable::Dec( uf.to_plain() ),
+      Callable::Def(ref fun) => PCallable::Def( fun.to_plain() ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PCallable) -> Res<Callable> {
+    match plain {
+      PCallable::Dec(uf) => Ok(
+        Callable::Dec( try!( Uf::of_plain(factory, uf) ) )
+      ),
+      PCallable::Def(fun) => Ok(
+        Callable::Def( try!( Fun::of_plain(factory, fun) ) )
+      ),
+    }
+  }
+}
+
+impl Serial for Sys {
+  type Plain = PSys ;
+  fn to_plain(& self) -> PSys {
+    PSys {
+      sym: plain_of_sym( self.sym() ),
+      state: plain_of_args( self.state().args() ),
+      locals: self.locals().iter().map(
+        |& (ref sym, ref typ, ref term)| (
+          sym.to_plain(), typ.clone(), term.to_plain()
+        )
+      ).collect(),
+      init: plain_of_pred( self.init() ),
+      trans: plain_of_pred( self.trans() ),
+      fair: plain_of_terms( self.fair() ),
+      subsys: self.subsys().iter().map(
+        |& (ref sub, ref params)| ( sub.to_plain(), plain_of_terms(params) )
+      ).collect(),
+      calls: plain_of_calls( self.calls() ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PSys) -> Res<Sys> {
+    let calls = try!( calls_of_plain(factory, plain.calls) ) ;
+    let mut subsys = Vec::with_capacity( plain.subsys.len() ) ;
+    for (sub, params) in plain.subsys.into_iter() {
+      subsys.push( (
+        Arc::new( try!( Sys::of_plain(factory, sub) ) ),
+        try!( terms_of_plain(factory, params) )
+      ) )
+    }
+    let sym = try!( sym_of_plain(factory, plain.sym) ) ;
+    let state = try!( args_of_plain(factory, plain.state) ) ;
+    for & (ref var, ref typ) in state.args() {
+      let svar: Var = factory.svar( var.get().clone(), State::Curr ) ;
+      try!(
+        factory.set_var_type(
+          Some( sym.get().clone() ), svar, typ.get().clone()
+        )
+      )
+    }
+    let mut locals = Vec::with_capacity( plain.locals.len() ) ;
+    for (local, typ, term) in plain.locals.into_iter() {
+      locals.push( (
+        try!( Sym::of_plain(factory, local) ), typ,
+        try!( Term::of_plain(factory, term) )
+      ) )
+    }
+    let init = try!( pred_of_plain(factory, plain.init) ) ;
+    let trans = try!( pred_of_plain(factory, plain.trans) ) ;
+    let fair = try!( terms_of_plain(factory, plain.fair) ) ;
+    Ok( Sys::mk(sym, state, locals, init, trans, fair, subsys, calls) )
+  }
+}
+
+impl Serial for Prop {
+  type Plain = PProp ;
+  fn to_plain(& self) -> PProp {
+    PProp {
+      sym: plain_of_sym( self.sym() ),
+      sys: self.sys().to_plain(),
+      body: self.body().to_plain(),
+      calls: plain_of_calls( self.calls() ),
+      live: self.live(),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PProp) -> Res<Prop> {
+    let sys = Arc::new( try!( Sys::of_plain(factory, plain.sys) ) ) ;
+    let calls = try!( calls_of_plain(factory, plain.calls) ) ;
+    let sym = try!( sym_of_plain(factory, plain.sym) ) ;
+    let body = try!( STerm::of_plain(factory, plain.body) ) ;
+    match plain.live {
+      None => Ok( Prop::mk(sym, sys, body, calls) ),
+      Some(live) => Ok( Prop::mk_live(sym, sys, body, calls, live) ),
+    }
+  }
+}
@@ -0,0 +1,833 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! NuSMV / nuXmv frontend, for a subset of the language.
+//!
+//! [`translate`](fn.translate.html) turns a single `main` module into a
+//! system `main` in the input language of kino, along with one property per
+//! invariant specification and a verify query over all of them. The result
+//! is parsed and type checked by a [`Context`][context] like any other
+//! input.
+//!
+//! Supported:
+//!
+//! - `VAR`, `IVAR` and `FROZENVAR` sections, with types `boolean`,
+//!   `integer`, `real`, ranges `lo..hi` and enumerations. Ranges and
+//!   enumerations are integers constrained in every state, the symbolic
+//!   constants of enumerations are numbered in order of appearance;
+//! - `ASSIGN` sections with `init(x) := e`, `next(x) := e` and `x := e`,
+//!   where `e` can be a set `{a, b}` of possible values, possibly under a
+//!   `case`;
+//! - `DEFINE` sections, inlined where they are used;
+//! - `INIT`, `TRANS` and `INVAR` constraints;
+//! - `INVARSPEC e` and `INVARSPEC NAME p := e`, the other specifications
+//!   are rejected.
+//!
+//! Expressions use the usual operators, `case ... esac`, `c ? a : b` and
+//! `next(e)` in transition constraints. Integer and real expressions are
+//! not converted to each other: constants in real expressions should be
+//! written as decimals.
+//!
+//! [context]: ../ctxt/struct.Context.html (Context struct)
+
+use std::collections::HashMap ;
+
+/// Maximum depth of `DEFINE` expansions, guards against cyclic definitions.
+const max_define_depth: usize = 100 ;
+
+/// Translates an SMV model to the input language of kino.
+pub fn translate(input: & str) -> Result<String, String> {
+  let tokens = try!( lex(input) ) ;
+  let model = try!( Parser { tokens: tokens, index: 0 }.model() ) ;
+  model.to_kino()
+}
+
+/// A token, with its line.
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+  /// Identifier or keyword.
+  Id(String),
+  /// Integer literal.
+  Int(String),
+  /// Decimal literal.
+  Real(String),
+  /// Punctuation or operator.
+  Sym(& 'static str),
+}
+
+/// Punctuation and operators, longest first.
+static syms: & 'static [& 'static str] = & [
+  "<->", ":=", "..", "->", "!=", "<=", ">=", ":", ";", "(", ")", "{", "}",
+  ",", "!", "&", "|", "=", "<", ">", "+", "-", "*", "/", "?",
+] ;
+
+/// Splits an SMV model in tokens, skipping the comments.
+fn lex(input: & str) -> Result< Vec<(Tok, usize)>, String > {
+  let chars: Vec<char> = input.chars().collect() ;
+  let mut res = vec![] ;
+  let (mut index, mut line) = (0, 1) ;
+  'chars: while index < chars.len() {
+    let c = chars[index] ;
+    if c == '\n' {
+      line += 1 ;
+      index += 1 ;
+      continue
+    }
+    if c.is_whitespace() {
+      index += 1 ;
+      continue
+    }
+    // Comment.
+    if c == '-' && chars.get(index + 1) == Some(& '-') {
+      while index < chars.len() && chars[index] != '\n' { index += 1 }
+      continue
+    }
+    if c.is_alphabetic() || c == '_' {
+      let start = index ;
+      while index < chars.len() && (
+        chars[index].is_alphanumeric() || "_$#".contains(chars[index])
+      ) {
+        index += 1
+      }
+      let id: String = chars[start .. index].iter().cloned().collect() ;
+      res.push( (Tok::Id(id), line) ) ;
+      continue
+    }
+    if c.is_digit(10) {
+      let start = index ;
+      while index < chars.len() && chars[index].is_digit(10) { index += 1 }
+      // Not a range `lo..hi`.
+      let is_real = chars.get(index) == Some(& '.') && chars.get(
+        index + 1
+      ).map( |c| c.is_digit(10) ).unwrap_or(false) ;
+      if is_real {
+        index += 1 ;
+        while index < chars.len() && chars[index].is_digit(10) {
+          index += 1
+        }
+      }
+      let lit: String = chars[start .. index].iter().cloned().collect() ;
+      res.push(
+        ( if is_real { Tok::Real(lit) } else { Tok::Int(lit) }, line )
+      ) ;
+      continue
+    }
+    for sym in syms.iter() {
+      let len = sym.len() ;
+      if index + len <= chars.len() && sym.chars().zip(
+        chars[index .. index + len].iter()
+      ).all( |(lhs, rhs)| lhs == * rhs ) {
+        res.push( (Tok::Sym(* sym), line) ) ;
+        index += len ;
+        continue 'chars
+      }
+    }
+    return Err( format!("[smv] line {}: unexpected character `{}`", line, c) )
+  }
+  Ok(res)
+}
+
+/// An SMV expression.
+#[derive(Debug, Clone)]
+enum Expr {
+  /// Boolean constant.
+  Bool(bool),
+  /// Integer constant.
+  Int(String),
+  /// Decimal constant.
+  Real(String),
+  /// Identifier: variable, define or symbolic constant.
+  Id(String, usize),
+  /// Next value.
+  Next(Box<Expr>, usize),
+  /// Unary operator.
+  Un(& 'static str, Box<Expr>),
+  /// Binary operator.
+  Bin(& 'static str, Box<Expr>, Box<Expr>),
+  /// If-then-else.
+  Ite(Box<Expr>, Box<Expr>, Box<Expr>),
+  /// Case, the branches in order.
+  Case(Vec<(Expr, Expr)>),
+  /// Set of possible values.
+  Set(Vec<Expr>),
+}
+
+/// Type of a variable.
+#[derive(Debug, Clone)]
+enum Typ {
+  /// Boolean.
+  Bool,
+  /// Unbounded integer.
+  Int,
+  /// Real.
+  Real,
+  /// Integer range, the bounds in the input language.
+  Range(String, String),
+  /// Enumeration, the values in the input language.
+  Enum(Vec<String>),
+}
+
+/// An SMV `main` module.
+struct Model {
+  /// Variables, with their type and whether they are frozen.
+  vars: Vec<(String, Typ, bool)>,
+  /// Defines.
+  defines: HashMap<String, Expr>,
+  /// Symbolic constants of the enumerations, and their value.
+  consts: HashMap<String, usize>,
+  /// Initial constraints.
+  init: Vec<Expr>,
+  /// Transition constraints.
+  trans: Vec<Expr>,
+  /// Constraints holding in every state.
+  invar: Vec<Expr>,
+  /// `init(x) := e` assignments.
+  init_asgn: Vec<(String, Expr, usize)>,
+  /// `next(x) := e` assignments.
+  next_asgn: Vec<(String, Expr, usize)>,
+  /// Invariant specifications, with their name.
+  specs: Vec<(String, Expr)>,
+}
+
+/// Recursive descent parser over tokens.
+struct Parser {
+  /// Tokens and their line.
+  tokens: Vec<(Tok, usize)>,
+  /// Index of the next token.
+  index: usize,
+}
+impl Parser {
+  /// Next token, if any.
+  fn peek(& self) -> Option<& Tok> {
+    self.tokens.get(self.index).map( |& (ref tok, _)| tok )
+  }
+  /// Line of the next token, or of the last one.
+  fn line(& self) -> usize {
+    self.tokens.get(self.index).or_else(
+      || self.tokens.last()
+    ).map( |& (_, line)| line ).unwrap_or(1)
+  }
+  /// Error at the current line.
+  fn error<T>(& self, blah: & str) -> Result<T, String> {
+    Err( format!("[smv] line {}: {}", self.line(), blah) )
+  }
+  /// True if the next token is some punctuation or operator.
+  fn is_sym(& self, sym: & str) -> bool {
+    match self.peek() {
+      Some( & Tok::Sym(s) ) => s == sym,
+      _ => false,
+    }
+  }
+  /// True if the next token is some identifier or keyword.
+  fn is_id(& self, id: & str) -> bool {
+    match self.peek() {
+      Some( & Tok::Id(ref s) ) => s == id,
+      _ => false,
+    }
+  }
+  /// Consumes some punctuation or operator if it is next.
+  fn eat(& mut self, sym: & str) -> bool {
+    if self.is_sym(sym) {
+      self.index += 1 ;
+      true
+    } else { false }
+  }
+  /// Consumes some punctuation or operator, fails if it is not next.
+  fn expect(& mut self, sym: & str) -> Result<(), String> {
+    if self.eat(sym) { Ok(()) } else {
+      self.error( & format!("expected `{}`", sym) )
+    }
+  }
+  /// Consumes an identifier.
+  fn ident(& mut self) -> Result<String, String> {
+    let id = match self.peek() {
+      Some( & Tok::Id(ref id) ) => id.clone(),
+      _ => return self.error("expected identifier"),
+    } ;
+    self.index += 1 ;
+    Ok(id)
+  }
+  /// True if the next token starts a section.
+  fn at_section(& self) -> bool {
+    match self.peek() {
+      Some( & Tok::Id(ref id) ) => is_section(id),
+      None => true,
+      _ => false,
+    }
+  }
+
+  /// Parses a `main` module.
+  fn model(mut self) -> Result<Model, String> {
+    let mut model = Model {
+      vars: vec![], defines: HashMap::new(), consts: HashMap::new(),
+      init: vec![], trans: vec![], invar: vec![],
+      init_asgn: vec![], next_asgn: vec![], specs: vec![],
+    } ;
+    if ! self.is_id("MODULE") {
+      return self.error("expected `MODULE main`")
+    }
+    self.index += 1 ;
+    if try!( self.ident() ) != "main" || self.is_sym("(") {
+      return self.error(
+        "only a single `main` module without parameters is supported"
+      )
+    }
+    while let Some(section) = self.peek().cloned() {
+      let section = match section {
+        Tok::Id(id) => id,
+        _ => return self.error("expected section"),
+      } ;
+      self.index += 1 ;
+      match section.as_str() {
+        "VAR" | "IVAR" | "FROZENVAR" => while ! self.at_section() {
+          let var = try!( self.ident() ) ;
+          try!( self.expect(":") ) ;
+          let typ = try!( self.typ(& mut model) ) ;
+          try!( self.expect(";") ) ;
+          model.vars.push( (var, typ, section == "FROZENVAR") )
+        },
+        "ASSIGN" => while ! self.at_section() {
+          let line = self.line() ;
+          let lhs = try!( self.ident() ) ;
+          let kind = if lhs == "init" || lhs == "next" {
+            try!( self.expect("(") ) ;
+            let var = try!( self.ident() ) ;
+            try!( self.expect(")") ) ;
+            Some( (lhs == "init", var) )
+          } else { None } ;
+          try!( self.expect(":=") ) ;
+          let rhs = try!( self.expr() ) ;
+          try!( self.expect(";") ) ;
+          match kind {
+            Some( (true, var) ) => model.init_asgn.push( (var, rhs, line) ),
+            Some( (false, var) ) => model.next_asgn.push( (var, rhs, line) ),
+            None => model.invar.push(
+              Expr::Bin( "=", Box::new( Expr::Id(lhs, line) ), Box::new(rhs) )
+            ),
+          }
+        },
+        "DEFINE" => while ! self.at_section() {
+          let name = try!( self.ident() ) ;
+          try!( self.expect(":=") ) ;
+          let def = try!( self.expr() ) ;
+          try!( self.expect(";") ) ;
+          model.defines.insert(name, def) ;
+          ()
+        },
+        "INIT" | "TRANS" | "INVAR" => {
+          let expr = try!( self.expr() ) ;
+          self.eat(";") ;
+          match section.as_str() {
+            "INIT" => model.init.push(expr),
+            "TRANS" => model.trans.push(expr),
+            _ => model.invar.push(expr),
+          }
+        },
+        "INVARSPEC" => {
+          let name = if self.is_id("NAME") {
+            self.index += 1 ;
+            let name = try!( self.ident() ) ;
+            try!( self.expect(":=") ) ;
+            name
+          } else {
+            format!("invarspec_{}", model.specs.len() + 1)
+          } ;
+          let expr = try!( self.expr() ) ;
+          self.eat(";") ;
+          model.specs.push( (name, expr) )
+        },
+        "MODULE" => return self.error(
+          "only a single `main` module is supported"
+        ),
+        "SPEC" | "CTLSPEC" | "LTLSPEC" | "PSLSPEC" | "COMPUTE" |
+        "FAIRNESS" | "JUSTICE" | "COMPASSION" => return self.error(
+          & format!("`{}` is not supported", section)
+        ),
+        _ => return self.error(
+          & format!("unexpected `{}`, expected section", section)
+        ),
+      }
+    }
+    Ok(model)
+  }
+
+  /// Parses a type.
+  fn typ(& mut self, model: & mut Model) -> Result<Typ, String> {
+    if self.eat("{") {
+      let mut values = vec![] ;
+      loop {
+        let value = match self.peek().cloned() {
+          Some( Tok::Int(int) ) => int,
+          Some( Tok::Sym("-") ) => {
+            self.index += 1 ;
+            match self.peek().cloned() {
+              Some( Tok::Int(int) ) => format!("(- {})", int),
+              _ => return self.error("expected integer"),
+            }
+          },
+          Some( Tok::Id(id) ) => {
+            let count = model.consts.len() ;
+            format!( "{}", * model.consts.entry(id).or_insert(count) )
+          },
+          _ => return self.error("expected enumeration value"),
+        } ;
+        self.index += 1 ;
+        values.push(value) ;
+        if ! self.eat(",") { break }
+      }
+      try!( self.expect("}") ) ;
+      return Ok( Typ::Enum(values) )
+    }
+    if self.is_id("boolean") {
+      self.index += 1 ;
+      return Ok(Typ::Bool)
+    }
+    if self.is_id("integer") {
+      self.index += 1 ;
+      return Ok(Typ::Int)
+    }
+    if self.is_id("real") {
+      self.index += 1 ;
+      return Ok(Typ::Real)
+    }
+    let lo = try!( self.int() ) ;
+    try!( self.expect("..") ) ;
+    let hi = try!( self.int() ) ;
+    Ok( Typ::Range(lo, hi) )
+  }
+
+  /// Parses a possibly negative integer, in the input language.
+  fn int(& mut self) -> Result<String, String> {
+    let neg = self.eat("-") ;
+    match self.peek().cloned() {
+      Some( Tok::Int(int) ) => {
+        self.index += 1 ;
+        Ok( if neg { format!("(- {})", int) } else { int } )
+      },
+      _ => self.error("expected type"),
+    }
+  }
+
+  /// Parses an expression.
+  fn expr(& mut self) -> Result<Expr, String> {
+    // `->` is right associative.
+    let lhs = try!( self.iff() ) ;
+    if self.eat("->") {
+      let rhs = try!( self.expr() ) ;
+      Ok( Expr::Bin( "=>", Box::new(lhs), Box::new(rhs) ) )
+    } else { Ok(lhs) }
+  }
+  /// `<->`.
+  fn iff(& mut self) -> Result<Expr, String> {
+    let mut lhs = try!( self.ite() ) ;
+    while self.eat("<->") {
+      let rhs = try!( self.ite() ) ;
+      lhs = Expr::Bin( "=", Box::new(lhs), Box::new(rhs) )
+    }
+    Ok(lhs)
+  }
+  /// `c ? a : b`.
+  fn ite(& mut self) -> Result<Expr, String> {
+    let cond = try!( self.or() ) ;
+    if self.eat("?") {
+      let thn = try!( self.or() ) ;
+      try!( self.expect(":") ) ;
+      let els = try!( self.ite() ) ;
+      Ok( Expr::Ite( Box::new(cond), Box::new(thn), Box::new(els) ) )
+    } else { Ok(cond) }
+  }
+  /// `|`, `xor` and `xnor`.
+  fn or(& mut self) -> Result<Expr, String> {
+    let mut lhs = try!( self.and() ) ;
+    loop {
+      let op = if self.eat("|") { "or" } else if self.is_id("xor") {
+        self.index += 1 ;
+        "xor"
+      } else if self.is_id("xnor") {
+        self.index += 1 ;
+        "xnor"
+      } else { break } ;
+      let rhs = try!( self.and() ) ;
+      lhs = Expr::Bin( op, Box::new(lhs), Box::new(rhs) )
+    }
+    Ok(lhs)
+  }
+  /// `&`.
+  fn and(& mut self) -> Result<Expr, String> {
+    let mut lhs = try!( self.cmp() ) ;
+    while self.eat("&") {
+      let rhs = try!( self.cmp() ) ;
+      lhs = Expr::Bin( "and", Box::new(lhs), Box::new(rhs) )
+    }
+    Ok(lhs)
+  }
+  /// Comparisons.
+  fn cmp(& mut self) -> Result<Expr, String> {
+    let mut lhs = try!( self.add() ) ;
+    loop {
+      let op = match self.peek() {
+        Some( & Tok::Sym(op) ) if [
+          "=", "!=", "<", "<=", ">", ">="
+        ].contains(& op) => op,
+        _ => break,
+      } ;
+      self.index += 1 ;
+      let rhs = try!( self.add() ) ;
+      lhs = Expr::Bin( op, Box::new(lhs), Box::new(rhs) )
+    }
+    Ok(lhs)
+  }
+  /// `+` and `-`.
+  fn add(& mut self) -> Result<Expr, String> {
+    let mut lhs = try!( self.mul() ) ;
+    loop {
+      let op = if self.eat("+") { "+" } else if self.eat("-") {
+        "-"
+      } else { break } ;
+      let rhs = try!( self.mul() ) ;
+      lhs = Expr::Bin( op, Box::new(lhs), Box::new(rhs) )
+    }
+    Ok(lhs)
+  }
+  /// `*`, `/` and `mod`.
+  fn mul(& mut self) -> Result<Expr, String> {
+    let mut lhs = try!( self.unary() ) ;
+    loop {
+      let op = if self.eat("*") { "*" } else if self.eat("/") {
+        "/"
+      } else if self.is_id("mod") {
+        self.index += 1 ;
+        "mod"
+      } else { break } ;
+      let rhs = try!( self.unary() ) ;
+      lhs = Expr::Bin( op, Box::new(lhs), Box::new(rhs) )
+    }
+    Ok(lhs)
+  }
+  /// `!` and unary `-`.
+  fn unary(& mut self) -> Result<Expr, String> {
+    if self.eat("!") {
+      Ok( Expr::Un( "not", Box::new( try!( self.unary() ) ) ) )
+    } else if self.eat("-") {
+      Ok( Expr::Un( "-", Box::new( try!( self.unary() ) ) ) )
+    } else {
+      self.atom()
+    }
+  }
+  /// Constants, identifiers, `next`, `case`, sets and parentheses.
+  fn atom(& mut self) -> Result<Expr, String> {
+    let line = self.line() ;
+    let tok = match self.peek().cloned() {
+      Some(tok) => tok,
+      None => return self.error("expected expression, found nothing"),
+    } ;
+    self.index += 1 ;
+    match tok {
+      Tok::Int(int) => Ok( Expr::Int(int) ),
+      Tok::Real(real) => Ok( Expr::Real(real) ),
+      Tok::Sym("(") => {
+        let expr = try!( self.expr() ) ;
+        try!( self.expect(")") ) ;
+        Ok(expr)
+      },
+      Tok::Sym("{") => {
+        let mut values = vec![ try!( self.expr() ) ] ;
+        while self.eat(",") {
+          values.push( try!( self.expr() ) )
+        }
+        try!( self.expect("}") ) ;
+        Ok( Expr::Set(values) )
+      },
+      Tok::Id(id) => match id.as_str() {
+        "TRUE" => Ok( Expr::Bool(true) ),
+        "FALSE" => Ok( Expr::Bool(false) ),
+        "next" => {
+          try!( self.expect("(") ) ;
+          let expr = try!( self.expr() ) ;
+          try!( self.expect(")") ) ;
+          Ok( Expr::Next( Box::new(expr), line ) )
+        },
+        "case" => {
+          let mut branches = vec![] ;
+          while ! self.is_id("esac") {
+            if self.peek().is_none() {
+              return self.error("expected `esac`, found nothing")
+            }
+            let cond = try!( self.expr() ) ;
+            try!( self.expect(":") ) ;
+            let value = try!( self.expr() ) ;
+            try!( self.expect(";") ) ;
+            branches.push( (cond, value) )
+          }
+          self.index += 1 ;
+          if branches.is_empty() {
+            return self.error("empty `case`")
+          }
+          Ok( Expr::Case(branches) )
+        },
+        _ => Ok( Expr::Id(id, line) ),
+      },
+      Tok::Sym(sym) => Err(
+        format!("[smv] line {}: unexpected `{}`", line, sym)
+      ),
+    }
+  }
+}
+
+/// True if an identifier starts a section.
+fn is_section(id: & str) -> bool {
+  [
+    "MODULE", "VAR", "IVAR", "FROZENVAR", "ASSIGN", "DEFINE", "INIT",
+    "TRANS", "INVAR", "INVARSPEC", "SPEC", "CTLSPEC", "LTLSPEC", "PSLSPEC",
+    "COMPUTE", "FAIRNESS", "JUSTICE", "COMPASSION",
+  ].contains(& id)
+}
+
+/// Quotes a symbol for the input language.
+fn quote(sym: & str) -> String {
+  format!("|{}|", sym)
+}
+
+impl Model {
+  /// The variable `var` in the input language.
+  fn svar(& self, var: & str, next: bool) -> String {
+    format!(
+      "(_ {} {})", if next { "next" } else { "curr" }, quote(var)
+    )
+  }
+
+  /// An expression in the input language. `next` is true in the next
+  /// state, `next_ok` if next values are legal.
+  fn term(
+    & self, expr: & Expr, next: bool, next_ok: bool, depth: usize
+  ) -> Result<String, String> {
+    match * expr {
+      Expr::Bool(b) => Ok( format!("{}", b) ),
+      Expr::Int(ref int) => Ok( int.clone() ),
+      Expr::Real(ref real) => Ok( real.clone() ),
+      Expr::Id(ref id, line) => if self.vars.iter().any(
+        |& (ref var, _, _)| var == id
+      ) {
+        Ok( self.svar(id, next) )
+      } else if let Some(def) = self.defines.get(id) {
+        if depth >= max_define_depth {
+          return Err(
+            format!("[smv] line {}: cyclic definition of `{}`", line, id)
+          )
+        }
+        self.term(def, next, next_ok, depth + 1)
+      } else if let Some(value) = self.consts.get(id) {
+        Ok( format!("{}", value) )
+      } else {
+        Err( format!("[smv] line {}: unknown identifier `{}`", line, id) )
+      },
+      Expr::Next(ref expr, line) => if next || ! next_ok {
+        Err( format!("[smv] line {}: illegal `next`", line) )
+      } else {
+        self.term(expr, true, next_ok, depth)
+      },
+      Expr::Un(op, ref kid) => Ok(
+        format!("({} {})", op, try!( self.term(kid, next, next_ok, depth) ))
+      ),
+      Expr::Bin(op, ref lhs, ref rhs) => {
+        let lhs = try!( self.term(lhs, next, next_ok, depth) ) ;
+        let rhs = try!( self.term(rhs, next, next_ok, depth) ) ;
+        Ok(
+          match op {
+            "!=" => format!("(not (= {} {}))", lhs, rhs),
+            "xnor" => format!("(not (xor {} {}))", lhs, rhs),
+            op => format!("({} {} {})", op, lhs, rhs),
+          }
+        )
+      },
+      Expr::Ite(ref cond, ref thn, ref els) => Ok(
+        format!(
+          "(ite {} {} {})",
+          try!( self.term(cond, next, next_ok, depth) ),
+          try!( self.term(thn, next, next_ok, depth) ),
+          try!( self.term(els, next, next_ok, depth) )
+        )
+      ),
+      Expr::Case(ref branches) => {
+        // The last branch is the default one.
+        let mut iter = branches.iter().rev() ;
+        let mut res = match iter.next() {
+          Some( & (_, ref value) ) => try!(
+            self.term(value, next, next_ok, depth)
+          ),
+          None => return Err( "[smv] empty `case`".to_string() ),
+        } ;
+        for & (ref cond, ref value) in iter {
+          res = format!(
+            "(ite {} {} {})",
+            try!( self.term(cond, next, next_ok, depth) ),
+            try!( self.term(value, next, next_ok, depth) ),
+            res
+          )
+        }
+        Ok(res)
+      },
+      Expr::Set(_) => Err(
+        "[smv] sets of values are only supported in assignments".to_string()
+      ),
+    }
+  }
+
+  /// The constraint that `lhs` is one of the values of an assignment.
+  fn assign(
+    & self, lhs: & str, rhs: & Expr, next: bool, depth: usize
+  ) -> Result<String, String> {
+    match * rhs {
+      Expr::Set(ref values) => {
+        let mut eqs = String::new() ;
+        for value in values.iter() {
+          eqs.push_str(
+            & format!(" {}", try!( self.assign(lhs, value, next, depth) ))
+          )
+        }
+        Ok( format!("(or{})", eqs) )
+      },
+      Expr::Case(ref branches) => {
+        let mut iter = branches.iter().rev() ;
+        let mut res = match iter.next() {
+          Some( & (_, ref value) ) => try!(
+            self.assign(lhs, value, next, depth)
+          ),
+          None => return Err( "[smv] empty `case`".to_string() ),
+        } ;
+        for & (ref cond, ref value) in iter {
+          res = format!(
+            "(ite {} {} {})", try!( self.term(cond, false, false, depth) ),
+            try!( self.assign(lhs, value, next, depth) ), res
+          )
+        }
+        Ok(res)
+      },
+      Expr::Ite(ref cond, ref thn, ref els) => Ok(
+        format!(
+          "(ite {} {} {})", try!( self.term(cond, false, false, depth) ),
+          try!( self.assign(lhs, thn, next, depth) ),
+          try!( self.assign(lhs, els, next, depth) )
+        )
+      ),
+      Expr::Id(ref id, line) if self.defines.contains_key(id) => {
+        if depth >= max_define_depth {
+          return Err(
+            format!("[smv] line {}: cyclic definition of `{}`", line, id)
+          )
+        }
+        self.assign(lhs, & self.defines[id], next, depth + 1)
+      },
+      ref value => Ok(
+        format!(
+          "(= {} {})", self.svar(lhs, next),
+          try!( self.term(value, false, false, depth) )
+        )
+      ),
+    }
+  }
+
+  /// Checks that an assignment is to a variable.
+  fn check_lhs(& self, var: & str, line: usize) -> Result<(), String> {
+    if self.vars.iter().any( |& (ref v, _, _)| v == var ) { Ok(()) } else {
+      Err(
+        format!(
+          "[smv] line {}: assignment to unknown variable `{}`", line, var
+        )
+      )
+    }
+  }
+
+  /// The model in the input language of kino.
+  fn to_kino(& self) -> Result<String, String> {
+    if self.specs.is_empty() {
+      return Err( "[smv] no `INVARSPEC` to verify".to_string() )
+    }
+    let (mut init, mut trans) = ( vec![], vec![] ) ;
+    let mut state = String::new() ;
+
+    for & (ref var, ref typ, frozen) in self.vars.iter() {
+      let (curr, next) = ( self.svar(var, false), self.svar(var, true) ) ;
+      let sort = match * typ {
+        Typ::Bool => "Bool",
+        Typ::Real => "Real",
+        _ => "Int",
+      } ;
+      state.push_str( & format!(" ({} {})", quote(var), sort) ) ;
+      let domain = |svar: & str| match * typ {
+        Typ::Range(ref lo, ref hi) => Some(
+          format!("(and (<= {} {}) (<= {} {}))", lo, svar, svar, hi)
+        ),
+        Typ::Enum(ref values) => Some(
+          format!(
+            "(or{})", values.iter().fold(
+              String::new(), |s, value| format!("{} (= {} {})", s, svar, value)
+            )
+          )
+        ),
+        _ => None,
+      } ;
+      if let Some(dom) = domain(& curr) {
+        init.push( dom.clone() ) ;
+        trans.push(dom) ;
+        trans.push( domain(& next).unwrap() )
+      }
+      if frozen {
+        trans.push( format!("(= {} {})", next, curr) )
+      }
+    }
+
+    for expr in self.init.iter() {
+      init.push( try!( self.term(expr, false, false, 0) ) )
+    }
+    for expr in self.trans.iter() {
+      trans.push( try!( self.term(expr, false, true, 0) ) )
+    }
+    // Holds in both states of the transition relation, for induction.
+    for expr in self.invar.iter() {
+      init.push( try!( self.term(expr, false, false, 0) ) ) ;
+      trans.push( try!( self.term(expr, false, false, 0) ) ) ;
+      trans.push( try!( self.term(expr, true, false, 0) ) )
+    }
+    for & (ref var, ref rhs, line) in self.init_asgn.iter() {
+      try!( self.check_lhs(var, line) ) ;
+      init.push( try!( self.assign(var, rhs, false, 0) ) )
+    }
+    for & (ref var, ref rhs, line) in self.next_asgn.iter() {
+      try!( self.check_lhs(var, line) ) ;
+      trans.push( try!( self.assign(var, rhs, true, 0) ) )
+    }
+
+    let conj = |conjs: Vec<String>| match conjs.len() {
+      0 => "true".to_string(),
+      1 => conjs[0].clone(),
+      _ => format!(
+        "(and\n    {}\n  )", conjs.join("\n    ")
+      ),
+    } ;
+
+    let mut res = format!(
+      ";; Translated from SMV.\n\
+      (define-sys main\n  ({} )\n  {}\n  {}\n  ()\n)\n",
+      state, conj(init), conj(trans)
+    ) ;
+    let mut names = String::new() ;
+    for & (ref name, ref expr) in self.specs.iter() {
+      res.push_str(
+        & format!(
+          "(define-prop {} main\n  {}\n)\n",
+          quote(name), try!( self.term(expr, false, false, 0) )
+        )
+      ) ;
+      names.push_str( & format!(" {}", quote(name)) )
+    }
+    res.push_str( & format!("(verify main ({} ))\n", names) ) ;
+    Ok(res)
+  }
+}
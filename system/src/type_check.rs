@@ -8,18 +8,216 @@
 // except according to those terms.
 
 //! Type checking.
+//!
+//! Terms go through a [well-formedness check of their
+//! binders](fn.check_binders.html) before being type checked.
 
-use std::collections::HashMap ;
+use std::collections::{ HashMap, HashSet } ;
 
 use term::{
   BindMaker, AppMaker, OpMaker, Type, Sym, Term
 } ;
 use term::zip::* ;
 use term::real_term::Var as RVar ;
+use term::real_term::Term as RTerm ;
 use term::parsing::{ Spn, Spnd } ;
 
 use super::parse::Context ;
 
+/// Kinds of binders.
+#[derive(Clone, Copy)]
+enum Binder {
+  /// Let-binding.
+  Let,
+  /// Universal quantifier.
+  Forall,
+  /// Existential quantifier.
+  Exists,
+}
+impl Binder {
+  /// Description of the binder.
+  fn desc(& self) -> & 'static str {
+    match * self {
+      Binder::Let => "let",
+      Binder::Forall => "forall",
+      Binder::Exists => "exists",
+    }
+  }
+}
+
+/// Checks the binders of a term.
+struct BinderChecker<'a> {
+  /// Context, for function symbols.
+  ctxt: & 'a Context,
+  /// State variables.
+  state: HashSet<Sym>,
+  /// Arguments of the function the term is the body of.
+  sig: HashSet<Sym>,
+  /// Symbols bound by the enclosing binders, innermost last.
+  scopes: Vec< HashMap<Sym, Binder> >,
+  /// Symbols of the let-bindings being checked, which are not bound in their
+  /// own bindings.
+  pending: Vec< HashSet<Sym> >,
+}
+impl<'a> BinderChecker<'a> {
+  /// Binder of a symbol in the enclosing binders, if any.
+  fn bound(& self, sym: & Sym) -> Option<Binder> {
+    for scope in self.scopes.iter().rev() {
+      if let Some(binder) = scope.get(sym) {
+        return Some(* binder)
+      }
+    }
+    None
+  }
+
+  /// Checks the symbols bound by a binder: no duplicates, no shadowing.
+  fn check_new<'b, Syms: Iterator<Item = & 'b Sym>>(
+    & self, binder: Binder, syms: Syms
+  ) -> Result<HashMap<Sym, Binder>, String> {
+    let mut scope = HashMap::new() ;
+    for sym in syms {
+      if scope.insert( sym.clone(), binder ).is_some() {
+        return Err(
+          format!("symbol {} is bound twice by the same {}", sym, binder.desc())
+        )
+      }
+      if let Some(outer) = self.bound(sym) {
+        return Err(
+          format!(
+            "symbol {} bound by {} shadows {} bound by an enclosing {}",
+            sym, binder.desc(), sym, outer.desc()
+          )
+        )
+      }
+      let shadowed = if self.state.contains(sym) {
+        Some("state variable")
+      } else if self.sig.contains(sym) {
+        Some("argument")
+      } else if self.ctxt.get_callable(sym).is_some() {
+        Some("function symbol")
+      } else {
+        None
+      } ;
+      if let Some(shadowed) = shadowed {
+        return Err(
+          format!(
+            "symbol {} bound by {} shadows {} {}",
+            sym, binder.desc(), shadowed, sym
+          )
+        )
+      }
+    }
+    Ok(scope)
+  }
+
+  /// Checks the binders of a term.
+  fn check(& mut self, term: & Term) -> Result<(), String> {
+    match * term.get() {
+      RTerm::V(ref var) => match * var.get() {
+        RVar::Var(ref sym) => {
+          if self.bound(sym).is_some()
+          || self.sig.contains(sym)
+          || self.ctxt.get_callable(sym).is_some() {
+            Ok(())
+          } else if self.pending.iter().any( |syms| syms.contains(sym) ) {
+            Err(
+              format!(
+                "let-bound symbol {} is used in the bindings of its own let",
+                sym
+              )
+            )
+          } else {
+            Err( format!("unbound symbol {}", sym) )
+          }
+        },
+        RVar::SVar(_, _) => Ok(()),
+      },
+      RTerm::C(_) => Ok(()),
+      RTerm::Op(_, ref kids) | RTerm::App(_, ref kids) => {
+        for kid in kids.iter() {
+          try!( self.check(kid) )
+        }
+        Ok(())
+      },
+      RTerm::Forall(ref vars, ref body) |
+      RTerm::Exists(ref vars, ref body) => {
+        let binder = match * term.get() {
+          RTerm::Forall(_, _) => Binder::Forall,
+          _ => Binder::Exists,
+        } ;
+        if vars.is_empty() {
+          return Err(
+            format!("{} quantifies over no variable", binder.desc())
+          )
+        }
+        let scope = try!(
+          self.check_new( binder, vars.iter().map(|& (ref sym, _)| sym) )
+        ) ;
+        self.scopes.push(scope) ;
+        let res = self.check(body) ;
+        self.scopes.pop() ;
+        res.map_err(
+          |e| format!("{}
+in the body of a {}", e, binder.desc())
+        )
+      },
+      RTerm::Let(ref bindings, ref body) => {
+        if bindings.is_empty() {
+          return Err( "let binds no symbol".to_string() )
+        }
+        let scope = try!(
+          self.check_new(
+            Binder::Let, bindings.iter().map(|& (ref sym, _)| sym)
+          )
+        ) ;
+        // Bindings are parallel, they cannot mention each other.
+        self.pending.push( scope.keys().cloned().collect() ) ;
+        for & (ref sym, ref def) in bindings.iter() {
+          if let Err(e) = self.check(def) {
+            self.pending.pop() ;
+            return Err(
+              format!("{}
+in the definition of let-bound symbol {}", e, sym)
+            )
+          }
+        }
+        self.pending.pop() ;
+        self.scopes.push(scope) ;
+        let res = self.check(body) ;
+        self.scopes.pop() ;
+        res.map_err( |e| format!("{}
+in the body of a let", e) )
+      },
+    }
+  }
+}
+
+/// Checks the well-formedness of the binders of a term: no symbol bound twice
+/// by the same binder, no shadowing, no unbound symbol, and no empty binder.
+///
+/// Let-bindings are parallel: the definitions of a let cannot mention the
+/// symbols it binds.
+pub fn check_binders(
+  ctxt: & Context, term: & Term,
+  state: Option<& [ ( Spnd<Sym>, Spnd<Type> ) ]>,
+  sig: Option<& [ ( Spnd<Sym>, Spnd<Type> ) ]>,
+) -> Result<(), String> {
+  let syms = |args: Option<& [ ( Spnd<Sym>, Spnd<Type> ) ]>| match args {
+    None => HashSet::new(),
+    Some(args) => args.iter().map(
+      |& (ref sym, _)| sym.get().clone()
+    ).collect(),
+  } ;
+  let mut checker = BinderChecker {
+    ctxt: ctxt,
+    state: syms(state),
+    sig: syms(sig),
+    scopes: vec![],
+    pending: vec![],
+  } ;
+  checker.check(term)
+}
+
 /// Function passed to `fold` over terms for type checking.
 fn checker(
   context: & Context,
@@ -167,7 +365,7 @@ fn checker(
                   Err( (_, bla) ) => return Err( format!("{}", bla) ),
                 },
                 None => {
-                  if let Some( & (_, typ) ) = extract(sym, bindings) {
+                  if let Some( & (_, ref typ) ) = extract(sym, bindings) {
                     typ.clone()
                   } else {
                     if let Some(typ) = extract(sym, quantified) {
@@ -193,6 +391,7 @@ pub fn type_check(
   state: Option<& [ ( Spnd<Sym>, Spnd<Type> ) ]>,
   sig: Option<& [ ( Spnd<Sym>, Spnd<Type> ) ]>,
 ) -> Result<Type, String> {
+  try!( check_binders(ctxt, term, state, sig) ) ;
   let state = match state {
     None => None,
     Some(state) => {
@@ -236,6 +435,44 @@ pub mod test {
   use base::Callable ;
   use parse::{ Context, Res } ;
 
+  /// Checks that shadowing and self-referencing lets are rejected.
+  #[test]
+  fn binders() {
+    let factory = Factory::mk() ;
+    let context = Context::mk(factory.clone(), 1000) ;
+    let (x, y) = ( factory.sym("x"), factory.sym("y") ) ;
+    let x_term: Term = factory.var( x.clone() ) ;
+    let y_term: Term = factory.var( y.clone() ) ;
+    let zero: Term = factory.cst( Int::parse_bytes(b"0", 10).unwrap() ) ;
+
+    let ok = factory.forall(
+      vec![ (x.clone(), Type::Int) ],
+      factory.let_b(
+        vec![ (y.clone(), x_term.clone()) ],
+        factory.ge(y_term.clone(), zero.clone())
+      )
+    ) ;
+    assert!( super::check_binders(& context, & ok, None, None).is_ok() ) ;
+
+    let shadowing = factory.forall(
+      vec![ (x.clone(), Type::Int) ],
+      factory.exists(
+        vec![ (x.clone(), Type::Int) ], factory.ge(x_term.clone(), zero.clone())
+      )
+    ) ;
+    assert!(
+      super::check_binders(& context, & shadowing, None, None).is_err()
+    ) ;
+
+    let self_ref = factory.let_b(
+      vec![ (x.clone(), zero.clone()), (y.clone(), x_term.clone()) ],
+      factory.ge(y_term.clone(), zero.clone())
+    ) ;
+    assert!(
+      super::check_binders(& context, & self_ref, None, None).is_err()
+    ) ;
+  }
+
   /// Generates random terms to check the evaluator.
   // #[test]
   pub fn _rand_terms_fault_conf() {
@@ -370,7 +607,7 @@ pub mod test {
                 for t in vec![ Type::Bool, Type::Int, Type::Rat ] {
                   println!("| generating {} terms of type {}", term_cnt, t) ;
                   println!("| max depth is {}", depth) ;
-                  let terms = gen.generate(t, term_cnt, Some(depth)) ;
+                  let terms = gen.generate(t.clone(), term_cnt, Some(depth)) ;
                   println!("| > done ({})", terms.len()) ;
                   for term in terms {
                     println!("") ;
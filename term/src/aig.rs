@@ -0,0 +1,290 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! And-inverter graphs of propositional terms.
+//!
+//! A term is propositional if it only combines variables and boolean
+//! constants with the boolean operators, see
+//! [`is_propositional`](fn.is_propositional.html). An [`Aig`](struct.Aig.html)
+//! encodes propositional terms as and gates over *frames*, copies of the
+//! state variables. It can be written as a CNF in the DIMACS format for SAT
+//! solvers.
+//!
+//! Literals follow the AIGER convention: variable `v` is literal `2 * v`, its
+//! negation is `2 * v + 1`, and variable `0` is the constant `false`.
+
+use std::io ;
+use std::collections::HashMap ;
+
+use ::{ Sym, Term, State } ;
+use ::real_term::{ Term as RTerm, Var as RVar, Cst as RCst } ;
+
+/// A literal.
+pub type Lit = usize ;
+
+/// Literal `false`.
+pub const FALSE: Lit = 0 ;
+/// Literal `true`.
+pub const TRUE: Lit = 1 ;
+
+/// Negation of a literal.
+#[inline]
+pub fn neg(lit: Lit) -> Lit { lit ^ 1 }
+
+/// True iff a term is propositional: only variables, boolean constants and
+/// boolean operators, possibly under `let`s.
+///
+/// The types of the variables are not known here, it is up to the caller to
+/// check they are boolean.
+pub fn is_propositional(term: & Term) -> bool {
+  use Operator::* ;
+  match * term.get() {
+    RTerm::V(_) => true,
+    RTerm::C(ref cst) => match * cst.get() {
+      RCst::Bool(_) => true,
+      _ => false,
+    },
+    RTerm::Op(op, ref kids) => match op {
+      Eq | Ite | Not | And | Or | Impl | Xor | Distinct => kids.iter().all(
+        is_propositional
+      ),
+      _ => false,
+    },
+    RTerm::Let(ref bindings, ref body) => bindings.iter().all(
+      |& (_, ref def)| is_propositional(def)
+    ) && is_propositional(body),
+    RTerm::App(_, _) | RTerm::Forall(_, _) | RTerm::Exists(_, _) => false,
+  }
+}
+
+/// An and-inverter graph.
+pub struct Aig {
+  /// Number of variables, the constant included.
+  vars: usize,
+  /// Leaves of the graph: state variables at some frame.
+  leaves: HashMap<(Sym, usize), Lit>,
+  /// Definitions of the non-stateful variables.
+  defs: HashMap<Sym, Term>,
+  /// And gates, as the literal of the gate and the ones of its kids.
+  ands: Vec<(Lit, Lit, Lit)>,
+  /// Structural hashing of the and gates.
+  strash: HashMap<(Lit, Lit), Lit>,
+  /// Literals of the terms encoded so far, at some frame.
+  memo: HashMap<(Term, usize), Lit>,
+}
+impl Aig {
+  /// Creates an empty graph.
+  pub fn mk() -> Self {
+    Aig {
+      vars: 1,
+      leaves: HashMap::new(),
+      defs: HashMap::new(),
+      ands: vec![],
+      strash: HashMap::new(),
+      memo: HashMap::new(),
+    }
+  }
+
+  /// Number of variables, the constant excluded.
+  #[inline]
+  pub fn var_count(& self) -> usize { self.vars - 1 }
+  /// And gates, as the literal of the gate and the ones of its kids. Gates
+  /// come after their kids.
+  #[inline]
+  pub fn ands(& self) -> & [ (Lit, Lit, Lit) ] { & self.ands }
+
+  /// Literal of a state variable at some frame, created if needed.
+  pub fn leaf(& mut self, sym: & Sym, frame: usize) -> Lit {
+    let key = (sym.clone(), frame) ;
+    if let Some(lit) = self.leaves.get(& key) { return * lit }
+    let lit = self.fresh() ;
+    self.leaves.insert(key, lit) ;
+    lit
+  }
+  /// Literal of a state variable at some frame, if any.
+  #[inline]
+  pub fn get_leaf(& self, sym: & Sym, frame: usize) -> Option<Lit> {
+    self.leaves.get( & (sym.clone(), frame) ).cloned()
+  }
+
+  /// Defines a non-stateful variable, typically a local definition of a
+  /// system. Its definition is encoded wherever it appears.
+  pub fn define(& mut self, sym: Sym, def: Term) {
+    self.defs.insert(sym, def) ;
+    ()
+  }
+
+  /// A fresh variable.
+  fn fresh(& mut self) -> Lit {
+    let lit = 2 * self.vars ;
+    self.vars += 1 ;
+    lit
+  }
+
+  /// Conjunction of two literals.
+  pub fn and(& mut self, lhs: Lit, rhs: Lit) -> Lit {
+    if lhs == FALSE || rhs == FALSE || lhs == neg(rhs) { return FALSE }
+    if lhs == TRUE || lhs == rhs { return rhs }
+    if rhs == TRUE { return lhs }
+    let key = if lhs < rhs { (lhs, rhs) } else { (rhs, lhs) } ;
+    if let Some(lit) = self.strash.get(& key) { return * lit }
+    let lit = self.fresh() ;
+    self.ands.push( (lit, key.0, key.1) ) ;
+    self.strash.insert(key, lit) ;
+    lit
+  }
+  /// Disjunction of two literals.
+  #[inline]
+  pub fn or(& mut self, lhs: Lit, rhs: Lit) -> Lit {
+    let conj = self.and( neg(lhs), neg(rhs) ) ;
+    neg(conj)
+  }
+  /// Conjunction of some literals.
+  pub fn and_all(& mut self, lits: & [Lit]) -> Lit {
+    lits.iter().fold( TRUE, |acc, lit| self.and(acc, * lit) )
+  }
+  /// Disjunction of some literals.
+  pub fn or_all(& mut self, lits: & [Lit]) -> Lit {
+    lits.iter().fold( FALSE, |acc, lit| self.or(acc, * lit) )
+  }
+  /// Exclusive disjunction of two literals.
+  pub fn xor(& mut self, lhs: Lit, rhs: Lit) -> Lit {
+    let left = self.and( lhs, neg(rhs) ) ;
+    let right = self.and( neg(lhs), rhs ) ;
+    self.or(left, right)
+  }
+  /// If-then-else over literals.
+  pub fn ite(& mut self, cnd: Lit, thn: Lit, els: Lit) -> Lit {
+    let left = self.and(cnd, thn) ;
+    let right = self.and( neg(cnd), els ) ;
+    self.or(left, right)
+  }
+
+  /// Literal of a term at some frame: current state variables are at
+  /// `frame`, next state variables at `frame + 1`.
+  ///
+  /// Fails if the term is not propositional, if it has `let`s (see
+  /// [`let_expand`](../fn.let_expand.html)), or if it mentions a
+  /// non-stateful variable with no definition.
+  pub fn lit(& mut self, term: & Term, frame: usize) -> Result<Lit, String> {
+    use Operator::* ;
+    let key = (term.clone(), frame) ;
+    if let Some(lit) = self.memo.get(& key) { return Ok(* lit) }
+
+    let lit = match * term.get() {
+      RTerm::V(ref var) => match * var.get() {
+        RVar::SVar(ref sym, State::Curr) => self.leaf(sym, frame),
+        RVar::SVar(ref sym, State::Next) => self.leaf(sym, frame + 1),
+        RVar::Var(ref sym) => {
+          let def = self.defs.get(sym).cloned() ;
+          match def {
+            Some(def) => try!( self.lit(& def, frame) ),
+            None => return Err(
+              format!("[Aig::lit] undefined variable `{}`", sym)
+            ),
+          }
+        },
+      },
+      RTerm::C(ref cst) => match * cst.get() {
+        RCst::Bool(true) => TRUE,
+        RCst::Bool(false) => FALSE,
+        _ => return Err(
+          format!("[Aig::lit] non-boolean constant `{}`", term)
+        ),
+      },
+      RTerm::Op(op, ref kids) => {
+        let mut lits = Vec::with_capacity( kids.len() ) ;
+        for kid in kids.iter() {
+          lits.push( try!( self.lit(kid, frame) ) )
+        }
+        match (op, lits.len()) {
+          (Not, 1) => neg(lits[0]),
+          (Ite, 3) => self.ite(lits[0], lits[1], lits[2]),
+          (And, _) => self.and_all(& lits),
+          (Or, _) => self.or_all(& lits),
+          // Right associative.
+          (Impl, len) if len > 0 => {
+            let mut res = lits[len - 1] ;
+            for lhs in lits[0 .. len - 1].iter().rev() {
+              res = self.or( neg(* lhs), res )
+            }
+            res
+          },
+          (Xor, len) if len > 0 => {
+            let mut res = lits[0] ;
+            for rhs in lits[1..].iter() { res = self.xor(res, * rhs) }
+            res
+          },
+          (Eq, _) => {
+            let mut eqs = Vec::with_capacity( lits.len() ) ;
+            for pair in lits.windows(2) {
+              let xor = self.xor(pair[0], pair[1]) ;
+              eqs.push( neg(xor) )
+            }
+            self.and_all(& eqs)
+          },
+          // Three booleans cannot be pairwise distinct.
+          (Distinct, 2) => self.xor(lits[0], lits[1]),
+          (Distinct, len) => if len < 2 { TRUE } else { FALSE },
+          _ => return Err(
+            format!("[Aig::lit] unexpected application `{}`", term)
+          ),
+        }
+      },
+      RTerm::Let(_, _) => return Err(
+        format!("[Aig::lit] unexpected let-binding `{}`", term)
+      ),
+      RTerm::App(_, _) | RTerm::Forall(_, _) | RTerm::Exists(_, _) => {
+        return Err( format!("[Aig::lit] non-propositional term `{}`", term) )
+      },
+    } ;
+
+    self.memo.insert(key, lit) ;
+    Ok(lit)
+  }
+
+  /// Writes the graph as a CNF in the DIMACS format, with the Tseitin
+  /// encoding of its and gates, asserting some literals.
+  ///
+  /// DIMACS variable `v` is variable `v` of the graph.
+  pub fn write_dimacs<W: io::Write>(
+    & self, w: & mut W, roots: & [Lit]
+  ) -> io::Result<()> {
+    let roots: Vec<Lit> = roots.iter().cloned().filter(
+      |root| * root != TRUE
+    ).collect() ;
+    try!(
+      write!(
+        w, "p cnf {} {}\n",
+        self.var_count(), 3 * self.ands.len() + roots.len()
+      )
+    ) ;
+    for & (gate, lhs, rhs) in self.ands.iter() {
+      let (gate, lhs, rhs) = (dimacs(gate), dimacs(lhs), dimacs(rhs)) ;
+      try!( write!(w, "{} {} 0\n", - gate, lhs) ) ;
+      try!( write!(w, "{} {} 0\n", - gate, rhs) ) ;
+      try!( write!(w, "{} {} {} 0\n", gate, - lhs, - rhs) )
+    }
+    for root in roots.into_iter() {
+      if root == FALSE {
+        try!( write!(w, "0\n") )
+      } else {
+        try!( write!(w, "{} 0\n", dimacs(root)) )
+      }
+    }
+    Ok(())
+  }
+}
+
+/// DIMACS literal of a literal that is not a constant.
+#[inline]
+pub fn dimacs(lit: Lit) -> i64 {
+  let var = (lit >> 1) as i64 ;
+  if lit & 1 == 1 { - var } else { var }
+}
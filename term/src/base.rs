@@ -27,6 +27,7 @@ pub enum SymPrintStyle {
 
 /// A state is either current or next.
 #[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum State {
   /// Current state.
   Curr,
@@ -77,6 +78,7 @@ pub trait StateWritable<S: SymWritable, Svw: SVarWriter<S>> {
 #[derive(
   Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy
 )]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Offset { offset: i16 }
 
 impl Offset {
@@ -143,10 +145,11 @@ pub struct Offset2 {
 }
 
 impl Offset2 {
-  // /// Creates an `Offset2`. Sometimes necessary, but prefer `init`.
-  // pub fn mk(curr: Offset, next: Offset) -> Self {
-  //   Offset2 { curr: curr, next: next }
-  // }
+  /// Creates an `Offset2`. Sometimes necessary, for instance to relate two
+  /// states that are not consecutive, but prefer `init`.
+  pub fn mk(curr: Offset, next: Offset) -> Self {
+    Offset2 { curr: curr, next: next }
+  }
 
   /// Initial two-state offset.
   pub fn init() -> Self {
@@ -387,4 +390,62 @@ impl<T: Hash + Eq + Clone> Mkable for Arc<RwLock<HashConsign<T>>>{
       RwLock::new( HashConsign::empty() )
     )
   }
+}
+
+
+/// Approximate memory usage of a data structure.
+///
+/// Sizes only count the values stored and the space a hash table needs for
+/// them, not what the values point to.
+#[derive(Debug, Clone)]
+pub struct MemUsage {
+  /// Name of the data structure.
+  pub name: String,
+  /// Number of entries.
+  pub entries: usize,
+  /// Approximate size in bytes.
+  pub bytes: usize,
+}
+impl MemUsage {
+  /// Usage of `entries` values of type `T`.
+  pub fn of<T>(name: & str, entries: usize) -> Self {
+    MemUsage {
+      name: name.to_string(),
+      entries: entries,
+      bytes: entries * ::std::mem::size_of::<T>(),
+    }
+  }
+  /// Usage of a hash table of `entries` values of type `T`. Accounts for the
+  /// hash of each entry.
+  pub fn of_table<T>(name: & str, entries: usize) -> Self {
+    MemUsage {
+      name: name.to_string(),
+      entries: entries,
+      bytes: entries * (
+        ::std::mem::size_of::<T>() + ::std::mem::size_of::<u64>()
+      ),
+    }
+  }
+  /// Usage of a hash consign.
+  ///
+  /// Each entry stores the value as a key, and the hash consed value which
+  /// points to its own copy of the value.
+  pub fn of_consign<T: Hash + Eq + Clone>(
+    name: & str, consign: & HConsign<T>
+  ) -> Self {
+    let entries = match consign.read() {
+      Ok(consign) => consign.len(),
+      Err(_) => 0,
+    } ;
+    let mut res = Self::of_table::<(T, HConsed<T>)>(name, entries) ;
+    res.bytes += entries * ::std::mem::size_of::<T>() ;
+    res
+  }
+}
+impl fmt::Display for MemUsage {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    write!(
+      fmt, "{}: {} entries, ~{} bytes", self.name, self.entries, self.bytes
+    )
+  }
 }
\ No newline at end of file
@@ -28,22 +28,104 @@ pub enum RealCst {
   Int(typ::Int),
   /// Rational constant.
   Rat(typ::Rat),
+  /// Bitvector constant: width and unsigned value, smaller than `2^width`.
+  BitVec(usize, typ::Int),
+  /// Array constant: index type, default value and the values stored at
+  /// some indices.
+  ///
+  /// Entries are sorted by index and never store the default value, so that
+  /// equal arrays have the same representation. Use
+  /// [`select`](#method.select) and [`store`](#method.store).
+  Array(typ::Type, Box<RealCst>, Vec<(RealCst, RealCst)>),
 }
 
 impl RealCst {
+  /// Decimal approximation of a rational that is not an integer, with
+  /// `precision` digits after the point. `None` for other constants.
+  pub fn approx(& self, precision: usize) -> Option<String> {
+    match * self {
+      Rat(ref r) if ! r.is_integer() => Some( decimal(r, precision) ),
+      _ => None,
+    }
+  }
   /// The type of a `RealCst`.
   pub fn typ(& self) -> typ::Type {
     match * self {
       Bool(_) => typ::Type::Bool,
       Int(_) => typ::Type::Int,
       Rat(_) => typ::Type::Rat,
+      BitVec(width, _) => typ::Type::BitVec(width),
+      Array(ref idx, ref default, _) => typ::Type::Array(
+        Box::new( idx.clone() ), Box::new( default.typ() )
+      ),
+    }
+  }
+
+  /// Value of an array at some index. `None` if not an array.
+  pub fn select(& self, idx: & Self) -> Option<Self> {
+    match * self {
+      Array(_, ref default, ref entries) => Some(
+        match entries.binary_search_by( |& (ref i, _)| i.cmp(idx) ) {
+          Ok(pos) => entries[pos].1.clone(),
+          Err(_) => (** default).clone(),
+        }
+      ),
+      _ => None,
+    }
+  }
+
+  /// Array equal to this one except at some index. `None` if not an array.
+  pub fn store(& self, idx: Self, val: Self) -> Option<Self> {
+    match * self {
+      Array(ref idx_typ, ref default, ref entries) => {
+        let mut entries = entries.clone() ;
+        let pos = entries.binary_search_by( |& (ref i, _)| i.cmp(& idx) ) ;
+        match pos {
+          Ok(pos) => if val == ** default {
+            entries.remove(pos) ; ()
+          } else {
+            entries[pos].1 = val
+          },
+          Err(pos) => if val != ** default {
+            entries.insert(pos, (idx, val))
+          },
+        }
+        Some( Array(idx_typ.clone(), default.clone(), entries) )
+      },
+      _ => None,
+    }
+  }
+
+  /// Writes a constant in SMT-LIB.
+  fn write_smt(& self, writer: & mut io::Write) -> io::Result<()> {
+    match * self {
+      Bool(ref b) => write!( writer, "{}", b ),
+      Int(ref i) => write!( writer, "{}", i ),
+      Rat(ref r) => write!( writer, "(/ {} {})", r.numer(), r.denom() ),
+      BitVec(width, ref v) => write!( writer, "{}", bv_to_string(width, v) ),
+      Array(_, ref default, ref entries) => {
+        for _ in entries.iter() {
+          try!( write!(writer, "(store ") )
+        }
+        try!( write!(writer, "((as const {}) ", self.typ()) ) ;
+        try!( default.write_smt(writer) ) ;
+        try!( write!(writer, ")") ) ;
+        for & (ref idx, ref val) in entries.iter() {
+          try!( write!(writer, " ") ) ;
+          try!( idx.write_smt(writer) ) ;
+          try!( write!(writer, " ") ) ;
+          try!( val.write_smt(writer) ) ;
+          try!( write!(writer, ")") )
+        }
+        Ok(())
+      },
     }
   }
 
   /// Adds two constants if possible.
   pub fn add(& self, rhs: & Self) -> Result<Self, Self> {
     match * self {
-      Bool(_) => Err(self.clone()),
+      Bool(_) | BitVec(_, _) | Array(_, _, _) => Err(self.clone()),
       Int(ref lhs) => match * rhs {
         Int(ref rhs) => Ok( Int(lhs + rhs) ),
         _ => Err(rhs.clone()),
@@ -58,7 +140,7 @@ impl RealCst {
   /// Substracts two constants if possible.
   pub fn sub(& self, rhs: & Self) -> Result<Self, Self> {
     match * self {
-      Bool(_) => Err(self.clone()),
+      Bool(_) | BitVec(_, _) | Array(_, _, _) => Err(self.clone()),
       Int(ref lhs) => match * rhs {
         Int(ref rhs) => Ok( Int(lhs - rhs) ),
         _ => Err(rhs.clone()),
@@ -73,7 +155,7 @@ impl RealCst {
   /// Multiplies two constants if possible.
   pub fn mul(& self, rhs: & Self) -> Result<Self, Self> {
     match * self {
-      Bool(_) => Err(self.clone()),
+      Bool(_) | BitVec(_, _) | Array(_, _, _) => Err(self.clone()),
       Int(ref lhs) => match * rhs {
         Int(ref rhs) => Ok( Int(lhs * rhs) ),
         _ => Err(rhs.clone()),
@@ -109,10 +191,50 @@ impl RealCst {
     }
   }
 
+  /// Integer modulo of two constants if possible. The result is
+  /// non-negative, as in SMT-LIB.
+  pub fn modulo(& self, in_rhs: & Self) -> Result<Self, Self> {
+    use num::traits::{ Zero, Signed } ;
+    use num::Integer ;
+    match * self {
+      Int(ref lhs) => match * in_rhs {
+        Int(ref rhs) => if rhs.is_zero() {
+          Err(in_rhs.clone())
+        } else {
+          Ok( Int( lhs.mod_floor( & rhs.abs() ) ) )
+        },
+        _ => Err(in_rhs.clone()),
+      },
+      _ => Err(self.clone()),
+    }
+  }
+
+  /// Integer remainder of two constants if possible. The result is the
+  /// modulo, negated if the divisor is negative.
+  pub fn rem(& self, in_rhs: & Self) -> Result<Self, Self> {
+    use num::traits::Signed ;
+    match self.modulo(in_rhs) {
+      Ok( Int(res) ) => match * in_rhs {
+        Int(ref rhs) if rhs.is_negative() => Ok( Int(- res) ),
+        _ => Ok( Int(res) ),
+      },
+      res => res,
+    }
+  }
+
+  /// Absolute value of a constant if possible.
+  pub fn abs(& self) -> Result<Self, Self> {
+    use num::traits::Signed ;
+    match * self {
+      Int(ref v) => Ok( Int( v.abs() ) ),
+      _ => Err(self.clone()),
+    }
+  }
+
   /// Negates a constant if possible.
   pub fn neg(& self) -> Result<Self, Self> {
     match * self {
-      Bool(_) => Err(self.clone()),
+      Bool(_) | BitVec(_, _) | Array(_, _, _) => Err(self.clone()),
       Int(ref v) => Ok( Int(- v) ),
       Rat(ref v) => Ok( Rat(- v) ),
     }
@@ -125,21 +247,58 @@ impl fmt::Display for RealCst {
       Bool(ref b) => write!(fmt, "{}", b),
       Int(ref i) => write!(fmt, "{}", i),
       Rat(ref r) => write!(fmt, "{}", r),
+      BitVec(width, ref v) => write!(fmt, "{}", bv_to_string(width, v)),
+      Array(_, ref default, ref entries) => {
+        for _ in entries.iter() {
+          try!( write!(fmt, "(store ") )
+        }
+        try!( write!(fmt, "((as const {}) {})", self.typ(), default) ) ;
+        for & (ref idx, ref val) in entries.iter() {
+          try!( write!(fmt, " {} {})", idx, val) )
+        }
+        Ok(())
+      },
     }
   }
 }
 
+/// Decimal representation of a rational, rounded to the nearest with
+/// `precision` digits after the point.
+fn decimal(r: & typ::Rat, precision: usize) -> String {
+  use num::{ One, Zero, Signed } ;
+  let ten = typ::Int::parse_bytes(b"10", 10).unwrap() ;
+  let mut scale = typ::Int::one() ;
+  for _ in 0..precision { scale = scale * & ten }
+  let scaled = (
+    r.abs() * typ::Rat::from_integer(scale)
+  ).round().to_integer() ;
+  let mut digits = format!("{}", scaled) ;
+  while digits.len() <= precision { digits.insert(0, '0') }
+  if precision > 0 {
+    let point = digits.len() - precision ;
+    digits.insert(point, '.')
+  }
+  if r.is_negative() && ! scaled.is_zero() { digits.insert(0, '-') }
+  digits
+}
+
+/// SMT-LIB binary representation of a bitvector constant.
+fn bv_to_string(width: usize, v: & typ::Int) -> String {
+  let digits = v.to_str_radix(2) ;
+  let mut s = String::with_capacity(width + 2) ;
+  s.push_str("#b") ;
+  for _ in digits.len() .. width { s.push('0') }
+  s.push_str(& digits) ;
+  s
+}
+
 /// Hash consed constant.
 pub type Cst = HConsed<RealCst> ;
 
 impl Writable for Cst {
   #[inline(always)]
   fn write(& self, writer: & mut io::Write) -> io::Result<()> {
-    match * self.get() {
-      Bool(ref b) => write!( writer, "{}", b ),
-      Int(ref i) => write!( writer, "{}", i ),
-      Rat(ref r) => write!( writer, "(/ {} {})", r.numer(), r.denom() ),
-    }
+    self.get().write_smt(writer)
   }
 }
 
@@ -175,6 +334,11 @@ impl ConstMaker<typ::Rat> for CstConsign {
     self.mk( Rat(r) )
   }
 }
+impl ConstMaker<(usize, typ::Int)> for CstConsign {
+  fn constant(& self, (width, v): (usize, typ::Int)) -> Cst {
+    self.mk( BitVec(width, v) )
+  }
+}
 impl ConstMaker<RealCst> for CstConsign {
   fn constant(& self, cst: RealCst) -> Cst {
     self.mk( cst )
@@ -16,7 +16,7 @@ use nom::IResult ;
 
 use rsmt2::ParseSmt2 ;
 
-use base::{ Mkable, State, Offset, Offset2, Smt2Offset } ;
+use base::{ Mkable, State, Offset, Offset2, Smt2Offset, MemUsage } ;
 use typ::{ Type, Bool, Int, Rat } ;
 use sym::{ SymConsign, Sym, SymMaker } ;
 use cst::{ RealCst, Cst, CstConsign } ;
@@ -117,6 +117,26 @@ impl Factory {
     }
   }
 
+  /// Approximate memory usage of the hash consigns and type maps.
+  pub fn mem_usage(& self) -> Vec<MemUsage> {
+    vec![
+      MemUsage::of_consign("symbols", & self.sym),
+      MemUsage::of_consign("variables", & self.var),
+      MemUsage::of_consign("constants", & self.cst),
+      MemUsage::of_consign("terms", & self.term),
+      MemUsage::of_table::<((Sym, Term), Type)>(
+        "scoped types", self.scoped_types.read().map(|m| m.len()).unwrap_or(0)
+      ),
+      MemUsage::of_table::<(Term, Type)>(
+        "unscoped types",
+        self.unscoped_types.read().map(|m| m.len()).unwrap_or(0)
+      ),
+      MemUsage::of_table::<(Sym, Type)>(
+        "function types", self.fun_types.read().map(|m| m.len()).unwrap_or(0)
+      ),
+    ]
+  }
+
   /// An iterator over the constants in the factory.
   #[inline]
   pub fn cst_fold<
@@ -130,7 +150,7 @@ impl Factory {
     & self, sym: Sym, typ: Type
   ) -> Res<()> {
     let sym_bak = sym.clone() ;
-    match self.fun_types.write().unwrap().insert( sym, typ ) {
+    match self.fun_types.write().unwrap().insert( sym, typ.clone() ) {
       Some(t) if t != typ => Err(
         format!(
           "trying to redefine type of function {} from {} to {}",
@@ -149,7 +169,9 @@ impl Factory {
     match sym {
       Some(sym) => {
         let sym_bak = sym.clone() ;
-        match self.scoped_types.write().unwrap().insert( (sym, term), typ ) {
+        match self.scoped_types.write().unwrap().insert(
+          (sym, term), typ.clone()
+        ) {
           Some(t) if t != typ => Err(
             format!(
               "trying to redefine type of {}::{} from {} to {}",
@@ -159,7 +181,9 @@ impl Factory {
           _ => Ok(())
         }
       },
-      None => match self.unscoped_types.write().unwrap().insert( term, typ ) {
+      None => match self.unscoped_types.write().unwrap().insert(
+        term, typ.clone()
+      ) {
         Some(t) if t != typ => Err(
           format!(
             "trying to redefine type of {} from {} to {}",
@@ -187,7 +211,7 @@ impl Factory {
       },
       Some(state) => {
         let var: Term = self.mk_var(v) ;
-        try!( self.set_type_unsafe(sym.clone(), var.clone(), typ) ) ;
+        try!( self.set_type_unsafe(sym.clone(), var.clone(), typ.clone()) ) ;
         let var: Term = try!(
           match state {
             // Neither of these two can be an error.
@@ -218,7 +242,7 @@ impl Factory {
         match self.scoped_types.read().unwrap().get(
           & (scope.clone(), term.clone())
         ) {
-          Some(typ) => Ok(* typ),
+          Some(typ) => Ok( typ.clone() ),
           None => Err(
             format!(
               "can't type unknown term {} under scope {}", term, scope
@@ -228,7 +252,7 @@ impl Factory {
       },
       None => {
         match self.unscoped_types.read().unwrap().get( term ) {
-          Some(typ) => Ok(* typ),
+          Some(typ) => Ok( typ.clone() ),
           None => Err(
             format!("can't type unknown term {}", term).into()
           ),
@@ -397,6 +421,36 @@ impl Factory {
     self.op(Operator::Div, vec![ lhs, rhs ])
   }
 
+  /// Creates an integer modulo.
+  pub fn modulo(& self, lhs: Term, rhs: Term) -> Term {
+    self.op(Operator::Mod, vec![ lhs, rhs ])
+  }
+
+  /// Creates an integer remainder.
+  pub fn rem(& self, lhs: Term, rhs: Term) -> Term {
+    self.op(Operator::Rem, vec![ lhs, rhs ])
+  }
+
+  /// Creates an integer absolute value.
+  pub fn abs(& self, term: Term) -> Term {
+    self.op(Operator::Abs, vec![ term ])
+  }
+
+  /// Creates a conversion from Int to Real.
+  pub fn to_real(& self, term: Term) -> Term {
+    self.op(Operator::ToReal, vec![ term ])
+  }
+
+  /// Creates a conversion from Real to Int.
+  pub fn to_int(& self, term: Term) -> Term {
+    self.op(Operator::ToInt, vec![ term ])
+  }
+
+  /// Creates an integrality test.
+  pub fn is_int(& self, term: Term) -> Term {
+    self.op(Operator::IsInt, vec![ term ])
+  }
+
   /// Creates a less than or equal.
   pub fn le(& self, lhs: Term, rhs: Term) -> Term {
     self.op(Operator::Le, vec![ lhs, rhs])
@@ -424,6 +478,24 @@ impl Factory {
     ::term::eval::eval(& self, term, off, model, scope)
   }
 
+  /// Evaluates some terms in the same model, sharing the evaluation of
+  /// their common subterms.
+  pub fn eval_all(
+    & self, terms: & [Term], off: & Offset2, model: & ::Model, scope: Sym
+  ) -> Res< Vec<Cst> > {
+    ::term::eval::eval_all(& self, terms, off, model, scope)
+  }
+
+  /// Partially evaluates a term.
+  ///
+  /// Variables that are not in the model stay symbolic. The result is the
+  /// residual term, constant iff the model decides the value of the term.
+  pub fn partial_eval(
+    & self, term: & Term, off: & Offset2, model: & ::Model
+  ) -> Res<Term> {
+    ::term::eval::partial_eval(& self, term, off, model)
+  }
+
   /// Evaluates a term to a bool value.
   pub fn eval_bool(
     & self, term: & Term, off: & Offset2, model: & ::Model, scope: Sym
@@ -438,6 +510,12 @@ impl Factory {
         Rat(ref r) => Err(
           format!("[eval_bool] got rational value `{}`", r).into()
         ),
+        BitVec(_, _) => Err(
+          format!("[eval_bool] got bitvector value `{}`", val.get()).into()
+        ),
+        Array(_, _, _) => Err(
+          format!("[eval_bool] got array value `{}`", val.get()).into()
+        ),
       },
       Err(e) => Err(e),
     }
@@ -457,6 +535,12 @@ impl Factory {
         Rat(ref r) => Err(
           format!("[eval_int] got rational value `{}`", r).into()
         ),
+        BitVec(_, _) => Err(
+          format!("[eval_int] got bitvector value `{}`", val.get()).into()
+        ),
+        Array(_, _, _) => Err(
+          format!("[eval_int] got array value `{}`", val.get()).into()
+        ),
       },
       Err(e) => Err(e),
     }
@@ -476,6 +560,12 @@ impl Factory {
         Int(ref i) => Err(
           format!("[eval_int] got integer value `{}`", i).into()
         ),
+        BitVec(_, _) => Err(
+          format!("[eval_int] got bitvector value `{}`", val.get()).into()
+        ),
+        Array(_, _, _) => Err(
+          format!("[eval_int] got array value `{}`", val.get()).into()
+        ),
       },
       Err(e) => Err(e),
     }
@@ -522,6 +612,12 @@ impl CstMaker<Rat, Term> for Factory {
     self.term.cst( self.cst.constant(cst) )
   }
 }
+impl CstMaker<(usize, Int), Term> for Factory {
+  fn cst(& self, cst: (usize, Int)) -> Term {
+    use cst::ConstMaker ;
+    self.term.cst( self.cst.constant(cst) )
+  }
+}
 impl CstMaker<Bool, Cst> for Factory {
   fn cst(& self, cst: Bool) -> Cst {
     use cst::ConstMaker ;
@@ -541,6 +637,18 @@ impl CstMaker<Rat, Cst> for Factory {
   }
 }
 
+impl CstMaker<(usize, Int), Cst> for Factory {
+  fn cst(& self, cst: (usize, Int)) -> Cst {
+    use cst::ConstMaker ;
+    self.cst.constant(cst)
+  }
+}
+impl CstMaker<RealCst, Cst> for Factory {
+  fn cst(& self, cst: RealCst) -> Cst {
+    self.mk_rcst(cst)
+  }
+}
+
 
 /* |===| Factory can create variables. */
 
@@ -163,6 +163,13 @@ fn rand_arith_to_arith<Rand: Rng + Sized>(rng: & mut Rand) -> Operator {
   ops[ rand_int(rng, ops.len()) ]
 }
 
+/// Returns a random bitvector to bitvector operator preserving the width.
+fn rand_bv_to_bv<Rand: Rng + Sized>(rng: & mut Rand) -> Operator {
+  use Operator::* ;
+  let ops = vec![ BvAnd, BvOr, BvXor, BvAdd, BvSub, BvMul ] ;
+  ops[ rand_int(rng, ops.len()) ]
+}
+
 
 /// Constructive zipper step.
 enum Step {
@@ -379,7 +386,7 @@ impl<'a, Rand: 'a + Rng + Sized> Zip<'a, Rand> {
   /// Adds a term to the map from types to terms if we're not under a let
   /// binding.
   fn remember(& mut self, term: Term) -> Option<bool> {
-    let typ = self.typ ;
+    let typ = self.typ.clone() ;
     // Remember only if we're not under a let binding.
     for & (ref map, _) in self.bindings.iter() {
       for (_, ref vec) in map.iter() {
@@ -429,10 +436,17 @@ impl<'a, Rand: 'a + Rng + Sized> Zip<'a, Rand> {
   /// Goes down, arith version.
   fn arith_down(& mut self) {
     let op = rand_arith_to_arith(& mut self.rng) ;
-    let typ = self.typ ;
+    let typ = self.typ.clone() ;
     self.push( Step::Op(op, typ, 0, Vec::with_capacity(1)) )
   }
 
+  /// Goes down, bitvector version.
+  fn bv_down(& mut self) {
+    let op = rand_bv_to_bv(& mut self.rng) ;
+    let typ = self.typ.clone() ;
+    self.push( Step::Op(op, typ, 0, Vec::with_capacity(2)) )
+  }
+
   /// Builds a random term.
   pub fn build(& mut self, max_depth: Option<usize>) -> Term {
     self.max_depth = max_depth ;
@@ -460,7 +474,7 @@ impl<'a, Rand: 'a + Rng + Sized> Zip<'a, Rand> {
 
         // Generate an if then else with 5% chance.
         if down_allowed && rand_bool(& mut self.rng, 5) {
-          let typ = self.typ ;
+          let typ = self.typ.clone() ;
           self.push( Ite0(typ) ) ;
           self.typ = Type::Bool
         } else {
@@ -469,7 +483,7 @@ impl<'a, Rand: 'a + Rng + Sized> Zip<'a, Rand> {
           if ! down_allowed || (
             ! self.at_top() && rand_bool(& mut self.rng, 70)
           ) {
-            let typ = self.typ ;
+            let typ = self.typ.clone() ;
             let (term, depth) = self.get_term(typ) ;
             match self.up(term, depth) {
               Some(term) => return term,
@@ -481,6 +495,17 @@ impl<'a, Rand: 'a + Rng + Sized> Zip<'a, Rand> {
             match self.typ {
               Type::Bool => self.bool_down(),
               Type::Int | Type::Rat => self.arith_down(),
+              Type::BitVec(_) => self.bv_down(),
+              // No operator of a single type produces arrays, reusing an
+              // existing term.
+              Type::Array(_, _) => {
+                let typ = self.typ.clone() ;
+                let (term, depth) = self.get_term(typ) ;
+                match self.up(term, depth) {
+                  Some(term) => return term,
+                  None => (),
+                }
+              },
             }
           }
         }
@@ -0,0 +1,318 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stable structural hashes.
+//!
+//! `Hash` on hash consed values relies on the unique identifier given by the
+//! consign, which depends on the order in which values are created. The
+//! hashes computed here only depend on the structure of the values: they are
+//! the same from one run to the other and can be used as keys for caches
+//! stored on disk.
+//!
+//! The hash function is 64-bit FNV-1a.
+
+use std::sync::Arc ;
+use std::collections::HashMap ;
+
+use base::{ State, Writable } ;
+use typ::Type ;
+use sym::Sym ;
+use var::{ Var, RealVar } ;
+use cst::{ Cst, RealCst } ;
+use term::{ Operator, Term, RealTerm, STerm } ;
+use parser::Spnd ;
+
+/// FNV-1a offset basis.
+const FNV_OFFSET: u64 = 0xcbf29ce484222325 ;
+/// FNV-1a prime.
+const FNV_PRIME: u64 = 0x100000001b3 ;
+
+/// Computes stable structural hashes.
+///
+/// Remembers the hashes of the terms it has seen so that shared sub-terms
+/// are only hashed once.
+pub struct StableHasher {
+  /// Current state of the hash.
+  state: u64,
+  /// Hashes of the terms already hashed.
+  terms: HashMap<Term, u64>,
+}
+impl StableHasher {
+  /// Creates a new hasher.
+  pub fn mk() -> Self {
+    StableHasher { state: FNV_OFFSET, terms: HashMap::new() }
+  }
+  /// The hash of everything written so far.
+  #[inline]
+  pub fn finish(& self) -> u64 { self.state }
+  /// Writes some bytes.
+  #[inline]
+  pub fn write(& mut self, bytes: & [u8]) {
+    for byte in bytes {
+      self.state = (self.state ^ (* byte as u64)).wrapping_mul(FNV_PRIME)
+    }
+  }
+  /// Writes a byte.
+  #[inline]
+  pub fn write_u8(& mut self, byte: u8) {
+    self.write(& [byte])
+  }
+  /// Writes a `u64`, little-endian.
+  #[inline]
+  pub fn write_u64(& mut self, n: u64) {
+    let mut bytes = [0u8 ; 8] ;
+    for (i, byte) in bytes.iter_mut().enumerate() {
+      * byte = (n >> (8 * i)) as u8
+    }
+    self.write(& bytes)
+  }
+  /// Writes a `usize` as a `u64`.
+  #[inline]
+  pub fn write_usize(& mut self, n: usize) {
+    self.write_u64(n as u64)
+  }
+  /// Writes a string, prefixed by its length so that consecutive strings
+  /// cannot be confused.
+  #[inline]
+  pub fn write_str(& mut self, s: & str) {
+    self.write_usize( s.len() ) ;
+    self.write( s.as_bytes() )
+  }
+  /// Writes a sequence of values, prefixed by its length.
+  pub fn write_all<'a, T: 'a + StableHash, Iter: Iterator<Item=& 'a T>>(
+    & mut self, len: usize, elems: Iter
+  ) {
+    self.write_usize(len) ;
+    for elem in elems { elem.stable_hash_into(self) }
+  }
+  /// Writes a set of values: the result does not depend on the order of the
+  /// elements.
+  pub fn write_set<'a, T: 'a + StableHash, Iter: Iterator<Item=& 'a T>>(
+    & mut self, elems: Iter
+  ) {
+    let mut hashes = Vec::with_capacity(7) ;
+    for elem in elems {
+      hashes.push( self.sub_hash(elem) )
+    }
+    hashes.sort() ;
+    self.write_usize( hashes.len() ) ;
+    for hash in hashes { self.write_u64(hash) }
+  }
+
+  /// Hash of a value on its own, sharing the memory of the hasher.
+  pub fn sub_hash<T: StableHash + ?Sized>(& mut self, val: & T) -> u64 {
+    let saved = self.state ;
+    self.state = FNV_OFFSET ;
+    val.stable_hash_into(self) ;
+    let res = self.state ;
+    self.state = saved ;
+    res
+  }
+
+  /// Hash of a term, memoized.
+  fn term_hash(& mut self, term: & Term) -> u64 {
+    if let Some(hash) = self.terms.get(term) {
+      return * hash
+    }
+    let saved = self.state ;
+    self.state = FNV_OFFSET ;
+    match * term.get() {
+      RealTerm::V(ref var) => {
+        self.write_u8(0) ;
+        var.stable_hash_into(self)
+      },
+      RealTerm::C(ref cst) => {
+        self.write_u8(1) ;
+        cst.stable_hash_into(self)
+      },
+      RealTerm::Op(ref op, ref kids) => {
+        self.write_u8(2) ;
+        op.stable_hash_into(self) ;
+        self.write_all(kids.len(), kids.iter())
+      },
+      RealTerm::Forall(ref bindings, ref body) => {
+        self.write_u8(3) ;
+        self.write_usize( bindings.len() ) ;
+        for & (ref sym, ref typ) in bindings.iter() {
+          sym.stable_hash_into(self) ;
+          typ.stable_hash_into(self)
+        }
+        body.stable_hash_into(self)
+      },
+      RealTerm::Exists(ref bindings, ref body) => {
+        self.write_u8(4) ;
+        self.write_usize( bindings.len() ) ;
+        for & (ref sym, ref typ) in bindings.iter() {
+          sym.stable_hash_into(self) ;
+          typ.stable_hash_into(self)
+        }
+        body.stable_hash_into(self)
+      },
+      RealTerm::Let(ref bindings, ref body) => {
+        self.write_u8(5) ;
+        self.write_usize( bindings.len() ) ;
+        for & (ref sym, ref term) in bindings.iter() {
+          sym.stable_hash_into(self) ;
+          term.stable_hash_into(self)
+        }
+        body.stable_hash_into(self)
+      },
+      RealTerm::App(ref sym, ref kids) => {
+        self.write_u8(6) ;
+        sym.stable_hash_into(self) ;
+        self.write_all(kids.len(), kids.iter())
+      },
+    }
+    let hash = self.state ;
+    self.state = saved ;
+    self.terms.insert(term.clone(), hash) ;
+    hash
+  }
+}
+
+/// Values with a stable structural hash.
+pub trait StableHash {
+  /// Writes the structure of the value in a hasher.
+  fn stable_hash_into(& self, hasher: & mut StableHasher) ;
+  /// Stable structural hash of the value.
+  fn stable_hash(& self) -> u64 {
+    let mut hasher = StableHasher::mk() ;
+    self.stable_hash_into(& mut hasher) ;
+    hasher.finish()
+  }
+}
+
+impl<'a, T: StableHash + ?Sized> StableHash for & 'a T {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    (* self).stable_hash_into(hasher)
+  }
+}
+
+impl<T: StableHash + ?Sized> StableHash for Arc<T> {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    (** self).stable_hash_into(hasher)
+  }
+}
+
+impl<T: StableHash> StableHash for Spnd<T> {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    self.get().stable_hash_into(hasher)
+  }
+}
+
+impl StableHash for str {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    hasher.write_str(self)
+  }
+}
+
+impl StableHash for Type {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    hasher.write_str( & format!("{}", self) )
+  }
+}
+
+impl StableHash for Operator {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    let mut bytes: Vec<u8> = vec![] ;
+    self.write(& mut bytes).expect(
+      "[StableHash] writing an operator to a vector cannot fail"
+    ) ;
+    hasher.write_usize( bytes.len() ) ;
+    hasher.write(& bytes)
+  }
+}
+
+impl StableHash for Sym {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    hasher.write_str( self.get().sym() )
+  }
+}
+
+impl StableHash for Var {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    match * self.get() {
+      RealVar::Var(ref sym) => {
+        hasher.write_u8(0) ;
+        sym.stable_hash_into(hasher)
+      },
+      RealVar::SVar(ref sym, ref state) => {
+        hasher.write_u8(1) ;
+        sym.stable_hash_into(hasher) ;
+        hasher.write_u8(
+          match * state { State::Curr => 0, State::Next => 1 }
+        )
+      },
+    }
+  }
+}
+
+impl StableHash for Cst {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    self.get().stable_hash_into(hasher)
+  }
+}
+
+impl StableHash for RealCst {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    match * self {
+      RealCst::Bool(b) => {
+        hasher.write_u8(0) ;
+        hasher.write_u8( if b { 1 } else { 0 } )
+      },
+      RealCst::Int(ref i) => {
+        hasher.write_u8(1) ;
+        hasher.write_str( & format!("{}", i) )
+      },
+      RealCst::Rat(ref r) => {
+        hasher.write_u8(2) ;
+        hasher.write_str( & format!("{}", r.numer()) ) ;
+        hasher.write_str( & format!("{}", r.denom()) )
+      },
+      RealCst::BitVec(width, ref v) => {
+        hasher.write_u8(3) ;
+        hasher.write_usize(width) ;
+        hasher.write_str( & format!("{}", v) )
+      },
+      RealCst::Array(ref idx, ref default, ref entries) => {
+        hasher.write_u8(4) ;
+        idx.stable_hash_into(hasher) ;
+        default.stable_hash_into(hasher) ;
+        hasher.write_usize( entries.len() ) ;
+        for & (ref idx, ref val) in entries.iter() {
+          idx.stable_hash_into(hasher) ;
+          val.stable_hash_into(hasher)
+        }
+      },
+    }
+  }
+}
+
+impl StableHash for Term {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    let hash = hasher.term_hash(self) ;
+    hasher.write_u64(hash)
+  }
+}
+
+impl StableHash for STerm {
+  fn stable_hash_into(& self, hasher: & mut StableHasher) {
+    match * self {
+      STerm::One(ref curr, ref next) => {
+        hasher.write_u8(0) ;
+        curr.stable_hash_into(hasher) ;
+        next.stable_hash_into(hasher)
+      },
+      STerm::Two(ref next) => {
+        hasher.write_u8(1) ;
+        next.stable_hash_into(hasher)
+      },
+    }
+  }
+}
@@ -0,0 +1,229 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Let-inlining and let-introduction.
+//!
+//! Terms are hash consed, so a subterm appearing several times in a term is
+//! stored once. Printing a term binds the subterms appearing more than once
+//! with `let`s so that they are written once, and
+//! [`let_share`](fn.let_share.html) does the same on the term itself.
+//! [`let_expand`](fn.let_expand.html) inlines the `let`s of a term.
+
+use std::collections::{ HashMap, HashSet } ;
+
+use ::{
+  Sym, Term, Type, Factory, SymMaker, VarMaker, OpMaker, AppMaker,
+  BindMaker, vars_of
+} ;
+use ::real_term::{ Term as RTerm, Var as RVar } ;
+
+/// Inlines the `let`s of a term.
+///
+/// A definition mentioning a variable quantified in the body of its `let`
+/// cannot be inlined below the quantifier without capturing it. It is bound
+/// by a `let` around the quantifier instead.
+pub fn let_expand(factory: & Factory, term: & Term) -> Term {
+  expand(factory, term, & mut vec![])
+}
+
+/// Binds the subterms appearing more than once in a term with `let`s.
+///
+/// Subterms below `let`s and quantifiers are left as is, since they can
+/// mention bound variables. The symbols introduced are `@let_<int>`, and do
+/// not clash with the variables of the term.
+pub fn let_share(factory: & Factory, term: & Term) -> Term {
+  let shared = shared(term) ;
+  let names = share_names(term, shared.len()) ;
+  let mut memo = HashMap::new() ;
+  let mut defs = vec![] ;
+  // Kids come before their parents, so their definition is known.
+  for (node, name) in shared.into_iter().zip( names.into_iter() ) {
+    let def = rebuild(factory, & node, & mut memo) ;
+    let sym = factory.sym(name) ;
+    memo.insert( node, factory.var( sym.clone() ) ) ;
+    defs.push( (sym, def) )
+  }
+
+  let mut res = rewrite(factory, term, & mut memo) ;
+  for (sym, def) in defs.into_iter().rev() {
+    res = factory.let_b( vec![ (sym, def) ], res )
+  }
+  res
+}
+
+/// The subterms appearing more than once in a term, not below `let`s and
+/// quantifiers. Kids come before their parents.
+pub fn shared(term: & Term) -> Vec<Term> {
+  let mut counts = HashMap::new() ;
+  let mut order = vec![] ;
+  count(term, & mut counts, & mut order) ;
+  order.into_iter().filter( |node| counts[node] > 1 ).collect()
+}
+
+/// `n` names of the form `@let_<int>` that do not clash with the variables
+/// of a term.
+pub fn share_names(term: & Term, n: usize) -> Vec<String> {
+  let taken: HashSet<String> = vars_of(term).into_iter().map(
+    |var| match * var.get() {
+      RVar::Var(ref sym) | RVar::SVar(ref sym, _) =>
+        sym.get().sym().to_string(),
+    }
+  ).collect() ;
+  let mut names = Vec::with_capacity(n) ;
+  let mut index = 0 ;
+  while names.len() < n {
+    let name = format!("@let_{}", index) ;
+    index += 1 ;
+    if ! taken.contains(& name) { names.push(name) }
+  }
+  names
+}
+
+/// Inlines the `let`s of a term. `env` maps the symbols bound by the
+/// enclosing `let`s to their definition, and the quantified ones to `None`.
+fn expand(
+  factory: & Factory, term: & Term, env: & mut Vec<(Sym, Option<Term>)>
+) -> Term {
+  match * term.get() {
+    RTerm::V(ref var) => {
+      if let RVar::Var(ref sym) = * var.get() {
+        for & (ref bound, ref def) in env.iter().rev() {
+          if bound == sym { return def.as_ref().unwrap_or(term).clone() }
+        }
+      }
+      term.clone()
+    },
+    RTerm::C(_) => term.clone(),
+    RTerm::Op(op, ref kids) => {
+      let kids = kids.iter().map( |kid| expand(factory, kid, env) ).collect() ;
+      factory.op(op, kids)
+    },
+    RTerm::App(ref sym, ref kids) => {
+      let kids = kids.iter().map( |kid| expand(factory, kid, env) ).collect() ;
+      factory.app(sym.clone(), kids)
+    },
+    // Bindings are parallel: definitions are not in their scope.
+    RTerm::Let(ref bindings, ref body) => {
+      let defs: Vec<_> = bindings.iter().map(
+        |& (ref sym, ref def)| (
+          sym.clone(), Some( expand(factory, def, env) )
+        )
+      ).collect() ;
+      let len = env.len() ;
+      env.extend(defs) ;
+      let res = expand(factory, body, env) ;
+      env.truncate(len) ;
+      res
+    },
+    RTerm::Forall(ref vars, ref body) => expand_quantifier(
+      factory, vars, body, env, true
+    ),
+    RTerm::Exists(ref vars, ref body) => expand_quantifier(
+      factory, vars, body, env, false
+    ),
+  }
+}
+
+/// Inlines the `let`s of a quantified term, universal if `forall`.
+fn expand_quantifier(
+  factory: & Factory, vars: & [(Sym, Type)], body: & Term,
+  env: & mut Vec<(Sym, Option<Term>)>, forall: bool
+) -> Term {
+  // Definitions in scope mentioning a quantified variable.
+  let mut captured = vec![] ;
+  {
+    let mut seen = HashSet::new() ;
+    for & (ref sym, ref def) in env.iter().rev() {
+      if ! seen.insert( sym.clone() ) { continue }
+      if let Some(ref def) = * def {
+        let captures = vars_of(def).iter().any(
+          |var| match * var.get() {
+            RVar::Var(ref v) => vars.iter().any( |& (ref q, _)| q == v ),
+            RVar::SVar(_, _) => false,
+          }
+        ) ;
+        if captures { captured.push( ( sym.clone(), def.clone() ) ) }
+      }
+    }
+  }
+
+  let len = env.len() ;
+  for & (ref sym, _) in captured.iter() { env.push( (sym.clone(), None) ) }
+  for & (ref sym, _) in vars.iter() { env.push( (sym.clone(), None) ) }
+  let body = expand(factory, body, env) ;
+  env.truncate(len) ;
+
+  let quantified = if forall {
+    factory.forall( vars.to_vec(), body )
+  } else {
+    factory.exists( vars.to_vec(), body )
+  } ;
+  if captured.is_empty() {
+    quantified
+  } else {
+    factory.let_b(captured, quantified)
+  }
+}
+
+/// Counts the occurrences of the subterms of a term that are not variables
+/// or constants, without going below `let`s and quantifiers. `order`
+/// receives each subterm once, after its kids.
+fn count(
+  term: & Term, counts: & mut HashMap<Term, usize>, order: & mut Vec<Term>
+) {
+  match * term.get() {
+    RTerm::V(_) | RTerm::C(_) => return (),
+    _ => (),
+  }
+  let seen = {
+    let count = counts.entry( term.clone() ).or_insert(0) ;
+    * count += 1 ;
+    * count > 1
+  } ;
+  if seen { return () }
+  match * term.get() {
+    RTerm::Op(_, ref kids) | RTerm::App(_, ref kids) => for kid in kids {
+      count(kid, counts, order)
+    },
+    _ => (),
+  }
+  order.push( term.clone() )
+}
+
+/// Replaces the subterms of a term bound in `memo`, without going below
+/// `let`s and quantifiers.
+fn rewrite(
+  factory: & Factory, term: & Term, memo: & mut HashMap<Term, Term>
+) -> Term {
+  if let Some(res) = memo.get(term) { return res.clone() }
+  let res = rebuild(factory, term, memo) ;
+  memo.insert( term.clone(), res.clone() ) ;
+  res
+}
+
+/// Rebuilds a term, replacing the subterms of its kids bound in `memo`.
+fn rebuild(
+  factory: & Factory, term: & Term, memo: & mut HashMap<Term, Term>
+) -> Term {
+  match * term.get() {
+    RTerm::Op(op, ref kids) => {
+      let kids = kids.iter().map(
+        |kid| rewrite(factory, kid, memo)
+      ).collect() ;
+      factory.op(op, kids)
+    },
+    RTerm::App(ref sym, ref kids) => {
+      let kids = kids.iter().map(
+        |kid| rewrite(factory, kid, memo)
+      ).collect() ;
+      factory.app(sym.clone(), kids)
+    },
+    _ => term.clone(),
+  }
+}
@@ -96,6 +96,11 @@ extern crate rand ;
 extern crate hashconsing as hcons ;
 #[macro_use]
 extern crate rsmt2 ;
+#[cfg(feature = "serialize")]
+extern crate serde ;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive ;
 
 use std::collections::{ HashSet, HashMap } ;
 
@@ -164,7 +169,7 @@ pub mod errors {
 
 mod base ;
 pub use base::{
-  State, PrintSmt2, PrintVmt, Offset, Offset2, Smt2Offset
+  State, PrintSmt2, PrintVmt, Offset, Offset2, Smt2Offset, MemUsage
 } ;
 mod typ ;
 pub use typ::{ Type, Bool, Int, Rat } ;
@@ -176,9 +181,12 @@ mod var ;
 pub use var::{ Var, VarMaker } ;
 mod term ;
 pub use term::{
-  Operator, Term, STerm, CstMaker, BindMaker, AppMaker, OpMaker
+  Operator, Term, STerm, CstMaker, BindMaker, AppMaker, OpMaker,
+  vars_of, state_vars_of
 } ;
 pub mod tmp ;
+mod hash ;
+pub use hash::{ StableHash, StableHasher } ;
 #[macro_use]
 mod parser ;
 /// Parsing stuff.
@@ -196,6 +204,13 @@ pub mod parsing {
 mod factory ;
 pub use factory::{ Factory, ParseVmt2, UnTermOps } ;
 pub mod gen ;
+pub mod simp ;
+pub mod normalize ;
+mod lets ;
+pub use lets::{ let_expand, let_share } ;
+pub mod aig ;
+#[cfg(feature = "serialize")]
+pub mod serial ;
 
 /// A model is a vector of variables with optional offset and values.
 pub type Model = Vec<( (Var, Option<Offset>), Cst )> ;
@@ -230,25 +245,216 @@ pub mod zip {
 ///
 /// Exposed for extensibility.
 pub mod write {
-  pub use base::{ Writable, SVarWriter, StateWritable } ;
+  pub use base::{
+    Writable, SVarWriter, StateWritable, SymWritable, SymPrintStyle
+  } ;
 }
 
 // Re-export of num.
 pub use ::num::* ;
 
 /// SMT solver.
+///
+/// Solver configurations come from `rsmt2`, which only knows z3 and cvc4
+/// styles. cvc5 runs with the cvc4 style, see the [`cvc5`](cvc5/index.html)
+/// module.
+///
+/// Queries get a soft timeout with the option of
+/// [`timeout_option`](fn.timeout_option.html), `:timeout` for z3 and
+/// `:tlimit-per` for cvc4, in milliseconds. A query timing out is answered
+/// `unknown`, which the technique handles following its `on_unknown`
+/// option: it aborts, skips the depth, treats the answer as `sat`, or
+/// retries with other random seeds, see [`seed_option`](fn.seed_option.html).
+///
+/// There is no hard timeout: killing a solver that does not answer needs its
+/// process, which `Kid` keeps private, and respawning it needs a new kid
+/// under a solver that borrows the old one for its whole lifetime. Once
+/// `rsmt2` gives access to the process, a technique can kill its kid, create
+/// a new one and replay the [ledger](../../unroll/ledger/index.html) of its
+/// unroller up to the depth it was at.
 pub mod smt {
   use ::std::process::Command ;
 
   pub use ::rsmt2::* ;
   use ::rsmt2::errors::* ;
 
+  mod multi ;
+  pub use self::multi::{ MultiSolver, racer_conf } ;
+  pub mod cvc5 ;
+  pub mod mathsat ;
+
   /// The default z3 command.
   #[inline(always)]
   pub fn z3_cmd() -> Command { Command::new("z3") }
   /// The default cvc4 command.
   #[inline(always)]
   pub fn cvc4_cmd() -> Command { Command::new("cvc4") }
+  /// The default cvc5 command.
+  #[inline(always)]
+  pub fn cvc5_cmd() -> Command { Command::new("cvc5") }
+  /// The default mathsat command, see [`mathsat`](mathsat/index.html) for
+  /// its configuration.
+  #[inline(always)]
+  pub fn mathsat_cmd() -> Command { Command::new("mathsat") }
+
+  /// The option setting the random seed of the next checks of a solver,
+  /// `None` if it cannot be changed once assertions are made. cvc4 only
+  /// takes `:random-seed` before its first assertion.
+  pub fn seed_option(style: & SolverStyle) -> Option<& 'static str> {
+    match * style {
+      SolverStyle::Z3 => Some(":smt.random_seed"),
+      SolverStyle::CVC4 => None,
+    }
+  }
+
+  /// The option setting the timeout of each query of a solver, in
+  /// milliseconds.
+  pub fn timeout_option(style: & SolverStyle) -> & 'static str {
+    match * style {
+      SolverStyle::Z3 => ":timeout",
+      SolverStyle::CVC4 => ":tlimit-per",
+    }
+  }
+
+  /// Sends a prelude to a solver: SMT-LIB commands such as options, tactics
+  /// or auxiliary definitions, meant to be sent right after its creation.
+  ///
+  /// If the solver prints success, one `success` is parsed per top-level
+  /// command of the prelude. It should thus not contain queries.
+  pub fn prelude<'kid, P: ParseSmt2 + 'static, S: Solver<'kid, P>>(
+    solver: & mut S, script: & str
+  ) -> Res<()> {
+    let print_success = solver.solver().conf().get_parse_success() ;
+    try!(
+      solver.write(
+        |w| smt_cast_io!( "writing prelude" => write!(w, "{}\n", script) )
+      )
+    ) ;
+    if print_success {
+      for _ in 0..command_count(script) {
+        try!( solver.parse_success() )
+      }
+    }
+    Ok(())
+  }
+
+  /// Interpolation queries, in the MathSAT dialect.
+  ///
+  /// Assertions are put in named interpolation groups. After an unsat
+  /// `check-sat`, the interpolant of some groups is implied by their
+  /// assertions, inconsistent with the assertions of the other groups, and
+  /// only mentions the symbols they share.
+  ///
+  /// MathSAT runs with the configuration of
+  /// [`mathsat::conf`](mathsat/fn.conf.html).
+  pub trait Interpolate<'kid, P: ParseSmt2 + 'static> : Solver<'kid, P> {
+    /// Asserts an expression in an interpolation group.
+    fn assert_in_group<Info, E: Expr2Smt<Info>>(
+      & mut self, expr: & E, info: & Info, group: & str
+    ) -> Res<()> {
+      let print_success = self.solver().conf().get_parse_success() ;
+      try!(
+        self.write(
+          |w| smt_cast_io!(
+            "writing assertion in interpolation group" =>
+              write!(w, "(assert (! ") ;
+              expr.expr_to_smt2(w, info) ;
+              write!(w, " :interpolation-group {}))\n", group)
+          )
+        )
+      ) ;
+      if print_success { self.parse_success() } else { Ok(()) }
+    }
+
+    /// Interpolant of some groups against the other ones.
+    fn get_interpolant(
+      & mut self, groups: & [ & str ], info: & P::I
+    ) -> Res<P::Expr> {
+      try!(
+        self.write(
+          |w| smt_cast_io!(
+            "writing interpolant query" =>
+              write!(w, "(get-interpolant ({}))\n", groups.join(" "))
+          )
+        )
+      ) ;
+      self.parse(
+        |bytes, parser| {
+          use ::nom::IResult::Done ;
+          let start = bytes.iter().position(
+            |byte| ! (* byte as char).is_whitespace()
+          ).unwrap_or( bytes.len() ) ;
+          match parser.parse_expr(& bytes[start..], info) {
+            Done(rest, expr) => (
+              String::from_utf8_lossy(rest).into_owned(), Ok(expr)
+            ),
+            _ => (
+              String::new(), Err(
+                format!(
+                  "could not parse interpolant in `{}`",
+                  String::from_utf8_lossy(bytes)
+                ).into()
+              )
+            ),
+          }
+        }
+      )
+    }
+  }
+  impl<
+    'kid, P: ParseSmt2 + 'static, S: Solver<'kid, P>
+  > Interpolate<'kid, P> for S {}
+
+  /// Number of top-level s-expressions in an SMT-LIB script.
+  fn command_count(script: & str) -> usize {
+    let (mut count, mut depth) = (0, 0) ;
+    let mut chars = script.chars() ;
+    while let Some(c) = chars.next() {
+      match c {
+        '(' => depth += 1,
+        ')' => if depth > 0 {
+          depth -= 1 ;
+          if depth == 0 { count += 1 }
+        },
+        ';' => while let Some(c) = chars.next() {
+          if c == '\n' { break }
+        },
+        // Escaped quotes are doubled, handled as two strings.
+        '"' => while let Some(c) = chars.next() {
+          if c == '"' { break }
+        },
+        '|' => while let Some(c) = chars.next() {
+          if c == '|' { break }
+        },
+        _ => (),
+      }
+    }
+    count
+  }
+
+  #[cfg(test)]
+  mod prelude {
+    use super::command_count ;
+
+    #[test]
+    fn commands() {
+      assert_eq!( command_count(""), 0 ) ;
+      assert_eq!( command_count("(set-option :timeout 10)"), 1 ) ;
+      assert_eq!(
+        command_count(
+          "(set-logic QF_LIA)\n(define-fun f ((x Int)) Int (+ x 1))\n"
+        ), 2
+      ) ;
+      // Comments, strings and quoted symbols.
+      assert_eq!( command_count("; (assert false)\n(assert true)"), 1 ) ;
+      assert_eq!( command_count("(echo \"(\")\n(echo \")\")"), 2 ) ;
+      assert_eq!( command_count("(declare-fun |(| () Int)"), 1 ) ;
+      assert_eq!( command_count("(echo \"\"\"(\")"), 1 ) ;
+      // Unbalanced parentheses.
+      assert_eq!( command_count("(push 1"), 0 ) ;
+      assert_eq!( command_count(") (pop 1)"), 1 )
+    }
+  }
 
   impl Sym2Smt<::Offset> for ::Sym {
     fn sym_to_smt2(
@@ -0,0 +1,190 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Negation and conjunctive normal forms.
+//!
+//! Only the boolean structure of a term is normalized: negations are pushed
+//! down to the atoms (equalities, comparisons, boolean variables, function
+//! applications...), and implications, exclusive disjunctions and `ite`s in
+//! boolean positions are expressed with conjunctions and disjunctions.
+//! Negated comparisons are replaced by the dual comparison.
+//!
+//! Local definitions and quantifiers are kept: negations are pushed below
+//! them, but they are atoms for the CNF. The CNF is computed by
+//! distributing disjunctions over conjunctions, which can be exponential in
+//! the size of the term.
+
+use ::{
+  Term, Factory, CstMaker, OpMaker, BindMaker
+} ;
+use ::real_term::{ Term as RTerm, Cst as RCst } ;
+
+/// Negation normal form of a term.
+pub fn nnf(factory: & Factory, term: & Term) -> Term {
+  nnf_pol(factory, term, true)
+}
+
+/// Conjunctive normal form of a term, as a conjunction of disjunctions of
+/// literals.
+pub fn cnf(factory: & Factory, term: & Term) -> Term {
+  let mut conj = Vec::new() ;
+  for clause in clauses(factory, term).into_iter() {
+    if clause.is_empty() { return factory.cst(false) }
+    conj.push( factory.or(clause) )
+  }
+  if conj.is_empty() { factory.cst(true) } else { factory.and(conj) }
+}
+
+/// Clauses of the conjunctive normal form of a term. A clause is a
+/// disjunction of literals, empty if it is false.
+pub fn clauses(factory: & Factory, term: & Term) -> Vec< Vec<Term> > {
+  clauses_of_nnf( & nnf(factory, term) )
+}
+
+/// Boolean value of a term, if it is a boolean constant.
+fn bool_of(term: & Term) -> Option<bool> {
+  match * term.get() {
+    RTerm::C(ref cst) => match * cst.get() {
+      RCst::Bool(b) => Some(b),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Negation normal form of a term if `pos`, of its negation otherwise.
+fn nnf_pol(factory: & Factory, term: & Term, pos: bool) -> Term {
+  use Operator::* ;
+  match * term.get() {
+
+    RTerm::Op(Not, ref kids) if kids.len() == 1 => nnf_pol(
+      factory, & kids[0], ! pos
+    ),
+
+    RTerm::Op(op, ref kids) if op == And || op == Or => {
+      if kids.is_empty() { return factory.cst( (op == And) == pos ) }
+      let kids = kids.iter().map(
+        |kid| nnf_pol(factory, kid, pos)
+      ).collect() ;
+      if (op == And) == pos { factory.and(kids) } else { factory.or(kids) }
+    },
+
+    // Right-associative: `(=> a b c)` is `(=> a (=> b c))`.
+    RTerm::Op(Impl, ref kids) if kids.len() >= 2 => {
+      let last = kids.len() - 1 ;
+      let kids = kids.iter().enumerate().map(
+        |(index, kid)| nnf_pol(
+          factory, kid, if index == last { pos } else { ! pos }
+        )
+      ).collect() ;
+      if pos { factory.or(kids) } else { factory.and(kids) }
+    },
+
+    // Left-associative: `(xor a b c)` is `(xor (xor a b) c)`.
+    RTerm::Op(Xor, ref kids) => match kids.len() {
+      0 => factory.cst(! pos),
+      1 => nnf_pol(factory, & kids[0], pos),
+      2 => {
+        // Negating the xor is negating one of its kids.
+        let lhs = nnf_pol(factory, & kids[0], true) ;
+        let rhs = nnf_pol(factory, & kids[1], pos) ;
+        let not_lhs = nnf_pol(factory, & kids[0], false) ;
+        let not_rhs = nnf_pol(factory, & kids[1], ! pos) ;
+        factory.or(
+          vec![
+            factory.and( vec![ lhs, not_rhs ] ),
+            factory.and( vec![ not_lhs, rhs ] ),
+          ]
+        )
+      },
+      len => {
+        let init = factory.op( Xor, kids[0..len - 1].to_vec() ) ;
+        let xor = factory.op( Xor, vec![ init, kids[len - 1].clone() ] ) ;
+        nnf_pol(factory, & xor, pos)
+      },
+    },
+
+    // `(ite c t e)` is `(and (or (not c) t) (or c e))`, and its negation
+    // is `(ite c (not t) (not e))`.
+    RTerm::Op(Ite, ref kids) if kids.len() == 3 => factory.and(
+      vec![
+        factory.or(
+          vec![
+            nnf_pol(factory, & kids[0], false),
+            nnf_pol(factory, & kids[1], pos)
+          ]
+        ),
+        factory.or(
+          vec![
+            nnf_pol(factory, & kids[0], true),
+            nnf_pol(factory, & kids[2], pos)
+          ]
+        ),
+      ]
+    ),
+
+    RTerm::Let(ref bindings, ref body) => factory.let_b(
+      bindings.clone(), nnf_pol(factory, body, pos)
+    ),
+
+    RTerm::Forall(ref vars, ref body) => if pos {
+      factory.forall( vars.clone(), nnf_pol(factory, body, true) )
+    } else {
+      factory.exists( vars.clone(), nnf_pol(factory, body, false) )
+    },
+
+    RTerm::Exists(ref vars, ref body) => if pos {
+      factory.exists( vars.clone(), nnf_pol(factory, body, true) )
+    } else {
+      factory.forall( vars.clone(), nnf_pol(factory, body, false) )
+    },
+
+    _ => if pos { term.clone() } else { factory.not( term.clone() ) },
+  }
+}
+
+/// Clauses of a term in negation normal form.
+fn clauses_of_nnf(term: & Term) -> Vec< Vec<Term> > {
+  use Operator::* ;
+  match * term.get() {
+    RTerm::Op(And, ref kids) => {
+      let mut res = Vec::new() ;
+      for kid in kids.iter() {
+        res.extend( clauses_of_nnf(kid) )
+      }
+      res
+    },
+    RTerm::Op(Or, ref kids) => {
+      // Distributing the disjunction over the clauses of the kids.
+      let mut res = vec![ vec![] ] ;
+      for kid in kids.iter() {
+        let kid_clauses = clauses_of_nnf(kid) ;
+        let mut nu_res = Vec::with_capacity(
+          res.len() * kid_clauses.len()
+        ) ;
+        for clause in res.iter() {
+          for kid_clause in kid_clauses.iter() {
+            let mut nu_clause: Vec<Term> = clause.clone() ;
+            for lit in kid_clause.iter() {
+              if ! nu_clause.contains(lit) { nu_clause.push( lit.clone() ) }
+            }
+            nu_res.push(nu_clause)
+          }
+        }
+        res = nu_res
+      }
+      res
+    },
+    _ => match bool_of(term) {
+      Some(true) => vec![],
+      Some(false) => vec![ vec![] ],
+      None => vec![ vec![ term.clone() ] ],
+    },
+  }
+}
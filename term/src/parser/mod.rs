@@ -16,7 +16,7 @@ use std::io::Write ;
 use nom::{ digit, multispace, IResult, not_line_ending } ;
 
 use typ::{ Type, Bool, Int, Rat } ;
-use cst::Cst ;
+use cst::{ Cst, RealCst } ;
 use term::{ CstMaker, Operator } ;
 use rsmt2::{ Sort2Smt, Sym2Smt, Expr2Smt } ;
 use rsmt2::errors::Res ;
@@ -67,6 +67,7 @@ macro_rules! try_parse {
 
 /// A span indicates a position (new lines count as regular characters).
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Spn {
   /// Start of the span.
   pub bgn: usize,
@@ -427,6 +428,7 @@ pub mod smt2 ;
 mk_parser!{
   #[doc = "Spanned type parser."]
   pub fn type_parser(bytes, offset: usize) -> Spnd<Type> {
+    let mut len = 0 ;
     alt!(
       bytes,
       map!(
@@ -435,11 +437,54 @@ mk_parser!{
         tag!("Bool"), |bytes: Bytes| Spnd::bytes_mk(Type::Bool, offset, bytes)
       ) | map!(
         tag!("Real"), |bytes: Bytes| Spnd::bytes_mk(Type::Rat,  offset, bytes)
+      ) | do_parse!(
+        len_set!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char '_') >>
+        len_add!(len < int space_comment) >>
+        len_add!(len < tag "BitVec") >>
+        len_add!(len < int space_comment) >>
+        width: len_add!(
+          len < spn apply!(index_parser, offset + len)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >> (
+          Spnd::len_mk(Type::BitVec(width), offset, len)
+        )
+      ) | do_parse!(
+        len_set!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < tag "Array") >>
+        len_add!(len < int space_comment) >>
+        idx: len_add!(
+          len < spn apply!(type_parser, offset + len)
+        ) >>
+        len_add!(len < int space_comment) >>
+        elm: len_add!(
+          len < spn apply!(type_parser, offset + len)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >> (
+          Spnd::len_mk(
+            Type::Array( Box::new(idx), Box::new(elm) ), offset, len
+          )
+        )
       )
     )
   }
 }
 
+mk_parser!{
+  #[doc = "Parses a spanned index, as used in indexed sorts and operators."]
+  pub fn index_parser(bytes, offset: usize) -> Spnd<usize> {
+    map_opt!(
+      bytes, digit, |bytes: Bytes| ::std::str::from_utf8(bytes).ok().and_then(
+        |s| s.parse::<usize>().ok()
+      ).map( |index| Spnd::bytes_mk(index, offset, bytes) )
+    )
+  }
+}
+
 named_attr!{
   #[doc = "Parses a line comment, returns the number of bytes parsed."],
   pub comment<usize>,
@@ -612,15 +657,156 @@ mk_parser!{
   }
 }
 
+mk_parser!{
+  #[doc = "Parses a spanned bitvector constant: `#b<bits>`, `#x<hex>` or \
+    `(_ bv<value> <width>)`. Yields the width and the value."]
+  pub fn bv_parser(bytes, offset: usize) -> Spnd<(usize, Int)> {
+    let mut len = 0 ;
+    alt!(
+      bytes,
+      do_parse!(
+        len_set!(len < char '#') >>
+        len_add!(len < char 'b') >>
+        bv: map_opt!(
+          is_a!("01"), |bits: Bytes| Int::parse_bytes(bits, 2).map(
+            |v| { len += bits.len() ; (bits.len(), v) }
+          )
+        ) >> (
+          Spnd::len_mk(bv, offset, len)
+        )
+      ) |
+      do_parse!(
+        len_set!(len < char '#') >>
+        len_add!(len < char 'x') >>
+        bv: map_opt!(
+          is_a!("0123456789abcdefABCDEF"), |hex: Bytes| Int::parse_bytes(
+            hex, 16
+          ).map(
+            |v| { len += hex.len() ; (4 * hex.len(), v) }
+          )
+        ) >> (
+          Spnd::len_mk(bv, offset, len)
+        )
+      ) |
+      do_parse!(
+        len_set!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char '_') >>
+        len_add!(len < int space_comment) >>
+        len_add!(len < tag "bv") >>
+        v: len_add!(
+          len < spn apply!(int_parser, offset + len)
+        ) >>
+        len_add!(len < int space_comment) >>
+        width: len_add!(
+          len < spn apply!(index_parser, offset + len)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >> (
+          Spnd::len_mk( (width, v), offset, len )
+        )
+      )
+    )
+  }
+}
+
+/// Parses a spanned array constant: `((as const <type>) <cst>)`, possibly
+/// under `store`s of constants.
+pub fn array_parser<'a, F>(
+  bytes: & 'a [u8], offset: usize, f: & F
+) -> IResult<& 'a [u8], Spnd<RealCst>>
+where F: CstMaker<Bool, Cst> + CstMaker<Int, Cst> + CstMaker<Rat, Cst> +
+CstMaker<(usize, Int), Cst> + CstMaker<RealCst, Cst> {
+  let mut len = 0 ;
+  alt!(
+    bytes,
+    map_opt!(
+      do_parse!(
+        len_set!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < tag "as") >>
+        len_add!(len < int space_comment) >>
+        len_add!(len < tag "const") >>
+        len_add!(len < int space_comment) >>
+        typ: len_add!(
+          len < spn apply!(type_parser, offset + len)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        default: len_add!(
+          len < spn apply!(cst_parser, offset + len, f)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >> (
+          (typ, default)
+        )
+      ), |(typ, default): (Type, Cst)| match typ {
+        Type::Array(idx, elm) => if * elm == default.get().typ() {
+          Some(
+            Spnd::len_mk(
+              RealCst::Array(
+                * idx, Box::new( default.get().clone() ), vec![]
+              ), offset, len
+            )
+          )
+        } else { None },
+        _ => None,
+      }
+    ) |
+    map_opt!(
+      do_parse!(
+        len_set!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < tag "store") >>
+        len_add!(len < int space_comment) >>
+        array: len_add!(
+          len < spn apply!(array_parser, offset + len, f)
+        ) >>
+        len_add!(len < int space_comment) >>
+        idx: len_add!(
+          len < spn apply!(cst_parser, offset + len, f)
+        ) >>
+        len_add!(len < int space_comment) >>
+        val: len_add!(
+          len < spn apply!(cst_parser, offset + len, f)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >> (
+          (array, idx, val)
+        )
+      ), |(array, idx, val): (RealCst, Cst, Cst)| match array.typ() {
+        Type::Array(ref idx_typ, ref val_typ) if (
+          ** idx_typ == idx.get().typ() && ** val_typ == val.get().typ()
+        ) => array.store(
+          idx.get().clone(), val.get().clone()
+        ).map( |array| Spnd::len_mk(array, offset, len) ),
+        _ => None,
+      }
+    )
+  )
+}
+
 pub fn cst_parser<'a, F>(
   bytes: & 'a [u8], offset: usize, f: & F
 ) -> IResult<& 'a [u8], Spnd<Cst>>
-where F: CstMaker<Bool, Cst> + CstMaker<Int, Cst> + CstMaker<Rat, Cst> {
+where F: CstMaker<Bool, Cst> + CstMaker<Int, Cst> + CstMaker<Rat, Cst> +
+CstMaker<(usize, Int), Cst> + CstMaker<RealCst, Cst> {
   let mut len = 0 ;
   preceded!(
     bytes,
     opt!( len_add!(len < int space_comment) ),
     alt_complete!(
+      map!(
+        apply!(bv_parser, offset),
+        |bv: Spnd<(usize, Int)>| bv.map(|bv| f.cst(bv))
+      ) |
+      map!(
+        apply!(array_parser, offset, f),
+        |array: Spnd<RealCst>| array.map(|array| f.cst(array))
+      ) |
       map!(
         apply!(rat_parser, offset),  |r:Spnd<Rat>| r.map(|r| f.cst(r))
       ) |
@@ -663,10 +849,22 @@ mk_parser!{
   pub fn operator_parser(bytes, offset: usize) -> Spnd<Operator> {
     alt!(
       bytes,
+      apply!(bv_operator_parser, offset) |
+      apply!(bv_predicate_parser, offset) |
+      apply!(int_operator_parser, offset) |
+      apply!(conversion_operator_parser, offset) |
       map!(
         tag!("=>"),
         |b: Bytes| Spnd::len_mk(Operator::Impl, offset, b.len())
       ) |
+      map!(
+        tag!("select"),
+        |b: Bytes| Spnd::len_mk(Operator::Select, offset, b.len())
+      ) |
+      map!(
+        tag!("store"),
+        |b: Bytes| Spnd::len_mk(Operator::Store, offset, b.len())
+      ) |
       map!(
         tag!("="),
         |b: Bytes| Spnd::len_mk(Operator::Eq, offset, b.len())
@@ -731,6 +929,170 @@ mk_parser!{
   }
 }
 
+mk_parser!{
+  #[doc = "Parses an integer-only operator."]
+  pub fn int_operator_parser(bytes, offset: usize) -> Spnd<Operator> {
+    alt!(
+      bytes,
+      map!(
+        tag!("mod"),
+        |b: Bytes| Spnd::len_mk(Operator::Mod, offset, b.len())
+      ) |
+      map!(
+        tag!("rem"),
+        |b: Bytes| Spnd::len_mk(Operator::Rem, offset, b.len())
+      ) |
+      map!(
+        tag!("abs"),
+        |b: Bytes| Spnd::len_mk(Operator::Abs, offset, b.len())
+      )
+    )
+  }
+}
+
+mk_parser!{
+  #[doc = "Parses a conversion operator between Int and Real."]
+  pub fn conversion_operator_parser(bytes, offset: usize) -> Spnd<Operator> {
+    alt!(
+      bytes,
+      map!(
+        tag!("to_real"),
+        |b: Bytes| Spnd::len_mk(Operator::ToReal, offset, b.len())
+      ) |
+      map!(
+        tag!("to_int"),
+        |b: Bytes| Spnd::len_mk(Operator::ToInt, offset, b.len())
+      ) |
+      map!(
+        tag!("is_int"),
+        |b: Bytes| Spnd::len_mk(Operator::IsInt, offset, b.len())
+      )
+    )
+  }
+}
+
+mk_parser!{
+  #[doc = "Parses a bitvector operator returning a bitvector."]
+  pub fn bv_operator_parser(bytes, offset: usize) -> Spnd<Operator> {
+    let mut len = 0 ;
+    alt!(
+      bytes,
+      map!(
+        tag!("bvnot"),
+        |b: Bytes| Spnd::len_mk(Operator::BvNot, offset, b.len())
+      ) |
+      map!(
+        tag!("bvneg"),
+        |b: Bytes| Spnd::len_mk(Operator::BvNeg, offset, b.len())
+      ) |
+      map!(
+        tag!("bvand"),
+        |b: Bytes| Spnd::len_mk(Operator::BvAnd, offset, b.len())
+      ) |
+      map!(
+        tag!("bvor"),
+        |b: Bytes| Spnd::len_mk(Operator::BvOr, offset, b.len())
+      ) |
+      map!(
+        tag!("bvxor"),
+        |b: Bytes| Spnd::len_mk(Operator::BvXor, offset, b.len())
+      ) |
+      map!(
+        tag!("bvadd"),
+        |b: Bytes| Spnd::len_mk(Operator::BvAdd, offset, b.len())
+      ) |
+      map!(
+        tag!("bvsub"),
+        |b: Bytes| Spnd::len_mk(Operator::BvSub, offset, b.len())
+      ) |
+      map!(
+        tag!("bvmul"),
+        |b: Bytes| Spnd::len_mk(Operator::BvMul, offset, b.len())
+      ) |
+      map!(
+        tag!("bvudiv"),
+        |b: Bytes| Spnd::len_mk(Operator::BvUdiv, offset, b.len())
+      ) |
+      map!(
+        tag!("bvurem"),
+        |b: Bytes| Spnd::len_mk(Operator::BvUrem, offset, b.len())
+      ) |
+      map!(
+        tag!("bvshl"),
+        |b: Bytes| Spnd::len_mk(Operator::BvShl, offset, b.len())
+      ) |
+      map!(
+        tag!("bvlshr"),
+        |b: Bytes| Spnd::len_mk(Operator::BvLshr, offset, b.len())
+      ) |
+      map!(
+        tag!("concat"),
+        |b: Bytes| Spnd::len_mk(Operator::Concat, offset, b.len())
+      ) |
+      do_parse!(
+        len_set!(len < char '(') >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char '_') >>
+        len_add!(len < int space_comment) >>
+        len_add!(len < tag "extract") >>
+        len_add!(len < int space_comment) >>
+        high: len_add!(
+          len < spn apply!(index_parser, offset + len)
+        ) >>
+        len_add!(len < int space_comment) >>
+        low: len_add!(
+          len < spn apply!(index_parser, offset + len)
+        ) >>
+        opt!( len_add!(len < int space_comment) ) >>
+        len_add!(len < char ')') >> (
+          Spnd::len_mk(Operator::Extract(high, low), offset, len)
+        )
+      )
+    )
+  }
+}
+
+mk_parser!{
+  #[doc = "Parses a bitvector comparison operator."]
+  pub fn bv_predicate_parser(bytes, offset: usize) -> Spnd<Operator> {
+    alt!(
+      bytes,
+      map!(
+        tag!("bvult"),
+        |b: Bytes| Spnd::len_mk(Operator::BvUlt, offset, b.len())
+      ) |
+      map!(
+        tag!("bvule"),
+        |b: Bytes| Spnd::len_mk(Operator::BvUle, offset, b.len())
+      ) |
+      map!(
+        tag!("bvugt"),
+        |b: Bytes| Spnd::len_mk(Operator::BvUgt, offset, b.len())
+      ) |
+      map!(
+        tag!("bvuge"),
+        |b: Bytes| Spnd::len_mk(Operator::BvUge, offset, b.len())
+      ) |
+      map!(
+        tag!("bvslt"),
+        |b: Bytes| Spnd::len_mk(Operator::BvSlt, offset, b.len())
+      ) |
+      map!(
+        tag!("bvsle"),
+        |b: Bytes| Spnd::len_mk(Operator::BvSle, offset, b.len())
+      ) |
+      map!(
+        tag!("bvsgt"),
+        |b: Bytes| Spnd::len_mk(Operator::BvSgt, offset, b.len())
+      ) |
+      map!(
+        tag!("bvsge"),
+        |b: Bytes| Spnd::len_mk(Operator::BvSge, offset, b.len())
+      )
+    )
+  }
+}
+
 /// A quantifier at parsing time.
 pub enum Quantifier {
   /// Universal.
@@ -798,6 +1160,25 @@ mod typ3 {
       Spnd::len_mk(::typ::Type::Rat, 0, 4)
     )
   }
+  #[test]
+  fn bv() {
+    use super::* ;
+    try_parse_val!(
+      |bytes| type_parser(bytes, 0), b"(_ BitVec 32)",
+      Spnd::len_mk(::typ::Type::BitVec(32), 0, 13)
+    )
+  }
+  #[test]
+  fn array() {
+    use super::* ;
+    use typ::Type ;
+    try_parse_val!(
+      |bytes| type_parser(bytes, 0), b"(Array Int (_ BitVec 8))",
+      Spnd::len_mk(
+        Type::Array( Box::new(Type::Int), Box::new(Type::BitVec(8)) ), 0, 24
+      )
+    )
+  }
 }
 
 
@@ -842,6 +1223,23 @@ mod int {
     )
   }
   #[test]
+  fn operators() {
+    use super::* ;
+    try_parse_val!(
+      |bytes| operator_parser(bytes, 0), b"mod",
+      Spnd::len_mk(Operator::Mod, 0, 3)
+    ) ;
+    try_parse_val!(
+      |bytes| operator_parser(bytes, 0), b"abs",
+      Spnd::len_mk(Operator::Abs, 0, 3)
+    ) ;
+    try_parse_val!(
+      |bytes| operator_parser(bytes, 0), b"to_real",
+      Spnd::len_mk(Operator::ToReal, 0, 7)
+    ) ;
+    ()
+  }
+  #[test]
   fn empty() {
     use super::* ;
     match int_parser(& b""[..], 0) {
@@ -915,4 +1313,35 @@ mod rat {
     ) ;
     ()
   }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod bv {
+  #[test]
+  fn bv() {
+    use super::* ;
+    use std::str::FromStr ;
+    use typ::Int ;
+    try_parse_val!(
+      |bytes| bv_parser(bytes, 0), b"#b0101",
+      Spnd::len_mk( (4, Int::from_str("5").unwrap()), 0, 6 )
+    ) ;
+    try_parse_val!(
+      |bytes| bv_parser(bytes, 0), b"#x1f",
+      Spnd::len_mk( (8, Int::from_str("31").unwrap()), 0, 4 )
+    ) ;
+    try_parse_val!(
+      |bytes| bv_parser(bytes, 0), b"(_ bv5 8)",
+      Spnd::len_mk( (8, Int::from_str("5").unwrap()), 0, 9 )
+    ) ;
+    ()
+  }
+  #[test]
+  fn extract() {
+    use super::* ;
+    try_parse_val!(
+      |bytes| operator_parser(bytes, 0), b"(_ extract 7 4)",
+      Spnd::len_mk(Operator::Extract(7, 4), 0, 15)
+    ) ;
+    ()
+  }
+}
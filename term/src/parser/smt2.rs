@@ -334,7 +334,7 @@ fn quantified_parser<'a>(
           ) >>
           multispace >>
           ty: apply!(type_parser, 0) >>
-          opt!(multispace) >> (sym, * ty)
+          opt!(multispace) >> (sym, ty.val)
         ),
         char!(')')
       )
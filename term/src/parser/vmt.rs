@@ -143,7 +143,7 @@ impl TermAndDep {
       subs, apps, vars, types, kids_with_vars, linear, qf
     ) = Self::merge(kids) ;
     let linear = linear && match op {
-      Mul | Div => kids_with_vars >= 2,
+      Mul | Div | Mod | Rem => kids_with_vars >= 2,
       _ => false,
     } ;
     let term = factory.op(op, subs) ;
@@ -196,7 +196,7 @@ impl TermAndDep {
       let var = factory.var(sym.clone()) ;
       let was_there = vars.remove(& var) ;
       if was_there.is_some() {
-        binds.push( (sym, * typ) ) ;
+        binds.push( (sym, typ.get().clone()) ) ;
         types.entry(typ.val).or_insert(vec![]).push(typ.span) ;
         ()
       } ;
@@ -0,0 +1,340 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serialization of hash consed structures, with the `serialize` feature.
+//!
+//! Hash consed structures can only be created by a [`Factory`][factory], so
+//! they do not implement serde's `Deserialize` directly. Instead they convert
+//! to and from *plain* structures that do (trait [`Serial`][serial]), and
+//! converting back re-interns them in a factory.
+//!
+//! [`serialize`][serialize] serializes anything `Serial` and is usable as a
+//! `serialize_with` attribute, [`Seed`][seed] is the `DeserializeSeed`
+//! deserializing and re-interning in one go.
+//!
+//! Integers and rationals are serialized as strings, since they are
+//! arbitrary precision. State terms only store their state version when they
+//! are one-state, the next version is re-created by bumping it.
+//!
+//! [factory]: ../struct.Factory.html (Factory struct)
+//! [serial]: trait.Serial.html (Serial trait)
+//! [serialize]: fn.serialize.html (serialize function)
+//! [seed]: struct.Seed.html (Seed struct)
+
+use std::marker::PhantomData ;
+
+use serde::{ Serialize, Serializer, Deserialize, Deserializer } ;
+use serde::de::{ DeserializeOwned, DeserializeSeed, Error as DeError } ;
+
+use ::{
+  Sym, Var, Cst, Term, STerm, Type, State, Offset, Operator, Model, Int,
+  Factory, SymMaker, VarMaker, OpMaker, AppMaker, BindMaker, UnTermOps
+} ;
+use ::real_term ;
+use ::errors::* ;
+use ::num::Zero ;
+
+/// Can be converted to and from a plain serializable structure.
+pub trait Serial: Sized {
+  /// The plain version of the structure.
+  type Plain: Serialize + DeserializeOwned ;
+  /// Plain version of a value.
+  fn to_plain(& self) -> Self::Plain ;
+  /// Re-creates a value from its plain version in a factory.
+  fn of_plain(factory: & Factory, plain: Self::Plain) -> Res<Self> ;
+}
+
+/// Serializes anything `Serial`.
+pub fn serialize<T: Serial, S: Serializer>(
+  val: & T, serializer: S
+) -> Result<S::Ok, S::Error> {
+  val.to_plain().serialize(serializer)
+}
+
+/// Deserializes anything `Serial`, re-interning it in a factory.
+pub struct Seed<'a, T> {
+  /// Factory to re-intern in.
+  factory: & 'a Factory,
+  /// Type deserialized.
+  phantom: PhantomData<T>,
+}
+impl<'a, T> Seed<'a, T> {
+  /// Creates a seed.
+  #[inline]
+  pub fn mk(factory: & 'a Factory) -> Self {
+    Seed { factory: factory, phantom: PhantomData }
+  }
+}
+impl<'de, 'a, T: Serial> DeserializeSeed<'de> for Seed<'a, T> {
+  type Value = T ;
+  fn deserialize<D: Deserializer<'de>>(
+    self, deserializer: D
+  ) -> Result<T, D::Error> {
+    let plain = try!(
+      <T::Plain as Deserialize<'de>>::deserialize(deserializer)
+    ) ;
+    T::of_plain(self.factory, plain).map_err( |e| D::Error::custom(e) )
+  }
+}
+
+/// Plain variable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PVar {
+  /// Non-stateful variable.
+  Var(String),
+  /// Stateful variable.
+  SVar(String, State),
+}
+
+/// Plain constant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PCst {
+  /// Boolean constant.
+  Bool(bool),
+  /// Integer constant.
+  Int(String),
+  /// Rational constant, numerator and denominator.
+  Rat(String, String),
+  /// Bitvector constant: width and unsigned value.
+  BitVec(usize, String),
+  /// Array constant: index type, default value and entries.
+  Array(Type, Box<PCst>, Vec<(PCst, PCst)>),
+}
+
+/// Plain term.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PTerm {
+  /// A variable.
+  V(PVar),
+  /// A constant value.
+  C(PCst),
+  /// An application of an operator.
+  Op(Operator, Vec<PTerm>),
+  /// A universal quantification.
+  Forall(Vec<(String, Type)>, Box<PTerm>),
+  /// An existential quantification.
+  Exists(Vec<(String, Type)>, Box<PTerm>),
+  /// A let-binding.
+  Let(Vec<(String, PTerm)>, Box<PTerm>),
+  /// An application of a function symbol.
+  App(String, Vec<PTerm>),
+}
+
+/// Plain state term.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PSTerm {
+  /// A one-state term, state version only.
+  One(PTerm),
+  /// A two-state term.
+  Two(PTerm),
+}
+
+/// Parses a decimal integer.
+fn int_of(s: & str) -> Res<Int> {
+  match Int::parse_bytes(s.as_bytes(), 10u32) {
+    Some(int) => Ok(int),
+    None => Err( format!("illegal integer `{}`", s).into() ),
+  }
+}
+
+/// Plain version of a real constant.
+fn plain_of_rcst(cst: & real_term::Cst) -> PCst {
+  use real_term::Cst::* ;
+  match * cst {
+    Bool(b) => PCst::Bool(b),
+    Int(ref i) => PCst::Int( i.to_string() ),
+    Rat(ref r) => PCst::Rat( r.numer().to_string(), r.denom().to_string() ),
+    BitVec(width, ref val) => PCst::BitVec( width, val.to_string() ),
+    Array(ref idx, ref default, ref entries) => PCst::Array(
+      idx.clone(), Box::new( plain_of_rcst(default) ),
+      entries.iter().map(
+        |& (ref idx, ref val)| ( plain_of_rcst(idx), plain_of_rcst(val) )
+      ).collect()
+    ),
+  }
+}
+
+/// Real constant from its plain version.
+fn rcst_of_plain(cst: PCst) -> Res<real_term::Cst> {
+  use real_term::Cst::* ;
+  match cst {
+    PCst::Bool(b) => Ok( Bool(b) ),
+    PCst::Int(i) => Ok( Int( try!( int_of(& i) ) ) ),
+    PCst::Rat(num, den) => {
+      let (num, den) = ( try!( int_of(& num) ), try!( int_of(& den) ) ) ;
+      if den.is_zero() {
+        Err( "rational constant with zero denominator".into() )
+      } else {
+        Ok( Rat( ::Rat::new(num, den) ) )
+      }
+    },
+    PCst::BitVec(width, val) => Ok( BitVec( width, try!( int_of(& val) ) ) ),
+    PCst::Array(idx, default, entries) => {
+      let default = try!( rcst_of_plain(* default) ) ;
+      let mut nu_entries = Vec::with_capacity( entries.len() ) ;
+      for (idx, val) in entries.into_iter() {
+        nu_entries.push(
+          ( try!( rcst_of_plain(idx) ), try!( rcst_of_plain(val) ) )
+        )
+      }
+      Ok( Array( idx, Box::new(default), nu_entries ) )
+    },
+  }
+}
+
+impl Serial for Sym {
+  type Plain = String ;
+  fn to_plain(& self) -> String { self.get().sym().to_string() }
+  fn of_plain(factory: & Factory, plain: String) -> Res<Sym> {
+    Ok( factory.sym(plain) )
+  }
+}
+
+impl Serial for Var {
+  type Plain = PVar ;
+  fn to_plain(& self) -> PVar {
+    match * self.get() {
+      real_term::Var::Var(ref sym) => PVar::Var( sym.to_plain() ),
+      real_term::Var::SVar(ref sym, state) => PVar::SVar(
+        sym.to_plain(), state
+      ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PVar) -> Res<Var> {
+    Ok(
+      match plain {
+        PVar::Var(sym) => factory.var( factory.sym(sym) ),
+        PVar::SVar(sym, state) => factory.svar( factory.sym(sym), state ),
+      }
+    )
+  }
+}
+
+impl Serial for Cst {
+  type Plain = PCst ;
+  fn to_plain(& self) -> PCst { plain_of_rcst( self.get() ) }
+  fn of_plain(factory: & Factory, plain: PCst) -> Res<Cst> {
+    Ok( factory.mk_rcst( try!( rcst_of_plain(plain) ) ) )
+  }
+}
+
+impl Serial for Term {
+  type Plain = PTerm ;
+  fn to_plain(& self) -> PTerm {
+    use real_term::Term::* ;
+    match * self.get() {
+      V(ref var) => PTerm::V( var.to_plain() ),
+      C(ref cst) => PTerm::C( cst.to_plain() ),
+      Op(op, ref kids) => PTerm::Op(
+        op, kids.iter().map( |kid| kid.to_plain() ).collect()
+      ),
+      Forall(ref vars, ref body) => PTerm::Forall(
+        vars.iter().map(
+          |& (ref sym, ref typ)| ( sym.to_plain(), typ.clone() )
+        ).collect(),
+        Box::new( body.to_plain() )
+      ),
+      Exists(ref vars, ref body) => PTerm::Exists(
+        vars.iter().map(
+          |& (ref sym, ref typ)| ( sym.to_plain(), typ.clone() )
+        ).collect(),
+        Box::new( body.to_plain() )
+      ),
+      Let(ref bindings, ref body) => PTerm::Let(
+        bindings.iter().map(
+          |& (ref sym, ref term)| ( sym.to_plain(), term.to_plain() )
+        ).collect(),
+        Box::new( body.to_plain() )
+      ),
+      App(ref sym, ref args) => PTerm::App(
+        sym.to_plain(), args.iter().map( |arg| arg.to_plain() ).collect()
+      ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PTerm) -> Res<Term> {
+    let terms_of = |terms: Vec<PTerm>| -> Res< Vec<Term> > {
+      let mut res = Vec::with_capacity( terms.len() ) ;
+      for term in terms.into_iter() {
+        res.push( try!( Term::of_plain(factory, term) ) )
+      }
+      Ok(res)
+    } ;
+    let vars_of = |vars: Vec<(String, Type)>| -> Vec<(Sym, Type)> {
+      vars.into_iter().map( |(sym, typ)| ( factory.sym(sym), typ ) ).collect()
+    } ;
+    Ok(
+      match plain {
+        PTerm::V(var) => factory.mk_var( try!( Var::of_plain(factory, var) ) ),
+        PTerm::C(cst) => factory.mk_cst( try!( Cst::of_plain(factory, cst) ) ),
+        PTerm::Op(op, kids) => factory.op( op, try!( terms_of(kids) ) ),
+        PTerm::Forall(vars, body) => factory.forall(
+          vars_of(vars), try!( Term::of_plain(factory, * body) )
+        ),
+        PTerm::Exists(vars, body) => factory.exists(
+          vars_of(vars), try!( Term::of_plain(factory, * body) )
+        ),
+        PTerm::Let(bindings, body) => {
+          let mut nu_bindings = Vec::with_capacity( bindings.len() ) ;
+          for (sym, term) in bindings.into_iter() {
+            nu_bindings.push(
+              ( factory.sym(sym), try!( Term::of_plain(factory, term) ) )
+            )
+          }
+          factory.let_b( nu_bindings, try!( Term::of_plain(factory, * body) ) )
+        },
+        PTerm::App(sym, args) => factory.app(
+          factory.sym(sym), try!( terms_of(args) )
+        ),
+      }
+    )
+  }
+}
+
+impl Serial for STerm {
+  type Plain = PSTerm ;
+  fn to_plain(& self) -> PSTerm {
+    match * self {
+      STerm::One(ref state, _) => PSTerm::One( state.to_plain() ),
+      STerm::Two(ref next) => PSTerm::Two( next.to_plain() ),
+    }
+  }
+  fn of_plain(factory: & Factory, plain: PSTerm) -> Res<STerm> {
+    match plain {
+      PSTerm::One(state) => {
+        let state = try!( Term::of_plain(factory, state) ) ;
+        let next = try!( factory.bump( state.clone() ) ) ;
+        Ok( STerm::One(state, next) )
+      },
+      PSTerm::Two(next) => Ok(
+        STerm::Two( try!( Term::of_plain(factory, next) ) )
+      ),
+    }
+  }
+}
+
+impl Serial for Model {
+  type Plain = Vec<( (PVar, Option<Offset>), PCst )> ;
+  fn to_plain(& self) -> Self::Plain {
+    self.iter().map(
+      |& ( (ref var, ref off), ref cst )| (
+        ( var.to_plain(), off.clone() ), cst.to_plain()
+      )
+    ).collect()
+  }
+  fn of_plain(factory: & Factory, plain: Self::Plain) -> Res<Model> {
+    let mut res = Vec::with_capacity( plain.len() ) ;
+    for ( (var, off), cst ) in plain.into_iter() {
+      res.push( (
+        ( try!( Var::of_plain(factory, var) ), off ),
+        try!( Cst::of_plain(factory, cst) )
+      ) )
+    }
+    Ok(res)
+  }
+}
@@ -0,0 +1,169 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Term simplification.
+//!
+//! A bottom-up pass that folds constants, flattens nested conjunctions and
+//! disjunctions, removes neutral elements and double negations, and decides
+//! `ite`s and implications with constant arguments. Unlike
+//! [`partial_eval`](../struct.Factory.html#method.partial_eval), local
+//! definitions are kept.
+//!
+//! Applications of operators whose evaluation fails, typically a division
+//! by zero, are left as is.
+
+use ::{
+  Term, Operator, Factory, CstMaker, OpMaker, AppMaker, BindMaker
+} ;
+use ::real_term::{ Term as RTerm, Cst as RCst } ;
+use ::zip::{ Step, fold } ;
+
+/// Simplifies a term.
+pub fn simplify(factory: & Factory, term: & Term) -> Term {
+  fold( |step| simplify_step(factory, step), term.clone() )
+}
+
+/// Function passed to fold to simplify a term.
+fn simplify_step(factory: & Factory, step: Step<Term>) -> Term {
+  match step {
+    Step::App(sym, args) => factory.app(sym, args),
+    Step::Op(op, args) => simplify_op(factory, op, args),
+    Step::Let(bindings, body) => factory.let_b(bindings, body),
+    Step::Forall(vars, body) => factory.forall(vars, body),
+    Step::Exists(vars, body) => factory.exists(vars, body),
+    Step::C(cst) => factory.mk_cst(cst),
+    Step::V(var) => factory.mk_var(var),
+  }
+}
+
+/// Boolean value of a term, if it is a boolean constant.
+fn bool_of(term: & Term) -> Option<bool> {
+  match * term.get() {
+    RTerm::C(ref cst) => match * cst.get() {
+      RCst::Bool(b) => Some(b),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// True if a term is the numeric constant zero (one if `one`).
+fn is_unit(term: & Term, one: bool) -> bool {
+  use num::{ Zero, One } ;
+  use ::{ Int, Rat } ;
+  match * term.get() {
+    RTerm::C(ref cst) => match * cst.get() {
+      RCst::Int(ref i) => if one { * i == Int::one() } else { i.is_zero() },
+      RCst::Rat(ref r) => if one { * r == Rat::one() } else { r.is_zero() },
+      _ => false,
+    },
+    _ => false,
+  }
+}
+
+/// Simplifies the application of an operator to simplified arguments.
+fn simplify_op(
+  factory: & Factory, op: Operator, mut args: Vec<Term>
+) -> Term {
+  use Operator::* ;
+
+  // Constant folding.
+  let mut csts = Vec::with_capacity( args.len() ) ;
+  for arg in args.iter() {
+    match * arg.get() {
+      RTerm::C(ref cst) => csts.push( cst.clone() ),
+      _ => break,
+    }
+  }
+  if csts.len() == args.len() {
+    if let Ok(cst) = op.eval(factory, csts) {
+      return factory.mk_cst(cst)
+    }
+    return factory.op(op, args)
+  }
+
+  match op {
+
+    Not if args.len() == 1 => {
+      let inner = match * args[0].get() {
+        RTerm::Op(Not, ref kids) if kids.len() == 1 => Some( kids[0].clone() ),
+        _ => None,
+      } ;
+      match inner {
+        Some(inner) => inner,
+        None => factory.op(Not, args),
+      }
+    },
+
+    And | Or => {
+      let neutral = op == And ;
+      let mut kids = Vec::with_capacity( args.len() ) ;
+      let mut to_do = args ;
+      to_do.reverse() ;
+      while let Some(arg) = to_do.pop() {
+        if let RTerm::Op(kid_op, ref grand_kids) = * arg.get() {
+          if kid_op == op {
+            to_do.extend( grand_kids.iter().rev().cloned() ) ;
+            continue
+          }
+        }
+        match bool_of(& arg) {
+          Some(b) if b == neutral => (),
+          Some(_) => return factory.cst(! neutral),
+          None => if ! kids.contains(& arg) { kids.push(arg) },
+        }
+      }
+      match kids.len() {
+        0 => factory.cst(neutral),
+        1 => kids.pop().unwrap(),
+        _ => factory.op(op, kids),
+      }
+    },
+
+    Impl if args.len() == 2 => match (
+      bool_of(& args[0]), bool_of(& args[1])
+    ) {
+      (Some(false), _) | (_, Some(true)) => factory.cst(true),
+      (Some(true), _) => args.pop().unwrap(),
+      (_, Some(false)) => {
+        args.pop() ;
+        simplify_op(factory, Not, args)
+      },
+      _ => factory.op(Impl, args),
+    },
+
+    Ite if args.len() == 3 => match bool_of(& args[0]) {
+      Some(b) => {
+        let els3 = args.pop().unwrap() ;
+        let then = args.pop().unwrap() ;
+        if b { then } else { els3 }
+      },
+      None => if args[1] == args[2] {
+        args.pop().unwrap()
+      } else {
+        factory.op(Ite, args)
+      },
+    },
+
+    // Some argument is not a constant, so some are left.
+    Add | Mul => {
+      let one = op == Mul ;
+      let mut kids: Vec<Term> = args.into_iter().filter(
+        |arg| ! is_unit(arg, one)
+      ).collect() ;
+      if kids.len() == 1 {
+        kids.pop().unwrap()
+      } else {
+        factory.op(op, kids)
+      }
+    },
+
+    _ => factory.op(op, args),
+  }
+}
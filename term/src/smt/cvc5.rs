@@ -0,0 +1,277 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! cvc5 backend.
+//!
+//! `rsmt2` only knows the z3 and cvc4 styles, cvc5 runs with the cvc4 one
+//! and a few more options, see [`conf`](fn.conf.html). A solver runs cvc5
+//! if the name of the binary of its command starts with `cvc5`, whatever
+//! the style it was given: [`adapt`](fn.adapt.html) turns its
+//! configuration into a cvc5 one.
+//!
+//! The cvc4 style has no `check-sat-assuming`,
+//! [`check_sat_assuming`](fn.check_sat_assuming.html) sends the standard
+//! command instead. cvc5 omits the `model` keyword in the answer to
+//! `get-model`, which [`get_model`](fn.get_model.html) accepts. It also
+//! resolves the `(_ as-array <fun>)` values of z3's models to the arrays
+//! the functions they mention define.
+
+use std::path::Path ;
+
+use ::nom::IResult ;
+
+use ::rsmt2::* ;
+use ::rsmt2::errors::* ;
+
+/// Configuration of cvc5: the cvc4 style running `cvc5`, solving
+/// incrementally and producing models.
+pub fn conf() -> SolverConf {
+  SolverConf::cvc4().cmd( "cvc5".to_string() ).option(
+    "--incremental"
+  ).option("--produce-models")
+}
+
+/// True if a command runs cvc5, going by the name of its binary.
+pub fn is_cvc5(cmd: & str) -> bool {
+  Path::new(cmd).file_name().map(
+    |name| name.to_string_lossy().to_lowercase().starts_with("cvc5")
+  ).unwrap_or(false)
+}
+
+/// The configuration of a solver, replaced by the one of cvc5 if its
+/// command runs cvc5. The command and printing success are kept.
+pub fn adapt(conf: SolverConf) -> SolverConf {
+  if ! is_cvc5( conf.get_cmd() ) { return conf }
+  let cvc5 = self::conf().cmd( conf.get_cmd().to_string() ) ;
+  if conf.get_parse_success() { cvc5.print_success() } else { cvc5 }
+}
+
+/// Check-sat with assumptions. Solvers whose style has none, cvc4's, get
+/// the standard `check-sat-assuming` command.
+pub fn check_sat_assuming<
+  'kid, P: ParseSmt2 + 'static, Info, Ident: Sym2Smt<Info>,
+  S: QueryIdent<'kid, P, Info, Ident>
+>(
+  solver: & mut S, idents: & [Ident], info: & Info
+) -> Res<bool> {
+  if solver.solver().conf().get_check_sat_assuming().is_some() {
+    return solver.check_sat_assuming(idents, info)
+  }
+  let err_info = "writing check sat assuming query" ;
+  try!(
+    solver.write(
+      |w| {
+        smtry_io!( err_info => write!(w, "(check-sat-assuming (") ) ;
+        for ident in idents {
+          smtry_io!( err_info =>
+            write!(w, " ") ;
+            ident.sym_to_smt2(w, info)
+          )
+        }
+        smt_cast_io!( err_info => write!(w, " ))\n") )
+      }
+    )
+  ) ;
+  solver.parse_check_sat()
+}
+
+/// Get-model, the `model` keyword of the answer is optional.
+pub fn get_model<'kid, P: ParseSmt2 + 'static, S: Query<'kid, P>>(
+  solver: & mut S
+) -> Res< Vec<(P::Ident, P::Value)> > {
+  try!( solver.print_get_model() ) ;
+  solver.parse(
+    |bytes, parser| match model(bytes, parser) {
+      Some(model) => ( String::new(), Ok(model) ),
+      None => (
+        String::new(), Err(
+          format!(
+            "could not parse model in `{}`",
+            String::from_utf8_lossy(bytes).trim()
+          ).into()
+        )
+      ),
+    }
+  )
+}
+
+/// Definition of a model: name, arguments, sort and value.
+type Def<'a> = (& 'a [u8], & 'a [u8], & 'a [u8], & 'a [u8]) ;
+
+/// Parses a model, `(model (define-fun <id> <args> <sort> <value>) ...)`
+/// where `model` is optional. Definitions with arguments are not part of
+/// the model, they only resolve the `(_ as-array <fun>)` values.
+fn model<P: ParseSmt2>(
+  bytes: & [u8], parser: & P
+) -> Option< Vec<(P::Ident, P::Value)> > {
+  let mut bytes = match token(bytes, b"(") {
+    Some(rest) => rest,
+    None => return None,
+  } ;
+  if let Some(rest) = token(bytes, b"model") { bytes = rest }
+  let mut defs: Vec<Def> = vec![] ;
+  while token(bytes, b")").is_none() {
+    let (def, rest) = match sexp(bytes) {
+      Some(res) => res,
+      None => return None,
+    } ;
+    bytes = rest ;
+    match list(def) {
+      Some(ref elems) if elems.len() == 5 && elems[0] == b"define-fun" => {
+        defs.push( (elems[1], elems[2], elems[3], elems[4]) )
+      },
+      _ => return None,
+    }
+  }
+  let mut model = vec![] ;
+  for & (id, args, sort, value) in defs.iter() {
+    match list(args) {
+      Some(ref args) if args.is_empty() => (),
+      _ => continue,
+    }
+    let array ;
+    let value = match as_array(value) {
+      Some(fun) => {
+        array = match array_of(fun, sort, & defs) {
+          Some(array) => array,
+          None => return None,
+        } ;
+        array.as_bytes()
+      },
+      None => value,
+    } ;
+    let id = match parser.parse_ident(id) {
+      IResult::Done(_, id) => id,
+      _ => return None,
+    } ;
+    let value = match parser.parse_value(value) {
+      IResult::Done(_, value) => value,
+      _ => return None,
+    } ;
+    model.push( (id, value) )
+  }
+  Some(model)
+}
+
+/// The function of a `(_ as-array <fun>)` value.
+fn as_array(value: & [u8]) -> Option<& [u8]> {
+  match list(value) {
+    Some(ref elems) if elems.len() == 3 && elems[0] == b"_" && elems[
+      1
+    ] == b"as-array" => Some(elems[2]),
+    _ => None,
+  }
+}
+
+/// The array of sort `sort` a function of the model defines. z3 writes
+/// them as `ite`s on the index, which become `store`s over a constant
+/// array.
+fn array_of(fun: & [u8], sort: & [u8], defs: & [Def]) -> Option<String> {
+  let (args, mut body) = match defs.iter().find(
+    |& & (id, _, _, _)| id == fun
+  ) {
+    Some(& (_, args, _, body)) => (args, body),
+    None => return None,
+  } ;
+  let param = match list(args) {
+    Some(ref args) if args.len() == 1 => match list(args[0]) {
+      Some(ref decl) if decl.len() == 2 => decl[0],
+      _ => return None,
+    },
+    _ => return None,
+  } ;
+  let mut stores = vec![] ;
+  loop {
+    let (idx, val, els) = match list(body) {
+      Some(ref elems) if elems.len() == 4 && elems[0] == b"ite" => {
+        match list(elems[1]) {
+          Some(ref eq) if eq.len() == 3 && eq[0] == b"=" => {
+            if eq[1] == param { (eq[2], elems[2], elems[3]) }
+            else if eq[2] == param { (eq[1], elems[2], elems[3]) }
+            else { return None }
+          },
+          _ => return None,
+        }
+      },
+      _ => break,
+    } ;
+    stores.push( (idx, val) ) ;
+    body = els
+  }
+  let mut array = format!(
+    "((as const {}) {})",
+    String::from_utf8_lossy(sort), String::from_utf8_lossy(body)
+  ) ;
+  for & (idx, val) in stores.iter().rev() {
+    array = format!(
+      "(store {} {} {})", array,
+      String::from_utf8_lossy(idx), String::from_utf8_lossy(val)
+    )
+  }
+  Some(array)
+}
+
+/// Splits the s-expression after some whitespaces from what follows.
+fn sexp(bytes: & [u8]) -> Option<(& [u8], & [u8])> {
+  let bytes = skip(bytes) ;
+  let mut depth = 0usize ;
+  let mut index = 0 ;
+  while index < bytes.len() {
+    match bytes[index] {
+      quote @ b'|' | quote @ b'"' => match bytes[index + 1 ..].iter().position(
+        |byte| * byte == quote
+      ) {
+        Some(len) => index += len + 1,
+        None => return None,
+      },
+      b'(' => depth += 1,
+      b')' => if depth == 0 { break } else {
+        depth -= 1 ;
+        if depth == 0 { index += 1 ; break }
+      },
+      byte => if depth == 0 && (byte as char).is_whitespace() { break },
+    }
+    index += 1
+  }
+  if index == 0 || depth > 0 { None } else {
+    Some( (& bytes[.. index], & bytes[index ..]) )
+  }
+}
+
+/// The elements of an s-expression, if it is a list.
+fn list(bytes: & [u8]) -> Option< Vec<& [u8]> > {
+  let mut bytes = match token(bytes, b"(") {
+    Some(rest) => rest,
+    None => return None,
+  } ;
+  let mut elems = vec![] ;
+  while token(bytes, b")").is_none() {
+    let (elem, rest) = match sexp(bytes) {
+      Some(res) => res,
+      None => return None,
+    } ;
+    elems.push(elem) ;
+    bytes = rest
+  }
+  Some(elems)
+}
+
+/// Skips leading whitespaces.
+fn skip(bytes: & [u8]) -> & [u8] {
+  let start = bytes.iter().position(
+    |byte| ! (* byte as char).is_whitespace()
+  ).unwrap_or( bytes.len() ) ;
+  & bytes[start..]
+}
+
+/// Parses a token after some whitespaces, returns what follows.
+fn token<'a>(bytes: & 'a [u8], tkn: & [u8]) -> Option<& 'a [u8]> {
+  let bytes = skip(bytes) ;
+  if bytes.starts_with(tkn) { Some(& bytes[tkn.len()..]) } else { None }
+}
@@ -0,0 +1,73 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! MathSAT backend.
+//!
+//! `rsmt2` has no MathSAT style, and launches solvers with the options of
+//! their style, `-in -smt2` for z3, which MathSAT does not take. MathSAT
+//! thus runs with the z3 style through a wrapper script ignoring them and
+//! producing interpolants, see [`conf`](fn.conf.html). Interpolation
+//! queries are in [`Interpolate`](../trait.Interpolate.html).
+
+use std::collections::hash_map::DefaultHasher ;
+use std::hash::{ Hash, Hasher } ;
+use std::io::Write ;
+use std::fs::{ self, OpenOptions } ;
+use std::os::unix::fs::OpenOptionsExt ;
+use std::sync::atomic::{ AtomicUsize, Ordering } ;
+
+use ::rsmt2::* ;
+use ::rsmt2::errors::* ;
+
+/// Counts the wrapper scripts written, for their temporary names.
+static WRITTEN: AtomicUsize = AtomicUsize::new(0) ;
+
+/// Configuration running MathSAT with some command, `mathsat` for the one
+/// of [`mathsat_cmd`](../fn.mathsat_cmd.html).
+///
+/// The command of the configuration is a wrapper script in the temporary
+/// directory, one per MathSAT command. It is written on the first call, and
+/// replaced atomically so that solvers running it are not disturbed.
+pub fn conf(cmd: & str) -> Res<SolverConf> {
+  let script = format!(
+    "#!/bin/sh\n\
+    # MathSAT, without the options of the z3 style.\n\
+    exec '{}' -interpolation=true\n",
+    cmd.replace('\'', "'\\''")
+  ) ;
+  let mut hasher = DefaultHasher::new() ;
+  script.hash(& mut hasher) ;
+  let dir = ::std::env::temp_dir() ;
+  let path = dir.join( format!("kino_mathsat_{:x}.sh", hasher.finish()) ) ;
+  if ! path.exists() {
+    let tmp = dir.join(
+      format!(
+        "kino_mathsat_{:x}_{}_{}.tmp", hasher.finish(),
+        ::std::process::id(), WRITTEN.fetch_add(1, Ordering::SeqCst)
+      )
+    ) ;
+    let written = OpenOptions::new().write(true).create(true).truncate(
+      true
+    ).mode(0o755).open(& tmp).and_then(
+      |mut file| file.write_all( script.as_bytes() )
+    ).and_then(
+      |()| fs::rename(& tmp, & path)
+    ) ;
+    if let Err(e) = written {
+      return Err(
+        format!(
+          "could not write MathSAT wrapper `{}`: {}", path.display(), e
+        ).into()
+      )
+    }
+  }
+  Ok(
+    SolverConf::z3().cmd( path.to_string_lossy().into_owned() )
+  )
+}
@@ -0,0 +1,480 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Solvers racing on each query.
+//!
+//! A [`MultiSolver`](struct.MultiSolver.html) wraps a solver and spawns
+//! *racers*: one with its configuration, and one per additional command.
+//! The configuration of a racer depends on its command, see
+//! [`racer_conf`](fn.racer_conf.html). Each racer runs in its own thread
+//! and receives all the commands. Satisfiability checks (`check-sat` and
+//! `check-sat-assuming`) are sent to all of them, the first answer wins.
+//! The winner is then *pinned*: the `get-` queries go to it only until the
+//! next check, so that models and values are consistent with the answer to
+//! the check.
+//!
+//! The racers that lose are **not** cancelled: solvers cannot be
+//! interrupted through their input, and killing them would end the race.
+//! They keep working on the check, their answers are dropped and they
+//! catch up with the commands sent in the meantime once they are done. The
+//! solver wrapped only provides the configuration and the parser, it
+//! receives no command. The racers receive the prelude of the solver, if
+//! any, when they are spawned, see
+//! [`mk_with_prelude`](struct.MultiSolver.html#method.mk_with_prelude).
+//!
+//! In *comparison* mode, see
+//! [`compare`](struct.MultiSolver.html#method.compare), satisfiability
+//! checks wait for the answers of all the racers instead. If they disagree,
+//! the commands sent so far are dumped in a file and the check fails. This
+//! is meant to catch bugs in the printing of terms or in the unrollers, by
+//! comparing two solvers on the same queries.
+
+use std::io::Write ;
+use std::fs::File ;
+use std::path::Path ;
+use std::thread ;
+use std::sync::mpsc::{ channel, Sender, Receiver } ;
+
+use ::rsmt2::* ;
+use ::rsmt2::errors::* ;
+use ::rsmt2::internals::{ SolverBasic, SolverPrims } ;
+
+/// Command sent to a racer.
+enum Cmd {
+  /// Not a query, with the number of `success`es it yields.
+  Plain(Vec<u8>, usize),
+  /// Query, with its index.
+  Query(usize, Vec<u8>),
+}
+
+/// Message from a racer.
+enum Msg {
+  /// Answer of a racer to the query of some index.
+  Answer(usize, usize, Vec<u8>),
+  /// A racer failed and stopped.
+  Dead(usize, String),
+}
+
+/// What to do with the next answer fetched.
+enum Answer {
+  /// Nothing to fetch.
+  Plain,
+  /// Wait for the first answer to the query of some index.
+  Race(usize),
+  /// Wait for all the answers to the query of some index and compare them.
+  Compare(usize),
+  /// Answer received, replay it.
+  Replay(Vec<u8>),
+}
+
+/// Wraps a solver, racing several solvers on each query.
+pub struct MultiSolver<S> {
+  /// Solver wrapped.
+  solver: S,
+  /// Command channels of the racers.
+  racers: Vec< Sender<Cmd> >,
+  /// Commands of the racers.
+  cmds: Vec<String>,
+  /// Messages of the racers.
+  answers: Receiver<Msg>,
+  /// Number of racers still running.
+  alive: usize,
+  /// Index of the last query.
+  query: usize,
+  /// What to do with the next answer.
+  answer: Answer,
+  /// Index of the last satisfiability check.
+  check: usize,
+  /// Racer that won the last check, receives the `get-` queries.
+  pinned: Option<usize>,
+  /// Prefix of the dump files, if comparing the racers.
+  compare: Option<String>,
+  /// Commands sent so far, if comparing the racers.
+  script: Vec<u8>,
+}
+impl<S> MultiSolver<S> {
+  /// Wraps a solver. Spawns a racer with its configuration, and one per
+  /// command in `cmds`, see [`racer_conf`](fn.racer_conf.html).
+  pub fn mk<
+    'kid, P: ParseSmt2 + Clone + Send + 'static
+  >(solver: S, cmds: & [String]) -> Res<Self>
+  where S: SolverBasic<'kid, P> {
+    Self::mk_with_prelude(solver, cmds, "")
+  }
+  /// Same as [`mk`](#method.mk), the racers receive a prelude as soon as
+  /// they are spawned, see [`prelude`](../fn.prelude.html).
+  pub fn mk_with_prelude<
+    'kid, P: ParseSmt2 + Clone + Send + 'static
+  >(
+    mut solver: S, cmds: & [String], prelude: & str
+  ) -> Res<Self>
+  where S: SolverBasic<'kid, P> {
+    let conf = solver.solver().conf().clone() ;
+    let parser = solver.parser().clone() ;
+    let (answer_sender, answers) = channel() ;
+    let mut all = vec![ conf.get_cmd().to_string() ] ;
+    all.extend( cmds.iter().cloned() ) ;
+    let mut racers = Vec::with_capacity( all.len() ) ;
+    for (racer, cmd) in all.iter().enumerate() {
+      let own_conf = racer_conf(& conf, cmd) ;
+      // Checks with assumptions are written in the style of `conf`.
+      let assuming = conf.get_check_sat_assuming().is_some() && (
+        own_conf.get_check_sat_assuming().is_none()
+      ) ;
+      let kid = try!(
+        Kid::mk(own_conf).chain_err(
+          || format!("while spawning racer `{}`", cmd)
+        )
+      ) ;
+      let (sender, receiver) = channel() ;
+      let (answer_sender, parser) = (answer_sender.clone(), parser.clone()) ;
+      let prelude = prelude.to_string() ;
+      thread::spawn(
+        move || race(
+          racer, kid, parser, & prelude, assuming, receiver, answer_sender
+        )
+      ) ;
+      racers.push(sender)
+    }
+    Ok(
+      MultiSolver {
+        solver: solver,
+        alive: racers.len(),
+        racers: racers,
+        cmds: all,
+        answers: answers,
+        query: 0,
+        answer: Answer::Plain,
+        check: 0,
+        pinned: None,
+        compare: None,
+        script: vec![],
+      }
+    )
+  }
+
+  /// Compares the racers instead of racing them: satisfiability checks
+  /// fail if the racers disagree, after dumping the commands sent so far in
+  /// file `<prefix>_<query>.smt2`.
+  pub fn compare(mut self, prefix: String) -> Self {
+    self.compare = Some(prefix) ;
+    self
+  }
+
+  /// Checks all the racers agree on the answer to a query, dumps the
+  /// commands sent so far otherwise.
+  fn agree(
+    & mut self, query: usize, mut answers: Vec<(usize, Vec<u8>)>
+  ) -> Res<()> {
+    let agree = match answers.first() {
+      Some(& (_, ref first)) => answers[1..].iter().all(
+        |& (_, ref answer)| trimmed(answer) == trimmed(first)
+      ),
+      None => return Err( "all the racers are gone".into() ),
+    } ;
+    if agree {
+      let (racer, answer) = answers.swap_remove(0) ;
+      self.pinned = Some(racer) ;
+      self.answer = Answer::Replay(answer) ;
+      return Ok(())
+    }
+    let mut blah = String::new() ;
+    for & (racer, ref answer) in answers.iter() {
+      blah.push_str(
+        & format!("; `{}` says `{}`\n", self.cmds[racer], trimmed(answer))
+      )
+    }
+    let path = format!(
+      "{}_{}.smt2", self.compare.as_ref().map(
+        |prefix| & prefix[..]
+      ).unwrap_or("disagreement"), query
+    ) ;
+    let dumped = File::create(& path).and_then(
+      |mut file| file.write_all( blah.as_bytes() ).and_then(
+        |()| file.write_all(& self.script)
+      )
+    ) ;
+    match dumped {
+      Ok(()) => Err(
+        format!(
+          "solvers disagree on query {}, dumped to `{}`:\n{}",
+          query, path, blah.trim()
+        ).into()
+      ),
+      Err(e) => Err(
+        format!(
+          "solvers disagree on query {}, could not dump it to `{}`: {}\n{}",
+          query, path, e, blah.trim()
+        ).into()
+      ),
+    }
+  }
+
+  /// True if some command is a query.
+  fn is_query(cmd: & [u8]) -> bool {
+    match ::std::str::from_utf8(cmd) {
+      Ok(cmd) => {
+        let cmd = cmd.trim_left() ;
+        cmd.starts_with("(check-sat") || cmd.starts_with("(get-")
+      },
+      Err(_) => false,
+    }
+  }
+  /// True if some command is a satisfiability check.
+  fn is_check(cmd: & [u8]) -> bool {
+    match ::std::str::from_utf8(cmd) {
+      Ok(cmd) => cmd.trim_left().starts_with("(check-sat"),
+      Err(_) => false,
+    }
+  }
+}
+
+/// Configuration of the racer running some command, given the
+/// configuration of the solver wrapped.
+///
+/// If the name of the binary starts with `z3` or `cvc4` and the style of
+/// `conf` is not the same, the racer uses the default configuration of its
+/// style. If it starts with `cvc5`, it uses the one of
+/// [`cvc5`](cvc5/fn.conf.html). Otherwise it uses `conf`. Either way, it
+/// runs `cmd` and prints success if `conf` does.
+pub fn racer_conf(conf: & SolverConf, cmd: & str) -> SolverConf {
+  let name = Path::new(cmd).file_name().map(
+    |name| name.to_string_lossy().to_lowercase()
+  ).unwrap_or( String::new() ) ;
+  let style = if name.starts_with("z3") {
+    Some( SolverStyle::Z3 )
+  } else if name.starts_with("cvc4") {
+    Some( SolverStyle::CVC4 )
+  } else {
+    None
+  } ;
+  match style {
+    Some(style) => if style.to_string() != conf.style().to_string() {
+      let racer = style.default() ;
+      let racer = if conf.get_parse_success() {
+        racer.print_success()
+      } else { racer } ;
+      return racer.cmd( cmd.to_string() )
+    },
+    None => (),
+  }
+  super::cvc5::adapt( conf.clone().cmd( cmd.to_string() ) )
+}
+
+/// An answer as a string, without surrounding whitespaces.
+fn trimmed(answer: & [u8]) -> String {
+  String::from_utf8_lossy(answer).trim().to_string()
+}
+
+/// Runs a racer until its command channel is closed or it fails, after
+/// sending it the prelude. If `assuming`, the checks with assumptions it
+/// receives are `check-sat`s with arguments, and it gets standard
+/// `check-sat-assuming`s instead.
+fn race<P: ParseSmt2 + 'static>(
+  racer: usize, mut kid: Kid, parser: P, prelude: & str, assuming: bool,
+  cmds: Receiver<Cmd>, answers: Sender<Msg>
+) {
+  let res = match PlainSolver::mk(& mut kid, parser) {
+    Ok(mut solver) => super::prelude(& mut solver, prelude).and_then(
+      |()| serve(racer, & mut solver, assuming, cmds, & answers)
+    ),
+    Err(e) => Err(e),
+  } ;
+  if let Err(e) = res {
+    let _ = answers.send( Msg::Dead( racer, format!("{}", e) ) ) ;
+  }
+  let _ = kid.kill() ;
+}
+
+/// Sends the commands received to a racer, and its answers back.
+fn serve<'kid, P: ParseSmt2 + 'static>(
+  racer: usize, solver: & mut PlainSolver<'kid, P>, assuming: bool,
+  cmds: Receiver<Cmd>, answers: & Sender<Msg>
+) -> Res<()> {
+  for cmd in cmds.iter() {
+    match cmd {
+      Cmd::Plain(bytes, successes) => {
+        try!( send(solver, & bytes) ) ;
+        for _ in 0..successes {
+          try!( solver.parse_success() )
+        }
+      },
+      Cmd::Query(index, bytes) => {
+        if assuming {
+          try!( send(solver, & standard_assuming(& bytes)) )
+        } else {
+          try!( send(solver, & bytes) )
+        }
+        let answer = try!(
+          solver.parse( |bytes, _| ( String::new(), Ok( bytes.to_vec() ) ) )
+        ) ;
+        if answers.send( Msg::Answer(racer, index, answer) ).is_err() {
+          break
+        }
+      },
+    }
+  }
+  Ok(())
+}
+
+/// Turns a `check-sat` with arguments, the check with assumptions of the z3
+/// style, into a standard `check-sat-assuming`. Other commands are left as
+/// is.
+fn standard_assuming(cmd: & [u8]) -> Vec<u8> {
+  let txt = String::from_utf8_lossy(cmd) ;
+  let txt = txt.trim() ;
+  if txt.starts_with("(check-sat") && ! txt.starts_with("(check-sat-")
+  && txt.ends_with(')') {
+    let args = txt[ "(check-sat".len() .. txt.len() - 1 ].trim() ;
+    if ! args.is_empty() {
+      return format!("(check-sat-assuming ( {} ))\n", args).into_bytes()
+    }
+  }
+  cmd.to_vec()
+}
+
+/// Sends some bytes to a racer.
+fn send<'kid, P: ParseSmt2 + 'static>(
+  solver: & mut PlainSolver<'kid, P>, cmd: & [u8]
+) -> Res<()> {
+  solver.write(
+    |w| w.write_all(cmd).chain_err(
+      || ErrorKind::IoError( "writing command to racer".into() )
+    )
+  )
+}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>
+> SolverBasic<'kid, P> for MultiSolver<S> {
+  fn fetch(& mut self) -> Res<()> {
+    let (query, compare) = match self.answer {
+      Answer::Race(query) => (query, false),
+      Answer::Compare(query) => (query, true),
+      _ => return Ok(()),
+    } ;
+    let mut answers = vec![] ;
+    loop {
+      match self.answers.recv() {
+        Ok( Msg::Answer(racer, index, answer) ) => if index == query {
+          if ! compare {
+            if query == self.check { self.pinned = Some(racer) }
+            self.answer = Answer::Replay(answer) ;
+            return Ok(())
+          }
+          answers.push( (racer, answer) ) ;
+          if answers.len() == self.alive {
+            return self.agree(query, answers)
+          }
+        },
+        Ok( Msg::Dead(racer, e) ) => {
+          if compare || self.pinned == Some(racer) {
+            return Err(
+              format!("solver `{}` failed: {}", self.cmds[racer], e).into()
+            )
+          }
+          self.alive -= 1 ;
+          if self.alive == 0 {
+            return Err(
+              format!("all the racers failed, the last one with {}", e).into()
+            )
+          }
+        },
+        Err(_) => return Err( "all the racers are gone".into() ),
+      }
+    }
+  }
+  fn write<
+    F: Fn(& mut Write) -> Res<()>
+  >(& mut self, f: F) -> Res<()> {
+    let mut cmd: Vec<u8> = Vec::with_capacity(100) ;
+    try!( f(& mut cmd) ) ;
+    if self.compare.is_some() {
+      self.script.extend_from_slice(& cmd) ;
+      if cmd.last() != Some(& b'\n') { self.script.push(b'\n') }
+    }
+    if Self::is_query(& cmd) {
+      self.query += 1 ;
+      let check = Self::is_check(& cmd) ;
+      if check {
+        self.check = self.query ;
+        self.pinned = None
+      }
+      self.answer = if self.compare.is_some() && check {
+        Answer::Compare(self.query)
+      } else {
+        Answer::Race(self.query)
+      } ;
+      match self.pinned {
+        Some(racer) => {
+          let _ = self.racers[racer].send(
+            Cmd::Query(self.query, cmd.clone())
+          ) ;
+        },
+        None => for racer in self.racers.iter() {
+          let _ = racer.send( Cmd::Query(self.query, cmd.clone()) ) ;
+        },
+      }
+    } else {
+      let successes = if self.solver.solver().conf().get_parse_success() {
+        self.answer = Answer::Replay( b"success".to_vec() ) ;
+        super::command_count( & String::from_utf8_lossy(& cmd) )
+      } else {
+        self.answer = Answer::Plain ;
+        0
+      } ;
+      for racer in self.racers.iter() {
+        let _ = racer.send( Cmd::Plain(cmd.clone(), successes) ) ;
+      }
+    }
+    Ok(())
+  }
+  fn comment(& mut self, txt: & str) -> Res<()> {
+    self.solver.comment(txt)
+  }
+  fn parser(& self) -> & P {
+    self.solver.parser()
+  }
+  fn as_ref(& self) -> & [u8] {
+    match self.answer {
+      Answer::Replay(ref answer) => & answer[..],
+      _ => & [],
+    }
+  }
+  fn solver(& mut self) -> & mut PlainSolver<'kid, P> {
+    self.solver.solver()
+  }
+}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>
+> SolverPrims<'kid, P> for MultiSolver<S> {}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>
+> Solver<'kid, P> for MultiSolver<S> {}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>
+> Query<'kid, P> for MultiSolver<S> {}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>,
+  Info, Ident: Sym2Smt<Info>
+> QueryIdent<'kid, P, Info, Ident> for MultiSolver<S> {}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>,
+  Info, Expr: Expr2Smt<Info>
+> QueryExpr<'kid, P, Info, Expr> for MultiSolver<S> {}
+
+impl<
+  'kid, P: ParseSmt2 + Clone + Send + 'static, S: SolverBasic<'kid, P>,
+  Expr: Expr2Smt<P::I>
+> QueryExprInfo<'kid, P, Expr> for MultiSolver<S> {}
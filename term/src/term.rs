@@ -11,6 +11,7 @@
 
 use std::io ;
 use std::fmt ;
+use std::collections::{ HashMap, HashSet } ;
 
 use errors::* ;
 
@@ -18,7 +19,7 @@ use base::{
   StateWritable, Writable, SVarWriter, PrintSmt2, PrintVmt, SymWritable,
   Offset2, HConsed, HConsign, HConser, State, SymPrintStyle
 } ;
-use typ::Type ;
+use typ::{ Type, Rat } ;
 use sym::Sym ;
 use cst::Cst ;
 use var::{ Var, VarMaker } ;
@@ -26,6 +27,7 @@ use self::RealTerm::* ;
 
 /// Standard operators.
 #[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Operator {
   /// Equality.
   Eq,
@@ -51,6 +53,18 @@ pub enum Operator {
   Mul,
   /// Division operator.
   Div,
+  /// Integer modulo operator, the result is non-negative.
+  Mod,
+  /// Integer remainder operator, the result has the sign of the divisor.
+  Rem,
+  /// Integer absolute value operator.
+  Abs,
+  /// Conversion from Int to Real.
+  ToReal,
+  /// Conversion from Real to Int, the floor of its argument.
+  ToInt,
+  /// True iff a Real is an integer.
+  IsInt,
   /// Less or equal operator.
   Le,
   /// Greater or equal operator.
@@ -59,6 +73,55 @@ pub enum Operator {
   Lt,
   /// Greater than operator.
   Gt,
+  /// Bitvector bitwise negation.
+  BvNot,
+  /// Bitvector arithmetic negation.
+  BvNeg,
+  /// Bitvector bitwise conjunction.
+  BvAnd,
+  /// Bitvector bitwise disjunction.
+  BvOr,
+  /// Bitvector bitwise exclusive disjunction.
+  BvXor,
+  /// Bitvector addition.
+  BvAdd,
+  /// Bitvector subtraction.
+  BvSub,
+  /// Bitvector multiplication.
+  BvMul,
+  /// Bitvector unsigned division.
+  BvUdiv,
+  /// Bitvector unsigned remainder.
+  BvUrem,
+  /// Bitvector shift left.
+  BvShl,
+  /// Bitvector logical shift right.
+  BvLshr,
+  /// Bitvector unsigned less than.
+  BvUlt,
+  /// Bitvector unsigned less or equal.
+  BvUle,
+  /// Bitvector unsigned greater than.
+  BvUgt,
+  /// Bitvector unsigned greater or equal.
+  BvUge,
+  /// Bitvector signed less than.
+  BvSlt,
+  /// Bitvector signed less or equal.
+  BvSle,
+  /// Bitvector signed greater than.
+  BvSgt,
+  /// Bitvector signed greater or equal.
+  BvSge,
+  /// Bitvector concatenation.
+  Concat,
+  /// Bitvector extraction of the bits between two indices, inclusive. The
+  /// first index is the highest one.
+  Extract(usize, usize),
+  /// Array read.
+  Select,
+  /// Array write.
+  Store,
 }
 
 impl Operator {
@@ -68,15 +131,20 @@ impl Operator {
     use self::Operator::* ;
     match * self {
       // Unary.
-      Not => Some(1u8),
+      Not | Abs | ToReal | ToInt | IsInt |
+      BvNot | BvNeg | Extract(_, _) => Some(1u8),
       // Binary.
-      Div | Le | Ge | Lt | Gt => Some(2),
+      Div | Mod | Rem | Le | Ge | Lt | Gt |
+      BvSub | BvUdiv | BvUrem | BvShl | BvLshr |
+      BvUlt | BvUle | BvUgt | BvUge | BvSlt | BvSle | BvSgt | BvSge |
+      Concat | Select => Some(2),
       // Ternary.
-      Ite => Some(3),
+      Ite | Store => Some(3),
       // N-ary.
       Eq | And | Or | Impl | Xor |
       Distinct |
-      Add | Sub | Mul => None,
+      Add | Sub | Mul |
+      BvAnd | BvOr | BvXor | BvAdd | BvMul => None,
     }
   }
 
@@ -234,6 +302,57 @@ impl Operator {
         }
       },
 
+      Mod | Rem | Abs => {
+        let arity = self.arity().unwrap_or(0) as usize ;
+        if sig.len() != arity {
+          return Err( (
+            None,
+            format!(
+              "operator {} expects {} argument(s), got {}",
+              self, arity, sig.len()
+            )
+          ) )
+        } ;
+        for (index, typ) in sig.iter().enumerate() {
+          if * typ != Type::Int {
+            return Err( (
+              Some( vec![index] ),
+              format!(
+                "argument {} of operator {}: expected Int, got {}",
+                index + 1, self, typ
+              )
+            ) )
+          }
+        }
+        Ok(Type::Int)
+      },
+
+      ToReal | ToInt | IsInt => {
+        let (arg, res) = match * self {
+          ToReal => (Type::Int, Type::Rat),
+          ToInt => (Type::Rat, Type::Int),
+          _ => (Type::Rat, Type::Bool),
+        } ;
+        if sig.len() != 1 {
+          Err( (
+            None,
+            format!(
+              "operator {} expects 1 argument, got {}", self, sig.len()
+            )
+          ) )
+        } else if sig[0] != arg {
+          Err( (
+            Some( vec![0] ),
+            format!(
+              "argument of operator {} should have sort {}, got {}",
+              self, arg, sig[0]
+            )
+          ) )
+        } else {
+          Ok(res)
+        }
+      },
+
       Le | Ge | Lt | Gt => {
         let mut sig = sig.iter() ;
         if let Some(first) = sig.next() {
@@ -271,6 +390,143 @@ impl Operator {
         }
       },
 
+      BvNot | BvNeg | BvAnd | BvOr | BvXor |
+      BvAdd | BvSub | BvMul | BvUdiv | BvUrem | BvShl | BvLshr |
+      BvUlt | BvUle | BvUgt | BvUge | BvSlt | BvSle | BvSgt | BvSge |
+      Concat | Extract(_, _) => self.bv_type_check(sig),
+
+      Select => if sig.len() != 2 {
+        Err( (
+          None,
+          format!("operator select expects 2 arguments, got {}", sig.len())
+        ) )
+      } else {
+        match sig[0] {
+          Type::Array(ref idx, ref elm) => if sig[1] != ** idx {
+            Err( (
+              Some( vec![1] ),
+              format!(
+                "second argument of select should have sort {}, got {}",
+                idx, sig[1]
+              )
+            ) )
+          } else {
+            Ok( (** elm).clone() )
+          },
+          ref typ => Err( (
+            Some( vec![0] ),
+            format!(
+              "first argument of select should be an array, got {}", typ
+            )
+          ) ),
+        }
+      },
+
+      Store => if sig.len() != 3 {
+        Err( (
+          None,
+          format!("operator store expects 3 arguments, got {}", sig.len())
+        ) )
+      } else {
+        match sig[0] {
+          Type::Array(ref idx, ref elm) => if sig[1] != ** idx {
+            Err( (
+              Some( vec![1] ),
+              format!(
+                "second argument of store should have sort {}, got {}",
+                idx, sig[1]
+              )
+            ) )
+          } else if sig[2] != ** elm {
+            Err( (
+              Some( vec![2] ),
+              format!(
+                "third argument of store should have sort {}, got {}",
+                elm, sig[2]
+              )
+            ) )
+          } else {
+            Ok( sig[0].clone() )
+          },
+          ref typ => Err( (
+            Some( vec![0] ),
+            format!(
+              "first argument of store should be an array, got {}", typ
+            )
+          ) ),
+        }
+      },
+
+    }
+  }
+
+  /// Type checks a bitvector operator.
+  fn bv_type_check(& self, sig: & [Type]) -> Result<
+    Type, (Option<Vec<usize>>, String)
+  > {
+    use Operator::* ;
+    let mut widths = Vec::with_capacity( sig.len() ) ;
+    for (index, typ) in sig.iter().enumerate() {
+      match * typ {
+        Type::BitVec(width) => widths.push(width),
+        _ => return Err( (
+          Some( vec![index] ),
+          format!(
+            "argument {} of operator {}: expected a bitvector, got {}",
+            index + 1, self, typ
+          )
+        ) ),
+      }
+    }
+    match self.arity() {
+      Some(arity) => if sig.len() != arity as usize {
+        return Err( (
+          None,
+          format!(
+            "operator {} expects {} argument(s), got {}",
+            self, arity, sig.len()
+          )
+        ) )
+      },
+      None => if sig.is_empty() {
+        return Err( (
+          None,
+          format!("operator {} is applied to nothing", self)
+        ) )
+      },
+    }
+    match * self {
+      Concat => Ok( Type::BitVec(widths[0] + widths[1]) ),
+      Extract(high, low) => if low <= high && high < widths[0] {
+        Ok( Type::BitVec(high - low + 1) )
+      } else {
+        Err( (
+          Some( vec![0] ),
+          format!(
+            "operator {} cannot be applied to a bitvector of width {}",
+            self, widths[0]
+          )
+        ) )
+      },
+      _ => {
+        for (index, width) in widths.iter().enumerate().skip(1) {
+          if * width != widths[0] {
+            return Err( (
+              Some( vec![index] ),
+              format!(
+                "argument {} of operator {}: \
+                  first argument(s) have type {}, got {}",
+                index + 1, self, sig[0], sig[index]
+              )
+            ) )
+          }
+        }
+        match * self {
+          BvUlt | BvUle | BvUgt | BvUge |
+          BvSlt | BvSle | BvSgt | BvSge => Ok(Type::Bool),
+          _ => Ok( sig[0].clone() ),
+        }
+      },
     }
   }
 
@@ -509,6 +765,75 @@ impl Operator {
         }
       },
 
+      Mod | Rem => if args.len() == 2 {
+        let res = if * self == Mod {
+          args[0].get().modulo( args[1].get() )
+        } else {
+          args[0].get().rem( args[1].get() )
+        } ;
+        match res {
+          Ok(cst) => Ok( factory.mk_rcst(cst) ),
+          Err(cst) => Err(
+            ErrorKind::OpTypeError(
+              * self, cst.typ(), Type::Int, Some(
+                format!("(found `{}`, or a zero divisor)", cst)
+              )
+            ).into()
+          ),
+        }
+      } else {
+        Err( ErrorKind::OpArityError(* self, args.len(), "2").into() )
+      },
+
+      Abs => if args.len() == 1 {
+        match args[0].get().abs() {
+          Ok(cst) => Ok( factory.mk_rcst(cst) ),
+          Err(cst) => Err(
+            ErrorKind::OpTypeError(
+              Abs, cst.typ(), Type::Int, Some( format!("(found `{}`)", cst) )
+            ).into()
+          ),
+        }
+      } else {
+        Err( ErrorKind::OpArityError(Abs, args.len(), "1").into() )
+      },
+
+      ToReal => if args.len() == 1 {
+        match * args[0].get() {
+          RCst::Int(ref i) => Ok(
+            factory.cst( Rat::from_integer( i.clone() ) )
+          ),
+          ref arg => Err(
+            ErrorKind::OpTypeError(
+              ToReal, arg.typ(), Type::Int, Some(
+                format!("(found `{}`)", arg)
+              )
+            ).into()
+          ),
+        }
+      } else {
+        Err( ErrorKind::OpArityError(ToReal, args.len(), "1").into() )
+      },
+
+      ToInt | IsInt => if args.len() == 1 {
+        match * args[0].get() {
+          RCst::Rat(ref r) => if * self == ToInt {
+            Ok( factory.cst( r.floor().to_integer() ) )
+          } else {
+            Ok( factory.cst( r.is_integer() ) )
+          },
+          ref arg => Err(
+            ErrorKind::OpTypeError(
+              * self, arg.typ(), Type::Rat, Some(
+                format!("(found `{}`)", arg)
+              )
+            ).into()
+          ),
+        }
+      } else {
+        Err( ErrorKind::OpArityError(* self, args.len(), "1").into() )
+      },
+
       Le => if args.len() == 2 {
         match * args[0].get() {
           RCst::Int(ref lhs) => match * args[1].get() {
@@ -645,6 +970,149 @@ impl Operator {
           Err( ErrorKind::OpArityError(Gt, args.len(), "2").into() )
       },
 
+      BvNot | BvNeg | BvAnd | BvOr | BvXor |
+      BvAdd | BvSub | BvMul | BvUdiv | BvUrem | BvShl | BvLshr |
+      BvUlt | BvUle | BvUgt | BvUge | BvSlt | BvSle | BvSgt | BvSge |
+      Concat | Extract(_, _) => self.bv_eval(factory, args),
+
+      Select => if args.len() != 2 {
+        Err( ErrorKind::OpArityError(Select, args.len(), "2").into() )
+      } else {
+        match args[0].get().select( args[1].get() ) {
+          Some(cst) => Ok( factory.mk_rcst(cst) ),
+          None => Err(
+            format!(
+              "in evaluation of select: expected an array, got `{}`",
+              args[0].get()
+            ).into()
+          ),
+        }
+      },
+
+      Store => if args.len() != 3 {
+        Err( ErrorKind::OpArityError(Store, args.len(), "3").into() )
+      } else {
+        match args[0].get().store(
+          args[1].get().clone(), args[2].get().clone()
+        ) {
+          Some(cst) => Ok( factory.mk_rcst(cst) ),
+          None => Err(
+            format!(
+              "in evaluation of store: expected an array, got `{}`",
+              args[0].get()
+            ).into()
+          ),
+        }
+      },
+
+    }
+  }
+
+  /// Evaluates a bitvector operator. Arithmetic is modulo `2^width`,
+  /// division by zero follows the SMT-LIB semantics.
+  fn bv_eval(& self, factory: & ::Factory, args: Vec<Cst>) -> Res<Cst> {
+    use num::{ Zero, One, Integer, ToPrimitive, BigUint } ;
+    use num::bigint::Sign ;
+    use typ::Int ;
+    use Operator::* ;
+    use ::real_term::Cst as RCst ;
+
+    let sig: Vec<Type> = args.iter().map( |arg| arg.get().typ() ).collect() ;
+    let typ = match self.bv_type_check(& sig) {
+      Ok(typ) => typ,
+      Err( (_, blah) ) => bail!(
+        "in evaluation of operator `{}`: {}", self, blah
+      ),
+    } ;
+    let mut width = 0 ;
+    let mut vals = Vec::with_capacity( args.len() ) ;
+    for arg in args.iter() {
+      if let RCst::BitVec(w, ref v) = * arg.get() {
+        if vals.is_empty() { width = w }
+        vals.push( v.clone() )
+      }
+    }
+
+    let modulus: Int = Int::one() << width ;
+    let half: Int = modulus.clone() >> 1 ;
+    let norm = |v: Int| v.mod_floor(& modulus) ;
+    let signed = |v: & Int| if * v >= half {
+      v - & modulus
+    } else { v.clone() } ;
+    let bits = |v: & Int| v.to_biguint().unwrap_or_else( BigUint::zero ) ;
+    let of_bits = |v: BigUint| Int::from_biguint(Sign::Plus, v) ;
+    let shift = |v: & Int| v.to_usize().and_then(
+      |shift| if shift < width { Some(shift) } else { None }
+    ) ;
+
+    let res = match * self {
+      BvNot => & modulus - Int::one() - & vals[0],
+      BvNeg => norm( - & vals[0] ),
+      BvAnd => of_bits(
+        vals.iter().skip(1).fold( bits(& vals[0]), |acc, v| acc & bits(v) )
+      ),
+      BvOr => of_bits(
+        vals.iter().skip(1).fold( bits(& vals[0]), |acc, v| acc | bits(v) )
+      ),
+      BvXor => of_bits(
+        vals.iter().skip(1).fold( bits(& vals[0]), |acc, v| acc ^ bits(v) )
+      ),
+      BvAdd => norm(
+        vals.iter().skip(1).fold( vals[0].clone(), |acc, v| acc + v )
+      ),
+      BvMul => norm(
+        vals.iter().skip(1).fold( vals[0].clone(), |acc, v| acc * v )
+      ),
+      BvSub => norm( & vals[0] - & vals[1] ),
+      BvUdiv => if vals[1].is_zero() {
+        & modulus - Int::one()
+      } else {
+        & vals[0] / & vals[1]
+      },
+      BvUrem => if vals[1].is_zero() {
+        vals[0].clone()
+      } else {
+        & vals[0] % & vals[1]
+      },
+      BvShl => match shift(& vals[1]) {
+        Some(shift) => norm( vals[0].clone() << shift ),
+        None => Int::zero(),
+      },
+      BvLshr => match shift(& vals[1]) {
+        Some(shift) => vals[0].clone() >> shift,
+        None => Int::zero(),
+      },
+      BvUlt => return Ok( factory.cst( vals[0] < vals[1] ) ),
+      BvUle => return Ok( factory.cst( vals[0] <= vals[1] ) ),
+      BvUgt => return Ok( factory.cst( vals[0] > vals[1] ) ),
+      BvUge => return Ok( factory.cst( vals[0] >= vals[1] ) ),
+      BvSlt => return Ok(
+        factory.cst( signed(& vals[0]) < signed(& vals[1]) )
+      ),
+      BvSle => return Ok(
+        factory.cst( signed(& vals[0]) <= signed(& vals[1]) )
+      ),
+      BvSgt => return Ok(
+        factory.cst( signed(& vals[0]) > signed(& vals[1]) )
+      ),
+      BvSge => return Ok(
+        factory.cst( signed(& vals[0]) >= signed(& vals[1]) )
+      ),
+      Concat => match sig[1] {
+        Type::BitVec(low) => (vals[0].clone() << low) + & vals[1],
+        _ => bail!("unreachable, concat of non-bitvectors"),
+      },
+      Extract(high, low) => (vals[0].clone() >> low).mod_floor(
+        & ( Int::one() << (high - low + 1) )
+      ),
+      _ => bail!("operator `{}` is not a bitvector operator", self),
+    } ;
+
+    match typ {
+      Type::BitVec(width) => Ok( factory.cst( (width, res) ) ),
+      _ => bail!(
+        "operator `{}` should produce a bitvector, got {}", self, typ
+      ),
     }
   }
 }
@@ -662,28 +1130,57 @@ impl Writable for Operator {
   fn write(
     & self, writer: & mut io::Write
   ) -> io::Result<()> {
-    write!(
-      writer,
-      "{}",
-      match * self {
-        Operator::Eq => "=",
-        Operator::Ite => "ite",
-        Operator::Not => "not",
-        Operator::And => "and",
-        Operator::Or => "or",
-        Operator::Impl => "=>",
-        Operator::Xor => "xor",
-        Operator::Distinct => "distinct",
-        Operator::Add => "+",
-        Operator::Sub => "-",
-        Operator::Mul => "*",
-        Operator::Div => "/",
-        Operator::Le => "<=",
-        Operator::Ge => ">=",
-        Operator::Lt => "<",
-        Operator::Gt => ">",
-      }
-    )
+    let s = match * self {
+      Operator::Eq => "=",
+      Operator::Ite => "ite",
+      Operator::Not => "not",
+      Operator::And => "and",
+      Operator::Or => "or",
+      Operator::Impl => "=>",
+      Operator::Xor => "xor",
+      Operator::Distinct => "distinct",
+      Operator::Add => "+",
+      Operator::Sub => "-",
+      Operator::Mul => "*",
+      Operator::Div => "/",
+      Operator::Mod => "mod",
+      Operator::Rem => "rem",
+      Operator::Abs => "abs",
+      Operator::ToReal => "to_real",
+      Operator::ToInt => "to_int",
+      Operator::IsInt => "is_int",
+      Operator::Le => "<=",
+      Operator::Ge => ">=",
+      Operator::Lt => "<",
+      Operator::Gt => ">",
+      Operator::BvNot => "bvnot",
+      Operator::BvNeg => "bvneg",
+      Operator::BvAnd => "bvand",
+      Operator::BvOr => "bvor",
+      Operator::BvXor => "bvxor",
+      Operator::BvAdd => "bvadd",
+      Operator::BvSub => "bvsub",
+      Operator::BvMul => "bvmul",
+      Operator::BvUdiv => "bvudiv",
+      Operator::BvUrem => "bvurem",
+      Operator::BvShl => "bvshl",
+      Operator::BvLshr => "bvlshr",
+      Operator::BvUlt => "bvult",
+      Operator::BvUle => "bvule",
+      Operator::BvUgt => "bvugt",
+      Operator::BvUge => "bvuge",
+      Operator::BvSlt => "bvslt",
+      Operator::BvSle => "bvsle",
+      Operator::BvSgt => "bvsgt",
+      Operator::BvSge => "bvsge",
+      Operator::Concat => "concat",
+      Operator::Select => "select",
+      Operator::Store => "store",
+      Operator::Extract(high, low) => return write!(
+        writer, "(_ extract {} {})", high, low
+      ),
+    } ;
+    write!(writer, "{}", s)
   }
 }
 
@@ -782,7 +1279,7 @@ impl fmt::Display for RealTerm {
 pub type Term = HConsed<RealTerm> ;
 
 /// A stateful term. Either one-state or two-state.
-#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+#[derive(Debug,Clone,PartialEq,Eq,PartialOrd,Ord,Hash)]
 pub enum STerm {
   /// A one-state term. Stores the state (init) and next (trans) version. That
   /// is, the second element is the bump of the first.
@@ -809,6 +1306,25 @@ impl STerm {
       STerm::Two(ref t) => t,
     }
   }
+  /// Size of the next version of a term: its number of nodes, sub-terms
+  /// appearing several times are counted several times.
+  pub fn size(& self) -> usize {
+    let mut res = 0 ;
+    let mut stack = vec![ self.next() ] ;
+    while let Some(term) = stack.pop() {
+      res += 1 ;
+      match * term.get() {
+        Op(_, ref kids) | App(_, ref kids) => stack.extend( kids.iter() ),
+        Let(ref bindings, ref body) => {
+          for & (_, ref term) in bindings.iter() { stack.push(term) }
+          stack.push(body)
+        },
+        Forall(_, ref body) | Exists(_, ref body) => stack.push(body),
+        V(_) | C(_) => (),
+      }
+    }
+    res
+  }
 }
 
 impl fmt::Display for STerm {
@@ -823,67 +1339,113 @@ impl fmt::Display for STerm {
 /// Hash cons table for terms.
 pub type TermConsign = HConsign<RealTerm> ;
 
+/// Terms bind the subterms appearing more than once with `let`s, so that
+/// they are written once. See [`let_share`](fn.let_share.html).
 impl<Svw: SVarWriter<Sym>> StateWritable<Sym, Svw> for Term {
   fn write(
     & self, writer: & mut io::Write, sv_writer: & Svw, style: SymPrintStyle
   ) -> io::Result<()> {
-    let mut stack = vec![ (true, vec![ self.clone() ]) ] ;
-    loop {
-      if let Some( (is_first, mut to_do) ) = stack.pop() {
-
-        if let Some( term ) = to_do.pop() {
-          stack.push( (false, to_do) ) ;
-          if ! is_first { try!( write!(writer, " ") ) } ;
-          match term.get() {
-            & V(ref var) => {
-              try!( var.write(writer, sv_writer, style) )
-            },
-            & C(ref cst) => {
-              try!( cst.write(writer) )
-            },
-            & App(ref sym, ref args) => {
+    let shared = ::lets::shared(self) ;
+    let names = ::lets::share_names(self, shared.len()) ;
+    let mut bound = HashMap::with_capacity( shared.len() ) ;
+    // Kids come before their parents, so their name is known.
+    for (node, name) in shared.into_iter().zip( names.into_iter() ) {
+      try!( write!(writer, "(let ( (") ) ;
+      try!( write_share_name(writer, & name, style) ) ;
+      try!( write!(writer, " ") ) ;
+      try!( write_term(& node, writer, sv_writer, style, & bound) ) ;
+      try!( write!(writer, ") ) ") ) ;
+      bound.insert(node, name) ;
+    }
+    try!( write_term(self, writer, sv_writer, style, & bound) ) ;
+    for _ in 0..bound.len() {
+      try!( write!(writer, ")") )
+    }
+    Ok(())
+  }
+}
+
+/// Writes the name of a shared subterm.
+fn write_share_name(
+  writer: & mut io::Write, name: & str, style: SymPrintStyle
+) -> io::Result<()> {
+  match style {
+    SymPrintStyle::Internal => write!(writer, "| {}|", name),
+    SymPrintStyle::External => write!(writer, "|{}|", name),
+  }
+}
+
+/// Writes a term, its strict subterms bound in `bound` are written as their
+/// name.
+fn write_term<Svw: SVarWriter<Sym>>(
+  root: & Term, writer: & mut io::Write, sv_writer: & Svw,
+  style: SymPrintStyle, bound: & HashMap<Term, String>
+) -> io::Result<()> {
+  let mut stack = vec![ (true, vec![ root.clone() ]) ] ;
+  loop {
+    if let Some( (is_first, mut to_do) ) = stack.pop() {
+
+      if let Some( term ) = to_do.pop() {
+        stack.push( (false, to_do) ) ;
+        if ! is_first { try!( write!(writer, " ") ) } ;
+        if term != * root {
+          if let Some(name) = bound.get(& term) {
+            try!( write_share_name(writer, name, style) ) ;
+            continue
+          }
+        }
+        match term.get() {
+          & V(ref var) => {
+            try!( var.write(writer, sv_writer, style) )
+          },
+          & C(ref cst) => {
+            try!( cst.write(writer) )
+          },
+          & App(ref sym, ref args) => {
+            try!( write!(writer, "(|") ) ;
+            try!( sym.write(writer, style) ) ;
+            try!( write!(writer, "| ") ) ;
+            let mut args = args.clone() ;
+            args.reverse() ;
+            stack.push( (true, args) )
+          },
+          & Op(ref op, ref args) => {
+            try!( write!(writer, "(") ) ;
+            try!( op.write(writer) ) ;
+            try!( write!(writer, " ") ) ;
+            let mut args = args.clone() ;
+            args.reverse() ;
+            stack.push( (true, args) )
+          },
+          & Let(ref binding, ref term) => {
+            try!( write!(writer, "(let ( ") ) ;
+            for & (ref sym, ref term) in binding {
               try!( write!(writer, "(|") ) ;
               try!( sym.write(writer, style) ) ;
               try!( write!(writer, "| ") ) ;
-              let mut args = args.clone() ;
-              args.reverse() ;
-              stack.push( (true, args) )
-            },
-            & Op(ref op, ref args) => {
-              try!( write!(writer, "(") ) ;
-              try!( op.write(writer) ) ;
-              try!( write!(writer, " ") ) ;
-              let mut args = args.clone() ;
-              args.reverse() ;
-              stack.push( (true, args) )
-            },
-            & Let(ref binding, ref term) => {
-              try!( write!(writer, "(let ( ") ) ;
-              for & (ref sym, ref term) in binding {
-                try!( write!(writer, "(|") ) ;
-                try!( sym.write(writer, style) ) ;
-                try!( write!(writer, "| ") ) ;
-                try!( term.write(writer, sv_writer, style) ) ;
-                try!( write!(writer, ") ") ) ;
-              } ;
+              try!( term.write(writer, sv_writer, style) ) ;
               try!( write!(writer, ") ") ) ;
-              stack.push( (true, vec![term.clone()]) )
-            }
-            _ => unimpl!(),
-          } ;
-        } else {
-          // Don't close paren for the last element of the stack.
-          if ! stack.is_empty() {
+            } ;
+            try!( write!(writer, ") ") ) ;
+            // Shared subterms are not bound below a `let`, which can
+            // shadow their variables.
+            try!( term.write(writer, sv_writer, style) ) ;
             try!( write!(writer, ")") )
           }
-        }
-
+          _ => unimpl!(),
+        } ;
       } else {
-        break
+        // Don't close paren for the last element of the stack.
+        if ! stack.is_empty() {
+          try!( write!(writer, ")") )
+        }
       }
-    } ;
-    Ok(())
-  }
+
+    } else {
+      break
+    }
+  } ;
+  Ok(())
 }
 
 impl PrintVmt for Term {
@@ -1070,6 +1632,55 @@ pub fn debump<F: Factory>(f: & F, term: Term) -> Res<Term> {
 }
 
 
+/// Free variables of a term: its state variables, and its non-stateful
+/// variables not bound by a `let` or a quantifier.
+pub fn vars_of(term: & Term) -> HashSet<Var> {
+  let mut res = HashSet::new() ;
+  collect_vars(term, & mut vec![], & mut res) ;
+  res
+}
+
+/// State variables of a term.
+pub fn state_vars_of(term: & Term) -> HashSet<Var> {
+  vars_of(term).into_iter().filter(
+    |var| var.state().is_some()
+  ).collect()
+}
+
+/// Adds the free variables of a term to `res`. `bound` contains the symbols
+/// bound by the enclosing `let`s and quantifiers.
+fn collect_vars(term: & Term, bound: & mut Vec<Sym>, res: & mut HashSet<Var>) {
+  match * term.get() {
+    V(ref var) => {
+      let is_bound = match * var.get() {
+        ::var::RealVar::Var(ref sym) => bound.contains(sym),
+        ::var::RealVar::SVar(_, _) => false,
+      } ;
+      if ! is_bound { res.insert( var.clone() ) ; () }
+    },
+    C(_) => (),
+    Op(_, ref kids) | App(_, ref kids) => for kid in kids.iter() {
+      collect_vars(kid, bound, res)
+    },
+    // Bindings are parallel: definitions are not in their scope.
+    Let(ref bindings, ref body) => {
+      for & (_, ref def) in bindings.iter() {
+        collect_vars(def, bound, res)
+      }
+      let len = bound.len() ;
+      bound.extend( bindings.iter().map( |& (ref sym, _)| sym.clone() ) ) ;
+      collect_vars(body, bound, res) ;
+      bound.truncate(len)
+    },
+    Forall(ref vars, ref body) | Exists(ref vars, ref body) => {
+      let len = bound.len() ;
+      bound.extend( vars.iter().map( |& (ref sym, _)| sym.clone() ) ) ;
+      collect_vars(body, bound, res) ;
+      bound.truncate(len)
+    },
+  }
+}
+
 
 
 /// Zipper stuff.
@@ -1584,8 +2195,10 @@ pub mod zip2 {
 /// Term evaluator.
 pub mod eval {
   use ::{
-    Type, Cst, Sym, Term, Offset2, Factory, UnTermOps
+    Type, Cst, Sym, Term, Operator, Offset2, Factory, UnTermOps,
+    CstMaker, OpMaker, AppMaker, BindMaker
   } ;
+  use ::real_term::{ Term as RTerm, Cst as RCst } ;
   use ::errors::* ;
   use std::collections::HashMap ;
   use ::zip::{ Step, fold_info, extract } ;
@@ -1643,11 +2256,11 @@ pub mod eval {
     }
   }
 
-  /// Evaluates a term.
-  pub fn eval(
-    factory: & Factory, term: & Term, offset: & Offset2,
-    model: & ::Model, scope: Sym
-  ) -> Res<Cst> {
+  /// Maps the variables of a model to their value, for the terms of some
+  /// offset.
+  fn model_map<'a>(
+    factory: & Factory, offset: & Offset2, model: & 'a ::Model
+  ) -> HashMap<Term, & 'a Cst> {
     let mut map = HashMap::new() ;
     for & ( (ref v, ref o), ref cst ) in model.iter() {
       if let Some(ref o) = * o {
@@ -1665,6 +2278,15 @@ pub mod eval {
         map.insert( v, cst ) ;
       }
     } ;
+    map
+  }
+
+  /// Evaluates a term.
+  pub fn eval(
+    factory: & Factory, term: & Term, offset: & Offset2,
+    model: & ::Model, scope: Sym
+  ) -> Res<Cst> {
+    let map = model_map(factory, offset, model) ;
     fold_info(
       |step, bindings, quantified| eval_term(
         factory, & map, step, bindings, quantified, & scope
@@ -1672,4 +2294,184 @@ pub mod eval {
       term
     )
   }
+
+  /// Evaluates some terms in the same model.
+  ///
+  /// Same as calling [`eval`](fn.eval.html) on each term, but the model is
+  /// only processed once and each distinct subterm is only evaluated once:
+  /// the values of the subterms are cached and shared by all the terms.
+  /// Let-bindings are evaluated as a whole, their subterms are not cached.
+  pub fn eval_all(
+    factory: & Factory, terms: & [Term], offset: & Offset2,
+    model: & ::Model, scope: Sym
+  ) -> Res< Vec<Cst> > {
+    let map = model_map(factory, offset, model) ;
+    let mut cache: HashMap<Term, Cst> = HashMap::new() ;
+    let mut res = Vec::with_capacity( terms.len() ) ;
+    for term in terms.iter() {
+      // Subterms to evaluate, with `true` if their kids are evaluated.
+      let mut stack = vec![ (term, false) ] ;
+      while let Some( (term, kids_done) ) = stack.pop() {
+        if cache.contains_key(term) { continue }
+        let value = match * term.get() {
+          RTerm::Op(op, ref kids) => if kids_done {
+            let args = kids.iter().map( |kid| cache[kid].clone() ).collect() ;
+            try!( op.eval(factory, args) )
+          } else {
+            stack.push( (term, true) ) ;
+            for kid in kids.iter() {
+              stack.push( (kid, false) )
+            }
+            continue
+          },
+          RTerm::C(ref cst) => cst.clone(),
+          RTerm::V(ref var) => try!(
+            eval_term(
+              factory, & map, Step::V( var.clone() ), & [], & [], & scope
+            )
+          ),
+          _ => try!(
+            fold_info(
+              |step, bindings, quantified| eval_term(
+                factory, & map, step, bindings, quantified, & scope
+              ),
+              term
+            )
+          ),
+        } ;
+        cache.insert( term.clone(), value ) ;
+      }
+      res.push( cache[term].clone() )
+    }
+    Ok(res)
+  }
+
+  /// Boolean constant as a term.
+  fn bool_term(factory: & Factory, b: bool) -> Term {
+    factory.cst(b)
+  }
+
+  /// Boolean value of a term, if it is a boolean constant.
+  fn bool_of(term: & Term) -> Option<bool> {
+    match * term.get() {
+      RTerm::C(ref cst) => match * cst.get() {
+        RCst::Bool(b) => Some(b),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// Simplifies the application of an operator to partially evaluated
+  /// arguments.
+  fn simplify(
+    factory: & Factory, op: Operator, mut args: Vec<Term>
+  ) -> Res<Term> {
+    use Operator::* ;
+
+    let mut csts = Vec::with_capacity( args.len() ) ;
+    for arg in args.iter() {
+      match * arg.get() {
+        RTerm::C(ref cst) => csts.push( cst.clone() ),
+        _ => break,
+      }
+    }
+    if csts.len() == args.len() {
+      return op.eval(factory, csts).map( |cst| factory.mk_cst(cst) )
+    }
+
+    match op {
+      Ite if args.len() == 3 => match bool_of(& args[0]) {
+        Some(b) => {
+          let els3 = args.pop().unwrap() ;
+          let then = args.pop().unwrap() ;
+          Ok( if b { then } else { els3 } )
+        },
+        None => if args[1] == args[2] {
+          Ok( args.pop().unwrap() )
+        } else {
+          Ok( factory.op(Ite, args) )
+        },
+      },
+
+      And | Or => {
+        // Decides the value of the conjunction (disjunction) on its own.
+        let absorbing = op == Or ;
+        let mut kids = Vec::with_capacity( args.len() ) ;
+        for arg in args.into_iter() {
+          match bool_of(& arg) {
+            Some(b) => if b == absorbing {
+              return Ok( bool_term(factory, absorbing) )
+            },
+            None => kids.push(arg),
+          }
+        }
+        if kids.len() == 1 {
+          Ok( kids.pop().unwrap() )
+        } else {
+          Ok( factory.op(op, kids) )
+        }
+      },
+
+      Impl if args.len() == 2 => match (
+        bool_of(& args[0]), bool_of(& args[1])
+      ) {
+        (Some(false), _) | (_, Some(true)) => Ok( bool_term(factory, true) ),
+        (Some(true), _) => Ok( args.pop().unwrap() ),
+        (_, Some(false)) => {
+          args.pop() ;
+          Ok( factory.op( Not, args ) )
+        },
+        _ => Ok( factory.op(Impl, args) ),
+      },
+
+      _ => Ok( factory.op(op, args) ),
+    }
+  }
+
+  /// Function passed to fold to partially evaluate a term.
+  fn partial_eval_term(
+    factory: & Factory,
+    model: & HashMap<Term, & Cst>,
+    step: Step<Term>,
+    bindings: & [ HashMap<Sym, Term> ]
+  ) -> Res<Term> {
+    match step {
+      App(sym, args) => Ok( factory.app(sym, args) ),
+      Op(op, args) => simplify(factory, op, args),
+      Let(_, body) => Ok(body),
+      Forall(vars, body) => Ok( factory.forall(vars, body) ),
+      Exists(vars, body) => Ok( factory.exists(vars, body) ),
+      C(cst) => Ok( factory.mk_cst(cst) ),
+      V(r_var) => {
+        let sym = r_var.sym().clone() ;
+        let var = factory.mk_var(r_var) ;
+        match model.get(& var) {
+          Some(cst) => Ok( factory.mk_cst( (* cst).clone() ) ),
+          None => match extract(& sym, bindings) {
+            Some(term) => Ok( term.clone() ),
+            // Quantified, or not in the model: stays symbolic.
+            None => Ok(var),
+          },
+        }
+      },
+    }
+  }
+
+  /// Partially evaluates a term.
+  ///
+  /// Unlike [`eval`](fn.eval.html), variables that are not in the model do
+  /// not get a default value, they stay symbolic. The result is the residual
+  /// term: constants are propagated, the branches decided by the model are
+  /// pruned, and let-bindings are inlined. It is a constant iff the model
+  /// decides the value of the term.
+  pub fn partial_eval(
+    factory: & Factory, term: & Term, offset: & Offset2, model: & ::Model
+  ) -> Res<Term> {
+    let map = model_map(factory, offset, model) ;
+    fold_info(
+      |step, bindings, _| partial_eval_term(factory, & map, step, bindings),
+      term
+    )
+  }
 }
\ No newline at end of file
@@ -16,40 +16,47 @@ use base::{ Writable } ;
 use real_term::Cst ;
 
 /// A primitive type.
-#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+#[derive(Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Type {
   /// Bool type.
   Bool,
   /// Int type.
   Int,
   /// Rat type.
-  Rat
+  Rat,
+  /// Bitvector type of some width.
+  BitVec(usize),
+  /// Array type, from an index type to an element type.
+  Array(Box<Type>, Box<Type>),
 }
 
 impl fmt::Display for Type {
   fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
-    write!(
-      fmt, "{}",
-      match * self {
-        Type::Bool => "Bool",
-        Type::Int => "Int",
-        Type::Rat => "Real",
-      }
-    )
+    match * self {
+      Type::BitVec(width) => write!(fmt, "(_ BitVec {})", width),
+      Type::Array(ref idx, ref elm) => write!(fmt, "(Array {} {})", idx, elm),
+      _ => write!(fmt, "{}", self.to_str()),
+    }
   }
 }
 
 impl Type {
-  /// String representation of a type.
+  /// String representation of a type. Does not mention the width of
+  /// bitvectors nor the index and element types of arrays, use `Display`
+  /// for that.
   #[inline]
   pub fn to_str(& self) -> & 'static str {
     match * self {
       Type::Bool => "Bool",
       Type::Int => "Int",
       Type::Rat => "Real",
+      Type::BitVec(_) => "BitVec",
+      Type::Array(_, _) => "Array",
     }
   }
-  /// Default value of a type.
+  /// Default value of a type. The default array maps all indices to the
+  /// default value of its element type.
   #[inline]
   pub fn default(& self) -> Cst {
     match * self {
@@ -63,6 +70,12 @@ impl Type {
           Int::parse_bytes(b"1", 10).unwrap(),
         )
       ),
+      Type::BitVec(width) => Cst::BitVec(
+        width, Int::parse_bytes(b"0", 10).unwrap()
+      ),
+      Type::Array(ref idx, ref elm) => Cst::Array(
+        (** idx).clone(), Box::new( elm.default() ), vec![]
+      ),
     }
   }
 }
@@ -70,7 +83,7 @@ impl Type {
 impl Writable for Type {
   #[inline]
   fn write(& self, writer: & mut io::Write) -> io::Result<()> {
-    write!(writer, "{}", self.to_str())
+    write!(writer, "{}", self)
   }
 }
 
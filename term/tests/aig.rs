@@ -0,0 +1,87 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests for and-inverter graphs.
+
+extern crate term ;
+
+use term::{
+  Factory, Term, State, Int, One, SymMaker, VarMaker, CstMaker, OpMaker,
+  Operator
+} ;
+use term::aig::{ Aig, TRUE, FALSE, neg, is_propositional } ;
+
+/// Creates a state variable.
+fn svar(factory: & Factory, name: & str, state: State) -> Term {
+  factory.svar( factory.sym(name), state )
+}
+
+#[test]
+fn propositional() {
+  let factory = Factory::mk() ;
+  let x = svar(& factory, "x", State::Curr) ;
+  let y = svar(& factory, "y", State::Next) ;
+  let tru: Term = factory.cst(true) ;
+  assert!(
+    is_propositional(
+      & factory.op( Operator::Impl, vec![ x.clone(), factory.not(y) ] )
+    )
+  ) ;
+  assert!(
+    is_propositional(
+      & factory.op( Operator::Eq, vec![ x.clone(), tru ] )
+    )
+  ) ;
+  let one: Term = factory.cst( Int::one() ) ;
+  assert!(
+    ! is_propositional(
+      & factory.op( Operator::Eq, vec![ x, one ] )
+    )
+  )
+}
+
+#[test]
+fn frames_and_sharing() {
+  let factory = Factory::mk() ;
+  let x = svar(& factory, "x", State::Curr) ;
+  let x_next = svar(& factory, "x", State::Next) ;
+  let mut aig = Aig::mk() ;
+  // The next state at 0 is the current one at 1.
+  assert_eq!(
+    aig.lit(& x_next, 0).unwrap(), aig.lit(& x, 1).unwrap()
+  ) ;
+  let conj = factory.op( Operator::And, vec![ x.clone(), x_next.clone() ] ) ;
+  let lit = aig.lit(& conj, 0).unwrap() ;
+  let swapped = factory.op( Operator::And, vec![ x_next, x.clone() ] ) ;
+  assert_eq!( aig.lit(& swapped, 0).unwrap(), lit ) ;
+  assert_eq!( aig.ands().len(), 1 ) ;
+  // Trivial conjunctions need no gate.
+  let x_lit = aig.lit(& x, 0).unwrap() ;
+  assert_eq!( aig.and(x_lit, neg(x_lit)), FALSE ) ;
+  assert_eq!( aig.and(x_lit, TRUE), x_lit ) ;
+  assert_eq!( aig.ands().len(), 1 )
+}
+
+#[test]
+fn dimacs() {
+  let factory = Factory::mk() ;
+  let x = svar(& factory, "x", State::Curr) ;
+  let y = svar(& factory, "y", State::Curr) ;
+  let mut aig = Aig::mk() ;
+  let lit = aig.lit(
+    & factory.op( Operator::Or, vec![ x, y ] ), 0
+  ).unwrap() ;
+  let mut bytes = vec![] ;
+  aig.write_dimacs(& mut bytes, & [ lit ]).unwrap() ;
+  // `(or x y)` is `(not (and (not x) (not y)))`.
+  assert_eq!(
+    String::from_utf8(bytes).unwrap(),
+    "p cnf 3 4\n-3 -1 0\n-3 -2 0\n3 1 2 0\n-3 0\n"
+  )
+}
@@ -12,7 +12,8 @@
 extern crate term ;
 
 use term::{
-  BigInt, Factory, Cst, Term, CstMaker, SymMaker, Offset2
+  BigInt, Rat, Factory, Cst, Term, Var, Type, Operator,
+  CstMaker, SymMaker, VarMaker, OpMaker, Offset2
 } ;
 
 /// Creates a constant integer term.
@@ -25,6 +26,26 @@ pub fn int_cst(factory: & Factory, bytes: & [u8]) -> Cst {
   factory.cst( BigInt::parse_bytes(bytes, 10u32).unwrap() )
 }
 
+/// Creates a constant rational term.
+pub fn rat(factory: & Factory, num: & [u8], den: & [u8]) -> Term {
+  factory.cst(
+    Rat::new(
+      BigInt::parse_bytes(num, 10u32).unwrap(),
+      BigInt::parse_bytes(den, 10u32).unwrap()
+    )
+  )
+}
+
+/// Creates a constant bitvector `Cst`.
+pub fn bv_cst(factory: & Factory, width: usize, bytes: & [u8]) -> Cst {
+  factory.cst( (width, BigInt::parse_bytes(bytes, 10u32).unwrap()) )
+}
+
+/// Creates a constant bitvector term.
+pub fn bv(factory: & Factory, width: usize, bytes: & [u8]) -> Term {
+  factory.cst( bv_cst(factory, width, bytes) )
+}
+
 
 #[test]
 fn and() {
@@ -281,3 +302,230 @@ fn gt() {
     Err(s) => panic!("{}", s),
   }
 }
+
+#[test]
+fn partial_and() {
+  let factory = Factory::mk() ;
+  let v: Term = factory.var( factory.sym("v") ) ;
+  let term = factory.and( vec![ factory.cst(true), v.clone() ] ) ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  match factory.partial_eval(& term, & offset, & model) {
+    Ok(residual) => assert_eq!(residual, v),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn partial_ite() {
+  let factory = Factory::mk() ;
+  let c: Var = factory.var( factory.sym("c") ) ;
+  let x: Term = factory.var( factory.sym("x") ) ;
+  let term = factory.ite(
+    factory.mk_var( c.clone() ), x, int(& factory, b"42")
+  ) ;
+  let model = vec![ ( (c, None), factory.cst(false) ) ] ;
+  let offset = Offset2::init() ;
+  match factory.partial_eval(& term, & offset, & model) {
+    Ok(residual) => assert_eq!(residual, int(& factory, b"42")),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn eval_all() {
+  let factory = Factory::mk() ;
+  let x: Var = factory.var( factory.sym("x") ) ;
+  let x_term = factory.mk_var( x.clone() ) ;
+  let succ = factory.op(
+    Operator::Add, vec![ x_term.clone(), int(& factory, b"1") ]
+  ) ;
+  let terms = vec![
+    succ.clone(),
+    factory.op( Operator::Gt, vec![ succ, int(& factory, b"3") ] ),
+    x_term,
+  ] ;
+  let model = vec![ ( (x, None), int_cst(& factory, b"3") ) ] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval_all(& terms, & offset, & model, scope) {
+    Ok(csts) => assert_eq!(
+      csts, vec![
+        int_cst(& factory, b"4"), factory.cst(true), int_cst(& factory, b"3")
+      ]
+    ),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn modulo() {
+  let factory = Factory::mk() ;
+  let term = factory.modulo(
+    int(& factory, b"-7"), int(& factory, b"-3")
+  ) ;
+  let res: Cst = int_cst(& factory, b"2") ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn rem() {
+  let factory = Factory::mk() ;
+  let term = factory.rem(
+    int(& factory, b"7"), int(& factory, b"-3")
+  ) ;
+  let res: Cst = int_cst(& factory, b"-1") ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn abs() {
+  let factory = Factory::mk() ;
+  let term = factory.abs( int(& factory, b"-7") ) ;
+  let res: Cst = int_cst(& factory, b"7") ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn to_int() {
+  let factory = Factory::mk() ;
+  let term = factory.to_int( rat(& factory, b"-7", b"2") ) ;
+  let res: Cst = int_cst(& factory, b"-4") ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn is_int() {
+  let factory = Factory::mk() ;
+  let term = factory.is_int(
+    factory.to_real( int(& factory, b"3") )
+  ) ;
+  let res: Cst = factory.cst(true) ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn bvadd() {
+  let factory = Factory::mk() ;
+  let term = factory.op(
+    Operator::BvAdd, vec![ bv(& factory, 8, b"250"), bv(& factory, 8, b"10") ]
+  ) ;
+  let res = bv_cst(& factory, 8, b"4") ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn bvslt() {
+  let factory = Factory::mk() ;
+  let term = factory.op(
+    Operator::BvSlt, vec![ bv(& factory, 4, b"15"), bv(& factory, 4, b"1") ]
+  ) ;
+  let res: Cst = factory.cst(true) ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn extract_concat() {
+  let factory = Factory::mk() ;
+  let concat = factory.op(
+    Operator::Concat, vec![ bv(& factory, 4, b"10"), bv(& factory, 4, b"3") ]
+  ) ;
+  let term = factory.op( Operator::Extract(5, 2), vec![ concat ] ) ;
+  let res = bv_cst(& factory, 4, b"8") ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  match factory.eval(& term, & offset, & model, scope) {
+    Ok(cst) => assert_eq!(res, cst),
+    Err(s) => panic!("{}", s),
+  }
+}
+
+#[test]
+fn select_store() {
+  let factory = Factory::mk() ;
+  let array: Term = factory.cst(
+    factory.mk_rcst(
+      Type::Array( Box::new(Type::Int), Box::new(Type::Int) ).default()
+    )
+  ) ;
+  let array = factory.op(
+    Operator::Store, vec![ array, int(& factory, b"3"), int(& factory, b"7") ]
+  ) ;
+  let model = vec![] ;
+  let offset = Offset2::init() ;
+  let scope = factory.sym("whatever") ;
+  let checks = [ (& b"3"[..], & b"7"[..]), (& b"4"[..], & b"0"[..]) ] ;
+  for & (idx, val) in checks.iter() {
+    let term = factory.op(
+      Operator::Select, vec![ array.clone(), int(& factory, idx) ]
+    ) ;
+    match factory.eval(& term, & offset, & model, scope.clone()) {
+      Ok(cst) => assert_eq!(int_cst(& factory, val), cst),
+      Err(s) => panic!("{}", s),
+    }
+  }
+}
+
+#[test]
+fn rat_approx() {
+  let factory = Factory::mk() ;
+  let rat = |num: & [u8], den: & [u8]| -> Cst {
+    factory.cst(
+      Rat::new(
+        BigInt::parse_bytes(num, 10u32).unwrap(),
+        BigInt::parse_bytes(den, 10u32).unwrap()
+      )
+    )
+  } ;
+  assert_eq!(
+    rat(b"1234567", b"99991").approx(4), Some( "12.3468".to_string() )
+  ) ;
+  assert_eq!( rat(b"-1", b"3").approx(2), Some( "-0.33".to_string() ) ) ;
+  assert_eq!( rat(b"2", b"3").approx(0), Some( "1".to_string() ) ) ;
+  assert_eq!( rat(b"-1", b"1000").approx(2), Some( "0.00".to_string() ) ) ;
+  assert_eq!( rat(b"4", b"2").approx(3), None ) ;
+  assert_eq!( int_cst(& factory, b"7").approx(3), None )
+}
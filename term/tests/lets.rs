@@ -0,0 +1,151 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests for let-inlining and let-introduction.
+
+extern crate term ;
+
+use term::{
+  Factory, Term, Type, Operator, SymMaker, VarMaker, OpMaker, BindMaker,
+  PrintVmt, let_expand, let_share
+} ;
+
+/// Creates a variable.
+fn var(factory: & Factory, name: & str) -> Term {
+  factory.var( factory.sym(name) )
+}
+
+#[test]
+fn expand_inlines() {
+  let factory = Factory::mk() ;
+  let x = var(& factory, "x") ;
+  let y = var(& factory, "y") ;
+  let v = var(& factory, "v") ;
+  let sum = factory.op( Operator::Add, vec![ x.clone(), y.clone() ] ) ;
+  // (let ( (v (+ x y)) ) (<= v v))
+  let term = factory.let_b(
+    vec![ ( factory.sym("v"), sum.clone() ) ],
+    factory.le( v.clone(), v )
+  ) ;
+  assert_eq!(
+    let_expand(& factory, & term), factory.le( sum.clone(), sum )
+  )
+}
+
+#[test]
+fn expand_avoids_capture() {
+  let factory = Factory::mk() ;
+  let x = var(& factory, "x") ;
+  let n = var(& factory, "n") ;
+  let v = var(& factory, "v") ;
+  // (let ( (v x) (w n) ) (forall ( (n Int) ) (<= v w)))
+  let quantified = factory.forall(
+    vec![ ( factory.sym("n"), Type::Int ) ],
+    factory.le( v, var(& factory, "w") )
+  ) ;
+  let term = factory.let_b(
+    vec![ ( factory.sym("v"), x.clone() ), ( factory.sym("w"), n.clone() ) ],
+    quantified
+  ) ;
+  // `w` cannot be inlined below the quantifier, `v` can.
+  assert_eq!(
+    let_expand(& factory, & term),
+    factory.let_b(
+      vec![ ( factory.sym("w"), n ) ],
+      factory.forall(
+        vec![ ( factory.sym("n"), Type::Int ) ],
+        factory.le( x, var(& factory, "w") )
+      )
+    )
+  )
+}
+
+#[test]
+fn share_binds_repeated_subterms() {
+  let factory = Factory::mk() ;
+  let x = var(& factory, "x") ;
+  let y = var(& factory, "y") ;
+  let sum = factory.op( Operator::Add, vec![ x.clone(), y.clone() ] ) ;
+  let term = factory.eq(
+    vec![
+      factory.op( Operator::Mul, vec![ sum.clone(), sum.clone() ] ),
+      factory.op( Operator::Sub, vec![ sum.clone(), x.clone() ] ),
+    ]
+  ) ;
+  let shared = let_share(& factory, & term) ;
+  let v = var(& factory, "@let_0") ;
+  assert_eq!(
+    shared,
+    factory.let_b(
+      vec![ ( factory.sym("@let_0"), sum ) ],
+      factory.eq(
+        vec![
+          factory.op( Operator::Mul, vec![ v.clone(), v.clone() ] ),
+          factory.op( Operator::Sub, vec![ v, x ] ),
+        ]
+      )
+    )
+  ) ;
+  assert_eq!( let_expand(& factory, & shared), term )
+}
+
+#[test]
+fn share_nested() {
+  let factory = Factory::mk() ;
+  let x = var(& factory, "x") ;
+  let y = var(& factory, "y") ;
+  let sum = factory.op( Operator::Add, vec![ x.clone(), y.clone() ] ) ;
+  let prod = factory.op( Operator::Mul, vec![ sum.clone(), sum.clone() ] ) ;
+  let term = factory.and(
+    vec![
+      factory.le( prod.clone(), x.clone() ),
+      factory.le( y.clone(), prod.clone() ),
+      factory.le( sum.clone(), y ),
+    ]
+  ) ;
+  let shared = let_share(& factory, & term) ;
+  assert!( shared != term ) ;
+  assert_eq!( let_expand(& factory, & shared), term ) ;
+  // Nothing left to share.
+  assert_eq!( let_share(& factory, & x), x )
+}
+
+#[test]
+fn print_shares() {
+  let factory = Factory::mk() ;
+  let x = var(& factory, "x") ;
+  let y = var(& factory, "y") ;
+  let sum = factory.op( Operator::Add, vec![ x.clone(), y.clone() ] ) ;
+  let term = factory.eq(
+    vec![
+      factory.op( Operator::Mul, vec![ sum.clone(), sum.clone() ] ),
+      factory.op( Operator::Sub, vec![ sum, x ] ),
+    ]
+  ) ;
+  let mut bytes = vec![] ;
+  term.to_vmt(& mut bytes).unwrap() ;
+  assert_eq!(
+    String::from_utf8(bytes).unwrap(),
+    "(let ( (|@let_0| (+ |x| |y|)) ) \
+    (= (* |@let_0| |@let_0|) (- |@let_0| |x|)))"
+  ) ;
+  // A variable named like a shared subterm is not captured.
+  let clash = var(& factory, "@let_0") ;
+  let prod = factory.op( Operator::Mul, vec![ y.clone(), y ] ) ;
+  let term = factory.le(
+    factory.op( Operator::Add, vec![ prod.clone(), prod ] ), clash
+  ) ;
+  let mut bytes = vec![] ;
+  term.to_vmt(& mut bytes).unwrap() ;
+  assert_eq!(
+    String::from_utf8(bytes).unwrap(),
+    "(let ( (|@let_1| (* |y| |y|)) ) \
+    (<= (+ |@let_1| |@let_1|) |@let_0|))"
+  )
+}
@@ -0,0 +1,120 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests for the negation and conjunctive normal forms.
+
+extern crate term ;
+
+use term::{
+  BigInt, Factory, Term, Operator,
+  CstMaker, SymMaker, VarMaker, OpMaker
+} ;
+use term::normalize::{ nnf, cnf, clauses } ;
+
+/// Creates a boolean variable.
+fn var(factory: & Factory, name: & str) -> Term {
+  factory.var( factory.sym(name) )
+}
+
+#[test]
+fn nnf_pushes_negations() {
+  let factory = Factory::mk() ;
+  let a = var(& factory, "a") ;
+  let b = var(& factory, "b") ;
+  let x = var(& factory, "x") ;
+  let zero: Term = factory.cst( BigInt::parse_bytes(b"0", 10u32).unwrap() ) ;
+  // (not (=> a (and b (<= x 0))))
+  let term = factory.op(
+    Operator::Not, vec![
+      factory.op(
+        Operator::Impl, vec![
+          a.clone(),
+          factory.and(
+            vec![ b.clone(), factory.le( x.clone(), zero.clone() ) ]
+          )
+        ]
+      )
+    ]
+  ) ;
+  assert_eq!(
+    nnf(& factory, & term),
+    factory.and(
+      vec![
+        a,
+        factory.or(
+          vec![ factory.op(Operator::Not, vec![ b ]), factory.gt(x, zero) ]
+        )
+      ]
+    )
+  )
+}
+
+#[test]
+fn nnf_xor() {
+  let factory = Factory::mk() ;
+  let a = var(& factory, "a") ;
+  let b = var(& factory, "b") ;
+  let not_a = factory.op( Operator::Not, vec![ a.clone() ] ) ;
+  let not_b = factory.op( Operator::Not, vec![ b.clone() ] ) ;
+  let term = factory.op(
+    Operator::Not, vec![
+      factory.op( Operator::Xor, vec![ a.clone(), b.clone() ] )
+    ]
+  ) ;
+  assert_eq!(
+    nnf(& factory, & term),
+    factory.or(
+      vec![
+        factory.and( vec![ a, b ] ), factory.and( vec![ not_a, not_b ] )
+      ]
+    )
+  )
+}
+
+#[test]
+fn cnf_distributes() {
+  let factory = Factory::mk() ;
+  let a = var(& factory, "a") ;
+  let b = var(& factory, "b") ;
+  let c = var(& factory, "c") ;
+  // (or a (and b c))
+  let term = factory.or(
+    vec![ a.clone(), factory.and( vec![ b.clone(), c.clone() ] ) ]
+  ) ;
+  assert_eq!(
+    clauses(& factory, & term),
+    vec![ vec![ a.clone(), b.clone() ], vec![ a.clone(), c.clone() ] ]
+  ) ;
+  assert_eq!(
+    cnf(& factory, & term),
+    factory.and(
+      vec![
+        factory.or( vec![ a.clone(), b ] ), factory.or( vec![ a, c ] )
+      ]
+    )
+  )
+}
+
+#[test]
+fn cnf_ite() {
+  let factory = Factory::mk() ;
+  let c = var(& factory, "c") ;
+  let t = var(& factory, "t") ;
+  let term = factory.op(
+    Operator::Ite, vec![ c.clone(), t.clone(), factory.cst(false) ]
+  ) ;
+  // (and (or (not c) t) c)
+  assert_eq!(
+    clauses(& factory, & term),
+    vec![
+      vec![ factory.op( Operator::Not, vec![ c.clone() ] ), t ],
+      vec![ c ],
+    ]
+  )
+}
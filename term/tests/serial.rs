@@ -0,0 +1,77 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests for the serialization of terms.
+
+#![cfg(feature = "serialize")]
+
+extern crate term ;
+extern crate serde ;
+extern crate serde_json ;
+
+use serde::de::DeserializeSeed ;
+
+use term::{
+  BigInt, Factory, Term, STerm, Type, State, Model, Offset, UnTermOps,
+  CstMaker, SymMaker, VarMaker, BindMaker
+} ;
+use term::serial::{ Serial, Seed } ;
+
+/// Serializes something to JSON and deserializes it back.
+fn round_trip<T: Serial>(factory: & Factory, val: & T) -> T {
+  let json = serde_json::to_string( & val.to_plain() ).unwrap() ;
+  let mut deserializer = serde_json::Deserializer::from_str(& json) ;
+  Seed::mk(factory).deserialize(& mut deserializer).unwrap()
+}
+
+#[test]
+fn terms() {
+  let factory = Factory::mk() ;
+  let x: Term = factory.svar( factory.sym("x"), State::Curr ) ;
+  let n: Term = factory.var( factory.sym("n") ) ;
+  let big: Term = factory.cst(
+    BigInt::parse_bytes(b"123456789012345678901234567890", 10u32).unwrap()
+  ) ;
+  let term = factory.forall(
+    vec![ ( factory.sym("n"), Type::Int ) ],
+    factory.or(
+      vec![ factory.le( x.clone(), n ), factory.eq( vec![ x, big ] ) ]
+    )
+  ) ;
+  // Same factory, re-interned to the same term.
+  assert_eq!( round_trip(& factory, & term), term )
+}
+
+#[test]
+fn state_terms() {
+  let factory = Factory::mk() ;
+  let x: Term = factory.svar( factory.sym("x"), State::Curr ) ;
+  let state = factory.not(x) ;
+  let next = factory.bump( state.clone() ).unwrap() ;
+  let sterm = STerm::One(state, next) ;
+  assert_eq!( round_trip(& factory, & sterm), sterm )
+}
+
+#[test]
+fn models() {
+  let factory = Factory::mk() ;
+  let model: Model = vec![
+    (
+      (
+        factory.svar( factory.sym("x"), State::Curr ),
+        Some( Offset::of_int(3) )
+      ),
+      factory.cst( BigInt::parse_bytes(b"-7", 10u32).unwrap() )
+    ),
+    (
+      ( factory.var( factory.sym("y") ), None ), factory.cst(true)
+    ),
+  ] ;
+  assert_eq!( round_trip(& factory, & model), model )
+}
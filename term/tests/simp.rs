@@ -0,0 +1,88 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests for the simplifier.
+
+extern crate term ;
+
+use term::{
+  BigInt, Factory, Term, Operator,
+  CstMaker, SymMaker, VarMaker, OpMaker, BindMaker
+} ;
+use term::simp::simplify ;
+
+/// Creates a constant integer term.
+pub fn int(factory: & Factory, bytes: & [u8]) -> Term {
+  factory.cst( BigInt::parse_bytes(bytes, 10u32).unwrap() )
+}
+
+#[test]
+fn constants() {
+  let factory = Factory::mk() ;
+  let term = factory.lt(
+    factory.add( vec![ int(& factory, b"3"), int(& factory, b"4") ] ),
+    int(& factory, b"5")
+  ) ;
+  let res: Term = factory.cst(false) ;
+  assert_eq!( simplify(& factory, & term), res )
+}
+
+#[test]
+fn flatten_and() {
+  let factory = Factory::mk() ;
+  let a: Term = factory.var( factory.sym("a") ) ;
+  let b: Term = factory.var( factory.sym("b") ) ;
+  let c: Term = factory.var( factory.sym("c") ) ;
+  let term = factory.and(
+    vec![
+      a.clone(),
+      factory.and( vec![ factory.cst(true), b.clone(), a.clone() ] ),
+      factory.op(
+        Operator::Not, vec![ factory.op( Operator::Not, vec![ c.clone() ] ) ]
+      ),
+    ]
+  ) ;
+  assert_eq!(
+    simplify(& factory, & term), factory.and( vec![ a, b, c ] )
+  )
+}
+
+#[test]
+fn absorbing_or() {
+  let factory = Factory::mk() ;
+  let a: Term = factory.var( factory.sym("a") ) ;
+  let term = factory.or(
+    vec![ a.clone(), factory.or( vec![ factory.cst(true), a ] ) ]
+  ) ;
+  let res: Term = factory.cst(true) ;
+  assert_eq!( simplify(& factory, & term), res )
+}
+
+#[test]
+fn neutral_add() {
+  let factory = Factory::mk() ;
+  let x: Term = factory.var( factory.sym("x") ) ;
+  let term = factory.add( vec![ int(& factory, b"0"), x.clone() ] ) ;
+  assert_eq!( simplify(& factory, & term), x )
+}
+
+#[test]
+fn keeps_lets() {
+  let factory = Factory::mk() ;
+  let x: Term = factory.var( factory.sym("x") ) ;
+  let v: Term = factory.var( factory.sym("v") ) ;
+  let term = factory.let_b(
+    vec![ ( factory.sym("v"), x.clone() ) ],
+    factory.and( vec![ factory.cst(true), v.clone() ] )
+  ) ;
+  assert_eq!(
+    simplify(& factory, & term),
+    factory.let_b( vec![ ( factory.sym("v"), x ) ], v )
+  )
+}
@@ -0,0 +1,58 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests for the collection of the variables of a term.
+
+extern crate term ;
+
+use std::collections::HashSet ;
+
+use term::{
+  Factory, Term, Var, State, Type,
+  SymMaker, VarMaker, OpMaker, BindMaker
+} ;
+use term::{ vars_of, state_vars_of } ;
+
+#[test]
+fn let_binders() {
+  let factory = Factory::mk() ;
+  let x: Term = factory.svar( factory.sym("x"), State::Next ) ;
+  let v: Term = factory.var( factory.sym("v") ) ;
+  let w: Term = factory.var( factory.sym("w") ) ;
+  // `v` is free in the definition, bound in the body.
+  let term = factory.let_b(
+    vec![ ( factory.sym("v"), factory.add( vec![ x, v.clone() ] ) ) ],
+    factory.eq( vec![ v, w ] )
+  ) ;
+  let mut expected = HashSet::new() ;
+  let x: Var = factory.svar( factory.sym("x"), State::Next ) ;
+  let v: Var = factory.var( factory.sym("v") ) ;
+  let w: Var = factory.var( factory.sym("w") ) ;
+  expected.insert( x.clone() ) ;
+  expected.insert(v) ;
+  expected.insert(w) ;
+  assert_eq!( vars_of(& term), expected ) ;
+  let mut expected = HashSet::new() ;
+  expected.insert(x) ;
+  assert_eq!( state_vars_of(& term), expected )
+}
+
+#[test]
+fn quantifier_binders() {
+  let factory = Factory::mk() ;
+  let x: Term = factory.svar( factory.sym("x"), State::Curr ) ;
+  let n: Term = factory.var( factory.sym("n") ) ;
+  let term = factory.forall(
+    vec![ ( factory.sym("n"), Type::Int ) ], factory.ge( x, n )
+  ) ;
+  let mut expected = HashSet::new() ;
+  let x: Var = factory.svar( factory.sym("x"), State::Curr ) ;
+  expected.insert(x) ;
+  assert_eq!( vars_of(& term), expected )
+}
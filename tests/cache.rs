@@ -0,0 +1,130 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Checks that two identical runs over the smt cache give the same keys:
+//! the second run must not store any new answer, even if its factory
+//! creates the symbols in a different order.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate unroll ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::fs ;
+use std::io::Read ;
+
+use term::{ Factory, Sym, Offset2, SymMaker } ;
+use term::smt::{ Kid, SolverConf, solver } ;
+use unroll::{ Unroller, PropManager } ;
+use kino::{ Context, Sys, Prop, CtxtRes } ;
+use common::cache::CachedSolver ;
+
+/// Loads a file like `fixture::load_check`. If `shuffled`, the factory
+/// first creates the symbols of the file in reverse order, so that they are
+/// hash consed in a different order. They are returned so that they stay
+/// in the consign.
+fn load(
+  path: & str, shuffled: bool
+) -> (Context, Sys, Vec<Prop>, Vec<Sym>) {
+  let mut txt = String::new() ;
+  fs::File::open(path).unwrap().read_to_string(& mut txt).unwrap() ;
+  let factory = Factory::mk() ;
+  let mut syms = vec![] ;
+  if shuffled {
+    let tokens: Vec<& str> = txt.split(
+      |c: char| c.is_whitespace() || c == '(' || c == ')'
+    ).filter( |token| ! token.is_empty() ).collect() ;
+    for token in tokens.into_iter().rev() {
+      syms.push( factory.sym(token) )
+    }
+  }
+  let mut context = Context::mk(factory, 1000) ;
+  match context.read(& mut txt.as_bytes()).unwrap() {
+    CtxtRes::Check(sys, props) => (context, sys, props, syms),
+    _ => panic!("no verify query in `{}`", path),
+  }
+}
+
+/// Runs BMC on the model of a file up to some depth over a cached solver.
+fn run(file: & str, max: usize, dir: & str, shuffled: bool) {
+  let path = format!("rsc/simple/{}.vmt", file) ;
+  let (context, sys, props, _syms) = load(& path, shuffled) ;
+  let mut kid = Kid::mk( SolverConf::z3() ).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let solver = CachedSolver::mk(solver, dir) ;
+  let mut unroller = Unroller::mk(& sys, & props, solver).unwrap() ;
+  let mut props = PropManager::mk(props, unroller.solver()).unwrap() ;
+  let mut k = Offset2::init() ;
+  unroller.assert_init(& k).unwrap() ;
+  for depth in 0 .. max + 1 {
+    if depth > 0 {
+      unroller.unroll(& k).unwrap()
+    }
+    loop {
+      let one_false = if depth == 0 {
+        props.one_false_state()
+      } else { props.one_false_next() } ;
+      let one_false = match one_false {
+        Some(one_false) => one_false,
+        None => break,
+      } ;
+      let actlit = unroller.fresh_actlit().unwrap() ;
+      unroller.assert(& actlit.activate_term(one_false), & k).unwrap() ;
+      if ! unroller.check_sat_assuming( & [ actlit.name() ] ).unwrap() {
+        unroller.deactivate(actlit).unwrap() ;
+        break
+      }
+      let falsified = if depth == 0 {
+        props.get_false_state(unroller.solver(), & k).unwrap()
+      } else {
+        props.get_false_next(unroller.solver(), & k).unwrap()
+      } ;
+      unroller.deactivate(actlit).unwrap() ;
+      props.inhibit(& falsified).unwrap()
+    }
+    if depth > 0 { k = k.nxt() }
+  }
+}
+
+/// Number of answers in the cache.
+fn answers(dir: & str) -> usize {
+  fs::read_dir(dir).map( |entries| entries.count() ).unwrap_or(0)
+}
+
+/// Runs a file twice, checks the second run does not store anything.
+fn runs_hit(file: & str, max: usize) {
+  let dir = std::env::temp_dir().join(
+    format!("kino_cache_{}_{}", file, std::process::id())
+  ) ;
+  let dir = dir.to_string_lossy().to_string() ;
+  let _ = fs::remove_dir_all(& dir) ;
+  run(file, max, & dir, false) ;
+  let stored = answers(& dir) ;
+  assert!( stored > 0 ) ;
+  run(file, max, & dir, false) ;
+  assert_eq!( answers(& dir), stored ) ;
+  run(file, max, & dir, true) ;
+  assert_eq!( answers(& dir), stored ) ;
+  let _ = fs::remove_dir_all(& dir) ;
+}
+
+#[test]
+fn identical_runs_hit() {
+  runs_hit("within", 5)
+}
+
+#[test]
+fn dependent_functions_hit() {
+  runs_hit("dependent_funs", 5)
+}
+
@@ -0,0 +1,56 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks Cegar on its own: a property the first abstraction proves, one
+//! needing a refinement, and a falsified one.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+
+/// Runs Cegar alone on a file, returns the verdict on a property:
+/// `proved`, `falsified` or `unknown`.
+fn verdict(path: & str, prop: & str, max_preds: & str) -> & 'static str {
+  let conf = Master::default().with("all", "turn", "off").and_then(
+    |conf| conf.with("cegar", "turn", "on")
+  ).and_then(
+    |conf| conf.with("cegar", "max_preds", max_preds)
+  ).unwrap() ;
+  let (context, _) = kino::analyze_with(path, conf).unwrap() ;
+  match context.get_prop( & context.factory().sym(prop) ) {
+    Some( & (_, PropStatus::Invariant(_)) ) |
+    Some( & (_, PropStatus::MinInvariant(_, _)) ) => "proved",
+    Some( & (_, PropStatus::Falsified(_)) ) => "falsified",
+    Some(_) => "unknown",
+    None => panic!("unknown property {}", prop),
+  }
+}
+
+#[test]
+fn safe() {
+  assert_eq!( verdict("rsc/cegar/safe.vmt", "bounded", "1"), "proved" )
+}
+
+#[test]
+fn refined() {
+  assert_eq!( verdict("rsc/cegar/refine.vmt", "zero", "32"), "proved" ) ;
+  // The property alone is not enough.
+  assert_eq!( verdict("rsc/cegar/refine.vmt", "zero", "1"), "unknown" )
+}
+
+#[test]
+fn falsified() {
+  assert_eq!(
+    verdict("rsc/cegar/unsafe.vmt", "small", "32"), "falsified"
+  )
+}
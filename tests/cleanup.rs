@@ -0,0 +1,88 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks that cleaning up only kills the solvers registered, and that the
+//! solvers of a thread are killed when it panics.
+
+extern crate common ;
+
+use std::fs::File ;
+use std::io::Read ;
+use std::process::{ Command, Child } ;
+use std::sync::mpsc::channel ;
+use std::thread ;
+use std::time::Duration ;
+
+use common::cleanup ;
+
+/// Spawns a process sleeping for a minute, a fake solver.
+fn sleeper() -> Child {
+  Command::new("sleep").arg("60").spawn().unwrap()
+}
+
+/// True if a process is still running: it exists and is not a zombie.
+fn running(pid: u32) -> bool {
+  let mut stat = String::new() ;
+  if File::open( format!("/proc/{}/stat", pid) ).and_then(
+    |mut file| file.read_to_string(& mut stat)
+  ).is_err() {
+    return false
+  }
+  match stat.rfind(')') {
+    Some(index) => stat[ index + 1 .. ].split_whitespace().next() != Some(
+      "Z"
+    ),
+    None => false,
+  }
+}
+
+/// True if a process dies within a second.
+fn dies(pid: u32) -> bool {
+  for _ in 0..100 {
+    if ! running(pid) { return true }
+    thread::sleep( Duration::from_millis(10) )
+  }
+  false
+}
+
+#[test]
+fn registered_only() {
+  if File::open("/proc/self/stat").is_err() {
+    println!("no `/proc`, skipping") ;
+    return ()
+  }
+  cleanup::install() ;
+
+  // Solver of a thread that panics.
+  let (sender, receiver) = channel() ;
+  let panicking = thread::spawn(
+    move || {
+      let kid = cleanup::solvers(sleeper) ;
+      sender.send( kid.id() ).unwrap() ;
+      panic!("on purpose")
+    }
+  ) ;
+  let pid = receiver.recv().unwrap() ;
+  assert!( panicking.join().is_err() ) ;
+  assert!( dies(pid) ) ;
+
+  let mut solver = cleanup::solvers(sleeper) ;
+  let mut other = sleeper() ;
+  assert!( running( solver.id() ) && running( other.id() ) ) ;
+  let msgs = cleanup::run(false) ;
+  assert_eq!( msgs, vec![ "killed 1 solver process(es) still running" ] ) ;
+  assert!( dies( solver.id() ) ) ;
+  assert!( running( other.id() ) ) ;
+  other.kill().unwrap() ;
+  let _ = solver.wait() ;
+  let _ = other.wait() ;
+  ()
+}
@@ -1,11 +1,26 @@
 //! Things used by all tests.
 
+#![allow(dead_code)]
+
 use std::fmt ;
 
 pub use kino::PropStatus ;
+use kino::{ Context, Sys, Prop, CtxtRes } ;
+
+/// Loads a file ending with a verify query: its context, the system
+/// verified and its properties. Panics if there is no verify query.
+pub fn load_check(path: & str) -> (Context, Sys, Vec<Prop>) {
+  let (context, res) = match kino::load(path) {
+    Ok(loaded) => loaded,
+    Err(e) => panic!("could not load `{}`: {}", path, e),
+  } ;
+  match res {
+    CtxtRes::Check(sys, props) => (context, sys, props),
+    _ => panic!("no verify query in `{}`", path),
+  }
+}
 
 /// Statuses expected by test functions.
-#[allow(dead_code)]
 pub enum ExpStatus {
   /// K-true.
   KTru(usize),
@@ -151,5 +166,6 @@ pub fn str_of_status(status: & PropStatus) -> String {
     Falsified(ref cex) => format!("<{}-false>", cex.len()),
     Invariant(ref n) => format!("<{}-invariant>", n),
     MinInvariant(ref n, _) => format!("<{}-min-invariant>", n),
+    Split(ref subs) => format!("<split-in-{}>", subs.len()),
   }
 }
\ No newline at end of file
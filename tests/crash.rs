@@ -0,0 +1,61 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that a technique panicking is reported as a crash.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate system ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::sync::Arc ;
+
+use term::Offset ;
+use system::{ Sys, Prop } ;
+use common::{ Tek, CanRun } ;
+use common::msg::{ Event, KidManager, MsgUp } ;
+
+/// Technique panicking after its first depth.
+struct Panics ;
+impl CanRun<()> for Panics {
+  fn id(& self) -> Tek { Tek::Tec("panics", "panics after one depth") }
+  fn run(& self, _: Arc<()>, _: Sys, _: Vec<Prop>, event: Event) {
+    event.depth_done( vec![], & Offset::of_int(3) ) ;
+    panic!("on purpose")
+  }
+}
+
+#[test]
+fn panic_is_a_crash() {
+  common::crash::install() ;
+  let (context, sys, props) = fixture::load_check("rsc/simple/simple_false.vmt") ;
+  let mut manager = KidManager::mk() ;
+  manager.launch(
+    Panics, sys, props, context.factory(), Arc::new(())
+  ).unwrap() ;
+  loop {
+    match manager.recv().unwrap() {
+      MsgUp::Depth(_, _, _, _) => (),
+      MsgUp::Crashed(crash) => {
+        assert_eq!( crash.tek, Tek::Tec("panics", "panics after one depth") ) ;
+        assert_eq!( crash.msg, "on purpose" ) ;
+        assert_eq!( crash.depth, Some( "3".to_string() ) ) ;
+        // Location as `<file>:<line>`, the line is not checked.
+        let location = crash.location.unwrap() ;
+        let file = location.rsplitn(2, ':').last().unwrap() ;
+        assert!( file.ends_with("crash.rs") ) ;
+        break
+      },
+      msg => panic!("unexpected message {}", msg),
+    }
+  }
+}
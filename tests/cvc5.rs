@@ -0,0 +1,95 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Checks the cvc5 backend on a fake cvc5, see `rsc/cvc5`, and on the real
+//! one if it is installed.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate unroll ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::process::Command ;
+
+use term::{ Factory, Int, Cst, CstMaker, Offset2 } ;
+use term::smt::{ Kid, SolverConf, SolverStyle, solver, cvc5, racer_conf } ;
+use unroll::Unroller ;
+
+#[test]
+fn confs() {
+  let z3 = SolverConf::z3().print_success() ;
+  let conf = cvc5::adapt( z3.clone().cmd( "/opt/bin/cvc5".to_string() ) ) ;
+  assert_eq!( conf.style().to_string(), SolverStyle::CVC4.to_string() ) ;
+  assert_eq!( conf.get_cmd(), "/opt/bin/cvc5" ) ;
+  assert_eq!( conf.get_options(), cvc5::conf().get_options() ) ;
+  assert!( conf.get_parse_success() ) ;
+  // Racers too.
+  let racer = racer_conf(& z3, "cvc5-1.0") ;
+  assert_eq!( racer.get_options(), cvc5::conf().get_options() ) ;
+  // Other solvers are left as is.
+  let other = cvc5::adapt( z3.clone().cmd( "cvc4".to_string() ) ) ;
+  assert_eq!( other.get_options(), z3.get_options() )
+}
+
+#[test]
+fn dialect() {
+  let factory = Factory::mk() ;
+  let conf = cvc5::adapt(
+    SolverConf::z3().cmd( "rsc/cvc5/cvc5_fake.sh".to_string() )
+  ) ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let mut solver = solver(& mut kid, factory.clone()).unwrap() ;
+  assert!(
+    cvc5::check_sat_assuming(
+      & mut solver, & [ "act".to_string() ], & ()
+    ).unwrap()
+  ) ;
+  let model = cvc5::get_model(& mut solver).unwrap() ;
+  assert_eq!( model.len(), 1 ) ;
+  let three: Cst = factory.cst( Int::parse_bytes(b"3", 10).unwrap() ) ;
+  assert!( model[0].1 == three )
+}
+
+#[test]
+fn as_array() {
+  let factory = Factory::mk() ;
+  let conf = SolverConf::z3().cmd( "rsc/cvc5/z3_arrays.sh".to_string() ) ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let mut solver = solver(& mut kid, factory.clone()).unwrap() ;
+  assert!(
+    cvc5::check_sat_assuming(
+      & mut solver, & [ "act".to_string() ], & ()
+    ).unwrap()
+  ) ;
+  // `k!0` is not part of the model, `a` is the array `b` is.
+  let model = cvc5::get_model(& mut solver).unwrap() ;
+  assert_eq!( model.len(), 2 ) ;
+  assert!( model[0].1 == model[1].1 )
+}
+
+#[test]
+fn real_cvc5() {
+  if Command::new("cvc5").arg("--version").output().is_err() {
+    println!("cvc5 is not installed, skipping") ;
+    return ()
+  }
+  let (context, sys, props) = fixture::load_check("rsc/simple/within.vmt") ;
+  let conf = cvc5::adapt( SolverConf::cvc4().cmd( "cvc5".to_string() ) ) ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk(& sys, & props, solver).unwrap() ;
+  let k = Offset2::init() ;
+  unroller.assert_init(& k).unwrap() ;
+  unroller.unroll(& k).unwrap() ;
+  assert!( unroller.check_sat().unwrap() ) ;
+  assert!( ! unroller.get_full_model().unwrap().is_empty() )
+}
@@ -0,0 +1,58 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Checks that the counterexamples are the same under the relation-style
+//! and the functional encodings. The latter defines the next state variables
+//! instead of declaring them, they are not part of the answer to
+//! `get-model`.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate unroll ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use term::Offset2 ;
+use term::smt::{ Kid, SolverConf, solver } ;
+use unroll::Unroller ;
+use common::conf::Encoding ;
+
+/// Unrolls the system of `rsc/simple/within.vmt` three times from its
+/// initial state, and returns the model of the trace, sorted.
+fn trace(encoding: Encoding) -> Vec<String> {
+  let (context, sys, props) = fixture::load_check("rsc/simple/within.vmt") ;
+  let mut kid = Kid::mk( SolverConf::z3() ).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk_encoded(
+    & sys, & props, solver, & encoding
+  ).unwrap() ;
+  let mut k = Offset2::init() ;
+  unroller.assert_init(& k).unwrap() ;
+  for _ in 0..3 {
+    unroller.unroll(& k).unwrap() ;
+    k = k.nxt()
+  }
+  assert!( unroller.check_sat().unwrap() ) ;
+  let mut model: Vec<String> = unroller.get_full_model().unwrap().iter().map(
+    |& ( (ref var, ref off), ref val )| format!("{} {:?} {}", var, off, val)
+  ).collect() ;
+  model.sort() ;
+  model
+}
+
+#[test]
+fn same_cex() {
+  let rel = trace(Encoding::Rel) ;
+  // Four state variables in four states at least.
+  assert!( rel.len() >= 16 ) ;
+  assert_eq!( rel, trace(Encoding::Fun) )
+}
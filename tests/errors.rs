@@ -0,0 +1,68 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+
+//! Checks that the errors of a technique carry its context.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate system ;
+#[macro_use]
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::sync::Arc ;
+
+use system::{ Sys, Prop } ;
+use common::{ Tek, CanRun } ;
+use common::errors::ErrorKind ;
+use common::msg::{ Event, KidManager, MsgUp } ;
+
+/// Technique failing right away.
+struct Fails ;
+impl CanRun<()> for Fails {
+  fn id(& self) -> Tek { Tek::Tec("fails", "fails right away") }
+  fn run(& self, _: Arc<()>, _: Sys, _: Vec<Prop>, event: Event) {
+    log_error!(event, "on purpose")
+  }
+}
+
+#[test]
+fn error_has_context() {
+  let (context, sys, props) = fixture::load_check(
+    "rsc/simple/simple_false.vmt"
+  ) ;
+  let mut manager = KidManager::mk() ;
+  manager.launch(
+    Fails, sys, props, context.factory(), Arc::new(())
+  ).unwrap() ;
+  match manager.recv().unwrap() {
+    MsgUp::Error(tek, error) => {
+      assert_eq!( tek, Tek::Tec("fails", "fails right away") ) ;
+      match * error.kind() {
+        ErrorKind::TekContext(tek, file, _) => {
+          assert_eq!( tek, Tek::Tec("fails", "fails right away") ) ;
+          assert!( file.ends_with("errors.rs") )
+        },
+        ref kind => panic!("unexpected error kind {}", kind),
+      }
+      let causes: Vec<String> = error.iter().map(
+        |err| format!("{}", err)
+      ).collect() ;
+      assert_eq!(
+        causes.last().map( |cause| cause.as_str() ), Some("on purpose")
+      )
+    },
+    msg => panic!("unexpected message {}", msg),
+  }
+}
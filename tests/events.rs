@@ -0,0 +1,60 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that the JSON sink writes one timestamped object per event.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate common ;
+
+use std::io ;
+use std::sync::{ Arc, Mutex } ;
+use std::time::Duration ;
+
+use term::{ Factory, SymMaker, Offset } ;
+use common::Tek ;
+use kino::{ MasterLog, JsonSink } ;
+
+/// Buffer shared with the sink.
+#[derive(Clone)]
+struct Buffer(Arc< Mutex< Vec<u8> > >) ;
+impl io::Write for Buffer {
+  fn write(& mut self, bytes: & [u8]) -> io::Result<usize> {
+    self.0.lock().unwrap().write(bytes)
+  }
+  fn flush(& mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[test]
+fn events() {
+  let buffer = Buffer( Arc::new( Mutex::new( vec![] ) ) ) ;
+  let log = MasterLog::empty().quiet().sink( JsonSink::mk( buffer.clone() ) ) ;
+  let factory = Factory::mk() ;
+  let prop = factory.sym("prop") ;
+
+  log.log_k_true( & Tek::Kino, & [ prop.clone() ], & Offset::of_int(3) ) ;
+  log.bad(& Tek::Kino, "oops") ;
+  log.log_safe( Duration::from_secs(1) ) ;
+
+  let bytes = buffer.0.lock().unwrap().clone() ;
+  let events = String::from_utf8(bytes).unwrap() ;
+  let lines: Vec<& str> = events.lines().collect() ;
+  assert_eq!( lines.len(), 3 ) ;
+  for line in lines.iter() {
+    assert!( line.starts_with("{\"event\":") ) ;
+    assert!( line.contains(",\"timestamp\":") ) ;
+    assert!( line.ends_with("}") )
+  }
+  assert!( lines[0].starts_with("{\"event\":\"k_true\"") ) ;
+  assert!( lines[0].contains("\"k\":3") ) ;
+  assert!( lines[1].starts_with("{\"event\":\"error\"") ) ;
+  assert!( lines[1].contains("\"msg\":\"oops\"") ) ;
+  assert!( lines[2].starts_with("{\"event\":\"result\"") ) ;
+  assert!( lines[2].contains("\"result\":\"safe\"") )
+}
@@ -0,0 +1,84 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that the falsifier reports the invariants concrete traces
+//! falsify, and only those.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::sync::mpsc::channel ;
+use std::time::Duration ;
+
+use term::{ Factory, Int, Cst, STerm, STermSet, SymMaker, CstMaker } ;
+use common::msg::MsgUp ;
+use common::samples::Sample ;
+use common::falsify::Falsifier ;
+
+/// A state of `sys_a` of `simple_false`.
+fn state(factory: & Factory, input: bool, out: & str) -> Sample {
+  let input: Cst = factory.cst(input) ;
+  let out: Cst = factory.cst(
+    Int::parse_bytes(out.as_bytes(), 10u32).unwrap()
+  ) ;
+  vec![ (factory.sym("in"), input), (factory.sym("out"), out) ]
+}
+
+#[test]
+fn simple_false() {
+  let (context, sys, props) = fixture::load_check("rsc/simple/simple_false.vmt") ;
+  let factory = context.factory().clone() ;
+  // `out <= 10` and `out = out'`.
+  let (one, two): (Vec<STerm>, Vec<STerm>) = props.iter().map(
+    |prop| prop.body().clone()
+  ).partition(
+    |body| match * body { STerm::One(_, _) => true, _ => false }
+  ) ;
+  assert_eq!( (one.len(), two.len()), (1, 1) ) ;
+
+  let (s, r) = channel() ;
+  let falsifier = Falsifier::launch(
+    s, sys.sym().get().clone(), factory.clone()
+  ).unwrap() ;
+  let timeout = Duration::from_secs(5) ;
+  let falsified = || match r.recv_timeout(timeout) {
+    Ok( MsgUp::Falsified(_, invs) ) => invs,
+    Ok(msg) => panic!("unexpected message {}", msg),
+    Err(e) => panic!("no answer from the falsifier: {}", e),
+  } ;
+
+  let invs: STermSet = one.iter().chain( two.iter() ).cloned().collect() ;
+  falsifier.invariants(invs) ;
+
+  // `out` changes, falsifies the two-state invariant only.
+  falsifier.trace(
+    vec![ state(& factory, false, "0"), state(& factory, true, "1") ]
+  ) ;
+  let invs = falsified() ;
+  assert_eq!( invs.len(), 1 ) ;
+  assert!( invs.contains(& two[0]) ) ;
+
+  // Falsifies the one-state invariant, the other one is not checked
+  // anymore.
+  falsifier.trace(
+    vec![ state(& factory, true, "10"), state(& factory, false, "11") ]
+  ) ;
+  let invs = falsified() ;
+  assert_eq!( invs.len(), 1 ) ;
+  assert!( invs.contains(& one[0]) ) ;
+
+  // New invariants are checked on the states seen so far.
+  falsifier.invariants( one.iter().cloned().collect() ) ;
+  let invs = falsified() ;
+  assert!( invs.contains(& one[0]) )
+}
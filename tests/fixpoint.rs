@@ -0,0 +1,31 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Kind reaching a fixpoint stops itself and invariant generation only, the
+//! other techniques keep running.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+
+#[test]
+fn bmc_runs_after_fixpoint() {
+  let conf = Master::default().with("kind", "fixpoint", "1").unwrap() ;
+  let (context, _) = kino::analyze_with(
+    "rsc/simple/fixpoint.vmt", conf
+  ).unwrap() ;
+  match context.get_prop( & context.factory().sym("cnt_small") ) {
+    Some( & (_, PropStatus::Falsified(ref cex)) ) => assert_eq!(cex.len(), 5),
+    Some( & (_, ref status) ) => panic!("unexpected status {}", status),
+    None => panic!("unknown property cnt_small"),
+  }
+}
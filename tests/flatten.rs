@@ -0,0 +1,32 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that flattening inlines the sub-system instances as namespaced
+//! state variables.
+
+extern crate kino_api as kino ;
+extern crate term ;
+
+mod common ;
+
+use term::SymMaker ;
+
+#[test]
+fn modular() {
+  let (context, sys, _) = common::load_check("rsc/simple/modular.vmt") ;
+  let factory = context.factory() ;
+  let flat = sys.flatten(factory).unwrap() ;
+  assert!( flat.subsys().is_empty() ) ;
+  assert_eq!( flat.state().len(), sys.state().len() + 2 ) ;
+  assert!( flat.state().contains( & factory.sym("sys_a[0].in") ) ) ;
+  assert!( flat.state().contains( & factory.sym("sys_a[0].out") ) ) ;
+  // Flattening a flat system changes nothing.
+  let again = flat.flatten(factory).unwrap() ;
+  assert_eq!( again.trans().2, flat.trans().2 )
+}
@@ -0,0 +1,136 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that the property managers find the same falsified properties
+//! by reading the values of the properties only, as when parsing the terms
+//! of the answer of the solver and mapping them back to the properties.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate unroll ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::collections::HashMap ;
+
+use term::{ Factory, Sym, Term, Offset2, STerm, real_term } ;
+use term::smt::{ Kid, SolverConf, QueryExprInfo, solver } ;
+use unroll::{ Unroller, PropManager } ;
+use kino::Prop ;
+
+/// Sorted names of some properties.
+fn names(mut keys: Vec<Sym>) -> Vec<String> {
+  keys.sort_by( |lhs, rhs| lhs.get().sym().cmp( rhs.get().sym() ) ) ;
+  keys.iter().map( |key| key.get().sym().to_string() ).collect()
+}
+
+/// The properties among `keys` false in the model of the last check,
+/// found by parsing the answer of `get-value` on their state version if
+/// `state`, on their next version otherwise. One-state properties can come
+/// back in their state version when asking for the next one.
+fn parsed<'kid, S: QueryExprInfo<'kid, Factory, Term>>(
+  solver: & mut S, props: & [Prop], keys: & [Sym], state: bool, k: & Offset2
+) -> Vec<Sym> {
+  let mut terms = vec![] ;
+  let mut back_map: HashMap<Term, Sym> = HashMap::new() ;
+  for prop in props.iter() {
+    let key = prop.sym().get() ;
+    if ! keys.contains(key) { continue }
+    let term = match ( prop.body(), state ) {
+      ( & STerm::One(ref curr, _), true ) => curr.clone(),
+      ( & STerm::One(ref curr, ref next), false ) => {
+        back_map.insert( curr.clone(), key.clone() ) ;
+        next.clone()
+      },
+      ( & STerm::Two(ref next), _ ) => next.clone(),
+    } ;
+    back_map.insert( term.clone(), key.clone() ) ;
+    terms.push(term)
+  }
+  let mut falsified = vec![] ;
+  for ( (term, _), value ) in solver.get_values(& terms, k).unwrap() {
+    match * value.get() {
+      real_term::Cst::Bool(true) => (),
+      real_term::Cst::Bool(false) => falsified.push(
+        back_map.get(& term).expect("unknown term in get-value").clone()
+      ),
+      _ => panic!("unexpected value {} for {}", value, term),
+    }
+  }
+  falsified
+}
+
+/// Runs BMC on the model of a file up to some depth, and checks that
+/// `get_false` agrees with parsing the answer of the solver on each
+/// counterexample. Returns the properties falsified, which stay inhibited
+/// afterwards.
+fn agree(file: & str, max: usize) -> Vec<String> {
+  let path = format!("rsc/simple/{}.vmt", file) ;
+  let (context, sys, all) = fixture::load_check(& path) ;
+  let mut kid = Kid::mk( SolverConf::z3().print_success() ).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk(& sys, & all, solver).unwrap() ;
+  let mut props = PropManager::mk(all.clone(), unroller.solver()).unwrap() ;
+
+  let mut falsified = vec![] ;
+  let mut k = Offset2::init() ;
+  unroller.assert_init(& k).unwrap() ;
+  for depth in 0 .. max + 1 {
+    if depth > 0 {
+      unroller.unroll(& k).unwrap()
+    }
+    loop {
+      let one_false = if depth == 0 {
+        props.one_false_state()
+      } else { props.one_false_next() } ;
+      let one_false = match one_false {
+        Some(one_false) => one_false,
+        None => break,
+      } ;
+      let actlit = unroller.fresh_actlit().unwrap() ;
+      unroller.assert(& actlit.activate_term(one_false), & k).unwrap() ;
+      if ! unroller.check_sat_assuming( & [ actlit.name() ] ).unwrap() {
+        unroller.deactivate(actlit).unwrap() ;
+        break
+      }
+      let values = if depth == 0 {
+        props.get_false_state(unroller.solver(), & k).unwrap()
+      } else {
+        props.get_false_next(unroller.solver(), & k).unwrap()
+      } ;
+      let parsed = parsed(
+        unroller.solver(), & all, & props.not_inhibited(), depth == 0, & k
+      ) ;
+      unroller.deactivate(actlit).unwrap() ;
+      let (values, parsed) = ( names(values), names(parsed) ) ;
+      assert_eq!(values, parsed) ;
+      assert!( ! values.is_empty() ) ;
+      let keys: Vec<Sym> = props.keys().into_iter().filter(
+        |key| values.iter().any( |name| name == key.get().sym() )
+      ).collect() ;
+      props.inhibit(& keys).unwrap() ;
+      falsified.extend(values)
+    }
+    if depth > 0 { k = k.nxt() }
+  }
+  falsified
+}
+
+#[test]
+fn simple_false() {
+  assert_eq!(
+    agree("simple_false", 12), vec![ "out_positive1", "out_positive" ]
+  )
+}
+
+#[test]
+fn simple_two_props() {
+  assert_eq!( agree("simple_two_props", 12), vec![ "out_le_10" ] )
+}
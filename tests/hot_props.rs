@@ -0,0 +1,71 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks that properties added while an analysis runs are checked: the
+//! analysis starts paused, a property the verify query does not mention is
+//! added, and the analysis resumes.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::thread ;
+use std::time::Duration ;
+
+use kino::{ Master, MasterLog, SymMaker, PropStatus } ;
+use common::pause ;
+
+#[test]
+fn added_mid_run() {
+  let (mut context, sys, props) = fixture::load_check(
+    "rsc/simple/unqueried.vmt"
+  ) ;
+  let small = context.factory().sym("small") ;
+  let added = match context.get_prop(& small) {
+    Some( & (ref prop, _) ) => prop.clone(),
+    None => panic!("no property `small`"),
+  } ;
+
+  pause::request(true) ;
+  let analysis = thread::spawn(
+    move || {
+      let log = MasterLog::default() ;
+      let res = Master::launch(
+        & log, & mut context, sys, props, None,
+        common::conf::Master::default()
+      ) ;
+      (context, res)
+    }
+  ) ;
+  // Waiting for the master to register.
+  while pause::add_props( vec![ added.clone() ] ) == 0 {
+    thread::sleep( Duration::from_millis(10) )
+  }
+  pause::request(false) ;
+  let (context, res) = analysis.join().unwrap() ;
+  assert!( res.is_ok() ) ;
+
+  let pos = context.factory().sym("pos") ;
+  match context.get_prop(& pos) {
+    Some( & (_, PropStatus::Invariant(_)) ) |
+    Some( & (_, PropStatus::MinInvariant(_, _)) ) => (),
+    _ => panic!("`pos` should be proved"),
+  }
+  // Falsified at depth 3, where `cnt` reaches `3`.
+  match context.get_prop(& small) {
+    Some( & (_, PropStatus::Falsified(ref cex)) ) => {
+      assert_eq!( cex.len(), 3 )
+    },
+    _ => panic!("`small` should be falsified"),
+  }
+}
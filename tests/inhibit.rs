@@ -0,0 +1,109 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Checks the per-property inhibition and actlits of the property managers,
+//! and that checking a property alone ignores the other ones.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate unroll ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use term::{ Offset2, SymMaker } ;
+use term::smt::{ Kid, SolverConf, solver } ;
+use unroll::{ Unroller, PropManager } ;
+
+/// Runs BMC on the model of a file up to some depth with all the
+/// properties inhibited but `only`. Returns the depth `only` is falsified
+/// at, if any.
+fn falsified_alone(file: & str, only: & str, max: usize) -> Option<usize> {
+  let path = format!("rsc/simple/{}.vmt", file) ;
+  let (context, sys, all) = fixture::load_check(& path) ;
+  let only = context.factory().sym(only) ;
+  let mut kid = Kid::mk( SolverConf::z3() ).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk(& sys, & all, solver).unwrap() ;
+  let mut props = PropManager::mk(all, unroller.solver()).unwrap() ;
+  props.inhibit_all_but(& only).unwrap() ;
+  assert_eq!( props.not_inhibited(), vec![ only.clone() ] ) ;
+
+  let mut k = Offset2::init() ;
+  unroller.assert_init(& k).unwrap() ;
+  for depth in 0 .. max + 1 {
+    if depth > 0 {
+      unroller.unroll(& k).unwrap()
+    }
+    let one_false = if depth == 0 {
+      props.one_false_state()
+    } else { props.one_false_next() }.unwrap() ;
+    let actlit = unroller.fresh_actlit().unwrap() ;
+    unroller.assert(& actlit.activate_term(one_false), & k).unwrap() ;
+    let mut actlits = props.actlits() ;
+    actlits.push( actlit.name() ) ;
+    if unroller.check_sat_assuming(& actlits).unwrap() {
+      let falsified = if depth == 0 {
+        props.get_false_state(unroller.solver(), & k).unwrap()
+      } else {
+        props.get_false_next(unroller.solver(), & k).unwrap()
+      } ;
+      assert_eq!( falsified, vec![ only ] ) ;
+      return Some(depth)
+    }
+    unroller.deactivate(actlit).unwrap() ;
+    if depth > 0 { k = k.nxt() }
+  }
+  None
+}
+
+#[test]
+fn actlits_of_properties() {
+  let (context, sys, all) = fixture::load_check(
+    "rsc/simple/simple_two_props.vmt"
+  ) ;
+  let (le_10, pos) = (
+    context.factory().sym("out_le_10"), context.factory().sym("out_pos")
+  ) ;
+  let mut kid = Kid::mk( SolverConf::z3() ).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk(& sys, & all, solver).unwrap() ;
+  let mut props = PropManager::mk(all, unroller.solver()).unwrap() ;
+
+  let le_10_actlit = props.actlit_of(& le_10).unwrap().to_string() ;
+  let pos_actlit = props.actlit_of(& pos).unwrap().to_string() ;
+  assert!( le_10_actlit != pos_actlit ) ;
+  assert_eq!( props.actlit_of(& sys.sym().get().clone()), None ) ;
+
+  // Inhibiting one property only drops its actlit.
+  assert!( props.inhibit_one(& le_10) ) ;
+  assert!( ! props.inhibit_one(& le_10) ) ;
+  assert_eq!( props.actlits(), vec![ pos_actlit.clone() ] ) ;
+  assert_eq!( props.actlit_of(& le_10), Some(& le_10_actlit as & str) ) ;
+
+  props.inhibit_all_but(& le_10).unwrap() ;
+  assert_eq!( props.actlits(), vec![ le_10_actlit.clone() ] ) ;
+  assert!( props.inhibit_all_but(& sys.sym().get().clone()).is_err() ) ;
+
+  props.uninhibit( vec![ pos.clone(), le_10.clone() ].iter() ) ;
+  let mut actlits = props.actlits() ;
+  actlits.sort() ;
+  let mut expected = vec![ le_10_actlit, pos_actlit ] ;
+  expected.sort() ;
+  assert_eq!( actlits, expected )
+}
+
+#[test]
+fn checked_alone() {
+  assert_eq!( falsified_alone("simple_two_props", "out_pos", 12), None ) ;
+  assert_eq!(
+    falsified_alone("simple_two_props", "out_le_10", 12), Some(10)
+  )
+}
@@ -0,0 +1,73 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks that the JSON reader of `common` reads back what kino writes,
+//! escapes included.
+
+extern crate common ;
+
+use common::json ;
+use common::log::json_str ;
+
+/// Writes a string with `json_str`, reads it back.
+fn round_trip(s: & str) -> String {
+  let txt = json_str(s) ;
+  let mut chars = txt.chars().peekable() ;
+  let res = json::string(& mut chars).unwrap() ;
+  assert_eq!( chars.next(), None ) ;
+  res
+}
+
+#[test]
+fn strings_round_trip() {
+  for s in & [
+    "", "prop", "with \"quotes\" and \\", "lines\nand\rtabs\t",
+    "bell\u{7}, escape\u{1b} and nul\u{0}", "unicode: kinō, λ, 🦀"
+  ] {
+    assert_eq!( & round_trip(s), s )
+  }
+}
+
+#[test]
+fn unicode_escapes() {
+  let txt = r#""Aé🦀\/\b\f""# ;
+  assert_eq!(
+    json::string(& mut txt.chars().peekable()),
+    Ok( "Aé🦀/\u{8}\u{c}".to_string() )
+  ) ;
+  for txt in & [ r#""\ud83e""#, r#""\ud83eA""#, r#""\u00g1""# ] {
+    assert!( json::string(& mut txt.chars().peekable()).is_err() )
+  }
+}
+
+#[test]
+fn objects() {
+  let txt = format!(
+    r#" [ {{ "name": {}, "k": 3, "tek": null }}, {{}} ] "#,
+    json_str("p\u{1}")
+  ) ;
+  assert_eq!(
+    json::objects(& mut txt.chars().peekable()),
+    Ok( vec![
+      vec![
+        ( "name".to_string(), Some("p\u{1}".to_string()) ),
+        ( "k".to_string(), Some("3".to_string()) ),
+        ( "tek".to_string(), None ),
+      ],
+      vec![],
+    ] )
+  ) ;
+  let txt = r#"[ { "name": } ]"# ;
+  assert_eq!(
+    json::objects(& mut txt.chars().peekable()),
+    Err( "expected value for field `name` in object 0".to_string() )
+  )
+}
@@ -0,0 +1,53 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+
+//! Checks the k-liveness reduction end to end: a liveness property that
+//! holds, one failing on a lasso, and one holding only on fair paths.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+
+/// Verifies a file, returns the verdict on property `eventually`: `proved`
+/// or `unknown`, since falsifying the reduction proves nothing.
+fn verdict(path: & str) -> & 'static str {
+  let (context, _) = kino::analyze_with(path, Master::default()).unwrap() ;
+  match context.get_prop( & context.factory().sym("eventually") ) {
+    Some( & (_, PropStatus::Invariant(_)) ) |
+    Some( & (_, PropStatus::MinInvariant(_, _)) ) => "proved",
+    Some( & (_, PropStatus::Falsified(_)) ) => panic!(
+      "falsified liveness property in {}", path
+    ),
+    Some(_) => "unknown",
+    None => panic!("no property `eventually` in {}", path),
+  }
+}
+
+#[test]
+fn holds() {
+  assert_eq!( verdict("rsc/live/holds.vmt"), "proved" )
+}
+
+#[test]
+fn lasso() {
+  assert_eq!( verdict("rsc/live/lasso.vmt"), "unknown" )
+}
+
+#[test]
+fn fairness() {
+  // `b` can stay false forever...
+  assert_eq!( verdict("rsc/live/unfair.vmt"), "unknown" ) ;
+  // ...but not on fair paths.
+  assert_eq!( verdict("rsc/live/fair.vmt"), "proved" )
+}
@@ -0,0 +1,62 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+
+//! Checks the LTL properties on a system where `b` toggles and `c` is
+//! always false: for each temporal operator, a formula that holds and one
+//! that does not.
+//!
+//! The formulas are reduced to liveness properties, a failing one is
+//! unknown since falsifying the reduction proves nothing.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+
+/// Verifies a file, returns the verdicts on properties `holds` and `fails`:
+/// `proved` or `unknown`.
+fn verdicts(path: & str) -> (& 'static str, & 'static str) {
+  let (context, _) = kino::analyze_with(path, Master::default()).unwrap() ;
+  let verdict = |prop: & str| match context.get_prop(
+    & context.factory().sym(prop)
+  ) {
+    Some( & (_, PropStatus::Invariant(_)) ) |
+    Some( & (_, PropStatus::MinInvariant(_, _)) ) => "proved",
+    Some( & (_, PropStatus::Falsified(_)) ) => panic!(
+      "falsified LTL property {} in {}", prop, path
+    ),
+    Some(_) => "unknown",
+    None => panic!("no property `{}` in {}", prop, path),
+  } ;
+  ( verdict("holds"), verdict("fails") )
+}
+
+#[test]
+fn next() {
+  assert_eq!( verdicts("rsc/ltl/x.vmt"), ("proved", "unknown") )
+}
+
+#[test]
+fn eventually() {
+  assert_eq!( verdicts("rsc/ltl/f.vmt"), ("proved", "unknown") )
+}
+
+#[test]
+fn globally() {
+  assert_eq!( verdicts("rsc/ltl/g.vmt"), ("proved", "unknown") )
+}
+
+#[test]
+fn until() {
+  assert_eq!( verdicts("rsc/ltl/u.vmt"), ("proved", "unknown") )
+}
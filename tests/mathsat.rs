@@ -0,0 +1,71 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks the MathSAT configuration, and interpolation on the real MathSAT
+//! if it is installed.
+
+extern crate term ;
+
+use std::fs::File ;
+use std::io::Read ;
+use std::process::Command ;
+
+use term::{
+  Factory, Term, Type, Int, Offset2, Operator,
+  SymMaker, VarMaker, OpMaker, CstMaker
+} ;
+use term::smt::{
+  Kid, SolverStyle, Solver, Query, Interpolate, solver, mathsat
+} ;
+
+#[test]
+fn conf() {
+  let conf = mathsat::conf("/opt/mathsat 5/bin/mathsat").unwrap() ;
+  assert_eq!( conf.style().to_string(), SolverStyle::Z3.to_string() ) ;
+  let mut script = String::new() ;
+  File::open( conf.get_cmd() ).unwrap().read_to_string(
+    & mut script
+  ).unwrap() ;
+  assert!(
+    script.contains("exec '/opt/mathsat 5/bin/mathsat' -interpolation=true")
+  ) ;
+  // One script per command.
+  let again = mathsat::conf("/opt/mathsat 5/bin/mathsat").unwrap() ;
+  assert_eq!( again.get_cmd(), conf.get_cmd() ) ;
+  let other = mathsat::conf("mathsat").unwrap() ;
+  assert!( other.get_cmd() != conf.get_cmd() )
+}
+
+#[test]
+fn real_mathsat() {
+  if Command::new("mathsat").arg("-version").output().is_err() {
+    println!("mathsat is not installed, skipping") ;
+    return ()
+  }
+  let factory = Factory::mk() ;
+  let conf = mathsat::conf("mathsat").unwrap() ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let mut solver = solver(& mut kid, factory.clone()).unwrap() ;
+  let off = Offset2::init() ;
+  let x = factory.sym("x") ;
+  solver.declare_fun(& x, & [] as & [Type], & Type::Int, & off).unwrap() ;
+  let x: Term = factory.var(x) ;
+  let zero: Term = factory.cst( Int::parse_bytes(b"0", 10).unwrap() ) ;
+  // x > 0 and x < 0, the interpolant of the first one is about x only.
+  let pos = factory.op( Operator::Gt, vec![ x.clone(), zero.clone() ] ) ;
+  let neg = factory.op( Operator::Lt, vec![ x, zero ] ) ;
+  solver.assert_in_group(& pos, & off, "pos").unwrap() ;
+  solver.assert_in_group(& neg, & off, "neg").unwrap() ;
+  assert!( ! solver.check_sat().unwrap() ) ;
+  let (interpolant, _) = solver.get_interpolant(& ["pos"], & off).unwrap() ;
+  let tru: Term = factory.cst(true) ;
+  assert!( interpolant != tru )
+}
@@ -0,0 +1,49 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks the semantics of the `implies_within` pattern at its bound.
+
+extern crate kino_api as kino ;
+
+mod common ;
+
+use kino::{ Context, SymMaker, PropStatus } ;
+
+/// Status of a property.
+fn status<'a>(context: & 'a Context, name: & str) -> & 'a PropStatus {
+  match context.get_prop( & context.factory().sym(name) ) {
+    Some( & (_, ref status) ) => status,
+    None => panic!("unknown property `{}`", name),
+  }
+}
+
+#[test]
+fn implies_within() {
+  let (context, _) = match kino::analyze("rsc/simple/within.vmt") {
+    Ok(res) => res,
+    Err(e) => panic!("could not analyze `within.vmt`: {}", e),
+  } ;
+  for name in [ "within_3", "within_0" ].iter() {
+    match * status(& context, name) {
+      PropStatus::Invariant(_) | PropStatus::MinInvariant(_, _) => (),
+      ref status => panic!(
+        "`{}` should be proved, got {}", name, common::str_of_status(status)
+      ),
+    }
+  }
+  for & (name, len) in [ ("within_2", 2), ("within_0_false", 0) ].iter() {
+    match * status(& context, name) {
+      PropStatus::Falsified(ref cex) if cex.len() == len => (),
+      ref status => panic!(
+        "`{}` should be falsified at {}, got {}",
+        name, len, common::str_of_status(status)
+      ),
+    }
+  }
+}
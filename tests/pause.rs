@@ -0,0 +1,56 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+
+//! Checks that the techniques stay paused until they are resumed.
+//!
+//! The requests of the operator apply to every analysis of the process,
+//! this test has its own binary.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use std::thread ;
+use std::time::Duration ;
+use std::sync::mpsc::channel ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+use common::pause ;
+
+#[test]
+fn paused_until_resumed() {
+  // Requested before the analysis starts, the techniques pause at their
+  // first query boundary.
+  pause::request(true) ;
+  let (sender, receiver) = channel() ;
+  let analysis = thread::spawn(
+    move || {
+      let (context, _) = kino::analyze_with(
+        "rsc/simple/deep_false.vmt", Master::default()
+      ).unwrap() ;
+      let falsified = match context.get_prop(
+        & context.factory().sym("small")
+      ) {
+        Some( & (_, PropStatus::Falsified(ref cex)) ) => Some( cex.len() ),
+        _ => None,
+      } ;
+      sender.send(falsified).unwrap()
+    }
+  ) ;
+
+  assert!( receiver.recv_timeout( Duration::from_secs(2) ).is_err() ) ;
+  assert!( pause::paused() ) ;
+
+  pause::request(false) ;
+  assert_eq!( receiver.recv().unwrap(), Some(20) ) ;
+  analysis.join().unwrap()
+}
@@ -0,0 +1,45 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+
+//! Checks the values of the history variables in a counterexample.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+
+#[test]
+fn cex_values() {
+  let mut conf = Master::default() ;
+  conf.pre = 2 ;
+  let (context, _) = kino::analyze_with("rsc/simple/pre.vmt", conf).unwrap() ;
+  let json = match context.get_prop( & context.factory().sym("small") ) {
+    Some( & (_, PropStatus::Falsified(ref cex)) ) => {
+      assert_eq!( cex.len(), 3 ) ;
+      cex.to_json(false)
+    },
+    _ => panic!("expected property `small` to be falsified"),
+  } ;
+  // History variables are unconstrained in the first state, and hold the
+  // previous values of `cnt` afterwards.
+  for & (state, values) in [
+    (1, "\"cnt\":\"1\",\"pre1.cnt\":\"0\","),
+    (2, "\"cnt\":\"2\",\"pre1.cnt\":\"1\",\"pre2.cnt\":\"0\""),
+    (3, "\"cnt\":\"3\",\"pre1.cnt\":\"2\",\"pre2.cnt\":\"1\""),
+  ].iter() {
+    let expected = format!("{{\"state\":{},\"values\":{{{}", state, values) ;
+    assert!(
+      json.contains(& expected), "expected `{}` in\n{}", expected, json
+    )
+  }
+}
@@ -0,0 +1,54 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Stores proofs, replays them with a fake checker, see `rsc/proof`, and
+//! clears them.
+
+extern crate term ;
+extern crate common ;
+
+use std::fs ;
+
+use term::{ Factory, SymMaker } ;
+use common::proof ;
+
+#[test]
+fn store_check_clear() {
+  let factory = Factory::mk() ;
+  let dir = std::env::temp_dir().join("kino_proof_store_check_clear") ;
+  let dir = dir.to_string_lossy().to_string() ;
+  let _ = fs::remove_dir_all(& dir) ;
+  let script = b"(declare-fun x () Int)\n(assert (< x 0))\n(check-sat)\n" ;
+  let good = proof::store(
+    & dir, "sys_0_step", & [ factory.sym("p"), factory.sym("q") ], script,
+    "(proof good)"
+  ).unwrap() ;
+  let bad = proof::store(
+    & dir, "sys_1_step", & [ factory.sym("r") ], script, "(proof bad)"
+  ).unwrap() ;
+
+  let problem = fs::File::open(format!("{}/sys_0_step.smt2", dir)) ;
+  let mut txt = String::new() ;
+  std::io::Read::read_to_string(& mut problem.unwrap(), & mut txt).unwrap() ;
+  assert!( txt.ends_with( std::str::from_utf8(script).unwrap() ) ) ;
+
+  let checked = proof::check(& dir, "sh rsc/proof/checker.sh").unwrap() ;
+  assert_eq!( checked.len(), 2 ) ;
+  assert_eq!( checked[0].0, good ) ;
+  assert_eq!( checked[0].1, vec![ "p".to_string(), "q".to_string() ] ) ;
+  assert!( checked[0].2.is_none() ) ;
+  assert_eq!( checked[1].0, bad ) ;
+  assert_eq!( checked[1].1, vec![ "r".to_string() ] ) ;
+  assert!( checked[1].2.is_some() ) ;
+
+  proof::clear(& dir).unwrap() ;
+  assert_eq!( fs::read_dir(& dir).unwrap().count(), 0 ) ;
+  let _ = fs::remove_dir_all(& dir) ;
+}
@@ -0,0 +1,35 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks that `--prop` rejects the names of no property of the verify
+//! query.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use common::conf::Master ;
+
+/// Verifies `rsc/simple/unqueried.vmt` checking some properties only.
+fn verify(props: & [& str]) -> bool {
+  let mut conf = Master::default() ;
+  conf.props = props.iter().map( |name| name.to_string() ).collect() ;
+  kino::analyze_with("rsc/simple/unqueried.vmt", conf).is_ok()
+}
+
+#[test]
+fn names() {
+  assert!( verify(& ["pos"]) ) ;
+  // Misspelled.
+  assert!( ! verify(& ["post"]) ) ;
+  // Defined, but not in the verify query.
+  assert!( ! verify(& ["small"]) ) ;
+  assert!( ! verify(& ["pos", "small"]) )
+}
@@ -0,0 +1,54 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Races two fake solvers, see `rsc/race`. `quick_check` wins the check
+//! but is slow on models, `quick_model` is the other way around. The model
+//! must come from `quick_check` anyway.
+
+extern crate term ;
+
+use term::{ Factory, Int, Cst, CstMaker } ;
+use term::smt::{
+  Kid, SolverConf, SolverStyle, MultiSolver, Query, solver, racer_conf
+} ;
+
+#[test]
+fn model_from_winner() {
+  let factory = Factory::mk() ;
+  let conf = SolverConf::z3().cmd( "rsc/race/quick_check.sh".to_string() ) ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let solver = solver(& mut kid, factory.clone()).unwrap() ;
+  let mut solver = MultiSolver::mk(
+    solver, & [ "rsc/race/quick_model.sh".to_string() ]
+  ).unwrap() ;
+  assert!( solver.check_sat().unwrap() ) ;
+  let model = solver.get_model().unwrap() ;
+  assert_eq!( model.len(), 1 ) ;
+  let one: Cst = factory.cst( Int::parse_bytes(b"1", 10).unwrap() ) ;
+  assert!( model[0].1 == one ) ;
+  // Pinned until the next check.
+  let model = solver.get_model().unwrap() ;
+  assert!( model[0].1 == one )
+}
+
+#[test]
+fn racer_confs() {
+  let z3 = SolverConf::z3().print_success() ;
+  let cvc4 = racer_conf(& z3, "/usr/local/bin/cvc4") ;
+  assert_eq!( cvc4.style().to_string(), SolverStyle::CVC4.to_string() ) ;
+  assert_eq!( cvc4.get_cmd(), "/usr/local/bin/cvc4" ) ;
+  assert_eq!( cvc4.get_options(), SolverConf::cvc4().get_options() ) ;
+  assert!( cvc4.get_parse_success() ) ;
+  let other = racer_conf(& z3, "z3-4.5") ;
+  assert_eq!( other.style().to_string(), SolverStyle::Z3.to_string() ) ;
+  assert_eq!( other.get_cmd(), "z3-4.5" ) ;
+  let script = racer_conf(& z3, "rsc/race/quick_check.sh") ;
+  assert_eq!( script.get_options(), z3.get_options() )
+}
@@ -0,0 +1,111 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks that a technique left with no property to check after some are
+//! proved is reassigned to the cluster of properties it is given.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate system ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::sync::Arc ;
+use std::thread ;
+use std::time::Duration ;
+
+use system::{ Sys, Prop } ;
+use common::{ Tek, CanRun } ;
+use common::msg::{ Event, KidManager, MsgDown, MsgUp, Status } ;
+
+/// Technique logging the properties it receives and forgets, stops after
+/// its first `Forget`.
+struct Echo(Tek) ;
+impl CanRun<()> for Echo {
+  fn id(& self) -> Tek { self.0 }
+  fn run(& self, _: Arc<()>, _: Sys, _: Vec<Prop>, mut event: Event) {
+    loop {
+      match event.recv() {
+        None => return (),
+        Some(msgs) => for msg in msgs {
+          match msg {
+            MsgDown::NewProps(props) => for prop in props.iter() {
+              event.log( & format!("new {}", prop.sym().get()) )
+            },
+            MsgDown::Forget(props, _) => {
+              for prop in props.iter() {
+                event.log( & format!("forget {}", prop) )
+              }
+              return ()
+            },
+            _ => (),
+          }
+        },
+      }
+      thread::sleep( Duration::from_millis(10) )
+    }
+  }
+}
+
+/// What the techniques of a manager log until `count` of them stopped.
+fn logs(manager: & KidManager, count: usize) -> Vec<(Tek, String)> {
+  let mut logs = vec![] ;
+  let mut stopped = 0 ;
+  while stopped < count {
+    match manager.recv().unwrap() {
+      MsgUp::Bla(tek, bla) => {
+        if bla.starts_with("forget") { stopped += 1 }
+        logs.push( (tek, bla) )
+      },
+      msg => panic!("unexpected message {}", msg),
+    }
+  }
+  logs
+}
+
+#[test]
+fn freed_technique_picks_up_the_cluster() {
+  let (context, sys, props) = fixture::load_check(
+    "rsc/simple/simple_two_props.vmt"
+  ) ;
+  let (first, second) = ( props[0].clone(), props[1].clone() ) ;
+  let mut manager = KidManager::mk() ;
+  // K-induction works on both properties, 2-induction on the first one.
+  manager.launch(
+    Echo(Tek::KInd), sys.clone(), props.clone(), context.factory(),
+    Arc::new(())
+  ).unwrap() ;
+  manager.launch(
+    Echo(Tek::Twind), sys, vec![ first.clone() ], context.factory(),
+    Arc::new(())
+  ).unwrap() ;
+
+  let reassigned = manager.forget_props(
+    & [ first.sym().get().clone() ], Status::Proved, & [ second.clone() ]
+  ) ;
+  assert_eq!( reassigned, vec![ Tek::Twind ] ) ;
+
+  let logs = logs(& manager, 2) ;
+  let forget = format!("forget {}", first.sym().get()) ;
+  let new = format!("new {}", second.sym().get()) ;
+  // 2-induction receives the cluster before forgetting the property.
+  let twind: Vec<& str> = logs.iter().filter(
+    |& & (tek, _)| tek == Tek::Twind
+  ).map( |& (_, ref bla)| bla.as_str() ).collect() ;
+  assert_eq!( twind, vec![ new.as_str(), forget.as_str() ] ) ;
+  // K-induction still has the second property, it only forgets the first.
+  let kind: Vec<& str> = logs.iter().filter(
+    |& & (tek, _)| tek == Tek::KInd
+  ).map( |& (_, ref bla)| bla.as_str() ).collect() ;
+  assert_eq!( kind, vec![ forget.as_str() ] )
+}
@@ -0,0 +1,129 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks the options of `--reload` are read again on each reload, on top
+//! of the ones of the command line, and that the budgets of check-sats of
+//! the techniques running are updated when the file changes.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate system ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use std::fs::File ;
+use std::io::Write ;
+use std::sync::Arc ;
+use std::thread ;
+use std::time::Duration ;
+
+use system::{ Sys, Prop } ;
+use common::{ Tek, CanRun, pause } ;
+use common::conf::Master ;
+use common::msg::{ Event, KidManager, MsgUp } ;
+
+/// Writes some options to a file.
+fn write(path: & str, options: & str) {
+  let mut file = File::create(path).unwrap() ;
+  file.write_all( options.as_bytes() ).unwrap()
+}
+
+/// A file for the options of a test, unique to the process.
+fn path_of(test: & str) -> String {
+  std::env::temp_dir().join(
+    format!("kino_reload_{}_{}", test, std::process::id())
+  ).to_string_lossy().to_string()
+}
+
+/// Technique issuing (fake) check-sats until its budget is exhausted.
+struct Queries ;
+impl CanRun<()> for Queries {
+  fn id(& self) -> Tek { Tek::KInd }
+  fn run(& self, _: Arc<()>, _: Sys, _: Vec<Prop>, mut event: Event) {
+    loop {
+      if event.recv().is_none() { return () }
+      if ! event.query() {
+        event.log( & format!("stopped after {}", event.queries()) ) ;
+        return ()
+      }
+      thread::sleep( Duration::from_millis(5) )
+    }
+  }
+}
+
+#[test]
+fn reloads_budgets_while_running() {
+  let path = path_of("running") ;
+  write(& path, "") ;
+  let mut conf = Master::default().with(
+    "kind", "max_queries", "1000"
+  ).unwrap() ;
+  conf.reload = Some( path.clone() ) ;
+
+  let (context, sys, props) = fixture::load_check(
+    "rsc/simple/simple_two_props.vmt"
+  ) ;
+  let mut manager = KidManager::mk() ;
+  let _watch = pause::watch( path.clone(), manager.sender() ) ;
+  manager.launch(
+    Queries, sys, props, context.factory(), Arc::new(())
+  ).unwrap() ;
+  // Making sure the modification time changes.
+  thread::sleep( Duration::from_millis(20) ) ;
+  write(& path, "kind(max_queries: 200)") ;
+
+  let mut reloaded = false ;
+  loop {
+    match manager.recv().unwrap() {
+      MsgUp::Reload => {
+        let conf = conf.reloaded().unwrap() ;
+        manager.reload( |tek| conf.max_queries_of(tek) ) ;
+        reloaded = true
+      },
+      MsgUp::Bla(_, ref bla) if bla.starts_with("stopped") => {
+        assert!(reloaded) ;
+        assert_eq!(bla, "stopped after 200") ;
+        break
+      },
+      _ => (),
+    }
+  }
+  let _ = std::fs::remove_file(& path) ;
+}
+
+#[test]
+fn reloads() {
+  let path = path_of("options") ;
+  let mut conf = Master::default().with("bmc", "max", "3").unwrap() ;
+  conf.reload = Some( path.clone() ) ;
+
+  write(& path, "") ;
+  let reloaded = conf.reloaded().unwrap() ;
+  assert_eq!( * reloaded.bmc.unwrap().max(), Some(3) ) ;
+
+  write(& path, "bmc(max: 7), kind(max: 2)") ;
+  let reloaded = conf.reloaded().unwrap() ;
+  assert_eq!( * reloaded.bmc.unwrap().max(), Some(7) ) ;
+  assert_eq!( * reloaded.kind.unwrap().max(), Some(2) ) ;
+
+  // Options removed from the file are back to the ones of the command line.
+  write(& path, "kind(max: 2)") ;
+  let reloaded = conf.reloaded().unwrap() ;
+  assert_eq!( * reloaded.bmc.unwrap().max(), Some(3) ) ;
+
+  write(& path, "bmc(max: seven)") ;
+  assert!( conf.reloaded().is_err() ) ;
+
+  let _ = std::fs::remove_file(& path) ;
+  assert!( conf.reloaded().is_err() )
+}
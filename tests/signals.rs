@@ -0,0 +1,72 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks that `SIGUSR1` pauses the techniques and `SIGUSR2` resumes them.
+//!
+//! The signals apply to every analysis of the process, this test has its
+//! own binary.
+
+extern crate kino_api as kino ;
+extern crate common ;
+extern crate libc ;
+
+use std::thread ;
+use std::time::{ Instant, Duration } ;
+use std::sync::mpsc::channel ;
+
+use libc::{ kill, getpid, c_int, SIGUSR1, SIGUSR2 } ;
+
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+use common::{ cleanup, pause } ;
+
+/// Sends a signal to the process, waits for it to be handled.
+fn signal(sig: c_int, paused: bool) {
+  assert_eq!( unsafe { kill( getpid(), sig ) }, 0 ) ;
+  let start = Instant::now() ;
+  while pause::paused() != paused {
+    assert!( start.elapsed() < Duration::from_secs(2) ) ;
+    thread::sleep( Duration::from_millis(10) )
+  }
+}
+
+#[test]
+fn paused_and_resumed_by_signals() {
+  cleanup::install() ;
+  // Only BMC, which falsifies `small` at 20.
+  let conf = Master::default().with("all", "turn", "off").and_then(
+    |conf| conf.with("bmc", "turn", "on")
+  ).unwrap() ;
+  let (sender, receiver) = channel() ;
+  let analysis = thread::spawn(
+    move || {
+      let (context, _) = kino::analyze_with(
+        "rsc/simple/deep_false.vmt", conf
+      ).unwrap() ;
+      let falsified = match context.get_prop(
+        & context.factory().sym("small")
+      ) {
+        Some( & (_, PropStatus::Falsified(ref cex)) ) => Some( cex.len() ),
+        _ => None,
+      } ;
+      sender.send(falsified).unwrap()
+    }
+  ) ;
+
+  // Whether the kids are launched already or not, they pause at their next
+  // query boundary.
+  signal(SIGUSR1, true) ;
+  assert!( receiver.recv_timeout( Duration::from_secs(2) ).is_err() ) ;
+
+  signal(SIGUSR2, false) ;
+  assert_eq!( receiver.recv().unwrap(), Some(20) ) ;
+  analysis.join().unwrap()
+}
@@ -27,7 +27,7 @@ mk_test!{
 #[test]
 mk_test!{
   simple_two_props, path_to("simple_two_props"),
-  "out_positive" => exp!(inv 1),
+  "out_pos" => exp!(inv 1),
   "out_le_10" => exp!(false 10),
 }
 
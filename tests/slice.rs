@@ -0,0 +1,38 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that slicing keeps what the variables kept depend on, and only
+//! that.
+
+extern crate kino_api as kino ;
+extern crate term ;
+
+mod common ;
+
+use term::SymMaker ;
+
+#[test]
+fn modular() {
+  let (context, sys, _) = common::load_check("rsc/simple/modular.vmt") ;
+  let factory = context.factory() ;
+  assert_eq!( sys.subsys().len(), 1 ) ;
+
+  // The output comes from `sys_a`, which depends on all the state.
+  let sliced = sys.slice( & [ factory.sym("output(b,out)") ], factory ) ;
+  assert_eq!( sliced.subsys().len(), 1 ) ;
+  assert_eq!( sliced.init().2, sys.init().2 ) ;
+  assert_eq!( sliced.trans().2, sys.trans().2 ) ;
+
+  // Nothing constrains the next value of an input.
+  let sliced = sys.slice( & [ factory.sym("input(b,deact)") ], factory ) ;
+  assert!( sliced.subsys().is_empty() ) ;
+  assert!( sliced.init().2 != sys.init().2 ) ;
+  assert!( sliced.trans().2 != sys.trans().2 ) ;
+  assert_eq!( sliced.state().len(), sys.state().len() )
+}
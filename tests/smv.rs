@@ -0,0 +1,36 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that SMV models are translated and loaded.
+
+extern crate kino_api as kino ;
+extern crate system ;
+
+mod common ;
+
+use system::smv::translate ;
+
+#[test]
+fn counter() {
+  let (_, sys, props) = common::load_check("rsc/smv/counter.smv") ;
+  assert_eq!( sys.state().len(), 3 ) ;
+  let mut names: Vec<String> = props.iter().map(
+    |prop| prop.sym().get().sym().to_string()
+  ).collect() ;
+  names.sort() ;
+  assert_eq!( names, vec![ "bounded", "invarspec_2" ] )
+}
+
+#[test]
+fn unsupported() {
+  assert!( translate("MODULE main VAR x : boolean; LTLSPEC G x").is_err() ) ;
+  assert!( translate("MODULE m(a) VAR x : boolean;").is_err() ) ;
+  // Nothing to verify.
+  assert!( translate("MODULE main VAR x : boolean;").is_err() )
+}
@@ -0,0 +1,65 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks the statuses of the properties split in sub-properties, see
+//! `--split`, once recombined.
+
+extern crate kino_api as kino ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use kino::{ Sym, SymMaker, PropStatus } ;
+
+/// Sub-properties of a property split in two, as symbols.
+fn split(context: & mut kino::Context, name: & str) -> (Sym, Sym) {
+  let sym = context.factory().sym(name) ;
+  let subs = context.split_prop(& sym, 2).unwrap() ;
+  assert_eq!( subs.len(), 2 ) ;
+  ( subs[0].sym().get().clone(), subs[1].sym().get().clone() )
+}
+
+#[test]
+fn weakest() {
+  let (mut context, _, _) = fixture::load_check("rsc/simple/split.vmt") ;
+
+  // Both sub-properties proved: proved at the highest depth.
+  let (left, right) = split(& mut context, "both") ;
+  context.set_prop_inv(& left, 1).unwrap() ;
+  context.set_prop_inv(& right, 4).unwrap() ;
+  let both = context.factory().sym("both") ;
+  assert_eq!(
+    context.recombine_prop(& both).unwrap(), Some( (2, 2, 0) )
+  ) ;
+  match context.get_prop(& both) {
+    Some( & (_, PropStatus::Invariant(4)) ) => (),
+    Some( & (_, ref status) ) => panic!(
+      "expected `both` to be 4-inductive, got {}", status
+    ),
+    None => panic!("unknown property `both`"),
+  }
+
+  // One of them only true up to some depth: the property is too.
+  let (left, right) = split(& mut context, "mixed") ;
+  context.set_prop_inv(& left, 2).unwrap() ;
+  context.set_prop_k_true(& right, 3).unwrap() ;
+  let mixed = context.factory().sym("mixed") ;
+  assert_eq!(
+    context.recombine_prop(& mixed).unwrap(), Some( (2, 1, 0) )
+  ) ;
+  match context.get_prop(& mixed) {
+    Some( & (_, PropStatus::KTrue(3)) ) => (),
+    Some( & (_, ref status) ) => panic!(
+      "expected `mixed` to be 3-true, got {}", status
+    ),
+    None => panic!("unknown property `mixed`"),
+  }
+}
@@ -0,0 +1,76 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Checks that the stable hashes of the systems and of their properties do
+//! not depend on the order the factory hash conses the symbols in.
+
+extern crate kino_api as kino ;
+extern crate term ;
+
+use std::fs ;
+use std::io::Read ;
+
+use term::{ Factory, Sym, SymMaker, StableHash } ;
+use kino::{ Context, Sys, Prop, CtxtRes } ;
+
+/// Loads a file in a new factory. If `shuffled`, the factory first creates
+/// the symbols of the file in reverse order, so that they are hash consed
+/// in a different order. They are returned so that they stay in the
+/// consign.
+fn load(
+  path: & str, shuffled: bool
+) -> (Context, Sys, Vec<Prop>, Vec<Sym>) {
+  let mut txt = String::new() ;
+  fs::File::open(path).unwrap().read_to_string(& mut txt).unwrap() ;
+  let factory = Factory::mk() ;
+  let mut syms = vec![] ;
+  if shuffled {
+    let tokens: Vec<& str> = txt.split(
+      |c: char| c.is_whitespace() || c == '(' || c == ')'
+    ).filter( |token| ! token.is_empty() ).collect() ;
+    for token in tokens.into_iter().rev() {
+      syms.push( factory.sym(token) )
+    }
+  }
+  let mut context = Context::mk(factory, 1000) ;
+  match context.read(& mut txt.as_bytes()).unwrap() {
+    CtxtRes::Check(sys, props) => (context, sys, props, syms),
+    _ => panic!("no verify query in `{}`", path),
+  }
+}
+
+/// Stable hashes of the system and of the properties of a file.
+fn hashes(file: & str, shuffled: bool) -> (u64, Vec<u64>) {
+  let path = format!("rsc/simple/{}.vmt", file) ;
+  let (_context, sys, props, _syms) = load(& path, shuffled) ;
+  // The factories do hash cons differently.
+  if shuffled {
+    let plain = load(& path, false) ;
+    assert!( sys.sym().uid() != plain.1.sym().uid() )
+  }
+  (
+    sys.stable_hash(),
+    props.iter().map( |prop| prop.stable_hash() ).collect()
+  )
+}
+
+#[test]
+fn independent_of_creation_order() {
+  let mut systems = vec![] ;
+  for file in & [ "simple", "simple_calls", "modular", "dependent_funs" ] {
+    let plain = hashes(file, false) ;
+    assert_eq!( plain, hashes(file, true) ) ;
+    systems.push(plain.0)
+  }
+  // Different systems, different hashes.
+  for (index, hash) in systems.iter().enumerate() {
+    assert!( ! systems[ index + 1 .. ].contains(hash) )
+  }
+}
@@ -0,0 +1,53 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks the assumptions suggested to rule out a counterexample.
+
+extern crate kino_api as kino ;
+extern crate term ;
+
+mod common ;
+
+use term::{ Factory, Int, Cst, Offset, SymMaker, VarMaker, CstMaker } ;
+
+/// An integer constant.
+fn int(factory: & Factory, int: & str) -> Cst {
+  factory.cst( Int::parse_bytes(int.as_bytes(), 10u32).unwrap() )
+}
+
+#[test]
+fn bounds_are_numeric() {
+  let (context, sys, _) = common::load_check("rsc/simple/int_input.vmt") ;
+  let factory = context.factory() ;
+  let (input, sum) = ( factory.sym("in"), factory.sym("sum") ) ;
+  // Created in an order different from their numeric order.
+  let values = vec![
+    int(factory, "7"), int(factory, "-5"), int(factory, "2")
+  ] ;
+  let mut model = vec![] ;
+  let mut off = Offset::zero() ;
+  for value in values.iter() {
+    model.push(
+      ( (factory.var( input.clone() ), Some(off)), value.clone() )
+    ) ;
+    model.push(
+      ( (factory.var( sum.clone() ), Some(off)), int(factory, "0") )
+    ) ;
+    off = off.nxt()
+  }
+  let cex = context.cex_of(& model, & sys) ;
+  let suggestions = cex.suggest_assumptions() ;
+  let var = format!("(_ curr {})", input) ;
+  assert_eq!(
+    suggestions, vec![
+      format!("(> {} {})", var, values[1]),
+      format!("(< {} {})", var, values[0]),
+    ]
+  )
+}
@@ -0,0 +1,87 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+//! Checks the synthesis of the coefficients of the linear templates of TIG.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate tig ;
+extern crate common ;
+
+use term::Rat ;
+use tig::templates::{ Ineq, hull, holds } ;
+use kino::{ SymMaker, PropStatus } ;
+use common::conf::Master ;
+
+/// A point with integer coordinates.
+fn point(x: isize, y: isize) -> (Rat, Rat) {
+  ( Rat::from_integer( x.into() ), Rat::from_integer( y.into() ) )
+}
+
+/// A template with integer coefficients.
+fn ineq(a: isize, b: isize, c: isize) -> Ineq {
+  let (a, b) = point(a, b) ;
+  (a, b, Rat::from_integer( c.into() ))
+}
+
+#[test]
+fn hull_of_a_point() {
+  let ineqs = hull(& [ point(3, -1) ]) ;
+  assert_eq!(ineqs.len(), 4) ;
+  assert!( ineqs.contains(& ineq(1, 0, 3)) ) ;
+  assert!( ineqs.contains(& ineq(-1, 0, -3)) ) ;
+  assert!( ineqs.contains(& ineq(0, 1, -1)) ) ;
+  assert!( ineqs.contains(& ineq(0, -1, 1)) )
+}
+
+#[test]
+fn hull_of_aligned_points() {
+  let ineqs = hull(& [ point(4, 2), point(0, 0), point(2, 1) ]) ;
+  assert_eq!(ineqs.len(), 4) ;
+  // The line `x = 2*y`.
+  assert!( ineqs.contains(& ineq(1, -2, 0)) ) ;
+  assert!( ineqs.contains(& ineq(-1, 2, 0)) ) ;
+  // The ends of the segment.
+  assert!( ineqs.contains(& ineq(2, 1, 10)) ) ;
+  assert!( ineqs.contains(& ineq(-2, -1, 0)) ) ;
+  assert!( ! holds( & ineq(1, -2, 0), & point(1, 0) ) )
+}
+
+#[test]
+fn hull_of_a_triangle() {
+  let points = [
+    point(0, 0), point(3, 0), point(0, 2), point(1, 1), point(3, 0)
+  ] ;
+  let ineqs = hull(& points) ;
+  assert_eq!(ineqs.len(), 3) ;
+  assert!( ineqs.contains(& ineq(0, -1, 0)) ) ;
+  assert!( ineqs.contains(& ineq(-1, 0, 0)) ) ;
+  assert!( ineqs.contains(& ineq(2, 3, 6)) ) ;
+  for ineq in ineqs.iter() {
+    for point in points.iter() { assert!( holds(ineq, point) ) }
+  }
+  assert!( ! holds( & ineq(2, 3, 6), & point(2, 1) ) )
+}
+
+#[test]
+fn proves_with_synthesized_coefficients() {
+  let mut conf = Master::default().with("tig", "templates", "3").unwrap() ;
+  conf.timeout = Some(60) ;
+  let (context, _) = kino::analyze_with(
+    "rsc/simple/templates.vmt", conf
+  ).unwrap() ;
+  match context.get_prop( & context.factory().sym("far") ) {
+    Some( & (_, PropStatus::Invariant(_)) ) |
+    Some( & (_, PropStatus::MinInvariant(_, _)) ) => (),
+    Some( & (_, ref status) ) => panic!("unexpected status {}", status),
+    None => panic!("unknown property far"),
+  }
+}
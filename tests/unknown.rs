@@ -0,0 +1,102 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Checks the policies of the unroller on `unknown` answers, with a fake
+//! solver answering `unknown` until its random seed is changed, and the
+//! timeouts of the checks with one answering `unknown` once they have one,
+//! see `rsc/unknown`.
+
+extern crate kino_api as kino ;
+extern crate term ;
+extern crate unroll ;
+extern crate common ;
+
+#[path = "common/mod.rs"]
+mod fixture ;
+
+use term::smt::{
+  Kid, SolverConf, SolverStyle, solver, seed_option, timeout_option
+} ;
+use unroll::Unroller ;
+use common::conf::OnUnknown ;
+use common::errors::unknown_reason ;
+
+/// Checks the system of `rsc/simple/within.vmt` on the fake solver with
+/// some policy. Returns the result, the number of `unknown` answers, and
+/// whether the check was treated as `sat`.
+fn check(
+  on_unknown: OnUnknown, style: SolverStyle
+) -> (Result<bool, common::errors::Error>, usize, bool) {
+  let (context, sys, props) = fixture::load_check("rsc/simple/within.vmt") ;
+  let conf = SolverConf::z3().cmd( "rsc/unknown/seeded.sh".to_string() ) ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk(& sys, & props, solver).unwrap() ;
+  unroller.set_on_unknown(on_unknown) ;
+  unroller.set_seed_option( seed_option(& style) ) ;
+  let res = unroller.check_sat() ;
+  (res, unroller.unknowns(), unroller.last_unknown())
+}
+
+#[test]
+fn abort() {
+  let (res, unknowns, _) = check(OnUnknown::Abort, SolverStyle::Z3) ;
+  assert_eq!(
+    unknown_reason( & res.unwrap_err() ), Some( "incomplete".to_string() )
+  ) ;
+  assert_eq!(unknowns, 1)
+}
+
+#[test]
+fn skip() {
+  // Skipping is up to the technique, the check fails as with `Abort`.
+  let (res, unknowns, _) = check(OnUnknown::Skip, SolverStyle::Z3) ;
+  assert!( unknown_reason( & res.unwrap_err() ).is_some() ) ;
+  assert_eq!(unknowns, 1)
+}
+
+#[test]
+fn sat() {
+  let (res, unknowns, last_unknown) = check(OnUnknown::Sat, SolverStyle::Z3) ;
+  assert!( res.unwrap() ) ;
+  assert!(last_unknown) ;
+  assert_eq!(unknowns, 1)
+}
+
+#[test]
+fn retry() {
+  let (res, unknowns, last_unknown) = check(
+    OnUnknown::Retry, SolverStyle::Z3
+  ) ;
+  assert!( res.unwrap() ) ;
+  assert!( ! last_unknown ) ;
+  assert_eq!(unknowns, 1) ;
+  // cvc4 cannot change its seed, retrying aborts.
+  let (res, unknowns, _) = check(OnUnknown::Retry, SolverStyle::CVC4) ;
+  assert!( unknown_reason( & res.unwrap_err() ).is_some() ) ;
+  assert_eq!(unknowns, 1)
+}
+
+#[test]
+fn timeout() {
+  let (context, sys, props) = fixture::load_check("rsc/simple/within.vmt") ;
+  let conf = SolverConf::z3().cmd( "rsc/unknown/timeout.sh".to_string() ) ;
+  let mut kid = Kid::mk(conf).unwrap() ;
+  let solver = solver(& mut kid, context.factory().clone()).unwrap() ;
+  let mut unroller = Unroller::mk(& sys, & props, solver).unwrap() ;
+  assert!( unroller.check_sat().unwrap() ) ;
+  assert_eq!( timeout_option(& SolverStyle::CVC4), ":tlimit-per" ) ;
+  unroller.set_timeout( timeout_option(& SolverStyle::Z3), 10 ).unwrap() ;
+  let res = unroller.check_sat() ;
+  assert_eq!(
+    unknown_reason( & res.unwrap_err() ), Some( "timeout".to_string() )
+  ) ;
+  assert_eq!(unroller.unknowns(), 1)
+}
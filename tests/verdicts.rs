@@ -0,0 +1,54 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+
+
+//! Checks that the verdicts cached across runs are keyed by the lemmas they
+//! can depend on.
+
+extern crate kino_api as kino ;
+extern crate common ;
+
+use std::fs ;
+
+use common::conf::Master ;
+
+/// Verifies a file with a verdict cache, only checking some properties if
+/// not empty.
+fn verify(dir: & str, props: & [& str]) {
+  let mut conf = Master::default() ;
+  conf.cache = Some( dir.to_string() ) ;
+  conf.props = props.iter().map( |prop| prop.to_string() ).collect() ;
+  kino::analyze_with("rsc/simple/simple_two_props.vmt", conf).unwrap() ;
+  ()
+}
+
+/// Number of entries in a directory.
+fn entries(dir: & str) -> usize {
+  fs::read_dir(dir).map( |entries| entries.count() ).unwrap_or(0)
+}
+
+#[test]
+fn keyed_by_lemmas() {
+  let dir = std::env::temp_dir().join("kino_verdicts_keyed_by_lemmas") ;
+  let dir = dir.to_string_lossy().to_string() ;
+  let _ = fs::remove_dir_all(& dir) ;
+
+  verify(& dir, & []) ;
+  assert_eq!( entries(& dir), 1 ) ;
+  // Same properties, same lemmas: the verdicts are reused.
+  verify(& dir, & []) ;
+  assert_eq!( entries(& dir), 1 ) ;
+  // Checked alone, `out_pos` has other lemmas.
+  verify(& dir, & ["out_pos"]) ;
+  assert_eq!( entries(& dir), 2 ) ;
+
+  let _ = fs::remove_dir_all(& dir) ;
+}
@@ -0,0 +1,34 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks the VMT export of a system with a sub-system.
+
+extern crate kino_api as kino ;
+
+mod common ;
+
+#[test]
+fn modular() {
+  let (_, sys, props) = common::load_check("rsc/simple/modular.vmt") ;
+  let mut bytes = vec![] ;
+  sys.to_vmt(& props, & mut bytes).unwrap() ;
+  let vmt = String::from_utf8(bytes).unwrap() ;
+
+  assert!( vmt.contains("(declare-fun |oracle| () Bool)") ) ;
+  assert!( vmt.contains("(declare-fun |output(b,out)| () Int)") ) ;
+  assert!( vmt.contains("(declare-fun |output(b,out).next| () Int)") ) ;
+  assert!( vmt.contains(":next |output(b,out).next|") ) ;
+  assert!( vmt.contains("(define-fun |init[sys_a]| ((") ) ;
+  assert!( vmt.contains("(define-fun |trans[sys_a]| (") ) ;
+  assert!( vmt.contains(":init true") ) ;
+  assert!( vmt.contains(":trans true") ) ;
+  // The only property is two-state, it has no VMT counterpart.
+  assert!( ! vmt.contains(":invar-property") ) ;
+  assert!( vmt.contains("; skipped property") )
+}
@@ -89,6 +89,10 @@ impl<Val: Domain> Eval<Val> {
     ()
   }
 
+  /// The model the evaluator evaluates with.
+  #[inline]
+  pub fn model(& self) -> & Model { & self.model }
+
   /// Evaluates a real term. Cached at top level.
   pub fn eval_term(& mut self, term: & Term) -> Res<Val> {
     self.eval(
@@ -96,6 +100,41 @@ impl<Val: Domain> Eval<Val> {
     )
   }
 
+  /// Evaluates some real terms, sharing the evaluation of their common
+  /// subterms. Cached at top level.
+  pub fn eval_terms(& mut self, terms: & [Term]) -> Res< Vec<Val> > {
+    let todo: Vec<Term> = terms.iter().filter(
+      |term| ! self.cache.contains_key( & TmpTerm::Trm( (* term).clone() ) )
+    ).cloned().collect() ;
+    let values = try_chain!(
+      self.factory.eval_all(
+        & todo, & self.offset, & self.model, self.sys.sym().get().clone()
+      ) => "could not evaluate {} terms", todo.len()
+    ) ;
+    for (term, value) in todo.into_iter().zip( values.into_iter() ) {
+      self.cache.insert( TmpTerm::Trm(term), value ) ;
+    }
+    let mut res = Vec::with_capacity( terms.len() ) ;
+    for term in terms.iter() {
+      match self.cache.get( & TmpTerm::Trm( term.clone() ) ) {
+        Some(cst) => res.push(
+          try_chain!(
+            Val::of_cst(cst) => "while evaluating term {}", term
+          )
+        ),
+        None => bail!("term {} was not evaluated", term),
+      }
+    }
+    Ok(res)
+  }
+
+  /// Evaluates a real term to a constant, without caching.
+  pub fn value_of(& self, term: & Term) -> Res<Cst> {
+    self.factory.eval(
+      term, & self.offset, & self.model, self.sys.sym().get().clone()
+    ).chain_err( || format!("could not evaluate term {}", term) )
+  }
+
   /// Evaluates a temp term, cached at temp level.
   pub fn eval(& mut self, term: & TmpTerm) -> Res<Val> {
     use term::zip::Step ;
@@ -12,18 +12,19 @@ technique. */
 
 use std::io ;
 
-use common::msg::Event ;
+use common::msg::{ Event, GraphEvent } ;
+use common::samples::SamplePool ;
 use common::conf ;
 use common::errors::* ;
 
 use term::{
-  Sym, Factory, Term, TermSet, TermMap, Bool
+  Sym, Factory, Term, TermSet, TermMap, Bool, MemUsage, Offset2
 } ;
 use term::tmp::{ TmpTerm, TmpTermSet, TmpTermMap } ;
 
-use system::Sys ;
+use system::{ Sys, Prop } ;
 
-use Domain ;
+use { Domain, Octagon, Interval } ;
 use eval::Eval ;
 use chain::* ;
 use lsd::* ;
@@ -72,6 +73,10 @@ pub trait CanStabilize {
   type Val: Domain ;
   /// Stabilizes itself given an evaluator.
   fn split(& mut self, & mut Eval< Self::Val >) -> Res<()> ;
+  /// Records the classes split from now on.
+  fn record_splits(& mut self) ;
+  /// The classes split since the last call, empty if not recording.
+  fn splits(& mut self) -> Vec<GraphEvent> ;
 }
 
 /// Can log itself.
@@ -531,6 +536,12 @@ pub struct Learner<Graph: HasClasses> {
   early_eqs: bool,
   /// Activates early cmp invariant discovery.
   early_cmps: bool,
+  /// Reports the evolution of the graph.
+  events: bool,
+  /// The system, for evaluation.
+  system: Sys,
+  /// Number of reachable states from the event already used for splitting.
+  sampled: usize,
 }
 
 impl<Graph: HasClasses> CanLog for Learner<Graph> {
@@ -551,15 +562,20 @@ impl<
   pub fn mk(
     sys: Sys, rep: Term, class: TermSet, factory: Factory, conf: & conf::Tig
   ) -> Self {
+    let mut graph = Graph::mk(rep, class) ;
+    if * conf.graph_events() { graph.record_splits() }
     Learner {
       sys: sys.sym().get().clone(),
-      graph: Graph::mk(rep, class),
+      system: sys.clone(),
+      sampled: 0,
+      graph: graph,
       known: TmpTermSet::with_capacity(211),
       stable: TermSet::with_capacity(17),
       factory: factory,
       candidates: TmpTermMap::with_capacity(211),
       early_eqs: * conf.early_eqs(),
       early_cmps: * conf.early_cmps(),
+      events: * conf.graph_events(),
     }
   }
 
@@ -580,6 +596,65 @@ impl<
     res
   }
 
+  /// Splits the graph with the reachable states received since the last
+  /// call: the candidates they falsify are not invariants. The pending
+  /// candidates they falsify are dropped.
+  ///
+  /// Must be called before stabilization, as it changes the
+  /// representatives.
+  pub fn split_with_samples(& mut self, event: & Event) -> Res<()> {
+    let samples = & event.samples().samples()[self.sampled ..] ;
+    if samples.is_empty() { return Ok(()) }
+    self.sampled += samples.len() ;
+    let mut eval = Eval::mk(
+      self.system.clone(), vec![], Offset2::init(), self.factory.clone()
+    ) ;
+    let mut bool_eval = Eval::<Bool>::mk(
+      self.system.clone(), vec![], Offset2::init(), self.factory.clone()
+    ) ;
+    for sample in samples {
+      let model = SamplePool::model_of(sample, & self.factory) ;
+      bool_eval.recycle( model.clone(), Offset2::init() ) ;
+      eval.recycle( model, Offset2::init() ) ;
+      // Candidates that cannot be evaluated on the sample are kept.
+      let falsified: Vec<TmpTerm> = self.candidates.keys().filter(
+        |cand| match bool_eval.eval(cand) {
+          Ok(value) => ! value,
+          Err(_) => false,
+        }
+      ).cloned().collect() ;
+      for cand in falsified.iter() {
+        self.candidates.remove(cand) ; ()
+      }
+      try_chain!(
+        self.graph.split(& mut eval)
+        => "[Learner::split_with_samples] while splitting with a sample"
+      )
+    }
+    self.send_splits(event) ;
+    Ok(())
+  }
+
+  /// Sends the classes split since the last call, if reporting the
+  /// evolution of the graph.
+  fn send_splits(& mut self, event: & Event) {
+    for split in self.graph.splits() {
+      event.graph(split)
+    }
+  }
+
+  /// Approximate memory usage of the learner.
+  pub fn mem_usage(& self) -> Vec<MemUsage> {
+    vec![
+      MemUsage::of_table::<Term>("candidates", self.len()),
+      MemUsage::of_table::<TmpTerm>("known", self.known.len()),
+      MemUsage::of_table::<Term>("stable", self.stable.len()),
+      MemUsage::of_table::<(TmpTerm, Option<(Term, Term)>)>(
+        "candidates to check", self.candidates.len()
+      ),
+    ]
+  }
+
 
   /// Receives invariants, updates the checkers.
   fn recv<Base, Step>(
@@ -591,6 +666,8 @@ impl<
 
     let err_pref = "[Learner::recv]" ;
 
+    event.stats( self.mem_usage() ) ;
+
     match event.recv() {
       None => (),
       Some(msgs) => for msg in msgs {
@@ -612,8 +689,8 @@ impl<
                 err_pref, step.unroll_len()
             ) ;
           },
-          msg => event.error(
-            format!("unknown message `{:?}`", msg).into()
+          msg => log_error!(
+            event, format!("unknown message `{:?}`", msg)
           ),
         }
       },
@@ -651,11 +728,14 @@ impl<
       let mut set = STermSet::with_capacity(invars.len()) ;
       for (invar, info) in invars.into_iter() {
         if let Some( (rep, to_drop) ) = info {
-          try_chain!(
+          let dropped = try_chain!(
             self.graph.drop_term(& rep, & to_drop)
             => "{} while dropping `{}` from the class of `{}`",
               err_pref, to_drop, rep
           ) ;
+          if dropped && self.events {
+            event.graph( GraphEvent::Dropped(rep, to_drop) )
+          }
         }
         let wasnt_there = self.known.insert(invar.clone()) ;
         debug_assert!(wasnt_there) ;
@@ -742,8 +822,11 @@ impl<
       ) ;
 
       if let Some(mut eval) = eval_opt {
+        // The trace of the base checker is reachable.
+        event.reachable( eval.model() ) ;
         // event.log("class is not stable, splitting") ;
         try!( self.graph.split(& mut eval) ) ;
+        self.send_splits(event) ;
         next = match get_next(self) {
           Some(next) => next,
           None => bail!(
@@ -814,6 +897,21 @@ impl<
     return None
   }
 
+  /// Sends the classes of the graph and the representatives of their kids,
+  /// if reporting the evolution of the graph. Called once the graph is
+  /// stable at some iteration.
+  pub fn send_stable(& self, iteration: usize, event: & Event) {
+    if ! self.events { return () }
+    let mut classes = Vec::with_capacity( self.graph.classes().len() ) ;
+    for (rep, class) in self.graph.classes().iter() {
+      let kids = self.graph.kids_of(rep).map(
+        |kids| kids.iter().cloned().collect()
+      ).unwrap_or_else( |_| vec![] ) ;
+      classes.push( (rep.clone(), class.len() + 1, kids) )
+    }
+    event.graph( GraphEvent::Stable(iteration, classes) )
+  }
+
   /// Stabilizes an equivalence class, extracts invariants.
   /// **Communicates invariants itself.** Returns `true` iff the graph is
   /// stable in base.
@@ -865,7 +963,9 @@ impl<
       ) ;
 
       if let Some(mut eval) = eval_opt {
-        try!( self.graph.split(& mut eval) )
+        event.reachable( eval.model() ) ;
+        try!( self.graph.split(& mut eval) ) ;
+        self.send_splits(event)
       } else {
         break 'base_cmp
       }
@@ -959,13 +1059,57 @@ impl<
 
 
 /// Creates a graph-based learner.
+///
+/// The candidates are limited as specified by the configuration, the
+/// properties decide which ones to keep when there are too many.
 pub fn mk_bool_learner(
-  sys: Sys, factory: Factory, conf: & conf::Tig
+  sys: Sys, props: & [Prop], factory: Factory, conf: & conf::Tig
 ) -> Learner< Graph<Bool> > {
-  let (rep, class) = mine::bool(& factory, & sys, * conf.all_out()) ;
+  let (rep, mut class) = mine::bool(
+    & factory, & sys, * conf.all_out(), * conf.max_cand_size()
+  ) ;
+  if let Some(max) = * conf.max_cands() {
+    // The representative counts as a candidate.
+    class = mine::keep_relevant( class, props, max.saturating_sub(1) )
+  }
   Learner::mk(sys, rep, class, factory, conf)
 }
 
+/// Creates a learner over the octagonal candidate terms of a system, `None`
+/// if it has no numeric state variable.
+///
+/// The candidates are limited as for boolean learners.
+pub fn mk_octagon_learner(
+  sys: Sys, props: & [Prop], factory: Factory, conf: & conf::Tig
+) -> Option< Learner< Graph<Octagon> > > {
+  let (rep, mut class) = match mine::octagons(& factory, & sys) {
+    Some(candidates) => candidates,
+    None => return None,
+  } ;
+  if let Some(max) = * conf.max_cands() {
+    class = mine::keep_relevant( class, props, max.saturating_sub(1) )
+  }
+  Some( Learner::mk(sys, rep, class, factory, conf) )
+}
+
+/// Creates a learner over the interval candidate terms of a system, mined
+/// from some samples. `None` if it has no numeric state variable.
+///
+/// The candidates are limited as for boolean learners.
+pub fn mk_interval_learner(
+  sys: Sys, props: & [Prop], factory: Factory, samples: & SamplePool,
+  conf: & conf::Tig
+) -> Option< Learner< Graph<Interval> > > {
+  let (rep, mut class) = match mine::intervals(& factory, & sys, samples) {
+    Some(candidates) => candidates,
+    None => return None,
+  } ;
+  if let Some(max) = * conf.max_cands() {
+    class = mine::keep_relevant( class, props, max.saturating_sub(1) )
+  }
+  Some( Learner::mk(sys, rep, class, factory, conf) )
+}
+
 
 
 
@@ -982,6 +1126,8 @@ pub struct Graph<Val: Domain> {
   memory: TermSet,
   /// Stores the representatives that have been split and their value.
   values: TermMap<Val>,
+  /// Classes split since the last call to `splits`, if recording.
+  splits: Option< Vec<GraphEvent> >,
 }
 
 impl<Val: Domain> Graph<Val> {
@@ -995,6 +1141,7 @@ impl<Val: Domain> Graph<Val> {
       classes: TermMap::with_capacity( capa ),
       memory:  TermSet::with_capacity( capa ),
       values:  TermMap::with_capacity( capa ),
+      splits:  None,
     }
   }
 
@@ -1582,26 +1729,20 @@ digraph mode_graph {{
         Some(class) => class,
         None => bail!("{} representative {} is unknown", err_pref, rep),
       } ;
-      // Evaluate representative first.
-      chain = try_chain!(
-        chain.insert(
-          try_chain!(
-            eval.eval_term(rep)
-            => "{} while evaluating representative", err_pref
-          ),
-          rep.clone()
-        ) => "{} while inserting representative in the chain", err_pref
+      // Evaluate representative and members together, they share a lot of
+      // subterms.
+      let mut terms = Vec::with_capacity( class.len() + 1 ) ;
+      terms.push( rep.clone() ) ;
+      terms.extend( class.iter().cloned() ) ;
+      let values = try_chain!(
+        eval.eval_terms(& terms)
+        => "{} while evaluating class of rep {}", err_pref, rep
       ) ;
-      // Evaluate everyone and insert as needed.
-      for term in class.iter() {
+      // Insert everyone as needed, representative first.
+      for (value, term) in values.into_iter().zip( terms.into_iter() ) {
         chain = try_chain!(
-          chain.insert(
-            try_chain!(
-              eval.eval_term(term)
-              => "{} while evaluating term for rep {}", err_pref, rep
-            ),
-            term.clone()
-          ) => "{} while inserting in chain for rep {}", err_pref, rep
+          chain.insert(value, term)
+          => "{} while inserting in chain for rep {}", err_pref, rep
         ) ;
       }
     } ;
@@ -1620,6 +1761,19 @@ digraph mode_graph {{
     Ok(chain)
   }
 
+  /// Event describing the chain a class was split into, once the classes of
+  /// the chain are in the graph.
+  fn split_event(& self, rep: & Term, chain: & Chain<Val, ()>) -> GraphEvent {
+    let chain = chain.fold(
+      Vec::new(), |mut vec, val, rep, _| {
+        let size = self.classes.get(rep).map_or(0, |class| class.len()) ;
+        vec.push( ( format!("{}", val), rep.clone(), size + 1 ) ) ;
+        vec
+      }
+    ) ;
+    GraphEvent::Split(rep.clone(), chain)
+  }
+
   /// Inserts a chain in a graph.
   pub fn insert_chain(
     & mut self, rep: & Term, chain: Chain<Val, ()>
@@ -1829,6 +1983,10 @@ impl< Val: Domain > CanStabilize for Graph<Val> {
         self.split_class(& rep, eval)
         => "{} while splitting rep {}", err_pref, rep
       ) ;
+      if self.splits.is_some() {
+        let split = self.split_event(& rep, & chain) ;
+        if let Some(ref mut splits) = self.splits { splits.push(split) }
+      }
       // Insert resulting chain.
       try_chain!(
         self.insert_chain(& rep, chain)
@@ -1839,6 +1997,17 @@ impl< Val: Domain > CanStabilize for Graph<Val> {
 
     Ok(())
   }
+
+  fn record_splits(& mut self) {
+    if self.splits.is_none() { self.splits = Some( vec![] ) }
+  }
+
+  fn splits(& mut self) -> Vec<GraphEvent> {
+    match self.splits {
+      Some(ref mut splits) => ::std::mem::replace(splits, vec![]),
+      None => vec![],
+    }
+  }
 }
 
 impl< Val: Domain > HasClasses for Graph<Val> {
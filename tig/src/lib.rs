@@ -20,6 +20,7 @@ extern crate common ;
 extern crate unroll ;
 
 use std::sync::Arc ;
+use std::fmt ;
 use std::fmt::Display ;
 
 use term::{
@@ -35,7 +36,7 @@ use term::tmp::{
 use system::{ Sys, Prop } ;
 
 use common::{ SolverTrait, CanRun } ;
-use common::msg::Event ;
+use common::msg::{ Event, Unknown } ;
 use common::conf ;
 use common::errors::* ;
 
@@ -45,6 +46,7 @@ pub mod chain ;
 pub mod graph ;
 use graph::CanLog ;
 pub mod lsd ;
+pub mod templates ;
 
 
 /// Invgen technique.
@@ -54,7 +56,8 @@ impl CanRun<conf::Tig> for Tig {
   fn id(& self) -> common::Tek { common::Tek::Tig }
 
   fn run(
-    & self, conf: Arc<conf::Tig>, sys: Sys, _: Vec<Prop>, mut event: Event
+    & self, conf: Arc<conf::Tig>, sys: Sys, props: Vec<Prop>,
+    mut event: Event
   ) {
 
     let mut solver_conf = conf.smt().clone().default().print_success() ;
@@ -64,30 +67,77 @@ impl CanRun<conf::Tig> for Tig {
     } ;
 
     mk_two_solver_run!(
-      solver_conf, conf.smt_log(), "tig", event.factory(),
-      (solver_1 "base", solver_2 "step") => {
-        if let Some(ref dir) = * conf.graph_log() {
-          use std::fs::DirBuilder ;
-          let mut db = DirBuilder::new() ;
-          db.recursive(true) ;
-          log_try!(
-            event, db.create(dir)
-            => "while creating directory `{}` for graph logging", dir
+      solver_conf, conf.smt_log(), conf.smt_prelude(), "tig", event.factory(),
+      (solver_1 "base", solver_2 "step") => match * conf.domain() {
+        conf::TigDomain::Bool => {
+          let graph = graph::mk_bool_learner(
+            sys.clone(), & props, event.factory().clone(), & * conf
           ) ;
-          invgen(
-            conf.clone(), solver_1, solver_2, sys, & mut event,
-            |graph, tag1, tag2| graph.log_to(
-              & format!("{}/graph_{}_{}.dot", dir, tag1, tag2)
-            )
+          run_on(
+            conf.clone(), solver_1, solver_2, sys, & mut event, graph
           )
-        } else {
-          invgen(
-            conf.clone(), solver_1, solver_2, sys, & mut event,
-            |_, _, _| Ok(())
-          )
-        }
+        },
+        conf::TigDomain::Octagon => match graph::mk_octagon_learner(
+          sys.clone(), & props, event.factory().clone(), & * conf
+        ) {
+          Some(graph) => run_on(
+            conf.clone(), solver_1, solver_2, sys, & mut event, graph
+          ),
+          None => {
+            event.unknown(
+              Unknown::Disabled(
+                "no numeric state variable, no octagon to learn".into()
+              )
+            ) ;
+            event.done_at( & Offset::of_int(0) )
+          },
+        },
+        conf::TigDomain::Interval => match graph::mk_interval_learner(
+          sys.clone(), & props, event.factory().clone(), event.samples(),
+          & * conf
+        ) {
+          Some(graph) => run_on(
+            conf.clone(), solver_1, solver_2, sys, & mut event, graph
+          ),
+          None => {
+            event.unknown(
+              Unknown::Disabled(
+                "no numeric state variable, no interval to learn".into()
+              )
+            ) ;
+            event.done_at( & Offset::of_int(0) )
+          },
+        },
       },
-      err => event.error(err)
+      err => log_error!(event, err)
+    )
+  }
+}
+
+
+/// Runs invgen on a graph, logging it if asked to.
+fn run_on<'a, S: SolverTrait<'a>, Val: Domain>(
+  conf: Arc<conf::Tig>, solver_1: S, solver_2: S, sys: Sys,
+  event: & mut Event, graph: graph::Learner< graph::Graph<Val> >
+) {
+  if let Some(ref dir) = * conf.graph_log() {
+    use std::fs::DirBuilder ;
+    let mut db = DirBuilder::new() ;
+    db.recursive(true) ;
+    log_try!(
+      event, db.create(dir)
+      => "while creating directory `{}` for graph logging", dir
+    ) ;
+    invgen(
+      conf.clone(), solver_1, solver_2, sys, graph, event,
+      |graph, tag1, tag2| graph.log_to(
+        & format!("{}/graph_{}_{}.dot", dir, tag1, tag2)
+      )
+    )
+  } else {
+    invgen(
+      conf.clone(), solver_1, solver_2, sys, graph, event,
+      |_, _, _| Ok(())
     )
   }
 }
@@ -95,19 +145,23 @@ impl CanRun<conf::Tig> for Tig {
 
 /// Runs invgen.
 fn invgen<
-  'a, S: SolverTrait<'a>,
+  'a, S: SolverTrait<'a>, Val: Domain,
   GraphLog: Fn(
-    & graph::Learner< graph::Graph<Bool> >, & str, & str
+    & graph::Learner< graph::Graph<Val> >, & str, & str
   ) -> Res<()>
 >(
-  conf: Arc<conf::Tig>, solver_1: S, solver_2: S, sys: Sys, event: & mut Event,
+  conf: Arc<conf::Tig>, solver_1: S, solver_2: S, sys: Sys,
+  mut graph: graph::Learner< graph::Graph<Val> >, event: & mut Event,
   graph_log: GraphLog
 ) {
-  use std::time::Instant ;
+  use std::time::{ Instant, Duration } ;
   use lsd::top_only::* ;
 
   let max_k = * conf.max() ;
   let unroll_step = * conf.step_roll() ;
+  let iter_time = conf.iter_time().map(
+    |secs| Duration::from_secs(secs as u64)
+  ) ;
 
   let factory = solver_1.parser().clone() ;
 
@@ -123,13 +177,21 @@ fn invgen<
   //   Graph::<Bool>::mk(sys.clone(), rep, class),
   //   & (* conf)
   // ) ;
-  let mut graph = graph::mk_bool_learner(
-    sys.clone(), factory, & * conf
+  let mut templates = conf.templates().map(
+    |raises| templates::Templates::mk(sys.clone(), factory.clone(), raises)
   ) ;
 
   event.log(
     & format!("running with {} candidate terms", graph.len() + 1)
   ) ;
+  if let Some(ref templates) = templates {
+    event.log(
+      & format!(
+        "running with templates over {} variable(s) and pair(s)",
+        templates.len()
+      )
+    )
+  }
 
   // event.log("creating base checker") ;
   let mut base = log_try!(
@@ -161,6 +223,12 @@ fn invgen<
     let mut inner_cnt = 0 ;
     let start = Instant::now() ;
 
+    // Reachable states falsify candidates without any query.
+    log_try!(
+      event, graph.split_with_samples(event)
+      => "while splitting with reachable states at {}", cnt
+    ) ;
+
     event.log( & format!("stabilizing at {}...", cnt) ) ;
 
     'stabilize: while ! is_done {
@@ -178,11 +246,33 @@ fn invgen<
       //   step.restart(), event, "while restarting step at {}", cnt - 1
       // ) ;
 
-      inner_cnt += 1
+      inner_cnt += 1 ;
+
+      if ! is_done && (
+        iter_time.map_or( false, |max| Instant::now() - start > max ) ||
+        event.clock().is_over()
+      ) {
+        break 'stabilize
+      }
     }
 
     let time = Instant::now() - start ;
 
+    // Base only checks the last state of the unrolling, so the next
+    // iterations rely on the graph being stable at this one.
+    if ! is_done {
+      event.log(
+        & format!(
+          "out of time while stabilizing at {} after {}.{}, stopping",
+          cnt, time.as_secs(), time.subsec_nanos()
+        )
+      ) ;
+      event.unknown(
+        Unknown::Timeout( format!("stabilizing the graph at {}", cnt) )
+      ) ;
+      break 'work
+    }
+
     event.log(
       & format!(
         "graph stablized at {} in {}.{}",
@@ -190,6 +280,12 @@ fn invgen<
       )
     ) ;
 
+    graph.send_stable(cnt, event) ;
+    log_try!(
+      event, graph_log(& graph, & format!("{}", cnt), "stable")
+      => "while logging the graph stable at {}", cnt
+    ) ;
+
     graph.clear() ;
 
     log_try!(
@@ -197,6 +293,20 @@ fn invgen<
       => "while splitting all at {}", cnt
     ) ;
 
+    // Templates take many queries, skipped near the deadline.
+    if let Some(ref mut templates) = templates {
+      if event.clock().is_near() {
+        event.log(
+          & format!("skipping templates at {}, deadline is near", cnt)
+        )
+      } else {
+        log_try!(
+          event, templates.run(& mut base, & mut step, event)
+          => "while solving templates at {}", cnt
+        )
+      }
+    }
+
     log_try!(
       event, base.restart() => "while restarting base at {}", cnt - 1
     ) ;
@@ -219,6 +329,9 @@ fn invgen<
 
   }
 
+  if let Some(max) = max_k {
+    if cnt > max { event.unknown( Unknown::Bound( Offset::of_int(max) ) ) }
+  }
   event.done_at( & Offset::of_int(cnt) ) ;
 }
 
@@ -346,4 +459,124 @@ impl Domain for Rat  {
     debug_assert!( was_there ) ;
     Ok( (rep, set) )
   }
-}
\ No newline at end of file
+}
+
+
+/** Values of octagonal terms.
+
+Candidate terms are `± x ± y` over numeric state variables, and constants, all
+of the same type: integer or rational. The relations between them discovered by
+the graph include the octagonal invariants `± x ± y <= c`. Values are
+rationals, relations between constants are not candidates. */
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Octagon(Rat) ;
+impl Display for Octagon {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    write!(fmt, "{}", self.0)
+  }
+}
+impl Domain for Octagon {
+  fn of_cst(cst: & Cst) -> Res<Self> {
+    rat_of_cst(cst).map(Octagon).chain_err(
+      || "[Octagon::of_cst] unexpected constant"
+    )
+  }
+  fn mk_cmp(lhs: & Term, rhs: & Term) -> Option<TmpTerm> {
+    if is_cst(lhs) && is_cst(rhs) {
+      None
+    } else {
+      Some( TmpTerm::mk_term_le(lhs.clone(), rhs.clone()) )
+    }
+  }
+  fn mk_eq(lhs: & Term, rhs: & Term) -> Option<TmpTerm> {
+    if is_cst(lhs) && is_cst(rhs) {
+      None
+    } else {
+      Some( TmpTerm::mk_term_eq(lhs.clone(), rhs.clone()) )
+    }
+  }
+  /// Constants make for better representatives: the equalities of the class
+  /// are octagonal invariants.
+  fn choose_rep(_: & Factory, set: TermSet) -> Res<(Term, TermSet)> {
+    cst_rep(set).chain_err(
+      || "[Octagon::choose_rep] cannot choose representative of empty set"
+    )
+  }
+}
+
+/** Values of numeric state variables.
+
+Candidate terms are numeric state variables, and constants of the same type:
+the constants of the system, and the smallest and biggest values each variable
+takes in the reachable states known when the candidates are mined. Splitting
+the graph with the reachable states found later, during BMC and invariant
+generation, moves the variables along the chain of constants. The relations
+between the variables and the constants include the interval invariants
+`lo <= x <= hi`. Values are rationals, relations between constants are not
+candidates. */
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Interval(Rat) ;
+impl Display for Interval {
+  fn fmt(& self, fmt: & mut fmt::Formatter) -> fmt::Result {
+    write!(fmt, "{}", self.0)
+  }
+}
+impl Domain for Interval {
+  fn of_cst(cst: & Cst) -> Res<Self> {
+    rat_of_cst(cst).map(Interval).chain_err(
+      || "[Interval::of_cst] unexpected constant"
+    )
+  }
+  fn mk_cmp(lhs: & Term, rhs: & Term) -> Option<TmpTerm> {
+    if is_cst(lhs) && is_cst(rhs) {
+      None
+    } else {
+      Some( TmpTerm::mk_term_le(lhs.clone(), rhs.clone()) )
+    }
+  }
+  fn mk_eq(lhs: & Term, rhs: & Term) -> Option<TmpTerm> {
+    if is_cst(lhs) && is_cst(rhs) {
+      None
+    } else {
+      Some( TmpTerm::mk_term_eq(lhs.clone(), rhs.clone()) )
+    }
+  }
+  /// Constants make for better representatives: the equalities of the class
+  /// fix the value of variables.
+  fn choose_rep(_: & Factory, set: TermSet) -> Res<(Term, TermSet)> {
+    cst_rep(set).chain_err(
+      || "[Interval::choose_rep] cannot choose representative of empty set"
+    )
+  }
+}
+
+/// True if a term is a constant.
+fn is_cst(term: & Term) -> bool {
+  match * term.get() {
+    ::term::real_term::Term::C(_) => true,
+    _ => false,
+  }
+}
+
+/// Rational value of a numeric constant.
+fn rat_of_cst(cst: & Cst) -> Res<Rat> {
+  match * cst.get() {
+    ::term::real_term::Cst::Int(ref i) => Ok( Rat::from_integer( i.clone() ) ),
+    ::term::real_term::Cst::Rat(ref r) => Ok( r.clone() ),
+    ref cst => bail!("expected numeric constant, got {}", cst),
+  }
+}
+
+/// Chooses a constant as the representative of a set of terms if there is
+/// one, any term otherwise.
+fn cst_rep(mut set: TermSet) -> Res<(Term, TermSet)> {
+  let rep = match set.iter().find(
+    |term| is_cst(term)
+  ).or_else( || set.iter().next() ) {
+    Some(rep) => rep.clone(),
+    None => bail!("empty set"),
+  } ;
+  let was_there = set.remove(& rep) ;
+  debug_assert!( was_there ) ;
+  Ok( (rep, set) )
+}
@@ -98,7 +98,7 @@ pub mod top_only {
     ) -> Res<Offset2> {
       let mut k = Offset2::init() ;
       try_chain!(
-        unroller.solver().reset()
+        unroller.reset()
         => "[Base] while `reset`ing the solver {}", k
       ) ;
       try_chain!(
@@ -182,7 +182,7 @@ pub mod top_only {
       let res = if is_sat {
         // Sat, getting model.
         let model = try_chain!(
-          self.unroller.solver().get_model()
+          self.unroller.get_full_model()
           => "[Base::k_falsify] could not retrieve model"
         ) ;
         self.eval.recycle( model, self.k.clone() ) ;
@@ -205,15 +205,12 @@ pub mod top_only {
     'a, Val: Domain, Solver: SolverTrait<'a>
   > Lsd< Val, Base<Val, Solver>, Step<Val, Solver> > for Base<Val, Solver> {
     fn restart(& mut self) -> Res<()> {
-      match Base::<Val, Solver>::setup(
-        & mut self.unroller, self.k.next().to_usize()
-      ) {
-        Ok(k) => {
-          debug_assert!(k == self.k) ;
-          Ok(())
-        },
-        Err(e) => Err(e),
-      }
+      // Same depth, replaying the ledger drops the actlits.
+      try_chain!(
+        self.unroller.rollback( self.k.next() )
+        => "[Base] while rolling back to {}", self.k
+      ) ;
+      Ok(())
     }
     fn to_base(self) -> Res< Base<Val, Solver> > {
       Ok(self)
@@ -276,7 +273,7 @@ pub mod top_only {
       let check_offset = Step::<Val, Solver>::check_offset() ;
       let mut k = check_offset.clone() ;
       try_chain!(
-        unroller.solver().reset() => "[Step] while `reset`ing the solver"
+        unroller.reset() => "[Step] while `reset`ing the solver"
       ) ;
       try_chain!(
         unroller.defclare_funs(& [])
@@ -458,15 +455,13 @@ pub mod top_only {
     'a, Val: Domain, Solver: SolverTrait<'a>
   > Lsd< Val, Base<Val, Solver>, Step<Val, Solver> > for Step<Val, Solver> {
     fn restart(& mut self) -> Res<()> {
-      let k = self.unroll_len() ;
-      match Step::<Val, Solver>::setup(& mut self.unroller, k) {
-        Ok( (check, k) ) => {
-          debug_assert!(k == self.k) ;
-          debug_assert!(check == self.check) ;
-          Ok(())
-        },
-        Err(e) => Err(e),
-      }
+      // Same depth, replaying the ledger drops the actlits. Unrolling
+      // backwards, the deepest offset is the current one.
+      try_chain!(
+        self.unroller.rollback( self.k.curr() )
+        => "[Step] while rolling back to {}", self.k
+      ) ;
+      Ok(())
     }
     fn to_base(self) -> Res< Base<Val, Solver> > {
       Base::of_step(self)
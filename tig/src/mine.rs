@@ -15,8 +15,10 @@ use term::{
   Factory, Term, STerm, TermSet, STermSet, Type, Sym, Cst
 } ;
 
-use system::Sys ;
+use system::{ Sys, Prop } ;
+use system::bounds::Bounds ;
 
+use common::samples::SamplePool ;
 use common::errors::* ;
 
 /// A set of symbols.
@@ -155,6 +157,8 @@ impl Miner {
           ) ;
           rat.add_term( svar )
         },
+        // Bitvectors and arrays are not mined.
+        BitVec(_) | Array(_, _) => (),
       }
     }
 
@@ -186,7 +190,7 @@ impl Miner {
       }, | mut miner, cst | {
         use term::Type::* ;
         match cst.typ() {
-          Bool => (),
+          Bool | BitVec(_) | Array(_, _) => (),
           Int  => miner.int.add_cst( cst.clone() ),
           Rat  => miner.rat.add_cst( cst.clone() ),
         }
@@ -438,7 +442,11 @@ impl Miner {
 }
 
 /// Mines a system for boolean candidate terms.
-pub fn bool(factory: & Factory, sys: & Sys, all_out: bool) -> (Term, TermSet) {
+///
+/// Candidates bigger than `max_size` nodes are discarded.
+pub fn bool(
+  factory: & Factory, sys: & Sys, all_out: bool, max_size: Option<usize>
+) -> (Term, TermSet) {
   use term::CstMaker ;
   let mut miner = Miner::mk(sys, factory, all_out) ;
   if all_out {
@@ -459,9 +467,14 @@ pub fn bool(factory: & Factory, sys: & Sys, all_out: bool) -> (Term, TermSet) {
   let (set, _, _) = miner.to_sets() ;
 
   let mut set: TermSet = set.into_iter().filter_map(
-    |sterm| match sterm {
-      STerm::One(_, nxt) => Some(nxt),
-      STerm::Two(_) => None,
+    |sterm| {
+      if max_size.map_or(false, |max| sterm.size() > max) {
+        return None
+      }
+      match sterm {
+        STerm::One(_, nxt) => Some(nxt),
+        STerm::Two(_) => None,
+      }
     }
   ).collect() ;
 
@@ -469,4 +482,154 @@ pub fn bool(factory: & Factory, sys: & Sys, all_out: bool) -> (Term, TermSet) {
   set.remove(& rep) ;
   set.insert( factory.cst(true) ) ;
   (rep, set)
+}
+
+/// Mines a system for octagonal candidate terms: `x`, `- x`, `x + y`,
+/// `x - y`, `y - x` and `- x - y` over its integer state variables, or over
+/// its rational ones if it has none, and the constants of that type.
+///
+/// The representative is zero. `None` if the system has no numeric state
+/// variable.
+pub fn octagons(factory: & Factory, sys: & Sys) -> Option<(Term, TermSet)> {
+  use term::{ CstMaker, VarMaker, Int, Rat, Zero, State } ;
+  let miner = Miner::mk(sys, factory, false) ;
+  let (info, zero): (& Info, Term) = if ! miner.int.vars.is_empty() {
+    ( miner.int_info(), factory.cst( Int::zero() ) )
+  } else if ! miner.rat.vars.is_empty() {
+    ( miner.rat_info(), factory.cst( Rat::zero() ) )
+  } else {
+    return None
+  } ;
+
+  let svars: Vec<Term> = info.svars().iter().map(
+    |svar| factory.svar( svar.clone(), State::Next )
+  ).collect() ;
+  let mut set = TermSet::with_capacity(
+    info.csts().len() + 2 * svars.len() * svars.len()
+  ) ;
+  for cst in info.csts().iter() {
+    set.insert( factory.mk_cst( cst.clone() ) ) ;
+  }
+  for (index, x) in svars.iter().enumerate() {
+    let minus_x = factory.neg( x.clone() ) ;
+    set.insert( x.clone() ) ;
+    set.insert( minus_x.clone() ) ;
+    for y in svars[ index + 1 .. ].iter() {
+      set.insert( factory.add( vec![ x.clone(), y.clone() ] ) ) ;
+      set.insert( factory.sub( vec![ x.clone(), y.clone() ] ) ) ;
+      set.insert( factory.sub( vec![ y.clone(), x.clone() ] ) ) ;
+      set.insert( factory.sub( vec![ minus_x.clone(), y.clone() ] ) ) ;
+    }
+  }
+
+  set.remove(& zero) ;
+  Some( (zero, set) )
+}
+
+/// Candidate terms for interval invariants: the integer state variables of a
+/// system, the rational ones if there are none, and constants. The
+/// representative is zero.
+///
+/// The constants are the ones of the system, the ends of the structural
+/// ranges of the variables, and the smallest and biggest values each
+/// variable takes in some samples.
+///
+/// `None` if the system has no numeric state variable.
+pub fn intervals(
+  factory: & Factory, sys: & Sys, samples: & SamplePool
+) -> Option<(Term, TermSet)> {
+  use term::{ CstMaker, VarMaker, Int, Rat, Zero, State } ;
+  let miner = Miner::mk(sys, factory, false) ;
+  let (info, zero): (& Info, Term) = if ! miner.int.vars.is_empty() {
+    ( miner.int_info(), factory.cst( Int::zero() ) )
+  } else if ! miner.rat.vars.is_empty() {
+    ( miner.rat_info(), factory.cst( Rat::zero() ) )
+  } else {
+    return None
+  } ;
+  let bounds = Bounds::of(sys) ;
+
+  let mut set = TermSet::with_capacity(
+    info.csts().len() + 5 * info.svars().len()
+  ) ;
+  for cst in info.csts().iter() {
+    set.insert( factory.mk_cst( cst.clone() ) ) ;
+  }
+  for svar in info.svars().iter() {
+    set.insert( factory.svar( svar.clone(), State::Next ) ) ;
+    if let Some( & (ref lo, ref hi) ) = bounds.range(svar) {
+      set.insert( factory.cst( lo.clone() ) ) ;
+      set.insert( factory.cst( hi.clone() ) ) ;
+    }
+    let (mut lo, mut hi): (Option<& Cst>, Option<& Cst>) = (None, None) ;
+    let values = samples.values_of(svar) ;
+    for value in values.iter() {
+      if lo.map( |lo| value.get() < lo.get() ).unwrap_or(true) {
+        lo = Some(value)
+      }
+      if hi.map( |hi| value.get() > hi.get() ).unwrap_or(true) {
+        hi = Some(value)
+      }
+    }
+    for cst in lo.into_iter().chain( hi.into_iter() ) {
+      set.insert( factory.mk_cst( cst.clone() ) ) ;
+    }
+  }
+
+  set.remove(& zero) ;
+  Some( (zero, set) )
+}
+
+/// Keeps at most `max` candidates, the ones most relevant to some
+/// properties.
+///
+/// A candidate is more relevant than another if it mentions more of the
+/// state variables appearing in the properties. Constants are always kept.
+pub fn keep_relevant(
+  set: TermSet, props: & [Prop], max: usize
+) -> TermSet {
+  use std::cmp::Ordering::Equal ;
+  use term::state_vars_of ;
+  if set.len() <= max { return set }
+
+  let svars_of = |term: & Term| -> SymSet {
+    state_vars_of(term).into_iter().map(
+      |var| var.sym().clone()
+    ).collect()
+  } ;
+
+  let mut relevant = SymSet::with_capacity(17) ;
+  for prop in props.iter() {
+    for sym in svars_of( prop.body().next() ) {
+      relevant.insert(sym) ;
+      ()
+    }
+  }
+
+  let mut res = TermSet::with_capacity(max) ;
+  let mut ranked = Vec::with_capacity( set.len() ) ;
+  for term in set.into_iter() {
+    if term.is_true() || term.is_false() {
+      res.insert(term) ;
+      ()
+    } else {
+      let score = svars_of(& term).iter().filter(
+        |sym| relevant.contains(sym)
+      ).count() ;
+      ranked.push( (score, term) )
+    }
+  }
+  // Most relevant first, term order breaks ties to be deterministic.
+  ranked.sort_by(
+    |& (s_1, ref t_1), & (s_2, ref t_2)| match s_2.cmp(& s_1) {
+      Equal => t_1.cmp(t_2),
+      ord => ord,
+    }
+  ) ;
+  for (_, term) in ranked.into_iter() {
+    if res.len() >= max { break }
+    res.insert(term) ;
+    ()
+  }
+  res
 }
\ No newline at end of file
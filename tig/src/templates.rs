@@ -0,0 +1,371 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! Linear templates.
+//!
+//! A template is `a*x + b*y <= c` over two numeric state variables `x` and
+//! `y` of the same type, or `a*x <= c` over one. Its coefficients are
+//! synthesized from samples, counterexample-guided: the templates of some
+//! variables are the faces of the convex hull of the values they take in
+//! the states sampled so far, see [`hull`](fn.hull.html). The base checker
+//! looks for a state of the last unrolling falsifying one of them, whose
+//! values are sampled, until there is no such state. Variables still
+//! sampled after `raises` rounds in one iteration are dropped, so the
+//! templates of the ones left hold in all the states of the unrollings so
+//! far.
+//!
+//! The step checker then looks for the templates that are k-inductive.
+//! They are broadcast as invariants once.
+
+use term::{
+  Factory, Term, Type, State, STerm, STermSet, Int, Rat,
+  CstMaker, OpMaker, VarMaker, UnTermOps, Zero, One, Signed, Integer
+} ;
+use term::real_term ;
+use term::tmp::{ TmpTerm, TmpTermMap, TmpTermSet } ;
+
+use system::Sys ;
+
+use common::msg::Event ;
+use common::errors::* ;
+
+use Domain ;
+use lsd::{ BaseTrait, StepTrait } ;
+
+/// A template `a*x + b*y <= c`: `a`, `b` and `c`.
+pub type Ineq = (Rat, Rat, Rat) ;
+
+/// Cross product of `a - o` and `b - o`, positive iff `o`, `a`, `b` turn
+/// counterclockwise.
+fn cross(o: & (Rat, Rat), a: & (Rat, Rat), b: & (Rat, Rat)) -> Rat {
+  (a.0.clone() - o.0.clone()) * (b.1.clone() - o.1.clone()) -
+  (a.1.clone() - o.1.clone()) * (b.0.clone() - o.0.clone())
+}
+
+/// Template `a*x + b*y <= a*p.0 + b*p.1`, the coefficients divided by their
+/// gcd if they are integers.
+fn ineq(a: Rat, b: Rat, p: & (Rat, Rat)) -> Ineq {
+  let (a, b) = if a.is_integer() && b.is_integer() {
+    let gcd = a.to_integer().gcd( & b.to_integer() ) ;
+    if gcd.is_zero() || gcd == Int::one() { (a, b) } else {
+      let gcd = Rat::from_integer(gcd) ;
+      (a / gcd.clone(), b / gcd)
+    }
+  } else { (a, b) } ;
+  let c = a.clone() * p.0.clone() + b.clone() * p.1.clone() ;
+  (a, b, c)
+}
+
+/// True iff a point satisfies a template.
+pub fn holds(ineq: & Ineq, point: & (Rat, Rat)) -> bool {
+  let & (ref a, ref b, ref c) = ineq ;
+  a.clone() * point.0.clone() + b.clone() * point.1.clone() <= * c
+}
+
+/// The templates whose conjunction is the convex hull of some points.
+///
+/// The hull of one point is a box, the one of aligned points is a line
+/// and the bounds of the segment along it. Otherwise the templates are
+/// the edges of the hull, whose coefficients are the normals of the edges.
+pub fn hull(points: & [ (Rat, Rat) ]) -> Vec<Ineq> {
+  let mut points = points.to_vec() ;
+  points.sort() ;
+  points.dedup() ;
+  if points.is_empty() { return vec![] }
+  let (zero, one) = ( Rat::zero(), Rat::one() ) ;
+  if points.len() == 1 {
+    let p = & points[0] ;
+    return vec![
+      ineq( one.clone(), zero.clone(), p ),
+      ineq( - one.clone(), zero.clone(), p ),
+      ineq( zero.clone(), one.clone(), p ),
+      ineq( zero, - one, p ),
+    ]
+  }
+
+  // Monotone chain, counterclockwise, without aligned points.
+  let mut vertices: Vec<(Rat, Rat)> = Vec::with_capacity( points.len() ) ;
+  for half in 0..2 {
+    let start = vertices.len() ;
+    let chain: Vec<& (Rat, Rat)> = if half == 0 {
+      points.iter().collect()
+    } else {
+      points.iter().rev().collect()
+    } ;
+    for point in chain {
+      while vertices.len() >= start + 2 && ! cross(
+        & vertices[ vertices.len() - 2 ], & vertices[ vertices.len() - 1 ],
+        point
+      ).is_positive() {
+        vertices.pop() ;
+      }
+      vertices.push( point.clone() )
+    }
+    // The last point of a half is the first one of the other.
+    vertices.pop() ;
+  }
+
+  if vertices.len() == 2 {
+    let (p, q) = ( & vertices[0], & vertices[1] ) ;
+    let (dx, dy) = ( q.0.clone() - p.0.clone(), q.1.clone() - p.1.clone() ) ;
+    return vec![
+      ineq( dy.clone(), - dx.clone(), p ),
+      ineq( - dy.clone(), dx.clone(), p ),
+      ineq( dx.clone(), dy.clone(), q ),
+      ineq( - dx, - dy, p ),
+    ]
+  }
+
+  // The inside is on the left of each edge `p -> q`.
+  let mut ineqs = Vec::with_capacity( vertices.len() ) ;
+  for index in 0..vertices.len() {
+    let p = & vertices[index] ;
+    let q = & vertices[ (index + 1) % vertices.len() ] ;
+    ineqs.push(
+      ineq( q.1.clone() - p.1.clone(), p.0.clone() - q.0.clone(), p )
+    )
+  }
+  ineqs
+}
+
+/// Numeric state variables and the values they take in the states sampled
+/// so far.
+struct Samples {
+  /// First variable, over the next state.
+  x: Term,
+  /// Second variable, over the next state, if any.
+  y: Option<Term>,
+  /// Type of the variables.
+  typ: Type,
+  /// Values sampled, `0` for `y` if there is none.
+  points: Vec<(Rat, Rat)>,
+  /// Templates, the convex hull of the points.
+  hull: Vec<Ineq>,
+}
+impl Samples {
+  /// Samples the values of the variables in a state. True iff they are
+  /// outside the hull.
+  fn sample(& mut self, point: (Rat, Rat)) -> bool {
+    if ! self.points.is_empty() && self.hull.iter().all(
+      |ineq| holds(ineq, & point)
+    ) {
+      return false
+    }
+    self.points.push(point) ;
+    self.hull = hull(& self.points) ;
+    if self.y.is_none() {
+      // Only the bounds of `x` make sense.
+      self.hull.retain( |& (_, ref b, _)| b.is_zero() )
+    }
+    true
+  }
+}
+
+/// Linear templates over the state variables of a system.
+pub struct Templates {
+  /// System the templates are for.
+  sys: Sys,
+  /// Term factory.
+  factory: Factory,
+  /// Variables the templates are over, with their samples.
+  samples: Vec<Samples>,
+  /// Templates proved invariant.
+  proved: TmpTermSet,
+  /// Maximum number of sampling rounds per iteration.
+  raises: usize,
+}
+impl Templates {
+  /// Instantiates the templates over the numeric state variables of a
+  /// system, and over the pairs of these variables of the same type.
+  pub fn mk(sys: Sys, factory: Factory, raises: usize) -> Self {
+    let mut svars = vec![] ;
+    for & (ref sym, ref typ) in sys.state().args() {
+      match * typ.get() {
+        Type::Int | Type::Rat => {
+          let svar: Term = factory.svar( sym.get().clone(), State::Next ) ;
+          svars.push( (svar, typ.get().clone()) )
+        },
+        _ => (),
+      }
+    }
+    let mut samples = vec![] ;
+    for (index, & (ref x, ref x_typ)) in svars.iter().enumerate() {
+      samples.push(
+        Samples {
+          x: x.clone(), y: None, typ: x_typ.clone(),
+          points: vec![], hull: vec![],
+        }
+      ) ;
+      for & (ref y, ref y_typ) in svars[ index + 1 .. ].iter() {
+        if x_typ != y_typ { continue }
+        samples.push(
+          Samples {
+            x: x.clone(), y: Some( y.clone() ), typ: x_typ.clone(),
+            points: vec![], hull: vec![],
+          }
+        )
+      }
+    }
+    Templates {
+      sys: sys, factory: factory, samples: samples,
+      proved: TmpTermSet::new(), raises: raises,
+    }
+  }
+
+  /// Number of variables and pairs of variables the templates are over.
+  #[inline]
+  pub fn len(& self) -> usize { self.samples.len() }
+
+  /// A coefficient or a bound as a constant of some type. Integer bounds
+  /// are rounded down, which is fine for integer left-hand sides.
+  fn cst(& self, val: & Rat, typ: & Type) -> Term {
+    match * typ {
+      Type::Int => self.factory.cst( val.floor().to_integer() ),
+      _ => self.factory.cst( val.clone() ),
+    }
+  }
+
+  /// `coef * var`, simplified.
+  fn monomial(& self, coef: & Rat, var: & Term, typ: & Type) -> Term {
+    use term::Operator::{ Sub, Mul } ;
+    if * coef == Rat::one() {
+      var.clone()
+    } else if - coef.clone() == Rat::one() {
+      self.factory.op( Sub, vec![ var.clone() ] )
+    } else {
+      self.factory.op( Mul, vec![ self.cst(coef, typ), var.clone() ] )
+    }
+  }
+
+  /// The candidate invariants of some samples, the ones proved excluded.
+  fn candidates(& self, samples: & Samples) -> Vec<TmpTerm> {
+    use term::Operator::Add ;
+    let mut cands = Vec::with_capacity( samples.hull.len() ) ;
+    for & (ref a, ref b, ref c) in samples.hull.iter() {
+      let mut lhs = vec![] ;
+      if ! a.is_zero() {
+        lhs.push( self.monomial(a, & samples.x, & samples.typ) )
+      }
+      if let Some(ref y) = samples.y {
+        if ! b.is_zero() { lhs.push( self.monomial(b, y, & samples.typ) ) }
+      }
+      let lhs = if lhs.len() == 1 {
+        lhs.pop().unwrap()
+      } else { self.factory.op(Add, lhs) } ;
+      let cand = TmpTerm::mk_term_le( lhs, self.cst(c, & samples.typ) ) ;
+      if ! self.proved.contains(& cand) { cands.push(cand) }
+    }
+    cands
+  }
+
+  /// Value of a state variable as a rational.
+  fn value_of<Val: Domain>(
+    eval: & ::eval::Eval<Val>, svar: & Term
+  ) -> Res<Rat> {
+    let cst = try!( eval.value_of(svar) ) ;
+    match * cst.get() {
+      real_term::Cst::Int(ref int) => Ok( Rat::from_integer( int.clone() ) ),
+      real_term::Cst::Rat(ref rat) => Ok( rat.clone() ),
+      ref cst => bail!("unexpected value {} for {}", cst, svar),
+    }
+  }
+
+  /// Samples the last unrolling of `base` until the templates hold in all
+  /// its states, broadcasts the ones `step` proves k-inductive.
+  pub fn run<Val, Base, Step>(
+    & mut self, base: & mut Base, step: & mut Step, event: & mut Event
+  ) -> Res<()> where
+  Val: Domain,
+  Base: BaseTrait<Val, Step>,
+  Step: StepTrait<Val, Base> {
+    let err_pref = "[Templates::run]" ;
+    let k = base.unroll_len() ;
+
+    // Sampling any state of the last unrolling first if some variables
+    // have no sample yet. This round does not count.
+    let mut first = self.samples.iter().any(
+      |samples| samples.points.is_empty()
+    ) ;
+    let mut rounds = 0 ;
+    loop {
+      let cands: Vec<TmpTerm> = if first {
+        vec![ TmpTerm::Trm( self.factory.cst(false) ) ]
+      } else {
+        self.samples.iter().flat_map(
+          |samples| self.candidates(samples)
+        ).collect()
+      } ;
+      if cands.is_empty() { break }
+      let eval = match try_chain!(
+        base.k_falsify(cands)
+        => "{} while sampling at {}", err_pref, k
+      ) {
+        Some(eval) => eval,
+        // No state at this depth, nor at the next ones.
+        None => if first { return Ok(()) } else { break },
+      } ;
+      let mut samples = Vec::with_capacity( self.samples.len() ) ;
+      for mut sample in self.samples.drain(..) {
+        let x = try_chain!(
+          Self::value_of(eval, & sample.x)
+          => "{} while evaluating {}", err_pref, sample.x
+        ) ;
+        let y = match sample.y {
+          Some(ref y) => try_chain!(
+            Self::value_of(eval, y)
+            => "{} while evaluating {}", err_pref, y
+          ),
+          None => Rat::zero(),
+        } ;
+        // Samples still growing past the limit are dropped.
+        if ! sample.sample( (x, y) ) || first || rounds < self.raises {
+          samples.push(sample)
+        }
+      }
+      self.samples = samples ;
+      if first { first = false } else { rounds += 1 }
+    }
+
+    // Checking the templates for k-induction.
+    let mut cands = TmpTermMap::with_capacity( self.samples.len() ) ;
+    for samples in self.samples.iter() {
+      for cand in self.candidates(samples) {
+        cands.insert(cand, ()) ;
+      }
+    }
+    if cands.is_empty() { return Ok(()) }
+    let invars = try_chain!(
+      step.k_split(& mut cands) => "{} step query", err_pref
+    ) ;
+    if invars.is_empty() { return Ok(()) }
+
+    let mut set = STermSet::with_capacity( invars.len() ) ;
+    for (invar, ()) in invars.into_iter() {
+      self.proved.insert( invar.clone() ) ;
+      let invar = try_chain!(
+        invar.to_term_safe(& self.factory)
+        => "{} while building one-state invariant", err_pref
+      ) ;
+      let curr = try_chain!(
+        self.factory.debump(& invar)
+        => "{} while building one-state invariant", err_pref
+      ) ;
+      set.insert( STerm::One(curr, invar) ) ;
+    }
+    event.log(
+      & format!(
+        "{} template(s) proved at {}, {} proved so far",
+        set.len(), k, self.proved.len()
+      )
+    ) ;
+    event.invariants_at( self.sys.sym().get(), set, k ) ;
+    Ok(())
+  }
+}
@@ -43,7 +43,7 @@ impl common::CanRun<conf::Twind> for Twind {
     mut event: Event
   ) {
     // event.log(
-    //   & format!("checking {} propertie(s) on system {}", props.len(), sys.sym())
+    //   & format!("checking {} property(ies) on system {}", props.len(), sys.sym())
     // ) ;
 
     // event.log("creating solver") ;
@@ -55,9 +55,10 @@ impl common::CanRun<conf::Twind> for Twind {
     } ;
 
     mk_solver_run!(
-      solver_conf, conf.smt_log(), "twind", event.factory(),
-      solver => twind(solver, sys, props, & mut event),
-      err => event.error(err)
+      solver_conf, conf.smt_log(), conf.smt_cache(), conf.smt_prelude(),
+      "twind", event.factory(),
+      solver => twind(solver, conf.clone(), sys, props, & mut event),
+      err => log_error!(event, err)
     )
   }
 }
@@ -66,17 +67,20 @@ fn twind<
   'a,
   S: SolverTrait<'a>
 >(
-  solver: S, sys: Sys, props: Vec<Prop>, event: & mut Event
+  solver: S, conf: Arc<conf::Twind>,
+  sys: Sys, props: Vec<Prop>, event: & mut Event
 ) {
 
   let duration = Duration::from_millis(73) ;
 
+  event.set_max_queries( * conf.max_queries() ) ;
+
   // Reversed to unroll backwards.
   let check_offset = Offset2::init().rev() ;
   let k = check_offset.clone() ;
 
   let mut unroller = log_try!(
-    event, Unroller::mk(& sys, & props, solver)
+    event, Unroller::mk_encoded(& sys, & props, solver, conf.encoding())
     => "while creating unroller"
   ) ;
 
@@ -135,6 +139,10 @@ fn twind<
 
     props.reset_inhibited() ;
 
+    let mut usage = unroller.mem_usage() ;
+    usage.extend( props.mem_usage() ) ;
+    event.stats(usage) ;
+
     // event.log( & format!("activating state at {}", k) ) ;
     log_try!(
       event, props.activate_state(unroller.solver(), & k)
@@ -145,10 +153,12 @@ fn twind<
       None => return (),
       Some(msgs) => for msg in msgs {
         match msg {
-          MsgDown::Forget(ps, _) => log_try!(
-            event, props.forget(unroller.solver(), ps.iter())
-            => "while forgetting some properties\n\
-              because of a `Forget` message (1)"
+          MsgDown::Forget(ps, status) => log_try!(
+            event, props.forget_status(
+              unroller.solver(), ps.iter(), & status
+            )
+            => "while forgetting {} properties\n\
+              because of a `Forget` message (1)", status
           ),
           MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
             log_try!(
@@ -156,8 +166,20 @@ fn twind<
               => "while adding invariants from supervisor"
             )
           },
-          msg => event.error(
-            format!("unexpected message `{:?}`", msg).into()
+          MsgDown::NewProps(ps) => {
+            let count = ps.len() ;
+            log_try!(
+              event, unroller.defclare_prop_funs(& ps)
+              => "while declaring functions for {} new properties", count
+            ) ;
+            log_try!(
+              event, props.add_props_between(
+                ps, unroller.solver(), & check_offset, & k
+              ) => "while adding {} new properties to manager", count
+            ) ;
+          },
+          msg => log_error!(
+            event, format!("unexpected message `{:?}`", msg)
           ),
         }
       },
@@ -188,6 +210,11 @@ fn twind<
       let mut actlits = props.actlits() ;
       actlits.push(actlit.name()) ;
 
+      if ! event.query() {
+        event.out_of_queries(k.curr()) ;
+        return ()
+      }
+
       // Check sat.
       let is_sat = log_try!(
         event, unroller.check_sat_assuming( & actlits )
@@ -253,7 +280,7 @@ fn twind<
                   match msg {
                     MsgDown::Forget(ps, Status::Proved) => {
                       log_try!(
-                        event, props.forget(unroller.solver(), ps.iter())
+                        event, props.forget_keep(unroller.solver(), ps.iter())
                         => "while forgetting some properties \
                           because of a `Forget` message (2, proved)"
                       ) ;
@@ -285,8 +312,21 @@ fn twind<
                         => "while adding invariants from supervisor"
                       )
                     },
-                    msg => event.error(
-                      format!("unexpected message `{:?}`", msg).into()
+                    MsgDown::NewProps(ps) => {
+                      let count = ps.len() ;
+                      log_try!(
+                        event, unroller.defclare_prop_funs(& ps)
+                        => "while declaring functions for {} new properties",
+                        count
+                      ) ;
+                      log_try!(
+                        event, props.add_props_between(
+                          ps, unroller.solver(), & check_offset, & k
+                        ) => "while adding {} new properties to manager", count
+                      ) ;
+                    },
+                    msg => log_error!(
+                      event, format!("unexpected message `{:?}`", msg)
                     ),
                   }
                 }
@@ -311,10 +351,12 @@ fn twind<
         None => return (),
         Some(msgs) => for msg in msgs {
           match msg {
-            MsgDown::Forget(ps, _) => log_try!(
-              event, props.forget(unroller.solver(), ps.iter())
-              => "while forgetting some properties \
-                because of a `Forget` message (1)"
+            MsgDown::Forget(ps, status) => log_try!(
+              event, props.forget_status(
+                unroller.solver(), ps.iter(), & status
+              )
+              => "while forgetting {} properties \
+                because of a `Forget` message (1)", status
             ),
             MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
               // event.log(
@@ -326,8 +368,20 @@ fn twind<
                 => "while adding invariants from supervisor"
               )
             },
-            msg => event.error(
-              format!("unexpected message `{:?}`", msg).into()
+            MsgDown::NewProps(ps) => {
+              let count = ps.len() ;
+              log_try!(
+                event, unroller.defclare_prop_funs(& ps)
+                => "while declaring functions for {} new properties", count
+              ) ;
+              log_try!(
+                event, props.add_props_between(
+                  ps, unroller.solver(), & check_offset, & k
+                ) => "while adding {} new properties to manager", count
+              ) ;
+            },
+            msg => log_error!(
+              event, format!("unexpected message `{:?}`", msg)
             ),
           }
         },
@@ -349,12 +403,14 @@ fn twind<
         None => return (),
         Some(msgs) => for msg in msgs {
           match msg {
-            MsgDown::Forget(ps, _) => {
+            MsgDown::Forget(ps, status) => {
               new_stuff = true ;
               log_try!(
-                event, props.forget(unroller.solver(), ps.iter())
-                => "while forgetting some properties\n\
-                  because of a `Forget` message (1)"
+                event, props.forget_status(
+                  unroller.solver(), ps.iter(), & status
+                )
+                => "while forgetting {} properties\n\
+                  because of a `Forget` message (1)", status
               )
             },
             MsgDown::Invariants(sym, invs) => if sys.sym().get() == & sym  {
@@ -368,8 +424,21 @@ fn twind<
                 => "while adding invariants from supervisor"
               )
             },
-            msg => event.error(
-              format!("unexpected message `{:?}`", msg).into()
+            MsgDown::NewProps(ps) => {
+              new_stuff = true ;
+              let count = ps.len() ;
+              log_try!(
+                event, unroller.defclare_prop_funs(& ps)
+                => "while declaring functions for {} new properties", count
+              ) ;
+              log_try!(
+                event, props.add_props_between(
+                  ps, unroller.solver(), & check_offset, & k
+                ) => "while adding {} new properties to manager", count
+              ) ;
+            },
+            msg => log_error!(
+              event, format!("unexpected message `{:?}`", msg)
             ),
           }
         },
@@ -0,0 +1,143 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Certificate checks for proved properties.
+//!
+//! A certificate is a conjecture and a depth `k`: the conjecture holds in
+//! the first `k` states of all the traces from the initial states (base
+//! case), and `k` consecutive states satisfying it are always followed by a
+//! state satisfying it (step case). The conjecture is then an invariant,
+//! and so are its conjuncts.
+//!
+//! The check is done from scratch, independently of how the certificate was
+//! found, so that it can be done with another solver. It is also how the
+//! proofs of the properties are produced, see `common::proof`: the problem
+//! of each unsat check only mentions the system and the certificate.
+
+use term::{ Term, STerm, Offset2, Factory, CstMaker } ;
+use term::tmp::TmpTerm ;
+
+use sys::{ Sys, Prop } ;
+
+use common::SolverTrait ;
+use common::errors::* ;
+
+use Unroller ;
+
+/// Conjunction of the state versions of the one-state terms of a conjecture,
+/// for its first state.
+fn first(factory: & Factory, conj: & [STerm]) -> Term {
+  let states: Vec<Term> = conj.iter().filter_map(
+    |term| term.state().cloned()
+  ).collect() ;
+  if states.is_empty() { factory.cst(true) } else { factory.and(states) }
+}
+
+/// Conjunction of the next versions of the terms of a conjecture, which
+/// cannot be empty.
+fn next(factory: & Factory, conj: & [STerm]) -> Term {
+  factory.and(
+    conj.iter().map( |term| term.next().clone() ).collect()
+  )
+}
+
+/// True if a term can be false at some offset, assuming some actlits.
+/// Calls `on_unsat` with the unroller and `label` if it cannot, right after
+/// the check.
+fn falsifiable<
+  'a, S: SolverTrait<'a>, F: FnMut(& mut Unroller<S>, & str) -> Res<()>
+>(
+  unroller: & mut Unroller<S>, factory: & Factory, term: Term,
+  off: & Offset2, assuming: & [String], on_unsat: & mut F, label: & str
+) -> Res<bool> {
+  let actlit = try!( unroller.fresh_actlit() ) ;
+  let neg = TmpTerm::Trm( factory.not(term) ) ;
+  try!( unroller.assert(& actlit.activate_term(neg), off) ) ;
+  let mut actlits = assuming.to_vec() ;
+  actlits.push( actlit.name() ) ;
+  let sat = try!( unroller.check_sat_assuming(& actlits) ) ;
+  if ! sat {
+    try!( on_unsat(unroller, label) )
+  }
+  try!( unroller.deactivate(actlit) ) ;
+  Ok(sat)
+}
+
+/// Checks that a conjecture is `k`-inductive and holds in the first `k`
+/// states, with `k` at least one. `props` are the properties the conjecture
+/// mentions, for the functions they use.
+pub fn check<'a, S: SolverTrait<'a>>(
+  solver: S, sys: & Sys, props: & [Prop], conj: & [STerm], k: usize,
+  factory: & Factory
+) -> Res<bool> {
+  check_with(solver, sys, props, conj, k, factory, |_, _| Ok(()))
+}
+
+/// Same as [`check`](fn.check.html), but calls `on_unsat` right after each
+/// unsat check with the unroller and a label: `base_<i>` for the base case
+/// in state `i`, `step` for the step case. Typically used to retrieve the
+/// proofs of the checks.
+pub fn check_with<
+  'a, S: SolverTrait<'a>, F: FnMut(& mut Unroller<S>, & str) -> Res<()>
+>(
+  solver: S, sys: & Sys, props: & [Prop], conj: & [STerm], k: usize,
+  factory: & Factory, mut on_unsat: F
+) -> Res<bool> {
+  if conj.is_empty() { return Ok(true) }
+  let k = if k == 0 { 1 } else { k } ;
+  let (first, next) = ( first(factory, conj), next(factory, conj) ) ;
+
+  let mut unroller = try!( Unroller::mk(sys, props, solver) ) ;
+  let mut off = Offset2::init() ;
+  try!( unroller.declare_svars( off.curr() ) ) ;
+  let init = try!( unroller.fresh_actlit() ) ;
+  try!(
+    unroller.assert(
+      & init.activate_term( TmpTerm::Trm( sys.init_term().clone() ) ), & off
+    )
+  ) ;
+  let init = vec![ init.name() ] ;
+
+  // Base case, states `0` to `k - 1`.
+  if try!(
+    falsifiable(
+      & mut unroller, factory, first.clone(), & off, & init,
+      & mut on_unsat, "base_0"
+    )
+  ) {
+    return Ok(false)
+  }
+  for i in 1..k {
+    try!( unroller.unroll(& off) ) ;
+    if try!(
+      falsifiable(
+        & mut unroller, factory, next.clone(), & off, & init,
+        & mut on_unsat, & format!("base_{}", i)
+      )
+    ) {
+      return Ok(false)
+    }
+    off = off.nxt()
+  }
+
+  // Step case, states `0` to `k - 1` satisfy the conjecture.
+  let mut o = Offset2::init() ;
+  try!( unroller.assert(& first, & o) ) ;
+  for _ in 1..k {
+    try!( unroller.assert(& next, & o) ) ;
+    o = o.nxt()
+  }
+  try!( unroller.unroll(& off) ) ;
+  let step = try!(
+    falsifiable(
+      & mut unroller, factory, next, & off, & [], & mut on_unsat, "step"
+    )
+  ) ;
+  Ok(! step)
+}
@@ -0,0 +1,179 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detects the `ite` branches of the transition relation that are never
+//! taken.
+//!
+//! A branch is guarded by the conjunction of the conditions of the `ite`s
+//! leading to it, negated for `else` branches. The BMC phase looks for a
+//! transition from the initial states taking each branch, up to some bound.
+//! The induction phase then tries to prove the branches never taken are
+//! dead: if `k` consecutive transitions not taking a branch cannot be
+//! followed by one taking it, the branch is never taken.
+//!
+//! Terms do not remember their position in the input, branches are located
+//! by the span of the definition of their system.
+
+use term::{ Sym, Term, Offset2, Factory, Operator, BindMaker } ;
+use term::tmp::TmpTerm ;
+
+use sys::Sys ;
+
+use common::SolverTrait ;
+use common::errors::* ;
+
+use Unroller ;
+
+/// A branch of an `ite` of the transition relation.
+pub struct Branch {
+  /// Index of the branch, in the order the transition relation is traversed.
+  pub index: usize,
+  /// Condition of the `ite`.
+  pub cond: Term,
+  /// True for the `then` branch.
+  pub then: bool,
+  /// Conjunction of the conditions leading to the branch, under the local
+  /// definitions of the transition relation it appears under.
+  pub guard: Term,
+}
+
+/// The `ite` branches of a term. Quantified sub-terms are ignored.
+pub fn branches(term: & Term, factory: & Factory) -> Vec<Branch> {
+  let mut res = vec![] ;
+  let mut lets = vec![] ;
+  collect(term, factory, & mut vec![], & mut lets, & mut res) ;
+  res
+}
+
+/// Collects the `ite` branches of a term. `path` are the conditions leading
+/// to the term, `lets` the local definitions it is under, outermost first.
+fn collect(
+  term: & Term, factory: & Factory, path: & mut Vec<Term>,
+  lets: & mut Vec< Vec<(Sym, Term)> >, res: & mut Vec<Branch>
+) {
+  use term::real_term::Term::* ;
+  match * term.get() {
+    Op(Operator::Ite, ref kids) if kids.len() == 3 => {
+      collect(& kids[0], factory, path, lets, res) ;
+      for & (then, kid) in [ (true, & kids[1]), (false, & kids[2]) ].iter() {
+        let cond = if then {
+          kids[0].clone()
+        } else {
+          factory.not( kids[0].clone() )
+        } ;
+        path.push(cond) ;
+        let mut guard = factory.and( path.clone() ) ;
+        for bindings in lets.iter().rev() {
+          guard = factory.let_b( bindings.clone(), guard )
+        }
+        res.push(
+          Branch {
+            index: res.len(), cond: kids[0].clone(), then: then, guard: guard
+          }
+        ) ;
+        collect(kid, factory, path, lets, res) ;
+        path.pop() ; ()
+      }
+    },
+    Op(_, ref kids) | App(_, ref kids) => for kid in kids.iter() {
+      collect(kid, factory, path, lets, res)
+    },
+    Let(ref bindings, ref body) => {
+      for & (_, ref term) in bindings.iter() {
+        collect(term, factory, path, lets, res)
+      }
+      lets.push( bindings.clone() ) ;
+      collect(body, factory, path, lets, res) ;
+      lets.pop() ; ()
+    },
+    V(_) | C(_) | Forall(_, _) | Exists(_, _) => (),
+  }
+}
+
+/// Looks for the first transition from the initial states taking each
+/// branch, among the first `bound` ones.
+///
+/// Returns, for each branch, the index of the first transition taking it if
+/// any.
+pub fn bmc_phase<'a, S: SolverTrait<'a>>(
+  solver: S, sys: & Sys, branches: & [Branch], bound: usize
+) -> Res<Vec<Option<usize>>> {
+  let mut taken = vec![ None ; branches.len() ] ;
+  let mut unroller = try!( Unroller::mk(sys, & [], solver) ) ;
+  let mut off = Offset2::init() ;
+  try!( unroller.assert_init(& off) ) ;
+
+  for depth in 0..bound {
+    if taken.iter().all( |t| t.is_some() ) { break }
+    try!( unroller.unroll(& off) ) ;
+    for (branch, taken) in branches.iter().zip( taken.iter_mut() ) {
+      if taken.is_some() { continue }
+      let actlit = try!( unroller.fresh_actlit() ) ;
+      try!(
+        unroller.assert(
+          & actlit.activate_term( TmpTerm::Trm( branch.guard.clone() ) ), & off
+        )
+      ) ;
+      if try!( unroller.check_sat_assuming( & [ actlit.name() ] ) ) {
+        * taken = Some(depth)
+      }
+      try!( unroller.deactivate(actlit) )
+    }
+    off = off.nxt()
+  }
+
+  Ok(taken)
+}
+
+/// Tries to prove that some branches are never taken, by induction over at
+/// most `bound` transitions.
+///
+/// Only sound for branches the BMC phase did not take in its first `bound`
+/// transitions. Returns, for each branch, true if it is provably dead.
+pub fn induction_phase<'a, S: SolverTrait<'a>>(
+  solver: S, sys: & Sys, branches: & [Branch], factory: & Factory,
+  bound: usize
+) -> Res<Vec<bool>> {
+  let mut dead = vec![ false ; branches.len() ] ;
+  // Actlits of the negation of the guard of each branch, one per transition.
+  let mut untaken: Vec< Vec<String> > = branches.iter().map(
+    |_| Vec::with_capacity(bound)
+  ).collect() ;
+  let mut unroller = try!( Unroller::mk(sys, & [], solver) ) ;
+  let mut off = Offset2::init() ;
+  try!( unroller.declare_svars( off.curr() ) ) ;
+
+  for _ in 0..bound {
+    if dead.iter().all( |d| * d ) { break }
+    try!( unroller.unroll(& off) ) ;
+    for (index, branch) in branches.iter().enumerate() {
+      if dead[index] { continue }
+      let actlit = try!( unroller.fresh_actlit() ) ;
+      try!(
+        unroller.assert(
+          & actlit.activate_term( TmpTerm::Trm( branch.guard.clone() ) ), & off
+        )
+      ) ;
+      let mut assuming = untaken[index].clone() ;
+      assuming.push( actlit.name() ) ;
+      if ! try!( unroller.check_sat_assuming(& assuming) ) {
+        dead[index] = true
+      }
+      try!( unroller.deactivate(actlit) ) ;
+
+      let actlit = try!( unroller.fresh_actlit() ) ;
+      let not_guard = TmpTerm::Trm( factory.not( branch.guard.clone() ) ) ;
+      try!( unroller.assert(& actlit.activate_term(not_guard), & off) ) ;
+      untaken[index].push( actlit.name() )
+    }
+    off = off.nxt()
+  }
+
+  Ok(dead)
+}
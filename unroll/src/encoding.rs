@@ -0,0 +1,241 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encodings of the transition relation used when unrolling a system.
+//!
+//! The encoding has a big impact on the performance of the solver, which is
+//! why it can be chosen per technique. See the `encoding` option of the
+//! techniques in `common::conf`.
+
+use std::collections::HashSet ;
+
+use term::{ Type, Var, Term, State, Offset2, Operator, real_term } ;
+
+use sys::Sys ;
+
+use common::SolverTrait ;
+use common::conf::Encoding ;
+use common::errors::* ;
+
+/// An encoding of the transition relation of a system.
+///
+/// Used by an [`Unroller`](../struct.Unroller.html) to define the top system
+/// and to unroll it.
+pub trait Unroll<S> {
+  /// Name of the encoding, for error messages.
+  fn name(& self) -> & 'static str ;
+
+  /// Defines the top system. Called once, after the functions and the
+  /// sub-systems have been defined.
+  fn define(& mut self, sys: & Sys, solver: & mut S, o: & Offset2) -> Res<()> ;
+
+  /// Unrolls the transition relation once. **Declares** state variables in
+  /// the next offset if the offset is not reversed, in the current offset
+  /// otherwise (for backward unrolling).
+  fn unroll(& mut self, sys: & Sys, solver: & mut S, o: & Offset2) -> Res<()> ;
+
+  /// Next state variables **defined** instead of declared when unrolling
+  /// forward. They are not part of the answer to `get-model`, see
+  /// `Unroller::get_full_model`.
+  fn defined(& self) -> Vec<Var> { vec![] }
+}
+
+/// Creates the encoding corresponding to an encoding option.
+pub fn encoding_of<'a, S: SolverTrait<'a>>(
+  encoding: & Encoding
+) -> Box< Unroll<S> > {
+  match * encoding {
+    Encoding::Rel => Box::new( RelUnroll ),
+    Encoding::Fun => Box::new( FunUnroll::mk() ),
+  }
+}
+
+/// Relation-style encoding.
+///
+/// The transition relation is a predicate over the current and next state,
+/// unrolling declares the fresh state variables and asserts the predicate.
+pub struct RelUnroll ;
+
+impl<'a, S: SolverTrait<'a>> Unroll<S> for RelUnroll {
+  fn name(& self) -> & 'static str { "rel" }
+
+  fn define(& mut self, sys: & Sys, solver: & mut S, o: & Offset2) -> Res<()> {
+    super::define(sys, solver, o)
+  }
+
+  fn unroll(& mut self, sys: & Sys, solver: & mut S, o: & Offset2) -> Res<()> {
+    let off = if o.is_rev() { o.curr() } else { o.next() } ;
+    try!(
+      chain_err!(
+        unroll, format!("during unrolling at {}", o) => super::declare_svars(
+          sys, solver, off, & HashSet::new()
+        )
+      )
+    ) ;
+    chain_err!(
+      unroll, format!("during unrolling at {}", o) => solver.assert(
+        sys.trans_term(), o
+      )
+    )
+  }
+}
+
+/// Functional next-state encoding.
+///
+/// The top-level conjuncts of the transition relation of the form
+/// `(= (next v) e)`, where `e` does not mention the next state, are turned
+/// into one `define-fun` per variable and unrolling. The remaining conjuncts
+/// are asserted as is.
+///
+/// Backward unrolling cannot define the fresh state as a function of the
+/// state after it, so it falls back to the relation-style encoding.
+pub struct FunUnroll {
+  /// Next state variables with their type and definition over the current
+  /// state.
+  defs: Vec<(Var, Type, Term)>,
+  /// Next state variables defined by `defs`.
+  defined: HashSet<Var>,
+  /// Conjuncts of the transition relation not captured by `defs`.
+  rest: Vec<Term>,
+}
+
+impl FunUnroll {
+  /// Creates an empty functional encoding, populated by `define`.
+  pub fn mk() -> Self {
+    FunUnroll {
+      defs: Vec::with_capacity(17),
+      defined: HashSet::with_capacity(17),
+      rest: Vec::with_capacity(17),
+    }
+  }
+
+  /// True iff a term mentions a next state variable.
+  fn has_next(term: & Term) -> bool {
+    use term::real_term::Term::* ;
+    match * term.get() {
+      V(ref var) => var.get().state() == Some(State::Next),
+      C(_) => false,
+      Op(_, ref kids) | App(_, ref kids) => kids.iter().any(Self::has_next),
+      Forall(_, ref body) | Exists(_, ref body) => Self::has_next(body),
+      Let(ref bindings, ref body) => bindings.iter().any(
+        |& (_, ref term)| Self::has_next(term)
+      ) || Self::has_next(body),
+    }
+  }
+
+  /// If `lhs` is a next state variable and `rhs` does not mention the next
+  /// state, returns `lhs` as a variable.
+  fn as_def(lhs: & Term, rhs: & Term) -> Option<Var> {
+    if let real_term::Term::V(ref var) = * lhs.get() {
+      if var.get().state() == Some(State::Next) && ! Self::has_next(rhs) {
+        return Some( var.clone() )
+      }
+    }
+    None
+  }
+
+  /// Splits the transition relation of a system into definitions and
+  /// remaining conjuncts.
+  fn split(& mut self, sys: & Sys) -> Res<()> {
+    self.defs.clear() ;
+    self.defined.clear() ;
+    self.rest.clear() ;
+    let mut conjs = vec![ sys.trans().2.clone() ] ;
+    while let Some(conj) = conjs.pop() {
+      let def = match * conj.get() {
+        real_term::Term::Op(Operator::And, ref kids) => {
+          conjs.extend( kids.iter().cloned() ) ;
+          continue
+        },
+        real_term::Term::Op(Operator::Eq, ref kids) if kids.len() == 2 => {
+          Self::as_def(& kids[0], & kids[1]).map(
+            |var| (var, kids[1].clone())
+          ).or_else(
+            || Self::as_def(& kids[1], & kids[0]).map(
+              |var| (var, kids[0].clone())
+            )
+          )
+        },
+        _ => None,
+      } ;
+      match def {
+        Some( (var, def) ) => if self.defined.contains(& var) {
+          // Already defined, keep the equality as a constraint.
+          self.rest.push(conj.clone())
+        } else {
+          let typ = match sys.trans().1.iter().find(
+            |& & (ref v, _)| v == & var
+          ) {
+            Some( & (_, ref typ) ) => typ.clone(),
+            None => bail!(
+              format!(
+                "[FunUnroll::split] unknown state variable {} in system {}",
+                var, sys.sym()
+              )
+            ),
+          } ;
+          self.defined.insert( var.clone() ) ;
+          self.defs.push( (var, typ, def) )
+        },
+        None => self.rest.push(conj.clone()),
+      }
+    }
+    Ok(())
+  }
+}
+
+impl<'a, S: SolverTrait<'a>> Unroll<S> for FunUnroll {
+  fn name(& self) -> & 'static str { "fun" }
+
+  fn defined(& self) -> Vec<Var> {
+    self.defs.iter().map( |& (ref var, _, _)| var.clone() ).collect()
+  }
+
+  fn define(& mut self, sys: & Sys, solver: & mut S, o: & Offset2) -> Res<()> {
+    try!( self.split(sys) ) ;
+    // The trans predicate is still used by backward unrolling.
+    super::define(sys, solver, o)
+  }
+
+  fn unroll(& mut self, sys: & Sys, solver: & mut S, o: & Offset2) -> Res<()> {
+    if o.is_rev() {
+      return Unroll::<S>::unroll(& mut RelUnroll, sys, solver, o)
+    }
+    let mut skip = HashSet::with_capacity( self.defined.len() ) ;
+    for & (ref var, _, _) in self.defs.iter() {
+      skip.insert( var.get().sym().clone() ) ; ()
+    }
+    try!(
+      chain_err!(
+        unroll, format!("during functional unrolling at {}", o) =>
+        super::declare_svars(sys, solver, o.next(), & skip)
+      )
+    ) ;
+    let no_args: & [ (Var, Type) ] = & [] ;
+    for & (ref var, ref typ, ref def) in self.defs.iter() {
+      try!(
+        chain_err!(
+          unroll, format!(
+            "while defining {} for functional unrolling at {}", var, o
+          ) => solver.define_fun(var, no_args, typ, def, o)
+        )
+      )
+    }
+    for conj in self.rest.iter() {
+      try!(
+        chain_err!(
+          unroll, format!(
+            "while asserting transition for functional unrolling at {}", o
+          ) => solver.assert(conj, o)
+        )
+      )
+    }
+    Ok(())
+  }
+}
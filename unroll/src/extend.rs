@@ -0,0 +1,68 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Extends a counterexample with the transitions following its last state.
+//!
+//! All the states of the counterexample are fixed, and the transition
+//! relation is unrolled further one step at a time. The extension stops
+//! early when no transition can be taken anymore.
+
+use term::{ Term, Model, Offset2, State, Factory, VarMaker, CstMaker } ;
+
+use sys::Sys ;
+
+use common::SolverTrait ;
+use common::samples::SamplePool ;
+use common::errors::* ;
+
+use Unroller ;
+
+/// Extends a counterexample by at most `steps` transitions.
+///
+/// Returns the extended model, which includes the states of `model`, or
+/// nothing if no transition can be taken from the last state of `model`.
+pub fn extend<'a, S: SolverTrait<'a>>(
+  solver: S, sys: & Sys, model: & Model, factory: & Factory, steps: usize
+) -> Res< Option<Model> > {
+  let states = SamplePool::states_of(model) ;
+  if states.is_empty() { return Ok(None) }
+
+  let mut unroller = try!( Unroller::mk(sys, & [], solver) ) ;
+
+  // Fixing the states of the counterexample.
+  let mut off = Offset2::init() ;
+  try!( unroller.assert_init(& off) ) ;
+  for (index, state) in states.iter().enumerate() {
+    if index > 0 {
+      try!( unroller.unroll(& off) ) ;
+      off = off.nxt()
+    }
+    for & (ref sym, ref cst) in state.iter() {
+      let svar: Term = factory.svar( sym.clone(), State::Curr ) ;
+      let eq = factory.eq( vec![ svar, factory.cst( cst.clone() ) ] ) ;
+      try!( unroller.assert(& eq, & off) )
+    }
+  }
+
+  let mut extended = None ;
+  for _ in 0..steps {
+    try!( unroller.unroll(& off) ) ;
+    off = off.nxt() ;
+    if ! try!( unroller.check_sat() ) { break }
+    extended = Some(
+      try!(
+        unroller.get_full_model().chain_err(
+          || "[Unroller] while retrieving extended counterexample"
+        )
+      )
+    )
+  }
+
+  Ok(extended)
+}
@@ -0,0 +1,210 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generalizes proved properties into lemmas.
+//!
+//! The parameters of a system are the state variables its transition
+//! relation keeps constant, with a top-level conjunct
+//! `(= (_ next v) (_ state v))`. A candidate lemma replaces all the
+//! occurrences of a constant of a proved one-state property by a parameter
+//! of the same type. Candidates are checked by 1-induction, assuming the
+//! property and some invariants, and the ones that hold are lemmas about
+//! all the values of the parameter.
+
+use term::{
+  Sym, Term, Cst, STerm, STermSet, Offset2, Type, State, Operator, Factory,
+  VarMaker, OpMaker, AppMaker, BindMaker, UnTermOps
+} ;
+use term::tmp::TmpTerm ;
+use term::zip::{ Step, fold } ;
+
+use sys::{ Sys, Prop } ;
+
+use common::SolverTrait ;
+use common::errors::* ;
+
+use Unroller ;
+
+/// A candidate lemma generalizing a property.
+pub struct Lemma {
+  /// Constant generalized.
+  pub cst: Cst,
+  /// Parameter replacing the constant.
+  pub param: Sym,
+  /// The lemma.
+  pub lemma: STerm,
+}
+
+/// The parameters of a system, with their type.
+pub fn params(sys: & Sys) -> Vec<(Sym, Type)> {
+  use term::real_term::Term::{ Op, V } ;
+  use term::real_term::Var::SVar ;
+  let trans = & sys.trans().2 ;
+  let conjs = match * trans.get() {
+    Op(Operator::And, ref kids) => kids.clone(),
+    _ => vec![ trans.clone() ],
+  } ;
+  let mut frozen = vec![] ;
+  for conj in conjs.iter() {
+    if let Op(Operator::Eq, ref kids) = * conj.get() {
+      if kids.len() != 2 { continue }
+      let (lhs, rhs) = ( kids[0].get(), kids[1].get() ) ;
+      if let ( & V(ref lhs), & V(ref rhs) ) = (lhs, rhs) {
+        match ( lhs.get(), rhs.get() ) {
+          ( & SVar(ref l, State::Next), & SVar(ref r, State::Curr) ) |
+          ( & SVar(ref l, State::Curr), & SVar(ref r, State::Next) )
+          if l == r => frozen.push( l.clone() ),
+          _ => (),
+        }
+      }
+    }
+  }
+  sys.state().args().iter().filter(
+    |& & (ref sym, _)| frozen.contains( sym.get() )
+  ).map(
+    |& (ref sym, ref typ)| ( sym.get().clone(), typ.get().clone() )
+  ).collect()
+}
+
+/// Candidate lemmas generalizing a one-state property, none for two-state
+/// properties.
+pub fn candidates(
+  factory: & Factory, sys: & Sys, prop: & Prop
+) -> Res< Vec<Lemma> > {
+  let state = match * prop.body() {
+    STerm::One(ref state, _) => state.clone(),
+    STerm::Two(_) => return Ok( vec![] ),
+  } ;
+  let params = params(sys) ;
+  if params.is_empty() { return Ok( vec![] ) }
+
+  let mut res = vec![] ;
+  for cst in csts_of(& state).into_iter() {
+    let typ = cst.typ() ;
+    if typ == Type::Bool { continue }
+    for & (ref param, ref param_typ) in params.iter() {
+      if * param_typ != typ { continue }
+      let svar: Term = factory.svar( param.clone(), State::Curr ) ;
+      let lemma = fold(
+        |step| match step {
+          Step::C(c) => if c == cst {
+            svar.clone()
+          } else {
+            factory.mk_cst(c)
+          },
+          step => rebuild(factory, step),
+        }, state.clone()
+      ) ;
+      let next = try!( factory.bump( lemma.clone() ) ) ;
+      res.push(
+        Lemma {
+          cst: cst.clone(), param: param.clone(),
+          lemma: STerm::One(lemma, next),
+        }
+      )
+    }
+  }
+  Ok(res)
+}
+
+/// The constants of a term, without duplicates.
+fn csts_of(term: & Term) -> Vec<Cst> {
+  use term::real_term::Term::* ;
+  let mut res = vec![] ;
+  let mut stack = vec![ term ] ;
+  while let Some(term) = stack.pop() {
+    match * term.get() {
+      C(ref cst) => if ! res.contains(cst) { res.push( cst.clone() ) },
+      V(_) => (),
+      Op(_, ref kids) | App(_, ref kids) => stack.extend( kids.iter() ),
+      Let(ref bindings, ref body) => {
+        for & (_, ref term) in bindings.iter() { stack.push(term) }
+        stack.push(body)
+      },
+      Forall(_, ref body) | Exists(_, ref body) => stack.push(body),
+    }
+  }
+  res
+}
+
+/// Rebuilds a term from a step of a fold.
+fn rebuild(factory: & Factory, step: Step<Term>) -> Term {
+  match step {
+    Step::App(sym, args) => factory.app(sym, args),
+    Step::Op(op, args) => factory.op(op, args),
+    Step::Let(bindings, body) => factory.let_b(bindings, body),
+    Step::Forall(vars, body) => factory.forall(vars, body),
+    Step::Exists(vars, body) => factory.exists(vars, body),
+    Step::C(cst) => factory.mk_cst(cst),
+    Step::V(var) => factory.mk_var(var),
+  }
+}
+
+/// Checks some candidate lemmas generalizing a proved property by
+/// 1-induction, assuming the property and some invariants in both states.
+///
+/// Returns the candidates that are lemmas.
+pub fn check<'a, S: SolverTrait<'a>>(
+  solver: S, sys: & Sys, prop: & Prop, invs: & STermSet,
+  factory: & Factory, candidates: Vec<Lemma>
+) -> Res< Vec<Lemma> > {
+  let mut unroller = try!(
+    Unroller::mk( sys, & [ prop.clone() ], solver )
+  ) ;
+  let off = Offset2::init() ;
+  try!( unroller.declare_svars( off.curr() ) ) ;
+  try!( unroller.unroll(& off) ) ;
+
+  // Invariants hold in both states.
+  let mut assumptions: Vec<& STerm> = invs.iter().collect() ;
+  assumptions.push( prop.body() ) ;
+  for inv in assumptions.into_iter() {
+    if let Some(state) = inv.state() {
+      try!( unroller.assert(state, & off) )
+    }
+    try!( unroller.assert(inv.next(), & off) )
+  }
+
+  let init = try!( unroller.fresh_actlit() ) ;
+  try!(
+    unroller.assert(
+      & init.activate_term( TmpTerm::Trm( sys.init_term().clone() ) ), & off
+    )
+  ) ;
+
+  let mut res = vec![] ;
+  for candidate in candidates.into_iter() {
+    let (state, next) = match candidate.lemma {
+      STerm::One(ref state, ref next) => (state.clone(), next.clone()),
+      STerm::Two(_) => continue,
+    } ;
+
+    // Base case.
+    let actlit = try!( unroller.fresh_actlit() ) ;
+    let neg = TmpTerm::Trm( factory.not( state.clone() ) ) ;
+    try!( unroller.assert(& actlit.activate_term(neg), & off) ) ;
+    let base = try!(
+      unroller.check_sat_assuming( & [ init.name(), actlit.name() ] )
+    ) ;
+    try!( unroller.deactivate(actlit) ) ;
+    if base { continue }
+
+    // Step case.
+    let actlit = try!( unroller.fresh_actlit() ) ;
+    let step = TmpTerm::Trm(
+      factory.and( vec![ state, factory.not(next) ] )
+    ) ;
+    try!( unroller.assert(& actlit.activate_term(step), & off) ) ;
+    let step = try!( unroller.check_sat_assuming( & [ actlit.name() ] ) ) ;
+    try!( unroller.deactivate(actlit) ) ;
+    if ! step { res.push(candidate) }
+  }
+
+  Ok(res)
+}
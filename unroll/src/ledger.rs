@@ -0,0 +1,92 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Ledger of the declarations and assertions of an unroller.
+//!
+//! The [`Unroller`](../struct.Unroller.html) records what it declares and
+//! asserts, along with the offsets involved. Rolling back to a smaller depth
+//! resets the solver and replays the entries that do not go beyond that
+//! depth.
+//!
+//! Assertions made through
+//! [`Unroller::assert`](../struct.Unroller.html#method.assert) and
+//! activation literals are **not** recorded: clients are responsible for
+//! asserting them again after a rollback.
+
+use std::cmp::max ;
+
+use term::{ Offset, Offset2, STermSet } ;
+
+use sys::Prop ;
+
+/// An entry of the ledger, one per operation of the unroller.
+#[derive(Clone)]
+pub enum Entry {
+  /// UFs, functions and system predicates declared/defined for some
+  /// properties.
+  Funs(Vec<Prop>),
+  /// UFs and functions declared/defined for some properties added later.
+  PropFuns(Vec<Prop>),
+  /// State variables declared at some offset.
+  Svars(Offset),
+  /// Init predicate asserted.
+  Init(Offset2),
+  /// One-state invariants asserted.
+  OsInvs(Offset2),
+  /// Transition relation unrolled with `unroll`.
+  Unroll(Offset2),
+  /// Transition relation unrolled with `unroll_init`.
+  UnrollInit(Offset2),
+  /// Transition relation unrolled with `unroll_bak`.
+  UnrollBak(Offset2),
+  /// Invariants added and asserted between two offsets.
+  Invs(STermSet, Offset2, Offset2),
+  /// State at some offset asserted different from the ones before it.
+  Distinct(Offset),
+}
+impl Entry {
+  /// Largest offset mentioned by an entry, `None` if it does not mention
+  /// any.
+  pub fn depth(& self) -> Option<Offset> {
+    use self::Entry::* ;
+    match * self {
+      Funs(_) | PropFuns(_) => None,
+      Svars(ref o) | Distinct(ref o) => Some( o.clone() ),
+      // Only mention the current state.
+      Init(ref o) | OsInvs(ref o) => Some( o.curr().clone() ),
+      Unroll(ref o) | UnrollInit(ref o) | UnrollBak(ref o) => Some( last(o) ),
+      Invs(_, ref begin, ref end) => Some(
+        max( last(begin), last(end) )
+      ),
+    }
+  }
+
+  /// Restricts an entry to some depth. Returns `None` if nothing is left.
+  ///
+  /// Invariant entries are shortened and always kept, so that the
+  /// invariants are remembered. The others are kept iff their depth is at
+  /// most `depth`.
+  pub fn upto(self, depth: & Offset) -> Option<Entry> {
+    match self {
+      Entry::Invs(invs, begin, mut end) => {
+        while & last(& end) > depth && end >= begin { end = end.pre() }
+        Some( Entry::Invs(invs, begin, end) )
+      },
+      entry => match entry.depth() {
+        Some(ref d) if d > depth => None,
+        _ => Some(entry),
+      },
+    }
+  }
+}
+
+/// Largest offset of a two-state offset.
+fn last(o: & Offset2) -> Offset {
+  max( o.curr().clone(), o.next().clone() )
+}
@@ -18,7 +18,7 @@ extern crate common ;
 #[macro_use]
 extern crate error_chain ;
 
-use std::collections::{ HashSet, HashMap } ;
+use std::collections::{ HashSet, HashMap, BTreeMap } ;
 use std::hash::Hash ;
 use std::cmp::Eq ;
 use std::fmt::Display ;
@@ -26,10 +26,10 @@ use std::iter::{ Iterator, IntoIterator } ;
 
 use term::{
   Type, Sym, Term, Model,
-  Offset, Offset2, STerm, STermSet, real_term
+  Offset, Offset2, STerm, STermSet, MemUsage, StableHash, real_term
 } ;
 use term::smt::{
-  Expr2Smt
+  Expr2Smt, cvc5
 } ;
 use term::tmp::* ;
 // use term::parsing::Spnd ;
@@ -37,6 +37,10 @@ use term::tmp::* ;
 use sys::{ Prop, Sys, Callable } ;
 
 use common::SolverTrait ;
+use common::values ;
+use common::crash ;
+use common::conf::{ Encoding, OnUnknown } ;
+use common::msg::{ Status, Event } ;
 use common::errors::* ;
 
 /// Manages some properties.
@@ -44,6 +48,14 @@ pub type PropManager = TermManager<Sym> ;
 /// Manages some invariants.
 pub type InvManager = TermManager<STerm> ;
 
+/// Number of properties whose value `get_false_state` and `get_false_next`
+/// ask for in one query.
+const FALSE_BATCH: usize = 50 ;
+
+/// Number of times a check answered `unknown` is retried with another
+/// random seed, if retrying.
+const RETRIES: usize = 3 ;
+
 macro_rules! chain_err {
   (term man, $desc:expr => $e:expr) => (
     chain_err!("TermManager", $desc => $e)
@@ -61,6 +73,18 @@ macro_rules! chain_err {
   ) ;
 }
 
+mod encoding ;
+pub use encoding::{ Unroll, RelUnroll, FunUnroll, encoding_of } ;
+pub mod ledger ;
+use ledger::Entry ;
+pub mod localize ;
+pub mod deadcode ;
+pub mod extend ;
+pub mod generalize ;
+pub mod certify ;
+pub mod schedule ;
+use schedule::Schedule ;
+
 /// Associates a key and a description to some type.
 #[derive(Clone)]
 pub struct Opt<T: Clone> {
@@ -118,6 +142,25 @@ fn define<'a, S: SolverTrait<'a>>(
 }
 
 
+/// Declares the state variables of a system at some offset, except the ones
+/// in `skip`.
+fn declare_svars<'a, S: SolverTrait<'a>>(
+  sys: & sys::Sys, solver: & mut S, o: & Offset, skip: & HashSet<Sym>
+) -> Res<()> {
+  for & (ref var, ref typ) in sys.init().1.iter() {
+    if ! skip.contains( var.get().sym() ) {
+      try!(
+        chain_err!(
+          unroll, "during svar declaration" => solver.declare_fun(
+            var, & vec![], typ, o
+          )
+        )
+      )
+    }
+  } ;
+  Ok(())
+}
+
 /// Can unroll a system.
 ///
 /// An `Unroller` does **not** handle the unrolling depth. This is up to the
@@ -132,6 +175,13 @@ fn define<'a, S: SolverTrait<'a>>(
 /// - [`unroll`](struct.Unroller.html#method.unroll)
 /// - [`unroll_init`](struct.Unroller.html#method.unroll_init)
 /// - [`unroll_bak`](struct.Unroller.html#method.unroll_bak)
+///
+/// The encoding of the transition relation is given by an
+/// [`Unroll`](trait.Unroll.html) implementation, relation-style by default.
+///
+/// The unroller keeps a [ledger](ledger/index.html) of what it declares and
+/// asserts, so that it can [roll back](#method.rollback) to a smaller depth
+/// without the client rebuilding everything.
 pub struct Unroller<S> {
   /// The system to unroll.
   sys: Sys,
@@ -145,16 +195,51 @@ pub struct Unroller<S> {
   // end_k: Offset2,
   /// Actlit factory.
   act_factory: ActlitFactory,
+  /// Functions declared/defined so far.
+  funs: HashSet<Sym>,
+  /// Encoding of the transition relation.
+  encoding: Box< Unroll<S> >,
+  /// What was declared and asserted so far.
+  ledger: Vec<Entry>,
+  /// What to do when the solver answers `unknown` to a check.
+  on_unknown: OnUnknown,
+  /// Option setting the random seed of the next checks, if the solver can
+  /// change it after assertions.
+  seed_option: Option<& 'static str>,
+  /// Number of `unknown` answers to the checks so far.
+  unknowns: usize,
+  /// True iff the last check was answered `unknown` and treated as `sat`.
+  last_unknown: bool,
 }
 
 impl<
   'a, S: SolverTrait<'a>
 > Unroller<S> {
-  /// Creates an unroller from a system.
+  /// Creates an unroller from a system, using the relation-style encoding.
   ///
   /// Declares everything needed at `0`.
   #[inline]
   pub fn mk(sys: & Sys, props: & [Prop], solver: S) -> Res<Self> {
+    Self::mk_with(sys, props, solver, Box::new(RelUnroll))
+  }
+
+  /// Creates an unroller from a system, using the encoding specified by an
+  /// option.
+  ///
+  /// Declares everything needed at `0`.
+  #[inline]
+  pub fn mk_encoded(
+    sys: & Sys, props: & [Prop], solver: S, encoding: & Encoding
+  ) -> Res<Self> {
+    Self::mk_with(sys, props, solver, encoding_of(encoding))
+  }
+
+  /// Creates an unroller from a system and an encoding.
+  ///
+  /// Declares everything needed at `0`.
+  pub fn mk_with(
+    sys: & Sys, props: & [Prop], solver: S, encoding: Box< Unroll<S> >
+  ) -> Res<Self> {
     let mut unroller = Unroller {
       sys: sys.clone(),
       solver: solver,
@@ -162,6 +247,13 @@ impl<
       // beg_k: Offset2::init(),
       // end_k: Offset2::init().pre(),
       act_factory: ActlitFactory::mk(),
+      funs: HashSet::with_capacity(7),
+      encoding: encoding,
+      ledger: Vec::with_capacity(17),
+      on_unknown: OnUnknown::Abort,
+      seed_option: None,
+      unknowns: 0,
+      last_unknown: false,
     } ;
     try!(
       chain_err!(
@@ -189,6 +281,112 @@ impl<
   /// Accessor for the invariants.
   #[inline]
   pub fn invs(& self) -> & STermSet { & self.invs }
+  /// What was declared and asserted so far, in order.
+  #[inline]
+  pub fn ledger(& self) -> & [Entry] { & self.ledger }
+  /// Sets what to do when the solver answers `unknown` to a check, see
+  /// [`OnUnknown`](../common/conf/enum.OnUnknown.html). `Skip` is up to
+  /// the caller, the check fails as with `Abort`.
+  #[inline]
+  pub fn set_on_unknown(& mut self, on_unknown: OnUnknown) {
+    self.on_unknown = on_unknown
+  }
+  /// What to do when the solver answers `unknown` to a check.
+  #[inline]
+  pub fn on_unknown(& self) -> OnUnknown { self.on_unknown }
+  /// Sets the option changing the random seed of the solver between the
+  /// checks, see [`seed_option`](../term/smt/fn.seed_option.html). Without
+  /// one, `Retry` cannot retry and fails as `Abort`.
+  #[inline]
+  pub fn set_seed_option(& mut self, option: Option<& 'static str>) {
+    self.seed_option = option
+  }
+  /// Sets the timeout of the next checks in milliseconds, with the option
+  /// of [`timeout_option`](../term/smt/fn.timeout_option.html). Checks
+  /// timing out are answered `unknown`.
+  pub fn set_timeout(
+    & mut self, option: & 'static str, millis: usize
+  ) -> Res<()> {
+    chain_err!(
+      unroll, "while setting the timeout of the checks" =>
+      self.solver.set_option(option, millis)
+    )
+  }
+  /// Number of `unknown` answers to the checks so far, including the ones
+  /// retried.
+  #[inline]
+  pub fn unknowns(& self) -> usize { self.unknowns }
+  /// True iff the solver answered `unknown` to the last check, which was
+  /// treated as `sat`. There is no model then.
+  #[inline]
+  pub fn last_unknown(& self) -> bool { self.last_unknown }
+
+  /// Resets the solver and forgets the ledger. The invariants are kept.
+  pub fn reset(& mut self) -> Res<()> {
+    try!(
+      chain_err!( unroll, "during solver reset" => self.solver.reset() )
+    ) ;
+    self.ledger.clear() ;
+    self.funs.clear() ;
+    Ok(())
+  }
+
+  /// Rolls back to some depth: resets the solver and replays the entries of
+  /// the ledger that do not go beyond `depth`. Invariants are asserted up to
+  /// `depth`.
+  ///
+  /// Assertions and activation literals are not part of the ledger, see the
+  /// [`ledger`](ledger/index.html) module.
+  pub fn rollback(& mut self, depth: & Offset) -> Res<()> {
+    use std::mem::replace ;
+    let ledger = replace(& mut self.ledger, Vec::with_capacity(17)) ;
+    try!( self.reset() ) ;
+    for entry in ledger.into_iter() {
+      if let Some(entry) = entry.upto(depth) {
+        try!(
+          chain_err!(
+            unroll, format!("while rolling back to {}", depth) =>
+            self.replay(entry)
+          )
+        )
+      }
+    }
+    Ok(())
+  }
+
+  /// Replays an entry of the ledger, which records it again.
+  fn replay(& mut self, entry: Entry) -> Res<()> {
+    match entry {
+      Entry::Funs(props) => self.defclare_funs(& props),
+      Entry::PropFuns(props) => self.defclare_prop_funs(& props),
+      Entry::Svars(o) => self.declare_svars(& o),
+      Entry::Init(o) => self.assert_init(& o),
+      Entry::OsInvs(o) => self.assert_os_invs(& o),
+      Entry::Unroll(o) => self.unroll(& o),
+      Entry::UnrollInit(o) => self.unroll_init(& o),
+      Entry::UnrollBak(o) => self.unroll_bak(& o),
+      Entry::Distinct(o) => self.assert_distinct(& o),
+      Entry::Invs(invs, begin, end) => {
+        if begin > end {
+          // Nothing to assert, only remembering them.
+          self.just_add_invs( invs.clone() ) ;
+          self.ledger.push( Entry::Invs(invs, begin, end) ) ;
+          Ok(())
+        } else {
+          self.add_invs(invs, & begin, & end)
+        }
+      },
+    }
+  }
+
+  /// Approximate memory usage of the unroller.
+  pub fn mem_usage(& self) -> Vec<MemUsage> {
+    vec![
+      MemUsage::of_table::<STerm>("invariants", self.invs.len()),
+      MemUsage::of_table::<Sym>("functions", self.funs.len()),
+      MemUsage::of::<Entry>("ledger", self.ledger.len()),
+    ]
+  }
 
   /// Creates and declares a fresh activation literal.
   #[inline]
@@ -214,12 +412,25 @@ impl<
     )
   }
 
+  /// Opens an assertion scope, closed by [`pop`](#method.pop).
+  #[inline]
+  pub fn push(& mut self) -> Res<()> {
+    chain_err!( unroll, "during push" => self.solver.push(& 1) )
+  }
+
+  /// Closes the last assertion scope: the solver forgets what was asserted
+  /// since the matching [`push`](#method.push). Nothing should be declared
+  /// in a scope, the unroller would not know it is forgotten.
+  #[inline]
+  pub fn pop(& mut self) -> Res<()> {
+    chain_err!( unroll, "during pop" => self.solver.pop(& 1) )
+  }
+
   /// Performs a check sat.
   #[inline]
   pub fn check_sat(& mut self) -> Res<bool> {
-    chain_err!(
-      unroll, "during check sat" => self.solver.check_sat()
-    )
+    crash::query( "(check-sat)".to_string() ) ;
+    self.check_with( |solver| solver.check_sat(), "during check sat" )
   }
 
   /// Performs a check sat assuming.
@@ -227,13 +438,93 @@ impl<
   pub fn check_sat_assuming(
     & mut self, idents: & [String]
   ) -> Res<bool> {
+    crash::query(
+      format!("(check-sat-assuming ({}))", idents.join(" "))
+    ) ;
+    self.check_with(
+      |solver| cvc5::check_sat_assuming(solver, idents, & ()),
+      "during check sat assuming"
+    )
+  }
+
+  /// Performs a check and handles an `unknown` answer following the
+  /// policy of the unroller. If the check fails because of it, the error
+  /// is a `SolverUnknown` with the reason the solver gives.
+  fn check_with<E, Check>(
+    & mut self, mut check: Check, desc: & str
+  ) -> Res<bool>
+  where
+  E: ::std::error::Error + Send + Sync + 'static,
+  Check: FnMut(& mut S) -> Result<bool, E> {
+    self.last_unknown = false ;
+    let mut res = check(& mut self.solver) ;
+    if ! self.is_unknown(& res) {
+      return chain_err!(unroll, desc => res)
+    }
+    self.unknowns += 1 ;
+    let mut reason = self.reason_unknown() ;
+    match self.on_unknown {
+      OnUnknown::Sat => {
+        self.last_unknown = true ;
+        return Ok(true)
+      },
+      // The seed is kept for the next checks.
+      OnUnknown::Retry => if let Some(option) = self.seed_option {
+        for seed in 1 .. RETRIES + 1 {
+          try!(
+            chain_err!(
+              unroll, "while changing the random seed" =>
+              self.solver.set_option(option, & seed.to_string())
+            )
+          ) ;
+          res = check(& mut self.solver) ;
+          if ! self.is_unknown(& res) {
+            return chain_err!(unroll, desc => res)
+          }
+          self.unknowns += 1 ;
+          reason = self.reason_unknown()
+        }
+      },
+      OnUnknown::Abort | OnUnknown::Skip => (),
+    }
     chain_err!(
-      unroll, "during check sat assuming" => self.solver.check_sat_assuming(
-        idents, & ()
-      )
+      unroll, desc => res.chain_err( || ErrorKind::SolverUnknown(reason) )
     )
   }
 
+  /// True iff a query failed because the solver answered `unknown`.
+  fn is_unknown<T, E>(& self, res: & Result<T, E>) -> bool {
+    res.is_err() && String::from_utf8_lossy(
+      self.solver.as_ref()
+    ).trim().starts_with("unknown")
+  }
+
+  /// Asks the solver why it answered `unknown` to the last query.
+  fn reason_unknown(& mut self) -> String {
+    let asked = self.solver.get_info(":reason-unknown") ;
+    let answer = asked.and_then(
+      |()| self.solver.parse(
+        |bytes, _| (
+          String::new(), Ok( String::from_utf8_lossy(bytes).into_owned() )
+        )
+      )
+    ) ;
+    // Usually `(:reason-unknown "<reason>")`.
+    let reason = match answer {
+      Ok(answer) => {
+        let answer = answer.trim().trim_matches(
+          |c| c == '(' || c == ')'
+        ).trim() ;
+        let answer = if answer.starts_with(":reason-unknown") {
+          & answer[ ":reason-unknown".len() .. ]
+        } else { answer } ;
+        answer.trim().trim_matches('"').to_string()
+      },
+      Err(_) => String::new(),
+    } ;
+    if reason.is_empty() { "no reason given".to_string() } else { reason }
+  }
+
   /// Asserts something.
   #[inline]
   pub fn assert< Expr: Expr2Smt<Offset2> >(
@@ -244,10 +535,23 @@ impl<
     )
   }
 
+  /// Asserts the constraint of a schedule on the state at `k.curr()`, if
+  /// the schedule has that many steps.
+  pub fn assert_schedule(
+    & mut self, schedule: & Schedule, k: & Offset2
+  ) -> Res<()> {
+    match schedule.at( k.curr().to_usize() ) {
+      Some(step) => chain_err!(
+        unroll, "while asserting schedule" => self.solver.assert(step, k)
+      ),
+      None => Ok(()),
+    }
+  }
+
   fn defclare_funs_iter<'b, T: Iterator<Item = & 'b Callable>>(
     solver: & mut S, funs: T, offset: & Offset2,
     known: & mut HashSet<Sym>,
-    rest: & mut HashSet<& 'b Callable>
+    rest: & mut Vec<& 'b Callable>
   ) -> Res<()> {
     use sys::real_sys::Callable::* ;
     for fun in funs {
@@ -287,7 +591,7 @@ impl<
               )
             )
           } else {
-            rest.insert(fun) ; ()
+            rest.push(fun)
           }
         },
       }
@@ -335,7 +639,9 @@ impl<
     let offset = Offset2::init() ;
 
     let mut known = HashSet::with_capacity(7) ;
-    let mut rest = HashSet::with_capacity(7) ;
+    // Functions left to define, in the order of the system so that the
+    // commands are the same from one run to the other.
+    let mut rest = Vec::with_capacity(7) ;
     // Declaring UFs and defining functions.
     // println!("declaring UFs, defining funs") ;
     try!(
@@ -346,7 +652,7 @@ impl<
     ) ;
     while ! rest.is_empty() {
       use std::mem::swap ;
-      let mut calls = HashSet::with_capacity(7) ;
+      let mut calls = Vec::with_capacity(7) ;
       swap(& mut calls, & mut rest) ;
       try!(
         Self::defclare_funs_iter(
@@ -367,7 +673,7 @@ impl<
 
     while ! rest.is_empty() {
       use std::mem::swap ;
-      let mut calls = HashSet::with_capacity(7) ;
+      let mut calls = Vec::with_capacity(7) ;
       swap(& mut calls, & mut rest) ;
       try!(
         Self::defclare_funs_iter(
@@ -377,6 +683,9 @@ impl<
       )
     }
 
+    // Remembering declared functions for properties added later.
+    self.funs = known ;
+
     // Defining sub systems.
     // println!("defining sub systems") ;
     let mut known = HashSet::with_capacity(7) ;
@@ -401,21 +710,56 @@ impl<
 
     // Define current system.
     // println!("defining top system") ;
-    define(& self.sys, & mut self.solver, & offset)
+    let name = self.encoding.name() ;
+    try!(
+      chain_err!(
+        unroll, format!("while defining top system ({} encoding)", name) =>
+        self.encoding.define(& self.sys, & mut self.solver, & offset)
+      )
+    ) ;
+    self.ledger.push( Entry::Funs( props.to_vec() ) ) ;
+    Ok(())
   }
 
-  /// Declares state variables at some offset.
-  #[inline]
-  pub fn declare_svars(& mut self, o: & Offset) -> Res<()> {
-    for & (ref var, ref typ) in self.sys.init().1.iter() {
+  /// Declares/defines the UFs and functions used by some properties that
+  /// are not known yet. Used when properties are added during a run.
+  pub fn defclare_prop_funs(& mut self, props: & [ Prop ]) -> Res<()> {
+    // Will not really be used.
+    let offset = Offset2::init() ;
+
+    let mut rest = Vec::with_capacity(7) ;
+    for prop in props.iter() {
       try!(
-        chain_err!(
-          unroll, "during svar declaration" => self.solver.declare_fun(
-            var, & vec![], typ, o
-          )
+        Self::defclare_funs_iter(
+          & mut self.solver, prop.calls().get().into_iter(),
+          & offset, & mut self.funs, & mut rest
         )
       )
-    } ;
+    }
+
+    while ! rest.is_empty() {
+      use std::mem::swap ;
+      let mut calls = Vec::with_capacity(7) ;
+      swap(& mut calls, & mut rest) ;
+      try!(
+        Self::defclare_funs_iter(
+          & mut self.solver, calls.into_iter(),
+          & offset, & mut self.funs, & mut rest
+        )
+      )
+    }
+
+    self.ledger.push( Entry::PropFuns( props.to_vec() ) ) ;
+    Ok(())
+  }
+
+  /// Declares state variables at some offset.
+  #[inline]
+  pub fn declare_svars(& mut self, o: & Offset) -> Res<()> {
+    try!(
+      declare_svars(& self.sys, & mut self.solver, o, & HashSet::new())
+    ) ;
+    self.ledger.push( Entry::Svars( o.clone() ) ) ;
     Ok(())
   }
 
@@ -433,6 +777,7 @@ impl<
         )
       }
     }
+    self.ledger.push( Entry::OsInvs( off.clone() ) ) ;
     Ok(())
   }
 
@@ -440,30 +785,35 @@ impl<
   /// offset.
   #[inline]
   pub fn assert_init(& mut self, o: & Offset2) -> Res<()> {
+    try!( self.annotate( & format!("init at {}", o) ) ) ;
     try!(
-      self.declare_svars( o.curr() )
+      declare_svars(
+        & self.sys, & mut self.solver, o.curr(), & HashSet::new()
+      )
     ) ;
-    chain_err!(
-      unroll, "during init predicate assertion" => self.solver.assert(
-        self.sys.init_term(), o
+    try!(
+      chain_err!(
+        unroll, "during init predicate assertion" => self.solver.assert(
+          self.sys.init_term(), o
+        )
       )
-    )
+    ) ;
+    self.ledger.push( Entry::Init( o.clone() ) ) ;
+    Ok(())
   }
 
   /// Unrolls the transition relation once. **Declares** state variables in
   /// the next offset if the offset is not reversed, in the current offset
   /// otherwise (for backward unrolling).
   fn just_unroll(& mut self, o: & Offset2) -> Res<()> {
-    let off = if o.is_rev() { o.curr() } else { o.next() } ;
-    try!(
-      chain_err!(
-        unroll, format!("during unrolling at {}", o) => self.declare_svars(off)
-      )
-    ) ;
+    try!( self.annotate( & format!("unrolling at {}", o) ) ) ;
+    self.encoding.unroll(& self.sys, & mut self.solver, o)
+  }
+
+  /// Comments the smt trace of the solver, if any.
+  fn annotate(& mut self, blah: & str) -> Res<()> {
     chain_err!(
-      unroll, format!("during unrolling at {}", o) => self.solver.assert(
-        self.sys.trans_term(), o
-      )
+      unroll, "while commenting the smt trace" => self.solver.comment(blah)
     )
   }
 
@@ -485,6 +835,7 @@ impl<
         )
       ) ;
     }
+    self.ledger.push( Entry::Unroll( o.clone() ) ) ;
     Ok(())
   }
 
@@ -527,6 +878,7 @@ impl<
         )
       )
     }
+    self.ledger.push( Entry::UnrollInit( o.clone() ) ) ;
     Ok(())
   }
 
@@ -557,6 +909,51 @@ impl<
         )
       )
     }
+    self.ledger.push( Entry::UnrollBak( o.clone() ) ) ;
+    Ok(())
+  }
+
+  /// Equality of the current and next states of the system, the
+  /// conjunction of the equalities of its state variables.
+  pub fn state_eq(& self) -> Term {
+    use term::{ VarMaker, CstMaker, State } ;
+    let factory = self.solver.parser() ;
+    let eqs: Vec<Term> = self.sys.state().args().iter().map(
+      |& (ref sym, _)| factory.eq(
+        vec![
+          factory.svar( sym.get().clone(), State::Curr ),
+          factory.svar( sym.get().clone(), State::Next )
+        ]
+      )
+    ).collect() ;
+    if eqs.is_empty() { factory.cst(true) } else { factory.and(eqs) }
+  }
+
+  /// Asserts that the state at `o` differs from the ones at the offsets
+  /// before it, see [`state_eq`](#method.state_eq). State variables must be
+  /// declared up to `o`.
+  ///
+  /// Used by **Kind** to only consider paths without repeated states in the
+  /// step case, which then becomes unsat on finite-state systems once the
+  /// unrolling is longer than the longest such path.
+  pub fn assert_distinct(& mut self, o: & Offset) -> Res<()> {
+    let neq = {
+      let eq = self.state_eq() ;
+      self.solver.parser().not(eq)
+    } ;
+    let mut other = Offset::zero() ;
+    while other < * o {
+      try!(
+        chain_err!(
+          unroll, format!(
+            "while asserting the state at {} differs from the one at {}",
+            o, other
+          ) => self.solver.assert( & neq, & Offset2::mk(* o, other) )
+        )
+      ) ;
+      other = other.nxt()
+    }
+    self.ledger.push( Entry::Distinct( o.clone() ) ) ;
     Ok(())
   }
 
@@ -615,10 +1012,39 @@ impl<
         low = low.nxt()
       }
     }
+    self.ledger.push(
+      Entry::Invs( invs.clone(), begin.clone(), end.clone() )
+    ) ;
     self.just_add_invs(invs) ;
     Ok(())
   }
 
+  /// Strengthens the unrolling with the invariants it does not know yet.
+  /// They are asserted between `begin` and `end` as in
+  /// [`add_invs`](#method.add_invs), or only memorized if `begin > end`.
+  /// Either way, the unrollings that follow assert them at the fresh
+  /// offsets.
+  ///
+  /// Returns the number of new invariants.
+  pub fn strengthen(
+    & mut self, invs: STermSet, begin: & Offset2, end: & Offset2
+  ) -> Res<usize> {
+    let new: STermSet = invs.into_iter().filter(
+      |inv| ! self.invs.contains(inv)
+    ).collect() ;
+    let count = new.len() ;
+    if count == 0 { return Ok(0) }
+    if begin > end {
+      self.ledger.push(
+        Entry::Invs( new.clone(), begin.clone(), end.clone() )
+      ) ;
+      self.just_add_invs(new)
+    } else {
+      try!( self.add_invs(new, begin, end) )
+    }
+    Ok(count)
+  }
+
   /// The variables to ask the value of for `get_model`.
   pub fn get_model_vars(& self) -> Vec<Term> {
     use term::{ VarMaker, State } ;
@@ -682,6 +1108,61 @@ impl<
     }
     Ok(model)
   }
+
+  /// A model of all the states unrolled so far, the answer to `get-model`
+  /// completed with the next state variables the encoding defines, see
+  /// [`Unroll::defined`](encoding/trait.Unroll.html#method.defined). Their
+  /// values are retrieved with `get-value` at each offset the transition
+  /// relation was unrolled forward at.
+  ///
+  /// Counterexamples must be retrieved with this function rather than with
+  /// the `get_model` of the solver, which misses the variables defined.
+  pub fn get_full_model(& mut self) -> Res<Model> {
+    use term::{ VarMaker, State, Smt2Offset } ;
+    let mut model = try!(
+      cvc5::get_model(& mut self.solver).chain_err(
+        || "[Unroller] while getting full model"
+      )
+    ) ;
+    let vars: Vec<Term> = self.encoding.defined().iter().map(
+      |var| self.solver.parser().svar(
+        var.get().sym().clone(), State::Next
+      )
+    ).collect() ;
+    if vars.is_empty() {
+      return Ok(model)
+    }
+    let offsets: Vec<Offset2> = self.ledger.iter().filter_map(
+      |entry| match * entry {
+        Entry::Unroll(ref o) |
+        Entry::UnrollInit(ref o) |
+        Entry::UnrollBak(ref o) if ! o.is_rev() => Some( o.clone() ),
+        _ => None,
+      }
+    ).collect() ;
+    for o in offsets.iter() {
+      let values = try!(
+        self.solver.get_values( & vars, o ).chain_err(
+          || format!(
+            "[Unroller] while getting defined state variables at {}", o
+          )
+        )
+      ) ;
+      for ( (term, off), val ) in values.into_iter() {
+        match (term.get(), off) {
+          (
+            & real_term::Term::V(ref var), Smt2Offset::One(off)
+          ) => model.push( ( (var.clone(), Some(off)), val ) ),
+          _ => bail!(
+            format!(
+              "unexpected term {}, expected a one-state variable", term
+            )
+          ),
+        }
+      }
+    }
+    Ok(model)
+  }
 }
 
 
@@ -769,17 +1250,45 @@ fn actlit_name_of(prop: & Prop) -> String {
   actlit_name_of_sym(prop.sym().get())
 }
 
+/// Key of the maps of a [`TermManager`](struct.TermManager.html), its
+/// stable hash first so that the maps are iterated over in the same order
+/// from one run to the other.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Ordered<Key>(u64, Key) ;
+impl<Key: StableHash + Clone> Ordered<Key> {
+  /// The key of the maps for a key of the manager.
+  fn of(key: & Key) -> Self {
+    Ordered( key.stable_hash(), key.clone() )
+  }
+}
+
 /// Handles properties by providing a positive actlits for each.
 ///
 /// Also, provides a few helper functions to temporarily inhibit properties.
 /// See `inhibit`, `all_inhibited`, `reset_inhibited` and `not_inhibited`.
-pub struct TermManager<Key: Hash> {
+/// Individual properties can be (un)inhibited with `inhibit_one`,
+/// `uninhibit` and `inhibit_all_but`, and their actlits queried with
+/// `actlit_of`.
+///
+/// The manager also remembers the highest offset each property is known to
+/// be k-true at, see `set_bound` and `bound_of`.
+///
+/// The terms are iterated over in the order of the
+/// [stable hashes](../term/trait.StableHash.html) of their keys, and the
+/// actlits of invariants are named after theirs, so that the commands sent
+/// to the solver are the same from one run to the other. The order of hash
+/// consing depends on the order the terms are created in. This is what
+/// makes the keys of the smt cache stable, see
+/// [`CachedSolver`](../common/cache/struct.CachedSolver.html).
+pub struct TermManager<Key: Hash + Ord + StableHash> {
   /// Map from property name to one-state properties.
-  terms_1: HashMap<Key, (Term, Term, TmpTerm, String)>,
+  terms_1: BTreeMap<Ordered<Key>, (Term, Term, TmpTerm, String)>,
   /// Map from property name to two-state properties.
-  terms_2: HashMap<Key, (Term, TmpTerm, String)>,
+  terms_2: BTreeMap<Ordered<Key>, (Term, TmpTerm, String)>,
   /// Temporarily inhibited properties.
   inhibited: HashSet<Key>,
+  /// Highest offset the properties are known to be k-true at.
+  bounds: HashMap<Key, Offset>,
 }
 
 impl TermManager<Sym> {
@@ -796,10 +1305,49 @@ impl TermManager<Sym> {
 
     // let calls = sys.calls() ;
 
-    let mut map_1 = HashMap::new() ;
-    let mut map_2 = HashMap::new() ;
+    let mut manager = TermManager {
+      terms_1: BTreeMap::new(),
+      terms_2: BTreeMap::new(),
+      inhibited: HashSet::with_capacity(props.len()),
+      bounds: HashMap::new(),
+    } ;
+    try!( manager.add_props(props, solver) ) ;
+    Ok(manager)
+  }
+
+  /// Raises the bounds of the properties to the k-true offsets an event
+  /// received from the other techniques.
+  pub fn update_bounds(& mut self, event: & Event) {
+    for key in self.keys() {
+      if let Some(ref o) = * event.get_k_true(& key) {
+        self.set_bound(& key, o) ; ()
+      }
+    }
+  }
 
+  /// Adds some properties to the manager. Creates and declares one positive
+  /// activation literal per property, but **does not activate them**. See
+  /// `add_props_between` for properties added during a run.
+  ///
+  /// Returns the names of the properties added. Fails if one of them is
+  /// already known by the manager.
+  ///
+  /// Assumes everything has already been defined.
+  pub fn add_props<
+    'a, S: SolverTrait<'a>
+  >(
+    & mut self, props: Vec<Prop>, solver: & mut S
+  ) -> Res<Vec<Sym>> {
+    let mut keys = Vec::with_capacity( props.len() ) ;
     for prop in props {
+      let sym = prop.sym().get().clone() ;
+      if self.contains(& sym) {
+        bail!(
+          format!(
+            "[TermManager::add_props] property {} is already known", sym
+          )
+        )
+      }
       let actlit = actlit_name_of(& prop) ;
       try!(
         chain_err!(
@@ -812,26 +1360,40 @@ impl TermManager<Sym> {
       match prop.body().clone() {
         STerm::One(state, next) => {
           let state_impl = state.clone().under_actlit( actlit.clone() ) ;
-          let was_there = map_1.insert(
-            prop.sym().get().clone(), (state, next, state_impl, actlit)
+          let was_there = self.terms_1.insert(
+            Ordered::of(& sym), (state, next, state_impl, actlit)
           ) ;
           debug_assert!( was_there.is_none() )
         },
         STerm::Two(next) => {
           let next_impl = next.clone().under_actlit( actlit.clone() ) ;
-          let was_there = map_2.insert(
-            prop.sym().get().clone(), (next, next_impl, actlit)
+          let was_there = self.terms_2.insert(
+            Ordered::of(& sym), (next, next_impl, actlit)
           ) ;
           debug_assert!( was_there.is_none() )
         },
       }
+      keys.push(sym)
     } ;
-    let inhibited = HashSet::with_capacity(map_1.len() + map_2.len()) ;
-    Ok(
-      TermManager {
-        terms_1: map_1, terms_2: map_2, inhibited: inhibited
-      }
-    )
+    Ok(keys)
+  }
+
+  /// Adds some properties to the manager during a run. Declares their
+  /// actlits and activates them between two offsets (see `activate_between`)
+  /// so that they are in the same state as the properties already there.
+  ///
+  /// Assumes the functions the properties use have already been defined.
+  pub fn add_props_between<
+    'a, S: SolverTrait<'a>
+  >(
+    & mut self, props: Vec<Prop>, solver: & mut S,
+    begin: & Offset2, end: & Offset2
+  ) -> Res<Vec<Sym>> {
+    let keys = try!( self.add_props(props, solver) ) ;
+    try!(
+      self.activate_between(solver, keys.iter(), begin, end)
+    ) ;
+    Ok(keys)
   }
 }
 
@@ -850,11 +1412,17 @@ impl TermManager<STerm> {
 
     // let calls = sys.calls() ;
 
-    let mut map_1 = HashMap::new() ;
-    let mut map_2 = HashMap::new() ;
+    let mut map_1 = BTreeMap::new() ;
+    let mut map_2 = BTreeMap::new() ;
 
+    // Declaring the actlits in the order of the maps, named after the
+    // stable hash of the terms.
+    let mut sterms: Vec<Ordered<STerm>> = sterms.iter().map(
+      Ordered::of
+    ).collect() ;
+    sterms.sort() ;
     for sterm in sterms {
-      let actlit = format!("| actlit for candidate {}|", sterm.next().uid()) ;
+      let actlit = format!("| actlit for candidate {:x}|", sterm.0) ;
       try!(
         chain_err!(
           term man, "during positive actlit declaration (STerm)" =>
@@ -863,7 +1431,7 @@ impl TermManager<STerm> {
           )
         )
       ) ;
-      match sterm.clone() {
+      match sterm.1.clone() {
         STerm::One(state, next) => {
           let state_impl = state.clone().under_actlit(actlit.clone()) ;
           let was_there = map_1.insert(
@@ -883,7 +1451,8 @@ impl TermManager<STerm> {
     let inhibited = HashSet::with_capacity(map_1.len() + map_2.len()) ;
     Ok(
       TermManager {
-        terms_1: map_1, terms_2: map_2, inhibited: inhibited
+        terms_1: map_1, terms_2: map_2, inhibited: inhibited,
+        bounds: HashMap::new(),
       }
     )
   }
@@ -891,7 +1460,9 @@ impl TermManager<STerm> {
 
 
 
-impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
+impl<
+  Key: Hash + Ord + Clone + Eq + Display + StableHash
+> TermManager<Key> {
 
   /// Removes some terms from a manager.
   pub fn forget<
@@ -901,13 +1472,15 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
   ) -> Res<()>
   where Key: 'a + 'b {
     for key in keys {
-      let actlit = match self.terms_1.remove(& key) {
+      let ordered = Ordered::of(key) ;
+      let actlit = match self.terms_1.remove(& ordered) {
         Some( (_, _, _, actlit) ) => actlit,
-        None => match self.terms_2.remove(& key) {
+        None => match self.terms_2.remove(& ordered) {
           Some( (_, _, actlit) ) => actlit,
           None => continue,
         },
       } ;
+      let _ = self.bounds.remove(& key) ;
       try!(
         chain_err!(
           term man, "during actlit deactivation" => solver.assert(
@@ -919,9 +1492,83 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     Ok(())
   }
 
+  /// Removes the terms of a manager whose key is not in some set.
+  pub fn retain<'a, S: SolverTrait<'a>>(
+    & mut self, solver: & mut S, keys: & HashSet<Key>
+  ) -> Res<()>
+  where Key: 'a {
+    let gone: Vec<Key> = self.keys().into_iter().filter(
+      |key| ! keys.contains(key)
+    ).collect() ;
+    self.forget(solver, gone.iter())
+  }
+
+  /// Removes some terms from a manager but keeps them as assertions.
+  ///
+  /// Instead of deactivating the actlits of the terms, asserts them. The
+  /// terms thus keep strengthening all the offsets they were activated at.
+  /// Only makes sense for terms known to be invariant, typically proved
+  /// properties.
+  pub fn forget_keep<
+    'a, 'b, S: SolverTrait<'a>, Keys: Iterator<Item=& 'b Key>
+  >(
+    & mut self, solver: & mut S, keys: Keys
+  ) -> Res<()>
+  where Key: 'a + 'b {
+    for key in keys {
+      let ordered = Ordered::of(key) ;
+      let actlit = match self.terms_1.remove(& ordered) {
+        Some( (_, _, _, actlit) ) => actlit,
+        None => match self.terms_2.remove(& ordered) {
+          Some( (_, _, actlit) ) => actlit,
+          None => continue,
+        },
+      } ;
+      let _ = self.inhibited.remove(& key) ;
+      let _ = self.bounds.remove(& key) ;
+      try!(
+        chain_err!(
+          term man, "during actlit activation" => solver.assert(
+            & actlit, & ()
+          )
+        )
+      ) ;
+    }
+    Ok(())
+  }
+
+  /// Removes some terms from a manager, keeps them as assertions iff the
+  /// status is `Proved`.
+  pub fn forget_status<
+    'a, 'b, S: SolverTrait<'a>, Keys: Iterator<Item=& 'b Key>
+  >(
+    & mut self, solver: & mut S, keys: Keys, status: & Status
+  ) -> Res<()>
+  where Key: 'a + 'b {
+    if status.is_proved() {
+      self.forget_keep(solver, keys)
+    } else {
+      self.forget(solver, keys)
+    }
+  }
+
   /// Total number of properties in a manager.
   pub fn len(& self) -> usize { self.terms_1.len() + self.terms_2.len() }
 
+  /// Approximate memory usage of the manager.
+  pub fn mem_usage(& self) -> Vec<MemUsage> {
+    vec![
+      MemUsage::of_table::<(Key, (Term, Term, TmpTerm, String))>(
+        "one-state properties", self.terms_1.len()
+      ),
+      MemUsage::of_table::<(Key, (Term, TmpTerm, String))>(
+        "two-state properties", self.terms_2.len()
+      ),
+      MemUsage::of_table::<Key>("inhibited", self.inhibited.len()),
+      MemUsage::of_table::<(Key, Offset)>("bounds", self.bounds.len()),
+    ]
+  }
+
   /// Returns true iff the manager does not have any property left.
   pub fn none_left(& self) -> bool {
     self.terms_1.is_empty() && self.terms_2.is_empty()
@@ -968,11 +1615,65 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     Ok(())
   }
 
+  /// Activates some terms, including inhibited ones, for all the offsets
+  /// between `begin` and `end`. **Inclusive**. Does nothing if
+  /// `begin > end`.
+  ///
+  /// One-state terms are activated in their current version at all offsets,
+  /// two-state terms at all offsets but `begin`. That is, this is equivalent
+  /// to `activate_state` at `begin`, followed by `activate_next` and
+  /// `activate_state` at all the other offsets.
+  ///
+  /// Used to back-assert terms added after the unrolling started.
+  pub fn activate_between<
+    'a, 'b, S: SolverTrait<'a>, Keys: Iterator<Item=& 'b Key>
+  >(
+    & self, solver: & mut S, keys: Keys, begin: & Offset2, end: & Offset2
+  ) -> Res<()> where Key: 'b {
+    debug_assert!( begin.is_rev() == end.is_rev() ) ;
+    if begin > end { return Ok(()) }
+    for key in keys {
+      let ordered = Ordered::of(key) ;
+      if let Some( & (_, _, ref act, _) ) = self.terms_1.get(& ordered) {
+        let mut low = begin.clone() ;
+        while end >= & low {
+          try!(
+            chain_err!(
+              term man, format!(
+                "during one-state activation of {} at {}", key, low
+              ) => solver.assert(act, & low)
+            )
+          ) ;
+          low = low.nxt()
+        }
+      } else if let Some( & (_, ref act, _) ) = self.terms_2.get(& ordered) {
+        let mut low = begin.nxt() ;
+        while end >= & low {
+          try!(
+            chain_err!(
+              term man, format!(
+                "during two-state activation of {} at {}", key, low
+              ) => solver.assert(act, & low)
+            )
+          ) ;
+          low = low.nxt()
+        }
+      } else {
+        bail!(
+          format!("[TermManager::activate_between] unknown key {}", key)
+        )
+      }
+    } ;
+    Ok(())
+  }
+
   /// Returns the term corresponding to one of the one-state, non-inhibited
   /// properties being false **in state**.
   pub fn one_false_state(& self) -> Option<TmpTerm> {
     let mut terms = Vec::with_capacity(self.terms_1.len()) ;
-    for (ref key, & (ref state, _, _, _)) in self.terms_1.iter() {
+    for (
+      & Ordered(_, ref key), & (ref state, _, _, _)
+    ) in self.terms_1.iter() {
       if ! self.inhibited.contains(key) {
         // If manager is well-founded the unwrap cannot fail.
         terms.push( state.clone() )
@@ -989,12 +1690,12 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     let mut terms = Vec::with_capacity(
       self.terms_1.len() + self.terms_2.len()
     ) ;
-    for (ref key, & (_, ref next, _, _)) in self.terms_1.iter() {
+    for (& Ordered(_, ref key), & (_, ref next, _, _)) in self.terms_1.iter() {
       if ! self.inhibited.contains(key) {
         terms.push( next.clone() )
       }
     } ;
-    for (ref key, & (ref next, _, _)) in self.terms_2.iter() {
+    for (& Ordered(_, ref key), & (ref next, _, _)) in self.terms_2.iter() {
       if ! self.inhibited.contains(key) {
         terms.push( next.clone() )
       }
@@ -1009,12 +1710,14 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     let mut vec = Vec::with_capacity(
       self.terms_1.len() + self.terms_2.len()
     ) ;
-    for (ref key, & (_, _, _, ref actlit)) in self.terms_1.iter() {
+    for (
+      & Ordered(_, ref key), & (_, _, _, ref actlit)
+    ) in self.terms_1.iter() {
       if ! self.inhibited.contains(key) {
         vec.push( actlit.clone() )
       }
     } ;
-    for (ref key, & (_, _, ref actlit)) in self.terms_2.iter() {
+    for (& Ordered(_, ref key), & (_, _, ref actlit)) in self.terms_2.iter() {
       if ! self.inhibited.contains(key) {
         vec.push( actlit.clone() )
       }
@@ -1025,228 +1728,75 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
 
   /// Returns the list of non-inhibited properties that evaluate to false in
   /// their **state** version for some offset in a solver.
+  ///
+  /// Only reads the values the solver gives to the properties, see
+  /// [`values::bools`](../common/values/fn.bools.html).
   pub fn get_false_state<
     'a, S: SolverTrait<'a>
   >(
     & self, solver: & mut S, o: & Offset2
   ) -> Res<Vec<Key>> {
-    let mut terms = Vec::with_capacity(self.terms_1.len()) ;
-    let mut back_map = HashMap::with_capacity(self.terms_1.len()) ;
-    for (ref key, & (ref state, _, _, _)) in self.terms_1.iter() {
+    let mut keyed = Vec::with_capacity(self.terms_1.len()) ;
+    for (
+      & Ordered(_, ref key), & (ref state, _, _, _)
+    ) in self.terms_1.iter() {
       if ! self.inhibited.contains(key) {
-        terms.push(state.clone()) ;
-        match back_map.insert(
-          state.clone(), key.clone()
-        ) {
-          None => (),
-          Some(old) => bail!(
-            format!(
-              "[TermManager::get_false_state] term {}\n\
-              already mapped to key {}\n\
-              trying to map it to {}",
-              state, old, key
-            )
-          ),
-        }
-      }
-    } ;
-    let values = try!(
-      chain_err!(
-        term man, format!(
-          "while retrieving values of props at {}", o
-        ) => solver.get_values(& terms, o)
-      )
-    ) ;
-    let mut keys = Vec::with_capacity(7) ;
-    for ((t, _), v) in values {
-      match * v.get() {
-        real_term::Cst::Bool(true) => (),
-        real_term::Cst::Bool(false) => keys.push(
-          match back_map.remove(& t) {
-            Some(t) => t.clone(),
-            None => bail!(
-              format!("[TermManager::get_false_state] unknown term {}", t)
-            ),
-          }
-        ),
-        _ => bail!(
-          format!(
-            "[TermManager::get_false_state] unexpected term value {}", v
-          )
-        )
+        keyed.push( ((* key).clone(), state.clone()) )
       }
-    } ;
-    Ok(keys)
+    }
+    Self::get_false_of(solver, keyed, o)
   }
 
   /// Returns the list of non-inhibited properties that evaluate to false in
   /// their next version for some offset in a solver.
+  ///
+  /// Only reads the values the solver gives to the properties, see
+  /// [`values::bools`](../common/values/fn.bools.html).
   pub fn get_false_next<
     'a, S: SolverTrait<'a>
   >(
     & self, solver: & mut S, o: & Offset2
   ) -> Res< Vec<Key> > {
-    let mut terms = Vec::with_capacity(
-      (self.terms_1.len() * 2) + self.terms_2.len()
-    ) ;
-    // Maps terms back to the property they correspond to.
-    let mut back_map = HashMap::with_capacity(
+    let mut keyed = Vec::with_capacity(
       self.terms_1.len() + self.terms_2.len()
     ) ;
-    for (ref key, & (ref state, ref next, _, _)) in self.terms_1.iter() {
+    for (& Ordered(_, ref key), & (_, ref next, _, _)) in self.terms_1.iter() {
       if ! self.inhibited.contains(key) {
-        terms.push(next.clone()) ;
-        match back_map.insert(
-          next.clone(), key.clone()
-        ) {
-          None => (),
-          Some(old) => bail!(
-            format!(
-              "[TermManager::get_false_next] term {}\n    \
-              already mapped to property {}\n    \
-              trying to map it to {}",
-              next, old, key
-            )
-          ),
-        } ;
-        // We also insert state. If there is no two-state property, one-state
-        // ones will be parsed as state.
-        match back_map.insert(
-          state.clone(), key.clone()
-        ) {
-          None => (),
-          Some(old) => bail!(
-            format!(
-              "[TermManager::get_false_next]term {}\n    \
-              already mapped to key {}\n    \
-              trying to map it to {}",
-              state, old, key
-            )
-          ),
-        } ;
+        keyed.push( ((* key).clone(), next.clone()) )
       }
-    } ;
-    for (ref key, & (ref next, _, _)) in self.terms_2.iter() {
+    }
+    for (& Ordered(_, ref key), & (ref next, _, _)) in self.terms_2.iter() {
       if ! self.inhibited.contains(key) {
-        terms.push(next.clone()) ;
-        match back_map.insert(next.clone(), key.clone()) {
-          None => (),
-          Some(old) => bail!(
-            format!(
-              "[TermManager::get_false_next]term {}\n    \
-              already mapped to key {}\n    \
-              trying to map it to {}",
-              next, old, key
-            )
-          ),
-        }
+        keyed.push( ((* key).clone(), next.clone()) )
       }
-    } ;
-    let vec = try!(
-      chain_err!(
-        term man, "while retrieving term values for false next props" =>
-        solver.get_values(& terms, o)
-      )
-    ) ;
-    let mut keys = Vec::with_capacity(7) ;
-    for ((t, _), v) in vec {
-      match * v.get() {
-        real_term::Cst::Bool(true) => (),
-        real_term::Cst::Bool(false) => match back_map.get(& t) {
-          Some(ref key) => keys.push( (** key).clone() ),
-          None => {
-            let t1 = t ;
-            let mut s = format!(
-              "[TermManager::get_false_next] \
-              can't find term `t1` in map back\n{}", t1
-            ) ;
-            let t1_str = format!("{}", t1) ;
-
-            for (t2, _) in back_map.iter() {
-              let t2_str = format!("{}", t2) ;
-              if t1_str == t2_str {
-                s = format!(
-                  "{}\nterm is present in map though, analyzing problem\n\
-                  t1's hash is {}\nt2's hash is {}", s, t1.uid(), t2.uid()
-                ) ;
-                match (t1.get(), t2.get()) {
-                  (
-                    & real_term::Term::Op(_, ref t1_subs),
-                    & real_term::Term::Op(_, ref t2_subs),
-                  ) => {
-                    for (
-                      t1_sub, t2_sub
-                    ) in t1_subs.iter().zip( t2_subs.iter() ) {
-                      if t1_sub.uid() != t2_sub.uid() {
-                        s = format!("{}\nhash inconsistency:\nlvl 1:", s) ;
-                        s = format!(
-                          "{}\n  t1: {} {}", s, t1_sub.uid(), t1_sub
-                        ) ;
-                        s = format!(
-                          "{}\n  t2: {} {}", s, t2_sub.uid(), t2_sub
-                        ) ;
-                        match (t1_sub.get(), t2_sub.get()) {
-                          (
-                            & real_term::Term::Op(_, ref t1_subs),
-                            & real_term::Term::Op(_, ref t2_subs),
-                          ) => {
-                            for (
-                              t1_sub, t2_sub
-                            ) in t1_subs.iter().zip( t2_subs.iter() ) {
-                              if t1_sub.uid() != t2_sub.uid() {
-                                s = format!(
-                                  "{}\nlvl 2:\n  t1: {} {}", s, t1_sub.uid(), t1_sub
-                                ) ;
-                                s = format!(
-                                  "{}\n  t2: {} {}", s, t2_sub.uid(), t2_sub
-                                ) ;
-                                match (t1_sub.get(), t2_sub.get()) {
-                                  (
-                                    & real_term::Term::Op(_, ref t1_subs),
-                                    & real_term::Term::Op(_, ref t2_subs),
-                                  ) => {
-                                    let mut aaa = true ;
-                                    for (
-                                      t1_sub, t2_sub
-                                    ) in t1_subs.iter().zip( t2_subs.iter() ) {
-                                      if t1_sub.uid() != t2_sub.uid() {
-                                        s = format!(
-                                          "{}\nlvl 3:\n  t1: {} {}",
-                                          s, t1_sub.uid(), t1_sub
-                                        ) ;
-                                        s = format!(
-                                          "{}\n  t2: {} {}", s, t2_sub.uid(), t2_sub
-                                        ) ;
-                                        aaa = false
-                                      }
-                                    }
-                                    if aaa {
-                                      s = format!("{}\nno more hash inconsistency", s)
-                                    }
-                                  },
-                                  _ => (),
-                                }
-                              }
-                            }
-                          },
-                          _ => (),
-                        }
-                      }
-                    }
-                  },
-                  _ => bail!("error while handling hash inconsistency"),
-                }
-              }
-            }
+    }
+    Self::get_false_of(solver, keyed, o)
+  }
 
-            bail!(s)
-          },
-        },
-        _ => bail!(
-          format!("[TermManager::get_false_next] unexpected prop value {}", v)
-        ),
+  /// The keys of the terms false at some offset in the model of the last
+  /// check. Asks for the values of `FALSE_BATCH` terms at a time, so that
+  /// the answers of the solver stay small.
+  fn get_false_of<
+    'a, S: SolverTrait<'a>
+  >(
+    solver: & mut S, keyed: Vec<(Key, Term)>, o: & Offset2
+  ) -> Res< Vec<Key> > {
+    let mut keys = Vec::with_capacity(7) ;
+    for batch in keyed.chunks(FALSE_BATCH) {
+      let terms: Vec<Term> = batch.iter().map(
+        |& (_, ref term)| term.clone()
+      ).collect() ;
+      let bools = try!(
+        chain_err!(
+          term man, format!(
+            "while retrieving values of props at {}", o
+          ) => values::bools(solver, & terms, o)
+        )
+      ) ;
+      for (& (ref key, _), value) in batch.iter().zip( bools.into_iter() ) {
+        if ! value { keys.push( key.clone() ) }
       }
-    } ;
+    }
     keys.shrink_to_fit() ;
     Ok(keys)
   }
@@ -1263,6 +1813,114 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     Ok(())
   }
 
+  /// Inhibits a single property. Returns `false` if the property was already
+  /// inhibited or is unknown.
+  pub fn inhibit_one(& mut self, key: & Key) -> bool {
+    if self.contains(key) {
+      self.inhibited.insert(key.clone())
+    } else { false }
+  }
+
+  /// Lifts the inhibition of some properties. Properties that were not
+  /// inhibited are ignored.
+  pub fn uninhibit<'b, Keys: Iterator<Item=& 'b Key>>(
+    & mut self, keys: Keys
+  ) where Key: 'b {
+    for key in keys {
+      let _ = self.inhibited.remove(key) ;
+      ()
+    }
+  }
+
+  /// Inhibits all properties but one. Previous inhibitions are lost.
+  ///
+  /// Typically used to check a single property, for instance when
+  /// generalizing a step check that succeeded for that property only.
+  pub fn inhibit_all_but(& mut self, key: & Key) -> Res<()> {
+    if ! self.contains(key) {
+      bail!(
+        format!("[PropManager::inhibit_all_but] unknown property {}", key)
+      )
+    }
+    self.inhibited.clear() ;
+    for (& Ordered(_, ref other), _) in self.terms_1.iter() {
+      if other != key {
+        let _ = self.inhibited.insert( (* other).clone() ) ;
+        ()
+      }
+    }
+    for (& Ordered(_, ref other), _) in self.terms_2.iter() {
+      if other != key {
+        let _ = self.inhibited.insert( (* other).clone() ) ;
+        ()
+      }
+    }
+    Ok(())
+  }
+
+  /// True iff the manager knows a property.
+  #[inline]
+  pub fn contains(& self, key: & Key) -> bool {
+    let ordered = Ordered::of(key) ;
+    self.terms_1.contains_key(& ordered) ||
+    self.terms_2.contains_key(& ordered)
+  }
+
+  /// The positive actlit of a property, inhibited or not. `None` if the
+  /// property is unknown.
+  pub fn actlit_of(& self, key: & Key) -> Option<& str> {
+    match self.terms_1.get(& Ordered::of(key)) {
+      Some( & (_, _, _, ref actlit) ) => Some(actlit),
+      None => match self.terms_2.get(& Ordered::of(key)) {
+        Some( & (_, _, ref actlit) ) => Some(actlit),
+        None => None,
+      },
+    }
+  }
+
+  /// Raises the bound of a property, the highest offset it is known to be
+  /// k-true at. Returns `false` if the property is unknown or its bound was
+  /// already at least `o`.
+  pub fn set_bound(& mut self, key: & Key, o: & Offset) -> bool {
+    if ! self.contains(key) { return false }
+    if let Some(old) = self.bounds.get(key) {
+      if old >= o { return false }
+    }
+    self.bounds.insert( key.clone(), o.clone() ) ;
+    true
+  }
+
+  /// Raises the bounds of some properties, see `set_bound`.
+  pub fn set_bounds<'b, Keys: Iterator<Item=& 'b Key>>(
+    & mut self, keys: Keys, o: & Offset
+  ) where Key: 'b {
+    for key in keys {
+      self.set_bound(key, o) ; ()
+    }
+  }
+
+  /// The highest offset a property is known to be k-true at, if any.
+  #[inline]
+  pub fn bound_of(& self, key: & Key) -> Option<& Offset> {
+    self.bounds.get(key)
+  }
+
+  /// True iff some properties are all known to be k-true at least at `o`.
+  pub fn all_true_at<'b, Keys: Iterator<Item=& 'b Key>>(
+    & self, mut keys: Keys, o: & Offset
+  ) -> bool where Key: 'b {
+    keys.all(
+      |key| self.bound_of(key).map_or(false, |bound| bound >= o)
+    )
+  }
+
+  /// All the properties, inhibited or not.
+  pub fn keys(& self) -> Vec<Key> {
+    self.terms_1.keys().chain( self.terms_2.keys() ).map(
+      |& Ordered(_, ref key)| key.clone()
+    ).collect()
+  }
+
   /// Returns true iff all properties are inhibited.
   pub fn all_inhibited(& self) -> bool {
     self.inhibited.len() == self.terms_1.len() + self.terms_2.len()
@@ -1278,13 +1936,13 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     let mut set = HashSet::with_capacity(
       self.terms_1.len() + self.terms_2.len() - self.inhibited.len()
     ) ;
-    for (ref key, _) in self.terms_1.iter() {
+    for (& Ordered(_, ref key), _) in self.terms_1.iter() {
       if ! self.inhibited.contains(key) {
         let _ = set.insert((* key).clone()) ;
         ()
       }
     } ;
-    for (ref key, _) in self.terms_2.iter() {
+    for (& Ordered(_, ref key), _) in self.terms_2.iter() {
       if ! self.inhibited.contains(key) {
         let _ = set.insert((* key).clone()) ;
         ()
@@ -1298,8 +1956,11 @@ impl<Key: Hash + Clone + Eq + Display> TermManager<Key> {
     set
   }
 
-  /// Returns the properties that are not inhibited.
+  /// Returns the properties that are not inhibited, in the order of the
+  /// manager.
   pub fn not_inhibited(& self) -> Vec<Key> {
-    self.not_inhibited_set().into_iter().collect()
+    self.keys().into_iter().filter(
+      |key| ! self.inhibited.contains(key)
+    ).collect()
   }
 }
\ No newline at end of file
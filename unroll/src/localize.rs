@@ -0,0 +1,180 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Localizes the conjuncts of the transition relation responsible for a
+//! counterexample.
+//!
+//! The initial state and the inputs of the counterexample are fixed, and the
+//! properties it falsifies are asserted in its last state. Each top-level
+//! conjunct of the transition relation is asserted at each step under its
+//! own activation literal. If the result is unsatisfiable, the activation
+//! literals are removed one by one as long as it stays unsatisfiable, which
+//! yields a minimal set of conjunct instances explaining the violation.
+//! Conjuncts are ranked by the number of steps they appear in this set.
+//!
+//! Terms do not remember their position in the input, conjuncts are located
+//! by the span of the definition of their system.
+
+use std::collections::{ HashSet, HashMap } ;
+use std::cmp::Ordering ;
+
+use term::{
+  Sym, Term, Model, Offset2, State, STerm, Factory, Operator, real_term,
+  VarMaker, CstMaker, BindMaker
+} ;
+use term::tmp::TmpTerm ;
+
+use sys::{ Prop, Sys } ;
+use sys::deps::DepGraph ;
+
+use common::SolverTrait ;
+use common::samples::SamplePool ;
+use common::errors::* ;
+
+use Unroller ;
+
+/// A conjunct of the transition relation involved in a counterexample.
+pub struct Suspect {
+  /// Index of the conjunct in the transition relation.
+  pub index: usize,
+  /// The conjunct.
+  pub conj: Term,
+  /// Number of steps of the counterexample the conjunct is involved in.
+  pub steps: usize,
+}
+
+/// The top-level conjuncts of a term. Local definitions are pushed down to
+/// the conjuncts.
+pub fn conjuncts(term: & Term, factory: & Factory) -> Vec<Term> {
+  match * term.get() {
+    real_term::Term::Op(Operator::And, ref kids) => {
+      let mut res = Vec::with_capacity( kids.len() ) ;
+      for kid in kids.iter() {
+        res.extend( conjuncts(kid, factory) )
+      }
+      res
+    },
+    real_term::Term::Let(ref bindings, ref body) => conjuncts(
+      body, factory
+    ).into_iter().map(
+      |conj| factory.let_b( bindings.clone(), conj )
+    ).collect(),
+    _ => vec![ term.clone() ],
+  }
+}
+
+/// Ranks the conjuncts of the transition relation of a system by their
+/// involvement in a counterexample, most involved first.
+///
+/// `props` are the properties falsified by the counterexample, `model` its
+/// states. Uses at most `max` check-sats. Returns nothing if the
+/// counterexample is not explained by the transition relation, typically
+/// when it is non-deterministic beyond its inputs.
+pub fn localize<'a, S: SolverTrait<'a>>(
+  solver: S, sys: & Sys, props: & [Prop], model: & Model,
+  factory: & Factory, max: usize
+) -> Res<Vec<Suspect>> {
+  let states = SamplePool::states_of(model) ;
+  if states.is_empty() { return Ok( vec![] ) }
+  let last = states.len() - 1 ;
+
+  let graph = DepGraph::of(sys) ;
+  let inputs: HashSet<Sym> = graph.inputs().into_iter().cloned().collect() ;
+  let conjs = conjuncts(& sys.trans().2, factory) ;
+
+  let mut unroller = try!( Unroller::mk(sys, props, solver) ) ;
+
+  // Offsets of the states, fixing the initial state and the inputs.
+  let mut offs = Vec::with_capacity( states.len() ) ;
+  let mut off = Offset2::init() ;
+  for (index, state) in states.iter().enumerate() {
+    if index == 0 {
+      try!( unroller.assert_init(& off) )
+    } else {
+      try!( unroller.declare_svars( off.curr() ) )
+    }
+    for & (ref sym, ref cst) in state.iter() {
+      if index == 0 || inputs.contains(sym) {
+        let svar: Term = factory.svar( sym.clone(), State::Curr ) ;
+        let eq = factory.eq( vec![ svar, factory.cst( cst.clone() ) ] ) ;
+        try!( unroller.assert(& eq, & off) )
+      }
+    }
+    offs.push( off.clone() ) ;
+    off = off.nxt()
+  }
+
+  // Conjuncts at each step, under an actlit.
+  let mut actlits = Vec::with_capacity( conjs.len() * last ) ;
+  for off in offs.iter().take(last) {
+    for (index, conj) in conjs.iter().enumerate() {
+      let actlit = try!( unroller.fresh_actlit() ) ;
+      try!(
+        unroller.assert(
+          & actlit.activate_term( TmpTerm::Trm( conj.clone() ) ), off
+        )
+      ) ;
+      actlits.push( (index, actlit.name()) )
+    }
+  }
+
+  // Properties hold in the last state.
+  for prop in props.iter() {
+    match * prop.body() {
+      STerm::One(ref state, _) => try!(
+        unroller.assert(state, & offs[last])
+      ),
+      STerm::Two(ref next) => if last > 0 {
+        try!( unroller.assert(next, & offs[last - 1]) )
+      },
+    }
+  }
+
+  let names = |actlits: & [(usize, String)], skip: Option<usize>| {
+    actlits.iter().enumerate().filter(
+      |& (index, _)| Some(index) != skip
+    ).map(
+      |(_, & (_, ref name))| name.clone()
+    ).collect::<Vec<String>>()
+  } ;
+
+  if try!( unroller.check_sat_assuming( & names(& actlits, None) ) ) {
+    return Ok( vec![] )
+  }
+
+  // Removing the actlits not needed for unsatisfiability.
+  let mut queries = 1 ;
+  let mut index = 0 ;
+  while index < actlits.len() && queries < max {
+    queries += 1 ;
+    let without = names(& actlits, Some(index)) ;
+    if try!( unroller.check_sat_assuming(& without) ) {
+      index += 1
+    } else {
+      actlits.remove(index) ; ()
+    }
+  }
+
+  let mut steps = HashMap::new() ;
+  for & (index, _) in actlits.iter() {
+    * steps.entry(index).or_insert(0) += 1
+  }
+  let mut suspects: Vec<Suspect> = steps.into_iter().map(
+    |(index, steps)| Suspect {
+      index: index, conj: conjs[index].clone(), steps: steps
+    }
+  ).collect() ;
+  suspects.sort_by(
+    |lhs, rhs| match rhs.steps.cmp(& lhs.steps) {
+      Ordering::Equal => lhs.index.cmp(& rhs.index),
+      ord => ord,
+    }
+  ) ;
+  Ok(suspects)
+}
@@ -0,0 +1,221 @@
+// Copyright 2016 Adrien Champion. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Input schedules, the values of some state variables over the first steps
+//! of the traces.
+//!
+//! A schedule is read from a CSV file, or from a JSON file if its name ends
+//! with `.json`. In CSV, the first line lists state variables and each of
+//! the following lines gives their values at a step, starting with the
+//! initial state:
+//!
+//! ```text
+//! reset, mode, cnt
+//! true, #b01,
+//! false, , 3
+//! ```
+//!
+//! In JSON, the schedule is an array with one object per step mapping state
+//! variables to values:
+//!
+//! ```text
+//! [ { "reset": true, "mode": "#b01" }, { "reset": false, "cnt": 3 } ]
+//! ```
+//!
+//! Empty cells and `null`s leave the state variable unconstrained at that
+//! step. Values are booleans, integers, rationals `<num>/<den>`, and
+//! bitvectors written `#b<bits>`, `#x<hex>` or as naturals.
+//!
+//! The unroller
+//! [asserts](../struct.Unroller.html#method.assert_schedule) each step in
+//! the state at that step, the traces of the unrolling are then the
+//! completions of the schedule.
+
+use std::io::Read ;
+use std::fs::File ;
+
+use term::{
+  Factory, Term, Cst, Type, Int, Rat, State, VarMaker, CstMaker, Zero, One
+} ;
+
+use sys::Sys ;
+
+use common::errors::* ;
+use common::json ;
+
+/// Values of some state variables at some step, unparsed.
+type Row = Vec<(String, String)> ;
+
+/// Values of some state variables over the first steps of the traces.
+pub struct Schedule {
+  /// Constraint on the state at each step.
+  steps: Vec<Term>,
+}
+impl Schedule {
+  /// The empty schedule, constraining nothing.
+  pub fn empty() -> Self { Schedule { steps: vec![] } }
+
+  /// Reads a schedule for a system from a file.
+  pub fn of_file(path: & str, sys: & Sys, factory: & Factory) -> Res<Self> {
+    let mut txt = String::new() ;
+    let read = File::open(path).and_then(
+      |mut file| file.read_to_string(& mut txt)
+    ) ;
+    if let Err(e) = read {
+      bail!( ErrorKind::FileIoError(path.to_string(), e) )
+    }
+    Schedule::of_str(& txt, path.ends_with(".json"), sys, factory)
+  }
+
+  /// Parses a schedule for a system, in JSON if `json` and in CSV
+  /// otherwise.
+  pub fn of_str(
+    txt: & str, json: bool, sys: & Sys, factory: & Factory
+  ) -> Res<Self> {
+    let rows = if json {
+      try_chain!( json_rows(txt) => "while parsing JSON schedule" )
+    } else {
+      try_chain!( csv_rows(txt) => "while parsing CSV schedule" )
+    } ;
+    let mut steps = Vec::with_capacity( rows.len() ) ;
+    for (step, row) in rows.into_iter().enumerate() {
+      let mut eqs = Vec::with_capacity( row.len() ) ;
+      for (name, value) in row.into_iter() {
+        if value.is_empty() { continue }
+        let (sym, typ) = match sys.state().args().iter().find(
+          |& & (ref sym, _)| sym.get().sym() == name
+        ) {
+          Some( & (ref sym, ref typ) ) => (sym.get().clone(), typ.get()),
+          None => bail!(
+            format!(
+              "unknown state variable `{}` in schedule of system {}",
+              name, sys.sym()
+            )
+          ),
+        } ;
+        let cst = match value_of(& value, typ, factory) {
+          Some(cst) => cst,
+          None => bail!(
+            format!(
+              "illegal value `{}` for state variable `{}` of type {} at \
+              step {} of schedule", value, name, typ, step
+            )
+          ),
+        } ;
+        let svar: Term = factory.svar(sym, State::Curr) ;
+        eqs.push( factory.eq( vec![ svar, factory.mk_cst(cst) ] ) )
+      }
+      steps.push(
+        if eqs.is_empty() { factory.cst(true) } else { factory.and(eqs) }
+      )
+    }
+    Ok( Schedule { steps: steps } )
+  }
+
+  /// Number of steps of the schedule.
+  #[inline]
+  pub fn len(& self) -> usize { self.steps.len() }
+  /// True if the schedule has no step.
+  #[inline]
+  pub fn is_empty(& self) -> bool { self.steps.is_empty() }
+  /// Constraint on the current state at some step, if any.
+  #[inline]
+  pub fn at(& self, step: usize) -> Option<& Term> { self.steps.get(step) }
+}
+
+/// Parses a value of some type.
+fn value_of(value: & str, typ: & Type, factory: & Factory) -> Option<Cst> {
+  match * typ {
+    Type::Bool => value.parse::<bool>().ok().map( |b| factory.cst(b) ),
+    Type::Int => Int::parse_bytes(value.as_bytes(), 10).map(
+      |i| factory.cst(i)
+    ),
+    Type::Rat => {
+      let mut frac = value.split('/') ;
+      let num = frac.next().and_then(
+        |num| Int::parse_bytes(num.as_bytes(), 10)
+      ) ;
+      let den = match frac.next() {
+        Some(den) => Int::parse_bytes(den.as_bytes(), 10),
+        None => Some( Int::one() ),
+      } ;
+      match (num, den, frac.next()) {
+        (Some(num), Some(den), None) => if den.is_zero() { None } else {
+          Some( factory.cst( Rat::new(num, den) ) )
+        },
+        _ => None,
+      }
+    },
+    Type::BitVec(width) => {
+      let bv = if value.starts_with("#b") {
+        Int::parse_bytes(value[2..].as_bytes(), 2)
+      } else if value.starts_with("#x") {
+        Int::parse_bytes(value[2..].as_bytes(), 16)
+      } else {
+        Int::parse_bytes(value.as_bytes(), 10)
+      } ;
+      match bv {
+        Some(bv) => if bv < Int::zero() || bv >= Int::one() << width {
+          None
+        } else { Some( factory.cst( (width, bv) ) ) },
+        None => None,
+      }
+    },
+    _ => None,
+  }
+}
+
+/// Parses the steps of a CSV schedule, ignoring empty lines.
+fn csv_rows(txt: & str) -> Res< Vec<Row> > {
+  let mut lines = txt.lines().filter( |line| ! line.trim().is_empty() ) ;
+  let names: Vec<String> = match lines.next() {
+    Some(line) => line.split(',').map(
+      |name| name.trim().to_string()
+    ).collect(),
+    None => return Ok( vec![] ),
+  } ;
+  let mut rows = vec![] ;
+  for (step, line) in lines.enumerate() {
+    let values: Vec<String> = line.split(',').map(
+      |value| value.trim().to_string()
+    ).collect() ;
+    if values.len() != names.len() {
+      bail!(
+        format!(
+          "step {} has {} value(s) but there are {} state variable(s)",
+          step, values.len(), names.len()
+        )
+      )
+    }
+    rows.push( names.iter().cloned().zip( values.into_iter() ).collect() )
+  }
+  Ok(rows)
+}
+
+/// Parses the steps of a JSON schedule.
+fn json_rows(txt: & str) -> Res< Vec<Row> > {
+  let mut chars = txt.chars().peekable() ;
+  let mut rows = vec![] ;
+  try!( json::expect(& mut chars, '[') ) ;
+  if json::is_next(& mut chars, ']') { return Ok(rows) }
+  loop {
+    let row = try_chain!(
+      json::object(& mut chars).map_err(Error::from) =>
+        "at step {}", rows.len()
+    ) ;
+    rows.push(
+      row.into_iter().map(
+        |(name, value)| (name, value.unwrap_or_else(String::new))
+      ).collect()
+    ) ;
+    if json::is_next(& mut chars, ']') { break }
+    try!( json::expect(& mut chars, ',') )
+  }
+  Ok(rows)
+}